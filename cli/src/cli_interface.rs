@@ -120,6 +120,71 @@ pub enum Commands {
         #[clap(long, short)]
         path: Option<String>,
     },
+    /// Generate the JSON Schema for rindexer.yaml.
+    ///
+    /// Writes `rindexer.schema.json` next to your rindexer.yaml so editors can offer
+    /// autocomplete and validation, add `# yaml-language-server: $schema=./rindexer.schema.json`
+    /// to the top of your rindexer.yaml to use it.
+    ///
+    /// Example:
+    /// `rindexer schema` or `rindexer schema --stdout`
+    Schema {
+        /// optional - Print the schema to stdout instead of writing it to a file.
+        #[clap(long)]
+        stdout: bool,
+
+        /// optional - The path to run the command in, default will be where the command is run.
+        #[clap(long, short)]
+        path: Option<String>,
+    },
+    /// Validate rindexer.yaml and print the resolved manifest.
+    ///
+    /// Composes rindexer.yaml with every `include:` and `extends:` file and the environment
+    /// overlay (e.g. `rindexer.prod.yaml`), validates it and prints the result. Env variables are
+    /// left unsubstituted in the output.
+    ///
+    /// Example:
+    /// `rindexer validate` or `rindexer validate --env prod`
+    Validate {
+        /// optional - The environment overlay to merge, defaults to the `RINDEXER_ENV` env variable.
+        #[clap(long)]
+        env: Option<String>,
+
+        /// optional - The path to run the command in, default will be where the command is run.
+        #[clap(long, short)]
+        path: Option<String>,
+    },
+    /// Manage the on-disk RPC response cache set up with `config.rpc_cache`.
+    ///
+    /// Example:
+    /// `rindexer cache prune` or `rindexer cache prune --max-size-mb 256` or
+    /// `rindexer cache prune --all`
+    #[clap(name = "cache")]
+    Cache {
+        #[clap(subcommand)]
+        subcommand: CacheSubcommands,
+
+        /// optional - The path to run the command in, default will be where the command is run.
+        #[clap(long, short)]
+        path: Option<String>,
+    },
+    /// Build or extend the `.blockclock` block timestamp encoding of a network from its RPC.
+    ///
+    /// Encodings are written to `~/.rindexer/resources/blockclock` and used to resolve log
+    /// timestamps without RPC calls. Indexing keeps them extended after that.
+    ///
+    /// Example:
+    /// `rindexer blockclock build --network ethereum` or
+    /// `rindexer blockclock extend --network ethereum`
+    #[clap(name = "blockclock")]
+    Blockclock {
+        #[clap(subcommand)]
+        subcommand: BlockclockSubcommands,
+
+        /// optional - The path to run the command in, default will be where the command is run.
+        #[clap(long, short)]
+        path: Option<String>,
+    },
     /// Use phantom events to add your own events to contracts
     ///
     /// This command helps you use phantom events within rindexer.
@@ -208,6 +273,25 @@ pub enum AddSubcommands {
     /// Example:
     /// `rindexer add contract`
     Contract,
+
+    /// Add contracts from Foundry or Hardhat build artifacts to the rindexer.yaml file.
+    ///
+    /// Reads Foundry `out/` and `broadcast/` run files or Hardhat `deployments/` folders, writes
+    /// the ABIs to the abis folder and maps the deployed address, network and deployment block in
+    /// the yaml file. This does not need any network access.
+    ///
+    /// Example:
+    /// `rindexer add from-artifacts --artifacts-path ../contracts`
+    #[clap(name = "from-artifacts")]
+    FromArtifacts {
+        /// optional - The Foundry or Hardhat project path, default will be the rindexer project path.
+        #[clap(long)]
+        artifacts_path: Option<String>,
+
+        /// optional - Only add these contracts, can be passed multiple times.
+        #[clap(long)]
+        contract: Vec<String>,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -256,6 +340,51 @@ pub struct PhantomBaseArgs {
     pub network: String,
 }
 
+#[derive(Subcommand, Debug)]
+pub enum CacheSubcommands {
+    /// Drops the oldest cached responses until the cache fits its `max_size_mb`.
+    ///
+    /// Example:
+    /// `rindexer cache prune`
+    Prune {
+        /// optional - Prune down to this size in megabytes instead of the configured one.
+        #[clap(long)]
+        max_size_mb: Option<u64>,
+
+        /// optional - Drop every cached response.
+        #[clap(long, conflicts_with = "max_size_mb")]
+        all: bool,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct BlockclockArgs {
+    /// The network name in the rindexer.yaml
+    #[clap(long)]
+    pub network: String,
+
+    /// optional - The number of blocks fetched per batch, defaults to 100.
+    #[clap(long)]
+    pub batch_size: Option<u64>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum BlockclockSubcommands {
+    /// Encodes the network from genesis, replacing its existing encoding.
+    ///
+    /// Example:
+    /// `rindexer blockclock build --network ethereum`
+    Build(BlockclockArgs),
+
+    /// Encodes the blocks after the network's existing encoding up to the chain head.
+    ///
+    /// Starts from the encoding bundled with rindexer if there is none in the home directory yet.
+    ///
+    /// Example:
+    /// `rindexer blockclock extend --network ethereum`
+    Extend(BlockclockArgs),
+}
+
 #[derive(Subcommand, Debug)]
 pub enum PhantomSubcommands {
     /// Sets up phantom events on rindexer
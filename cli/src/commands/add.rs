@@ -1,20 +1,30 @@
-use std::{borrow::Cow, fs, path::PathBuf, time::Duration};
+use std::{
+    borrow::Cow,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use alloy::{primitives::Address, rpc::types::ValueOrArray};
 use alloy_chains::Chain;
 use foundry_block_explorers::Client;
 use rindexer::{
     manifest::{
-        contract::{Contract, ContractDetails},
-        yaml::{read_manifest_raw, write_manifest, YAML_CONFIG_NAME},
+        artifacts::read_artifacts,
+        contract::{Contract, ContractDetails, ProxySetting},
+        core::Manifest,
+        yaml::{read_manifest_raw, substitute_env_variables, write_manifest, YAML_CONFIG_NAME},
     },
+    provider::create_client,
+    proxy::{detect_proxy, fetch_etherscan_abi, implementation_abi_path, ProxyImplementation},
     public_read_env_value, write_file, StringOrArray,
 };
 
 use crate::{
     commands::BACKUP_ETHERSCAN_API_KEY,
     console::{
-        print_error_message, print_success_message, prompt_for_input, prompt_for_input_list,
+        print_error_message, print_success_message, print_warn_message, prompt_for_input,
+        prompt_for_input_list,
     },
     rindexer_yaml::validate_rindexer_yaml_exist,
 };
@@ -69,7 +79,7 @@ pub async fn handle_add_contract_command(
     );
 
     let client = Client::builder()
-        .with_api_key(etherscan_api_key)
+        .with_api_key(etherscan_api_key.clone())
         .chain(chain_network)
         .map_err(|e| {
             print_error_message(&format!("Invalid chain id {e}"));
@@ -85,6 +95,20 @@ pub async fn handle_add_contract_command(
         .parse::<Address>()
         .inspect_err(|e| print_error_message(&format!("Invalid contract address: {e}")))?;
 
+    // Resolve proxies on-chain first as explorers do not flag every proxy (beacons, diamonds)
+    let mut implementations = detect_contract_proxy(&manifest, &network, address)
+        .await
+        .map(|proxy| {
+            println!(
+                "This contract is an {} proxy with implementation(s): {}",
+                proxy.kind,
+                proxy.implementations.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(", ")
+            );
+            proxy.implementations
+        })
+        .unwrap_or_default();
+    let mut proxy_abi: Option<String> = None;
+
     let mut abi_lookup_address: Address = address;
     let mut timeout = 1000;
     let mut retry_attempts = 0;
@@ -124,9 +148,17 @@ pub async fn handle_add_contract_command(
         }
 
         let item = &metadata.items[0];
-        if item.proxy == 1 {
-            if let Some(implementation) = &item.implementation {
-                abi_lookup_address = implementation.to_string().parse()?;
+        if proxy_abi.is_none() {
+            if implementations.is_empty() && item.proxy == 1 {
+                if let Some(implementation) = &item.implementation {
+                    implementations.push(implementation.to_string().parse()?);
+                }
+            }
+
+            // Keep the proxy ABI so its own events (Upgraded, AdminChanged..) are indexed too
+            if let Some(implementation) = implementations.first() {
+                proxy_abi = Some(item.abi.clone());
+                abi_lookup_address = *implementation;
                 println!(
                     "This contract is a proxy contract. Loading the implementation contract {abi_lookup_address}"
                 );
@@ -150,11 +182,23 @@ pub async fn handle_add_contract_command(
         let abi_file_name = format!("{contract_name}.abi.json");
 
         let abi_path = rindexer_abis_folder.join(&abi_file_name);
-        write_file(&abi_path, &item.abi).map_err(|e| {
+        write_file(&abi_path, proxy_abi.as_ref().unwrap_or(&item.abi)).map_err(|e| {
             print_error_message(&format!("Failed to write ABI file: {e}"));
             e
         })?;
 
+        if proxy_abi.is_some() {
+            write_implementation_abis(
+                &project_path,
+                &contract_name,
+                chain_id,
+                &etherscan_api_key,
+                &implementations,
+                &item.abi,
+            )
+            .await?;
+        }
+
         let abi_path_relative = format!("./abis/{abi_file_name}");
 
         print_success_message(&format!(
@@ -184,6 +228,8 @@ pub async fn handle_add_contract_command(
             streams: None,
             chat: None,
             tables: None,
+            proxy: proxy_abi.is_some().then_some(ProxySetting::Auto),
+            retention: None,
         });
 
         write_manifest(&manifest, &rindexer_yaml_path).map_err(|e| {
@@ -198,3 +244,218 @@ pub async fn handle_add_contract_command(
 
     Ok(())
 }
+
+/// Resolve proxy implementations over the network RPC, any failure falls back to the explorer
+/// proxy detection.
+async fn detect_contract_proxy(
+    manifest: &Manifest,
+    network: &str,
+    address: Address,
+) -> Option<ProxyImplementation> {
+    let network = manifest.networks.iter().find(|n| n.name == network)?;
+    let rpc = substitute_env_variables(&network.rpc).ok()?;
+
+    let provider = match create_client(
+        &rpc,
+        network.chain_id,
+        None,
+        None,
+        None,
+        manifest.get_custom_headers(),
+        None,
+        None,
+    )
+    .await
+    {
+        Ok(provider) => provider,
+        Err(e) => {
+            print_warn_message(&format!(
+                "Could not connect to {} to check for proxies: {e}",
+                network.name
+            ));
+            return None;
+        }
+    };
+
+    detect_proxy(provider.as_ref(), address)
+        .await
+        .inspect_err(|e| print_warn_message(&format!("Could not check for proxies: {e}")))
+        .ok()
+        .flatten()
+}
+
+/// Write the implementation ABIs for a proxy, the first implementation ABI has already been
+/// downloaded and the rest (diamond facets) are fetched here.
+async fn write_implementation_abis(
+    project_path: &Path,
+    contract_name: &str,
+    chain_id: u64,
+    etherscan_api_key: &str,
+    implementations: &[Address],
+    first_implementation_abi: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for (i, implementation) in implementations.iter().enumerate() {
+        let abi = if i == 0 {
+            first_implementation_abi.to_string()
+        } else {
+            // Avoid hitting the explorer rate limits
+            tokio::time::sleep(Duration::from_millis(1000)).await;
+            match fetch_etherscan_abi(chain_id, *implementation, etherscan_api_key).await {
+                Ok(abi) => abi,
+                Err(e) => {
+                    print_warn_message(&format!(
+                        "Could not download the ABI for implementation {implementation}: {e}"
+                    ));
+                    continue;
+                }
+            }
+        };
+
+        let abi_path = implementation_abi_path(project_path, contract_name, *implementation);
+        write_file(&abi_path, &abi).map_err(|e| {
+            print_error_message(&format!("Failed to write ABI file: {e}"));
+            e
+        })?;
+
+        print_success_message(&format!(
+            "Downloaded implementation ABI for: {contract_name} in {}",
+            abi_path.strip_prefix(project_path).unwrap_or(&abi_path).display()
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn handle_add_from_artifacts_command(
+    project_path: PathBuf,
+    artifacts_path: PathBuf,
+    contract_names: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    validate_rindexer_yaml_exist(&project_path);
+
+    let rindexer_yaml_path = project_path.join(YAML_CONFIG_NAME);
+
+    let mut manifest = read_manifest_raw(&rindexer_yaml_path).inspect_err(|e| {
+        print_error_message(&format!("Could not read the rindexer.yaml file: {e}"))
+    })?;
+
+    let networks: Vec<(String, u64)> =
+        manifest.networks.iter().map(|network| (network.name.clone(), network.chain_id)).collect();
+    let networks: Vec<(&str, u64)> =
+        networks.iter().map(|(name, chain_id)| (name.as_str(), *chain_id)).collect();
+
+    if networks.is_empty() {
+        print_error_message(
+            "No networks found in rindexer.yaml. Please add a network first before adding contracts.",
+        );
+        return Err("No networks found in rindexer.yaml.".into());
+    }
+
+    let artifacts = read_artifacts(&artifacts_path)
+        .inspect_err(|e| print_error_message(&format!("Could not read artifacts: {e}")))?;
+
+    for name in &artifacts.missing_abis {
+        print_warn_message(&format!(
+            "Skipping {name} as no ABI was found for it, make sure the project is compiled."
+        ));
+    }
+
+    for name in contract_names {
+        if !artifacts.contracts.iter().any(|c| &c.name == name) {
+            print_warn_message(&format!("No deployments found for contract {name}."));
+        }
+    }
+
+    let rindexer_abis_folder = project_path.join("abis");
+    let mut added = 0;
+
+    for artifact in artifacts
+        .contracts
+        .iter()
+        .filter(|c| contract_names.is_empty() || contract_names.contains(&c.name))
+    {
+        let (details, unmatched) = artifact.contract_details(&networks);
+
+        for deployment in unmatched {
+            let network = deployment
+                .chain_id
+                .map(|chain_id| format!("chain id {chain_id}"))
+                .or(deployment.network_name.map(|name| format!("network {name}")))
+                .unwrap_or_else(|| "an unknown network".to_string());
+            print_warn_message(&format!(
+                "Skipping {} deployment at {} as {network} is not in rindexer.yaml.",
+                artifact.name, deployment.address
+            ));
+        }
+
+        if details.is_empty() {
+            continue;
+        }
+
+        // Re-running the command only adds networks which are not mapped yet
+        if let Some(existing) = manifest.contracts.iter_mut().find(|c| c.name == artifact.name) {
+            for detail in details {
+                if existing.details.iter().any(|d| d.network == detail.network) {
+                    print_warn_message(&format!(
+                        "Contract {} is already mapped on {}, skipping.",
+                        artifact.name, detail.network
+                    ));
+                    continue;
+                }
+
+                print_success_message(&format!(
+                    "Added {} network to contract: {}",
+                    detail.network, artifact.name
+                ));
+                existing.details.push(detail);
+                added += 1;
+            }
+            continue;
+        }
+
+        let abi_file_name = format!("{}.abi.json", artifact.name);
+        write_file(&rindexer_abis_folder.join(&abi_file_name), &artifact.abi).map_err(|e| {
+            print_error_message(&format!("Failed to write ABI file: {e}"));
+            e
+        })?;
+
+        let abi_path_relative = format!("./abis/{abi_file_name}");
+
+        print_success_message(&format!(
+            "Added contract: {} on {} with ABI path: {abi_path_relative}",
+            artifact.name,
+            details.iter().map(|d| d.network.as_str()).collect::<Vec<_>>().join(", ")
+        ));
+
+        manifest.contracts.push(Contract {
+            name: artifact.name.clone(),
+            details,
+            abi: StringOrArray::Single(abi_path_relative),
+            include_events: None,
+            index_event_in_order: None,
+            dependency_events: None,
+            reorg_safe_distance: None,
+            generate_csv: None,
+            streams: None,
+            chat: None,
+            tables: None,
+            proxy: None,
+            retention: None,
+        });
+        added += 1;
+    }
+
+    if added == 0 {
+        print_warn_message("No new contracts were added to rindexer.yaml.");
+        return Ok(());
+    }
+
+    write_manifest(&manifest, &rindexer_yaml_path).map_err(|e| {
+        print_error_message(&format!("Failed to write rindexer.yaml file: {e}"));
+        e
+    })?;
+
+    print_success_message("Updated rindexer.yaml with the contracts from the artifacts.");
+
+    Ok(())
+}
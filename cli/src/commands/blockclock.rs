@@ -0,0 +1,79 @@
+use std::path::PathBuf;
+
+use rindexer::{
+    blockclock::{blockclock_home_filepath, get_blockclock_filepath, DeltaEncoder},
+    manifest::yaml::{read_manifest, YAML_CONFIG_NAME},
+    setup_info_logger,
+};
+
+use crate::{
+    cli_interface::BlockclockSubcommands,
+    console::{print_error_message, print_success_message},
+};
+
+const DEFAULT_BATCH_SIZE: u64 = 100;
+
+pub async fn handle_blockclock_command(
+    project_path: PathBuf,
+    subcommand: &BlockclockSubcommands,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let manifest = read_manifest(&project_path.join(YAML_CONFIG_NAME)).map_err(|e| {
+        print_error_message(&format!("Could read the rindexer.yaml please make sure you are running the command with rindexer.yaml in root: trace: {e}"));
+        e
+    })?;
+
+    let args = match subcommand {
+        BlockclockSubcommands::Build(args) | BlockclockSubcommands::Extend(args) => args,
+    };
+
+    let Some(network) = manifest.networks.iter().find(|n| n.name == args.network) else {
+        let error_message = format!("Network {} not found in rindexer.yaml", args.network);
+        print_error_message(&error_message);
+        return Err(error_message.into());
+    };
+
+    let Some(output_path) = blockclock_home_filepath(network.chain_id) else {
+        let error_message = "Could not resolve the home directory to write the blockclock to";
+        print_error_message(error_message);
+        return Err(error_message.into());
+    };
+
+    setup_info_logger();
+
+    let network_id = network.chain_id as u32;
+    let mut encoder = match subcommand {
+        BlockclockSubcommands::Build(_) => {
+            DeltaEncoder::new(network_id, Some(&network.rpc), &output_path)
+        }
+        BlockclockSubcommands::Extend(_) => {
+            // start from the bundled encoding when there is none in the home directory yet
+            let existing_path =
+                get_blockclock_filepath(network.chain_id).unwrap_or(output_path.clone());
+            let mut encoder =
+                DeltaEncoder::from_file_inner(network_id, Some(&network.rpc), &existing_path)
+                    .map_err(|e| {
+                        print_error_message(&format!(
+                            "Could not read the blockclock at {}: {e}",
+                            existing_path.display()
+                        ));
+                        e
+                    })?;
+            encoder.set_file_path(&output_path);
+            encoder
+        }
+    };
+
+    encoder.poll_encode_loop(args.batch_size.unwrap_or(DEFAULT_BATCH_SIZE)).await.map_err(|e| {
+        print_error_message(&format!("Could not encode the blocks of {}: {e:#}", network.name));
+        e
+    })?;
+
+    print_success_message(&format!(
+        "Encoded {} blocks of {} to {}",
+        encoder.max_block(),
+        network.name,
+        output_path.display()
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use std::path::PathBuf;
+
+use rindexer::{
+    manifest::yaml::{read_manifest, YAML_CONFIG_NAME},
+    rpc_cache::RpcDiskCache,
+};
+
+use crate::{
+    cli_interface::CacheSubcommands,
+    console::{print_error_message, print_success_message},
+};
+
+pub fn handle_cache_command(
+    project_path: PathBuf,
+    subcommand: &CacheSubcommands,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let manifest = read_manifest(&project_path.join(YAML_CONFIG_NAME)).map_err(|e| {
+        print_error_message(&format!("Could read the rindexer.yaml please make sure you are running the command with rindexer.yaml in root: trace: {e}"));
+        e
+    })?;
+
+    // the default location is pruned when `rpc_cache` is no longer set in the yaml
+    let config = manifest.config.rpc_cache.unwrap_or_default();
+
+    match subcommand {
+        CacheSubcommands::Prune { max_size_mb, all } => {
+            let max_size_bytes = if *all {
+                0
+            } else {
+                max_size_mb
+                    .map(|mb| mb.saturating_mul(1024 * 1024))
+                    .unwrap_or(config.max_size_bytes())
+            };
+
+            let cache = RpcDiskCache::open(config.root(&project_path), max_size_bytes);
+            let result = cache.prune(max_size_bytes).map_err(|e| {
+                print_error_message(&format!(
+                    "Could not prune the RPC cache at {}: {e}",
+                    cache.root().display()
+                ));
+                e
+            })?;
+
+            print_success_message(&format!(
+                "Pruned {} cached responses ({} MB) from {}, {} MB left",
+                result.removed_entries,
+                result.removed_bytes / 1024 / 1024,
+                cache.root().display(),
+                result.remaining_bytes / 1024 / 1024
+            ));
+
+            Ok(())
+        }
+    }
+}
@@ -13,6 +13,7 @@ use rindexer::{
         graphql::default_graphql_port,
         yaml::{read_manifest, YAML_CONFIG_NAME},
     },
+    proxy::sync_manifest_proxy_abis,
 };
 
 pub async fn handle_codegen_command(
@@ -46,6 +47,12 @@ pub async fn handle_codegen_command(
         return Err(error.into());
     }
 
+    // `proxy: auto` contracts merge their implementation ABIs into the generated bindings
+    sync_manifest_proxy_abis(&project_path, &manifest).await.map_err(|e| {
+        print_error_message(&format!("Could not resolve the proxy implementations: {e}"));
+        e
+    })?;
+
     match subcommand {
         CodegenSubcommands::Typings => {
             generate_rindexer_typings(&manifest, &rindexer_yaml_path, true).map_err(|e| {
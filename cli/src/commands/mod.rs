@@ -1,8 +1,12 @@
 pub mod add;
+pub mod blockclock;
+pub mod cache;
 pub mod codegen;
 pub mod delete;
 pub mod new;
 pub mod phantom;
+pub mod schema;
 pub mod start;
+pub mod validate;
 
 const BACKUP_ETHERSCAN_API_KEY: &str = "DHBPB1EJ84JMSWP7C86387NK7IIRRQJVV1";
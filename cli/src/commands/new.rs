@@ -220,6 +220,8 @@ pub fn handle_new_command(
             timestamp_sample_rate: None,
             max_concurrent_view_calls: None,
             fetch_concurrency: None,
+            rpc_cache: None,
+            blockclock_catch_up: None,
         },
         constants: HashMap::new(),
         timestamps: None,
@@ -227,13 +229,16 @@ pub fn handle_new_command(
             name: "ethereum".to_string(),
             chain_id: 1,
             rpc: "https://mainnet.gateway.tenderly.co".to_string(),
+            ws_rpc: None,
             block_poll_frequency: None,
             compute_units_per_second: None,
             max_block_range: None,
             disable_logs_bloom_checks: None,
+            shared_live_fetcher: None,
             get_logs_settings: None,
             reth: final_reth_config,
             multicall3_address: None,
+            log_archive: None,
             reorg_handling: None,
         }],
         contracts: vec![Contract {
@@ -251,8 +256,8 @@ pub fn handle_new_command(
             )],
             abi: StringOrArray::Single(abi_example_path.display().to_string()),
             include_events: Some(vec![
-                ContractEvent { name: "Transfer".to_string(), timestamps: None },
-                ContractEvent { name: "Approval".to_string(), timestamps: None },
+                ContractEvent { name: "Transfer".to_string(), timestamps: None, retention: None },
+                ContractEvent { name: "Approval".to_string(), timestamps: None, retention: None },
             ]),
             index_event_in_order: None,
             dependency_events: None,
@@ -261,10 +266,13 @@ pub fn handle_new_command(
             streams: None,
             chat: None,
             tables: None,
+            proxy: None,
+            retention: None,
         }],
         native_transfers: NativeTransfers::default(),
         phantom: None,
         global: Global::default(),
+        templated_tables: HashMap::new(),
         storage: Storage {
             postgres: if postgres_enabled {
                 Some(PostgresDetails {
@@ -538,6 +538,7 @@ async fn handle_phantom_deploy(
                         name: name.to_string(),
                         chain_id: network.unwrap().chain_id,
                         rpc: rpc_url.to_string(),
+                        ws_rpc: None,
                         compute_units_per_second: None,
                         block_poll_frequency: None,
                         max_block_range: if phantom.dyrpc_enabled() {
@@ -546,9 +547,11 @@ async fn handle_phantom_deploy(
                             Some(U64::from(2_000))
                         },
                         disable_logs_bloom_checks: None,
+                        shared_live_fetcher: None,
                         get_logs_settings: None,
                         reth: None,
                         multicall3_address: None,
+                        log_archive: None,
                         reorg_handling: None,
                     });
                 }
@@ -0,0 +1,33 @@
+use std::{fs, path::PathBuf};
+
+use rindexer::manifest::schema::{generate_manifest_schema_json, SCHEMA_FILE_NAME};
+
+use crate::console::{print_error_message, print_success_message};
+
+pub fn handle_schema_command(
+    project_path: PathBuf,
+    stdout: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let schema = generate_manifest_schema_json().map_err(|e| {
+        print_error_message(&format!("Failed to generate the rindexer.yaml schema: {e}"));
+        e
+    })?;
+
+    if stdout {
+        println!("{schema}");
+        return Ok(());
+    }
+
+    let schema_path = project_path.join(SCHEMA_FILE_NAME);
+    fs::write(&schema_path, format!("{schema}\n")).map_err(|e| {
+        print_error_message(&format!("Failed to write {}: {e}", schema_path.display()));
+        e
+    })?;
+
+    print_success_message(&format!(
+        "Generated {}\n\nAdd `# yaml-language-server: $schema=./{SCHEMA_FILE_NAME}` to the top of your rindexer.yaml to enable editor autocomplete and validation.",
+        schema_path.display()
+    ));
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use std::{env, path::PathBuf, process::Command, thread, time::Duration};
+use std::{collections::HashMap, env, path::PathBuf, process::Command, thread, time::Duration};
 
 use colored::Colorize;
 use rindexer::{
@@ -223,6 +223,30 @@ async fn handle_schema_sync(
                     "    Type changes require manual migration. Please backup your data and handle this manually.",
                 );
             }
+            SchemaChange::AddIndex { .. }
+            | SchemaChange::DropIndex { .. }
+            | SchemaChange::ChangeIndex { .. } => {
+                // Indexes don't hold data, auto-apply them
+                println!("  {} {}", "✓".green(), change.description());
+
+                if let Err(e) = apply_schema_change(client, change).await {
+                    println!("    {} Failed to update index: {}", "✗".red(), e);
+                    return Err(e);
+                }
+                println!("    {} Index updated successfully", "→".green());
+            }
+            SchemaChange::ChangePartition { table_full_name, current_partition, new_partition } => {
+                println!(
+                    "\n  {} Partitioning change detected for table '{}':",
+                    "!".red().bold(),
+                    table_full_name
+                );
+                println!("    Current: {}", current_partition.as_deref().unwrap_or("none").red());
+                println!("    New:     {}", new_partition.as_deref().unwrap_or("none").green());
+                print_warn_message(
+                    "    Partitioning changes require manual migration. Please backup your data and recreate the table.",
+                );
+            }
         }
     }
 
@@ -365,6 +389,44 @@ async fn handle_clickhouse_schema_sync(
                     "    Type changes require manual migration. Please backup your data and handle this manually.",
                 );
             }
+            ClickhouseSchemaChange::AddIndex { .. }
+            | ClickhouseSchemaChange::DropIndex { .. }
+            | ClickhouseSchemaChange::ChangeIndex { .. } => {
+                // Indexes don't hold data, auto-apply them
+                println!("  {} {}", "✓".green(), change.description());
+
+                if let Err(e) = apply_clickhouse_schema_change(client, change).await {
+                    println!("    {} Failed to update index: {}", "✗".red(), e);
+                    return Err(e);
+                }
+                println!("    {} Index updated successfully", "→".green());
+            }
+            ClickhouseSchemaChange::ChangePartition {
+                table_full_name,
+                current_partition,
+                new_partition,
+            } => {
+                println!(
+                    "\n  {} Partitioning change detected for table '{}':",
+                    "!".red().bold(),
+                    table_full_name
+                );
+                println!("    Current: {}", current_partition.as_deref().unwrap_or("none").red());
+                println!("    New:     {}", new_partition.as_deref().unwrap_or("none").green());
+                print_warn_message(
+                    "    Partitioning changes require manual migration. Please backup your data and recreate the table.",
+                );
+            }
+            ClickhouseSchemaChange::ChangeTtl { .. } => {
+                // The TTL follows the retention in the YAML, auto-apply it
+                println!("  {} {}", "✓".green(), change.description());
+
+                if let Err(e) = apply_clickhouse_schema_change(client, change).await {
+                    println!("    {} Failed to update TTL: {}", "✗".red(), e);
+                    return Err(e);
+                }
+                println!("    {} TTL updated successfully", "→".green());
+            }
         }
     }
 
@@ -500,9 +562,13 @@ pub async fn start(
         }
     }
 
-    // ClickHouse schema sync for no-code projects with custom tables
+    // ClickHouse schema sync for no-code projects with custom tables or retention policies
     if manifest.storage.clickhouse_enabled() && manifest.project_type == ProjectType::NoCode {
-        let has_tables = manifest.contracts.iter().any(|c| c.tables.is_some());
+        let has_tables = manifest.contracts.iter().any(|c| {
+            c.tables.is_some()
+                || c.retention.is_some()
+                || c.include_events.iter().flatten().any(|e| e.retention.is_some())
+        });
         if has_tables {
             // Resolve column types from ABI before schema sync (if not already done for postgres)
             if !manifest.storage.postgres_enabled() {
@@ -562,7 +628,10 @@ pub async fn start(
             StartSubcommands::Indexer => {
                 let details = StartNoCodeDetails {
                     manifest_path: &project_path.join(YAML_CONFIG_NAME),
-                    indexing_details: IndexerNoCodeDetails { enabled: true },
+                    indexing_details: IndexerNoCodeDetails {
+                        enabled: true,
+                        provider_overrides: HashMap::new(),
+                    },
                     graphql_details: GraphqlOverrideSettings {
                         enabled: false,
                         override_port: None,
@@ -578,7 +647,10 @@ pub async fn start(
             StartSubcommands::Graphql { port } => {
                 let details = StartNoCodeDetails {
                     manifest_path: &project_path.join(YAML_CONFIG_NAME),
-                    indexing_details: IndexerNoCodeDetails { enabled: false },
+                    indexing_details: IndexerNoCodeDetails {
+                        enabled: false,
+                        provider_overrides: HashMap::new(),
+                    },
                     graphql_details: GraphqlOverrideSettings {
                         enabled: true,
                         override_port: port.as_ref().and_then(|port| port.parse().ok()),
@@ -594,7 +666,10 @@ pub async fn start(
             StartSubcommands::All { port } => {
                 let details = StartNoCodeDetails {
                     manifest_path: &project_path.join(YAML_CONFIG_NAME),
-                    indexing_details: IndexerNoCodeDetails { enabled: true },
+                    indexing_details: IndexerNoCodeDetails {
+                        enabled: true,
+                        provider_overrides: HashMap::new(),
+                    },
                     graphql_details: GraphqlOverrideSettings {
                         enabled: true,
                         override_port: port.as_ref().and_then(|port| port.parse().ok()),
@@ -0,0 +1,37 @@
+use std::path::PathBuf;
+
+use rindexer::manifest::{
+    compose::{compose_manifest, environment_from_env},
+    yaml::{read_manifest_for_environment, YAML_CONFIG_NAME},
+};
+
+use crate::console::{print_error_message, print_success_message};
+
+pub fn handle_validate_command(
+    project_path: PathBuf,
+    environment: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let manifest_path = project_path.join(YAML_CONFIG_NAME);
+    let environment = environment.or_else(environment_from_env);
+
+    read_manifest_for_environment(&manifest_path, environment.as_deref()).map_err(|e| {
+        print_error_message(&format!("{YAML_CONFIG_NAME} is not valid: {e}"));
+        e
+    })?;
+
+    // show the manifest before env variables are substituted so secrets are not printed
+    let composed = compose_manifest(&manifest_path, environment.as_deref(), false)?;
+
+    println!("{}", composed.contents.trim_end());
+    println!();
+    println!("# composed from:");
+    for file in &composed.files {
+        println!("#   {}", file.strip_prefix(&project_path).unwrap_or(file).display());
+    }
+
+    let environment =
+        environment.map(|e| format!(" for the `{e}` environment")).unwrap_or_default();
+    print_success_message(&format!("\n{YAML_CONFIG_NAME} is valid{environment}"));
+
+    Ok(())
+}
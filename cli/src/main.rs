@@ -23,9 +23,16 @@ use rindexer::manifest::reth::RethConfig;
 use crate::{
     cli_interface::{AddSubcommands, Commands, NewSubcommands, CLI},
     commands::{
-        add::handle_add_contract_command, codegen::handle_codegen_command,
-        delete::handle_delete_command, new::handle_new_command, phantom::handle_phantom_commands,
+        add::{handle_add_contract_command, handle_add_from_artifacts_command},
+        blockclock::handle_blockclock_command,
+        cache::handle_cache_command,
+        codegen::handle_codegen_command,
+        delete::handle_delete_command,
+        new::handle_new_command,
+        phantom::handle_phantom_commands,
+        schema::handle_schema_command,
         start::start,
+        validate::handle_validate_command,
     },
     console::print_error_message,
 };
@@ -85,6 +92,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             match subcommand {
                 AddSubcommands::Contract => handle_add_contract_command(resolved_path).await,
+                AddSubcommands::FromArtifacts { artifacts_path, contract } => {
+                    let artifacts_path = match artifacts_path {
+                        Some(_) => {
+                            resolve_path(artifacts_path).inspect_err(|e| print_error_message(e))?
+                        }
+                        None => resolved_path.clone(),
+                    };
+                    handle_add_from_artifacts_command(resolved_path, artifacts_path, contract)
+                }
             }
         }
         Commands::Codegen { subcommand, path } => {
@@ -102,6 +118,25 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             load_env_from_project_path(&resolved_path);
             handle_delete_command(resolved_path).await
         }
+        Commands::Schema { stdout, path } => {
+            let resolved_path = resolve_path(path).inspect_err(|e| print_error_message(e))?;
+            handle_schema_command(resolved_path, *stdout)
+        }
+        Commands::Validate { env, path } => {
+            let resolved_path = resolve_path(path).inspect_err(|e| print_error_message(e))?;
+            load_env_from_project_path(&resolved_path);
+            handle_validate_command(resolved_path, env.clone())
+        }
+        Commands::Cache { subcommand, path } => {
+            let resolved_path = resolve_path(path).inspect_err(|e| print_error_message(e))?;
+            load_env_from_project_path(&resolved_path);
+            handle_cache_command(resolved_path, subcommand)
+        }
+        Commands::Blockclock { subcommand, path } => {
+            let resolved_path = resolve_path(path).inspect_err(|e| print_error_message(e))?;
+            load_env_from_project_path(&resolved_path);
+            handle_blockclock_command(resolved_path, subcommand).await
+        }
         Commands::Phantom { subcommand, path } => {
             let resolved_path = resolve_path(path).inspect_err(|e| print_error_message(e))?;
             load_env_from_project_path(&resolved_path);
@@ -1,4 +1,8 @@
-use std::{collections::HashSet, fs, path::Path};
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
 
 use alloy::{
     primitives::{keccak256, B256},
@@ -410,8 +414,8 @@ impl EventInfo {
         contract_name: &str,
         csv_path: &str,
     ) -> Result<String, CreateCsvFileForEvent> {
-        let csv_file_name = format!("{}-{}.csv", contract_name, self.name).to_lowercase();
-        let csv_folder = project_path.join(csv_path).join(contract_name);
+        let csv_file = csv_file_path_for_event(project_path, csv_path, contract_name, &self.name);
+        let csv_folder = csv_file.parent().expect("CSV file path has a folder");
 
         // Create directory if it does not exist.
         if let Err(e) = fs::create_dir_all(&csv_folder) {
@@ -423,10 +427,22 @@ impl EventInfo {
             return Err(CreateCsvFileForEvent::CreateDirFailed(e));
         }
 
-        Ok(csv_folder.join(csv_file_name).display().to_string())
+        Ok(csv_file.display().to_string())
     }
 }
 
+/// Path of the CSV file of an event, `{csv_path}/{contract}/{contract}-{event}.csv` in
+/// lowercase, relative to `project_path`.
+pub fn csv_file_path_for_event(
+    project_path: &Path,
+    csv_path: &str,
+    contract_name: &str,
+    event_name: &str,
+) -> PathBuf {
+    let csv_file_name = format!("{}-{}.csv", contract_name, event_name).to_lowercase();
+    project_path.join(csv_path).join(contract_name).join(csv_file_name)
+}
+
 pub struct GetAbiItemWithDbMap {
     pub abi_item: ABIInput,
     pub db_column_name: String,
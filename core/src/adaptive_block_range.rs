@@ -0,0 +1,483 @@
+//! Adaptive `eth_getLogs` block ranges, learned per network provider and event.
+//!
+//! Historical sync asks the event's [`AdaptiveBlockRange`] how wide the next `eth_getLogs` may
+//! be, events differ too much in log density to share one range. The range grows while responses come back sparse and fast, shrinks towards a target number
+//! of logs per request as the density goes up, and follows the ranges the provider suggests when it
+//! rejects a request. The learned parameters are stored in `rindexer_internal.block_range_state`
+//! so a restart does not have to learn them again.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use alloy::primitives::U64;
+use anyhow::Context;
+use clickhouse::Row;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use tokio::sync::OnceCell;
+use tracing::{debug, info, warn};
+use url::Url;
+
+use crate::database::clickhouse::client::ClickhouseClient;
+use crate::database::postgres::client::PostgresClient;
+use crate::indexer::reorg::validate_sql_value;
+
+/// Logs a single `eth_getLogs` response should carry, well below the 10k most providers cap at.
+const TARGET_LOGS_PER_REQUEST: f64 = 5_000.0;
+
+/// Responses slower than this shrink the range.
+const TARGET_LATENCY_MS: f64 = 2_000.0;
+
+/// The range is never learned below this many blocks, provider suggestions can still go lower.
+const MIN_BLOCK_RANGE: u64 = 10;
+
+/// Weight of the newest sample in the moving averages.
+const EMA_ALPHA: f64 = 0.3;
+
+/// How often the learned state of an event is written to the database.
+const PERSIST_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The provider key of each network, see [`provider_key`].
+static BLOCK_RANGE_PROVIDERS: Lazy<Mutex<HashMap<String, String>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// The controllers keyed by network and event.
+static BLOCK_RANGE_CONTROLLERS: Lazy<Mutex<HashMap<(String, String), Arc<AdaptiveBlockRange>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Register the provider behind `rpc_url` for `network`, the controllers of its events learn for
+/// that provider. Controllers learned for a previous provider are dropped.
+pub fn register_block_range_controller(network: &str, rpc_url: &str) {
+    BLOCK_RANGE_PROVIDERS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(network.to_string(), provider_key(rpc_url));
+    BLOCK_RANGE_CONTROLLERS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .retain(|(controller_network, _), _| controller_network != network);
+}
+
+/// The controller of `event` on `network`, shared by every fetch of the event.
+pub fn block_range_controller(network: &str, event: &str) -> Arc<AdaptiveBlockRange> {
+    let provider = BLOCK_RANGE_PROVIDERS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(network)
+        .cloned()
+        .unwrap_or_else(|| "default".to_string());
+
+    BLOCK_RANGE_CONTROLLERS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .entry((network.to_string(), event.to_string()))
+        .or_insert_with(|| Arc::new(AdaptiveBlockRange::new(network, &provider, event)))
+        .clone()
+}
+
+/// Identifies a provider by its host, the path and query often carry an API key.
+fn provider_key(rpc_url: &str) -> String {
+    let host = Url::parse(rpc_url)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.to_string()))
+        .unwrap_or_else(|| "default".to_string());
+
+    host.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' }).collect()
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BlockRangeState {
+    /// The learned `eth_getLogs` range, `None` while requesting the full range works.
+    pub block_range: Option<u64>,
+    /// A hard range limit the provider enforces regardless of the logs returned.
+    pub provider_limit: Option<u64>,
+    /// Moving average of logs per block.
+    pub logs_per_block: f64,
+    /// Moving average of the `eth_getLogs` latency.
+    pub latency_ms: f64,
+}
+
+pub struct AdaptiveBlockRange {
+    network: String,
+    provider: String,
+    event: String,
+    state: Mutex<BlockRangeState>,
+    loaded: OnceCell<()>,
+    dirty: AtomicBool,
+    last_persisted: Mutex<Instant>,
+}
+
+impl AdaptiveBlockRange {
+    pub fn new(network: &str, provider: &str, event: &str) -> Self {
+        Self {
+            network: network.to_string(),
+            provider: provider.to_string(),
+            event: event.to_string(),
+            state: Mutex::new(BlockRangeState::default()),
+            loaded: OnceCell::new(),
+            dirty: AtomicBool::new(false),
+            last_persisted: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn state(&self) -> BlockRangeState {
+        *self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The range limitation to fetch with: the smallest of the learned range, the provider
+    /// limit and `max_block_range_limitation` from the network config or the last error.
+    pub fn limit(&self, max_block_range_limitation: Option<U64>) -> Option<U64> {
+        let state = self.state();
+        [state.block_range, state.provider_limit, max_block_range_limitation.map(|m| m.to())]
+            .into_iter()
+            .flatten()
+            .min()
+            .map(U64::from)
+    }
+
+    /// Learn from a successful `eth_getLogs` over `block_range` blocks.
+    pub fn record_success(&self, block_range: u64, logs: usize, latency: Duration) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let blocks = (block_range + 1) as f64;
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        state.logs_per_block = ema(state.logs_per_block, logs as f64 / blocks);
+        state.latency_ms = ema(state.latency_ms, latency_ms);
+
+        let slow = latency_ms > TARGET_LATENCY_MS;
+        let sparse = (logs as f64) < TARGET_LOGS_PER_REQUEST / 4.0;
+
+        // Fetching the full range keeps working, nothing to narrow down yet.
+        let Some(current) =
+            state.block_range.or(if slow || !sparse { Some(block_range) } else { None })
+        else {
+            return;
+        };
+
+        let current = current as f64;
+        let next = if slow {
+            (current * TARGET_LATENCY_MS / latency_ms).max(current / 2.0)
+        } else if sparse {
+            current * 2.0
+        } else {
+            (TARGET_LOGS_PER_REQUEST / state.logs_per_block.max(f64::EPSILON))
+                .clamp(current / 2.0, current * 2.0)
+        };
+
+        let mut next = (next as u64).max(MIN_BLOCK_RANGE);
+        if let Some(provider_limit) = state.provider_limit {
+            next = next.min(provider_limit);
+        }
+
+        if state.block_range != Some(next) {
+            debug!(
+                "{} - {} - eth_getLogs block range {} -> {} ({} logs in {:.0}ms)",
+                self.network, self.event, current, next, logs, latency_ms
+            );
+            state.block_range = Some(next);
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Learn from a request the provider rejected, suggesting `suggested_range` instead.
+    /// `provider_limit` is set when the error revealed a hard limit on the range.
+    pub fn record_rejected(&self, suggested_range: u64, provider_limit: Option<u64>) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(provider_limit) = provider_limit {
+            if state.provider_limit != Some(provider_limit) {
+                info!(
+                    "{} - Provider limits eth_getLogs to a range of {} blocks",
+                    self.network, provider_limit
+                );
+            }
+            state.provider_limit = Some(provider_limit);
+        }
+        state.block_range = Some(suggested_range.max(1));
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Load the learned state of the event once, later calls return immediately.
+    pub async fn load(&self, store: &BlockRangeStore) {
+        self.loaded
+            .get_or_init(|| async {
+                match store.load(&self.network, &self.provider, &self.event).await {
+                    Ok(Some(loaded)) => {
+                        info!(
+                            "{} - {} - Resuming learned eth_getLogs block range {:?}",
+                            self.network, self.event, loaded.block_range
+                        );
+                        *self.state.lock().unwrap_or_else(|e| e.into_inner()) = loaded;
+                    }
+                    Ok(None) => {}
+                    Err(e) => debug!(
+                        "{} - {} - No learned eth_getLogs block range: {:?}",
+                        self.network, self.event, e
+                    ),
+                }
+            })
+            .await;
+    }
+
+    /// Store the learned state if it changed and the last write is `PERSIST_INTERVAL` ago.
+    pub async fn persist_if_due(&self, store: &BlockRangeStore) {
+        let due = {
+            let mut last_persisted = self.last_persisted.lock().unwrap_or_else(|e| e.into_inner());
+            let due = last_persisted.elapsed() >= PERSIST_INTERVAL;
+            if due {
+                *last_persisted = Instant::now();
+            }
+            due
+        };
+
+        if due {
+            self.persist(store).await;
+        }
+    }
+
+    /// Store the learned state if it changed since the last write.
+    pub async fn persist(&self, store: &BlockRangeStore) {
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return;
+        }
+
+        if let Err(e) = store.store(&self.network, &self.provider, &self.event, &self.state()).await
+        {
+            self.dirty.store(true, Ordering::Relaxed);
+            warn!(
+                "{} - {} - Failed to store learned eth_getLogs block range: {:?}",
+                self.network, self.event, e
+            );
+        }
+    }
+}
+
+fn ema(average: f64, sample: f64) -> f64 {
+    if average == 0.0 {
+        sample
+    } else {
+        average + EMA_ALPHA * (sample - average)
+    }
+}
+
+/// Reads and writes `rindexer_internal.block_range_state`. Priority: postgres > clickhouse.
+pub struct BlockRangeStore {
+    postgres: Option<Arc<PostgresClient>>,
+    clickhouse: Option<Arc<ClickhouseClient>>,
+}
+
+impl BlockRangeStore {
+    pub fn new(
+        postgres: Option<Arc<PostgresClient>>,
+        clickhouse: Option<Arc<ClickhouseClient>>,
+    ) -> Self {
+        Self { postgres, clickhouse }
+    }
+
+    pub async fn load(
+        &self,
+        network: &str,
+        provider: &str,
+        event: &str,
+    ) -> anyhow::Result<Option<BlockRangeState>> {
+        validate_sql_value(network, "network")?;
+        validate_sql_value(provider, "provider")?;
+        validate_sql_value(event, "event")?;
+
+        if let Some(postgres) = &self.postgres {
+            let query = r#"
+                SELECT block_range, provider_limit, logs_per_block, latency_ms
+                FROM rindexer_internal.block_range_state
+                WHERE network = $1 AND provider = $2 AND event = $3"#;
+
+            let rows = postgres
+                .query(query, &[&network, &provider, &event])
+                .await
+                .context("Failed to load block_range_state from postgres")?;
+
+            return Ok(rows.first().map(|row| {
+                let block_range: Option<i64> = row.get("block_range");
+                let provider_limit: Option<i64> = row.get("provider_limit");
+                BlockRangeState {
+                    block_range: block_range.and_then(|r| u64::try_from(r).ok()),
+                    provider_limit: provider_limit.and_then(|l| u64::try_from(l).ok()),
+                    logs_per_block: row.get("logs_per_block"),
+                    latency_ms: row.get("latency_ms"),
+                }
+            }));
+        }
+
+        if let Some(clickhouse) = &self.clickhouse {
+            #[derive(Row, Deserialize)]
+            struct BlockRangeStateRow {
+                block_range: Option<u64>,
+                provider_limit: Option<u64>,
+                logs_per_block: f64,
+                latency_ms: f64,
+            }
+
+            let query = format!(
+                r#"SELECT block_range, provider_limit, logs_per_block, latency_ms
+                 FROM rindexer_internal.block_range_state FINAL
+                 WHERE network = '{}' AND provider = '{}' AND event = '{}'"#,
+                network, provider, event
+            );
+
+            let rows = clickhouse
+                .query_all::<BlockRangeStateRow>(&query)
+                .await
+                .context("Failed to load block_range_state from clickhouse")?;
+
+            return Ok(rows.first().map(|row| BlockRangeState {
+                block_range: row.block_range,
+                provider_limit: row.provider_limit,
+                logs_per_block: row.logs_per_block,
+                latency_ms: row.latency_ms,
+            }));
+        }
+
+        Ok(None)
+    }
+
+    pub async fn store(
+        &self,
+        network: &str,
+        provider: &str,
+        event: &str,
+        state: &BlockRangeState,
+    ) -> anyhow::Result<()> {
+        validate_sql_value(network, "network")?;
+        validate_sql_value(provider, "provider")?;
+        validate_sql_value(event, "event")?;
+
+        if let Some(postgres) = &self.postgres {
+            let query = r#"INSERT INTO rindexer_internal.block_range_state
+                         (network, provider, event, block_range, provider_limit, logs_per_block, latency_ms, updated_at)
+                         VALUES ($1, $2, $3, $4, $5, $6, $7, NOW())
+                         ON CONFLICT (network, provider, event)
+                         DO UPDATE SET block_range = EXCLUDED.block_range,
+                                       provider_limit = EXCLUDED.provider_limit,
+                                       logs_per_block = EXCLUDED.logs_per_block,
+                                       latency_ms = EXCLUDED.latency_ms,
+                                       updated_at = EXCLUDED.updated_at"#;
+
+            let block_range = state.block_range.map(|r| r as i64);
+            let provider_limit = state.provider_limit.map(|l| l as i64);
+            postgres
+                .execute(
+                    query,
+                    &[
+                        &network,
+                        &provider,
+                        &event,
+                        &block_range,
+                        &provider_limit,
+                        &state.logs_per_block,
+                        &state.latency_ms,
+                    ],
+                )
+                .await
+                .context("Failed to store block_range_state in postgres")?;
+        }
+
+        if let Some(clickhouse) = &self.clickhouse {
+            let nullable = |value: Option<u64>| value.map_or("NULL".to_string(), |v| v.to_string());
+            let query = format!(
+                "INSERT INTO rindexer_internal.block_range_state \
+                 (network, provider, event, block_range, provider_limit, logs_per_block, latency_ms) \
+                 VALUES ('{}', '{}', '{}', {}, {}, {}, {})",
+                network,
+                provider,
+                event,
+                nullable(state.block_range),
+                nullable(state.provider_limit),
+                state.logs_per_block,
+                state.latency_ms
+            );
+
+            clickhouse
+                .execute(&query)
+                .await
+                .context("Failed to store block_range_state in clickhouse")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn full_range_is_kept_while_it_works() {
+        let controller = AdaptiveBlockRange::new("test", "default", "Token_Transfer");
+        controller.record_success(1_000_000, 10, ms(100));
+        assert_eq!(controller.state().block_range, None);
+        assert_eq!(controller.limit(None), None);
+        assert_eq!(controller.limit(Some(U64::from(500))), Some(U64::from(500)));
+    }
+
+    #[test]
+    fn sparse_fast_responses_grow_the_range() {
+        let controller = AdaptiveBlockRange::new("test", "default", "Token_Transfer");
+        controller.record_rejected(1000, None);
+        controller.record_success(1000, 5, ms(100));
+        assert_eq!(controller.state().block_range, Some(2000));
+        controller.record_success(2000, 5, ms(100));
+        assert_eq!(controller.limit(None), Some(U64::from(4000)));
+    }
+
+    #[test]
+    fn dense_responses_size_towards_the_target_logs() {
+        let controller = AdaptiveBlockRange::new("test", "default", "Token_Transfer");
+        controller.record_rejected(1000, None);
+        // 10 logs per block, 5000 logs target -> ~500 blocks
+        controller.record_success(999, 10_000, ms(500));
+        assert_eq!(controller.state().block_range, Some(500));
+    }
+
+    #[test]
+    fn slow_responses_shrink_the_range() {
+        let controller = AdaptiveBlockRange::new("test", "default", "Token_Transfer");
+        controller.record_success(10_000, 10, ms(3_000));
+        assert_eq!(controller.state().block_range, Some(6_666));
+        controller.record_success(6_666, 10, ms(60_000));
+        assert_eq!(controller.state().block_range, Some(3_333));
+    }
+
+    #[test]
+    fn provider_limit_caps_growth() {
+        let controller = AdaptiveBlockRange::new("test", "default", "Token_Transfer");
+        controller.record_rejected(3000, Some(3000));
+        controller.record_success(3000, 1, ms(50));
+        assert_eq!(controller.state().block_range, Some(3000));
+        assert_eq!(controller.limit(Some(U64::from(10_000))), Some(U64::from(3000)));
+    }
+
+    #[test]
+    fn events_learn_their_own_range() {
+        register_block_range_controller("events-test", "https://rpc.example.com/key");
+        let transfers = block_range_controller("events-test", "Token_Transfer");
+        let approvals = block_range_controller("events-test", "Token_Approval");
+        transfers.record_rejected(500, None);
+
+        assert!(Arc::ptr_eq(&transfers, &block_range_controller("events-test", "Token_Transfer")));
+        assert_eq!(transfers.limit(None), Some(U64::from(500)));
+        assert_eq!(approvals.limit(None), None);
+        assert_eq!(approvals.provider, "rpc_example_com");
+    }
+
+    #[test]
+    fn provider_key_drops_path_and_query() {
+        assert_eq!(
+            provider_key("https://eth-mainnet.g.alchemy.com/v2/secret-key?x=1"),
+            "eth-mainnet_g_alchemy_com"
+        );
+        assert_eq!(provider_key("not a url"), "default");
+    }
+}
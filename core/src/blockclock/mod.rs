@@ -32,6 +32,14 @@
 //! Due to the lack of any strong guarantee, we can only do this up to a "known" block number where
 //! the fixed-timestamp consistency has been validated. If at any time a chain breaks this pattern
 //! we must drop back to delta run length encoding.
+//!
+//! ## Keeping encodings fresh
+//!
+//! `rindexer blockclock build|extend` encodes a network from an RPC. While indexing, timestamps
+//! fetched for events are appended to the loaded encoding with [`record_block_timestamps`] and
+//! written to `~/.rindexer/resources/blockclock` as long as they follow on from its last block.
+//! With `config.blockclock_catch_up` the blocks between the encoding and the indexed blocks are
+//! fetched as well, see [`blocks_to_extend`], so the file keeps up with the chain on its own.
 
 mod fetcher;
 mod fixed;
@@ -39,6 +47,8 @@ mod runlencoder;
 
 use crate::blockclock::fetcher::BlockFetcherError;
 use crate::blockclock::fixed::SpacedNetwork;
+use crate::indexer::reorg::reorg_safe_distance_for_chain;
+use crate::manifest::core::Manifest;
 use crate::provider::ChainProvider;
 use alloy::rpc::types::Log;
 pub use fetcher::BlockFetcher;
@@ -46,8 +56,21 @@ pub use runlencoder::DeltaEncoder;
 use std::env;
 use std::env::VarError;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 use thiserror::Error;
+use tracing::{error, info};
+
+/// How often encodings extended while indexing are written to disk.
+const BLOCKCLOCK_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The most blocks past an encoding's last block fetched per batch to close the gap to the
+/// indexed blocks, see [`blocks_to_extend`].
+const BLOCKCLOCK_EXTEND_BLOCKS_PER_BATCH: u64 = 500;
+
+/// Whether [`blocks_to_extend`] fetches blocks, set from `config.blockclock_catch_up`.
+static CATCH_UP: AtomicBool = AtomicBool::new(false);
 
 #[derive(Error, Debug)]
 pub enum BlockClockError {
@@ -76,7 +99,7 @@ pub struct BlockClock {
     ///
     /// Will be set to `None` if there is no backing file with pre-encoded data and the
     /// encoder lookup will be bypassed.
-    runlencoder: Option<Arc<DeltaEncoder>>,
+    runlencoder: Option<Arc<RwLock<DeltaEncoder>>>,
 }
 
 impl BlockClock {
@@ -102,6 +125,13 @@ impl BlockClock {
             })?
         });
 
+        // Encodings extended while indexing are written to the home directory so a bundled
+        // file is never modified.
+        if let (Some(coder), Some(home_path)) = (&runlencoder, blockclock_home_filepath(network_id))
+        {
+            coder.write().unwrap_or_else(|e| e.into_inner()).set_file_path(home_path);
+        }
+
         Self { network_id, fetcher, runlencoder }
     }
 
@@ -153,7 +183,7 @@ impl BlockClock {
 
         // 3. Use precomputed delta-encoded network timestamps
         let logs = if let Some(deltas) = &self.runlencoder {
-            deltas.try_attach_log_timestamps(logs)
+            deltas.read().unwrap_or_else(|e| e.into_inner()).try_attach_log_timestamps(logs)
         } else {
             logs
         };
@@ -165,10 +195,107 @@ impl BlockClock {
     }
 }
 
-fn get_blockclock_filepath(network: u64) -> Option<PathBuf> {
+/// Append timestamps fetched while indexing to the encoding loaded for a network, if any.
+///
+/// Blocks within the reorg safe distance of the highest recorded block are held back until
+/// later blocks are recorded. Blocks after a gap are only held for [`blocks_to_extend`] to fill
+/// the gap, without `config.blockclock_catch_up` they are dropped.
+pub async fn record_block_timestamps(network_id: u64, blocks: Vec<(u64, u64)>) {
+    let Some(encoder) = runlencoder::loaded_encoder(network_id as u32) else {
+        return;
+    };
+
+    let safe_distance = reorg_safe_distance_for_chain(network_id);
+    let hold_gaps = CATCH_UP.load(Ordering::Relaxed);
+    let result = tokio::task::spawn_blocking(move || {
+        let mut encoder = encoder.write().unwrap_or_else(|e| e.into_inner());
+        encoder.extend_with(blocks, safe_distance, hold_gaps)?;
+        encoder.flush_if_due(BLOCKCLOCK_FLUSH_INTERVAL)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => error!("Failed to extend blockclock for network {}: {:?}", network_id, e),
+        Err(e) => error!("Blockclock extend task for network {} panicked: {:?}", network_id, e),
+    }
+}
+
+/// The blocks right after the encoding loaded for a network to fetch along with `block_numbers`,
+/// so the encoding catches up with the indexed blocks.
+///
+/// At most [`BLOCKCLOCK_EXTEND_BLOCKS_PER_BATCH`] blocks are returned and only blocks at least
+/// the reorg safe distance behind the highest of `block_numbers`. Nothing is returned unless
+/// `config.blockclock_catch_up` is set.
+pub fn blocks_to_extend(network_id: u64, block_numbers: &[u64]) -> Vec<u64> {
+    if !CATCH_UP.load(Ordering::Relaxed) {
+        return vec![];
+    }
+    let Some(encoder) = runlencoder::loaded_encoder(network_id as u32) else {
+        return vec![];
+    };
+    let Some(highest_block) = block_numbers.iter().max() else {
+        return vec![];
+    };
+
+    let next_block = encoder.read().unwrap_or_else(|e| e.into_inner()).max_block() + 1;
+    let safe_block = highest_block.saturating_sub(reorg_safe_distance_for_chain(network_id));
+    let last_block = safe_block.min(next_block + BLOCKCLOCK_EXTEND_BLOCKS_PER_BATCH - 1);
+
+    let blocks: Vec<u64> =
+        (next_block..=last_block).filter(|block| !block_numbers.contains(block)).collect();
+    if !blocks.is_empty() {
+        info!(
+            "Blockclock: fetching {} blocks from {} to extend the encoding of network {}",
+            blocks.len(),
+            next_block,
+            network_id
+        );
+    }
+
+    blocks
+}
+
+/// Enable [`blocks_to_extend`] when the manifest sets `config.blockclock_catch_up`.
+pub fn configure_blockclock_catch_up(manifest: &Manifest) {
+    CATCH_UP.store(manifest.config.blockclock_catch_up.unwrap_or(false), Ordering::Relaxed);
+}
+
+/// Write every encoding extended while indexing to disk, called on shutdown.
+pub fn flush_block_clocks() {
+    for encoder in runlencoder::loaded_encoders() {
+        let mut encoder = encoder.write().unwrap_or_else(|e| e.into_inner());
+        match encoder.flush_if_due(Duration::ZERO) {
+            Ok(true) => info!("Flushed blockclock to {}", encoder.file_path().display()),
+            Ok(false) => {}
+            Err(e) => error!("Failed to flush blockclock: {:?}", e),
+        }
+    }
+}
+
+/// Where encodings built with `rindexer blockclock` and extended while indexing are written.
+pub fn blockclock_home_filepath(network: u64) -> Option<PathBuf> {
+    let home_dir = env::var("HOME").ok()?;
+    let mut path = PathBuf::from(home_dir);
+    path.push(".rindexer");
+    path.push("resources");
+    path.push("blockclock");
+    path.push(format!("{}.blockclock", network));
+
+    Some(path)
+}
+
+/// The `.blockclock` file used for a network, the home directory one takes precedence over the
+/// one bundled with rindexer as it is kept up to date.
+pub fn get_blockclock_filepath(network: u64) -> Option<PathBuf> {
     let filename = &format!("{}.blockclock", network);
     let mut paths = vec![];
 
+    // Built or extended by rindexer itself
+    if let Some(path) = blockclock_home_filepath(network) {
+        paths.push(path);
+    }
+
     // Assume `resources` directory is in the same directory as the executable (installed)
     if let Ok(executable_path) = env::current_exe() {
         let mut path = executable_path.to_path_buf();
@@ -188,16 +315,6 @@ fn get_blockclock_filepath(network: u64) -> Option<PathBuf> {
         paths.push(path);
     }
 
-    // Check additional common paths
-    if let Ok(home_dir) = env::var("HOME") {
-        let mut path = PathBuf::from(home_dir);
-        path.push(".rindexer");
-        path.push("resources");
-        path.push("blockclock");
-        path.push(filename);
-        paths.push(path);
-    }
-
     // Return the first valid path
     for path in &paths {
         if path.exists() {
@@ -285,6 +402,37 @@ mod tests {
         assert!(clock.runlencoder.is_none());
     }
 
+    #[test]
+    fn blocks_to_extend_fills_gap_after_loaded_encoding() {
+        let path = tempfile::tempdir().unwrap().keep().join("999997.blockclock");
+        let mut encoder = DeltaEncoder::new(999_997, None, &path);
+        encoder.extend_with(vec![(1, 12), (2, 24)], 0, true).unwrap();
+        encoder.serialize_to_file().unwrap();
+
+        // Nothing is loaded for the network yet.
+        CATCH_UP.store(true, Ordering::Relaxed);
+        assert!(blocks_to_extend(999_997, &[10_000]).is_empty());
+
+        DeltaEncoder::from_file(999_997, None, &path).unwrap();
+
+        // Catching up is opt in.
+        CATCH_UP.store(false, Ordering::Relaxed);
+        assert!(blocks_to_extend(999_997, &[10_000]).is_empty());
+        CATCH_UP.store(true, Ordering::Relaxed);
+
+        // Within the reorg safe distance (64) of the indexed blocks.
+        assert!(blocks_to_extend(999_997, &[10]).is_empty());
+
+        let blocks = blocks_to_extend(999_997, &[100, 5]);
+        assert_eq!(blocks.first(), Some(&3));
+        assert_eq!(blocks.last(), Some(&36));
+        assert!(!blocks.contains(&5));
+
+        let blocks = blocks_to_extend(999_997, &[10_000]);
+        assert_eq!(blocks.len() as u64, BLOCKCLOCK_EXTEND_BLOCKS_PER_BATCH);
+        assert_eq!(blocks.first(), Some(&3));
+    }
+
     #[tokio::test]
     async fn mixed_logs_some_with_timestamps_some_without() {
         use alloy::network::{AnyHeader, AnyRpcBlock, AnyRpcHeader};
@@ -41,16 +41,26 @@ use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
 use std::io::{BufReader, BufWriter};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 use std::time::Duration;
 use tokio::select;
 use tokio::time::{sleep, Instant};
 use zstd::{Decoder, Encoder};
 
-static BLOCKCLOCK_CACHE: Lazy<RwLock<HashMap<u32, Arc<DeltaEncoder>>>> =
+static BLOCKCLOCK_CACHE: Lazy<RwLock<HashMap<u32, Arc<RwLock<DeltaEncoder>>>>> =
     Lazy::new(|| RwLock::new(HashMap::new()));
 
+/// The encoder loaded for a network with [`DeltaEncoder::from_file`], if any.
+pub(crate) fn loaded_encoder(network: u32) -> Option<Arc<RwLock<DeltaEncoder>>> {
+    BLOCKCLOCK_CACHE.read().unwrap_or_else(|e| e.into_inner()).get(&network).cloned()
+}
+
+/// Every encoder loaded with [`DeltaEncoder::from_file`].
+pub(crate) fn loaded_encoders() -> Vec<Arc<RwLock<DeltaEncoder>>> {
+    BLOCKCLOCK_CACHE.read().unwrap_or_else(|e| e.into_inner()).values().cloned().collect()
+}
+
 /// The maximum size of an RPC call for the application
 const RPC_CHUNK_SIZE: usize = 1000;
 
@@ -59,6 +69,15 @@ const RPC_CHUNK_SIZE: usize = 1000;
 /// The smaller the number, the faster the lookups at the cost of some "memory".
 const MAGIC_INDEX_INTERVAL: u64 = 100_000;
 
+/// The maximum number of out-of-order blocks held while waiting for the blocks before them.
+const MAX_PENDING_BLOCKS: usize = 100_000;
+
+/// Consecutive failed batches after which `poll_encode_loop` gives up.
+const POLL_MAX_ATTEMPTS: u32 = 5;
+
+/// Backoff after the first failed batch, doubled on every further failure.
+const POLL_RETRY_BACKOFF: Duration = Duration::from_secs(2);
+
 /// A timestamp delta run length.
 #[derive(Debug, Deserialize, Serialize, Encode, Decode)]
 pub struct DeltaRunLen {
@@ -117,6 +136,13 @@ pub struct DeltaEncoder {
     ///
     /// `block_number → (run_index, cumulative_ts)`
     index: BTreeMap<u64, (usize, u64)>,
+    /// Timestamps past `max_block` recorded with [`DeltaEncoder::extend_with`] that are waiting
+    /// for the blocks before them.
+    pending: BTreeMap<u64, u64>,
+    /// The `max_block` last written to `file_path`.
+    flushed_max_block: u64,
+    /// When the encoder was last written to `file_path`.
+    last_flush: Instant,
 }
 
 impl DeltaEncoder {
@@ -132,6 +158,9 @@ impl DeltaEncoder {
             encoded_deltas: deltas,
             file_path: file_path.into(),
             index: BTreeMap::new(),
+            pending: BTreeMap::new(),
+            flushed_max_block: 0,
+            last_flush: Instant::now(),
         }
     }
 
@@ -140,7 +169,7 @@ impl DeltaEncoder {
         network: u32,
         rpc_url: Option<&str>,
         file_path: &PathBuf,
-    ) -> anyhow::Result<Arc<Self>> {
+    ) -> anyhow::Result<Arc<RwLock<Self>>> {
         {
             let cache = BLOCKCLOCK_CACHE.read().unwrap();
             if let Some(clock) = cache.get(&network) {
@@ -149,7 +178,7 @@ impl DeltaEncoder {
         }
 
         let encoder = DeltaEncoder::from_file_inner(network, rpc_url, file_path)?;
-        let encoder = Arc::new(encoder);
+        let encoder = Arc::new(RwLock::new(encoder));
 
         let mut cache = BLOCKCLOCK_CACHE.write().unwrap();
         Ok(cache.entry(network).or_insert_with(|| encoder.clone()).clone())
@@ -172,12 +201,16 @@ impl DeltaEncoder {
             return Ok(Self::new(network, rpc_url, file_path));
         };
 
+        let flushed_max_block = encoded_deltas.max_block;
         let mut encoder = Self {
             network,
             rpc: rpc_url.map(|url| RpcClient::new_http(url.parse().expect("RPC URL is invalid"))),
             encoded_deltas,
             file_path: file_path.clone(),
             index: BTreeMap::new(),
+            pending: BTreeMap::new(),
+            flushed_max_block,
+            last_flush: Instant::now(),
         };
 
         encoder.build_index(MAGIC_INDEX_INTERVAL);
@@ -202,8 +235,27 @@ impl DeltaEncoder {
         }
     }
 
+    /// The file the encoder is persisted to.
+    pub fn file_path(&self) -> &Path {
+        &self.file_path
+    }
+
+    /// Persist the encoder to another file, e.g. to keep a bundled file read-only.
+    pub fn set_file_path(&mut self, file_path: impl Into<PathBuf>) {
+        self.file_path = file_path.into();
+    }
+
+    /// The maximum block number that has been encoded.
+    pub fn max_block(&self) -> u64 {
+        self.encoded_deltas.max_block
+    }
+
     /// Write the contents of the in-memory datastructure to disk in [`zstd`] encoded binary format.
-    fn serialize_to_file(&self) -> anyhow::Result<()> {
+    pub fn serialize_to_file(&mut self) -> anyhow::Result<()> {
+        if let Some(parent) = self.file_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
         let bin_file = File::create(&self.file_path)?;
         let bin_writer = BufWriter::new(bin_file);
         let mut zstd_encoder = Encoder::new(bin_writer, 0)?;
@@ -224,9 +276,27 @@ impl DeltaEncoder {
             }
         }
 
+        self.flushed_max_block = self.encoded_deltas.max_block;
+        self.last_flush = Instant::now();
+
         Ok(())
     }
 
+    /// Write to disk when blocks were encoded since the last write and `interval` has passed.
+    ///
+    /// Returns whether the file was written.
+    pub fn flush_if_due(&mut self, interval: Duration) -> anyhow::Result<bool> {
+        if self.encoded_deltas.max_block == self.flushed_max_block
+            || self.last_flush.elapsed() < interval
+        {
+            return Ok(false);
+        }
+
+        self.serialize_to_file()?;
+
+        Ok(true)
+    }
+
     /// Fetch a set of blocks and their timestamps from RPC calls.
     async fn get_block_by_number_batch(
         &self,
@@ -369,6 +439,60 @@ impl DeltaEncoder {
         Ok(&self.encoded_deltas)
     }
 
+    /// Encode block timestamps fetched elsewhere, e.g. while indexing.
+    ///
+    /// Blocks can arrive in any order and with gaps, blocks past `max_block` are held until every
+    /// block before them is known. Only blocks at least `safe_distance` behind the highest block
+    /// seen are encoded so timestamps of reorged blocks never reach the file. Without `hold_gaps`
+    /// nothing is going to fill a gap, so only the blocks directly following `max_block` are held.
+    ///
+    /// Returns the number of newly encoded blocks.
+    pub fn extend_with(
+        &mut self,
+        blocks: impl IntoIterator<Item = (u64, u64)>,
+        safe_distance: u64,
+        hold_gaps: bool,
+    ) -> anyhow::Result<usize> {
+        // Without a genesis timestamp the first delta can not be computed.
+        if self.encoded_deltas.genesis_timestamp.is_none() {
+            return Ok(0);
+        }
+
+        let next_block = self.encoded_deltas.max_block + 1;
+        self.pending.extend(blocks.into_iter().filter(|(block, _)| *block >= next_block));
+
+        let Some(highest_block) = self.pending.keys().next_back().copied() else {
+            return Ok(0);
+        };
+        let safe_block = highest_block.saturating_sub(safe_distance);
+
+        let mut contiguous = Vec::new();
+        let mut expected_block = next_block;
+        while expected_block <= safe_block {
+            let Some(timestamp) = self.pending.remove(&expected_block) else { break };
+            contiguous.push((expected_block, timestamp));
+            expected_block += 1;
+        }
+
+        if !hold_gaps {
+            let mut run_end = expected_block;
+            while self.pending.contains_key(&run_end) {
+                run_end += 1;
+            }
+            self.pending.split_off(&run_end);
+        }
+
+        // Keep the blocks closest to being encoded.
+        while self.pending.len() > MAX_PENDING_BLOCKS {
+            self.pending.pop_last();
+        }
+
+        let encoded = contiguous.len();
+        self.encode_deltas(contiguous)?;
+
+        Ok(encoded)
+    }
+
     /// Some RPC do not return timestamp in block `0` so we are forced to provide genesis time
     /// manually instead.
     pub fn genesis_time(&self) -> Option<u64> {
@@ -440,10 +564,15 @@ impl DeltaEncoder {
     }
 
     /// Continuously poll for new blocks, encode deltas, and persist to disk.
+    ///
+    /// A failed batch is retried with backoff, after [`POLL_MAX_ATTEMPTS`] failures in a row the
+    /// progress so far is flushed and the error returned.
     pub async fn poll_encode_loop(&mut self, batch_size: u64) -> anyhow::Result<()> {
         let max_block_for_network = self.get_head_block().await?;
         let flush_duration_secs = 180;
         let mut flush_interval = Instant::now();
+        let mut failed_attempts = 0;
+        let mut retry_delay = Duration::ZERO;
 
         tracing::info!(
             "Beginning poll-based block-timestamp encoding. Max block {max_block_for_network}."
@@ -469,7 +598,10 @@ impl DeltaEncoder {
                     break;
                 }
 
-                result = self.fetch_encode_persist(batch_size) => {
+                result = async {
+                    sleep(retry_delay).await;
+                    self.fetch_encode_persist(batch_size).await
+                } => {
                     if flush_interval.elapsed().as_secs() >= flush_duration_secs {
                         match self.serialize_to_file() {
                             Ok(_) => tracing::info!("✅ Checkpoint reached. Flushed file to disk."),
@@ -478,8 +610,30 @@ impl DeltaEncoder {
                         flush_interval = Instant::now();
                     };
 
-                    if let Err(e) = result {
-                        tracing::error!("Error fetching and encoding blocks: {:?}", e)
+                    match result {
+                        Ok(()) => {
+                            failed_attempts = 0;
+                            retry_delay = Duration::ZERO;
+                        }
+                        Err(e) => {
+                            failed_attempts += 1;
+                            if failed_attempts >= POLL_MAX_ATTEMPTS {
+                                if let Err(e) = self.serialize_to_file() {
+                                    tracing::error!("Error flushing to disk: {:?}", e);
+                                }
+                                return Err(e.context(format!(
+                                    "Giving up after {failed_attempts} failed attempts at block {}",
+                                    self.encoded_deltas.max_block + 1
+                                )));
+                            }
+
+                            retry_delay = POLL_RETRY_BACKOFF * 2u32.pow(failed_attempts - 1);
+                            tracing::warn!(
+                                "Error fetching and encoding blocks (attempt {failed_attempts}/{POLL_MAX_ATTEMPTS}), retrying in {:?}: {:?}",
+                                retry_delay,
+                                e
+                            );
+                        }
                     }
                 }
             }
@@ -624,6 +778,9 @@ mod tests {
             rpc: None,
             encoded_deltas: EncodedDeltas::new(1),
             file_path: tempdir().unwrap().keep(),
+            pending: BTreeMap::new(),
+            flushed_max_block: 0,
+            last_flush: Instant::now(),
         };
 
         let blocks = vec![(100, 1000), (101, 1012), (102, 1024), (103, 1036), (104, 1051)];
@@ -655,6 +812,9 @@ mod tests {
                 max_block_timestamp: None,
                 runs: vec![],
             },
+            pending: BTreeMap::new(),
+            flushed_max_block: 0,
+            last_flush: Instant::now(),
         };
         let blocks = vec![(1, 10), (2, 20), (3, 30), (4, 31), (5, 32)];
 
@@ -662,6 +822,7 @@ mod tests {
         encoder.serialize_to_file().unwrap();
 
         let reloaded = DeltaEncoder::from_file(99, None, &file_path).unwrap();
+        let reloaded = reloaded.read().unwrap();
 
         assert_eq!(reloaded.encoded_deltas.network, 99);
         assert_eq!(reloaded.encoded_deltas.max_block, 5);
@@ -681,6 +842,50 @@ mod tests {
         assert_eq!(reloaded.get_block_timestamp(&6), None);
     }
 
+    #[test]
+    fn test_extend_with_drops_blocks_after_a_gap_unless_held() {
+        let file_path = tempdir().unwrap().keep().join("block-deltas");
+        let mut encoder = DeltaEncoder::new(7, None, &file_path);
+        encoder.encoded_deltas.genesis_timestamp = Some(0);
+
+        // Blocks 1 and 2 follow on from the encoding and wait for the safe distance, 5 is dropped.
+        assert_eq!(encoder.extend_with(vec![(1, 10), (2, 20), (5, 50)], 10, false).unwrap(), 0);
+        assert_eq!(encoder.pending.keys().copied().collect::<Vec<_>>(), vec![1, 2]);
+
+        assert_eq!(encoder.extend_with(vec![(3, 30), (4, 40)], 0, false).unwrap(), 4);
+        assert_eq!(encoder.max_block(), 4);
+        assert!(encoder.pending.is_empty());
+    }
+
+    #[test]
+    fn test_extend_with_encodes_contiguous_safe_blocks() {
+        let file_path = tempdir().unwrap().keep().join("block-deltas");
+        let mut encoder = DeltaEncoder::new(7, None, &file_path);
+        encoder.encoded_deltas.genesis_timestamp = Some(0);
+
+        // Block 2 is missing so block 3 waits for it.
+        assert_eq!(encoder.extend_with(vec![(3, 30), (1, 10)], 0, true).unwrap(), 1);
+        assert_eq!(encoder.max_block(), 1);
+
+        // Blocks within the safe distance of the highest block are held back.
+        assert_eq!(encoder.extend_with(vec![(2, 20), (4, 40), (5, 50)], 2, true).unwrap(), 2);
+        assert_eq!(encoder.max_block(), 3);
+        assert_eq!(encoder.get_block_timestamp(&3), Some(30));
+        assert_eq!(encoder.get_block_timestamp(&4), None);
+
+        // Already encoded blocks are ignored, held blocks are encoded once they are safe.
+        assert_eq!(encoder.extend_with(vec![(1, 999)], 0, true).unwrap(), 2);
+        assert_eq!(encoder.get_block_timestamp(&1), Some(10));
+        assert_eq!(encoder.get_block_timestamp(&5), Some(50));
+
+        assert!(encoder.flush_if_due(Duration::ZERO).unwrap());
+        assert!(!encoder.flush_if_due(Duration::ZERO).unwrap());
+
+        let reloaded = DeltaEncoder::from_file_inner(7, None, &file_path).unwrap();
+        assert_eq!(reloaded.max_block(), 5);
+        assert_eq!(reloaded.get_block_timestamp(&4), Some(40));
+    }
+
     /// E2E Test
     ///
     /// This test should ideally be moved to an e2e test and run less frequently
@@ -744,7 +949,7 @@ mod tests {
             return Ok(());
         }
 
-        let reloaded = DeltaEncoder::from_file(network_id, Some(rpc_url), &base_path)?;
+        let reloaded = DeltaEncoder::from_file_inner(network_id, Some(rpc_url), &base_path)?;
         let mut samples = Vec::with_capacity(sample_count);
 
         for _ in 1..=sample_count {
@@ -18,19 +18,51 @@ use crate::{
         pagerduty::{PagerDutyBot, PagerDutyError},
         slack::{SlackBot, SlackError},
         telegram::{TelegramBot, TelegramError},
-        template::Template,
+        template::{Template, TemplateTokens},
         twilio::{TwilioBot, TwilioError},
     },
-    event::{filter_by_expression, filter_event_data_by_conditions, EventMessage},
+    event::{
+        filter_by_expression, filter_event_data_by_conditions, find_tokens, lookup_literal,
+        substitute_tokens, EventMessage, TokenRef, TOKEN_PREFIX,
+    },
+    indexer::token_metadata::TokenMetadataMap,
     manifest::chat::{
         ChatConfig, DiscordConfig, DiscordEvent, OpsGenieConfig, OpsGenieEvent, PagerDutyConfig,
-        PagerDutyEvent, SlackConfig, SlackEvent, TelegramConfig, TelegramEvent, TwilioConfig,
-        TwilioEvent,
+        PagerDutyEvent, ReorgAlertConfig, SlackConfig, SlackEvent, TelegramConfig, TelegramEvent,
+        TwilioConfig, TwilioEvent,
     },
 };
 
 type SendMessage = Vec<JoinHandle<Result<(), ChatError>>>;
 
+/// Evaluates the filter expression of a message, replacing `$token(...)` references first.
+/// References to tokens without metadata never match.
+fn evaluate_filter_expression(
+    expression: &str,
+    event_data: &Value,
+    tokens: TemplateTokens<'_>,
+) -> bool {
+    let resolved: String;
+    let expression = if expression.contains(TOKEN_PREFIX) {
+        match substitute_tokens(expression, |token| {
+            tokens.resolve(token, event_data).map(|value| lookup_literal(&value))
+        }) {
+            Some(expression) => {
+                resolved = expression;
+                resolved.as_str()
+            }
+            None => return false,
+        }
+    } else {
+        expression
+    };
+
+    filter_by_expression(expression, event_data).unwrap_or_else(|e| {
+        tracing::error!("Error evaluating filter expression: {}", e);
+        false
+    })
+}
+
 #[derive(Error, Debug)]
 pub enum ChatError {
     #[error("Telegram error: {0}")]
@@ -91,6 +123,114 @@ struct OpsGenieInstance {
     client: Arc<OpsGenieBot>,
 }
 
+/// A configured channel messages are sent to.
+trait ChatInstance {
+    fn networks(&self) -> &[String];
+
+    fn send_task(&self, message: String) -> JoinHandle<Result<(), ChatError>>;
+}
+
+impl ChatInstance for TelegramInstance {
+    fn networks(&self) -> &[String] {
+        &self.config.networks
+    }
+
+    fn send_task(&self, message: String) -> JoinHandle<Result<(), ChatError>> {
+        let client = Arc::clone(&self.client);
+        let chat_id = ChatId(self.config.chat_id);
+        task::spawn(async move {
+            client.send_message(chat_id, &message).await?;
+            Ok(())
+        })
+    }
+}
+
+impl ChatInstance for DiscordInstance {
+    fn networks(&self) -> &[String] {
+        &self.config.networks
+    }
+
+    fn send_task(&self, message: String) -> JoinHandle<Result<(), ChatError>> {
+        let client = Arc::clone(&self.client);
+        let channel_id = ChannelId::new(self.config.channel_id);
+        task::spawn(async move {
+            client.send_message(channel_id, &message).await?;
+            Ok(())
+        })
+    }
+}
+
+impl ChatInstance for SlackInstance {
+    fn networks(&self) -> &[String] {
+        &self.config.networks
+    }
+
+    fn send_task(&self, message: String) -> JoinHandle<Result<(), ChatError>> {
+        let client = Arc::clone(&self.client);
+        let channel = self.config.channel.clone();
+        task::spawn(async move {
+            client.send_message(&channel, &message).await?;
+            Ok(())
+        })
+    }
+}
+
+impl ChatInstance for TwilioInstance {
+    fn networks(&self) -> &[String] {
+        &self.config.networks
+    }
+
+    fn send_task(&self, message: String) -> JoinHandle<Result<(), ChatError>> {
+        let client = Arc::clone(&self.client);
+        let to_number = self.config.to_number.clone();
+        task::spawn(async move {
+            client.send_message(&to_number, &message).await?;
+            Ok(())
+        })
+    }
+}
+
+impl ChatInstance for PagerDutyInstance {
+    fn networks(&self) -> &[String] {
+        &self.config.networks
+    }
+
+    fn send_task(&self, message: String) -> JoinHandle<Result<(), ChatError>> {
+        let client = Arc::clone(&self.client);
+        task::spawn(async move {
+            client.send_message(&message).await?;
+            Ok(())
+        })
+    }
+}
+
+impl ChatInstance for OpsGenieInstance {
+    fn networks(&self) -> &[String] {
+        &self.config.networks
+    }
+
+    fn send_task(&self, message: String) -> JoinHandle<Result<(), ChatError>> {
+        let client = Arc::clone(&self.client);
+        task::spawn(async move {
+            client.send_message(&message).await?;
+            Ok(())
+        })
+    }
+}
+
+/// Waits for the send tasks, returning the number of messages sent or the first error.
+async fn join_send_tasks(tasks: SendMessage) -> Result<usize, ChatError> {
+    let mut messages_sent = 0;
+    for result in join_all(tasks).await {
+        match result {
+            Ok(Ok(_)) => messages_sent += 1,
+            Ok(Err(e)) => return Err(e),
+            Err(e) => return Err(ChatError::JoinError(e)),
+        }
+    }
+    Ok(messages_sent)
+}
+
 pub struct ChatClients {
     telegram: Option<Vec<TelegramInstance>>,
     discord: Option<Vec<DiscordInstance>>,
@@ -98,6 +238,7 @@ pub struct ChatClients {
     twilio: Option<Vec<TwilioInstance>>,
     pagerduty: Option<Vec<PagerDutyInstance>>,
     opsgenie: Option<Vec<OpsGenieInstance>>,
+    reorg: Option<ReorgAlertConfig>,
 }
 
 impl ChatClients {
@@ -170,7 +311,11 @@ impl ChatClients {
                 .collect()
         });
 
-        Self { telegram, discord, slack, twilio, pagerduty, opsgenie }
+        Self { telegram, discord, slack, twilio, pagerduty, opsgenie, reorg: chat_config.reorg }
+    }
+
+    pub fn has_reorg_alert(&self) -> bool {
+        self.reorg.is_some()
     }
 
     fn find_accepted_block_range(&self, from_block: &U64, to_block: &U64) -> U64 {
@@ -198,11 +343,28 @@ impl ChatClients {
             || self.opsgenie.is_some()
     }
 
+    /// Every configured channel, whatever its platform.
+    fn instances(&self) -> impl Iterator<Item = &dyn ChatInstance> {
+        fn each<T: ChatInstance>(
+            instances: &Option<Vec<T>>,
+        ) -> impl Iterator<Item = &dyn ChatInstance> {
+            instances.iter().flatten().map(|instance| instance as &dyn ChatInstance)
+        }
+
+        each(&self.telegram)
+            .chain(each(&self.discord))
+            .chain(each(&self.slack))
+            .chain(each(&self.twilio))
+            .chain(each(&self.pagerduty))
+            .chain(each(&self.opsgenie))
+    }
+
     fn telegram_send_message_tasks(
         &self,
         instance: &TelegramInstance,
         event_for: &TelegramEvent,
         events_data: &[Value],
+        tokens: TemplateTokens<'_>,
     ) -> SendMessage {
         let tasks: Vec<_> = events_data
             .iter()
@@ -211,12 +373,7 @@ impl ChatClients {
                 // If both are present, the filter expression will be used
                 // If neither is present, all events will be sent
                 if let Some(expression) = &event_for.filter_expression {
-                    let result = filter_by_expression(expression, event_data);
-
-                    return result.unwrap_or_else(|e| {
-                        tracing::error!("Error evaluating filter expression: {}", e);
-                        false
-                    });
+                    return evaluate_filter_expression(expression, event_data, tokens);
                 }
                 if let Some(conditions) = &event_for.conditions {
                     return filter_event_data_by_conditions(event_data, conditions);
@@ -224,14 +381,10 @@ impl ChatClients {
                 true
             })
             .map(|event_data| {
-                let client = Arc::clone(&instance.client);
-                let chat_id = ChatId(instance.config.chat_id);
-                let message = Template::new(event_for.template_inline.clone())
-                    .parse_template_inline(event_data);
-                task::spawn(async move {
-                    client.send_message(chat_id, &message).await?;
-                    Ok(())
-                })
+                instance.send_task(
+                    Template::new(event_for.template_inline.clone())
+                        .parse_template_inline(event_data, tokens),
+                )
             })
             .collect();
         tasks
@@ -242,6 +395,7 @@ impl ChatClients {
         instance: &DiscordInstance,
         event_for: &DiscordEvent,
         events_data: &[Value],
+        tokens: TemplateTokens<'_>,
     ) -> SendMessage {
         let tasks: Vec<_> = events_data
             .iter()
@@ -250,12 +404,7 @@ impl ChatClients {
                 // If both are present, the filter expression will be used
                 // If neither is present, all events will be sent
                 if let Some(expression) = &event_for.filter_expression {
-                    let result = filter_by_expression(expression, event_data);
-
-                    return result.unwrap_or_else(|e| {
-                        tracing::error!("Error evaluating filter expression: {}", e);
-                        false
-                    });
+                    return evaluate_filter_expression(expression, event_data, tokens);
                 }
                 if let Some(conditions) = &event_for.conditions {
                     return filter_event_data_by_conditions(event_data, conditions);
@@ -263,14 +412,10 @@ impl ChatClients {
                 true
             })
             .map(|event_data| {
-                let client = Arc::clone(&instance.client);
-                let channel_id = ChannelId::new(instance.config.channel_id);
-                let message = Template::new(event_for.template_inline.clone())
-                    .parse_template_inline(event_data);
-                task::spawn(async move {
-                    client.send_message(channel_id, &message).await?;
-                    Ok(())
-                })
+                instance.send_task(
+                    Template::new(event_for.template_inline.clone())
+                        .parse_template_inline(event_data, tokens),
+                )
             })
             .collect();
         tasks
@@ -281,6 +426,7 @@ impl ChatClients {
         instance: &SlackInstance,
         event_for: &SlackEvent,
         events_data: &[Value],
+        tokens: TemplateTokens<'_>,
     ) -> SendMessage {
         let tasks: Vec<_> = events_data
             .iter()
@@ -289,12 +435,7 @@ impl ChatClients {
                 // If both are present, the filter expression will be used
                 // If neither is present, all events will be sent
                 if let Some(expression) = &event_for.filter_expression {
-                    let result = filter_by_expression(expression, event_data);
-
-                    return result.unwrap_or_else(|e| {
-                        tracing::error!("Error evaluating filter expression: {}", e);
-                        false
-                    });
+                    return evaluate_filter_expression(expression, event_data, tokens);
                 }
                 if let Some(conditions) = &event_for.conditions {
                     return filter_event_data_by_conditions(event_data, conditions);
@@ -302,14 +443,10 @@ impl ChatClients {
                 true
             })
             .map(|event_data| {
-                let client = Arc::clone(&instance.client);
-                let channel = instance.config.channel.clone();
-                let message = Template::new(event_for.template_inline.clone())
-                    .parse_template_inline(event_data);
-                task::spawn(async move {
-                    client.send_message(&channel, &message).await?;
-                    Ok(())
-                })
+                instance.send_task(
+                    Template::new(event_for.template_inline.clone())
+                        .parse_template_inline(event_data, tokens),
+                )
             })
             .collect();
         tasks
@@ -320,17 +457,13 @@ impl ChatClients {
         instance: &TwilioInstance,
         event_for: &TwilioEvent,
         events_data: &[Value],
+        tokens: TemplateTokens<'_>,
     ) -> SendMessage {
         let tasks: Vec<_> = events_data
             .iter()
             .filter(|event_data| {
                 if let Some(expression) = &event_for.filter_expression {
-                    let result = filter_by_expression(expression, event_data);
-
-                    return result.unwrap_or_else(|e| {
-                        tracing::error!("Error evaluating filter expression: {}", e);
-                        false
-                    });
+                    return evaluate_filter_expression(expression, event_data, tokens);
                 }
                 if let Some(conditions) = &event_for.conditions {
                     return filter_event_data_by_conditions(event_data, conditions);
@@ -338,14 +471,10 @@ impl ChatClients {
                 true
             })
             .map(|event_data| {
-                let client = Arc::clone(&instance.client);
-                let to_number = instance.config.to_number.clone();
-                let message = Template::new(event_for.template_inline.clone())
-                    .parse_template_inline(event_data);
-                task::spawn(async move {
-                    client.send_message(&to_number, &message).await?;
-                    Ok(())
-                })
+                instance.send_task(
+                    Template::new(event_for.template_inline.clone())
+                        .parse_template_inline(event_data, tokens),
+                )
             })
             .collect();
         tasks
@@ -356,17 +485,13 @@ impl ChatClients {
         instance: &PagerDutyInstance,
         event_for: &PagerDutyEvent,
         events_data: &[Value],
+        tokens: TemplateTokens<'_>,
     ) -> SendMessage {
         let tasks: Vec<_> = events_data
             .iter()
             .filter(|event_data| {
                 if let Some(expression) = &event_for.filter_expression {
-                    let result = filter_by_expression(expression, event_data);
-
-                    return result.unwrap_or_else(|e| {
-                        tracing::error!("Error evaluating filter expression: {}", e);
-                        false
-                    });
+                    return evaluate_filter_expression(expression, event_data, tokens);
                 }
                 if let Some(conditions) = &event_for.conditions {
                     return filter_event_data_by_conditions(event_data, conditions);
@@ -374,13 +499,10 @@ impl ChatClients {
                 true
             })
             .map(|event_data| {
-                let client = Arc::clone(&instance.client);
-                let message = Template::new(event_for.template_inline.clone())
-                    .parse_template_inline(event_data);
-                task::spawn(async move {
-                    client.send_message(&message).await?;
-                    Ok(())
-                })
+                instance.send_task(
+                    Template::new(event_for.template_inline.clone())
+                        .parse_template_inline(event_data, tokens),
+                )
             })
             .collect();
         tasks
@@ -391,17 +513,13 @@ impl ChatClients {
         instance: &OpsGenieInstance,
         event_for: &OpsGenieEvent,
         events_data: &[Value],
+        tokens: TemplateTokens<'_>,
     ) -> SendMessage {
         let tasks: Vec<_> = events_data
             .iter()
             .filter(|event_data| {
                 if let Some(expression) = &event_for.filter_expression {
-                    let result = filter_by_expression(expression, event_data);
-
-                    return result.unwrap_or_else(|e| {
-                        tracing::error!("Error evaluating filter expression: {}", e);
-                        false
-                    });
+                    return evaluate_filter_expression(expression, event_data, tokens);
                 }
                 if let Some(conditions) = &event_for.conditions {
                     return filter_event_data_by_conditions(event_data, conditions);
@@ -409,21 +527,80 @@ impl ChatClients {
                 true
             })
             .map(|event_data| {
-                let client = Arc::clone(&instance.client);
-                let message = Template::new(event_for.template_inline.clone())
-                    .parse_template_inline(event_data);
-                task::spawn(async move {
-                    client.send_message(&message).await?;
-                    Ok(())
-                })
+                instance.send_task(
+                    Template::new(event_for.template_inline.clone())
+                        .parse_template_inline(event_data, tokens),
+                )
             })
             .collect();
         tasks
     }
 
+    /// Sends the `reorg` alert to every channel configured for `network` when the reorg is at
+    /// least `min_depth` blocks deep. The template is rendered against `reorg_data`.
+    pub async fn send_reorg_alert(
+        &self,
+        network: &str,
+        depth: u64,
+        reorg_data: &Value,
+    ) -> Result<usize, ChatError> {
+        let Some(alert) = &self.reorg else {
+            return Ok(0);
+        };
+        if depth < alert.min_depth {
+            return Ok(0);
+        }
+
+        let metadata = TokenMetadataMap::new();
+        let tokens = TemplateTokens { network, metadata: &metadata };
+        let message =
+            Template::new(alert.template_inline.clone()).parse_template_inline(reorg_data, tokens);
+        let tasks: SendMessage = self
+            .instances()
+            .filter(|instance| instance.networks().iter().any(|n| n == network))
+            .map(|instance| instance.send_task(message.clone()))
+            .collect();
+
+        join_send_tasks(tasks).await
+    }
+
+    /// Returns the `$token(...)` references used by the message templates and filters of an
+    /// event, so their metadata can be read before sending.
+    pub fn token_references(&self, event_name: &str) -> Vec<TokenRef> {
+        macro_rules! message_values {
+            ($instances:expr) => {
+                $instances.iter().flatten().flat_map(|instance| {
+                    instance.config.messages.iter().filter(|m| m.event_name == event_name).flat_map(
+                        |m| m.filter_expression.iter().chain(std::iter::once(&m.template_inline)),
+                    )
+                })
+            };
+        }
+
+        let values = message_values!(self.telegram)
+            .chain(message_values!(self.discord))
+            .chain(message_values!(self.slack))
+            .chain(message_values!(self.twilio))
+            .chain(message_values!(self.pagerduty))
+            .chain(message_values!(self.opsgenie));
+
+        let mut references: Vec<TokenRef> = Vec::new();
+        for value in values {
+            for (_, _, token) in find_tokens(value) {
+                if !references.contains(&token) {
+                    references.push(token);
+                }
+            }
+        }
+        references
+    }
+
+    /// Sends the messages of an event. `token_metadata` holds the metadata of the tokens
+    /// referenced by the messages, see [`ChatClients::token_references`].
     pub async fn send_message(
         &self,
         event_message: &EventMessage,
+        token_metadata: &TokenMetadataMap,
         index_event_in_order: bool,
         from_block: &U64,
         to_block: &U64,
@@ -432,6 +609,8 @@ impl ChatClients {
             return Ok(0);
         }
 
+        let tokens = TemplateTokens { network: &event_message.network, metadata: token_metadata };
+
         // will always have something even if the event has no parameters due to the tx_information
         if let Value::Array(data_array) = &event_message.event_data {
             let mut messages: Vec<SendMessage> = Vec::new();
@@ -450,6 +629,7 @@ impl ChatClients {
                                 instance,
                                 telegram_event,
                                 data_array,
+                                tokens,
                             );
                             messages.push(message);
                         }
@@ -471,6 +651,7 @@ impl ChatClients {
                                 instance,
                                 discord_event,
                                 data_array,
+                                tokens,
                             );
                             messages.push(message);
                         }
@@ -488,8 +669,12 @@ impl ChatClients {
                             .find(|e| e.event_name == event_message.event_name);
 
                         if let Some(slack_event) = slack_event {
-                            let message =
-                                self.slack_send_message_tasks(instance, slack_event, data_array);
+                            let message = self.slack_send_message_tasks(
+                                instance,
+                                slack_event,
+                                data_array,
+                                tokens,
+                            );
                             messages.push(message);
                         }
                     }
@@ -506,8 +691,12 @@ impl ChatClients {
                             .find(|e| e.event_name == event_message.event_name);
 
                         if let Some(twilio_event) = twilio_event {
-                            let message =
-                                self.twilio_send_message_tasks(instance, twilio_event, data_array);
+                            let message = self.twilio_send_message_tasks(
+                                instance,
+                                twilio_event,
+                                data_array,
+                                tokens,
+                            );
                             messages.push(message);
                         }
                     }
@@ -528,6 +717,7 @@ impl ChatClients {
                                 instance,
                                 pagerduty_event,
                                 data_array,
+                                tokens,
                             );
                             messages.push(message);
                         }
@@ -549,6 +739,7 @@ impl ChatClients {
                                 instance,
                                 opsgenie_event,
                                 data_array,
+                                tokens,
                             );
                             messages.push(message);
                         }
@@ -569,15 +760,7 @@ impl ChatClients {
                     }
                 }
             } else {
-                let tasks: Vec<_> = messages.into_iter().flatten().collect();
-                let results = join_all(tasks).await;
-                for result in results {
-                    match result {
-                        Ok(Ok(_)) => messages_sent += 1,
-                        Ok(Err(e)) => return Err(e),
-                        Err(e) => return Err(ChatError::JoinError(e)),
-                    }
-                }
+                messages_sent = join_send_tasks(messages.into_iter().flatten().collect()).await?;
             }
 
             Ok(messages_sent)
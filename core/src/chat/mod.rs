@@ -7,4 +7,5 @@ mod pagerduty;
 mod slack;
 mod telegram;
 mod template;
+pub use template::TemplateTokens;
 mod twilio;
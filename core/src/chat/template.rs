@@ -1,9 +1,41 @@
 use std::str::FromStr;
 
-use alloy::primitives::U64;
+use alloy::primitives::{Address, U64};
 use regex::Regex;
 use serde_json::Value;
 
+use crate::event::{parse_token, substitute_tokens, TokenRef, TOKEN_PREFIX};
+use crate::indexer::token_metadata::TokenMetadataMap;
+
+/// Token metadata available to the `$token(address).field` references of a message.
+#[derive(Debug, Clone, Copy)]
+pub struct TemplateTokens<'a> {
+    pub network: &'a str,
+    pub metadata: &'a TokenMetadataMap,
+}
+
+impl TemplateTokens<'_> {
+    /// Resolves the address of a token reference, either a literal address or the path of an
+    /// event field such as `token` or `transaction_information.address`.
+    pub fn address(token: &TokenRef, event_data: &Value) -> Option<Address> {
+        let path = token.address.strip_prefix('$').unwrap_or(&token.address);
+        if let Ok(address) = path.parse() {
+            return Some(address);
+        }
+        let mut current = event_data;
+        for key in path.split('.') {
+            current = current.get(key)?;
+        }
+        current.as_str()?.parse().ok()
+    }
+
+    /// Resolves a token reference for one event.
+    pub fn resolve(&self, token: &TokenRef, event_data: &Value) -> Option<String> {
+        let address = Self::address(token, event_data)?;
+        self.metadata.get(&(self.network.to_string(), address))?.get(token.field)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Template {
     value: String,
@@ -14,12 +46,26 @@ impl Template {
         Self { value }
     }
 
-    pub fn parse_template_inline(&self, event_data: &Value) -> String {
+    pub fn parse_template_inline(&self, event_data: &Value, tokens: TemplateTokens<'_>) -> String {
         let mut template = self.value.clone();
         let placeholders = self.extract_placeholders(&template);
 
         for placeholder in placeholders {
-            if placeholder.contains('(') {
+            // Token metadata is substituted first so it can be used as a function argument
+            if placeholder.contains(TOKEN_PREFIX) {
+                let resolved = match parse_token(&placeholder) {
+                    Some(token) => tokens.resolve(&token, event_data),
+                    None => {
+                        substitute_tokens(&placeholder, |token| tokens.resolve(token, event_data))
+                            .and_then(|function_call| {
+                                self.evaluate_function(&function_call, event_data)
+                            })
+                    }
+                };
+                if let Some(value) = resolved {
+                    template = template.replace(&format!("{{{{{placeholder}}}}}"), &value);
+                }
+            } else if placeholder.contains('(') {
                 if let Some(value) = self.evaluate_function(&placeholder, event_data) {
                     template = template.replace(&format!("{{{{{placeholder}}}}}"), &value);
                 }
@@ -90,3 +136,40 @@ impl Template {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::indexer::token_metadata::TokenMetadata;
+
+    #[test]
+    fn test_parse_template_with_tokens() {
+        let token = Address::with_last_byte(1);
+        let metadata = TokenMetadataMap::from([(
+            ("ethereum".to_string(), token),
+            TokenMetadata { decimals: Some(6), symbol: Some("USDC".to_string()), name: None },
+        )]);
+        let tokens = TemplateTokens { network: "ethereum", metadata: &metadata };
+        let event_data = json!({
+            "value": "2500000",
+            "transaction_information": { "address": format!("{:?}", token) }
+        });
+
+        let template = Template::new(
+            "{{format_value(value, $token(transaction_information.address).decimals)}} \
+             {{$token(transaction_information.address).symbol}}"
+                .to_string(),
+        );
+        assert_eq!(template.parse_template_inline(&event_data, tokens), "2.500000 USDC");
+
+        // References without metadata are left as is
+        let template =
+            Template::new("{{$token(transaction_information.address).name}}".to_string());
+        assert_eq!(
+            template.parse_template_inline(&event_data, tokens),
+            "{{$token(transaction_information.address).name}}"
+        );
+    }
+}
@@ -120,6 +120,17 @@ pub enum BatchOperationAction {
     Max,
     /// Keep the minimum (lower) value
     Min,
+    /// Keep the existing value, only new rows take the value with the lowest sequence
+    First,
+    /// Fold the new value into a running average, weighted by the samples column
+    /// named by `injected_columns::avg_samples`
+    Avg,
+    /// Append the new elements to the existing array
+    Append,
+    /// Remove every occurrence of the new elements from the existing array
+    Remove,
+    /// Append the new elements the existing array doesn't contain yet
+    SetUnion,
 }
 
 /// Definition of a column for batch operations.
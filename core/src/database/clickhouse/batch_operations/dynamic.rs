@@ -5,7 +5,7 @@ use crate::database::batch_operations::{
 };
 use crate::database::clickhouse::client::ClickhouseClient;
 
-use super::query_builder::{format_table_name, quote_identifier};
+use super::query_builder::{build_array_merge_insert, format_table_name, quote_identifier};
 
 /// Executes a dynamic batch operation with runtime-defined columns.
 ///
@@ -64,7 +64,32 @@ async fn execute_batch(
         })
         .collect();
 
+    let has_array_actions = columns.iter().any(|col| {
+        matches!(
+            col.action,
+            BatchOperationAction::Append
+                | BatchOperationAction::Remove
+                | BatchOperationAction::SetUnion
+        )
+    });
+    let key_columns: &[&str] =
+        if !where_columns.is_empty() { &where_columns } else { &distinct_cols };
+
     match op_type {
+        // Array actions merge with the stored row, so they can't be a plain INSERT
+        BatchOperationType::Update | BatchOperationType::Upsert
+            if has_array_actions && !key_columns.is_empty() =>
+        {
+            let query = build_array_merge_insert(table_name, key_columns, batch);
+
+            tracing::debug!("Custom indexing ClickHouse query: {}", query);
+
+            database.execute(&query).await.map_err(|e| {
+                tracing::error!("ClickHouse error: {:?}", e);
+                tracing::error!("Failed query:\n{}", query);
+                e.to_string()
+            })?;
+        }
         BatchOperationType::Update | BatchOperationType::Upsert | BatchOperationType::Insert => {
             // In ClickHouse, Update, Upsert, and Insert all map to INSERT
             // ReplacingMergeTree automatically keeps the latest version for Update/Upsert
@@ -97,8 +122,7 @@ async fn execute_batch(
         }
         BatchOperationType::Delete => {
             // Determine which columns to use for matching
-            let match_columns: Vec<&str> =
-                if !where_columns.is_empty() { where_columns } else { distinct_cols };
+            let match_columns = key_columns;
 
             if match_columns.is_empty() {
                 return Err("Delete operation requires WHERE or DISTINCT columns".to_string());
@@ -109,7 +133,7 @@ async fn execute_batch(
             for row_columns in batch.iter() {
                 let mut and_conditions: Vec<String> = Vec::new();
 
-                for match_col in &match_columns {
+                for match_col in match_columns {
                     if let Some(col_def) = row_columns.iter().find(|c| c.name == *match_col) {
                         let quoted_col = quote_identifier(match_col);
                         let value = col_def.value.to_clickhouse_value();
@@ -7,8 +7,10 @@
 mod dynamic;
 mod macros;
 mod query_builder;
+mod rollup;
 
 pub use dynamic::execute_dynamic_batch_operation;
+pub use rollup::{column_aggregate, execute_rollup_batch_operation, RollupAggregate, RollupColumn};
 
 // Re-exported for use by the create_batch_clickhouse_operation! macro
 #[allow(unused_imports)]
@@ -1,6 +1,8 @@
 //! Shared SQL query building logic for ClickHouse batch operations.
 
-use crate::database::batch_operations::RESERVED_KEYWORDS;
+use crate::database::batch_operations::{
+    BatchOperationAction, BatchOperationColumnBehavior, DynamicColumnDefinition, RESERVED_KEYWORDS,
+};
 
 /// Quotes an identifier if it's a reserved keyword (uses backticks for ClickHouse).
 #[inline]
@@ -27,3 +29,167 @@ pub fn format_table_name(table_name: &str) -> String {
         table_name.to_string()
     }
 }
+
+/// Builds the `INSERT ... SELECT` for batches writing array actions (append/remove/set_union).
+/// ReplacingMergeTree keeps whole rows, so the batch is grouped per key and merged with the row
+/// currently stored for that key (read with `FINAL`) before the new version is inserted. Columns
+/// the operation doesn't write keep their stored value.
+pub fn build_array_merge_insert(
+    table_name: &str,
+    key_columns: &[&str],
+    batch: &[Vec<DynamicColumnDefinition>],
+) -> String {
+    let columns = &batch[0];
+    let formatted_table_name = format_table_name(table_name);
+    let sequence_col = columns.iter().find_map(|col| match col.behavior {
+        BatchOperationColumnBehavior::Sequence => Some(quote_identifier(&col.name)),
+        _ => None,
+    });
+
+    let source = batch
+        .iter()
+        .map(|row| {
+            let values = row
+                .iter()
+                .map(|col| {
+                    format!(
+                        "{} AS {}",
+                        col.value.to_clickhouse_value(),
+                        quote_identifier(&col.name)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("SELECT {}", values)
+        })
+        .collect::<Vec<_>>()
+        .join(" UNION ALL ");
+    // groupArray keeps the order rows are read in
+    let source = match &sequence_col {
+        Some(seq) => format!("SELECT * FROM ({}) ORDER BY {}", source, seq),
+        None => source,
+    };
+
+    let mut inner_exprs: Vec<String> = Vec::new();
+    let mut outer_exprs: Vec<String> = Vec::new();
+    for col in columns {
+        let name = quote_identifier(&col.name);
+
+        if key_columns.contains(&col.name.as_str()) {
+            inner_exprs.push(name.clone());
+            outer_exprs.push(format!("n.{}", name));
+            continue;
+        }
+
+        inner_exprs.push(match (col.action, &sequence_col) {
+            (
+                BatchOperationAction::Append
+                | BatchOperationAction::Remove
+                | BatchOperationAction::SetUnion,
+                _,
+            ) => format!("groupArray({name}) AS {name}"),
+            _ if matches!(col.behavior, BatchOperationColumnBehavior::Sequence) => {
+                format!("max({name}) AS {name}")
+            }
+            (_, Some(seq)) => format!("argMax({name}, {seq}) AS {name}"),
+            (_, None) => format!("any({name}) AS {name}"),
+        });
+
+        outer_exprs.push(match col.action {
+            BatchOperationAction::Append => format!("arrayConcat(cur.{name}, n.{name})"),
+            BatchOperationAction::Remove => {
+                format!("arrayFilter(elem -> NOT has(n.{name}, elem), cur.{name})")
+            }
+            BatchOperationAction::SetUnion => {
+                format!("arrayDistinct(arrayConcat(cur.{name}, n.{name}))")
+            }
+            BatchOperationAction::Nothing => {
+                format!("if(cur.rindexer_exists = 1, cur.{name}, n.{name})")
+            }
+            _ => format!("n.{}", name),
+        });
+    }
+
+    let column_names =
+        columns.iter().map(|col| quote_identifier(&col.name)).collect::<Vec<_>>().join(", ");
+    let group_keys =
+        key_columns.iter().map(|col| quote_identifier(col)).collect::<Vec<_>>().join(", ");
+    let join_conditions = key_columns
+        .iter()
+        .map(|col| {
+            let name = quote_identifier(col);
+            format!("n.{} = cur.{}", name, name)
+        })
+        .collect::<Vec<_>>()
+        .join(" AND ");
+
+    format!(
+        "INSERT INTO {table} ({columns}) SELECT {outer} FROM (SELECT {inner} FROM ({source}) \
+         GROUP BY {keys}) AS n LEFT JOIN (SELECT *, 1 AS rindexer_exists FROM {table} FINAL) \
+         AS cur ON {join}",
+        table = formatted_table_name,
+        columns = column_names,
+        outer = outer_exprs.join(", "),
+        inner = inner_exprs.join(", "),
+        source = source,
+        keys = group_keys,
+        join = join_conditions,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::batch_operations::BatchOperationSqlType;
+    use crate::EthereumSqlTypeWrapper;
+
+    fn row(holder: &str, sequence: u128) -> Vec<DynamicColumnDefinition> {
+        vec![
+            DynamicColumnDefinition::new(
+                "pool".to_string(),
+                EthereumSqlTypeWrapper::String("0xpool".to_string()),
+                BatchOperationSqlType::Text,
+                BatchOperationColumnBehavior::Distinct,
+                BatchOperationAction::Where,
+            ),
+            DynamicColumnDefinition::new(
+                "holders".to_string(),
+                EthereumSqlTypeWrapper::String(holder.to_string()),
+                BatchOperationSqlType::Text,
+                BatchOperationColumnBehavior::Normal,
+                BatchOperationAction::SetUnion,
+            ),
+            DynamicColumnDefinition::new(
+                "label".to_string(),
+                EthereumSqlTypeWrapper::Null,
+                BatchOperationSqlType::Text,
+                BatchOperationColumnBehavior::Normal,
+                BatchOperationAction::Nothing,
+            ),
+            DynamicColumnDefinition::new(
+                "rindexer_sequence_id".to_string(),
+                EthereumSqlTypeWrapper::U128(sequence),
+                BatchOperationSqlType::Numeric,
+                BatchOperationColumnBehavior::Sequence,
+                BatchOperationAction::Set,
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_build_array_merge_insert() {
+        let batch = vec![row("0xa", 2), row("0xb", 1)];
+
+        let query = build_array_merge_insert("db.pools", &["pool"], &batch);
+
+        assert!(query.starts_with(
+            "INSERT INTO `db`.`pools` (pool, holders, label, rindexer_sequence_id) SELECT n.pool"
+        ));
+        assert!(query.contains("arrayDistinct(arrayConcat(cur.holders, n.holders))"));
+        assert!(query.contains("if(cur.rindexer_exists = 1, cur.label, n.label)"));
+        assert!(query.contains("groupArray(holders) AS holders"));
+        assert!(query.contains("max(rindexer_sequence_id) AS rindexer_sequence_id"));
+        assert!(query.contains("ORDER BY rindexer_sequence_id) GROUP BY pool) AS n"));
+        assert!(query.ends_with("FROM `db`.`pools` FINAL) AS cur ON n.pool = cur.pool"));
+    }
+}
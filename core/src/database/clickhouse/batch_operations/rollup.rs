@@ -0,0 +1,257 @@
+//! Batch writes for time-bucketed rollup tables backed by `AggregatingMergeTree`.
+//!
+//! Each batch is folded into one partial aggregate row per key and block. ClickHouse merges
+//! the partial states in the background, so reads use the `-Merge` combinators
+//! (`sumMerge`, `argMaxMerge`, ...) grouped by the key columns.
+
+use crate::database::clickhouse::client::ClickhouseClient;
+use crate::manifest::contract::{injected_columns, SetAction, Table};
+use crate::EthereumSqlTypeWrapper;
+
+use super::query_builder::{format_table_name, quote_identifier};
+
+/// How a column is folded into a rollup row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RollupAggregate {
+    /// Part of the `ORDER BY` key (network, `where` columns, bucket and block number).
+    Key,
+    /// `sum` state (add/increment/count).
+    Sum,
+    /// `max` state.
+    Max,
+    /// `min` state.
+    Min,
+    /// `argMin` state ordered by sequence id (first/open).
+    First,
+    /// `argMax` state ordered by sequence id (set/last/close).
+    Last,
+    /// `avg` state.
+    Avg,
+    /// Plain `max` stored as `SimpleAggregateFunction`, used for rindexer metadata columns.
+    Latest,
+}
+
+impl RollupAggregate {
+    /// Maps a table action to its aggregate. `validate_bucket` rejects subtract/decrement,
+    /// the array actions and count_distinct.
+    pub fn from_action(action: &SetAction) -> Self {
+        match action {
+            SetAction::Add
+            | SetAction::Increment
+            | SetAction::Count
+            | SetAction::Subtract
+            | SetAction::Decrement => RollupAggregate::Sum,
+            SetAction::Max => RollupAggregate::Max,
+            SetAction::Min => RollupAggregate::Min,
+            SetAction::First | SetAction::Open => RollupAggregate::First,
+            SetAction::Set | SetAction::Last | SetAction::Close => RollupAggregate::Last,
+            SetAction::Avg => RollupAggregate::Avg,
+            SetAction::Append
+            | SetAction::Remove
+            | SetAction::SetUnion
+            | SetAction::CountDistinct => RollupAggregate::Last,
+        }
+    }
+
+    /// ClickHouse column type storing values of `base_type` with this aggregate.
+    pub fn column_type(&self, base_type: &str) -> String {
+        match self {
+            RollupAggregate::Key => base_type.to_string(),
+            RollupAggregate::Sum => format!("AggregateFunction(sum, {})", base_type),
+            RollupAggregate::Max => format!("AggregateFunction(max, {})", base_type),
+            RollupAggregate::Min => format!("AggregateFunction(min, {})", base_type),
+            RollupAggregate::First => format!("AggregateFunction(argMin, {}, UInt128)", base_type),
+            RollupAggregate::Last => format!("AggregateFunction(argMax, {}, UInt128)", base_type),
+            RollupAggregate::Avg => format!("AggregateFunction(avg, {})", base_type),
+            RollupAggregate::Latest => format!("SimpleAggregateFunction(max, {})", base_type),
+        }
+    }
+
+    fn select_expr(&self, column: &str, sequence_column: &str) -> String {
+        match self {
+            RollupAggregate::Key => column.to_string(),
+            RollupAggregate::Sum => format!("sumState({})", column),
+            RollupAggregate::Max => format!("maxState({})", column),
+            RollupAggregate::Min => format!("minState({})", column),
+            RollupAggregate::First => format!("argMinState({}, {})", column, sequence_column),
+            RollupAggregate::Last => format!("argMaxState({}, {})", column, sequence_column),
+            RollupAggregate::Avg => format!("avgState({})", column),
+            RollupAggregate::Latest => format!("max({})", column),
+        }
+    }
+}
+
+/// Aggregate used for `column_name` of a bucket table. Primary key columns are keys,
+/// `avg` sample counters are summed and columns no operation sets keep the latest value.
+pub fn column_aggregate(table: &Table, column_name: &str) -> RollupAggregate {
+    if table.is_primary_key_column(column_name) {
+        return RollupAggregate::Key;
+    }
+
+    let is_avg_samples = table.all_operations().flat_map(|op| op.set.iter()).any(|set_col| {
+        set_col.action == SetAction::Avg
+            && injected_columns::avg_samples(&set_col.column) == column_name
+    });
+    if is_avg_samples {
+        return RollupAggregate::Sum;
+    }
+
+    table
+        .bucket_column_action(column_name)
+        .map_or(RollupAggregate::Last, RollupAggregate::from_action)
+}
+
+/// A rollup column with its plain (non aggregate) ClickHouse type.
+#[derive(Debug, Clone)]
+pub struct RollupColumn {
+    pub name: String,
+    pub clickhouse_type: String,
+    pub aggregate: RollupAggregate,
+}
+
+/// Builds the `INSERT ... SELECT ... GROUP BY` folding `rows` into partial aggregate rows.
+/// Row values are positional and must line up with `columns`.
+pub fn build_rollup_insert(
+    table_name: &str,
+    columns: &[RollupColumn],
+    sequence_column: &str,
+    rows: &[Vec<EthereumSqlTypeWrapper>],
+) -> String {
+    let source = rows
+        .iter()
+        .map(|row| {
+            let values = columns
+                .iter()
+                .zip(row)
+                .map(|(column, value)| {
+                    format!(
+                        "CAST({} AS {}) AS {}",
+                        value.to_clickhouse_value(),
+                        column.clickhouse_type,
+                        quote_identifier(&column.name)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("SELECT {}", values)
+        })
+        .collect::<Vec<_>>()
+        .join(" UNION ALL ");
+
+    let sequence_column = quote_identifier(sequence_column);
+    let column_names =
+        columns.iter().map(|column| quote_identifier(&column.name)).collect::<Vec<_>>().join(", ");
+    let select_exprs = columns
+        .iter()
+        .map(|column| {
+            column.aggregate.select_expr(&quote_identifier(&column.name), &sequence_column)
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let group_keys = columns
+        .iter()
+        .filter(|column| column.aggregate == RollupAggregate::Key)
+        .map(|column| quote_identifier(&column.name))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!(
+        "INSERT INTO {} ({}) SELECT {} FROM ({}) GROUP BY {}",
+        format_table_name(table_name),
+        column_names,
+        select_exprs,
+        source,
+        group_keys
+    )
+}
+
+/// Folds `rows` into a rollup table, chunked by the client batch size.
+pub async fn execute_rollup_batch_operation(
+    database: &ClickhouseClient,
+    table_name: &str,
+    columns: &[RollupColumn],
+    sequence_column: &str,
+    rows: Vec<Vec<EthereumSqlTypeWrapper>>,
+    event_name: &str,
+) -> Result<(), String> {
+    for batch in rows.chunks(database.batch_size()) {
+        let query = build_rollup_insert(table_name, columns, sequence_column, batch);
+
+        tracing::debug!("Custom indexing ClickHouse rollup query: {}", query);
+
+        database.execute(&query).await.map_err(|e| {
+            tracing::error!("{} - Rollup batch operation failed: {:?}", event_name, e);
+            tracing::error!("Failed query:\n{}", query);
+            e.to_string()
+        })?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rollup_column_types() {
+        assert_eq!(RollupAggregate::Key.column_type("String"), "String");
+        assert_eq!(
+            RollupAggregate::First.column_type("UInt256"),
+            "AggregateFunction(argMin, UInt256, UInt128)"
+        );
+        assert_eq!(
+            RollupAggregate::from_action(&SetAction::Close).column_type("UInt256"),
+            "AggregateFunction(argMax, UInt256, UInt128)"
+        );
+        assert_eq!(
+            RollupAggregate::from_action(&SetAction::Count).column_type("UInt64"),
+            "AggregateFunction(sum, UInt64)"
+        );
+        assert_eq!(
+            RollupAggregate::Latest.column_type("UInt128"),
+            "SimpleAggregateFunction(max, UInt128)"
+        );
+    }
+
+    #[test]
+    fn test_build_rollup_insert() {
+        let columns = vec![
+            RollupColumn {
+                name: "network".to_string(),
+                clickhouse_type: "String".to_string(),
+                aggregate: RollupAggregate::Key,
+            },
+            RollupColumn {
+                name: "open".to_string(),
+                clickhouse_type: "UInt64".to_string(),
+                aggregate: RollupAggregate::First,
+            },
+            RollupColumn {
+                name: "rindexer_sequence_id".to_string(),
+                clickhouse_type: "UInt128".to_string(),
+                aggregate: RollupAggregate::Latest,
+            },
+        ];
+        let rows = vec![
+            vec![
+                EthereumSqlTypeWrapper::String("ethereum".to_string()),
+                EthereumSqlTypeWrapper::U64(5),
+                EthereumSqlTypeWrapper::U128(1),
+            ],
+            vec![
+                EthereumSqlTypeWrapper::String("ethereum".to_string()),
+                EthereumSqlTypeWrapper::U64(7),
+                EthereumSqlTypeWrapper::U128(2),
+            ],
+        ];
+
+        let query = build_rollup_insert("db.candles", &columns, "rindexer_sequence_id", &rows);
+
+        assert!(query.starts_with("INSERT INTO `db`.`candles`"));
+        assert!(query.contains("argMinState(open, rindexer_sequence_id)"));
+        assert!(query.contains("max(rindexer_sequence_id)"));
+        assert!(query.contains(" UNION ALL "));
+        assert!(query.ends_with("GROUP BY network"));
+    }
+}
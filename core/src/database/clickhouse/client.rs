@@ -234,6 +234,9 @@ impl ClickhouseClient {
     ///
     /// All DELETE operations use `mutations_sync = 1` for synchronous execution.
     /// Each step is idempotent, so a crash mid-sequence can be retried from the start.
+    ///
+    /// Returns `(rows_deleted, affected_tx_hashes)` with one row count per event table, in
+    /// the order of `event_tables`.
     pub async fn reorg_rollback(
         &self,
         event_tables: &[(String, String)], // (database, table_name)
@@ -242,7 +245,7 @@ impl ClickhouseClient {
         detection_point: u64,
         checkpoint_tables: &[String],
         corrected_blocks: &[(u64, &str, &str)],
-    ) -> Result<(u64, Vec<String>), ClickhouseError> {
+    ) -> Result<(Vec<u64>, Vec<String>), ClickhouseError> {
         #[derive(Row, Deserialize)]
         struct CountAndHashes {
             c: u64,
@@ -272,7 +275,7 @@ impl ClickhouseClient {
             fork_point, detection_point, network
         );
 
-        let mut total_deleted: u64 = 0;
+        let mut rows_deleted: Vec<u64> = Vec::with_capacity(event_tables.len());
         let mut all_tx_hashes: HashSet<String> = HashSet::new();
 
         for (database, table_name) in event_tables {
@@ -282,7 +285,7 @@ impl ClickhouseClient {
                 fq_name, predicate
             );
             let row: CountAndHashes = self.conn.query(&sql).fetch_one().await?;
-            total_deleted += row.c;
+            rows_deleted.push(row.c);
             all_tx_hashes.extend(row.hashes.into_iter().map(ClickhouseHash::into_string));
         }
 
@@ -309,7 +312,7 @@ impl ClickhouseClient {
             self.execute(&sql).await?;
         }
 
-        Ok((total_deleted, all_tx_hashes.into_iter().collect()))
+        Ok((rows_deleted, all_tx_hashes.into_iter().collect()))
     }
 }
 
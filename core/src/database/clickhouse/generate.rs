@@ -8,14 +8,19 @@ use crate::{
     types::code::Code,
 };
 
+use crate::database::clickhouse::batch_operations::{column_aggregate, RollupAggregate};
 use crate::database::generate::{
-    generate_indexer_contract_schema_name, generate_internal_factory_event_table_name,
+    generate_distinct_values_table_full_name, generate_indexer_contract_schema_name,
+    generate_internal_factory_event_table_name,
     generate_internal_factory_event_table_name_no_shorten, GenerateTablesForIndexerSqlError,
 };
 use crate::database::postgres::generate::{
     generate_internal_event_table_name_no_shorten, GenerateInternalFactoryEventTableNameParams,
 };
-use crate::manifest::contract::{injected_columns, FactoryDetailsYaml, Table};
+use crate::manifest::contract::{
+    injected_columns, Contract, FactoryDetailsYaml, Retention, RetentionPolicy, Table, TableIndex,
+    TablePartition,
+};
 
 pub fn generate_reorg_block_hashes_table_clickhouse_sql() -> String {
     r#"CREATE TABLE IF NOT EXISTS rindexer_internal.reorg_block_hashes (
@@ -28,6 +33,59 @@ pub fn generate_reorg_block_hashes_table_clickhouse_sql() -> String {
         .to_string()
 }
 
+pub fn generate_reorg_events_table_clickhouse_sql() -> String {
+    r#"CREATE TABLE IF NOT EXISTS rindexer_internal.reorg_events (
+        network String,
+        fork_block UInt64,
+        detection_block UInt64,
+        depth UInt64,
+        detection_source LowCardinality(String),
+        events_deleted UInt64,
+        affected_tables String,
+        duration_secs Float64,
+        handled_at DateTime DEFAULT now()
+    ) ENGINE = MergeTree
+    ORDER BY (network, handled_at);"#
+        .to_string()
+}
+
+pub fn generate_block_range_state_table_clickhouse_sql() -> String {
+    r#"CREATE TABLE IF NOT EXISTS rindexer_internal.block_range_state (
+        network String,
+        provider String,
+        event String,
+        block_range Nullable(UInt64),
+        provider_limit Nullable(UInt64),
+        logs_per_block Float64,
+        latency_ms Float64,
+        updated_at DateTime DEFAULT now()
+    ) ENGINE = ReplacingMergeTree(updated_at)
+    ORDER BY (network, provider, event);"#
+        .to_string()
+}
+
+pub fn generate_contract_deployment_blocks_table_clickhouse_sql() -> String {
+    r#"CREATE TABLE IF NOT EXISTS rindexer_internal.contract_deployment_blocks (
+        network String,
+        address FixedString(42),
+        block_number UInt64
+    ) ENGINE = ReplacingMergeTree
+    ORDER BY (network, address);"#
+        .to_string()
+}
+
+pub fn generate_token_metadata_table_clickhouse_sql() -> String {
+    r#"CREATE TABLE IF NOT EXISTS rindexer_internal.token_metadata (
+        network String,
+        address FixedString(42),
+        decimals Nullable(UInt8),
+        symbol Nullable(String),
+        name Nullable(String)
+    ) ENGINE = ReplacingMergeTree
+    ORDER BY (network, address);"#
+        .to_string()
+}
+
 pub fn generate_derived_op_log_table_clickhouse_sql() -> String {
     r#"CREATE TABLE IF NOT EXISTS rindexer_internal.derived_op_log (
         derived_table String,
@@ -37,9 +95,11 @@ pub fn generate_derived_op_log_table_clickhouse_sql() -> String {
         value Float64,
         block_number UInt64,
         tx_index UInt32,
-        log_index UInt32
+        log_index UInt32,
+        value_text Nullable(String)
     ) ENGINE = MergeTree
-    ORDER BY (derived_table, column_name, where_key, block_number, tx_index, log_index);"#
+    ORDER BY (derived_table, column_name, where_key, block_number, tx_index, log_index);
+    ALTER TABLE rindexer_internal.derived_op_log ADD COLUMN IF NOT EXISTS value_text Nullable(String);"#
         .to_string()
 }
 
@@ -50,7 +110,11 @@ pub fn generate_tables_for_indexer_clickhouse(
 ) -> Result<Code, GenerateTablesForIndexerSqlError> {
     let mut sql = "CREATE DATABASE IF NOT EXISTS rindexer_internal;".to_string();
     sql.push_str(&generate_reorg_block_hashes_table_clickhouse_sql());
+    sql.push_str(&generate_reorg_events_table_clickhouse_sql());
     sql.push_str(&generate_derived_op_log_table_clickhouse_sql());
+    sql.push_str(&generate_contract_deployment_blocks_table_clickhouse_sql());
+    sql.push_str(&generate_block_range_state_table_clickhouse_sql());
+    sql.push_str(&generate_token_metadata_table_clickhouse_sql());
 
     for contract in &indexer.contracts {
         let contract_name = contract.before_modify_name_if_filter_readonly();
@@ -72,7 +136,7 @@ pub fn generate_tables_for_indexer_clickhouse(
                 .collect();
 
             if !raw_events.is_empty() {
-                sql.push_str(&generate_event_table_clickhouse(&raw_events, &schema_name));
+                sql.push_str(&generate_event_table_clickhouse(&raw_events, contract, &schema_name));
             }
 
             // Generate custom tables if defined
@@ -93,7 +157,11 @@ pub fn generate_tables_for_indexer_clickhouse(
     Ok(Code::new(sql))
 }
 
-fn generate_event_table_clickhouse(abi_inputs: &[EventInfo], schema_name: &str) -> String {
+fn generate_event_table_clickhouse(
+    abi_inputs: &[EventInfo],
+    contract: &Contract,
+    schema_name: &str,
+) -> String {
     abi_inputs
         .iter()
         .map(|event_info| {
@@ -123,8 +191,12 @@ fn generate_event_table_clickhouse(abi_inputs: &[EventInfo], schema_name: &str)
                     index idx_tx_hash (tx_hash) type bloom_filter granularity 1
                 )
                 ENGINE = ReplacingMergeTree
-                ORDER BY (network, block_number, tx_hash, log_index);"#,
-                table_name, event_columns
+                ORDER BY (network, block_number, tx_hash, log_index){};"#,
+                table_name,
+                event_columns,
+                generate_event_table_ttl_clickhouse(contract.event_retention(&event_info.name))
+                    .map(|ttl| format!(" TTL {}", ttl))
+                    .unwrap_or_default()
             );
 
             create_table_sql
@@ -133,6 +205,64 @@ fn generate_event_table_clickhouse(abi_inputs: &[EventInfo], schema_name: &str)
         .join("\n")
 }
 
+/// The side table holding the values counted by the `count_distinct` columns of a custom
+/// table, see `generate_distinct_values_table_full_name`.
+fn generate_distinct_values_table_clickhouse_sql(table_name: &str) -> String {
+    format!(
+        "\nCREATE TABLE IF NOT EXISTS {} (`network` String, `where_key` String, \
+         `column_name` String, `value` String, `block_number` UInt64) \
+         ENGINE = ReplacingMergeTree ORDER BY (`network`, `where_key`, `column_name`, `value`);",
+        generate_distinct_values_table_full_name(table_name)
+    )
+}
+
+/// Generate the `PARTITION BY` expression of a partitioned custom table, in the form
+/// ClickHouse reports it back in `system.tables.partition_key`.
+pub fn generate_partition_key_clickhouse(partition: &TablePartition) -> String {
+    match partition.block_range() {
+        Some(interval) => format!("intDiv({}, {})", injected_columns::BLOCK_NUMBER, interval),
+        None => "network".to_string(),
+    }
+}
+
+/// Generate the `TTL` expression deleting rows older than `max_age_secs`, in the form
+/// ClickHouse reports it back in `system.tables.engine_full`.
+fn generate_ttl_clickhouse(timestamp_expression: &str, max_age_secs: u64) -> String {
+    format!("{} + toIntervalSecond({})", timestamp_expression, max_age_secs)
+}
+
+/// Generate the `TTL` expression of a raw event table, None unless it has a `max_age`
+/// retention. Block ages have no TTL equivalent and are pruned by the retention job.
+pub fn generate_event_table_ttl_clickhouse(retention: Option<&Retention>) -> Option<String> {
+    match retention?.policy().ok()? {
+        // The column is nullable, which a TTL can't be. Timestamps are required by validation.
+        RetentionPolicy::MaxAge(secs) => {
+            Some(generate_ttl_clickhouse("assumeNotNull(block_timestamp)", secs))
+        }
+        RetentionPolicy::MaxBlocks(_) => None,
+    }
+}
+
+/// Generate the `TTL` expression of a custom table, None unless it has a `max_age` retention.
+pub fn generate_table_ttl_clickhouse(table: &Table) -> Option<String> {
+    match table.retention.as_ref()?.policy().ok()? {
+        RetentionPolicy::MaxAge(secs) => {
+            Some(generate_ttl_clickhouse(table.retention_timestamp_column()?, secs))
+        }
+        RetentionPolicy::MaxBlocks(_) => None,
+    }
+}
+
+/// Generate the data skipping index of a custom table index, as declared after `INDEX`.
+/// ClickHouse has no partial indexes, so the `where` condition only applies to Postgres.
+pub fn generate_table_index_clickhouse(table_name: &str, index: &TableIndex) -> String {
+    format!(
+        "`{}` ({}) TYPE bloom_filter GRANULARITY 4",
+        index.index_name(table_name),
+        index.columns.iter().map(|c| format!("`{}`", c)).collect::<Vec<_>>().join(", ")
+    )
+}
+
 /// Generate ClickHouse SQL for custom tables
 fn generate_tables_clickhouse(tables: &[Table], schema_name: &str) -> String {
     tables
@@ -152,6 +282,18 @@ fn generate_tables_clickhouse(tables: &[Table], schema_name: &str) -> String {
             // Build column definitions
             let mut columns: Vec<String> = vec![];
 
+            let index_declarations = table.indexes.iter().map(|index| {
+                format!("INDEX {}", generate_table_index_clickhouse(&table.name, index))
+            });
+            let partition_clause = table
+                .partition_by
+                .as_ref()
+                .map(|p| format!(" PARTITION BY {}", generate_partition_key_clickhouse(p)))
+                .unwrap_or_default();
+            let ttl_clause = generate_table_ttl_clickhouse(table)
+                .map(|ttl| format!(" TTL {}", ttl))
+                .unwrap_or_default();
+
             // Add network column (part of ORDER BY unless cross_chain is true)
             if !table.cross_chain {
                 columns.push("`network` String".to_string());
@@ -160,6 +302,15 @@ fn generate_tables_clickhouse(tables: &[Table], schema_name: &str) -> String {
             // Add user-defined columns
             for column in &table.columns {
                 let column_type = column.resolved_type().to_clickhouse_type();
+
+                // Bucket tables store aggregate states, which can't carry defaults
+                if table.bucket.is_some() {
+                    let aggregate = column_aggregate(table, &column.name);
+                    columns
+                        .push(format!("`{}` {}", column.name, aggregate.column_type(&column_type)));
+                    continue;
+                }
+
                 let mut column_def = format!("`{}` {}", column.name, column_type);
 
                 if let Some(default) = &column.default {
@@ -182,6 +333,40 @@ fn generate_tables_clickhouse(tables: &[Table], schema_name: &str) -> String {
                 columns.push(column_def);
             }
 
+            // Bucket tables keep one partial aggregate row per block (part of ORDER BY) so a
+            // reorg can drop the orphaned partials; per-event metadata doesn't apply to them.
+            if table.bucket.is_some() {
+                columns.push(format!("`{}` UInt64", injected_columns::BLOCK_NUMBER));
+                columns.push(format!(
+                    "`{}` {}",
+                    injected_columns::BLOCK_TIMESTAMP,
+                    RollupAggregate::Latest.column_type("DateTime('UTC')")
+                ));
+                columns.push(format!(
+                    "`{}` {}",
+                    injected_columns::RINDEXER_SEQUENCE_ID,
+                    RollupAggregate::Latest.column_type("UInt128")
+                ));
+
+                let mut order_by: Vec<String> = vec!["`network`".to_string()];
+                for pk_col in table.primary_key_columns() {
+                    order_by.push(format!("`{}`", pk_col));
+                }
+                order_by.push(format!("`{}`", injected_columns::BLOCK_NUMBER));
+
+                columns.extend(index_declarations);
+
+                return format!(
+                    "{}CREATE TABLE IF NOT EXISTS {} ({}) ENGINE = AggregatingMergeTree(){} ORDER BY ({}){};",
+                    create_db,
+                    table_name,
+                    columns.join(", "),
+                    partition_clause,
+                    order_by.join(", "),
+                    ttl_clause
+                );
+            }
+
             // Auto-injected metadata columns (always populated by rindexer, no defaults needed)
             columns.push(format!("`{}` UInt64", injected_columns::BLOCK_NUMBER));
             // Only add block timestamp column if table.timestamp is true
@@ -217,14 +402,22 @@ fn generate_tables_clickhouse(tables: &[Table], schema_name: &str) -> String {
                 format!("ReplacingMergeTree(`{}`)", injected_columns::RINDEXER_SEQUENCE_ID)
             };
 
-            format!(
-                "{}CREATE TABLE IF NOT EXISTS {} ({}) ENGINE = {} ORDER BY ({});",
+            columns.extend(index_declarations);
+
+            let mut sql = format!(
+                "{}CREATE TABLE IF NOT EXISTS {} ({}) ENGINE = {}{} ORDER BY ({}){};",
                 create_db,
                 table_name,
                 columns.join(", "),
                 engine,
-                order_by.join(", ")
-            )
+                partition_clause,
+                order_by.join(", "),
+                ttl_clause
+            );
+            if !table.count_distinct_columns().is_empty() {
+                sql.push_str(&generate_distinct_values_table_clickhouse_sql(&table_name));
+            }
+            sql
         })
         .collect::<Vec<_>>()
         .join("\n")
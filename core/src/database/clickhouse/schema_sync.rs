@@ -1,4 +1,5 @@
-//! Schema synchronization for custom tables in ClickHouse.
+//! Schema synchronization for custom tables in ClickHouse. The `TTL` of raw event tables is
+//! kept in sync as well, it follows their `retention`.
 //!
 //! Detects differences between YAML-defined table schemas and actual database schemas,
 //! and provides mechanisms to apply migrations.
@@ -8,10 +9,15 @@ use std::collections::{HashMap, HashSet};
 use clickhouse::Row;
 use serde::Deserialize;
 
+use crate::database::clickhouse::batch_operations::{column_aggregate, RollupAggregate};
 use crate::database::clickhouse::client::ClickhouseClient;
+use crate::database::clickhouse::generate::{
+    generate_event_table_ttl_clickhouse, generate_partition_key_clickhouse,
+    generate_table_index_clickhouse, generate_table_ttl_clickhouse,
+};
 use crate::database::generate::generate_indexer_contract_schema_name;
 use crate::helpers::camel_to_snake;
-use crate::manifest::contract::{injected_columns, Table};
+use crate::manifest::contract::{injected_columns, Table, TableIndex};
 use crate::manifest::core::Manifest;
 
 /// Represents a detected schema change that may need to be applied.
@@ -39,12 +45,40 @@ pub enum SchemaChange {
         current_type: String,
         new_type: String,
     },
+    /// A data skipping index was added to the YAML.
+    AddIndex { table_full_name: String, table_name: String, index: TableIndex },
+    /// A data skipping index created by rindexer is no longer in the YAML.
+    DropIndex { table_full_name: String, index_name: String },
+    /// The columns of a data skipping index have changed.
+    ChangeIndex {
+        table_full_name: String,
+        table_name: String,
+        index: TableIndex,
+        current_expression: String,
+    },
+    /// PARTITION BY has changed (requires manual migration).
+    ChangePartition {
+        table_full_name: String,
+        current_partition: Option<String>,
+        new_partition: Option<String>,
+    },
+    /// The TTL derived from `retention` has changed.
+    ChangeTtl { table_full_name: String, current_ttl: Option<String>, new_ttl: Option<String> },
 }
 
 impl SchemaChange {
     /// Returns true if this change is safe to auto-apply without user confirmation.
+    /// Index changes never touch data, they only rebuild indexes. TTL changes apply the
+    /// retention configured in the YAML.
     pub fn is_safe(&self) -> bool {
-        matches!(self, SchemaChange::AddColumn { .. })
+        matches!(
+            self,
+            SchemaChange::AddColumn { .. }
+                | SchemaChange::AddIndex { .. }
+                | SchemaChange::DropIndex { .. }
+                | SchemaChange::ChangeIndex { .. }
+                | SchemaChange::ChangeTtl { .. }
+        )
     }
 
     /// Returns a human-readable description of the change.
@@ -90,6 +124,50 @@ impl SchemaChange {
                     column_name, table_full_name, current_type, new_type
                 )
             }
+            SchemaChange::AddIndex { table_full_name, table_name, index } => {
+                format!(
+                    "Add index '{}' ({}) to table '{}'",
+                    index.index_name(table_name),
+                    index.columns.join(", "),
+                    table_full_name
+                )
+            }
+            SchemaChange::DropIndex { table_full_name, index_name } => {
+                format!(
+                    "Drop index '{}' from table '{}' as it is no longer in YAML",
+                    index_name, table_full_name
+                )
+            }
+            SchemaChange::ChangeIndex {
+                table_full_name,
+                table_name,
+                index,
+                current_expression,
+            } => {
+                format!(
+                    "Index '{}' changed in table '{}': ({}) -> ({})",
+                    index.index_name(table_name),
+                    table_full_name,
+                    current_expression,
+                    index.columns.join(", ")
+                )
+            }
+            SchemaChange::ChangePartition { table_full_name, current_partition, new_partition } => {
+                format!(
+                    "PARTITION BY changed for table '{}': {} -> {}",
+                    table_full_name,
+                    current_partition.as_deref().unwrap_or("none"),
+                    new_partition.as_deref().unwrap_or("none")
+                )
+            }
+            SchemaChange::ChangeTtl { table_full_name, current_ttl, new_ttl } => {
+                format!(
+                    "TTL changed for table '{}': {} -> {}",
+                    table_full_name,
+                    current_ttl.as_deref().unwrap_or("none"),
+                    new_ttl.as_deref().unwrap_or("none")
+                )
+            }
         }
     }
 }
@@ -108,6 +186,25 @@ struct TableInfo {
     sorting_key: String,
 }
 
+/// Row type for querying the partition key from system.tables.
+#[derive(Debug, Clone, Row, Deserialize)]
+struct PartitionInfo {
+    partition_key: String,
+}
+
+/// Row type for querying the engine definition, including the TTL, from system.tables.
+#[derive(Debug, Clone, Row, Deserialize)]
+struct EngineInfo {
+    engine_full: String,
+}
+
+/// Row type for querying data skipping indexes from system.data_skipping_indices.
+#[derive(Debug, Clone, Row, Deserialize)]
+struct IndexInfo {
+    name: String,
+    expr: String,
+}
+
 /// Row type for checking table existence.
 #[derive(Debug, Clone, Row, Deserialize)]
 struct ExistsResult {
@@ -162,6 +259,95 @@ async fn get_order_by_columns(
     }
 }
 
+/// Queries the PARTITION BY expression of a table from ClickHouse, None if it isn't partitioned.
+async fn get_partition_key(
+    client: &ClickhouseClient,
+    database: &str,
+    table_name: &str,
+) -> Result<Option<String>, String> {
+    let query = format!(
+        "SELECT partition_key FROM system.tables WHERE database = '{}' AND name = '{}'",
+        database, table_name
+    );
+
+    let result = client
+        .query_optional::<PartitionInfo>(&query)
+        .await
+        .map_err(|e| format!("Failed to query partition key: {}", e))?;
+
+    Ok(result.map(|info| info.partition_key).filter(|key| !key.is_empty()))
+}
+
+/// Queries the TTL expression of a table from ClickHouse, None if it has no TTL.
+async fn get_ttl(
+    client: &ClickhouseClient,
+    database: &str,
+    table_name: &str,
+) -> Result<Option<String>, String> {
+    let query = format!(
+        "SELECT engine_full FROM system.tables WHERE database = '{}' AND name = '{}'",
+        database, table_name
+    );
+
+    let result = client
+        .query_optional::<EngineInfo>(&query)
+        .await
+        .map_err(|e| format!("Failed to query engine: {}", e))?;
+
+    // engine_full reads `... ORDER BY (...) TTL <expression> SETTINGS ...`
+    Ok(result.and_then(|info| {
+        let (_, ttl) = info.engine_full.split_once(" TTL ")?;
+        Some(ttl.split(" SETTINGS ").next().unwrap_or(ttl).trim().to_string())
+    }))
+}
+
+/// Compares the TTL of a table with the expected one.
+async fn detect_ttl_change(
+    client: &ClickhouseClient,
+    database: &str,
+    table_name: &str,
+    expected_ttl: Option<String>,
+) -> Result<Option<SchemaChange>, String> {
+    let current_ttl = get_ttl(client, database, table_name).await?;
+    if current_ttl.as_deref().map(normalize_ch_expression)
+        == expected_ttl.as_deref().map(normalize_ch_expression)
+    {
+        return Ok(None);
+    }
+
+    Ok(Some(SchemaChange::ChangeTtl {
+        table_full_name: format!("{}.{}", database, table_name),
+        current_ttl,
+        new_ttl: expected_ttl,
+    }))
+}
+
+/// Queries the data skipping indexes of a table from ClickHouse.
+async fn get_existing_indexes(
+    client: &ClickhouseClient,
+    database: &str,
+    table_name: &str,
+) -> Result<Vec<IndexInfo>, String> {
+    let query = format!(
+        "SELECT name, expr FROM system.data_skipping_indices WHERE database = '{}' AND table = '{}'",
+        database, table_name
+    );
+
+    client
+        .query_all::<IndexInfo>(&query)
+        .await
+        .map_err(|e| format!("Failed to query indexes: {}", e))
+}
+
+/// Normalize a ClickHouse expression for comparison, ignoring quoting and whitespace.
+fn normalize_ch_expression(expression: &str) -> String {
+    expression
+        .chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, '`' | '(' | ')'))
+        .collect::<String>()
+        .to_lowercase()
+}
+
 /// Checks if a table exists in the database.
 async fn table_exists(
     client: &ClickhouseClient,
@@ -193,7 +379,12 @@ fn get_expected_columns(table: &Table) -> HashMap<String, String> {
     // User-defined columns
     for column in &table.columns {
         let ch_type = if let Some(col_type) = &column.column_type {
-            col_type.to_clickhouse_type()
+            let base_type = col_type.to_clickhouse_type();
+            if table.bucket.is_some() {
+                column_aggregate(table, &column.name).column_type(&base_type)
+            } else {
+                base_type
+            }
         } else {
             // Type not yet resolved - use a placeholder
             "unknown".to_string()
@@ -203,6 +394,18 @@ fn get_expected_columns(table: &Table) -> HashMap<String, String> {
 
     // Injected columns
     columns.insert(injected_columns::BLOCK_NUMBER.to_string(), "UInt64".to_string());
+    // Bucket tables only keep the latest timestamp/sequence of each partial aggregate row
+    if table.bucket.is_some() {
+        columns.insert(
+            injected_columns::BLOCK_TIMESTAMP.to_string(),
+            RollupAggregate::Latest.column_type("DateTime('UTC')"),
+        );
+        columns.insert(
+            injected_columns::RINDEXER_SEQUENCE_ID.to_string(),
+            RollupAggregate::Latest.column_type("UInt128"),
+        );
+        return columns;
+    }
     // Only add block timestamp column if table.timestamp is true
     if table.timestamp {
         columns
@@ -228,6 +431,10 @@ fn get_expected_order_by_columns(table: &Table) -> Vec<String> {
         order_by.push(pk_col.to_string());
     }
 
+    if table.bucket.is_some() {
+        order_by.push(injected_columns::BLOCK_NUMBER.to_string());
+    }
+
     order_by
 }
 
@@ -239,12 +446,27 @@ pub async fn detect_schema_changes(
     let mut changes: Vec<SchemaChange> = vec![];
 
     for contract in &manifest.contracts {
+        let database = generate_indexer_contract_schema_name(&manifest.name, &contract.name);
+
+        // Raw event tables only follow their retention
+        for event in contract.include_events.iter().flatten() {
+            let table_name = camel_to_snake(&event.name);
+            if !table_exists(client, &database, &table_name).await? {
+                continue;
+            }
+            let expected_ttl =
+                generate_event_table_ttl_clickhouse(contract.event_retention(&event.name));
+            if let Some(change) =
+                detect_ttl_change(client, &database, &table_name, expected_ttl).await?
+            {
+                changes.push(change);
+            }
+        }
+
         let Some(tables) = &contract.tables else {
             continue;
         };
 
-        let database = generate_indexer_contract_schema_name(&manifest.name, &contract.name);
-
         for table in tables {
             let table_name = camel_to_snake(&table.name);
             let table_full_name = format!("{}.{}", database, table_name);
@@ -352,6 +574,72 @@ pub async fn detect_schema_changes(
                     }
                 }
             }
+
+            // Check for PARTITION BY changes
+            let current_partition = get_partition_key(client, &database, &table_name).await?;
+            let expected_partition =
+                table.partition_by.as_ref().map(generate_partition_key_clickhouse);
+            if current_partition.as_deref().map(normalize_ch_expression)
+                != expected_partition.as_deref().map(normalize_ch_expression)
+            {
+                changes.push(SchemaChange::ChangePartition {
+                    table_full_name: table_full_name.clone(),
+                    current_partition,
+                    new_partition: expected_partition,
+                });
+            }
+
+            // Check for TTL changes
+            if let Some(change) = detect_ttl_change(
+                client,
+                &database,
+                &table_name,
+                generate_table_ttl_clickhouse(table),
+            )
+            .await?
+            {
+                changes.push(change);
+            }
+
+            // Check for index changes, only indexes named by rindexer are managed
+            let existing_indexes = get_existing_indexes(client, &database, &table_name).await?;
+            for index in &table.indexes {
+                let index_name = index.index_name(&table.name);
+                let expression = index.columns.join(",");
+                match existing_indexes.iter().find(|i| i.name == index_name) {
+                    None => changes.push(SchemaChange::AddIndex {
+                        table_full_name: table_full_name.clone(),
+                        table_name: table.name.clone(),
+                        index: index.clone(),
+                    }),
+                    Some(existing)
+                        if normalize_ch_expression(&existing.expr)
+                            != normalize_ch_expression(&expression) =>
+                    {
+                        changes.push(SchemaChange::ChangeIndex {
+                            table_full_name: table_full_name.clone(),
+                            table_name: table.name.clone(),
+                            index: index.clone(),
+                            current_expression: existing.expr.clone(),
+                        })
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            let managed_prefix = format!("idx_{}_", table_name);
+            let expected_indexes: HashSet<String> =
+                table.indexes.iter().map(|i| i.index_name(&table.name)).collect();
+            for existing in &existing_indexes {
+                if existing.name.starts_with(&managed_prefix)
+                    && !expected_indexes.contains(&existing.name)
+                {
+                    changes.push(SchemaChange::DropIndex {
+                        table_full_name: table_full_name.clone(),
+                        index_name: existing.name.clone(),
+                    });
+                }
+            }
         }
     }
 
@@ -374,6 +662,10 @@ fn normalize_ch_type(ch_type: &str) -> String {
         "int256" => "int256".to_string(),
         "uint256" => "uint256".to_string(),
         "bool" => "bool".to_string(),
+        // decimal(S) columns are created as Decimal256(S) and reported as Decimal(76, S)
+        decimal if decimal.starts_with("decimal256(") => {
+            format!("decimal(76, {}", &decimal["decimal256(".len()..])
+        }
         _ => inner.to_string(),
     }
 }
@@ -417,7 +709,63 @@ pub async fn apply_schema_change(
                 "Column type changes require manual migration. Please backup your data and recreate the table.".to_string(),
             );
         }
+        SchemaChange::AddIndex { table_full_name, table_name, index } => {
+            add_index(client, table_full_name, table_name, index).await?;
+        }
+        SchemaChange::DropIndex { table_full_name, index_name } => {
+            drop_index(client, table_full_name, index_name).await?;
+        }
+        SchemaChange::ChangeIndex { table_full_name, table_name, index, .. } => {
+            drop_index(client, table_full_name, &index.index_name(table_name)).await?;
+            add_index(client, table_full_name, table_name, index).await?;
+        }
+        SchemaChange::ChangePartition { .. } => {
+            // The partition key of a MergeTree table can't be altered
+            return Err(
+                "PARTITION BY changes require manual migration. Please backup your data and recreate the table.".to_string(),
+            );
+        }
+        SchemaChange::ChangeTtl { table_full_name, new_ttl, .. } => {
+            let sql = match new_ttl {
+                Some(ttl) => format!("ALTER TABLE {} MODIFY TTL {}", table_full_name, ttl),
+                None => format!("ALTER TABLE {} REMOVE TTL", table_full_name),
+            };
+
+            client.execute(&sql).await.map_err(|e| format!("Failed to change TTL: {}", e))?;
+        }
     }
 
     Ok(())
 }
+
+/// Adds a data skipping index and builds it for the existing parts.
+async fn add_index(
+    client: &ClickhouseClient,
+    table_full_name: &str,
+    table_name: &str,
+    index: &TableIndex,
+) -> Result<(), String> {
+    let sql = format!(
+        "ALTER TABLE {} ADD INDEX IF NOT EXISTS {}",
+        table_full_name,
+        generate_table_index_clickhouse(table_name, index)
+    );
+    client.execute(&sql).await.map_err(|e| format!("Failed to add index: {}", e))?;
+
+    let sql = format!(
+        "ALTER TABLE {} MATERIALIZE INDEX `{}`",
+        table_full_name,
+        index.index_name(table_name)
+    );
+    client.execute(&sql).await.map_err(|e| format!("Failed to materialize index: {}", e))
+}
+
+/// Drops a data skipping index.
+async fn drop_index(
+    client: &ClickhouseClient,
+    table_full_name: &str,
+    index_name: &str,
+) -> Result<(), String> {
+    let sql = format!("ALTER TABLE {} DROP INDEX IF EXISTS `{}`", table_full_name, index_name);
+    client.execute(&sql).await.map_err(|e| format!("Failed to drop index: {}", e))
+}
@@ -1,14 +1,18 @@
 use crate::abi::{EventInfo, ParamTypeError, ReadAbiError};
 use crate::database::postgres::client::PostgresError;
 use crate::database::postgres::generate::{
-    generate_columns_with_data_types, generate_derived_op_log_table_sql,
+    generate_block_range_state_table_sql, generate_columns_with_data_types,
+    generate_contract_deployment_blocks_table_sql, generate_derived_op_log_table_sql,
     generate_internal_cron_table_name, generate_internal_event_table_name,
-    generate_reorg_block_hashes_table_sql, GenerateInternalFactoryEventTableNameParams,
+    generate_reorg_block_hashes_table_sql, generate_reorg_events_table_sql,
+    generate_token_metadata_table_sql, GenerateInternalFactoryEventTableNameParams,
 };
 use crate::helpers::{camel_to_snake, snake_to_camel};
 use crate::indexer::native_transfer::{NATIVE_TRANSFER_ABI, NATIVE_TRANSFER_CONTRACT_NAME};
 use crate::indexer::Indexer;
-use crate::manifest::contract::{injected_columns, Contract, FactoryDetailsYaml, Table};
+use crate::manifest::contract::{
+    injected_columns, Contract, FactoryDetailsYaml, Table, TableIndex, TablePartition,
+};
 use crate::types::code::Code;
 use crate::ABIItem;
 use alloy::primitives::keccak256;
@@ -180,6 +184,7 @@ fn generate_tables_sql(
     contract_name: &str,
     schema_name: &str,
     clashing_table_names: &[String],
+    networks: &[&str],
 ) -> String {
     tables
         .iter()
@@ -256,29 +261,241 @@ fn generate_tables_sql(
                     primary_keys.push(format!("\"{}\"", pk_col));
                 }
             }
+            // Postgres requires the partition key to be part of the primary key
+            if table.partition_by.as_ref().and_then(|p| p.block_range()).is_some() {
+                primary_keys.push(format!("\"{}\"", injected_columns::BLOCK_NUMBER));
+            }
             let primary_key_constraint = format!("PRIMARY KEY ({})", primary_keys.join(", "));
 
+            // Versions share the row columns, captured before the primary key is added
+            let history_columns = table.history.then(|| columns.clone());
+
             columns.push(primary_key_constraint);
 
-            let create_table_sql =
-                format!("CREATE TABLE IF NOT EXISTS {} ({});", table_name, columns.join(", "));
+            let partition_clause = table
+                .partition_by
+                .as_ref()
+                .map(|p| format!(" PARTITION BY {}", generate_partition_key_sql(p)))
+                .unwrap_or_default();
+            let mut create_table_sql = format!(
+                "CREATE TABLE IF NOT EXISTS {} ({}){};",
+                table_name,
+                columns.join(", "),
+                partition_clause
+            );
+
+            // Network partitions are known upfront, block ranges are created as rows arrive
+            if table.partition_by.as_ref().is_some_and(|p| p.block_range().is_none()) {
+                for network in networks {
+                    create_table_sql
+                        .push_str(&generate_network_partition_sql(&table_name, network));
+                }
+            }
+
+            for index in &table.indexes {
+                create_table_sql.push_str(&format!(
+                    "\n{};\n{};",
+                    generate_table_index_sql(&table_name, &table.name, index, false),
+                    generate_table_index_comment_sql(&table_name, &table.name, index)
+                ));
+            }
 
             // Add table comment for GraphQL naming
             // If table name clashes with another contract's table, prefix with contract name
-            let table_comment = if clashing_table_names.contains(&table.name) {
-                let graphql_name = format!("{}{}", contract_name, snake_to_camel(&table.name));
-                format!("COMMENT ON TABLE {} IS E'@name {}';", table_name, graphql_name)
+            let graphql_name = if clashing_table_names.contains(&table.name) {
+                format!("{}{}", contract_name, snake_to_camel(&table.name))
             } else {
-                let graphql_name = snake_to_camel(&table.name);
-                format!("COMMENT ON TABLE {} IS E'@name {}';", table_name, graphql_name)
+                snake_to_camel(&table.name)
             };
+            let table_comment =
+                format!("COMMENT ON TABLE {} IS E'@name {}';", table_name, graphql_name);
 
-            format!("{}\n{}", create_table_sql, table_comment)
+            let mut sql = match history_columns {
+                Some(history_columns) => format!(
+                    "{}\n{}\n{}",
+                    create_table_sql,
+                    table_comment,
+                    generate_history_table_sql(
+                        &table_name,
+                        &graphql_name,
+                        history_columns,
+                        primary_keys
+                    )
+                ),
+                None => format!("{}\n{}", create_table_sql, table_comment),
+            };
+            if !table.count_distinct_columns().is_empty() {
+                sql.push_str(&generate_distinct_values_table_sql(&table_name));
+            }
+            sql
         })
         .collect::<Vec<_>>()
         .join("\n")
 }
 
+/// Generate the `PARTITION BY` key of a partitioned custom table, in the form Postgres
+/// reports it back through `pg_get_partkeydef`.
+pub fn generate_partition_key_sql(partition: &TablePartition) -> String {
+    match partition.block_range() {
+        Some(_) => format!("RANGE ({})", injected_columns::BLOCK_NUMBER),
+        None => "LIST (network)".to_string(),
+    }
+}
+
+/// Generate the name of a partition of a custom table.
+fn generate_partition_table_name(table_name: &str, suffix: &str) -> String {
+    let suffix: String =
+        suffix.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    format!("{}_{}", table_name, camel_to_snake(&suffix))
+}
+
+/// Generate the partition holding the rows of one network. Partitions are hidden from
+/// GraphQL, the parent table already exposes their rows.
+fn generate_network_partition_sql(table_name: &str, network: &str) -> String {
+    let partition_name = generate_partition_table_name(table_name, network);
+    format!(
+        "\nCREATE TABLE IF NOT EXISTS {partition_name} PARTITION OF {table_name} FOR VALUES IN ('{network}');\n\
+         COMMENT ON TABLE {partition_name} IS E'@omit';",
+        network = network.replace('\'', "''"),
+    )
+}
+
+/// Generate the partition holding the rows of blocks `[start_block, start_block + interval)`.
+pub fn generate_block_range_partition_sql(
+    table_name: &str,
+    start_block: u64,
+    interval: u64,
+) -> String {
+    let partition_name = generate_partition_table_name(table_name, &format!("p{}", start_block));
+    format!(
+        "CREATE TABLE IF NOT EXISTS {partition_name} PARTITION OF {table_name} FOR VALUES FROM ({start_block}) TO ({end_block});\n\
+         COMMENT ON TABLE {partition_name} IS E'@omit';",
+        end_block = start_block + interval,
+    )
+}
+
+/// Prefix of the comment rindexer stores on the custom table indexes it creates. Schema sync
+/// only changes or drops indexes carrying it, indexes created by users are left alone.
+pub const TABLE_INDEX_COMMENT_PREFIX: &str = "rindexer index: ";
+
+/// Generate the canonical definition of a custom table index. It is stored as the index
+/// comment so schema sync can tell when the columns or condition of an index changed.
+pub fn generate_table_index_definition(index: &TableIndex) -> String {
+    let columns = index.columns.iter().map(|c| format!("\"{}\"", c)).collect::<Vec<_>>().join(", ");
+    match &index.condition {
+        Some(condition) => format!("({}) WHERE {}", columns, condition),
+        None => format!("({})", columns),
+    }
+}
+
+/// Generate the `CREATE INDEX` statement of a custom table index.
+/// `CONCURRENTLY` avoids blocking writes on existing tables but can't be used on
+/// partitioned tables or inside a transaction.
+pub fn generate_table_index_sql(
+    table_full_name: &str,
+    table_name: &str,
+    index: &TableIndex,
+    concurrently: bool,
+) -> String {
+    format!(
+        "CREATE INDEX {}IF NOT EXISTS {} ON {} {}",
+        if concurrently { "CONCURRENTLY " } else { "" },
+        index.index_name(table_name),
+        table_full_name,
+        generate_table_index_definition(index)
+    )
+}
+
+/// Generate the statement storing the definition of a custom table index as its comment.
+pub fn generate_table_index_comment_sql(
+    table_full_name: &str,
+    table_name: &str,
+    index: &TableIndex,
+) -> String {
+    let schema_name = table_full_name.split('.').next().unwrap_or(table_full_name);
+    format!(
+        "COMMENT ON INDEX {}.{} IS '{}{}'",
+        schema_name,
+        index.index_name(table_name),
+        TABLE_INDEX_COMMENT_PREFIX,
+        generate_table_index_definition(index).replace('\'', "''")
+    )
+}
+
+/// Generate SQL for the versions table of a `history: true` custom table and the
+/// `{table}_at(block_number)` function exposing the rows as of a block, which GraphQL serves
+/// as the `{table}At(blockNumber: ...)` query. The function is `STRICT` so `blockNumber` is a
+/// required argument. A version is valid for blocks in `[rindexer_valid_from, rindexer_valid_to)`.
+fn generate_history_table_sql(
+    table_name: &str,
+    graphql_name: &str,
+    mut columns: Vec<String>,
+    mut primary_keys: Vec<String>,
+) -> String {
+    let history_table_name = generate_history_table_full_name(table_name);
+    info!("Creating history table if not exists: {}", history_table_name);
+
+    columns.push(format!("\"{}\" BIGINT NOT NULL", injected_columns::VALID_FROM));
+    columns.push(format!("\"{}\" BIGINT", injected_columns::VALID_TO));
+    primary_keys.push(format!("\"{}\"", injected_columns::VALID_FROM));
+    columns.push(format!("PRIMARY KEY ({})", primary_keys.join(", ")));
+
+    let bare_name = history_table_name.rsplit('.').next().unwrap_or(&history_table_name);
+
+    format!(
+        r#"
+        CREATE TABLE IF NOT EXISTS {history_table_name} ({columns});
+        COMMENT ON TABLE {history_table_name} IS E'@name {graphql_name}History';
+        CREATE INDEX IF NOT EXISTS {bare_name}_current ON {history_table_name} ("{valid_from}") WHERE "{valid_to}" IS NULL;
+        CREATE OR REPLACE FUNCTION {table_name}_at(block_number BIGINT)
+        RETURNS SETOF {history_table_name} AS $$
+            SELECT * FROM {history_table_name}
+            WHERE "{valid_from}" <= block_number
+            AND ("{valid_to}" IS NULL OR "{valid_to}" > block_number)
+        $$ LANGUAGE sql STABLE STRICT;
+        COMMENT ON FUNCTION {table_name}_at(BIGINT) IS E'@name {graphql_name}At';
+    "#,
+        columns = columns.join(", "),
+        valid_from = injected_columns::VALID_FROM,
+        valid_to = injected_columns::VALID_TO,
+    )
+}
+
+/// Generate SQL for the side table holding the values counted by the `count_distinct` columns
+/// of a custom table, one row per counted value keyed like the journal so a count is an index
+/// scan rather than an array stored on the row. It is hidden from GraphQL.
+fn generate_distinct_values_table_sql(table_name: &str) -> String {
+    let distinct_table_name = generate_distinct_values_table_full_name(table_name);
+    info!("Creating distinct values table if not exists: {}", distinct_table_name);
+
+    let bare_name = distinct_table_name.rsplit('.').next().unwrap_or(&distinct_table_name);
+
+    format!(
+        r#"
+        CREATE TABLE IF NOT EXISTS {distinct_table_name} (
+            network TEXT NOT NULL,
+            where_key TEXT NOT NULL,
+            column_name TEXT NOT NULL,
+            value TEXT NOT NULL,
+            block_number BIGINT NOT NULL,
+            PRIMARY KEY (network, where_key, column_name, value)
+        );
+        COMMENT ON TABLE {distinct_table_name} IS E'@omit';
+        CREATE INDEX IF NOT EXISTS {bare_name}_block ON {distinct_table_name} (network, block_number);
+    "#
+    )
+}
+
+/// Generate the side table name holding the values counted by `count_distinct` columns
+pub fn generate_distinct_values_table_full_name(table_full_name: &str) -> String {
+    format!("{}_distinct_values", table_full_name)
+}
+
+/// Generate the versions table name of a `history: true` custom table
+pub fn generate_history_table_full_name(table_full_name: &str) -> String {
+    format!("{}_history", table_full_name)
+}
+
 /// Generate table name for a custom table
 pub fn generate_table_full_name(
     indexer_name: &str,
@@ -359,7 +576,11 @@ pub fn generate_tables_for_indexer_sql(
 ) -> Result<Code, GenerateTablesForIndexerSqlError> {
     let mut sql = "CREATE SCHEMA IF NOT EXISTS rindexer_internal;".to_string();
     sql.push_str(&generate_reorg_block_hashes_table_sql());
+    sql.push_str(&generate_reorg_events_table_sql());
     sql.push_str(&generate_derived_op_log_table_sql());
+    sql.push_str(&generate_contract_deployment_blocks_table_sql());
+    sql.push_str(&generate_block_range_state_table_sql());
+    sql.push_str(&generate_token_metadata_table_sql());
 
     for contract in &indexer.contracts {
         let contract_name = contract.before_modify_name_if_filter_readonly();
@@ -405,6 +626,7 @@ pub fn generate_tables_for_indexer_sql(
                     &contract.name,
                     &schema_name,
                     &clashing_table_names,
+                    &networks,
                 ));
             }
         }
@@ -618,3 +840,70 @@ pub fn drop_tables_for_indexer_sql(project_path: &Path, indexer: &Indexer) -> Co
 
     Code::new(sql)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_history_table_sql_exposes_graphql_query() {
+        let sql = generate_history_table_sql(
+            "idx_token.balances",
+            "balances",
+            vec!["\"holder\" CHAR(42) NOT NULL".to_string()],
+            vec!["\"holder\"".to_string()],
+        );
+
+        assert!(sql.contains("CREATE TABLE IF NOT EXISTS idx_token.balances_history"));
+        assert!(sql.contains("PRIMARY KEY (\"holder\", \"rindexer_valid_from\")"));
+        assert!(
+            sql.contains("CREATE OR REPLACE FUNCTION idx_token.balances_at(block_number BIGINT)")
+        );
+        assert!(sql.contains("LANGUAGE sql STABLE STRICT"));
+        assert!(sql
+            .contains("COMMENT ON FUNCTION idx_token.balances_at(BIGINT) IS E'@name balancesAt'"));
+    }
+
+    #[test]
+    fn test_generate_table_index_sql() {
+        let index = TableIndex {
+            columns: vec!["pool".to_string(), "rindexer_block_number".to_string()],
+            name: Some("large".to_string()),
+            condition: Some("volume > 1000".to_string()),
+        };
+
+        assert_eq!(
+            generate_table_index_definition(&index),
+            "(\"pool\", \"rindexer_block_number\") WHERE volume > 1000"
+        );
+        assert_eq!(
+            generate_table_index_sql("idx_dex.pools", "pools", &index, true),
+            "CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_pools_large ON idx_dex.pools \
+             (\"pool\", \"rindexer_block_number\") WHERE volume > 1000"
+        );
+        assert_eq!(
+            generate_table_index_comment_sql("idx_dex.pools", "pools", &index),
+            "COMMENT ON INDEX idx_dex.idx_pools_large IS 'rindexer index: \
+             (\"pool\", \"rindexer_block_number\") WHERE volume > 1000'"
+        );
+
+        let index = TableIndex { columns: vec!["pool".to_string()], name: None, condition: None };
+        assert_eq!(
+            generate_table_index_sql("idx_dex.pools", "pools", &index, false),
+            "CREATE INDEX IF NOT EXISTS idx_pools_pool ON idx_dex.pools (\"pool\")"
+        );
+    }
+
+    #[test]
+    fn test_generate_distinct_values_table_sql() {
+        let sql = generate_distinct_values_table_sql("idx_dex.pools");
+
+        assert!(sql.contains("CREATE TABLE IF NOT EXISTS idx_dex.pools_distinct_values"));
+        assert!(sql.contains("PRIMARY KEY (network, where_key, column_name, value)"));
+        assert!(sql.contains("COMMENT ON TABLE idx_dex.pools_distinct_values IS E'@omit'"));
+        assert!(sql.contains(
+            "INDEX IF NOT EXISTS pools_distinct_values_block ON idx_dex.pools_distinct_values \
+             (network, block_number)"
+        ));
+    }
+}
@@ -3,16 +3,17 @@
 use tokio_postgres::types::ToSql;
 
 use super::query_builder::{
-    build_cte_header, build_delete_body, build_sequence_condition, build_set_clause,
-    build_to_process_cte, build_to_process_cte_aggregated, build_update_body, build_upsert_body,
-    build_upsert_set_clause, build_upsert_set_clause_latest_by_sequence, build_where_clause,
-    build_where_condition, format_table_name, ColumnAggregate, ColumnInfo, SetClauseType,
-    UpsertClauseType,
+    build_avg_set_clause, build_cte_header, build_delete_body, build_sequence_condition,
+    build_set_clause, build_to_process_cte, build_to_process_cte_aggregated, build_update_body,
+    build_upsert_avg_clause, build_upsert_body, build_upsert_set_clause,
+    build_upsert_set_clause_latest_by_sequence, build_where_clause, build_where_condition,
+    format_table_name, ColumnAggregate, ColumnInfo, SetClauseType, UpsertClauseType,
 };
 use crate::database::batch_operations::{
     BatchOperationAction, BatchOperationColumnBehavior, BatchOperationType, DynamicColumnDefinition,
 };
 use crate::database::postgres::client::PostgresClient;
+use crate::manifest::contract::injected_columns;
 use crate::EthereumSqlTypeWrapper;
 
 /// Executes a dynamic batch operation with runtime-defined columns.
@@ -120,6 +121,46 @@ async fn execute_batch(
         })
         .collect();
 
+    let first_columns: Vec<&str> = columns
+        .iter()
+        .filter_map(|col| match col.action {
+            BatchOperationAction::First => Some(col.name.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    let avg_columns: Vec<&str> = columns
+        .iter()
+        .filter_map(|col| match col.action {
+            BatchOperationAction::Avg => Some(col.name.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    let append_columns: Vec<&str> = columns
+        .iter()
+        .filter_map(|col| match col.action {
+            BatchOperationAction::Append => Some(col.name.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    let remove_columns: Vec<&str> = columns
+        .iter()
+        .filter_map(|col| match col.action {
+            BatchOperationAction::Remove => Some(col.name.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    let set_union_columns: Vec<&str> = columns
+        .iter()
+        .filter_map(|col| match col.action {
+            BatchOperationAction::SetUnion => Some(col.name.as_str()),
+            _ => None,
+        })
+        .collect();
+
     let where_columns: Vec<&str> = columns
         .iter()
         .filter_map(|col| match col.action {
@@ -154,13 +195,18 @@ async fn execute_batch(
     query.push(')');
 
     // Add to_process CTE
-    // When arithmetic columns exist (add/subtract/max/min), use GROUP BY with
-    // aggregations instead of DISTINCT ON. This fixes duplicate-key accumulation
-    // within a single batch (GitHub #383).
+    // When arithmetic columns exist (add/subtract/max/min/first/avg and the array
+    // actions), use GROUP BY with aggregations instead of DISTINCT ON. This fixes
+    // duplicate-key accumulation within a single batch (GitHub #383).
     let has_arithmetic = !add_columns.is_empty()
         || !subtract_columns.is_empty()
         || !max_columns.is_empty()
-        || !min_columns.is_empty();
+        || !min_columns.is_empty()
+        || !first_columns.is_empty()
+        || !avg_columns.is_empty()
+        || !append_columns.is_empty()
+        || !remove_columns.is_empty()
+        || !set_union_columns.is_empty();
 
     if has_arithmetic && !distinct_cols.is_empty() {
         let agg_columns: Vec<(&str, ColumnAggregate)> = columns
@@ -177,6 +223,14 @@ async fn execute_batch(
                     ColumnAggregate::Max
                 } else if min_columns.contains(&name) {
                     ColumnAggregate::Min
+                } else if first_columns.contains(&name) {
+                    ColumnAggregate::FirstBySeq
+                } else if avg_columns.contains(&name) {
+                    ColumnAggregate::Avg
+                } else if append_columns.contains(&name) || remove_columns.contains(&name) {
+                    ColumnAggregate::ArrayAgg
+                } else if set_union_columns.contains(&name) {
+                    ColumnAggregate::DistinctArrayAgg
                 } else if set_columns.contains(&name) {
                     ColumnAggregate::LastBySeq
                 } else {
@@ -232,6 +286,38 @@ async fn execute_batch(
                 all_set_clauses.push(build_set_clause(&col_info, SetClauseType::Min));
             }
 
+            for col_name in &first_columns {
+                let column_def = columns.iter().find(|c| c.name == *col_name).unwrap();
+                let col_info =
+                    ColumnInfo { name: col_name, table_column: column_def.table_column.as_deref() };
+                all_set_clauses.push(build_set_clause(&col_info, SetClauseType::First));
+            }
+
+            for col_name in &avg_columns {
+                let column_def = columns.iter().find(|c| c.name == *col_name).unwrap();
+                let col_info =
+                    ColumnInfo { name: col_name, table_column: column_def.table_column.as_deref() };
+                all_set_clauses.push(build_avg_set_clause(
+                    &col_info,
+                    &injected_columns::avg_samples(col_name),
+                ));
+            }
+
+            for (array_columns, clause_type) in [
+                (&append_columns, SetClauseType::Append),
+                (&remove_columns, SetClauseType::Remove),
+                (&set_union_columns, SetClauseType::SetUnion),
+            ] {
+                for col_name in array_columns.iter() {
+                    let column_def = columns.iter().find(|c| c.name == *col_name).unwrap();
+                    let col_info = ColumnInfo {
+                        name: col_name,
+                        table_column: column_def.table_column.as_deref(),
+                    };
+                    all_set_clauses.push(build_set_clause(&col_info, clause_type));
+                }
+            }
+
             query.push_str(&build_update_body(&formatted_table_name, all_set_clauses));
         }
         BatchOperationType::Delete => {
@@ -343,6 +429,42 @@ async fn execute_batch(
                 }
             }
 
+            for col in &first_columns {
+                if !where_columns.contains(col) && !distinct_cols.contains(col) {
+                    update_clauses.push(build_upsert_set_clause(
+                        col,
+                        &formatted_table_name,
+                        UpsertClauseType::First,
+                    ));
+                }
+            }
+
+            for col in &avg_columns {
+                if !where_columns.contains(col) && !distinct_cols.contains(col) {
+                    update_clauses.push(build_upsert_avg_clause(
+                        col,
+                        &injected_columns::avg_samples(col),
+                        &formatted_table_name,
+                    ));
+                }
+            }
+
+            // `remove` is limited to update operations by manifest validation
+            for (array_columns, clause_type) in [
+                (&append_columns, UpsertClauseType::Append),
+                (&set_union_columns, UpsertClauseType::SetUnion),
+            ] {
+                for col in array_columns.iter() {
+                    if !where_columns.contains(col) && !distinct_cols.contains(col) {
+                        update_clauses.push(build_upsert_set_clause(
+                            col,
+                            &formatted_table_name,
+                            clause_type,
+                        ));
+                    }
+                }
+            }
+
             query.push_str(&build_upsert_body(
                 &formatted_table_name,
                 &column_names,
@@ -83,6 +83,14 @@ pub enum ColumnAggregate {
     Min,
     /// Take the value from the row with the highest sequence (for set action).
     LastBySeq,
+    /// Take the value from the row with the lowest sequence (for first action).
+    FirstBySeq,
+    /// Average the values (for avg action, weighted by its samples column on conflict).
+    Avg,
+    /// Collect the values into an array in sequence order (for append/remove actions).
+    ArrayAgg,
+    /// Collect the distinct values into an array (for set_union action).
+    DistinctArrayAgg,
 }
 
 /// Builds the `to_process` CTE with GROUP BY and aggregations for arithmetic columns.
@@ -94,6 +102,10 @@ pub enum ColumnAggregate {
 /// - `max` columns → `MAX()`
 /// - `min` columns → `MIN()`
 /// - `set` columns → last value by sequence (`(array_agg(col ORDER BY seq DESC))[1]`)
+/// - `first` columns → first value by sequence (`(array_agg(col ORDER BY seq ASC))[1]`)
+/// - `avg` columns → `AVG()`
+/// - `append`/`remove` columns → `array_agg()` in sequence order
+/// - `set_union` columns → `array_agg(DISTINCT ...)`
 /// - sequence column → `MAX()` (latest sequence per group)
 pub fn build_to_process_cte_aggregated(
     columns: &[(&str, ColumnAggregate)],
@@ -134,6 +146,28 @@ pub fn build_to_process_cte_aggregated(
                         format!("MAX({}) AS {}", qname, qname)
                     }
                 }
+                ColumnAggregate::FirstBySeq => {
+                    if let Some(ref seq) = quoted_seq {
+                        format!("(array_agg({} ORDER BY {} ASC))[1] AS {}", qname, seq, qname)
+                    } else {
+                        format!("MIN({}) AS {}", qname, qname)
+                    }
+                }
+                ColumnAggregate::Avg => format!("AVG({}) AS {}", qname, qname),
+                ColumnAggregate::ArrayAgg => {
+                    let order_by = quoted_seq
+                        .as_ref()
+                        .map(|seq| format!(" ORDER BY {}", seq))
+                        .unwrap_or_default();
+                    format!(
+                        "COALESCE(array_agg({}{}) FILTER (WHERE {} IS NOT NULL), '{{}}') AS {}",
+                        qname, order_by, qname, qname
+                    )
+                }
+                ColumnAggregate::DistinctArrayAgg => format!(
+                    "COALESCE(array_agg(DISTINCT {}) FILTER (WHERE {} IS NOT NULL), '{{}}') AS {}",
+                    qname, qname, qname
+                ),
             }
         })
         .collect();
@@ -170,16 +204,60 @@ pub fn build_set_clause(column: &ColumnInfo, clause_type: SetClauseType) -> Stri
         SetClauseType::Subtract => format!("{} = am.{} - {}", column_name, column_name, tp_col),
         SetClauseType::Max => format!("{} = GREATEST(am.{}, {})", column_name, column_name, tp_col),
         SetClauseType::Min => format!("{} = LEAST(am.{}, {})", column_name, column_name, tp_col),
+        SetClauseType::First => {
+            format!("{} = COALESCE(am.{}, {})", column_name, column_name, tp_col)
+        }
+        SetClauseType::Append => {
+            format!("{} = COALESCE(am.{}, '{{}}') || {}", column_name, column_name, tp_col)
+        }
+        SetClauseType::Remove => format!(
+            "{} = ARRAY(SELECT elem FROM unnest(am.{}) AS elem WHERE elem <> ALL({}))",
+            column_name, column_name, tp_col
+        ),
+        SetClauseType::SetUnion => {
+            format!("{} = {}", column_name, array_union(&format!("am.{}", column_name), &tp_col))
+        }
     }
 }
 
+/// `existing` followed by the elements of `incoming` it doesn't contain yet.
+fn array_union(existing: &str, incoming: &str) -> String {
+    format!(
+        "COALESCE({existing}, '{{}}') || ARRAY(SELECT elem FROM unnest({incoming}) AS elem \
+         WHERE elem <> ALL(COALESCE({existing}, '{{}}')))",
+        existing = existing,
+        incoming = incoming,
+    )
+}
+
+/// Builds the SET clause folding `tp` averages into the stored average for UPDATE
+/// operations. See `build_upsert_avg_clause` for the weighting.
+pub fn build_avg_set_clause(column: &ColumnInfo, samples_col: &str) -> String {
+    let column_name = quote_identifier(column.table_column.unwrap_or(column.name));
+    let tp_col = format!("tp.{}", quote_identifier(column.name));
+    let samples_name = quote_identifier(samples_col);
+
+    format!(
+        "{col} = (COALESCE(am.{col}, 0) * COALESCE(am.{samples}, 0) + {tp} * tp.{samples}) \
+         / NULLIF(COALESCE(am.{samples}, 0) + tp.{samples}, 0)",
+        col = column_name,
+        tp = tp_col,
+        samples = samples_name,
+    )
+}
+
 /// Type of SET clause to generate.
+#[derive(Clone, Copy)]
 pub enum SetClauseType {
     Set,
     Add,
     Subtract,
     Max,
     Min,
+    First,
+    Append,
+    Remove,
+    SetUnion,
 }
 
 /// Builds the UPDATE statement body.
@@ -326,9 +404,47 @@ pub fn build_upsert_set_clause(
                 column_name, formatted_table_name, column_name, column_name, column_name
             )
         }
+        UpsertClauseType::First => {
+            format!(
+                "{} = COALESCE({}.{}, EXCLUDED.{})",
+                column_name, formatted_table_name, column_name, column_name
+            )
+        }
+        UpsertClauseType::Append => {
+            format!(
+                "{} = COALESCE({}.{}, '{{}}') || EXCLUDED.{}",
+                column_name, formatted_table_name, column_name, column_name
+            )
+        }
+        UpsertClauseType::SetUnion => format!(
+            "{} = {}",
+            column_name,
+            array_union(
+                &format!("{}.{}", formatted_table_name, column_name),
+                &format!("EXCLUDED.{}", column_name)
+            )
+        ),
     }
 }
 
+/// Builds an upsert SET clause folding the incoming average into the stored one.
+/// Both sides are weighted by their sample counts; the samples column itself is
+/// accumulated by a regular `Add` clause. Postgres evaluates every SET expression
+/// against the old row, so `samples_col` still holds the previous count here.
+pub fn build_upsert_avg_clause(col: &str, samples_col: &str, formatted_table_name: &str) -> String {
+    let column_name = quote_identifier(col);
+    let samples_name = quote_identifier(samples_col);
+
+    format!(
+        "{col} = (COALESCE({table}.{col}, 0) * COALESCE({table}.{samples}, 0) \
+         + EXCLUDED.{col} * EXCLUDED.{samples}) \
+         / NULLIF(COALESCE({table}.{samples}, 0) + EXCLUDED.{samples}, 0)",
+        col = column_name,
+        samples = samples_name,
+        table = formatted_table_name,
+    )
+}
+
 /// Builds an upsert SET clause that keeps the latest value by sequence while
 /// allowing arithmetic columns in the same upsert to accumulate regardless of
 /// processing order.
@@ -353,12 +469,16 @@ pub fn build_upsert_set_clause_latest_by_sequence(
 }
 
 /// Type of upsert SET clause to generate.
+#[derive(Clone, Copy)]
 pub enum UpsertClauseType {
     Set,
     Add,
     Subtract,
     Max,
     Min,
+    First,
+    Append,
+    SetUnion,
 }
 
 /// Builds WHERE conditions for UPDATE/DELETE operations.
@@ -493,7 +493,8 @@ impl PostgresClient {
     /// 3. Insert corrected reorg_block_hashes entries (marking reorg as handled)
     /// 4. Rewind checkpoint cursors
     ///
-    /// Returns `(total_rows_deleted, affected_tx_hashes)`.
+    /// Returns `(rows_deleted, affected_tx_hashes)` with one row count per event table, in
+    /// the order of `event_table_names`.
     pub async fn reorg_rollback_transaction(
         &self,
         event_table_names: &[&str],
@@ -502,7 +503,7 @@ impl PostgresClient {
         detection_point: u64,
         corrected_blocks: &[(u64, &str, &str)], // (block_number, block_hash, parent_hash)
         checkpoint_tables: &[&str],
-    ) -> Result<(u64, Vec<String>), PostgresError> {
+    ) -> Result<(Vec<u64>, Vec<String>), PostgresError> {
         let mut conn = self.pool.get().await?;
         let transaction = conn.transaction().await?;
 
@@ -512,7 +513,7 @@ impl PostgresClient {
             .map_err(|_| PostgresError::Custom("detection_point exceeds i64 range".to_string()))?;
         let fork_point_decimal = Decimal::from(fork_point);
         let detection_point_decimal = Decimal::from(detection_point);
-        let mut total_deleted: u64 = 0;
+        let mut rows_deleted: Vec<u64> = Vec::with_capacity(event_table_names.len());
         let mut all_affected_tx_hashes: Vec<String> = Vec::new();
 
         // 1. Delete stale events and collect affected tx hashes in one round-trip per table
@@ -524,7 +525,7 @@ impl PostgresClient {
             let rows = transaction
                 .query(&query, &[&network, &fork_point_decimal, &detection_point_decimal])
                 .await?;
-            total_deleted += rows.len() as u64;
+            rows_deleted.push(rows.len() as u64);
             let hashes: Vec<String> = rows.iter().map(|r| r.get::<_, String>("tx_hash")).collect();
             all_affected_tx_hashes.extend(hashes);
         }
@@ -564,6 +565,6 @@ impl PostgresClient {
 
         transaction.commit().await?;
 
-        Ok((total_deleted, all_affected_tx_hashes))
+        Ok((rows_deleted, all_affected_tx_hashes))
     }
 }
@@ -46,6 +46,61 @@ pub fn generate_reorg_block_hashes_table_sql() -> String {
     .to_string()
 }
 
+pub fn generate_reorg_events_table_sql() -> String {
+    r#"CREATE TABLE IF NOT EXISTS rindexer_internal.reorg_events (
+        id BIGSERIAL PRIMARY KEY,
+        network TEXT NOT NULL,
+        fork_block BIGINT NOT NULL,
+        detection_block BIGINT NOT NULL,
+        depth BIGINT NOT NULL,
+        detection_source TEXT NOT NULL,
+        events_deleted BIGINT NOT NULL,
+        affected_tables JSONB NOT NULL,
+        duration_secs DOUBLE PRECISION NOT NULL,
+        handled_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+    );
+    CREATE INDEX IF NOT EXISTS idx_reorg_events_network_handled_at
+        ON rindexer_internal.reorg_events (network, handled_at);"#
+        .to_string()
+}
+
+pub fn generate_block_range_state_table_sql() -> String {
+    r#"CREATE TABLE IF NOT EXISTS rindexer_internal.block_range_state (
+        network TEXT NOT NULL,
+        provider TEXT NOT NULL,
+        event TEXT NOT NULL,
+        block_range BIGINT,
+        provider_limit BIGINT,
+        logs_per_block DOUBLE PRECISION NOT NULL,
+        latency_ms DOUBLE PRECISION NOT NULL,
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        PRIMARY KEY (network, provider, event)
+    );"#
+    .to_string()
+}
+
+pub fn generate_contract_deployment_blocks_table_sql() -> String {
+    r#"CREATE TABLE IF NOT EXISTS rindexer_internal.contract_deployment_blocks (
+        network TEXT NOT NULL,
+        address CHAR(42) NOT NULL,
+        block_number BIGINT NOT NULL,
+        PRIMARY KEY (network, address)
+    );"#
+    .to_string()
+}
+
+pub fn generate_token_metadata_table_sql() -> String {
+    r#"CREATE TABLE IF NOT EXISTS rindexer_internal.token_metadata (
+        network TEXT NOT NULL,
+        address CHAR(42) NOT NULL,
+        decimals SMALLINT,
+        symbol TEXT,
+        name TEXT,
+        PRIMARY KEY (network, address)
+    );"#
+    .to_string()
+}
+
 pub fn generate_derived_op_log_table_sql() -> String {
     r#"CREATE TABLE IF NOT EXISTS rindexer_internal.derived_op_log (
         id BIGSERIAL PRIMARY KEY,
@@ -56,8 +111,10 @@ pub fn generate_derived_op_log_table_sql() -> String {
         value NUMERIC NOT NULL,
         block_number BIGINT NOT NULL,
         tx_index INTEGER NOT NULL,
-        log_index INTEGER NOT NULL
+        log_index INTEGER NOT NULL,
+        value_text TEXT
     );
+    ALTER TABLE rindexer_internal.derived_op_log ADD COLUMN IF NOT EXISTS value_text TEXT;
     CREATE INDEX IF NOT EXISTS idx_derived_op_log_reorg
         ON rindexer_internal.derived_op_log (derived_table, column_name, where_key, block_number);"#
         .to_string()
@@ -5,10 +5,16 @@
 
 use std::collections::{HashMap, HashSet};
 
-use crate::database::generate::generate_indexer_contract_schema_name;
+use tracing::warn;
+
+use crate::database::generate::{
+    generate_history_table_full_name, generate_indexer_contract_schema_name,
+    generate_partition_key_sql, generate_table_index_comment_sql, generate_table_index_definition,
+    generate_table_index_sql, TABLE_INDEX_COMMENT_PREFIX,
+};
 use crate::database::postgres::client::PostgresClient;
 use crate::helpers::camel_to_snake;
-use crate::manifest::contract::{injected_columns, Table};
+use crate::manifest::contract::{injected_columns, Table, TableIndex};
 use crate::manifest::core::Manifest;
 
 /// Represents a detected schema change that may need to be applied.
@@ -36,12 +42,37 @@ pub enum SchemaChange {
         current_type: String,
         new_type: String,
     },
+    /// An index was added to the YAML.
+    AddIndex { table_full_name: String, table_name: String, index: TableIndex, partitioned: bool },
+    /// An index created by rindexer is no longer in the YAML.
+    DropIndex { table_full_name: String, index_name: String, partitioned: bool },
+    /// The columns or condition of an index have changed.
+    ChangeIndex {
+        table_full_name: String,
+        table_name: String,
+        index: TableIndex,
+        current_definition: String,
+        partitioned: bool,
+    },
+    /// Partitioning has changed (requires manual migration).
+    ChangePartition {
+        table_full_name: String,
+        current_partition: Option<String>,
+        new_partition: Option<String>,
+    },
 }
 
 impl SchemaChange {
     /// Returns true if this change is safe to auto-apply without user confirmation.
+    /// Index changes never touch data, they only rebuild indexes.
     pub fn is_safe(&self) -> bool {
-        matches!(self, SchemaChange::AddColumn { .. })
+        matches!(
+            self,
+            SchemaChange::AddColumn { .. }
+                | SchemaChange::AddIndex { .. }
+                | SchemaChange::DropIndex { .. }
+                | SchemaChange::ChangeIndex { .. }
+        )
     }
 
     /// Returns a human-readable description of the change.
@@ -91,6 +122,43 @@ impl SchemaChange {
                     column_name, table_full_name, current_type, new_type
                 )
             }
+            SchemaChange::AddIndex { table_full_name, table_name, index, .. } => {
+                format!(
+                    "Add index '{}' {} to table '{}'",
+                    index.index_name(table_name),
+                    generate_table_index_definition(index),
+                    table_full_name
+                )
+            }
+            SchemaChange::DropIndex { table_full_name, index_name, .. } => {
+                format!(
+                    "Drop index '{}' from table '{}' as it is no longer in YAML",
+                    index_name, table_full_name
+                )
+            }
+            SchemaChange::ChangeIndex {
+                table_full_name,
+                table_name,
+                index,
+                current_definition,
+                ..
+            } => {
+                format!(
+                    "Index '{}' changed in table '{}': {} -> {}",
+                    index.index_name(table_name),
+                    table_full_name,
+                    current_definition,
+                    generate_table_index_definition(index)
+                )
+            }
+            SchemaChange::ChangePartition { table_full_name, current_partition, new_partition } => {
+                format!(
+                    "Partitioning changed for table '{}': {} -> {}",
+                    table_full_name,
+                    current_partition.as_deref().unwrap_or("none"),
+                    new_partition.as_deref().unwrap_or("none")
+                )
+            }
         }
     }
 }
@@ -170,6 +238,74 @@ async fn get_primary_key_columns(
     Ok(Some(DbPrimaryKey { columns }))
 }
 
+/// Information about a secondary index in the database.
+#[derive(Debug, Clone)]
+struct DbIndex {
+    name: String,
+    /// The index comment, rindexer stores the definition of the indexes it manages there
+    comment: Option<String>,
+}
+
+impl DbIndex {
+    /// The definition of an index created by rindexer, None for indexes created by users.
+    fn managed_definition(&self) -> Option<&str> {
+        self.comment.as_deref()?.strip_prefix(TABLE_INDEX_COMMENT_PREFIX)
+    }
+}
+
+/// Queries the secondary (non primary key) indexes of a table from PostgreSQL.
+async fn get_existing_indexes(
+    client: &PostgresClient,
+    schema_name: &str,
+    table_name: &str,
+) -> Result<Vec<DbIndex>, String> {
+    let query = r#"
+        SELECT i.relname::TEXT, obj_description(i.oid, 'pg_class')
+        FROM pg_index x
+        JOIN pg_class i ON i.oid = x.indexrelid
+        JOIN pg_class t ON t.oid = x.indrelid
+        JOIN pg_namespace n ON n.oid = t.relnamespace
+        WHERE n.nspname = $1 AND t.relname = $2 AND NOT x.indisprimary
+    "#;
+
+    let rows = client
+        .query(query, &[&schema_name, &table_name])
+        .await
+        .map_err(|e| format!("Failed to query indexes: {}", e))?;
+
+    let indexes = rows
+        .iter()
+        .map(|row| {
+            let name: String = row.get(0);
+            let comment: Option<String> = row.get(1);
+            DbIndex { name, comment }
+        })
+        .collect();
+
+    Ok(indexes)
+}
+
+/// Queries the partition key of a table from PostgreSQL, None if it isn't partitioned.
+async fn get_partition_key(
+    client: &PostgresClient,
+    schema_name: &str,
+    table_name: &str,
+) -> Result<Option<String>, String> {
+    let query = r#"
+        SELECT pg_get_partkeydef(c.oid)
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE n.nspname = $1 AND c.relname = $2
+    "#;
+
+    let rows = client
+        .query(query, &[&schema_name, &table_name])
+        .await
+        .map_err(|e| format!("Failed to query partition key: {}", e))?;
+
+    Ok(rows.first().and_then(|row| row.get::<_, Option<String>>(0)))
+}
+
 /// Checks if a table exists in the database.
 async fn table_exists(
     client: &PostgresClient,
@@ -223,6 +359,8 @@ fn get_expected_columns(table: &Table) -> HashMap<String, String> {
                 "char(42)" => "character",
                 "char(66)" => "character",
                 "bytea" => "bytea",
+                // decimal(S) columns, information_schema doesn't include the precision
+                t if t.starts_with("numeric(") => "numeric",
                 _ => &pg_type,
             }
             .to_string()
@@ -271,6 +409,11 @@ fn get_expected_pk_columns(table: &Table) -> Vec<String> {
         }
     }
 
+    // The partition key is part of the primary key of partitioned tables
+    if table.partition_by.as_ref().and_then(|p| p.block_range()).is_some() {
+        pk_columns.push(injected_columns::BLOCK_NUMBER.to_string());
+    }
+
     pk_columns
 }
 
@@ -298,73 +441,8 @@ pub async fn detect_schema_changes(
                 continue;
             }
 
-            // Get existing columns
             let existing_columns = get_existing_columns(client, &schema_name, &table_name).await?;
-            let existing_col_names: HashSet<String> =
-                existing_columns.iter().map(|c| c.name.clone()).collect();
-            let existing_col_types: HashMap<String, String> =
-                existing_columns.iter().map(|c| (c.name.clone(), c.data_type.clone())).collect();
-
-            // Get expected columns from YAML
-            let expected_columns = get_expected_columns(table);
-            let expected_col_names: HashSet<String> = expected_columns.keys().cloned().collect();
-
-            // Find new columns (in YAML but not in DB)
-            for col_name in expected_col_names.difference(&existing_col_names) {
-                let col_type = expected_columns.get(col_name).unwrap();
-
-                // Skip columns with unknown types (not yet resolved from ABI)
-                // These will be created when the table is set up with resolved types
-                if col_type == "unknown" {
-                    continue;
-                }
-
-                // Convert back to PostgreSQL CREATE type format
-                let pg_type = match col_type.as_str() {
-                    "character varying" => "VARCHAR(50)",
-                    "character" => {
-                        // Determine length based on column name
-                        if col_name == injected_columns::TX_HASH
-                            || col_name == injected_columns::BLOCK_HASH
-                        {
-                            "CHAR(66)"
-                        } else if col_name == injected_columns::CONTRACT_ADDRESS {
-                            "CHAR(42)"
-                        } else {
-                            "TEXT"
-                        }
-                    }
-                    "timestamp with time zone" => "TIMESTAMPTZ",
-                    other => other,
-                };
-
-                // Look up the default value from the column definition
-                let default_value = table
-                    .columns
-                    .iter()
-                    .find(|c| &c.name == col_name)
-                    .and_then(|c| c.default.clone());
-
-                changes.push(SchemaChange::AddColumn {
-                    table_full_name: table_full_name.clone(),
-                    column_name: col_name.clone(),
-                    column_type: pg_type.to_uppercase(),
-                    default_value,
-                });
-            }
-
-            // Find removed columns (in DB but not in YAML)
-            // Exclude internal columns that are auto-managed
-            let internal_columns: HashSet<&str> = ["rindexer_id"].into_iter().collect();
-
-            for col_name in existing_col_names.difference(&expected_col_names) {
-                if !internal_columns.contains(col_name.as_str()) {
-                    changes.push(SchemaChange::RemoveColumn {
-                        table_full_name: table_full_name.clone(),
-                        column_name: col_name.clone(),
-                    });
-                }
-            }
+            changes.extend(detect_column_changes(&table_full_name, table, &existing_columns));
 
             // Check for primary key changes
             if let Some(current_pk) =
@@ -381,38 +459,187 @@ pub async fn detect_schema_changes(
                 }
             }
 
-            // Check for type changes (only for user-defined columns with resolved types)
-            for column in &table.columns {
-                // Skip type comparison if column type isn't resolved yet
-                if column.column_type.is_none() {
-                    continue;
-                }
+            // Check for partitioning changes
+            let current_partition = get_partition_key(client, &schema_name, &table_name).await?;
+            let expected_partition = table.partition_by.as_ref().map(generate_partition_key_sql);
+            if current_partition != expected_partition {
+                changes.push(SchemaChange::ChangePartition {
+                    table_full_name: table_full_name.clone(),
+                    current_partition: current_partition.clone(),
+                    new_partition: expected_partition,
+                });
+            }
+            let partitioned = current_partition.is_some();
+
+            let existing_indexes = get_existing_indexes(client, &schema_name, &table_name).await?;
+            changes.extend(detect_index_changes(
+                &table_full_name,
+                table,
+                &existing_indexes,
+                partitioned,
+            ));
+        }
+    }
+
+    Ok(changes)
+}
+
+/// Column changes between the YAML definition of an existing table and its columns in the
+/// database: added and removed columns and changed types.
+fn detect_column_changes(
+    table_full_name: &str,
+    table: &Table,
+    existing_columns: &[DbColumn],
+) -> Vec<SchemaChange> {
+    let mut changes: Vec<SchemaChange> = vec![];
+
+    let existing_col_names: HashSet<String> =
+        existing_columns.iter().map(|c| c.name.clone()).collect();
+    let existing_col_types: HashMap<String, String> =
+        existing_columns.iter().map(|c| (c.name.clone(), c.data_type.clone())).collect();
+
+    // Get expected columns from YAML
+    let expected_columns = get_expected_columns(table);
+    let expected_col_names: HashSet<String> = expected_columns.keys().cloned().collect();
 
-                if let Some(existing_type) = existing_col_types.get(&column.name) {
-                    let expected_type = expected_columns.get(&column.name).unwrap();
-                    // Skip if expected type is unknown (not resolved)
-                    if expected_type == "unknown" {
-                        continue;
-                    }
-
-                    // Normalize types for comparison
-                    let normalized_existing = normalize_pg_type(existing_type);
-                    let normalized_expected = normalize_pg_type(expected_type);
-
-                    if normalized_existing != normalized_expected {
-                        changes.push(SchemaChange::ColumnTypeChanged {
-                            table_full_name: table_full_name.clone(),
-                            column_name: column.name.clone(),
-                            current_type: existing_type.clone(),
-                            new_type: expected_type.clone(),
-                        });
-                    }
+    // Find new columns (in YAML but not in DB)
+    for col_name in expected_col_names.difference(&existing_col_names) {
+        let col_type = expected_columns.get(col_name).unwrap();
+
+        // Skip columns with unknown types (not yet resolved from ABI)
+        // These will be created when the table is set up with resolved types
+        if col_type == "unknown" {
+            continue;
+        }
+
+        // Convert back to PostgreSQL CREATE type format
+        let pg_type = match col_type.as_str() {
+            "character varying" => "VARCHAR(50)",
+            "character" => {
+                // Determine length based on column name
+                if col_name == injected_columns::TX_HASH || col_name == injected_columns::BLOCK_HASH
+                {
+                    "CHAR(66)"
+                } else if col_name == injected_columns::CONTRACT_ADDRESS {
+                    "CHAR(42)"
+                } else {
+                    "TEXT"
                 }
             }
+            "timestamp with time zone" => "TIMESTAMPTZ",
+            other => other,
+        };
+
+        // Look up the default value from the column definition
+        let default_value =
+            table.columns.iter().find(|c| &c.name == col_name).and_then(|c| c.default.clone());
+
+        changes.push(SchemaChange::AddColumn {
+            table_full_name: table_full_name.to_string(),
+            column_name: col_name.clone(),
+            column_type: pg_type.to_uppercase(),
+            default_value,
+        });
+    }
+
+    // Find removed columns (in DB but not in YAML)
+    // Exclude internal columns that are auto-managed
+    let internal_columns: HashSet<&str> = ["rindexer_id"].into_iter().collect();
+
+    for col_name in existing_col_names.difference(&expected_col_names) {
+        if !internal_columns.contains(col_name.as_str()) {
+            changes.push(SchemaChange::RemoveColumn {
+                table_full_name: table_full_name.to_string(),
+                column_name: col_name.clone(),
+            });
         }
     }
 
-    Ok(changes)
+    // Check for type changes (only for user-defined columns with resolved types)
+    for column in &table.columns {
+        // Skip type comparison if column type isn't resolved yet
+        if column.column_type.is_none() {
+            continue;
+        }
+
+        if let Some(existing_type) = existing_col_types.get(&column.name) {
+            let expected_type = expected_columns.get(&column.name).unwrap();
+            // Skip if expected type is unknown (not resolved)
+            if expected_type == "unknown" {
+                continue;
+            }
+
+            // Normalize types for comparison
+            let normalized_existing = normalize_pg_type(existing_type);
+            let normalized_expected = normalize_pg_type(expected_type);
+
+            if normalized_existing != normalized_expected {
+                changes.push(SchemaChange::ColumnTypeChanged {
+                    table_full_name: table_full_name.to_string(),
+                    column_name: column.name.clone(),
+                    current_type: existing_type.clone(),
+                    new_type: expected_type.clone(),
+                });
+            }
+        }
+    }
+
+    changes
+}
+
+/// Index changes between the YAML definition of an existing table and its indexes in the
+/// database. Indexes rindexer created carry their definition in a comment, other indexes
+/// were created by users and are never changed or dropped.
+fn detect_index_changes(
+    table_full_name: &str,
+    table: &Table,
+    existing_indexes: &[DbIndex],
+    partitioned: bool,
+) -> Vec<SchemaChange> {
+    let mut changes: Vec<SchemaChange> = vec![];
+
+    for index in &table.indexes {
+        let index_name = index.index_name(&table.name);
+        let definition = generate_table_index_definition(index);
+        let Some(existing) = existing_indexes.iter().find(|i| i.name == index_name) else {
+            changes.push(SchemaChange::AddIndex {
+                table_full_name: table_full_name.to_string(),
+                table_name: table.name.clone(),
+                index: index.clone(),
+                partitioned,
+            });
+            continue;
+        };
+
+        match existing.managed_definition() {
+            None => warn!(
+                "Index '{}' of table '{}' was not created by rindexer, leaving it as it is",
+                index_name, table_full_name
+            ),
+            Some(current) if current != definition => changes.push(SchemaChange::ChangeIndex {
+                table_full_name: table_full_name.to_string(),
+                table_name: table.name.clone(),
+                index: index.clone(),
+                current_definition: current.to_string(),
+                partitioned,
+            }),
+            Some(_) => {}
+        }
+    }
+
+    let expected_indexes: HashSet<String> =
+        table.indexes.iter().map(|i| i.index_name(&table.name)).collect();
+    for existing in existing_indexes {
+        if existing.managed_definition().is_some() && !expected_indexes.contains(&existing.name) {
+            changes.push(SchemaChange::DropIndex {
+                table_full_name: table_full_name.to_string(),
+                index_name: existing.name.clone(),
+                partitioned,
+            });
+        }
+    }
+
+    changes
 }
 
 /// Normalize PostgreSQL type names for comparison.
@@ -490,6 +717,19 @@ pub async fn apply_schema_change(
                     .batch_execute(&sql)
                     .await
                     .map_err(|e| format!("Failed to add column: {}", e))?;
+
+                // Keep the row versions of `history: true` tables in step
+                let history_sql = format!(
+                    "ALTER TABLE IF EXISTS {} ADD COLUMN IF NOT EXISTS \"{}\" {} {}",
+                    generate_history_table_full_name(table_full_name),
+                    column_name,
+                    column_type,
+                    default_clause
+                );
+                client
+                    .batch_execute(&history_sql)
+                    .await
+                    .map_err(|e| format!("Failed to add history column: {}", e))?;
             }
         }
         SchemaChange::RemoveColumn { table_full_name, column_name } => {
@@ -499,6 +739,16 @@ pub async fn apply_schema_change(
                 .batch_execute(&sql)
                 .await
                 .map_err(|e| format!("Failed to remove column: {}", e))?;
+
+            let history_sql = format!(
+                "ALTER TABLE IF EXISTS {} DROP COLUMN IF EXISTS \"{}\"",
+                generate_history_table_full_name(table_full_name),
+                column_name
+            );
+            client
+                .batch_execute(&history_sql)
+                .await
+                .map_err(|e| format!("Failed to remove history column: {}", e))?;
         }
         SchemaChange::ChangePrimaryKey {
             table_full_name,
@@ -556,7 +806,196 @@ pub async fn apply_schema_change(
                 "Column type changes require manual migration. Please backup your data and recreate the table.".to_string(),
             );
         }
+        SchemaChange::AddIndex { table_full_name, table_name, index, partitioned } => {
+            create_index(client, table_full_name, table_name, index, *partitioned).await?;
+        }
+        SchemaChange::DropIndex { table_full_name, index_name, partitioned } => {
+            drop_index(client, table_full_name, index_name, *partitioned).await?;
+        }
+        SchemaChange::ChangeIndex { table_full_name, table_name, index, partitioned, .. } => {
+            drop_index(client, table_full_name, &index.index_name(table_name), *partitioned)
+                .await?;
+            create_index(client, table_full_name, table_name, index, *partitioned).await?;
+        }
+        SchemaChange::ChangePartition { .. } => {
+            // Rows can't be moved into a different partitioning in place
+            return Err(
+                "Partitioning changes require manual migration. Please backup your data and recreate the table.".to_string(),
+            );
+        }
     }
 
     Ok(())
 }
+
+/// Creates an index without blocking writes. Partitioned tables don't support
+/// `CONCURRENTLY` and lock while the index is built on every partition.
+async fn create_index(
+    client: &PostgresClient,
+    table_full_name: &str,
+    table_name: &str,
+    index: &TableIndex,
+    partitioned: bool,
+) -> Result<(), String> {
+    let create_sql = generate_table_index_sql(table_full_name, table_name, index, !partitioned);
+    client
+        .batch_execute(&create_sql)
+        .await
+        .map_err(|e| format!("Failed to create index: {}", e))?;
+
+    let comment_sql = generate_table_index_comment_sql(table_full_name, table_name, index);
+    client
+        .batch_execute(&comment_sql)
+        .await
+        .map_err(|e| format!("Failed to comment index: {}", e))?;
+
+    Ok(())
+}
+
+/// Drops an index of a table without blocking writes unless the table is partitioned.
+async fn drop_index(
+    client: &PostgresClient,
+    table_full_name: &str,
+    index_name: &str,
+    partitioned: bool,
+) -> Result<(), String> {
+    let schema_name = table_full_name.split('.').next().unwrap_or(table_full_name);
+    let sql = format!(
+        "DROP INDEX {}IF EXISTS {}.{}",
+        if partitioned { "" } else { "CONCURRENTLY " },
+        schema_name,
+        index_name
+    );
+
+    client.batch_execute(&sql).await.map_err(|e| format!("Failed to drop index: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pools_table(indexes: &str) -> Table {
+        serde_yaml::from_str(&format!(
+            r#"
+            name: pools
+            columns:
+              - name: pool
+                type: address
+              - name: volume
+                type: uint256
+            events:
+              - event: Swap
+                operations:
+                  - type: upsert
+                    where:
+                      pool: $pool
+                    set:
+                      - column: volume
+                        action: add
+                        value: $amount
+            {indexes}
+            "#
+        ))
+        .unwrap()
+    }
+
+    fn db_columns(table: &Table) -> Vec<DbColumn> {
+        get_expected_columns(table)
+            .into_iter()
+            .map(|(name, data_type)| DbColumn { name, data_type })
+            .collect()
+    }
+
+    fn managed_index(name: &str, definition: &str) -> DbIndex {
+        DbIndex {
+            name: name.to_string(),
+            comment: Some(format!("{}{}", TABLE_INDEX_COMMENT_PREFIX, definition)),
+        }
+    }
+
+    #[test]
+    fn test_detect_column_changes() {
+        let table = pools_table("");
+        assert!(detect_column_changes("s.pools", &table, &db_columns(&table)).is_empty());
+
+        let mut existing = db_columns(&table);
+        existing.retain(|c| c.name != "volume");
+        existing.push(DbColumn { name: "fees".to_string(), data_type: "numeric".to_string() });
+        let changes = detect_column_changes("s.pools", &table, &existing);
+        assert_eq!(changes.len(), 2);
+        assert!(changes.iter().any(|c| matches!(
+            c,
+            SchemaChange::AddColumn { column_name, column_type, .. }
+                if column_name == "volume" && column_type == "NUMERIC"
+        )));
+        assert!(changes.iter().any(|c| matches!(
+            c,
+            SchemaChange::RemoveColumn { column_name, .. } if column_name == "fees"
+        )));
+
+        let mut existing = db_columns(&table);
+        existing.iter_mut().find(|c| c.name == "volume").unwrap().data_type = "text".to_string();
+        let changes = detect_column_changes("s.pools", &table, &existing);
+        assert!(matches!(
+            changes.as_slice(),
+            [SchemaChange::ColumnTypeChanged { column_name, current_type, new_type, .. }]
+                if column_name == "volume" && current_type == "text" && new_type == "numeric"
+        ));
+    }
+
+    #[test]
+    fn test_detect_index_changes() {
+        let table = pools_table(
+            r#"indexes:
+              - columns: [volume]
+              - columns: [pool, volume]
+                name: big
+                where: volume > 1000"#,
+        );
+
+        let changes = detect_index_changes("s.pools", &table, &[], false);
+        assert_eq!(changes.len(), 2);
+        assert!(changes.iter().all(|c| matches!(c, SchemaChange::AddIndex { .. })));
+
+        let existing = vec![
+            managed_index("idx_pools_volume", "(\"volume\")"),
+            managed_index("idx_pools_big", "(\"pool\", \"volume\") WHERE volume > 1000"),
+        ];
+        assert!(detect_index_changes("s.pools", &table, &existing, false).is_empty());
+
+        let existing = vec![
+            managed_index("idx_pools_volume", "(\"volume\")"),
+            managed_index("idx_pools_big", "(\"pool\", \"volume\") WHERE volume > 10"),
+            managed_index("idx_pools_pool", "(\"pool\")"),
+        ];
+        let changes = detect_index_changes("s.pools", &table, &existing, true);
+        assert_eq!(changes.len(), 2);
+        assert!(changes.iter().any(|c| matches!(
+            c,
+            SchemaChange::ChangeIndex { current_definition, partitioned: true, .. }
+                if current_definition == "(\"pool\", \"volume\") WHERE volume > 10"
+        )));
+        assert!(changes.iter().any(|c| matches!(
+            c,
+            SchemaChange::DropIndex { index_name, partitioned: true, .. }
+                if index_name == "idx_pools_pool"
+        )));
+    }
+
+    #[test]
+    fn test_detect_index_changes_leaves_user_indexes_alone() {
+        let table = pools_table(
+            r#"indexes:
+              - columns: [volume]"#,
+        );
+        let existing = vec![
+            DbIndex { name: "idx_pools_volume".to_string(), comment: None },
+            DbIndex { name: "idx_pools_pool".to_string(), comment: None },
+            DbIndex {
+                name: "idx_pools_network".to_string(),
+                comment: Some("created by hand".to_string()),
+            },
+        ];
+        assert!(detect_index_changes("s.pools", &table, &existing, false).is_empty());
+    }
+}
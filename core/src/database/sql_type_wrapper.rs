@@ -1,7 +1,11 @@
 use std::str::FromStr;
 
 use crate::helpers::parse_solidity_integer_type;
-use crate::{abi::ABIInput, event::callback_registry::TxInformation, types::core::LogParam};
+use crate::{
+    abi::ABIInput,
+    event::{callback_registry::TxInformation, fixed_to_string},
+    types::core::LogParam,
+};
 #[allow(deprecated)]
 use alloy::{
     dyn_abi::DynSolValue,
@@ -92,6 +96,9 @@ pub enum EthereumSqlTypeWrapper {
     VecI256(Vec<I256>),
     VecI256Bytes(Vec<I256>),
 
+    // Fixed point numbers: unscaled value and scale, e.g. (1500, 3) is 1.500
+    DecimalNumeric(I256, u8),
+
     // 512-bit integers
     U512(U512),
     VecU512(Vec<U512>),
@@ -201,6 +208,9 @@ impl EthereumSqlTypeWrapper {
             EthereumSqlTypeWrapper::VecI256(_) => "VecI256",
             EthereumSqlTypeWrapper::VecI256Bytes(_) => "VecI256Bytes",
 
+            // Fixed point numbers
+            EthereumSqlTypeWrapper::DecimalNumeric(_, _) => "DecimalNumeric",
+
             // 512-bit integers
             EthereumSqlTypeWrapper::U512(_) => "U512",
             EthereumSqlTypeWrapper::VecU512(_) => "VecU512",
@@ -312,6 +322,9 @@ impl EthereumSqlTypeWrapper {
             EthereumSqlTypeWrapper::VecI256(_) => PgType::VARCHAR_ARRAY,
             EthereumSqlTypeWrapper::VecI256Bytes(_) => PgType::BYTEA_ARRAY,
 
+            // Fixed point numbers
+            EthereumSqlTypeWrapper::DecimalNumeric(_, _) => PgType::NUMERIC,
+
             // 512-bit integers
             EthereumSqlTypeWrapper::U512(_) => PgType::TEXT,
             EthereumSqlTypeWrapper::VecU512(_) => PgType::TEXT_ARRAY,
@@ -565,6 +578,11 @@ impl EthereumSqlTypeWrapper {
                 format!("[{}]", values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", "))
             }
             EthereumSqlTypeWrapper::I256Numeric(val) => val.to_string(),
+            // Quoted so ClickHouse parses it straight into the Decimal256 column instead of
+            // going through a Float64 literal.
+            EthereumSqlTypeWrapper::DecimalNumeric(value, scale) => {
+                format!("'{}'", fixed_to_string(*value, *scale))
+            }
 
             // U256Bytes / I256Bytes / AddressBytes — serialize as hex string
             EthereumSqlTypeWrapper::U256Bytes(val)
@@ -729,6 +747,60 @@ impl EthereumSqlTypeWrapper {
         Ok(IsNull::No)
     }
 
+    /// Writes a fixed point number as a binary NUMERIC with `scale` display digits. The digits
+    /// are split at the decimal point into base-10000 groups so no precision is lost.
+    fn write_decimal_numeric_to_postgres(
+        value: I256,
+        scale: u8,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn std::error::Error + Sync + Send>> {
+        let scale = scale as usize;
+        let digits = format!("{:0>width$}", value.unsigned_abs().to_string(), width = scale + 1);
+        let (int_part, frac_part) = digits.split_at(digits.len() - scale);
+
+        // Integer digits are grouped from the decimal point to the left, fraction digits
+        // from the decimal point to the right
+        let int_padded = format!("{:0>width$}", int_part, width = int_part.len().div_ceil(4) * 4);
+        let frac_padded =
+            format!("{:0<width$}", frac_part, width = frac_part.len().div_ceil(4) * 4);
+        let to_groups = |s: &str| -> Vec<i16> {
+            s.as_bytes()
+                .chunks(4)
+                .map(|chunk| std::str::from_utf8(chunk).unwrap_or("0").parse().unwrap_or(0))
+                .collect()
+        };
+        let mut groups = to_groups(&int_padded);
+        let mut weight = groups.len() as i16 - 1;
+        groups.extend(to_groups(&frac_padded));
+
+        // Leading zero groups only shift the weight, trailing ones are implied by dscale
+        let leading_zeros = groups.iter().take_while(|g| **g == 0).count();
+        groups.drain(..leading_zeros);
+        weight -= leading_zeros as i16;
+        while groups.last() == Some(&0) {
+            groups.pop();
+        }
+
+        if groups.is_empty() {
+            out.put_i16(0); // ndigits
+            out.put_i16(0); // weight
+            out.put_i16(0x0000); // sign
+            out.put_i16(scale as i16); // dscale
+            return Ok(IsNull::No);
+        }
+
+        out.put_i16(groups.len() as i16); // ndigits
+        out.put_i16(weight); // weight
+        out.put_i16(if value.is_negative() { 0x4000 } else { 0x0000 }); // sign
+        out.put_i16(scale as i16); // dscale
+
+        for group in groups {
+            out.put_i16(group);
+        }
+
+        Ok(IsNull::No)
+    }
+
     fn serialize_numeric_array<T>(
         values: &[T],
         out: &mut BytesMut,
@@ -888,6 +960,9 @@ impl ToSql for EthereumSqlTypeWrapper {
                     if is_negative { value.abs().into_raw() } else { value.into_raw() };
                 Self::write_u256_numeric_to_postgres(abs_value, is_negative, out)
             }
+            EthereumSqlTypeWrapper::DecimalNumeric(value, scale) => {
+                Self::write_decimal_numeric_to_postgres(*value, *scale, out)
+            }
             EthereumSqlTypeWrapper::I256Nullable(value) => {
                 if value.is_zero() {
                     return Ok(IsNull::Yes);
@@ -1996,6 +2071,9 @@ pub fn map_ethereum_wrapper_to_json(
                     | EthereumSqlTypeWrapper::I256BytesNullable(i) => {
                         json!(i.to_string())
                     }
+                    EthereumSqlTypeWrapper::DecimalNumeric(value, scale) => {
+                        json!(fixed_to_string(*value, *scale))
+                    }
                     EthereumSqlTypeWrapper::VecI256(i256s)
                     | EthereumSqlTypeWrapper::VecI256Bytes(i256s) => {
                         json!(i256s.iter().map(|i| i.to_string()).collect::<Vec<_>>())
@@ -2555,6 +2633,33 @@ mod tests {
         assert!(result.len() > 10); // large number
     }
 
+    #[test]
+    fn test_ch_decimal_numeric_is_quoted_decimal_string() {
+        let val = I256::try_from(-1_234_567i64).unwrap();
+        let wrapper = EthereumSqlTypeWrapper::DecimalNumeric(val, 6);
+        assert_eq!(wrapper.to_clickhouse_value(), "'-1.234567'");
+    }
+
+    #[test]
+    fn test_pg_decimal_numeric_round_trips() {
+        use tokio_postgres::types::FromSql;
+
+        for (value, scale, expected) in [
+            (123_456_789i64, 4, "12345.6789"),
+            (500, 4, "0.0500"),
+            (-5, 1, "-0.5"),
+            (10_000_000, 2, "100000.00"),
+            (0, 3, "0.000"),
+        ] {
+            let wrapper =
+                EthereumSqlTypeWrapper::DecimalNumeric(I256::try_from(value).unwrap(), scale);
+            let mut buf = BytesMut::new();
+            wrapper.to_sql(&PgType::NUMERIC, &mut buf).unwrap();
+            let decoded = Decimal::from_sql(&PgType::NUMERIC, &buf).unwrap();
+            assert_eq!(decoded.to_string(), expected);
+        }
+    }
+
     #[test]
     fn test_ch_address_bytes_is_checksummed() {
         // rindexer outputs EIP-55 checksummed addresses
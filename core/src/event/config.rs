@@ -13,7 +13,7 @@ use crate::event::rindexer_event_filter::FactoryFilter;
 use crate::indexer::reorg::ReorgEvent;
 use crate::indexer::tables::TableRuntime;
 use crate::manifest::config::Config;
-use crate::manifest::contract::EventInputIndexedFilters;
+use crate::manifest::contract::{BlockTag, EventInputIndexedFilters};
 use crate::manifest::core::Constants;
 use crate::provider::ChainProvider;
 use crate::streams::StreamsClients;
@@ -52,6 +52,8 @@ pub struct ContractEventProcessingConfig {
     pub index_event_in_order: bool,
     pub live_indexing: bool,
     pub indexing_distance_from_head: U64,
+    /// Live indexing follows this block tag instead of `indexing_distance_from_head` when set.
+    pub finality_tag: Option<BlockTag>,
     /// Per-generation cancellation token for hot-reload support.
     pub cancel_token: CancellationToken,
     /// Derived/custom tables associated with this event (for reorg cleanup).
@@ -144,6 +146,8 @@ pub struct FactoryEventProcessingConfig {
     pub index_event_in_order: bool,
     pub live_indexing: bool,
     pub indexing_distance_from_head: U64,
+    /// Live indexing follows this block tag instead of `indexing_distance_from_head` when set.
+    pub finality_tag: Option<BlockTag>,
     /// Per-generation cancellation token for hot-reload support.
     pub cancel_token: CancellationToken,
     /// Derived/custom tables associated with this event (for reorg cleanup).
@@ -322,6 +326,13 @@ impl EventProcessingConfig {
         }
     }
 
+    pub fn finality_tag(&self) -> Option<BlockTag> {
+        match self {
+            Self::ContractEventProcessing(config) => config.finality_tag,
+            Self::FactoryEventProcessing(config) => config.finality_tag,
+        }
+    }
+
     pub fn progress(&self) -> Arc<IndexingEventsProgressState> {
         match self {
             Self::ContractEventProcessing(config) => config.progress.clone(),
@@ -18,6 +18,7 @@ use alloy::{
     primitives::{Address, Log, U64},
     rpc::types::ValueOrArray,
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Error;
 use std::{any::Any, fs, path::Path, sync::Arc};
@@ -97,7 +98,7 @@ impl ContractInformation {
                         ),
                         decoder: Arc::clone(&decoder),
                         indexing_contract_setup: c.indexing_contract_setup(project_path),
-                        start_block: c.start_block,
+                        start_block: c.start_block_number(),
                         end_block: c.end_block,
                         disable_logs_bloom_checks: provider.disable_logs_bloom_checks,
                     });
@@ -181,7 +182,7 @@ impl TraceInformation {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, JsonSchema)]
 pub struct ContractEventMapping {
     pub contract_name: String,
     pub event_name: String,
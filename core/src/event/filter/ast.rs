@@ -149,6 +149,13 @@ pub enum ArithmeticExpr<'a> {
         /// The right operand
         right: Box<ArithmeticExpr<'a>>,
     },
+    /// `$scale(value, decimals)`: the value divided by 10^decimals as an exact fixed point number
+    Scale {
+        /// The raw value
+        value: Box<ArithmeticExpr<'a>>,
+        /// The number of decimals of the raw value
+        decimals: Box<ArithmeticExpr<'a>>,
+    },
 }
 
 /// Represents a condition in a filter expression.
@@ -244,6 +251,9 @@ impl<'a> ArithmeticExpr<'a> {
             ArithmeticExpr::Binary { left, right, .. } => {
                 left.has_table_references() || right.has_table_references()
             }
+            ArithmeticExpr::Scale { value, decimals } => {
+                value.has_table_references() || decimals.has_table_references()
+            }
         }
     }
 
@@ -283,6 +293,14 @@ impl<'a> ArithmeticExpr<'a> {
                     }
                 }
             }
+            ArithmeticExpr::Scale { value, decimals } => {
+                // NUMERIC division keeps the fractional digits
+                format!(
+                    "({} / POWER(10::NUMERIC, {}))",
+                    value.to_sql(table_name),
+                    decimals.to_sql(table_name)
+                )
+            }
         }
     }
 }
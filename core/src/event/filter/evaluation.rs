@@ -5,9 +5,12 @@ use super::{
         Accessor, ArithmeticExpr, ArithmeticOperator, ComparisonOperator, Condition, ConditionLeft,
         Expression, LiteralValue, LogicalOperator, VariableSource,
     },
-    helpers::{are_same_address, compare_ordered_values, string_to_i256, string_to_u256},
+    helpers::{
+        are_same_address, compare_ordered_values, rescale_fixed, string_to_fixed, string_to_i256,
+        string_to_u256, MAX_DECIMAL_SCALE,
+    },
 };
-use alloy::primitives::U256;
+use alloy::primitives::{I256, U256};
 use rust_decimal::Decimal;
 use serde_json::Value as JsonValue;
 use std::str::FromStr;
@@ -18,6 +21,8 @@ use thiserror::Error;
 pub enum ComputedValue {
     /// A U256 numeric result
     U256(U256),
+    /// An exact fixed point result, `value` / 10^`scale`
+    Decimal { value: I256, scale: u8 },
     /// A string result
     String(String),
 }
@@ -177,10 +182,163 @@ pub fn evaluate_with_table_data<'a>(
 enum ArithmeticValue {
     /// A numeric value (stored as U256 for precision)
     Number(U256),
+    /// An exact fixed point value, `value` / 10^`scale`
+    Fixed { value: I256, scale: u8 },
     /// A non-numeric value with its kind and string representation
     Other { kind: String, value: String },
 }
 
+impl ArithmeticValue {
+    /// Returns the value as an unscaled fixed point number and its scale, None if it isn't
+    /// numeric. Decimal strings like "1.5" and signed integers are accepted so they can be
+    /// combined with `$scale()` results. Unsigned values of 2^255 and above don't fit the
+    /// signed fixed point number and fail with an overflow.
+    fn to_fixed(&self) -> Result<Option<(I256, u8)>, EvaluationError> {
+        let fixed = match self {
+            ArithmeticValue::Number(n) => Some((
+                I256::try_from(*n).map_err(|_| {
+                    EvaluationError::ArithmeticOverflow(format!(
+                        "{n} is too large for decimal arithmetic, values must be below 2^255"
+                    ))
+                })?,
+                0,
+            )),
+            ArithmeticValue::Fixed { value, scale } => Some((*value, *scale)),
+            ArithmeticValue::Other { kind, value } if kind == "fixed" => {
+                let scale = value.split_once('.').map_or(0, |(_, fraction)| fraction.len());
+                u8::try_from(scale)
+                    .ok()
+                    .filter(|s| *s <= MAX_DECIMAL_SCALE)
+                    .and_then(|scale| Some((string_to_fixed(value, scale).ok()?, scale)))
+            }
+            ArithmeticValue::Other { kind, value }
+                if SIGNED_INTEGER_KINDS.contains(&kind.as_str()) =>
+            {
+                string_to_i256(value).ok().map(|value| (value, 0))
+            }
+            _ => None,
+        };
+        Ok(fixed)
+    }
+}
+
+/// Applies an arithmetic operator when at least one operand is a fixed point number.
+/// The arithmetic is exact: sums keep the larger scale, products add the scales, powers need a
+/// whole exponent and division truncates toward zero at the larger scale of its operands.
+fn evaluate_fixed_binary(
+    left: &ArithmeticValue,
+    operator: ArithmeticOperator,
+    right: &ArithmeticValue,
+) -> Result<ArithmeticValue, EvaluationError> {
+    let ((l, ls), (r, rs)) = match (left.to_fixed()?, right.to_fixed()?) {
+        (Some(l), Some(r)) => (l, r),
+        _ => {
+            return Err(EvaluationError::TypeMismatch(
+                "Arithmetic operations require numeric operands".to_string(),
+            ))
+        }
+    };
+    let overflow = |what: &str| EvaluationError::ArithmeticOverflow(format!("{what} overflow"));
+    let scale = ls.max(rs);
+
+    let (value, scale) = match operator {
+        ArithmeticOperator::Add | ArithmeticOperator::Subtract => {
+            let l = rescale_fixed(l, ls, scale).ok_or_else(|| overflow("rescale"))?;
+            let r = rescale_fixed(r, rs, scale).ok_or_else(|| overflow("rescale"))?;
+            let value = if operator == ArithmeticOperator::Add {
+                l.checked_add(r).ok_or_else(|| overflow("addition"))?
+            } else {
+                l.checked_sub(r).ok_or_else(|| overflow("subtraction"))?
+            };
+            (value, scale)
+        }
+        ArithmeticOperator::Multiply => {
+            let scale = ls
+                .checked_add(rs)
+                .filter(|s| *s <= MAX_DECIMAL_SCALE)
+                .ok_or_else(|| overflow("scale"))?;
+            (l.checked_mul(r).ok_or_else(|| overflow("multiplication"))?, scale)
+        }
+        ArithmeticOperator::Divide => {
+            if r.is_zero() {
+                return Err(EvaluationError::DivisionByZero);
+            }
+            // (l / 10^ls) / (r / 10^rs) with `scale` digits is l * 10^(scale + rs - ls) / r
+            let l = rescale_fixed(l, ls, scale + rs).ok_or_else(|| overflow("division"))?;
+            (l.checked_div(r).ok_or_else(|| overflow("division"))?, scale)
+        }
+        ArithmeticOperator::Power => {
+            let exponent = rescale_fixed(r, rs, 0)
+                .filter(|e| !e.is_negative() && rescale_fixed(*e, 0, rs) == Some(r))
+                .ok_or_else(|| {
+                    EvaluationError::TypeMismatch(
+                        "Exponent of a decimal must be a whole non-negative number".to_string(),
+                    )
+                })?
+                .unsigned_abs();
+            if exponent > U256::from(MAX_DECIMAL_SCALE) {
+                return Err(overflow("exponentiation"));
+            }
+            let scale = ls
+                .checked_mul(exponent.to::<u8>())
+                .filter(|s| *s <= MAX_DECIMAL_SCALE)
+                .ok_or_else(|| overflow("scale"))?;
+            (l.checked_pow(exponent).ok_or_else(|| overflow("exponentiation"))?, scale)
+        }
+    };
+
+    Ok(ArithmeticValue::Fixed { value, scale })
+}
+
+/// Evaluates `$scale(value, decimals)`: the value divided by 10^decimals, kept exact by
+/// raising the scale of the fixed point number instead of dividing.
+fn evaluate_scale(
+    value: &ArithmeticValue,
+    decimals: &ArithmeticValue,
+) -> Result<ArithmeticValue, EvaluationError> {
+    let decimals = match decimals {
+        ArithmeticValue::Number(d) if *d <= U256::from(MAX_DECIMAL_SCALE) => d.to::<u8>(),
+        _ => {
+            return Err(EvaluationError::TypeMismatch(format!(
+                "$scale() decimals must be a whole number up to {MAX_DECIMAL_SCALE}"
+            )))
+        }
+    };
+    let (value, scale) = value.to_fixed()?.ok_or_else(|| {
+        EvaluationError::TypeMismatch("$scale() requires a numeric value".to_string())
+    })?;
+    let scale =
+        scale.checked_add(decimals).filter(|s| *s <= MAX_DECIMAL_SCALE).ok_or_else(|| {
+            EvaluationError::ArithmeticOverflow(format!(
+                "$scale() results in more than {MAX_DECIMAL_SCALE} decimals"
+            ))
+        })?;
+
+    Ok(ArithmeticValue::Fixed { value, scale })
+}
+
+/// Compares two values of which at least one is a fixed point number.
+fn compare_fixed_values(
+    left: &ArithmeticValue,
+    operator: &ComparisonOperator,
+    right: &ArithmeticValue,
+) -> Result<bool, EvaluationError> {
+    let ((l, ls), (r, rs)) = match (left.to_fixed()?, right.to_fixed()?) {
+        (Some(l), Some(r)) => (l, r),
+        _ => {
+            return Err(EvaluationError::TypeMismatch(
+                "Decimals can only be compared with numbers".to_string(),
+            ))
+        }
+    };
+    let scale = ls.max(rs);
+    let overflow = || EvaluationError::ArithmeticOverflow("rescale overflow".to_string());
+    let l = rescale_fixed(l, ls, scale).ok_or_else(overflow)?;
+    let r = rescale_fixed(r, rs, scale).ok_or_else(overflow)?;
+
+    Ok(compare_ordered_values(&l, operator, &r))
+}
+
 /// Evaluates an arithmetic expression to a numeric value.
 /// # Arguments
 /// * `expr` - The arithmetic expression to evaluate.
@@ -239,14 +397,10 @@ fn evaluate_arithmetic_expr<'a>(
             let left_val = evaluate_arithmetic_expr(left, data)?;
             let right_val = evaluate_arithmetic_expr(right, data)?;
 
-            // Both must be numbers for arithmetic
+            // Integers stay U256, anything else goes through exact fixed point arithmetic
             let (left_num, right_num) = match (left_val, right_val) {
                 (ArithmeticValue::Number(l), ArithmeticValue::Number(r)) => (l, r),
-                _ => {
-                    return Err(EvaluationError::TypeMismatch(
-                        "Arithmetic operations require numeric operands".to_string(),
-                    ))
-                }
+                (l, r) => return evaluate_fixed_binary(&l, *operator, &r),
             };
 
             let result = match operator {
@@ -281,6 +435,11 @@ fn evaluate_arithmetic_expr<'a>(
 
             Ok(ArithmeticValue::Number(result))
         }
+        ArithmeticExpr::Scale { value, decimals } => {
+            let value = evaluate_arithmetic_expr(value, data)?;
+            let decimals = evaluate_arithmetic_expr(decimals, data)?;
+            evaluate_scale(&value, &decimals)
+        }
     }
 }
 
@@ -343,11 +502,7 @@ fn evaluate_arithmetic_expr_with_table<'a>(
 
             let (left_num, right_num) = match (left_val, right_val) {
                 (ArithmeticValue::Number(l), ArithmeticValue::Number(r)) => (l, r),
-                _ => {
-                    return Err(EvaluationError::TypeMismatch(
-                        "Arithmetic operations require numeric operands".to_string(),
-                    ))
-                }
+                (l, r) => return evaluate_fixed_binary(&l, *operator, &r),
             };
 
             let result = match operator {
@@ -382,6 +537,11 @@ fn evaluate_arithmetic_expr_with_table<'a>(
 
             Ok(ArithmeticValue::Number(result))
         }
+        ArithmeticExpr::Scale { value, decimals } => {
+            let value = evaluate_arithmetic_expr_with_table(value, event_data, table_data)?;
+            let decimals = evaluate_arithmetic_expr_with_table(decimals, event_data, table_data)?;
+            evaluate_scale(&value, &decimals)
+        }
     }
 }
 
@@ -418,6 +578,9 @@ fn evaluate_condition_with_table<'a>(
 
     // If both are numbers, do numeric comparison
     match (&left_val, &right_val) {
+        (ArithmeticValue::Fixed { .. }, _) | (_, ArithmeticValue::Fixed { .. }) => {
+            compare_fixed_values(&left_val, &condition.operator, &right_val)
+        }
         (ArithmeticValue::Number(l), ArithmeticValue::Number(r)) => {
             let result = match condition.operator {
                 ComparisonOperator::Eq => l == r,
@@ -855,7 +1018,8 @@ fn compare_boolean(
 /// * `data` - The JSON data containing variable values.
 ///
 /// # Returns
-/// * `Ok(ComputedValue)` - The computed result (U256 for numeric, String for non-numeric).
+/// * `Ok(ComputedValue)` - The computed result (U256 for integers, Decimal when a `$scale()` or
+///   decimal operand is involved, String for non-numeric).
 /// * `Err(EvaluationError)` - An error if parsing or evaluation fails.
 pub fn evaluate_arithmetic(
     expr_str: &str,
@@ -871,6 +1035,7 @@ pub fn evaluate_arithmetic(
 
     match result {
         ArithmeticValue::Number(n) => Ok(ComputedValue::U256(n)),
+        ArithmeticValue::Fixed { value, scale } => Ok(ComputedValue::Decimal { value, scale }),
         ArithmeticValue::Other { value, .. } => Ok(ComputedValue::String(value)),
     }
 }
@@ -927,6 +1092,7 @@ fn get_kind_from_json_value(value: &JsonValue) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::event::filter::helpers::fixed_to_string;
     use crate::event::filter::parsing::parse;
     use serde_json::json;
 
@@ -1255,6 +1421,64 @@ mod tests {
         assert!(evaluate(&expr, &data).unwrap());
     }
 
+    fn assert_decimal(expr: &str, data: &JsonValue, expected: &str) {
+        match evaluate_arithmetic(expr, data).unwrap() {
+            ComputedValue::Decimal { value, scale } => {
+                assert_eq!(fixed_to_string(value, scale), expected)
+            }
+            other => panic!("expected a decimal for '{expr}', got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_arithmetic_scale_is_exact() {
+        let data = json!({
+            "amount": "1234567",
+            "price": "250000000",
+            "huge": "123456789012345678901234567890123456789",
+            "delta": "-5"
+        });
+
+        assert_decimal("$scale($amount, 6)", &data, "1.234567");
+        assert_decimal("$scale($huge, 18)", &data, "123456789012345678901.234567890123456789");
+        // Products add the scales, sums keep the larger one
+        assert_decimal("$scale($amount, 6) * $scale($price, 8)", &data, "3.08641750000000");
+        assert_decimal("$scale($amount, 6) + 1.5", &data, "2.734567");
+        assert_decimal("$scale($delta, 2) - 1", &data, "-1.05");
+        // Division truncates at the larger operand scale
+        assert_decimal("$scale($amount, 6) / 3", &data, "0.411522");
+        assert_decimal("$scale($amount, 6) ^ 2", &data, "1.524155677489");
+
+        assert!(matches!(
+            evaluate_arithmetic("$scale($amount, 80)", &data),
+            Err(EvaluationError::TypeMismatch(_))
+        ));
+        assert!(matches!(
+            evaluate_arithmetic("$scale($amount, 6) / 0", &data),
+            Err(EvaluationError::DivisionByZero)
+        ));
+        // U256::MAX doesn't fit the signed fixed point number
+        let max = json!({ "max": U256::MAX.to_string() });
+        assert!(matches!(
+            evaluate_arithmetic("$scale($max, 18)", &max),
+            Err(EvaluationError::ArithmeticOverflow(e)) if e.contains("below 2^255")
+        ));
+        assert!(matches!(
+            evaluate_arithmetic("$max + 1.5", &max),
+            Err(EvaluationError::ArithmeticOverflow(_))
+        ));
+        // Pure integer arithmetic keeps its U256 result
+        assert!(matches!(evaluate_arithmetic("$amount / 2", &data), Ok(ComputedValue::U256(_))));
+    }
+
+    #[test]
+    fn test_condition_with_scale() {
+        let data = json!({"amount": "1500000", "decimals": 6});
+        assert!(evaluate(&parse("$scale($amount, $decimals) > 1.4").unwrap(), &data).unwrap());
+        assert!(evaluate(&parse("$scale($amount, 6) == 1.5").unwrap(), &data).unwrap());
+        assert!(!evaluate(&parse("$scale($amount, 6) >= 2").unwrap(), &data).unwrap());
+    }
+
     // --- Tests for NOT Operator ---
 
     #[test]
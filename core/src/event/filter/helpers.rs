@@ -56,6 +56,78 @@ pub fn string_to_i256(value_str: &str) -> Result<I256, String> {
     }
 }
 
+/// The largest scale of a fixed point number, matching `NUMERIC(76, s)` and `Decimal256(s)`.
+pub const MAX_DECIMAL_SCALE: u8 = 76;
+
+/// Returns 10^exp as an I256, or None if it overflows.
+fn pow10(exp: u8) -> Option<I256> {
+    I256::try_from(U256::from(10).checked_pow(U256::from(exp))?).ok()
+}
+
+/// Converts a decimal string like "-12.345" to a fixed point number with `scale` fractional
+/// digits, returned unscaled (e.g. "12.345" with scale 4 is 123450). Extra fractional digits are
+/// truncated. Integers may also be hex.
+pub fn string_to_fixed(value_str: &str, scale: u8) -> Result<I256, String> {
+    let trimmed = value_str.trim();
+    let multiplier = pow10(scale).ok_or_else(|| format!("Scale {scale} is too large"))?;
+
+    let Some((int_part, frac_part)) = trimmed.split_once('.') else {
+        return string_to_i256(trimmed)?
+            .checked_mul(multiplier)
+            .ok_or_else(|| format!("'{trimmed}' overflows with scale {scale}"));
+    };
+
+    let (negative, int_part) = match int_part.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, int_part),
+    };
+    let all_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if (int_part.is_empty() && frac_part.is_empty())
+        || !all_digits(int_part)
+        || !all_digits(frac_part)
+    {
+        return Err(format!("Failed to parse decimal '{trimmed}'"));
+    }
+
+    let frac: String = frac_part.chars().take(scale as usize).collect();
+    let digits = format!("{}{:0<width$}", int_part, frac, width = scale as usize);
+    let digits = if digits.is_empty() { "0" } else { digits.as_str() };
+    let value =
+        I256::from_str(digits).map_err(|e| format!("Failed to parse decimal '{trimmed}': {e}"))?;
+    Ok(if negative { -value } else { value })
+}
+
+/// Formats an unscaled fixed point number with `scale` fractional digits, e.g. 12345 with
+/// scale 2 is "123.45".
+pub fn fixed_to_string(value: I256, scale: u8) -> String {
+    let sign = if value.is_negative() { "-" } else { "" };
+    let digits = value.unsigned_abs().to_string();
+    if scale == 0 {
+        return format!("{sign}{digits}");
+    }
+
+    let scale = scale as usize;
+    let padded = format!("{:0>width$}", digits, width = scale + 1);
+    let (int_part, frac_part) = padded.split_at(padded.len() - scale);
+    format!("{sign}{int_part}.{frac_part}")
+}
+
+/// Changes the scale of an unscaled fixed point number. Dropped digits are truncated toward
+/// zero. Returns None on overflow.
+pub fn rescale_fixed(value: I256, from: u8, to: u8) -> Option<I256> {
+    if to >= from {
+        value.checked_mul(pow10(to - from)?)
+    } else {
+        value.checked_div(pow10(from - to)?)
+    }
+}
+
+/// Whether an unscaled fixed point number has at most [`MAX_DECIMAL_SCALE`] digits, the
+/// precision of both `NUMERIC(76, s)` and `Decimal256(s)` columns.
+pub fn fits_decimal_precision(value: I256) -> bool {
+    pow10(MAX_DECIMAL_SCALE).is_some_and(|limit| value.unsigned_abs() < limit.unsigned_abs())
+}
+
 /// Compares two values implementing the Ord trait using the specified comparison operator.
 pub fn compare_ordered_values<T: Ord>(left: &T, op: &ComparisonOperator, right: &T) -> bool {
     match op {
@@ -72,6 +144,31 @@ pub fn compare_ordered_values<T: Ord>(left: &T, op: &ComparisonOperator, right:
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_fixed_point_conversions() {
+        let fixed = |s: &str, scale: u8| string_to_fixed(s, scale).unwrap();
+        let int = |v: i64| I256::try_from(v).unwrap();
+
+        assert_eq!(fixed("12.345", 4), int(123450));
+        assert_eq!(fixed("-0.5", 2), int(-50));
+        assert_eq!(fixed(".25", 2), int(25));
+        assert_eq!(fixed("7", 3), int(7000));
+        assert_eq!(fixed("0x10", 1), int(160));
+        // Extra digits are truncated, never rounded
+        assert_eq!(fixed("1.999", 2), int(199));
+        assert!(string_to_fixed("1.2.3", 2).is_err());
+        assert!(string_to_fixed("abc", 2).is_err());
+
+        assert_eq!(fixed_to_string(int(123450), 4), "12.3450");
+        assert_eq!(fixed_to_string(int(-5), 3), "-0.005");
+        assert_eq!(fixed_to_string(int(42), 0), "42");
+
+        let value = int(12345);
+        assert_eq!(rescale_fixed(value, 2, 4), Some(int(1234500)));
+        assert_eq!(rescale_fixed(value, 2, 0), Some(int(123)));
+        assert_eq!(rescale_fixed(-value, 2, 0), Some(int(-123)));
+    }
+
     #[test]
     fn test_string_to_u256() {
         // --- Helpers ---
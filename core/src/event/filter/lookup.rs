@@ -0,0 +1,141 @@
+//! Support for `$lookup(table, key).column` references to other custom tables.
+//!
+//! Lookups are not part of the expression grammar. They are resolved before an expression is
+//! parsed by replacing every reference with the looked up value, so the parser and evaluator
+//! only ever see literals.
+
+use super::placeholder::{find_placeholders, parse_placeholder, substitute_placeholders};
+
+/// Prefix starting every lookup reference.
+pub const LOOKUP_PREFIX: &str = "$lookup(";
+
+/// A parsed `$lookup(table, key).column` reference.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LookupRef {
+    /// Name of the custom table to read, optionally qualified as `Contract.table`.
+    pub table: String,
+    /// Expression resolving to the value of the table's `where` column.
+    pub key: String,
+    /// Column returned from the matching row.
+    pub column: String,
+}
+
+/// Finds every lookup reference in `expr`.
+/// Returns (start, end, lookup) for each match, `end` being exclusive.
+pub fn find_lookups(expr: &str) -> Vec<(usize, usize, LookupRef)> {
+    find_placeholders(expr, LOOKUP_PREFIX, parse_lookup_parts)
+}
+
+/// Parses a value consisting of a single lookup reference and nothing else.
+pub fn parse_lookup(value: &str) -> Option<LookupRef> {
+    parse_placeholder(value, LOOKUP_PREFIX, parse_lookup_parts)
+}
+
+/// Replaces every lookup reference in `expr` with the text returned by `resolve`.
+/// Returns None as soon as one of the lookups cannot be resolved.
+pub fn substitute_lookups<F>(expr: &str, resolve: F) -> Option<String>
+where
+    F: FnMut(&LookupRef) -> Option<String>,
+{
+    substitute_placeholders(expr, LOOKUP_PREFIX, parse_lookup_parts, resolve)
+}
+
+/// Formats a looked up value as a filter expression literal.
+/// Numbers and booleans are kept as is, anything else is quoted.
+pub fn lookup_literal(value: &str) -> String {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    let is_number = !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit());
+    if is_number || value == "true" || value == "false" {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+}
+
+/// Builds the lookup from the `table, key` arguments and the column of a placeholder.
+fn parse_lookup_parts(args: &str, column: &str) -> Option<LookupRef> {
+    let (table, key) = split_top_level_comma(args)?;
+    if table.is_empty()
+        || key.is_empty()
+        || !table.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+    {
+        return None;
+    }
+
+    Some(LookupRef { table: table.to_string(), key: key.to_string(), column: column.to_string() })
+}
+
+/// Splits `table, key` on the first comma outside of parentheses.
+fn split_top_level_comma(args: &str) -> Option<(&str, &str)> {
+    let mut depth = 0;
+    for (i, c) in args.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => return Some((args[..i].trim(), args[i + 1..].trim())),
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(table: &str, key: &str, column: &str) -> LookupRef {
+        LookupRef { table: table.to_string(), key: key.to_string(), column: column.to_string() }
+    }
+
+    #[test]
+    fn test_parse_lookup() {
+        assert_eq!(
+            parse_lookup("$lookup(tokens, $token).decimals"),
+            Some(lookup("tokens", "$token", "decimals"))
+        );
+        assert_eq!(
+            parse_lookup("$lookup(Registry.tokens, $constant(weth)).symbol"),
+            Some(lookup("Registry.tokens", "$constant(weth)", "symbol"))
+        );
+        // Column accessor is required
+        assert_eq!(parse_lookup("$lookup(tokens, $token)"), None);
+        // Key is required
+        assert_eq!(parse_lookup("$lookup(tokens).decimals"), None);
+        // Anything after the lookup means it is not a standalone lookup
+        assert_eq!(parse_lookup("$lookup(tokens, $token).decimals * 2"), None);
+    }
+
+    #[test]
+    fn test_find_lookups_in_expression() {
+        let expr = "$amount / 10 ^ $lookup(tokens, $token).decimals";
+        let found = find_lookups(expr);
+        assert_eq!(found.len(), 1);
+        let (start, end, found_lookup) = &found[0];
+        assert_eq!(&expr[*start..*end], "$lookup(tokens, $token).decimals");
+        assert_eq!(found_lookup, &lookup("tokens", "$token", "decimals"));
+
+        let expr = "$lookup(a, $x).one + $lookup(b, $y).two";
+        let found: Vec<_> = find_lookups(expr).into_iter().map(|(_, _, l)| l).collect();
+        assert_eq!(found, vec![lookup("a", "$x", "one"), lookup("b", "$y", "two")]);
+    }
+
+    #[test]
+    fn test_substitute_lookups() {
+        let expr = "$amount / 10 ^ $lookup(tokens, $token).decimals";
+        assert_eq!(
+            substitute_lookups(expr, |_| Some("18".to_string())),
+            Some("$amount / 10 ^ 18".to_string())
+        );
+        assert_eq!(substitute_lookups(expr, |_| None), None);
+        assert_eq!(substitute_lookups("$amount * 2", |_| None), Some("$amount * 2".to_string()));
+    }
+
+    #[test]
+    fn test_lookup_literal() {
+        assert_eq!(lookup_literal("18"), "18");
+        assert_eq!(lookup_literal("true"), "true");
+        assert_eq!(lookup_literal("USDC"), "'USDC'");
+        assert_eq!(lookup_literal("-5"), "-5");
+        assert_eq!(lookup_literal("it's"), "'it\\'s'");
+    }
+}
@@ -4,7 +4,10 @@
 pub mod ast;
 pub mod evaluation;
 pub mod helpers;
+pub mod lookup;
 pub mod parsing;
+pub mod placeholder;
+pub mod token;
 
 use self::evaluation::EvaluationError;
 use once_cell::sync::Lazy;
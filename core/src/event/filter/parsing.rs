@@ -14,6 +14,9 @@ use winnow::{
     token::{literal, one_of, take_while},
 };
 
+/// Prefix of the `$scale(value, decimals)` fixed point conversion.
+pub const SCALE_PREFIX: &str = "$scale(";
+
 /// --- Helper aliases ---
 type Input<'a> = &'a str;
 /// Result for internal parser functions
@@ -307,6 +310,23 @@ fn parse_power_operator(input: &mut Input<'_>) -> ParserResult<ArithmeticOperato
         .parse_next(input)
 }
 
+/// Parses a `$scale(value, decimals)` fixed point conversion
+fn parse_scale<'a>(input: &mut Input<'a>) -> ParserResult<ArithmeticExpr<'a>> {
+    (
+        literal(SCALE_PREFIX),
+        parse_arithmetic_expr,
+        literal(","),
+        parse_arithmetic_expr,
+        literal(")"),
+    )
+        .map(|(_, value, _, decimals, _)| ArithmeticExpr::Scale {
+            value: Box::new(value),
+            decimals: Box::new(decimals),
+        })
+        .context(StrContext::Expected(StrContextValue::Description("$scale(value, decimals)")))
+        .parse_next(input)
+}
+
 /// Parses a primary arithmetic operand: variable, literal, or parenthesized arithmetic expression
 fn parse_arithmetic_primary<'a>(input: &mut Input<'a>) -> ParserResult<ArithmeticExpr<'a>> {
     delimited(
@@ -314,6 +334,8 @@ fn parse_arithmetic_primary<'a>(input: &mut Input<'a>) -> ParserResult<Arithmeti
         alt((
             // Parenthesized arithmetic expression
             delimited((literal("("), space0), parse_arithmetic_expr, (space0, literal(")"))),
+            // Fixed point conversion - must come before the `$scale` variable name
+            parse_scale,
             // Numeric/quoted literals - try this first to match numbers like "25" before variable names
             // Uses parse_numeric_or_quoted_literal to avoid matching identifiers as unquoted strings
             parse_numeric_or_quoted_literal.map(ArithmeticExpr::Literal),
@@ -553,6 +575,23 @@ fn parse_condition_lhs_with_dollar<'a>(input: &mut Input<'a>) -> ParserResult<Co
     }
 }
 
+/// Parses a `$scale(value, decimals)` fixed point conversion with $ prefix support
+fn parse_scale_with_dollar<'a>(input: &mut Input<'a>) -> ParserResult<ArithmeticExpr<'a>> {
+    (
+        literal(SCALE_PREFIX),
+        parse_arithmetic_expr_with_dollar,
+        literal(","),
+        parse_arithmetic_expr_with_dollar,
+        literal(")"),
+    )
+        .map(|(_, value, _, decimals, _)| ArithmeticExpr::Scale {
+            value: Box::new(value),
+            decimals: Box::new(decimals),
+        })
+        .context(StrContext::Expected(StrContextValue::Description("$scale(value, decimals)")))
+        .parse_next(input)
+}
+
 /// Parses a primary arithmetic operand with $ prefix support: variable, literal, or parenthesized expression
 fn parse_arithmetic_primary_with_dollar<'a>(
     input: &mut Input<'a>,
@@ -566,6 +605,8 @@ fn parse_arithmetic_primary_with_dollar<'a>(
                 parse_arithmetic_expr_with_dollar,
                 (space0, literal(")")),
             ),
+            // Fixed point conversion - must come before the `$scale` variable name
+            parse_scale_with_dollar,
             // Numeric/quoted literals - try this first to match numbers before variables
             parse_numeric_or_quoted_literal.map(ArithmeticExpr::Literal),
             // Variable reference (with optional $) - try after numeric literals
@@ -1427,4 +1468,29 @@ mod tests {
         let expr2 = parse("!($value > @balance)").unwrap();
         assert!(expr2.has_table_references());
     }
+
+    #[test]
+    fn test_parse_scale() {
+        let parsed = parse_arithmetic_expression("$scale($amount, 6) * 2").unwrap();
+        let ArithmeticExpr::Binary { left, .. } = parsed else { panic!("expected binary") };
+        assert_eq!(
+            *left,
+            ArithmeticExpr::Scale {
+                value: Box::new(ArithmeticExpr::Variable(ConditionLeft::Simple(
+                    "amount",
+                    VariableSource::Event
+                ))),
+                decimals: Box::new(ArithmeticExpr::Literal(LiteralValue::Number("6"))),
+            }
+        );
+
+        let condition = parse("$scale(@balance, $decimals) > 1.5").unwrap();
+        assert!(condition.has_table_references());
+        assert_eq!(
+            condition.to_sql_condition("my_table"),
+            "(my_table.\"balance\" / POWER(10::NUMERIC, EXCLUDED.\"decimals\")) > 1.5"
+        );
+
+        assert!(parse_arithmetic_expression("$scale($amount)").is_err());
+    }
 }
@@ -0,0 +1,141 @@
+//! Parsing of `$name(args).field` placeholders shared by lookups and token references.
+//!
+//! A placeholder is recognised by its prefix (e.g. `$lookup(`), its arguments run to the
+//! matching closing paren so they can hold calls like `$constant(...)`, and a `.field`
+//! accessor must directly follow. What the arguments and the field mean is up to the caller.
+
+/// Finds every placeholder starting with `prefix` in `expr` that `parse` accepts, `parse`
+/// receiving the arguments and the field.
+/// Returns (start, end, value) for each match, `end` being exclusive.
+pub fn find_placeholders<T, P>(expr: &str, prefix: &str, parse: P) -> Vec<(usize, usize, T)>
+where
+    P: Fn(&str, &str) -> Option<T>,
+{
+    let mut results = Vec::new();
+    let mut search_start = 0;
+
+    while let Some(offset) = expr[search_start..].find(prefix) {
+        let start = search_start + offset;
+        match parse_placeholder_at(expr, start, prefix)
+            .and_then(|(end, args, field)| Some((end, parse(args, field)?)))
+        {
+            Some((end, value)) => {
+                results.push((start, end, value));
+                search_start = end;
+            }
+            None => search_start = start + prefix.len(),
+        }
+    }
+
+    results
+}
+
+/// Parses a value consisting of a single placeholder and nothing else.
+pub fn parse_placeholder<T, P>(value: &str, prefix: &str, parse: P) -> Option<T>
+where
+    P: Fn(&str, &str) -> Option<T>,
+{
+    let value = value.trim();
+    if !value.starts_with(prefix) {
+        return None;
+    }
+    match parse_placeholder_at(value, 0, prefix) {
+        Some((end, args, field)) if end == value.len() => parse(args, field),
+        _ => None,
+    }
+}
+
+/// Replaces every placeholder found with `parse` in `expr` with the text returned by `resolve`.
+/// Returns None as soon as one of the placeholders cannot be resolved.
+pub fn substitute_placeholders<T, P, F>(
+    expr: &str,
+    prefix: &str,
+    parse: P,
+    mut resolve: F,
+) -> Option<String>
+where
+    P: Fn(&str, &str) -> Option<T>,
+    F: FnMut(&T) -> Option<String>,
+{
+    let mut result = String::with_capacity(expr.len());
+    let mut last_end = 0;
+
+    for (start, end, value) in find_placeholders(expr, prefix, parse) {
+        result.push_str(&expr[last_end..start]);
+        result.push_str(&resolve(&value)?);
+        last_end = end;
+    }
+    result.push_str(&expr[last_end..]);
+
+    Some(result)
+}
+
+/// Parses the placeholder starting at `start`, returning the exclusive end index, the trimmed
+/// arguments and the field.
+fn parse_placeholder_at<'a>(
+    expr: &'a str,
+    start: usize,
+    prefix: &str,
+) -> Option<(usize, &'a str, &'a str)> {
+    let args_start = start + prefix.len();
+
+    // Find the closing paren, arguments may contain calls like `$constant(...)`
+    let mut depth = 1;
+    let mut args_end = None;
+    for (i, c) in expr[args_start..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    args_end = Some(args_start + i);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let args_end = args_end?;
+
+    // The field accessor `.field` must directly follow the closing paren
+    let rest = expr[args_end + 1..].strip_prefix('.')?;
+    let field_len = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+    if field_len == 0 {
+        return None;
+    }
+
+    Some((args_end + 2 + field_len, expr[args_start..args_end].trim(), &rest[..field_len]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn any(args: &str, field: &str) -> Option<(String, String)> {
+        Some((args.to_string(), field.to_string()))
+    }
+
+    #[test]
+    fn test_find_placeholders_with_nested_calls() {
+        let expr = "$x($constant(a), $y).one + $x(b).two + $x(c)";
+        let found: Vec<_> = find_placeholders(expr, "$x(", any).into_iter().map(|f| f.2).collect();
+        assert_eq!(
+            found,
+            vec![
+                ("$constant(a), $y".to_string(), "one".to_string()),
+                ("b".to_string(), "two".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rejected_placeholders_are_skipped() {
+        let only_b = |args: &str, field: &str| (args == "b").then(|| field.to_string());
+        assert_eq!(find_placeholders("$x(a).one $x(b).two", "$x(", only_b).len(), 1);
+        assert_eq!(parse_placeholder("$x(a).one", "$x(", only_b), None);
+        assert_eq!(
+            substitute_placeholders("$x(b).two + 1", "$x(", only_b, |field| Some(field.clone())),
+            Some("two + 1".to_string())
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! Support for `$token(address).decimals|symbol|name` token metadata references.
+//!
+//! Like lookups, token references are resolved before an expression is parsed by replacing
+//! every reference with the token's metadata, so the parser and evaluator only see literals.
+
+use super::placeholder::{find_placeholders, parse_placeholder, substitute_placeholders};
+
+/// Prefix starting every token metadata reference.
+pub const TOKEN_PREFIX: &str = "$token(";
+
+/// Metadata field read from an ERC20 token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenField {
+    Decimals,
+    Symbol,
+    Name,
+}
+
+impl TokenField {
+    pub const ALL: [TokenField; 3] = [TokenField::Decimals, TokenField::Symbol, TokenField::Name];
+
+    /// The view function returning this field.
+    pub fn signature(&self) -> &'static str {
+        match self {
+            TokenField::Decimals => "decimals()",
+            TokenField::Symbol => "symbol()",
+            TokenField::Name => "name()",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "decimals" => Some(TokenField::Decimals),
+            "symbol" => Some(TokenField::Symbol),
+            "name" => Some(TokenField::Name),
+            _ => None,
+        }
+    }
+}
+
+/// A parsed `$token(address).field` reference.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TokenRef {
+    /// Expression resolving to the token contract address.
+    pub address: String,
+    /// Metadata field returned.
+    pub field: TokenField,
+}
+
+/// Finds every token reference in `expr`.
+/// Returns (start, end, token) for each match, `end` being exclusive.
+pub fn find_tokens(expr: &str) -> Vec<(usize, usize, TokenRef)> {
+    find_placeholders(expr, TOKEN_PREFIX, parse_token_parts)
+}
+
+/// Parses a value consisting of a single token reference and nothing else.
+pub fn parse_token(value: &str) -> Option<TokenRef> {
+    parse_placeholder(value, TOKEN_PREFIX, parse_token_parts)
+}
+
+/// Replaces every token reference in `expr` with the text returned by `resolve`.
+/// Returns None as soon as one of the references cannot be resolved.
+pub fn substitute_tokens<F>(expr: &str, resolve: F) -> Option<String>
+where
+    F: FnMut(&TokenRef) -> Option<String>,
+{
+    substitute_placeholders(expr, TOKEN_PREFIX, parse_token_parts, resolve)
+}
+
+/// Builds the token reference from the address argument and the field of a placeholder.
+fn parse_token_parts(address: &str, field: &str) -> Option<TokenRef> {
+    if address.is_empty() {
+        return None;
+    }
+
+    Some(TokenRef { address: address.to_string(), field: TokenField::from_name(field)? })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(address: &str, field: TokenField) -> TokenRef {
+        TokenRef { address: address.to_string(), field }
+    }
+
+    #[test]
+    fn test_parse_token() {
+        assert_eq!(
+            parse_token("$token($token0).decimals"),
+            Some(token("$token0", TokenField::Decimals))
+        );
+        assert_eq!(
+            parse_token("$token($constant(weth)).symbol"),
+            Some(token("$constant(weth)", TokenField::Symbol))
+        );
+        assert_eq!(parse_token("$token(0xabc).name"), Some(token("0xabc", TokenField::Name)));
+        // Field accessor is required and must be a known field
+        assert_eq!(parse_token("$token($token0)"), None);
+        assert_eq!(parse_token("$token($token0).totalSupply"), None);
+        // Address is required
+        assert_eq!(parse_token("$token().decimals"), None);
+        // Plain field references are not token references
+        assert_eq!(parse_token("$token"), None);
+        // Anything after the reference means it is not a standalone reference
+        assert_eq!(parse_token("$token($token0).decimals * 2"), None);
+    }
+
+    #[test]
+    fn test_find_tokens_in_expression() {
+        let expr = "$amount / 10 ^ $token($token).decimals";
+        let found = find_tokens(expr);
+        assert_eq!(found.len(), 1);
+        let (start, end, found_token) = &found[0];
+        assert_eq!(&expr[*start..*end], "$token($token).decimals");
+        assert_eq!(found_token, &token("$token", TokenField::Decimals));
+
+        let expr = "$token($token0).symbol/$token($token1).symbol";
+        let found: Vec<_> = find_tokens(expr).into_iter().map(|(_, _, t)| t).collect();
+        assert_eq!(
+            found,
+            vec![token("$token0", TokenField::Symbol), token("$token1", TokenField::Symbol)]
+        );
+    }
+
+    #[test]
+    fn test_substitute_tokens() {
+        let expr = "$amount / 10 ^ $token($token).decimals";
+        assert_eq!(
+            substitute_tokens(expr, |_| Some("6".to_string())),
+            Some("$amount / 10 ^ 6".to_string())
+        );
+        assert_eq!(substitute_tokens(expr, |_| None), None);
+        assert_eq!(substitute_tokens("$token == 0x1", |_| None), Some("$token == 0x1".to_string()));
+    }
+}
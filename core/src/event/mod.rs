@@ -3,8 +3,10 @@ pub mod callback_registry;
 pub mod config;
 pub mod contract_setup;
 
-mod rindexer_event_filter;
-pub use rindexer_event_filter::{BuildRindexerFilterError, RindexerEventFilter};
+pub(crate) mod rindexer_event_filter;
+pub use rindexer_event_filter::{
+    BuildRindexerFilterError, CombinedEventFilter, RindexerEventFilter,
+};
 
 mod message;
 pub use message::EventMessage;
@@ -18,5 +20,16 @@ mod filter;
 
 pub use filter::ast::VariableSource;
 pub use filter::evaluation::{evaluate_arithmetic, evaluate_with_table_data, ComputedValue};
-pub use filter::parsing::{parse as parse_filter_expression, parse_arithmetic_expression};
+pub use filter::helpers::{
+    fits_decimal_precision, fixed_to_string, rescale_fixed, string_to_fixed, MAX_DECIMAL_SCALE,
+};
+pub use filter::lookup::{
+    find_lookups, lookup_literal, parse_lookup, substitute_lookups, LookupRef, LOOKUP_PREFIX,
+};
+pub use filter::parsing::{
+    parse as parse_filter_expression, parse_arithmetic_expression, SCALE_PREFIX,
+};
+pub use filter::token::{
+    find_tokens, parse_token, substitute_tokens, TokenField, TokenRef, TOKEN_PREFIX,
+};
 pub use filter::{filter_by_expression, filter_event_data_by_conditions};
@@ -4,7 +4,7 @@ use crate::event::factory_event_filter_sync::{
 };
 use crate::manifest::storage::CsvDetails;
 use crate::{ClickhouseClient, PostgresClient};
-use alloy::rpc::types::Topic;
+use alloy::rpc::types::{Log, Topic};
 use alloy::{
     primitives::{Address, B256, U64},
     rpc::types::ValueOrArray,
@@ -123,6 +123,56 @@ impl FactoryFilter {
     }
 }
 
+/// The union of the filters of several events, fetched with a single `eth_getLogs` (chunked by
+/// address) and split back per event by the caller.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CombinedEventFilter {
+    /// None fetches the logs of every address.
+    pub addresses: Option<HashSet<Address>>,
+    pub topic_ids: Vec<B256>,
+    /// The indexed topic filters (topic1 to topic3) every event of the filter shares.
+    pub topics: [Topic; 3],
+    pub from_block: U64,
+    pub to_block: U64,
+}
+
+impl CombinedEventFilter {
+    /// One filter per event of the combined filter, for providers fetching them one by one.
+    pub fn event_filters(&self) -> impl Iterator<Item = RindexerEventFilter> + '_ {
+        self.topic_ids.iter().map(|topic_id| {
+            let [topic1, topic2, topic3] = self.topics.clone();
+            RindexerEventFilter::Filter(SimpleEventFilter {
+                address: self
+                    .addresses
+                    .as_ref()
+                    .map(|addresses| ValueOrArray::Array(addresses.iter().copied().collect())),
+                topic_id: *topic_id,
+                topics: [Topic::default(), topic1, topic2, topic3],
+                current_block: self.from_block,
+                next_block: self.to_block,
+            })
+        })
+    }
+
+    /// Whether `log` is one of the logs the combined filter fetches.
+    pub fn matches(&self, log: &Log) -> bool {
+        let topics = log.topics();
+        if !topics.first().is_some_and(|topic_id| self.topic_ids.contains(topic_id)) {
+            return false;
+        }
+
+        if let Some(addresses) = &self.addresses {
+            if !addresses.contains(&log.address()) {
+                return false;
+            }
+        }
+
+        self.topics.iter().enumerate().all(|(i, topic)| {
+            topic.is_empty() || topics.get(i + 1).is_some_and(|value| topic.matches(value))
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum RindexerEventFilter {
     Address(SimpleEventFilter),
@@ -545,4 +595,25 @@ mod tests {
         let addrs = f.contract_addresses().await.expect("expected Some");
         assert!(addrs.contains(&addr));
     }
+
+    #[tokio::test]
+    async fn combined_filter_splits_into_one_filter_per_event() {
+        let addr = make_address();
+        let topic1 = B256::from([9u8; 32]);
+        let combined = CombinedEventFilter {
+            addresses: Some(HashSet::from([addr])),
+            topic_ids: vec![make_topic_id(), B256::from([2u8; 32])],
+            topics: [topic1.into(), Topic::default(), Topic::default()],
+            from_block: U64::from(10u64),
+            to_block: U64::from(20u64),
+        };
+
+        let filters: Vec<_> = combined.event_filters().collect();
+        assert_eq!(filters.len(), 2);
+        assert_eq!(filters[1].event_signature(), B256::from([2u8; 32]));
+        assert_eq!(filters[1].topic1(), Topic::from(topic1));
+        assert_eq!(filters[1].from_block(), U64::from(10u64));
+        assert_eq!(filters[1].to_block(), U64::from(20u64));
+        assert_eq!(filters[1].contract_addresses().await, Some(HashSet::from([addr])));
+    }
 }
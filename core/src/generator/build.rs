@@ -642,12 +642,15 @@ mod tests {
             name: "ethereum".to_string(),
             chain_id: 1,
             rpc: "https://eth.example.com".to_string(),
+            ws_rpc: None,
             block_poll_frequency: None,
             compute_units_per_second: None,
             max_block_range: None,
             get_logs_settings: None,
             disable_logs_bloom_checks: None,
+            shared_live_fetcher: None,
             multicall3_address: None,
+            log_archive: None,
             reth: None,
             reorg_handling: None,
         };
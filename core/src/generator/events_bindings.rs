@@ -548,7 +548,7 @@ impl<TExtensions> {event_type_name}<TExtensions> where TExtensions: 'static + Se
                         ),
                         decoder: self.decoder(&c.network),
                         indexing_contract_setup: c.indexing_contract_setup(manifest_path),
-                        start_block: c.start_block,
+                        start_block: c.start_block_number(),
                         end_block: c.end_block,
                         disable_logs_bloom_checks: rindexer_yaml
                                                     .networks
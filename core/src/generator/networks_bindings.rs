@@ -13,14 +13,31 @@ pub fn network_provider_fn_name(network: &Network) -> String {
     format!("get_{fn_name}", fn_name = network_provider_name(network).to_lowercase())
 }
 
-#[cfg(not(feature = "reth"))]
-fn generate_reth_init_fn(_network: &Network) -> Code {
-    Code::new(
-        r#"
+/// Without reth, heads are pushed through a `newHeads` subscription when `ws_rpc` is set.
+fn generate_new_heads_init_fn(network: &Network) -> Code {
+    if let Some(ws_rpc) = &network.ws_rpc {
+        Code::new(format!(
+            r#"
+            let chain_state_notification = Some(rindexer::new_heads::subscribe_new_heads(
+                public_read_env_value("{ws_rpc}").unwrap_or("{ws_rpc}".to_string()),
+                "{network_name}".to_string(),
+            ));
+            "#,
+            network_name = network.name
+        ))
+    } else {
+        Code::new(
+            r#"
             let chain_state_notification = None;
             "#
-        .to_string(),
-    )
+            .to_string(),
+        )
+    }
+}
+
+#[cfg(not(feature = "reth"))]
+fn generate_reth_init_fn(network: &Network) -> Code {
+    generate_new_heads_init_fn(network)
 }
 
 #[cfg(feature = "reth")]
@@ -37,12 +54,7 @@ fn generate_reth_init_fn(network: &Network) -> Code {
             "#
         ))
     } else {
-        Code::new(
-            r#"
-            let chain_state_notification = None;
-            "#
-            .to_string(),
-        )
+        generate_new_heads_init_fn(network)
     }
 }
 
@@ -211,12 +223,15 @@ mod tests {
             name: name.to_string(),
             chain_id,
             rpc: format!("https://{name}.example.com"),
+            ws_rpc: None,
             block_poll_frequency: None,
             compute_units_per_second: None,
             max_block_range: None,
             get_logs_settings: None,
             disable_logs_bloom_checks: None,
+            shared_live_fetcher: None,
             multicall3_address: None,
+            log_archive: None,
             reth: None,
             reorg_handling: None,
         }
@@ -232,6 +247,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn generated_provider_subscribes_to_new_heads_when_ws_rpc_set() {
+        let mut network = test_network("ethereum", 1);
+        let code = generate_networks_code(&[network.clone()]).to_string();
+        assert!(!code.contains("subscribe_new_heads"));
+
+        network.ws_rpc = Some("wss://ethereum.example.com".to_string());
+        let code = generate_networks_code(&[network]).to_string();
+        assert!(code.contains("rindexer::new_heads::subscribe_new_heads("));
+        assert!(code.contains(r#"unwrap_or("wss://ethereum.example.com".to_string())"#));
+    }
+
     #[test]
     fn generated_provider_cache_for_network_returns_dyn_chain_provider() {
         let networks = vec![test_network("ethereum", 1)];
@@ -1,16 +1,21 @@
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc as std_mpsc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use tokio::sync::mpsc;
 use tracing::{debug, error, info, warn};
 
+use crate::manifest::compose::{environment_from_env, manifest_sources};
+
 /// Debounce window: after detecting a file change, wait this long before triggering reload.
 /// This prevents rapid successive saves from causing multiple reloads.
 const DEBOUNCE_DURATION: Duration = Duration::from_millis(500);
 
-/// Watches `rindexer.yaml` for modifications and sends reload signals via an mpsc channel.
+/// Watches `rindexer.yaml`, every file it includes or extends and its environment overlay for
+/// modifications and sends reload signals via an mpsc channel.
 ///
 /// Uses the `notify` crate for cross-platform file watching with custom debouncing
 /// to handle rapid successive saves (e.g., editor auto-save).
@@ -24,55 +29,57 @@ impl ManifestWatcher {
         Self { manifest_path, reload_tx }
     }
 
-    /// Starts watching the manifest file for changes.
+    /// The files and included folders making up the manifest, falling back to the manifest
+    /// itself when it can't be composed (e.g. mid edit) so it keeps being watched.
+    fn watched_sources(&self) -> WatchedSources {
+        let mut watched = WatchedSources::default();
+        match manifest_sources(&self.manifest_path, environment_from_env().as_deref()) {
+            Ok(sources) => {
+                watched.files.extend(sources.files);
+                watched.folders.extend(sources.folders);
+            }
+            Err(e) => warn!("Hot-reload: could not resolve manifest includes: {}", e),
+        }
+        watched.files.insert(canonical(&self.manifest_path));
+        watched
+    }
+
+    /// Starts watching the manifest files for changes.
     ///
     /// This function runs indefinitely. It spawns a blocking thread for the `notify` watcher
     /// and a tokio task for debouncing. Cancel the parent task to stop watching.
     pub async fn run(self) -> Result<(), ManifestWatchError> {
-        let watch_path =
-            self.manifest_path.parent().ok_or(ManifestWatchError::NoParentDirectory)?.to_path_buf();
+        self.manifest_path.parent().ok_or(ManifestWatchError::NoParentDirectory)?;
+        self.manifest_path.file_name().ok_or(ManifestWatchError::NoFileName)?;
 
-        let manifest_filename =
-            self.manifest_path.file_name().ok_or(ManifestWatchError::NoFileName)?.to_os_string();
+        let watched_sources = Arc::new(Mutex::new(self.watched_sources()));
+        let mut watched_directories = HashSet::new();
 
         // Channel from the notify watcher (sync) to our async debounce loop
         let (notify_tx, mut notify_rx) = mpsc::channel::<()>(16);
+        let (std_tx, std_rx) = std_mpsc::channel();
+
+        let mut watcher = RecommendedWatcher::new(
+            move |result: Result<Event, notify::Error>| {
+                let _ = std_tx.send(result);
+            },
+            notify::Config::default(),
+        )?;
 
-        // Spawn the notify watcher on a blocking thread since it uses sync callbacks
-        let watch_path_clone = watch_path.clone();
-        let manifest_filename_clone = manifest_filename.clone();
+        watch_directories(&mut watcher, &watched_sources, &mut watched_directories);
+
+        // Forward relevant events from the notify callback thread to the async channel
+        let watched_sources_clone = Arc::clone(&watched_sources);
         let _watcher_handle = tokio::task::spawn_blocking(move || {
             let notify_tx = notify_tx;
-            let (std_tx, std_rx) = std_mpsc::channel();
-
-            let watcher = RecommendedWatcher::new(
-                move |result: Result<Event, notify::Error>| {
-                    let _ = std_tx.send(result);
-                },
-                notify::Config::default(),
-            )
-            .map_err(|e| {
-                error!("Hot-reload: failed to create file watcher: {}", e);
-            });
-
-            let Ok(mut watcher) = watcher else {
-                return;
-            };
-
-            if let Err(e) = watcher.watch(&watch_path_clone, RecursiveMode::NonRecursive) {
-                error!("Hot-reload: failed to watch directory {:?}: {}", watch_path_clone, e);
-                return;
-            }
 
-            info!("Hot-reload: watching {:?} for changes", watch_path_clone);
-
-            // Forward relevant events to the async channel
             loop {
                 match std_rx.recv() {
                     Ok(Ok(event)) => {
-                        let matches_file = event.paths.iter().any(|p| {
-                            p.file_name().map(|f| f == manifest_filename_clone).unwrap_or(false)
-                        });
+                        let matches_file = {
+                            let sources = watched_sources_clone.lock().unwrap();
+                            event.paths.iter().any(|p| sources.matches(&canonical_event_path(p)))
+                        };
 
                         if matches_file {
                             debug!("Hot-reload: file event {:?} for {:?}", event.kind, event.paths);
@@ -130,6 +137,10 @@ impl ManifestWatcher {
 
             info!("Hot-reload: detected change to {:?}, triggering reload", self.manifest_path);
 
+            // includes may have been added or removed by the change
+            *watched_sources.lock().unwrap() = self.watched_sources();
+            watch_directories(&mut watcher, &watched_sources, &mut watched_directories);
+
             if let Err(e) = self.reload_tx.send(self.manifest_path.clone()).await {
                 error!("Hot-reload: failed to send reload signal: {}", e);
                 return Err(ManifestWatchError::ReloadChannelClosed);
@@ -138,6 +149,66 @@ impl ManifestWatcher {
     }
 }
 
+#[derive(Debug, Default)]
+struct WatchedSources {
+    files: HashSet<PathBuf>,
+    folders: HashSet<PathBuf>,
+}
+
+impl WatchedSources {
+    /// Whether a change to `path` changes the manifest, any yaml file in an included folder does
+    /// as creating one adds it to the manifest.
+    fn matches(&self, path: &Path) -> bool {
+        self.files.contains(path)
+            || (path.extension().is_some_and(|e| e == "yaml" || e == "yml")
+                && path.parent().is_some_and(|parent| self.folders.contains(parent)))
+    }
+
+    fn directories(&self) -> HashSet<PathBuf> {
+        self.files
+            .iter()
+            .filter_map(|file| file.parent().map(Path::to_path_buf))
+            .chain(self.folders.iter().cloned())
+            .collect()
+    }
+}
+
+/// Watches the folder of every manifest file and every included folder which isn't watched yet,
+/// watching folders rather than files keeps working when editors save by replacing the file.
+fn watch_directories(
+    watcher: &mut RecommendedWatcher,
+    watched_sources: &Mutex<WatchedSources>,
+    watched_directories: &mut HashSet<PathBuf>,
+) {
+    let directories = watched_sources.lock().unwrap().directories();
+
+    for directory in directories {
+        if watched_directories.contains(&directory) {
+            continue;
+        }
+
+        match watcher.watch(&directory, RecursiveMode::NonRecursive) {
+            Ok(()) => {
+                info!("Hot-reload: watching {:?} for changes", directory);
+                watched_directories.insert(directory);
+            }
+            Err(e) => error!("Hot-reload: failed to watch directory {:?}: {}", directory, e),
+        }
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Canonicalizes through the parent folder as removed files can't be canonicalized themselves.
+fn canonical_event_path(path: &Path) -> PathBuf {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(file_name)) => canonical(parent).join(file_name),
+        _ => path.to_path_buf(),
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum ManifestWatchError {
     #[error("Manifest path has no parent directory")]
@@ -155,3 +226,23 @@ pub enum ManifestWatchError {
     #[error("Notify watcher error: {0}")]
     NotifyError(#[from] notify::Error),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watched_sources_match_new_files_in_included_folders() {
+        let sources = WatchedSources {
+            files: HashSet::from([PathBuf::from("/project/rindexer.yaml")]),
+            folders: HashSet::from([PathBuf::from("/project/contracts")]),
+        };
+
+        assert!(sources.matches(Path::new("/project/rindexer.yaml")));
+        assert!(sources.matches(Path::new("/project/contracts/new.yaml")));
+        assert!(sources.matches(Path::new("/project/contracts/new.yml")));
+        assert!(!sources.matches(Path::new("/project/contracts/notes.txt")));
+        assert!(!sources.matches(Path::new("/project/other.yaml")));
+        assert!(sources.directories().contains(Path::new("/project/contracts")));
+    }
+}
@@ -1,13 +1,19 @@
+use crate::adaptive_block_range::{block_range_controller, AdaptiveBlockRange, BlockRangeStore};
 use crate::adaptive_concurrency::{AdaptiveConcurrency, ADAPTIVE_CONCURRENCY};
 use crate::blockclock::BlockClock;
 use crate::database::clickhouse::client::ClickhouseClient;
 use crate::event::callback_registry::{EventCallbackRegistry, TraceCallbackRegistry};
 use crate::helpers::{halved_block_number, is_relevant_block};
 use crate::indexer::heartbeat::{HeartbeatAction, HeartbeatTracker};
+use crate::indexer::live_fetcher::{log_matches, LiveSubscription, NetworkLiveFetcher};
+use crate::indexer::log_archive::{historical_log_source, HistoricalLogSource};
 use crate::indexer::reorg::{
-    detect_and_handle_reorg, reorg_safe_distance_for_chain, ReorgContext, ReorgCoordinator,
+    detect_and_handle_reorg, live_safe_block_number, reorg_safe_distance_for_chain, ReorgContext,
+    ReorgCoordinator,
 };
+use crate::manifest::contract::BlockTag;
 use crate::metrics::indexing as metrics;
+use crate::notifications::ChainStateSource;
 use crate::PostgresClient;
 use crate::{
     event::{config::EventProcessingConfig, RindexerEventFilter},
@@ -63,6 +69,7 @@ pub fn fetch_logs_stream(
     force_no_live_indexing: bool,
     reorg_coordinator: Option<Arc<Mutex<ReorgCoordinator>>>,
     trace_registry: Option<Arc<TraceCallbackRegistry>>,
+    live_fetcher: Option<Arc<NetworkLiveFetcher>>,
 ) -> impl tokio_stream::Stream<Item = Result<FetchLogsResult, Box<dyn Error + Send>>> + Send + Unpin
 {
     // If the sink is slower than the producer it can lead to unbounded memory growth and
@@ -82,6 +89,22 @@ pub fn fetch_logs_stream(
         let mut current_filter = config.to_event_filter().unwrap();
 
         let snapshot_to_block = current_filter.to_block();
+
+        // Blocks held by a local log archive are read from disk, the RPC only serves the tail
+        if let Some(source) = historical_log_source(&config.network_contract().network) {
+            current_filter = fetch_archived_logs_stream(
+                source.as_ref(),
+                config.timestamps(),
+                config.network_contract().block_clock.clone(),
+                &tx,
+                &config.topic_id(),
+                current_filter,
+                snapshot_to_block,
+                &config.info_log_name(),
+            )
+            .await;
+        }
+
         let from_block = current_filter.from_block();
 
         // add any max block range limitation before we start processing
@@ -89,6 +112,14 @@ pub fn fetch_logs_stream(
         let mut max_block_range_limitation =
             config.network_contract().cached_provider.max_block_range();
 
+        // The range learned for this event on this network, resumed from the last run
+        let block_range = block_range_controller(
+            &config.network_contract().network,
+            &format!("{}_{}", config.contract_name(), config.event_name()),
+        );
+        let block_range_store = BlockRangeStore::new(config.postgres(), config.clickhouse());
+        block_range.load(&block_range_store).await;
+
         // Parallel historical backfill path. Activated when fetch_concurrency > 1
         // and the event is not a factory event (factory needs sequential discovery).
         let use_parallel = matches!(
@@ -132,6 +163,7 @@ pub fn fetch_logs_stream(
                 let dispatcher_controller = Arc::clone(&ADAPTIVE_CONCURRENCY);
                 let dispatcher_active = Arc::clone(&active_workers);
                 let dispatcher_notify = Arc::clone(&worker_done_notify);
+                let dispatcher_block_range = Arc::clone(&block_range);
                 let dispatcher_handle = tokio::spawn(async move {
                     let mut next_from = from_block;
                     let mut sequence_id: u64 = 0;
@@ -162,16 +194,23 @@ pub fn fetch_logs_stream(
                             snapshot_to_block.to::<u64>(),
                         ));
 
+                        // The chunk is fetched in requests of the learned range
                         let worker_filter = dispatcher_filter
                             .clone()
                             .set_from_block(next_from)
-                            .set_to_block(sub_to);
+                            .set_to_block(calculate_process_historic_log_to_block(
+                                &next_from,
+                                &sub_to,
+                                &dispatcher_block_range.limit(original_max_limit),
+                            ));
 
                         let worker_state = WorkerState {
                             sequence_id,
                             filter: worker_filter,
+                            sub_range_end: sub_to,
                             max_block_range_limitation: original_max_limit,
                             original_max_limit,
+                            block_range: Arc::clone(&dispatcher_block_range),
                         };
 
                         let wtx = worker_tx.clone();
@@ -213,6 +252,8 @@ pub fn fetch_logs_stream(
                     error!("{} - Reorder task failed: {:?}", config.info_log_name(), e);
                 }
 
+                block_range.persist(&block_range_store).await;
+
                 info!(
                     "{} - {} - Finished parallel indexing historic events",
                     config.info_log_name(),
@@ -225,6 +266,11 @@ pub fn fetch_logs_stream(
                     let live_filter =
                         current_filter.clone().set_from_block(live_from).set_to_block(live_from);
 
+                    if let Some(live_fetcher) = live_fetcher {
+                        live_fetcher.subscribe(LiveSubscription::new(&config, live_filter, tx));
+                        return;
+                    }
+
                     live_indexing_stream(
                         config.timestamps(),
                         config.network_contract().block_clock.clone(),
@@ -233,6 +279,7 @@ pub fn fetch_logs_stream(
                         snapshot_to_block,
                         &config.topic_id(),
                         &config.indexing_distance_from_head(),
+                        config.finality_tag(),
                         live_filter,
                         &config.info_log_name(),
                         &config.network_contract().network,
@@ -258,13 +305,17 @@ pub fn fetch_logs_stream(
             }
         }
 
-        #[allow(clippy::unnecessary_unwrap)]
-        if max_block_range_limitation.is_some() {
+        let initial_limit = block_range.limit(max_block_range_limitation);
+        if initial_limit.is_some() {
             current_filter = current_filter.set_to_block(calculate_process_historic_log_to_block(
                 &from_block,
                 &snapshot_to_block,
-                &max_block_range_limitation,
+                &initial_limit,
             ));
+        }
+
+        #[allow(clippy::unnecessary_unwrap)]
+        if max_block_range_limitation.is_some() {
             if random_ratio(1, 20) {
                 warn!(
                     "{} - {} - max block range of {} applied - indexing will be slower than providers supplying the optimal ranges - https://rindexer.xyz/docs/references/rpc-node-providers#rpc-node-providers",
@@ -288,11 +339,14 @@ pub fn fetch_logs_stream(
                 &config.topic_id(),
                 current_filter.clone(),
                 max_block_range_limitation,
+                &block_range,
                 snapshot_to_block,
                 &config.info_log_name(),
             )
             .await;
 
+            block_range.persist_if_due(&block_range_store).await;
+
             // This check can be very noisy. We want to only sample this warning to notify
             // the user, rather than warn on every log fetch.
             if let Some(range) = max_block_range_limitation {
@@ -321,6 +375,8 @@ pub fn fetch_logs_stream(
             }
         }
 
+        block_range.persist(&block_range_store).await;
+
         info!(
             "{} - {} - Finished indexing historic events",
             &config.info_log_name(),
@@ -329,6 +385,12 @@ pub fn fetch_logs_stream(
 
         // Live indexing mode
         if config.live_indexing() && !force_no_live_indexing {
+            // The shared fetcher owns the sender from here, the stream stays open through it.
+            if let Some(live_fetcher) = live_fetcher {
+                live_fetcher.subscribe(LiveSubscription::new(&config, current_filter, tx));
+                return;
+            }
+
             let registry = config.registry();
             live_indexing_stream(
                 config.timestamps(),
@@ -338,6 +400,7 @@ pub fn fetch_logs_stream(
                 snapshot_to_block,
                 &config.topic_id(),
                 &config.indexing_distance_from_head(),
+                config.finality_tag(),
                 current_filter,
                 &config.info_log_name(),
                 &config.network_contract().network,
@@ -357,6 +420,90 @@ pub fn fetch_logs_stream(
     ReceiverStream::new(rx)
 }
 
+/// Sends the logs of every chunk `source` holds from the filter's `from_block` on, stopping at the
+/// first block it does not hold. Returns the filter to continue from over RPC.
+#[allow(clippy::too_many_arguments)]
+async fn fetch_archived_logs_stream(
+    source: &dyn HistoricalLogSource,
+    timestamps: bool,
+    block_clock: BlockClock,
+    tx: &mpsc::Sender<Result<FetchLogsResult, Box<dyn Error + Send>>>,
+    topic_id: &B256,
+    current_filter: RindexerEventFilter,
+    snapshot_to_block: U64,
+    info_log_name: &str,
+) -> RindexerEventFilter {
+    let mut from_block = current_filter.from_block();
+
+    while from_block <= snapshot_to_block {
+        if !is_running() {
+            break;
+        }
+
+        let Some(chunk_to_block) = source.chunk_to_block(from_block) else {
+            break;
+        };
+        let to_block = chunk_to_block.min(snapshot_to_block);
+
+        let logs = match source.get_logs(topic_id, from_block, to_block).await {
+            Ok(logs) => logs,
+            Err(e) => {
+                warn!(
+                    "{} - {} - Could not read archived logs, fetching from {} over RPC: {}",
+                    info_log_name,
+                    IndexingEventProgressStatus::syncing_log(),
+                    from_block,
+                    e
+                );
+                break;
+            }
+        };
+
+        let addresses = current_filter.contract_addresses().await;
+        let logs: Vec<Log> = logs
+            .into_iter()
+            .filter(|log| log_matches(log, topic_id, &addresses, &current_filter))
+            .collect();
+
+        let logs = if timestamps {
+            match block_clock.attach_log_timestamps(logs).await {
+                Ok(logs) => logs,
+                Err(e) => {
+                    warn!(
+                        "{} - {} - Could not attach timestamps to archived logs, fetching from {} over RPC: {}",
+                        info_log_name,
+                        IndexingEventProgressStatus::syncing_log(),
+                        from_block,
+                        e
+                    );
+                    break;
+                }
+            }
+        } else {
+            logs
+        };
+
+        if !logs.is_empty() {
+            info!(
+                "{} - {} - Read {} archived logs between: {} - {}",
+                info_log_name,
+                IndexingEventProgressStatus::syncing_log(),
+                logs.len(),
+                from_block,
+                to_block
+            );
+        }
+
+        if tx.send(Ok(FetchLogsResult { logs, from_block, to_block, reorg: None })).await.is_err() {
+            break;
+        }
+
+        from_block = to_block + U64::from(1);
+    }
+
+    current_filter.set_from_block(from_block).set_to_block(from_block.max(snapshot_to_block))
+}
+
 struct ProcessHistoricLogsStreamResult {
     pub next: RindexerEventFilter,
     pub max_block_range_limitation: Option<U64>,
@@ -371,6 +518,7 @@ async fn fetch_historic_logs_stream<P: ChainProvider>(
     topic_id: &B256,
     current_filter: RindexerEventFilter,
     max_block_range_limitation: Option<U64>,
+    block_range: &AdaptiveBlockRange,
     snapshot_to_block: U64,
     info_log_name: &str,
 ) -> Option<ProcessHistoricLogsStreamResult> {
@@ -417,8 +565,15 @@ async fn fetch_historic_logs_stream<P: ChainProvider>(
         );
     }
 
+    let started = Instant::now();
     match cached_provider.get_logs(&current_filter).await {
         Ok(logs) => {
+            block_range.record_success(
+                (to_block - from_block).to::<u64>(),
+                logs.len(),
+                started.elapsed(),
+            );
+
             debug!(
                 "{} - {} - topic_id {}, Logs: {} from {} to {}",
                 info_log_name,
@@ -475,7 +630,7 @@ async fn fetch_historic_logs_stream<P: ChainProvider>(
                     calculate_process_historic_log_to_block(
                         &next_from_block,
                         &snapshot_to_block,
-                        &max_block_range_limitation,
+                        &block_range.limit(max_block_range_limitation),
                     )
                 };
 
@@ -511,7 +666,7 @@ async fn fetch_historic_logs_stream<P: ChainProvider>(
                     calculate_process_historic_log_to_block(
                         &next_from_block,
                         &snapshot_to_block,
-                        &max_block_range_limitation,
+                        &block_range.limit(max_block_range_limitation),
                     )
                 };
 
@@ -533,6 +688,16 @@ async fn fetch_historic_logs_stream<P: ChainProvider>(
             )
             .await
             {
+                // A new max block range is a hard provider limit, otherwise the range only
+                // had too many logs.
+                block_range.record_rejected(
+                    (retry_result.to - retry_result.from).to::<u64>(),
+                    retry_result
+                        .max_block_range
+                        .filter(|max| Some(*max) != max_block_range_limitation)
+                        .map(|max| max.to::<u64>()),
+                );
+
                 // Log if we "overshrink"
                 if retry_result.to - retry_result.from < U64::from(1000) {
                     debug!(
@@ -706,8 +871,11 @@ impl ReorderBuffer {
 struct WorkerState {
     sequence_id: u64,
     filter: RindexerEventFilter,
+    /// The last block of the worker's chunk, `filter` only covers its first request.
+    sub_range_end: U64,
     max_block_range_limitation: Option<U64>,
     original_max_limit: Option<U64>,
+    block_range: Arc<AdaptiveBlockRange>,
 }
 
 /// Drop guard ensuring the reorder buffer always receives a message for every
@@ -767,7 +935,7 @@ async fn parallel_worker(
         sent: false,
     };
 
-    let sub_range_end = state.filter.to_block();
+    let sub_range_end = state.sub_range_end;
     let mut current_filter = state.filter.clone();
     let mut results: Vec<Result<FetchLogsResult, Box<dyn Error + Send>>> = Vec::new();
 
@@ -777,6 +945,7 @@ async fn parallel_worker(
     let block_clock = config.network_contract().block_clock.clone();
     let cached_provider = Arc::clone(&config.network_contract().cached_provider);
     let info_log_name = config.info_log_name();
+    let block_range_store = BlockRangeStore::new(config.postgres(), config.clickhouse());
 
     // Bail out if the same sub-range fails this many times in a row. Prevents
     // a stuck single/tiny range from holding the reorder buffer open forever
@@ -799,11 +968,14 @@ async fn parallel_worker(
             cached_provider.as_ref(),
             current_filter.clone(),
             state.max_block_range_limitation,
+            &state.block_range,
             sub_range_end,
             &info_log_name,
         )
         .await;
 
+        state.block_range.persist_if_due(&block_range_store).await;
+
         match maybe_result {
             Some(fetch_result) => {
                 controller.record_success();
@@ -913,6 +1085,7 @@ async fn fetch_logs_once<P: ChainProvider + ?Sized>(
     cached_provider: &P,
     current_filter: RindexerEventFilter,
     max_block_range_limitation: Option<U64>,
+    block_range: &AdaptiveBlockRange,
     snapshot_to_block: U64,
     info_log_name: &str,
 ) -> (Option<FetchLogsResult>, Option<ProcessHistoricLogsStreamResult>, Option<FetchErrorKind>) {
@@ -946,8 +1119,15 @@ async fn fetch_logs_once<P: ChainProvider + ?Sized>(
         );
     }
 
+    let started = Instant::now();
     match cached_provider.get_logs(&current_filter).await {
         Ok(logs) => {
+            block_range.record_success(
+                (to_block - from_block).to::<u64>(),
+                logs.len(),
+                started.elapsed(),
+            );
+
             let logs_empty = logs.is_empty();
             let last_log = logs.last().cloned();
 
@@ -989,7 +1169,7 @@ async fn fetch_logs_once<P: ChainProvider + ?Sized>(
                     let new_to_block = calculate_process_historic_log_to_block(
                         &next_from_block,
                         &snapshot_to_block,
-                        &max_block_range_limitation,
+                        &block_range.limit(max_block_range_limitation),
                     );
                     (
                         result,
@@ -1015,7 +1195,7 @@ async fn fetch_logs_once<P: ChainProvider + ?Sized>(
                     let new_to_block = calculate_process_historic_log_to_block(
                         &next_from_block,
                         &snapshot_to_block,
-                        &max_block_range_limitation,
+                        &block_range.limit(max_block_range_limitation),
                     );
                     (
                         result,
@@ -1042,6 +1222,14 @@ async fn fetch_logs_once<P: ChainProvider + ?Sized>(
             )
             .await
             {
+                block_range.record_rejected(
+                    (retry_result.to - retry_result.from).to::<u64>(),
+                    retry_result
+                        .max_block_range
+                        .filter(|max| Some(*max) != max_block_range_limitation)
+                        .map(|max| max.to::<u64>()),
+                );
+
                 return (
                     None,
                     Some(ProcessHistoricLogsStreamResult {
@@ -1091,6 +1279,7 @@ async fn live_indexing_stream(
     last_seen_block_number: U64,
     topic_id: &B256,
     reorg_safe_distance: &U64,
+    finality_tag: Option<BlockTag>,
     mut current_filter: RindexerEventFilter,
     info_log_name: &str,
     network: &str,
@@ -1108,10 +1297,11 @@ async fn live_indexing_stream(
     let mut heartbeat = HeartbeatTracker::new(Duration::from_secs(300));
     let target_iteration_duration = Duration::from_millis(200);
 
-    // Channel for reth-provided reorg signals (feature-gated, None for HTTP RPC).
-    // The spawned task converts ChainStateNotification → ReorgInfo and sends here;
+    // Channel for reorg signals pushed by a reth ExEx or a `newHeads` subscription (None for
+    // plain HTTP RPC). The spawned task converts ChainStateNotification → ReorgInfo and sends here;
     // the main loop try_recv()s to trigger the same recovery codepath as cache-based detection.
-    let (reth_reorg_tx, mut reth_reorg_rx) = mpsc::unbounded_channel::<ReorgInfo>();
+    let (notified_reorg_tx, mut notified_reorg_rx) =
+        mpsc::unbounded_channel::<(ReorgInfo, ChainStateSource)>();
 
     if let Some(notifications) = cached_provider.chain_state_notification() {
         let info_log_name = info_log_name.to_string();
@@ -1119,10 +1309,10 @@ async fn live_indexing_stream(
         tokio::spawn(async move {
             let mut rx = notifications.subscribe();
             while let Ok(notification) = rx.recv().await {
-                if let Some(reorg_info) =
+                if let Some(notified_reorg) =
                     handle_chain_notification(notification, &info_log_name, &network)
                 {
-                    let _ = reth_reorg_tx.send(reorg_info);
+                    let _ = notified_reorg_tx.send(notified_reorg);
                 }
             }
         });
@@ -1141,26 +1331,29 @@ async fn live_indexing_stream(
             break;
         }
 
-        // Reth reorg signal — instant detection via ExEx notification.
-        if let Ok(reth_reorg) = reth_reorg_rx.try_recv() {
-            let fork_block = reth_reorg.fork_block.to::<u64>();
+        // Pushed reorg signal — instant detection via a reth ExEx or `newHeads` notification.
+        if let Ok((notified_reorg, source)) = notified_reorg_rx.try_recv() {
+            let fork_block = notified_reorg.fork_block.to::<u64>();
             warn!(
-                "{} - REORG (reth notification): depth={}, fork_block={}",
-                info_log_name, reth_reorg.depth, fork_block
+                "{} - REORG ({} notification): depth={}, fork_block={}",
+                info_log_name,
+                source.as_str(),
+                notified_reorg.depth,
+                fork_block
             );
 
             // `fork_block` is the first reorged block and `depth` is the
             // inclusive count — last reorged block is `fork_block + depth - 1`.
             // An exclusive-end range covers exactly the reorged span and
             // degenerates to a no-op when depth == 0.
-            for b in fork_block..(fork_block + reth_reorg.depth) {
+            for b in fork_block..(fork_block + notified_reorg.depth) {
                 block_cache.pop(&b);
             }
 
             // Route through coordinator for full recovery (event deletion, checkpoint
             // rewind, derived table rollback, window update) when available.
             if let Some(coordinator) = reorg_coordinator.as_ref() {
-                let last_reverted = fork_block + reth_reorg.depth.saturating_sub(1);
+                let last_reverted = fork_block + notified_reorg.depth.saturating_sub(1);
                 // Mutex held across reorg handling (DB rollback, stream
                 // publishes in parallel, user on_reorg callback firing). On a
                 // real reorg this blocks the other indexing path for the
@@ -1168,7 +1361,7 @@ async fn live_indexing_stream(
                 // If latency becomes a concern, move handle_reorg out of the
                 // hot path.
                 let mut guard = coordinator.lock().await;
-                match guard.on_exex_reorg(fork_block, last_reverted) {
+                match guard.on_notified_reorg(source, fork_block, last_reverted) {
                     Ok(task) => {
                         let reorg_ctx = ReorgContext {
                             postgres: postgres.as_deref(),
@@ -1177,11 +1370,21 @@ async fn live_indexing_stream(
                             trace_registry,
                         };
                         if let Err(e) = guard.handle_reorg(task, &reorg_ctx).await {
-                            error!("{} - Failed to handle ExEx reorg: {:?}", info_log_name, e);
+                            error!(
+                                "{} - Failed to handle {} reorg: {:?}",
+                                info_log_name,
+                                source.as_str(),
+                                e
+                            );
                         }
                     }
                     Err(e) => {
-                        error!("{} - Invalid ExEx reorg range: {:?}", info_log_name, e);
+                        error!(
+                            "{} - Invalid {} reorg range: {:?}",
+                            info_log_name,
+                            source.as_str(),
+                            e
+                        );
                     }
                 }
             }
@@ -1191,7 +1394,7 @@ async fn live_indexing_stream(
                     logs: vec![],
                     from_block: U64::from(fork_block),
                     to_block: U64::from(fork_block),
-                    reorg: Some(reth_reorg),
+                    reorg: Some(notified_reorg),
                 }))
                 .await;
 
@@ -1303,11 +1506,16 @@ async fn live_indexing_stream(
                             last_seen_block_number
                         );
 
-                        let safe_block_number =
-                            latest_block_number.saturating_sub(*reorg_safe_distance);
+                        let safe_block_number = live_safe_block_number(
+                            cached_provider.as_ref(),
+                            finality_tag,
+                            latest_block_number,
+                            *reorg_safe_distance,
+                        )
+                        .await;
                         let from_block = current_filter.from_block();
                         if from_block > safe_block_number {
-                            if reorg_safe_distance.is_zero() {
+                            if reorg_safe_distance.is_zero() && finality_tag.is_none() {
                                 let block_distance = from_block - latest_block_number;
                                 let is_outside_reorg_range = block_distance
                                     > reorg_safe_distance_for_chain(cached_provider.chain().id());
@@ -1507,8 +1715,8 @@ async fn live_indexing_stream(
                                                 .set_from_block(U64::from(min_removed_block));
                                             last_seen_block_number =
                                                 U64::from(min_removed_block.saturating_sub(1));
-                                            // Drain any pending reth signals to avoid double recovery
-                                            while reth_reorg_rx.try_recv().is_ok() {}
+                                            // Drain any pending pushed signals to avoid double recovery
+                                            while notified_reorg_rx.try_recv().is_ok() {}
                                             continue;
                                         }
 
@@ -1663,9 +1871,9 @@ async fn live_indexing_stream(
 }
 
 #[derive(Debug)]
-struct RetryWithBlockRangeResult {
+pub(super) struct RetryWithBlockRangeResult {
     from: U64,
-    to: U64,
+    pub(super) to: U64,
     // This is only populated if you are using an RPC provider
     // who doesn't give block ranges, this tends to be providers
     // which are a lot slower than others, expect these providers
@@ -1674,7 +1882,7 @@ struct RetryWithBlockRangeResult {
 }
 
 /// Attempts to retry with a new block range based on the error message.
-async fn retry_with_block_range(
+pub(super) async fn retry_with_block_range(
     info_log_name: &str,
     error: &ProviderError,
     from_block: U64,
@@ -1995,7 +2203,7 @@ mod tests {
     use crate::blockclock::BlockClock;
     use crate::event::RindexerEventFilter;
     use crate::provider::mock::MockChainProvider;
-    use alloy::primitives::Log as PrimitiveLog;
+    use alloy::primitives::{Log as PrimitiveLog, LogData};
     use alloy::rpc::types::Log;
     use tokio::sync::mpsc;
 
@@ -2082,6 +2290,7 @@ mod tests {
             &B256::ZERO,
             filter,
             None,
+            &AdaptiveBlockRange::new("test", "default", "test"),
             U64::from(500),
             "test",
         )
@@ -2108,6 +2317,7 @@ mod tests {
             &B256::ZERO,
             filter,
             None,
+            &AdaptiveBlockRange::new("test", "default", "test"),
             U64::from(500),
             "test",
         )
@@ -2134,6 +2344,7 @@ mod tests {
             &B256::ZERO,
             filter,
             None,
+            &AdaptiveBlockRange::new("test", "default", "test"),
             U64::from(500),
             "test",
         )
@@ -2164,6 +2375,7 @@ mod tests {
             &B256::ZERO,
             filter,
             None,
+            &AdaptiveBlockRange::new("test", "default", "test"),
             U64::from(500),
             "test",
         )
@@ -2206,6 +2418,7 @@ mod tests {
             &B256::ZERO,
             filter,
             None,
+            &AdaptiveBlockRange::new("test", "default", "test"),
             U64::from(7),
             "test",
         )
@@ -2236,6 +2449,7 @@ mod tests {
             &B256::ZERO,
             filter,
             Some(U64::from(50)), // max range = 50
+            &AdaptiveBlockRange::new("test", "default", "test"),
             U64::from(5000),
             "test",
         )
@@ -2247,6 +2461,121 @@ mod tests {
         assert_eq!(result.next.to_block(), U64::from(251));
     }
 
+    // --- fetch_archived_logs_stream tests ---
+
+    /// Archive holding chunks of 100 blocks from 0 to 299, one log per 50 blocks.
+    struct InMemoryLogSource;
+
+    #[async_trait::async_trait]
+    impl HistoricalLogSource for InMemoryLogSource {
+        fn chunk_to_block(&self, from_block: U64) -> Option<U64> {
+            let from_block = from_block.to::<u64>();
+            (from_block < 300).then_some(U64::from(from_block / 100 * 100 + 99))
+        }
+
+        async fn get_logs(
+            &self,
+            _: &B256,
+            from_block: U64,
+            to_block: U64,
+        ) -> Result<Vec<Log>, crate::indexer::log_archive::LogArchiveError> {
+            Ok((from_block.to::<u64>()..=to_block.to::<u64>())
+                .filter(|block| block % 50 == 0)
+                .map(|block| {
+                    let mut log = make_log_at_block(block);
+                    log.inner.data = LogData::new_unchecked(vec![B256::ZERO], Default::default());
+                    log
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn archived_logs_are_sent_per_chunk_and_rpc_resumes_after_archive() {
+        let (tx, mut rx) = mpsc::channel(8);
+        let filter = RindexerEventFilter::empty_for_test()
+            .set_from_block(U64::from(120))
+            .set_to_block(U64::from(1000));
+
+        let next = fetch_archived_logs_stream(
+            &InMemoryLogSource,
+            false,
+            BlockClock::new(None, None, Arc::new(MockChainProvider::new(1))),
+            &tx,
+            &B256::ZERO,
+            filter,
+            U64::from(1000),
+            "test",
+        )
+        .await;
+        drop(tx);
+
+        let mut ranges = Vec::new();
+        while let Some(Ok(result)) = rx.recv().await {
+            let blocks: Vec<u64> =
+                result.logs.iter().map(|log| log.block_number.unwrap()).collect();
+            ranges.push((result.from_block.to::<u64>(), result.to_block.to::<u64>(), blocks));
+        }
+
+        assert_eq!(ranges, vec![(120, 199, vec![150]), (200, 299, vec![200, 250])]);
+        assert_eq!(next.from_block(), U64::from(300));
+        assert_eq!(next.to_block(), U64::from(1000));
+    }
+
+    #[tokio::test]
+    async fn archived_logs_stop_at_snapshot() {
+        let (tx, mut rx) = mpsc::channel(8);
+        let filter = RindexerEventFilter::empty_for_test()
+            .set_from_block(U64::from(0))
+            .set_to_block(U64::from(149));
+
+        let next = fetch_archived_logs_stream(
+            &InMemoryLogSource,
+            false,
+            BlockClock::new(None, None, Arc::new(MockChainProvider::new(1))),
+            &tx,
+            &B256::ZERO,
+            filter,
+            U64::from(149),
+            "test",
+        )
+        .await;
+        drop(tx);
+
+        let mut to_blocks = Vec::new();
+        while let Some(Ok(result)) = rx.recv().await {
+            to_blocks.push(result.to_block.to::<u64>());
+        }
+
+        assert_eq!(to_blocks, vec![99, 149]);
+        assert!(next.from_block() > U64::from(149), "the RPC must not fetch archived blocks again");
+    }
+
+    #[tokio::test]
+    async fn archived_logs_skipped_when_archive_does_not_hold_from_block() {
+        let (tx, mut rx) = mpsc::channel(8);
+        let filter = RindexerEventFilter::empty_for_test()
+            .set_from_block(U64::from(500))
+            .set_to_block(U64::from(1000));
+
+        let next = fetch_archived_logs_stream(
+            &InMemoryLogSource,
+            false,
+            BlockClock::new(None, None, Arc::new(MockChainProvider::new(1))),
+            &tx,
+            &B256::ZERO,
+            filter,
+            U64::from(1000),
+            "test",
+        )
+        .await;
+        drop(tx);
+
+        assert!(rx.recv().await.is_none());
+        assert_eq!(next.from_block(), U64::from(500));
+        assert_eq!(next.to_block(), U64::from(1000));
+    }
+
     // --- retry_with_block_range tests ---
 
     #[tokio::test]
@@ -2678,8 +3007,18 @@ mod tests {
                 .set_to_block(U64::from(200));
 
             let bc = BlockClock::new(None, None, Arc::new(MockChainProvider::new(1)));
-            let (result, next, kind) =
-                fetch_logs_once(false, &bc, &mock, filter, None, U64::from(500), "test").await;
+            let block_range = AdaptiveBlockRange::new("test", "default", "test");
+            let (result, next, kind) = fetch_logs_once(
+                false,
+                &bc,
+                &mock,
+                filter,
+                None,
+                &block_range,
+                U64::from(500),
+                "test",
+            )
+            .await;
 
             let r =
                 result.expect("empty logs still return a result so sink can advance checkpoint");
@@ -2691,6 +3030,34 @@ mod tests {
             assert!(kind.is_none(), "success path reports no error kind");
         }
 
+        #[tokio::test]
+        async fn fetch_logs_once_requests_the_learned_range() {
+            let mock = MockChainProvider::new(1);
+            let filter = RindexerEventFilter::empty_for_test()
+                .set_from_block(U64::from(100))
+                .set_to_block(U64::from(200));
+
+            let bc = BlockClock::new(None, None, Arc::new(MockChainProvider::new(1)));
+            let block_range = AdaptiveBlockRange::new("test", "default", "test");
+            block_range.record_rejected(50, None);
+            let (_result, next, _kind) = fetch_logs_once(
+                false,
+                &bc,
+                &mock,
+                filter,
+                None,
+                &block_range,
+                U64::from(500),
+                "test",
+            )
+            .await;
+
+            let next = next.expect("range not exhausted");
+            assert_eq!(next.next.from_block(), U64::from(201));
+            // sparse and fast, the learned range doubles
+            assert_eq!(next.next.to_block(), U64::from(301));
+        }
+
         #[tokio::test]
         async fn fetch_logs_once_past_snapshot_returns_no_next() {
             let mock = MockChainProvider::new(1);
@@ -2699,8 +3066,18 @@ mod tests {
                 .set_to_block(U64::from(500));
 
             let bc = BlockClock::new(None, None, Arc::new(MockChainProvider::new(1)));
-            let (_result, next, _kind) =
-                fetch_logs_once(false, &bc, &mock, filter, None, U64::from(500), "test").await;
+            let block_range = AdaptiveBlockRange::new("test", "default", "test");
+            let (_result, next, _kind) = fetch_logs_once(
+                false,
+                &bc,
+                &mock,
+                filter,
+                None,
+                &block_range,
+                U64::from(500),
+                "test",
+            )
+            .await;
 
             assert!(next.is_none(), "no further work past snapshot_to_block");
         }
@@ -2714,8 +3091,18 @@ mod tests {
                 .set_to_block(U64::from(200));
 
             let bc = BlockClock::new(None, None, Arc::new(MockChainProvider::new(1)));
-            let (result, next, _kind) =
-                fetch_logs_once(false, &bc, &mock, filter, None, U64::from(500), "test").await;
+            let block_range = AdaptiveBlockRange::new("test", "default", "test");
+            let (result, next, _kind) = fetch_logs_once(
+                false,
+                &bc,
+                &mock,
+                filter,
+                None,
+                &block_range,
+                U64::from(500),
+                "test",
+            )
+            .await;
 
             let r = result.expect("should return logs");
             assert_eq!(r.logs.len(), 2);
@@ -2732,8 +3119,18 @@ mod tests {
                 .set_to_block(U64::from(200));
 
             let bc = BlockClock::new(None, None, Arc::new(MockChainProvider::new(1)));
-            let (result, next, _kind) =
-                fetch_logs_once(false, &bc, &mock, filter, None, U64::from(500), "test").await;
+            let block_range = AdaptiveBlockRange::new("test", "default", "test");
+            let (result, next, _kind) = fetch_logs_once(
+                false,
+                &bc,
+                &mock,
+                filter,
+                None,
+                &block_range,
+                U64::from(500),
+                "test",
+            )
+            .await;
 
             assert!(result.is_none(), "no logs emitted for inverted range");
             let next = next.expect("self-correction returns a fixed next filter");
@@ -261,6 +261,33 @@ async fn update_last_synced_block_number_for_file(
     Ok(())
 }
 
+/// Rewind the last synced block file to `to_block` after a reorg, files already at or below it
+/// are left untouched.
+pub async fn rewind_last_synced_block_number_file(
+    full_path: &Path,
+    contract_name: &str,
+    network: &str,
+    event_name: &str,
+    to_block: U64,
+) -> Result<(), UpdateLastSyncedBlockNumberFile> {
+    let last_block =
+        get_last_synced_block_number_file(full_path, contract_name, network, event_name).await?;
+
+    if last_block.is_some_and(|last_block| last_block > to_block) {
+        let file_path =
+            build_last_synced_block_number_file(full_path, contract_name, network, event_name);
+        let temp_file_path = format!("{file_path}.tmp");
+
+        let mut file = File::create(&temp_file_path).await?;
+        file.write_all(to_block.to_string().as_bytes()).await?;
+        file.sync_all().await?;
+
+        fs::rename(temp_file_path, file_path).await?;
+    }
+
+    Ok(())
+}
+
 /// Update the last indexed block.
 ///
 /// Note: this is an async task and should be awaited rather than spawned in the background
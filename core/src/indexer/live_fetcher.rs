@@ -0,0 +1,897 @@
+use crate::blockclock::BlockClock;
+use crate::database::clickhouse::client::ClickhouseClient;
+use crate::event::callback_registry::{EventCallbackRegistry, TraceCallbackRegistry};
+use crate::event::config::EventProcessingConfig;
+use crate::event::{CombinedEventFilter, RindexerEventFilter};
+use crate::helpers::{halved_block_number, is_relevant_block};
+use crate::indexer::fetch_logs::{retry_with_block_range, BlockMeta, FetchLogsResult, ReorgInfo};
+use crate::indexer::heartbeat::{HeartbeatAction, HeartbeatTracker};
+use crate::indexer::reorg::{
+    detect_and_handle_reorg, handle_chain_notification, ReorgContext, ReorgCoordinator,
+};
+use crate::indexer::IndexingEventProgressStatus;
+use crate::manifest::contract::BlockTag;
+use crate::metrics::indexing as metrics;
+use crate::notifications::ChainStateSource;
+use crate::provider::ChainProvider;
+use crate::{is_running, PostgresClient};
+use alloy::primitives::{Address, B256, U64};
+use alloy::rpc::types::{Log, Topic};
+use lru::LruCache;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+type LogsSender = mpsc::Sender<Result<FetchLogsResult, Box<dyn Error + Send>>>;
+type TaggedBlocks = HashMap<BlockTag, Option<U64>>;
+
+/// An event handed over to the [`NetworkLiveFetcher`] once its historic sync is done.
+///
+/// The logs of the event are sent to `tx`, the same channel the per event live stream would
+/// have used, so the processing side does not know whether the logs came from a shared fetcher.
+pub struct LiveSubscription {
+    pub info_log_name: String,
+    pub topic_id: B256,
+    /// The `from_block` is the next block this event has to process.
+    pub filter: RindexerEventFilter,
+    pub indexing_distance_from_head: U64,
+    /// Follow this block tag instead of `indexing_distance_from_head` when set.
+    pub finality_tag: Option<BlockTag>,
+    pub timestamps: bool,
+    pub block_clock: BlockClock,
+    pub postgres: Option<Arc<PostgresClient>>,
+    pub clickhouse: Option<Arc<ClickhouseClient>>,
+    pub registry: Arc<EventCallbackRegistry>,
+    pub tx: LogsSender,
+}
+
+impl LiveSubscription {
+    pub fn new(
+        config: &EventProcessingConfig,
+        filter: RindexerEventFilter,
+        tx: LogsSender,
+    ) -> Self {
+        Self {
+            info_log_name: config.info_log_name(),
+            topic_id: config.topic_id(),
+            filter,
+            indexing_distance_from_head: config.indexing_distance_from_head(),
+            finality_tag: config.finality_tag(),
+            timestamps: config.timestamps(),
+            block_clock: config.network_contract().block_clock.clone(),
+            postgres: config.postgres(),
+            clickhouse: config.clickhouse(),
+            registry: config.registry(),
+            tx,
+        }
+    }
+
+    fn next_block(&self) -> U64 {
+        self.filter.from_block()
+    }
+
+    fn safe_block(&self, latest_block_number: U64, tagged_blocks: &TaggedBlocks) -> U64 {
+        match self.finality_tag.and_then(|tag| tagged_blocks.get(&tag).copied().flatten()) {
+            Some(tagged_block) => tagged_block.min(latest_block_number),
+            None => latest_block_number.saturating_sub(self.indexing_distance_from_head),
+        }
+    }
+
+    /// Move the subscription back to `fork_block` if it already processed past it.
+    fn rewind(&mut self, fork_block: u64) {
+        if self.next_block() > U64::from(fork_block) {
+            self.filter = self.filter.clone().set_from_block(U64::from(fork_block));
+        }
+    }
+
+    async fn send(&self, result: FetchLogsResult) -> bool {
+        if self.tx.capacity() == 0 {
+            warn!(
+                "{} - {} - Log channel full, live indexer will wait for events to be processed.",
+                self.info_log_name,
+                IndexingEventProgressStatus::live_log(),
+            );
+        }
+
+        if let Err(e) = self.tx.send(Ok(result)).await {
+            error!(
+                "{} - {} - Failed to send logs to stream consumer! Err: {}",
+                self.info_log_name,
+                IndexingEventProgressStatus::live_log(),
+                e
+            );
+            return false;
+        }
+
+        true
+    }
+}
+
+/// Live indexing for every event of a network with a single poller.
+///
+/// Instead of one `live_indexing_stream` per event, the fetcher polls the head once per
+/// iteration, runs one reorg check, keeps one block cache and fetches the logs of every
+/// subscribed event with a combined `eth_getLogs`. The logs are then fanned out to the
+/// subscriptions. A slow consumer applies backpressure to the whole network.
+pub struct NetworkLiveFetcher {
+    network: String,
+    cached_provider: Arc<dyn ChainProvider>,
+    disable_logs_bloom_checks: bool,
+    reorg_coordinator: Option<Arc<Mutex<ReorgCoordinator>>>,
+    trace_registry: Option<Arc<TraceCallbackRegistry>>,
+    cancel_token: CancellationToken,
+    subscriptions_tx: mpsc::UnboundedSender<LiveSubscription>,
+    subscriptions_rx: std::sync::Mutex<Option<mpsc::UnboundedReceiver<LiveSubscription>>>,
+}
+
+impl NetworkLiveFetcher {
+    pub fn new(
+        network: String,
+        cached_provider: Arc<dyn ChainProvider>,
+        disable_logs_bloom_checks: bool,
+        reorg_coordinator: Option<Arc<Mutex<ReorgCoordinator>>>,
+        trace_registry: Option<Arc<TraceCallbackRegistry>>,
+        cancel_token: CancellationToken,
+    ) -> Arc<Self> {
+        let (subscriptions_tx, subscriptions_rx) = mpsc::unbounded_channel();
+
+        Arc::new(Self {
+            network,
+            cached_provider,
+            disable_logs_bloom_checks,
+            reorg_coordinator,
+            trace_registry,
+            cancel_token,
+            subscriptions_tx,
+            subscriptions_rx: std::sync::Mutex::new(Some(subscriptions_rx)),
+        })
+    }
+
+    /// Hand an event over to the fetcher, the polling loop is started on the first subscription.
+    pub fn subscribe(self: &Arc<Self>, subscription: LiveSubscription) {
+        debug!(
+            "{} - {} - Joined shared live fetcher for {}",
+            subscription.info_log_name,
+            IndexingEventProgressStatus::live_log(),
+            self.network
+        );
+
+        if self.subscriptions_tx.send(subscription).is_err() {
+            error!("{} - Shared live fetcher is not running anymore", self.network);
+            return;
+        }
+
+        let subscriptions_rx =
+            self.subscriptions_rx.lock().expect("live fetcher lock poisoned").take();
+        if let Some(subscriptions_rx) = subscriptions_rx {
+            tokio::spawn(Arc::clone(self).run(subscriptions_rx));
+        }
+    }
+
+    async fn run(self: Arc<Self>, mut subscriptions_rx: mpsc::UnboundedReceiver<LiveSubscription>) {
+        info!(
+            "{} - {} - Starting shared live fetcher",
+            self.network,
+            IndexingEventProgressStatus::live_log()
+        );
+
+        let mut subscriptions: Vec<LiveSubscription> = Vec::new();
+        let mut log_response_to_large_to_block: Option<U64> = None;
+        let mut heartbeat = HeartbeatTracker::new(Duration::from_secs(300));
+        let target_iteration_duration = Duration::from_millis(200);
+
+        // Same as `live_indexing_stream`, pushed reorg signals are converted to `ReorgInfo` and
+        // picked up at the start of an iteration.
+        let (notified_reorg_tx, mut notified_reorg_rx) =
+            mpsc::unbounded_channel::<(ReorgInfo, ChainStateSource)>();
+        if let Some(notifications) = self.cached_provider.chain_state_notification() {
+            let network = self.network.clone();
+            tokio::spawn(async move {
+                let mut rx = notifications.subscribe();
+                while let Ok(notification) = rx.recv().await {
+                    if let Some(notified_reorg) =
+                        handle_chain_notification(notification, &network, &network)
+                    {
+                        let _ = notified_reorg_tx.send(notified_reorg);
+                    }
+                }
+            });
+        }
+
+        // One cache of recent block metadata for the whole network, see `live_indexing_stream`.
+        let mut block_cache: LruCache<u64, BlockMeta> =
+            LruCache::new(NonZeroUsize::new(1024).unwrap());
+
+        loop {
+            let iteration_start = Instant::now();
+
+            if !is_running() || self.cancel_token.is_cancelled() {
+                break;
+            }
+
+            while let Ok(subscription) = subscriptions_rx.try_recv() {
+                subscriptions.push(subscription);
+            }
+            subscriptions.retain(|subscription| !subscription.tx.is_closed());
+
+            if subscriptions.is_empty() {
+                tokio::time::sleep(target_iteration_duration).await;
+                continue;
+            }
+
+            if let Ok((notified_reorg, source)) = notified_reorg_rx.try_recv() {
+                let fork_block = notified_reorg.fork_block.to::<u64>();
+                warn!(
+                    "{} - REORG ({} notification): depth={}, fork_block={}",
+                    self.network,
+                    source.as_str(),
+                    notified_reorg.depth,
+                    fork_block
+                );
+
+                for b in fork_block..(fork_block + notified_reorg.depth) {
+                    block_cache.pop(&b);
+                }
+
+                if let Some(coordinator) = self.reorg_coordinator.as_ref() {
+                    let last_reverted = fork_block + notified_reorg.depth.saturating_sub(1);
+                    let reorg_ctx = self.reorg_context(&subscriptions[0]);
+                    let mut guard = coordinator.lock().await;
+                    match guard.on_notified_reorg(source, fork_block, last_reverted) {
+                        Ok(task) => {
+                            if let Err(e) = guard.handle_reorg(task, &reorg_ctx).await {
+                                error!(
+                                    "{} - Failed to handle {} reorg: {:?}",
+                                    self.network,
+                                    source.as_str(),
+                                    e
+                                );
+                            }
+                        }
+                        Err(e) => {
+                            error!(
+                                "{} - Invalid {} reorg range: {:?}",
+                                self.network,
+                                source.as_str(),
+                                e
+                            );
+                        }
+                    }
+                }
+
+                for subscription in subscriptions.iter_mut() {
+                    subscription
+                        .send(FetchLogsResult {
+                            logs: vec![],
+                            from_block: U64::from(fork_block),
+                            to_block: U64::from(fork_block),
+                            reorg: Some(ReorgInfo {
+                                fork_block: notified_reorg.fork_block,
+                                depth: notified_reorg.depth,
+                                affected_tx_hashes: notified_reorg.affected_tx_hashes.clone(),
+                            }),
+                        })
+                        .await;
+                    subscription.rewind(fork_block);
+                }
+                continue;
+            }
+
+            let latest_block = match self.cached_provider.get_latest_block().await {
+                Ok(Some(latest_block)) => latest_block,
+                Ok(None) => {
+                    info!("WARNING - empty latest block returned from provider, will try again in 200ms");
+                    tokio::time::sleep(target_iteration_duration).await;
+                    continue;
+                }
+                Err(e) => {
+                    error!(
+                        "Error getting latest block, will try again in 1 second - err: {}",
+                        e.to_string()
+                    );
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+            };
+
+            block_cache.put(
+                latest_block.header.number,
+                BlockMeta {
+                    hash: latest_block.header.hash,
+                    parent_hash: latest_block.header.parent_hash,
+                    timestamp: latest_block.header.timestamp,
+                },
+            );
+
+            let latest_tip = U64::from(latest_block.header.number);
+            match heartbeat.tick(latest_tip) {
+                HeartbeatAction::Silent => {}
+                HeartbeatAction::Alive => {
+                    info!(
+                        "{} - {} - Indexing alive - chain tip {}, {} events live",
+                        self.network,
+                        IndexingEventProgressStatus::live_log(),
+                        latest_tip,
+                        subscriptions.len()
+                    );
+                }
+                HeartbeatAction::Stalled => {
+                    warn!(
+                        "{} - {} - RPC tip has not advanced past block {} in the last 5 minutes",
+                        self.network,
+                        IndexingEventProgressStatus::live_log(),
+                        latest_tip
+                    );
+                }
+            }
+
+            if let Some(coordinator) = self.reorg_coordinator.as_ref() {
+                let log_prefix =
+                    format!("{} - {}", self.network, IndexingEventProgressStatus::live_log());
+                let reorg_ctx = self.reorg_context(&subscriptions[0]);
+                let mut guard = coordinator.lock().await;
+                match detect_and_handle_reorg(
+                    &mut guard,
+                    latest_block.header.number,
+                    latest_block.header.hash,
+                    latest_block.header.parent_hash,
+                    &log_prefix,
+                    &reorg_ctx,
+                )
+                .await
+                {
+                    Ok(Some(fork_point)) => {
+                        drop(guard);
+                        for subscription in subscriptions.iter_mut() {
+                            subscription.rewind(fork_point);
+                        }
+                        continue;
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        error!(
+                            "{} - Reorg handling failed, pausing before retry: {:?}",
+                            self.network, e
+                        );
+                        tokio::time::sleep(Duration::from_secs(2)).await;
+                        continue;
+                    }
+                }
+            }
+
+            let latest_block_number = log_response_to_large_to_block.unwrap_or(latest_tip);
+
+            // Tagged blocks are looked up once per iteration and shared by every subscription,
+            // a failed lookup falls back to the fixed distance.
+            let mut tagged_blocks = TaggedBlocks::new();
+            for tag in subscriptions.iter().filter_map(|s| s.finality_tag) {
+                if tagged_blocks.contains_key(&tag) {
+                    continue;
+                }
+                let tagged_block = match self.cached_provider.get_tagged_block_number(tag).await {
+                    Ok(tagged_block) => tagged_block,
+                    Err(e) => {
+                        debug!("{} - Failed to fetch {:?} block: {}", self.network, tag, e);
+                        None
+                    }
+                };
+                tagged_blocks.insert(tag, tagged_block);
+            }
+
+            let ready = subscriptions
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| {
+                    s.next_block() <= s.safe_block(latest_block_number, &tagged_blocks)
+                })
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>();
+
+            if ready.is_empty() {
+                debug!(
+                    "{} - {} - No new blocks to process...",
+                    self.network,
+                    IndexingEventProgressStatus::live_log()
+                );
+                sleep_remaining(iteration_start, target_iteration_duration).await;
+                continue;
+            }
+
+            let from_block = ready.iter().map(|&i| subscriptions[i].next_block()).min().unwrap();
+            let to_block = ready
+                .iter()
+                .map(|&i| subscriptions[i].safe_block(latest_block_number, &tagged_blocks))
+                .max()
+                .unwrap();
+
+            let mut targets: Vec<(usize, Option<HashSet<Address>>)> = Vec::new();
+            for &i in &ready {
+                let subscription = &mut subscriptions[i];
+                let addresses = subscription.filter.contract_addresses().await;
+                let next_block = subscription.next_block();
+                let safe_block = subscription.safe_block(latest_block_number, &tagged_blocks);
+
+                // The bloom shortcut only holds when the single block to fetch IS the tip.
+                let bloom_check_applies = next_block == latest_tip
+                    && next_block == safe_block
+                    && !self.disable_logs_bloom_checks;
+                if bloom_check_applies
+                    && !is_relevant_block(&addresses, &subscription.topic_id, &latest_block)
+                {
+                    debug!(
+                        "{} - {} - Did not need to hit RPC as no events in {} block - LogsBloom for block checked",
+                        subscription.info_log_name,
+                        IndexingEventProgressStatus::live_log(),
+                        next_block
+                    );
+                    let sent = subscription
+                        .send(FetchLogsResult {
+                            logs: Vec::new(),
+                            from_block: next_block,
+                            to_block: safe_block,
+                            reorg: None,
+                        })
+                        .await;
+                    if sent {
+                        subscription.filter =
+                            subscription.filter.clone().set_from_block(safe_block + U64::from(1));
+                    }
+                    continue;
+                }
+
+                targets.push((i, addresses));
+            }
+
+            if targets.is_empty() {
+                sleep_remaining(iteration_start, target_iteration_duration).await;
+                continue;
+            }
+
+            let (combined_filters, target_filters) = combine_filters(
+                targets.iter().map(|(i, addresses)| {
+                    let filter = &subscriptions[*i].filter;
+                    (
+                        subscriptions[*i].topic_id,
+                        addresses,
+                        [filter.topic1(), filter.topic2(), filter.topic3()],
+                    )
+                }),
+                from_block,
+                to_block,
+            );
+
+            let mut fetched: Vec<Vec<Log>> = Vec::with_capacity(combined_filters.len());
+            let mut fetch_error = None;
+            for combined_filter in &combined_filters {
+                debug!(
+                    "{} - {} - Processing combined live filter: {:?}",
+                    self.network,
+                    IndexingEventProgressStatus::live_log(),
+                    combined_filter
+                );
+                match self.cached_provider.get_combined_logs(combined_filter).await {
+                    Ok(logs) => fetched.push(logs),
+                    Err(err) => {
+                        fetch_error = Some(err);
+                        break;
+                    }
+                }
+            }
+
+            if let Some(err) = fetch_error {
+                if let Some(retry_result) = retry_with_block_range(
+                    &self.network,
+                    &err,
+                    from_block,
+                    to_block,
+                    self.cached_provider.max_block_range(),
+                )
+                .await
+                {
+                    debug!(
+                        "{} - {} - Overfetched from {} to {} - shrinking to block range: from {} to {}",
+                        self.network,
+                        IndexingEventProgressStatus::live_log(),
+                        from_block,
+                        to_block,
+                        from_block,
+                        retry_result.to
+                    );
+                    log_response_to_large_to_block = Some(retry_result.to);
+                } else {
+                    let halved_to_block = halved_block_number(to_block, from_block);
+                    error!(
+                        "{} - {} - Unexpected error fetching logs in range {} - {}. Retry fetching {} - {}: {:?}",
+                        self.network,
+                        IndexingEventProgressStatus::live_log(),
+                        from_block,
+                        to_block,
+                        from_block,
+                        halved_to_block,
+                        err
+                    );
+                    log_response_to_large_to_block = Some(halved_to_block);
+                }
+                sleep_remaining(iteration_start, target_iteration_duration).await;
+                continue;
+            }
+
+            // Reorg detection: the RPC provider signals reorged events via removed=true
+            let min_removed_block = fetched
+                .iter()
+                .flatten()
+                .filter(|l| l.removed)
+                .map(|l| l.block_number.unwrap_or(from_block.to::<u64>()))
+                .min();
+            if let Some(min_removed_block) = min_removed_block {
+                let depth = from_block.to::<u64>().saturating_sub(min_removed_block);
+                metrics::record_reorg(&self.network, depth);
+                warn!(
+                    "{} - REORG (removed logs): fork_block={}, depth={}",
+                    self.network, min_removed_block, depth
+                );
+
+                for b in min_removed_block..=to_block.to::<u64>() {
+                    block_cache.pop(&b);
+                }
+
+                if let Some(coordinator) = self.reorg_coordinator.as_ref() {
+                    let reorg_ctx = self.reorg_context(&subscriptions[0]);
+                    let mut guard = coordinator.lock().await;
+                    match guard
+                        .try_create_reorg_task_for_block_range(min_removed_block, to_block.to())
+                    {
+                        Ok(task) => {
+                            if let Err(e) = guard.handle_reorg(task, &reorg_ctx).await {
+                                error!(
+                                    "{} - Failed to handle removed-logs reorg: {}",
+                                    self.network, e
+                                );
+                            }
+                        }
+                        Err(e) => {
+                            error!("{} - Invalid removed-logs reorg range: {:?}", self.network, e);
+                        }
+                    }
+                } else {
+                    for subscription in subscriptions.iter() {
+                        subscription
+                            .send(FetchLogsResult {
+                                logs: vec![],
+                                from_block: U64::from(min_removed_block),
+                                to_block: U64::from(min_removed_block),
+                                reorg: Some(ReorgInfo {
+                                    fork_block: U64::from(min_removed_block),
+                                    depth,
+                                    affected_tx_hashes: vec![],
+                                }),
+                            })
+                            .await;
+                    }
+                }
+
+                for subscription in subscriptions.iter_mut() {
+                    subscription.rewind(min_removed_block);
+                }
+                // Drain any pending pushed signals to avoid double recovery
+                while notified_reorg_rx.try_recv().is_ok() {}
+                continue;
+            }
+
+            log_response_to_large_to_block = None;
+
+            // Attach timestamp from cached block metadata to the logs to prevent any further
+            // fetches.
+            for logs in fetched.iter_mut() {
+                for log in logs.iter_mut() {
+                    if let Some(meta) = log.block_number.and_then(|n| block_cache.get(&n)) {
+                        log.block_timestamp = Some(meta.timestamp);
+                    }
+                }
+            }
+
+            for ((i, addresses), filter_index) in targets.iter().zip(&target_filters) {
+                let subscription = &mut subscriptions[*i];
+                let sub_from_block = subscription.next_block();
+                let sub_to_block = subscription.safe_block(latest_block_number, &tagged_blocks);
+
+                let logs = fetched[*filter_index]
+                    .iter()
+                    .filter(|log| {
+                        log.block_number.is_some_and(|n| {
+                            n >= sub_from_block.to::<u64>() && n <= sub_to_block.to::<u64>()
+                        })
+                    })
+                    .filter(|log| {
+                        log_matches(log, &subscription.topic_id, addresses, &subscription.filter)
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                debug!(
+                    "{} - {} - Fetched {} event logs - blocks: {} - {}",
+                    subscription.info_log_name,
+                    IndexingEventProgressStatus::live_log(),
+                    logs.len(),
+                    sub_from_block,
+                    sub_to_block
+                );
+
+                let logs = if subscription.timestamps {
+                    match subscription.block_clock.attach_log_timestamps(logs).await {
+                        Ok(logs_with_ts) => logs_with_ts,
+                        Err(_) => {
+                            // Not advancing the subscription makes the next iteration refetch it.
+                            error!(
+                                "{} - Error getting blocktime, will try again next iteration",
+                                subscription.info_log_name
+                            );
+                            continue;
+                        }
+                    }
+                } else {
+                    logs
+                };
+
+                let sent = subscription
+                    .send(FetchLogsResult {
+                        logs,
+                        from_block: sub_from_block,
+                        to_block: sub_to_block,
+                        reorg: None,
+                    })
+                    .await;
+                if sent {
+                    subscription.filter =
+                        subscription.filter.clone().set_from_block(sub_to_block + U64::from(1));
+                }
+            }
+
+            sleep_remaining(iteration_start, target_iteration_duration).await;
+        }
+
+        info!(
+            "{} - {} - Stopped shared live fetcher",
+            self.network,
+            IndexingEventProgressStatus::live_log()
+        );
+    }
+
+    fn reorg_context<'a>(&'a self, subscription: &'a LiveSubscription) -> ReorgContext<'a> {
+        ReorgContext {
+            postgres: subscription.postgres.as_deref(),
+            clickhouse: subscription.clickhouse.as_ref(),
+            registry: Some(&subscription.registry),
+            trace_registry: self.trace_registry.as_deref(),
+        }
+    }
+}
+
+async fn sleep_remaining(iteration_start: Instant, target_iteration_duration: Duration) {
+    let elapsed = iteration_start.elapsed();
+    if elapsed < target_iteration_duration {
+        tokio::time::sleep(target_iteration_duration - elapsed).await;
+    }
+}
+
+/// Builds the combined filters for a set of `(topic_id, addresses, indexed topics)` targets,
+/// returning them with the index of the filter each target was put in.
+///
+/// Targets share a filter when they agree on the indexed topics, so an indexed filter still
+/// narrows the request on the node. Within those, events bound to addresses share one filter
+/// over the union of their addresses, and events without addresses get their own address-less
+/// filter so they never widen the address-bound request to every contract on chain.
+fn combine_filters<'a>(
+    targets: impl Iterator<Item = (B256, &'a Option<HashSet<Address>>, [Topic; 3])>,
+    from_block: U64,
+    to_block: U64,
+) -> (Vec<CombinedEventFilter>, Vec<usize>) {
+    let mut filters: Vec<CombinedEventFilter> = Vec::new();
+    let mut target_filters = Vec::new();
+
+    for (topic_id, target_addresses, topics) in targets {
+        let index = match filters
+            .iter()
+            .position(|f| f.addresses.is_some() == target_addresses.is_some() && f.topics == topics)
+        {
+            Some(index) => index,
+            None => {
+                filters.push(CombinedEventFilter {
+                    addresses: target_addresses.as_ref().map(|_| HashSet::new()),
+                    topic_ids: Vec::new(),
+                    topics,
+                    from_block,
+                    to_block,
+                });
+                filters.len() - 1
+            }
+        };
+
+        let filter = &mut filters[index];
+        if let (Some(addresses), Some(target_addresses)) =
+            (filter.addresses.as_mut(), target_addresses)
+        {
+            addresses.extend(target_addresses.iter().copied());
+        }
+        if !filter.topic_ids.contains(&topic_id) {
+            filter.topic_ids.push(topic_id);
+        }
+        target_filters.push(index);
+    }
+
+    (filters, target_filters)
+}
+
+/// Whether a log from a combined fetch belongs to the event of `filter`.
+pub(crate) fn log_matches(
+    log: &Log,
+    topic_id: &B256,
+    addresses: &Option<HashSet<Address>>,
+    filter: &RindexerEventFilter,
+) -> bool {
+    let topics = log.topics();
+    if topics.first() != Some(topic_id) {
+        return false;
+    }
+
+    if let Some(addresses) = addresses {
+        if !addresses.contains(&log.address()) {
+            return false;
+        }
+    }
+
+    [filter.topic1(), filter.topic2(), filter.topic3()].iter().enumerate().all(|(i, topic)| {
+        topic.is_empty() || topics.get(i + 1).is_some_and(|value| topic.matches(value))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::rindexer_event_filter::SimpleEventFilter;
+    use alloy::primitives::{Bytes, Log as PrimitiveLog, LogData};
+
+    fn topic(n: u8) -> B256 {
+        B256::from([n; 32])
+    }
+
+    fn address(n: u8) -> Address {
+        Address::from([n; 20])
+    }
+
+    fn make_log(address: Address, topics: Vec<B256>) -> Log {
+        Log {
+            inner: PrimitiveLog { address, data: LogData::new_unchecked(topics, Bytes::new()) },
+            block_hash: None,
+            block_number: Some(1),
+            block_timestamp: None,
+            transaction_hash: None,
+            transaction_index: None,
+            log_index: None,
+            removed: false,
+        }
+    }
+
+    fn filter_with_topic1(topic_id: B256, topic1: Option<B256>) -> RindexerEventFilter {
+        let mut topics: [alloy::rpc::types::Topic; 4] = Default::default();
+        if let Some(topic1) = topic1 {
+            topics[1] = topic1.into();
+        }
+        RindexerEventFilter::Filter(SimpleEventFilter {
+            address: None,
+            topic_id,
+            topics,
+            current_block: U64::ZERO,
+            next_block: U64::ZERO,
+        })
+    }
+
+    #[test]
+    fn combine_filters_unions_addresses_and_dedupes_topics() {
+        let a = Some(HashSet::from([address(1)]));
+        let b = Some(HashSet::from([address(2)]));
+        let (filters, target_filters) = combine_filters(
+            [
+                (topic(1), &a, Default::default()),
+                (topic(1), &b, Default::default()),
+                (topic(2), &a, Default::default()),
+            ]
+            .into_iter(),
+            U64::from(10),
+            U64::from(20),
+        );
+
+        assert_eq!(filters.len(), 1);
+        assert_eq!(target_filters, vec![0, 0, 0]);
+        assert_eq!(filters[0].addresses, Some(HashSet::from([address(1), address(2)])));
+        assert_eq!(filters[0].topic_ids, vec![topic(1), topic(2)]);
+        assert_eq!(filters[0].from_block, U64::from(10));
+        assert_eq!(filters[0].to_block, U64::from(20));
+    }
+
+    #[test]
+    fn combine_filters_keeps_address_less_events_separate() {
+        let a = Some(HashSet::from([address(1)]));
+        let none = None;
+        let (filters, target_filters) = combine_filters(
+            [(topic(1), &a, Default::default()), (topic(2), &none, Default::default())].into_iter(),
+            U64::from(1),
+            U64::from(1),
+        );
+
+        assert_eq!(filters.len(), 2);
+        assert_eq!(target_filters, vec![0, 1]);
+        assert_eq!(filters[0].topic_ids, vec![topic(1)]);
+        assert!(filters[0].addresses.is_some());
+        assert_eq!(filters[1].topic_ids, vec![topic(2)]);
+        assert!(filters[1].addresses.is_none());
+    }
+
+    #[test]
+    fn combine_filters_keeps_indexed_topics() {
+        let none = None;
+        let to_9: [Topic; 3] = [Topic::default(), topic(9).into(), Topic::default()];
+        let (filters, target_filters) = combine_filters(
+            [
+                (topic(1), &none, to_9.clone()),
+                (topic(1), &none, Default::default()),
+                (topic(2), &none, to_9.clone()),
+            ]
+            .into_iter(),
+            U64::from(1),
+            U64::from(1),
+        );
+
+        assert_eq!(filters.len(), 2);
+        assert_eq!(target_filters, vec![0, 1, 0]);
+        assert_eq!(filters[0].topics, to_9);
+        assert_eq!(filters[0].topic_ids, vec![topic(1), topic(2)]);
+        assert!(filters[1].topics.iter().all(Topic::is_empty));
+    }
+
+    #[test]
+    fn log_matches_checks_topic_id_and_address() {
+        let filter = filter_with_topic1(topic(1), None);
+        let addresses = Some(HashSet::from([address(1)]));
+
+        assert!(log_matches(&make_log(address(1), vec![topic(1)]), &topic(1), &addresses, &filter));
+        assert!(!log_matches(
+            &make_log(address(1), vec![topic(2)]),
+            &topic(1),
+            &addresses,
+            &filter
+        ));
+        assert!(!log_matches(
+            &make_log(address(2), vec![topic(1)]),
+            &topic(1),
+            &addresses,
+            &filter
+        ));
+        assert!(log_matches(&make_log(address(2), vec![topic(1)]), &topic(1), &None, &filter));
+    }
+
+    #[test]
+    fn log_matches_applies_indexed_filters() {
+        let filter = filter_with_topic1(topic(1), Some(topic(9)));
+
+        assert!(log_matches(
+            &make_log(address(1), vec![topic(1), topic(9)]),
+            &topic(1),
+            &None,
+            &filter
+        ));
+        assert!(!log_matches(
+            &make_log(address(1), vec![topic(1), topic(8)]),
+            &topic(1),
+            &None,
+            &filter
+        ));
+        assert!(!log_matches(&make_log(address(1), vec![topic(1)]), &topic(1), &None, &filter));
+    }
+}
@@ -0,0 +1,641 @@
+//! Historic logs served from local data instead of `eth_getLogs`.
+//!
+//! A network can be backed by a [`HistoricalLogSource`], historic sync then reads every range the
+//! source holds from it and only asks the RPC for the tail past its last block. rindexer ships
+//! [`LogArchive`], reading the JSON and Parquet output of `cryo logs`, Rust projects can register
+//! their own source (RLP receipts, ...) with [`register_historical_log_source`].
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use alloy::primitives::{Address, Bytes, Log as PrimitiveLog, LogData, B256, U64};
+use alloy::rpc::types::Log;
+use async_trait::async_trait;
+use lru::LruCache;
+use once_cell::sync::Lazy;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::record::{Field, Row};
+use regex::Regex;
+use serde::Deserialize;
+use tokio::sync::OnceCell;
+use tracing::info;
+
+use crate::manifest::core::Manifest;
+
+static HISTORICAL_LOG_SOURCES: Lazy<Mutex<HashMap<String, Arc<dyn HistoricalLogSource>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Matches the file names cryo writes for the `logs` dataset, `ethereum__logs__0_to_999.json`.
+static CRYO_LOGS_FILE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"__logs__(\d+)_to_(\d+)\.(json|jsonl|parquet)$").expect("Invalid regex")
+});
+
+/// Files kept parsed, every event syncing the same blocks reads them from here instead of parsing
+/// the file again.
+const CACHED_FILES: usize = 4;
+
+/// The logs of one archive file by their `topic0`, ordered by block and log index.
+type ArchivedChunk = HashMap<B256, Vec<Log>>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum LogArchiveError {
+    #[error("Could not read log archive {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+
+    #[error("Could not parse log archive file {0}: {1}")]
+    Parse(PathBuf, serde_json::Error),
+
+    #[error("Could not read parquet log archive file {0}: {1}")]
+    Parquet(PathBuf, parquet::errors::ParquetError),
+
+    #[error("Log archive file {0} has a missing or invalid {1} column")]
+    Column(PathBuf, &'static str),
+
+    #[error("Log archive {0} holds no cryo logs files")]
+    Empty(PathBuf),
+
+    #[error("Log archive files {0} and {1} overlap")]
+    Overlap(PathBuf, PathBuf),
+}
+
+/// Holds the logs of a range of blocks, must hold every log of the blocks it covers as a gap would
+/// silently skip events.
+#[async_trait]
+pub trait HistoricalLogSource: Send + Sync {
+    /// The last block of the chunk holding `from_block`, `None` if the source does not hold it.
+    fn chunk_to_block(&self, from_block: U64) -> Option<U64>;
+
+    /// The logs with `topic_id` of blocks `from_block..=to_block`, ordered by block and log index.
+    async fn get_logs(
+        &self,
+        topic_id: &B256,
+        from_block: U64,
+        to_block: U64,
+    ) -> Result<Vec<Log>, LogArchiveError>;
+}
+
+/// Serve the historic logs of `network` from `source`.
+pub fn register_historical_log_source(network: &str, source: Arc<dyn HistoricalLogSource>) {
+    HISTORICAL_LOG_SOURCES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(network.to_string(), source);
+}
+
+/// The source registered for `network`, if any.
+pub fn historical_log_source(network: &str) -> Option<Arc<dyn HistoricalLogSource>> {
+    HISTORICAL_LOG_SOURCES.lock().unwrap_or_else(|e| e.into_inner()).get(network).cloned()
+}
+
+/// Open the `log_archive` of every network in the manifest, relative to the project path.
+pub fn register_log_archives(manifest: &Manifest, project_path: &Path) -> anyhow::Result<()> {
+    for network in &manifest.networks {
+        let Some(log_archive) = &network.log_archive else {
+            continue;
+        };
+
+        let archive = LogArchive::open(project_path.join(&log_archive.path))?;
+        info!(
+            "{} - Log archive holds blocks {} - {}",
+            network.name,
+            archive.first_block().unwrap_or_default(),
+            archive.last_block().unwrap_or_default()
+        );
+
+        register_historical_log_source(&network.name, Arc::new(archive));
+    }
+
+    Ok(())
+}
+
+/// A row of the cryo `logs` dataset, written with `--hex` so binary columns are hex strings.
+#[derive(Deserialize)]
+struct ArchivedLog {
+    block_number: u64,
+    /// Not a default cryo column, files without it are rejected when read.
+    #[serde(default)]
+    block_hash: Option<B256>,
+    transaction_index: u64,
+    log_index: u64,
+    transaction_hash: B256,
+    address: Address,
+    topic0: Option<B256>,
+    topic1: Option<B256>,
+    topic2: Option<B256>,
+    topic3: Option<B256>,
+    data: Bytes,
+}
+
+impl ArchivedLog {
+    /// A row of a cryo Parquet file, binary columns are raw bytes or hex strings with `--hex`.
+    fn from_parquet_row(path: &Path, row: Row) -> Result<Self, LogArchiveError> {
+        let mut columns: HashMap<String, Field> = row.into_columns().into_iter().collect();
+        let mut column = |name: &'static str| columns.remove(name).unwrap_or(Field::Null);
+        let missing = |name: &'static str| LogArchiveError::Column(path.to_path_buf(), name);
+
+        let number =
+            |name: &'static str, field: Field| parquet_u64(&field).ok_or_else(|| missing(name));
+        let word = |name: &'static str, field: Field| {
+            parquet_bytes(&field)
+                .map(|bytes| B256::try_from(bytes.as_slice()).map_err(|_| missing(name)))
+                .transpose()
+        };
+
+        Ok(ArchivedLog {
+            block_number: number("block_number", column("block_number"))?,
+            block_hash: word("block_hash", column("block_hash"))?,
+            transaction_index: number("transaction_index", column("transaction_index"))?,
+            log_index: number("log_index", column("log_index"))?,
+            transaction_hash: word("transaction_hash", column("transaction_hash"))?
+                .ok_or_else(|| missing("transaction_hash"))?,
+            address: parquet_bytes(&column("address"))
+                .and_then(|bytes| Address::try_from(bytes.as_slice()).ok())
+                .ok_or_else(|| missing("address"))?,
+            topic0: word("topic0", column("topic0"))?,
+            topic1: word("topic1", column("topic1"))?,
+            topic2: word("topic2", column("topic2"))?,
+            topic3: word("topic3", column("topic3"))?,
+            data: parquet_bytes(&column("data")).map(Bytes::from).unwrap_or_default(),
+        })
+    }
+}
+
+fn parquet_u64(field: &Field) -> Option<u64> {
+    match field {
+        Field::UByte(value) => Some(u64::from(*value)),
+        Field::UShort(value) => Some(u64::from(*value)),
+        Field::UInt(value) => Some(u64::from(*value)),
+        Field::ULong(value) => Some(*value),
+        Field::Byte(value) => u64::try_from(*value).ok(),
+        Field::Short(value) => u64::try_from(*value).ok(),
+        Field::Int(value) => u64::try_from(*value).ok(),
+        Field::Long(value) => u64::try_from(*value).ok(),
+        _ => None,
+    }
+}
+
+fn parquet_bytes(field: &Field) -> Option<Vec<u8>> {
+    match field {
+        Field::Bytes(bytes) => Some(bytes.data().to_vec()),
+        Field::Str(hex_string) => hex::decode(hex_string.trim_start_matches("0x")).ok(),
+        _ => None,
+    }
+}
+
+impl From<ArchivedLog> for Log {
+    fn from(log: ArchivedLog) -> Self {
+        let topics =
+            [log.topic0, log.topic1, log.topic2, log.topic3].into_iter().flatten().collect();
+
+        Log {
+            inner: PrimitiveLog {
+                address: log.address,
+                data: LogData::new_unchecked(topics, log.data),
+            },
+            block_hash: log.block_hash,
+            block_number: Some(log.block_number),
+            block_timestamp: None,
+            transaction_hash: Some(log.transaction_hash),
+            transaction_index: Some(log.transaction_index),
+            log_index: Some(log.log_index),
+            removed: false,
+        }
+    }
+}
+
+/// A directory of `cryo logs` output, one JSON array, JSON lines or Parquet file per block range.
+/// JSON files must be written with `--hex`.
+pub struct LogArchive {
+    /// Files by their first block, with their last block.
+    files: BTreeMap<u64, (u64, PathBuf)>,
+    /// Recently read files, a cell is shared while the file is still being parsed.
+    cache: Mutex<LruCache<PathBuf, Arc<OnceCell<Arc<ArchivedChunk>>>>>,
+}
+
+impl LogArchive {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, LogArchiveError> {
+        let path = path.as_ref();
+        let entries =
+            std::fs::read_dir(path).map_err(|e| LogArchiveError::Io(path.to_path_buf(), e))?;
+
+        let mut files = BTreeMap::new();
+        for entry in entries {
+            let entry = entry.map_err(|e| LogArchiveError::Io(path.to_path_buf(), e))?;
+            let file_name = entry.file_name();
+            let Some(captures) = CRYO_LOGS_FILE.captures(&file_name.to_string_lossy()) else {
+                continue;
+            };
+
+            let (Ok(start), Ok(end)) = (captures[1].parse::<u64>(), captures[2].parse::<u64>())
+            else {
+                continue;
+            };
+
+            files.insert(start, (end, entry.path()));
+        }
+
+        if files.is_empty() {
+            return Err(LogArchiveError::Empty(path.to_path_buf()));
+        }
+
+        let mut previous: Option<&(u64, PathBuf)> = None;
+        for (start, file) in &files {
+            if let Some((previous_end, previous_path)) = previous {
+                if previous_end >= start {
+                    return Err(LogArchiveError::Overlap(previous_path.clone(), file.1.clone()));
+                }
+            }
+            previous = Some(file);
+        }
+
+        Ok(Self {
+            files,
+            cache: Mutex::new(LruCache::new(
+                NonZeroUsize::new(CACHED_FILES).expect("CACHED_FILES is not zero"),
+            )),
+        })
+    }
+
+    pub fn first_block(&self) -> Option<u64> {
+        self.files.keys().next().copied()
+    }
+
+    pub fn last_block(&self) -> Option<u64> {
+        self.files.values().map(|(end, _)| *end).max()
+    }
+
+    /// The logs of the file at `path`, parsed once while it stays in the cache.
+    async fn chunk(&self, path: &Path) -> Result<Arc<ArchivedChunk>, LogArchiveError> {
+        let cell = {
+            let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+            match cache.get(path) {
+                Some(cell) => Arc::clone(cell),
+                None => {
+                    let cell = Arc::new(OnceCell::new());
+                    cache.put(path.to_path_buf(), Arc::clone(&cell));
+                    cell
+                }
+            }
+        };
+
+        cell.get_or_try_init(|| Self::read_chunk(path)).await.cloned()
+    }
+
+    async fn read_chunk(path: &Path) -> Result<Arc<ArchivedChunk>, LogArchiveError> {
+        let archived = if path.extension().is_some_and(|extension| extension == "parquet") {
+            let parquet_path = path.to_path_buf();
+            tokio::task::spawn_blocking(move || Self::read_parquet_file(&parquet_path))
+                .await
+                .map_err(|e| LogArchiveError::Io(path.to_path_buf(), std::io::Error::other(e)))??
+        } else {
+            let contents = tokio::fs::read_to_string(path)
+                .await
+                .map_err(|e| LogArchiveError::Io(path.to_path_buf(), e))?;
+            Self::read_file(path, &contents)?
+        };
+
+        // every indexed log carries its block hash, cryo only writes it when asked to
+        if archived.iter().any(|log| log.block_hash.is_none()) {
+            return Err(LogArchiveError::Column(path.to_path_buf(), "block_hash"));
+        }
+
+        // logs without a topic0 are anonymous events, which can't be indexed
+        let mut chunk = ArchivedChunk::new();
+        for log in archived {
+            if let Some(topic0) = log.topic0 {
+                chunk.entry(topic0).or_default().push(Log::from(log));
+            }
+        }
+        for logs in chunk.values_mut() {
+            logs.sort_by_key(|log| (log.block_number, log.log_index));
+        }
+
+        Ok(Arc::new(chunk))
+    }
+
+    fn read_parquet_file(path: &Path) -> Result<Vec<ArchivedLog>, LogArchiveError> {
+        let file = File::open(path).map_err(|e| LogArchiveError::Io(path.to_path_buf(), e))?;
+        let reader = SerializedFileReader::new(file)
+            .map_err(|e| LogArchiveError::Parquet(path.to_path_buf(), e))?;
+        let rows = reader
+            .get_row_iter(None)
+            .map_err(|e| LogArchiveError::Parquet(path.to_path_buf(), e))?;
+
+        rows.map(|row| {
+            let row = row.map_err(|e| LogArchiveError::Parquet(path.to_path_buf(), e))?;
+            ArchivedLog::from_parquet_row(path, row)
+        })
+        .collect()
+    }
+
+    fn read_file(path: &Path, contents: &str) -> Result<Vec<ArchivedLog>, LogArchiveError> {
+        if contents.trim_start().starts_with('[') {
+            return serde_json::from_str(contents)
+                .map_err(|e| LogArchiveError::Parse(path.to_path_buf(), e));
+        }
+
+        contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line)
+                    .map_err(|e| LogArchiveError::Parse(path.to_path_buf(), e))
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl HistoricalLogSource for LogArchive {
+    fn chunk_to_block(&self, from_block: U64) -> Option<U64> {
+        let from_block = from_block.to::<u64>();
+        let (_, (end, _)) = self.files.range(..=from_block).next_back()?;
+
+        (*end >= from_block).then_some(U64::from(*end))
+    }
+
+    async fn get_logs(
+        &self,
+        topic_id: &B256,
+        from_block: U64,
+        to_block: U64,
+    ) -> Result<Vec<Log>, LogArchiveError> {
+        let (from, to) = (from_block.to::<u64>(), to_block.to::<u64>());
+
+        // Files starting before `from_block` can still hold some of the range.
+        let first = self.files.range(..=from).next_back().map(|(start, _)| *start).unwrap_or(from);
+
+        // The files don't overlap and each chunk is ordered, so the logs come out ordered.
+        let mut logs = Vec::new();
+        for (end, path) in self.files.range(first..=to).map(|(_, file)| file) {
+            if *end < from {
+                continue;
+            }
+
+            let chunk = self.chunk(path).await?;
+            if let Some(topic_logs) = chunk.get(topic_id) {
+                logs.extend(
+                    topic_logs
+                        .iter()
+                        .filter(|log| {
+                            log.block_number.is_some_and(|number| number >= from && number <= to)
+                        })
+                        .cloned(),
+                );
+            }
+        }
+
+        Ok(logs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn topic(n: u8) -> B256 {
+        B256::from([n; 32])
+    }
+
+    fn row(block_number: u64, log_index: u64, topic0: B256) -> String {
+        format!(
+            r#"{{"block_number":{block_number},"block_hash":"{}","transaction_index":0,"log_index":{log_index},"transaction_hash":"{}","address":"{}","topic0":"{topic0}","topic1":null,"topic2":null,"topic3":null,"data":"0x01","chain_id":1}}"#,
+            B256::from([8; 32]),
+            B256::from([7; 32]),
+            Address::from([1; 20]),
+        )
+    }
+
+    fn write(dir: &Path, name: &str, contents: &str) {
+        std::fs::write(dir.join(name), contents).expect("could not write archive file");
+    }
+
+    /// A parquet file laid out like `cryo logs` writes it, binary columns as raw bytes.
+    fn write_parquet(dir: &Path, name: &str, rows: &[(u64, u64, Option<B256>)]) {
+        use parquet::data_type::{ByteArray, ByteArrayType, Int32Type};
+        use parquet::file::properties::WriterProperties;
+        use parquet::file::writer::SerializedFileWriter;
+        use parquet::schema::parser::parse_message_type;
+
+        let schema = parse_message_type(
+            "message logs {
+                REQUIRED INT32 block_number (INTEGER(32,false));
+                REQUIRED BINARY block_hash;
+                REQUIRED INT32 transaction_index (INTEGER(32,false));
+                REQUIRED INT32 log_index (INTEGER(32,false));
+                REQUIRED BINARY transaction_hash;
+                REQUIRED BINARY address;
+                OPTIONAL BINARY topic0;
+                REQUIRED BINARY data;
+            }",
+        )
+        .expect("schema should parse");
+        let file = File::create(dir.join(name)).expect("could not create archive file");
+        let mut writer = SerializedFileWriter::new(
+            file,
+            Arc::new(schema),
+            Arc::new(WriterProperties::builder().build()),
+        )
+        .expect("could not create parquet writer");
+
+        let numbers = |index: usize| -> Vec<i32> {
+            rows.iter()
+                .map(|row| match index {
+                    0 => row.0 as i32,
+                    3 => row.1 as i32,
+                    _ => 0,
+                })
+                .collect()
+        };
+        let bytes = |value: &[u8]| -> Vec<ByteArray> {
+            rows.iter().map(|_| ByteArray::from(value.to_vec())).collect()
+        };
+        let topics: Vec<ByteArray> = rows
+            .iter()
+            .filter_map(|row| row.2)
+            .map(|topic| ByteArray::from(topic.to_vec()))
+            .collect();
+        let topic_levels: Vec<i16> = rows.iter().map(|row| i16::from(row.2.is_some())).collect();
+
+        let mut row_group = writer.next_row_group().expect("could not start row group");
+        let mut index = 0;
+        while let Some(mut column) = row_group.next_column().expect("could not get column") {
+            match index {
+                0 | 2 | 3 => column.typed::<Int32Type>().write_batch(&numbers(index), None, None),
+                1 => column.typed::<ByteArrayType>().write_batch(&bytes(&[8; 32]), None, None),
+                4 => column.typed::<ByteArrayType>().write_batch(&bytes(&[7; 32]), None, None),
+                5 => column.typed::<ByteArrayType>().write_batch(&bytes(&[1; 20]), None, None),
+                6 => {
+                    column.typed::<ByteArrayType>().write_batch(&topics, Some(&topic_levels), None)
+                }
+                _ => column.typed::<ByteArrayType>().write_batch(&bytes(&[1]), None, None),
+            }
+            .expect("could not write column");
+            column.close().expect("could not close column");
+            index += 1;
+        }
+        row_group.close().expect("could not close row group");
+        writer.close().expect("could not close parquet writer");
+    }
+
+    #[test]
+    fn open_rejects_empty_and_overlapping_archives() {
+        let dir = tempfile::tempdir().expect("could not create temp dir");
+        write(dir.path(), "notes.txt", "");
+        assert!(matches!(LogArchive::open(dir.path()), Err(LogArchiveError::Empty(_))));
+
+        write(dir.path(), "ethereum__logs__00000000_to_00000099.json", "[]");
+        write(dir.path(), "ethereum__logs__00000050_to_00000149.json", "[]");
+        assert!(matches!(LogArchive::open(dir.path()), Err(LogArchiveError::Overlap(_, _))));
+    }
+
+    #[test]
+    fn chunk_to_block_stops_at_gaps() {
+        let dir = tempfile::tempdir().expect("could not create temp dir");
+        write(dir.path(), "ethereum__logs__00000000_to_00000099.json", "[]");
+        write(dir.path(), "ethereum__logs__00000100_to_00000199.jsonl", "");
+        write(dir.path(), "ethereum__logs__00000300_to_00000399.json", "[]");
+
+        let archive = LogArchive::open(dir.path()).expect("archive should open");
+
+        assert_eq!(archive.chunk_to_block(U64::from(0)), Some(U64::from(99)));
+        assert_eq!(archive.chunk_to_block(U64::from(150)), Some(U64::from(199)));
+        assert_eq!(archive.chunk_to_block(U64::from(200)), None);
+        assert_eq!(archive.chunk_to_block(U64::from(399)), Some(U64::from(399)));
+        assert_eq!(archive.chunk_to_block(U64::from(400)), None);
+        assert_eq!(archive.last_block(), Some(399));
+    }
+
+    #[tokio::test]
+    async fn get_logs_reads_json_and_json_lines_in_order() {
+        let dir = tempfile::tempdir().expect("could not create temp dir");
+        write(
+            dir.path(),
+            "ethereum__logs__00000000_to_00000099.json",
+            &format!("[{},{}]", row(50, 1, topic(1)), row(50, 0, topic(1))),
+        );
+        write(
+            dir.path(),
+            "ethereum__logs__00000100_to_00000199.jsonl",
+            &format!(
+                "{}\n{}\n{}\n",
+                row(120, 0, topic(1)),
+                row(130, 0, topic(2)),
+                row(190, 0, topic(1))
+            ),
+        );
+
+        let archive = LogArchive::open(dir.path()).expect("archive should open");
+        let logs = archive
+            .get_logs(&topic(1), U64::from(40), U64::from(150))
+            .await
+            .expect("logs should be read");
+
+        let positions: Vec<(u64, u64)> =
+            logs.iter().map(|log| (log.block_number.unwrap(), log.log_index.unwrap())).collect();
+        assert_eq!(positions, vec![(50, 0), (50, 1), (120, 0)]);
+        assert_eq!(logs[0].topics(), &[topic(1)]);
+        assert_eq!(logs[0].transaction_hash, Some(B256::from([7; 32])));
+        assert_eq!(logs[0].inner.data.data, Bytes::from(vec![1u8]));
+    }
+
+    #[tokio::test]
+    async fn get_logs_reads_parquet() {
+        let dir = tempfile::tempdir().expect("could not create temp dir");
+        write_parquet(
+            dir.path(),
+            "ethereum__logs__00000000_to_00000099.parquet",
+            &[
+                (10, 1, Some(topic(1))),
+                (10, 0, Some(topic(1))),
+                (20, 0, None),
+                (30, 0, Some(topic(2))),
+            ],
+        );
+
+        let archive = LogArchive::open(dir.path()).expect("archive should open");
+        let logs = archive
+            .get_logs(&topic(1), U64::from(0), U64::from(99))
+            .await
+            .expect("logs should be read");
+
+        let positions: Vec<(u64, u64)> =
+            logs.iter().map(|log| (log.block_number.unwrap(), log.log_index.unwrap())).collect();
+        assert_eq!(positions, vec![(10, 0), (10, 1)]);
+        assert_eq!(logs[0].address(), Address::from([1; 20]));
+        assert_eq!(logs[0].block_hash, Some(B256::from([8; 32])));
+        assert_eq!(logs[0].transaction_hash, Some(B256::from([7; 32])));
+        assert_eq!(logs[0].inner.data.data, Bytes::from(vec![1u8]));
+    }
+
+    #[tokio::test]
+    async fn events_share_the_parsed_file() {
+        let dir = tempfile::tempdir().expect("could not create temp dir");
+        let file = "ethereum__logs__00000000_to_00000099.jsonl";
+        write(dir.path(), file, &format!("{}\n{}\n", row(10, 0, topic(1)), row(20, 0, topic(2))));
+
+        let archive = LogArchive::open(dir.path()).expect("archive should open");
+        let first = archive.get_logs(&topic(1), U64::from(0), U64::from(99)).await.unwrap();
+        // the second event is served from the parsed file
+        std::fs::remove_file(dir.path().join(file)).expect("could not remove archive file");
+        let second = archive.get_logs(&topic(2), U64::from(0), U64::from(99)).await.unwrap();
+
+        assert_eq!(first.len(), 1);
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].block_number, Some(20));
+    }
+
+    #[tokio::test]
+    async fn get_logs_rejects_files_without_block_hash() {
+        let dir = tempfile::tempdir().expect("could not create temp dir");
+        let without_hash = row(10, 0, topic(1))
+            .replace(&format!(r#""block_hash":"{}","#, B256::from([8; 32])), "");
+        write(dir.path(), "ethereum__logs__00000000_to_00000099.jsonl", &without_hash);
+
+        let archive = LogArchive::open(dir.path()).expect("archive should open");
+        let err = archive.get_logs(&topic(1), U64::from(0), U64::from(99)).await.unwrap_err();
+
+        assert!(matches!(err, LogArchiveError::Column(_, "block_hash")));
+    }
+
+    #[tokio::test]
+    async fn archived_logs_build_event_results() {
+        use crate::blockclock::BlockClock;
+        use crate::event::callback_registry::EventResult;
+        use crate::event::contract_setup::{FilterDetails, IndexingContractSetup, NetworkContract};
+        use crate::provider::mock::MockChainProvider;
+        use alloy::rpc::types::ValueOrArray;
+
+        let dir = tempfile::tempdir().expect("could not create temp dir");
+        write(dir.path(), "ethereum__logs__00000000_to_00000099.jsonl", &row(10, 0, topic(1)));
+        let archive = LogArchive::open(dir.path()).expect("archive should open");
+        let log = archive.get_logs(&topic(1), U64::from(0), U64::from(99)).await.unwrap().remove(0);
+
+        let provider = Arc::new(MockChainProvider::new(1));
+        let network_contract = Arc::new(NetworkContract {
+            id: "transfer".to_string(),
+            network: "ethereum".to_string(),
+            indexing_contract_setup: IndexingContractSetup::Filter(FilterDetails {
+                events: ValueOrArray::Value("Transfer".to_string()),
+                indexed_filters: None,
+            }),
+            cached_provider: provider.clone(),
+            block_clock: BlockClock::new(None, None, provider),
+            decoder: Arc::new(|_: Vec<B256>, _: Bytes| {
+                Arc::new(()) as Arc<dyn std::any::Any + Send + Sync>
+            }),
+            start_block: None,
+            end_block: None,
+            disable_logs_bloom_checks: false,
+        });
+
+        let result = EventResult::new(network_contract, log, U64::from(0), U64::from(99));
+
+        assert_eq!(result.tx_information.block_hash, B256::from([8; 32]));
+        assert_eq!(result.tx_information.block_number, 10);
+        assert_eq!(result.tx_information.transaction_hash, B256::from([7; 32]));
+    }
+}
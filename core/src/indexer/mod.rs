@@ -12,11 +12,16 @@ mod fetch_logs;
 pub use fetch_logs::FetchLogsResult;
 mod heartbeat;
 mod last_synced;
+pub mod live_fetcher;
+pub mod log_archive;
 pub mod native_transfer;
 pub mod no_code;
 pub mod reorg;
+pub mod retention;
 pub mod start;
+pub mod start_block;
 pub mod task_tracker;
+pub mod token_metadata;
 
 pub use dependency::{ContractEventDependencies, EventDependencies, EventsDependencyTree};
 
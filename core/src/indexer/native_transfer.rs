@@ -15,7 +15,9 @@ use tracing::{debug, error, info, warn};
 use tokio_util::sync::CancellationToken;
 
 use crate::database::clickhouse::client::ClickhouseClient;
-use crate::indexer::reorg::{detect_and_handle_reorg, ReorgContext, ReorgCoordinator};
+use crate::indexer::reorg::{
+    detect_and_handle_reorg, live_safe_block_number, ReorgContext, ReorgCoordinator,
+};
 use crate::is_running;
 use crate::provider::RECOMMENDED_RPC_CHUNK_SIZE;
 use crate::PostgresClient;
@@ -29,7 +31,7 @@ use crate::{
         process::ProcessEventError,
         task_tracker::{indexing_event_processed, indexing_event_processing},
     },
-    manifest::native_transfer::TraceProcessingMethod,
+    manifest::{contract::BlockTag, native_transfer::TraceProcessingMethod},
     provider::{ChainProvider, ProviderError},
 };
 
@@ -231,6 +233,7 @@ pub async fn native_transfer_block_fetch(
     start_block: U64,
     end_block: Option<U64>,
     indexing_distance_from_head: U64,
+    finality_tag: Option<BlockTag>,
     network: String,
     cancel_token: CancellationToken,
     postgres: Option<Arc<PostgresClient>>,
@@ -309,7 +312,13 @@ pub async fn native_transfer_block_fetch(
                 let block = U64::from(latest_block.header.number);
 
                 // Always trim back to the safe indexing threshold (which is zero if disabled)
-                let block = block - indexing_distance_from_head;
+                let block = live_safe_block_number(
+                    publisher.as_ref(),
+                    finality_tag,
+                    block,
+                    indexing_distance_from_head,
+                )
+                .await;
 
                 if block > last_seen_block {
                     let to_block = end_block.map(|end| block.min(end)).unwrap_or(block);
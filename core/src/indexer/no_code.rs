@@ -14,7 +14,12 @@ use tracing::{debug, error, info, warn};
 
 use super::cron_scheduler::{manifest_has_cron_tables, CronScheduler};
 use super::native_transfer::{NATIVE_TRANSFER_ABI, NATIVE_TRANSFER_CONTRACT_NAME};
-use super::tables::{process_table_operations, ProgressCheckpointConfig, TableRuntime, TxMetadata};
+use super::retention::spawn_retention_job;
+use super::tables::{
+    process_table_operations, resolve_lookup_tables, resolve_token_metadata,
+    ProgressCheckpointConfig, TableRuntime, TxMetadata,
+};
+use super::token_metadata::TokenMetadataMap;
 use crate::database::clickhouse::client::ClickhouseClient;
 use crate::database::clickhouse::setup::{setup_clickhouse, SetupClickhouseError};
 use crate::database::generate::generate_event_table_full_name;
@@ -26,7 +31,7 @@ use crate::manifest::core::Constants;
 use crate::provider::ChainProvider;
 use crate::{
     abi::{ABIItem, CreateCsvFileForEvent, EventInfo, ParamTypeError, ReadAbiError},
-    chat::ChatClients,
+    chat::{ChatClients, TemplateTokens},
     database::postgres::{
         client::PostgresClient,
         generate::generate_column_names_only_with_base_properties,
@@ -48,6 +53,7 @@ use crate::{
         yaml::{read_manifest, ReadManifestError},
     },
     provider::{CreateNetworkProvider, RetryClientError},
+    proxy::{spawn_proxy_implementation_watcher, sync_proxy_abis},
     setup_info_logger,
     streams::StreamsClients,
     types::core::LogParam,
@@ -178,6 +184,34 @@ pub async fn setup_no_code(
                 crate::indexer::tables::configure_view_call_limit(limit).await;
             }
 
+            let network_providers = if details.indexing_details.enabled {
+                let network_providers = CreateNetworkProvider::create_with_overrides(
+                    &manifest,
+                    &details.indexing_details.provider_overrides,
+                )
+                .await?;
+                info!(
+                    "Networks enabled: {}",
+                    network_providers
+                        .iter()
+                        .map(|result| result.network_name.as_str())
+                        .collect::<Vec<&str>>()
+                        .join(", ")
+                );
+                Some(network_providers)
+            } else {
+                None
+            };
+
+            // `proxy: auto` contracts merge their implementation ABIs, so they must be resolved
+            // before anything reads the ABIs
+            let proxy_watches = match &network_providers {
+                Some(network_providers) => {
+                    sync_proxy_abis(project_path, &manifest, network_providers).await
+                }
+                None => vec![],
+            };
+
             // Resolve table column types from ABI before database setup
             resolve_table_column_types(project_path, &mut manifest)?;
 
@@ -201,15 +235,8 @@ pub async fn setup_no_code(
                 });
             }
 
-            let network_providers = CreateNetworkProvider::create(&manifest).await?;
-            info!(
-                "Networks enabled: {}",
-                network_providers
-                    .iter()
-                    .map(|result| result.network_name.as_str())
-                    .collect::<Vec<&str>>()
-                    .join(", ")
-            );
+            let network_providers =
+                network_providers.expect("network providers are created when indexing is enabled");
 
             let events = process_events(
                 project_path,
@@ -254,17 +281,29 @@ pub async fn setup_no_code(
                 );
             }
 
+            let providers_map: Arc<HashMap<String, Arc<dyn ChainProvider>>> = Arc::new(
+                network_providers
+                    .iter()
+                    .map(|p| (p.network_name.clone(), Arc::clone(&p.client)))
+                    .collect(),
+            );
+
+            spawn_proxy_implementation_watcher(
+                project_path,
+                &manifest,
+                proxy_watches,
+                providers_map.clone(),
+            );
+
+            spawn_retention_job(
+                &manifest,
+                postgres.clone(),
+                clickhouse.clone(),
+                providers_map.clone(),
+            );
+
             // Start cron scheduler if any tables have cron triggers
             if manifest_has_cron_tables(&manifest) {
-                let providers_map: Arc<HashMap<String, Arc<dyn ChainProvider>>> = Arc::new(
-                    network_providers
-                        .iter()
-                        .map(|p| {
-                            (p.network_name.clone(), p.client.clone() as Arc<dyn ChainProvider>)
-                        })
-                        .collect(),
-                );
-
                 let scheduler = CronScheduler::new(
                     &manifest,
                     postgres.clone(),
@@ -807,9 +846,37 @@ fn no_code_callback(params: Arc<NoCodeCallbackParams>) -> EventCallbacks {
                         format!("- blocks: {} - {} - network: {}", from_block, to_block, network)
                     );
                 } else {
+                    // Read the metadata of the tokens referenced by the messages
+                    let token_references = chat_clients.token_references(&params.event_info.name);
+                    let token_metadata = if token_references.is_empty() {
+                        TokenMetadataMap::new()
+                    } else {
+                        let tokens = event_message
+                            .event_data
+                            .as_array()
+                            .into_iter()
+                            .flatten()
+                            .flat_map(|event_data| {
+                                token_references.iter().filter_map(move |token| {
+                                    TemplateTokens::address(token, event_data)
+                                })
+                            })
+                            .map(|address| (network.clone(), address))
+                            .collect();
+                        resolve_token_metadata(
+                            tokens,
+                            &params.providers,
+                            &params.multicall_addresses,
+                            params.postgres.as_deref(),
+                            params.clickhouse.as_deref(),
+                        )
+                        .await
+                    };
+
                     match chat_clients
                         .send_message(
                             &event_message,
+                            &token_metadata,
                             params.index_event_in_order,
                             &from_block,
                             &to_block,
@@ -909,6 +976,9 @@ pub enum ProcessIndexersError {
 
     #[error("Table column type resolution error: {0}")]
     TableColumnTypeResolutionError(String),
+
+    #[error("Table lookup resolution error: {0}")]
+    TableLookupResolutionError(String),
 }
 
 pub async fn process_events(
@@ -951,10 +1021,7 @@ async fn process_contract(
 
     // Build providers map for view calls
     let providers: Arc<HashMap<String, Arc<dyn ChainProvider>>> = Arc::new(
-        network_providers
-            .iter()
-            .map(|p| (p.network_name.clone(), p.client.clone() as Arc<dyn ChainProvider>))
-            .collect(),
+        network_providers.iter().map(|p| (p.network_name.clone(), Arc::clone(&p.client))).collect(),
     );
 
     // Build multicall3 addresses map from network configs
@@ -968,6 +1035,7 @@ async fn process_contract(
     let is_filter = contract.identify_and_modify_filter();
     let abi_items = ABIItem::get_abi_items(project_path, contract, is_filter)?;
     let event_names = ABIItem::extract_event_names_and_signatures_from_abi(abi_items)?;
+    let all_contracts = manifest.all_contracts();
 
     let mut events: Vec<EventCallbackRegistryInformation> = vec![];
 
@@ -1036,14 +1104,16 @@ async fn process_contract(
         // Build custom tables for this contract
         let contract_tables: Vec<TableRuntime> = contract
             .tables
-            .as_ref()
-            .map(|tables| {
-                tables
-                    .iter()
-                    .map(|table| TableRuntime::new(table.clone(), &manifest.name, &contract.name))
-                    .collect()
+            .iter()
+            .flatten()
+            .map(|table| {
+                let lookup_tables =
+                    resolve_lookup_tables(table, &manifest.name, &contract.name, &all_contracts)
+                        .map_err(ProcessIndexersError::TableLookupResolutionError)?;
+                Ok(TableRuntime::new(table.clone(), &manifest.name, &contract.name)
+                    .with_lookup_tables(lookup_tables))
             })
-            .unwrap_or_default();
+            .collect::<Result<_, ProcessIndexersError>>()?;
 
         // Determine if this event should store raw events
         // Store raw events if: (a) event is in include_events, OR (b) tables are defined
@@ -1195,7 +1265,7 @@ pub async fn process_trace_events(
         let providers: Arc<HashMap<String, Arc<dyn ChainProvider>>> = Arc::new(
             network_providers
                 .iter()
-                .map(|p| (p.network_name.clone(), p.client.clone() as Arc<dyn ChainProvider>))
+                .map(|p| (p.network_name.clone(), Arc::clone(&p.client)))
                 .collect(),
         );
 
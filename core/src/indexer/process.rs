@@ -15,7 +15,8 @@ use tracing::{debug, error, info, warn};
 use crate::helpers::is_relevant_block;
 use crate::indexer::heartbeat::{HeartbeatAction, HeartbeatTracker};
 use crate::indexer::reorg::{
-    detect_and_handle_reorg, reorg_safe_distance_for_chain, ReorgContext, ReorgCoordinator,
+    detect_and_handle_reorg, live_safe_block_number, reorg_safe_distance_for_chain, ReorgContext,
+    ReorgCoordinator,
 };
 use crate::metrics::indexing as metrics;
 use crate::provider::ChainProvider;
@@ -29,6 +30,7 @@ use crate::{
         dependency::{ContractEventsDependenciesConfig, EventDependencies},
         fetch_logs::{fetch_logs_stream, FetchLogsResult},
         last_synced::update_progress_and_last_synced_task,
+        live_fetcher::NetworkLiveFetcher,
         progress::IndexingEventProgressStatus,
         task_tracker::{indexing_event_processed, indexing_event_processing},
     },
@@ -55,10 +57,19 @@ pub async fn process_non_blocking_event(
     config: EventProcessingConfig,
     reorg_coordinator: Option<Arc<Mutex<ReorgCoordinator>>>,
     trace_registry: Option<Arc<TraceCallbackRegistry>>,
+    live_fetcher: Option<Arc<NetworkLiveFetcher>>,
 ) -> Result<(), ProcessEventError> {
     debug!("{} - Processing non blocking event", config.info_log_name());
 
-    process_event_logs(Arc::new(config), false, false, reorg_coordinator, trace_registry).await?;
+    process_event_logs(
+        Arc::new(config),
+        false,
+        false,
+        reorg_coordinator,
+        trace_registry,
+        live_fetcher,
+    )
+    .await?;
 
     Ok(())
 }
@@ -72,7 +83,7 @@ pub async fn process_blocking_event_historical_data(
 
     // Historical data processing does not use reorg coordinator — reorgs are only
     // handled during live indexing.
-    process_event_logs(config, true, true, None, None).await?;
+    process_event_logs(config, true, true, None, None, None).await?;
 
     Ok(())
 }
@@ -86,6 +97,7 @@ async fn process_event_logs(
     block_until_indexed: bool,
     reorg_coordinator: Option<Arc<Mutex<ReorgCoordinator>>>,
     trace_registry: Option<Arc<TraceCallbackRegistry>>,
+    live_fetcher: Option<Arc<NetworkLiveFetcher>>,
 ) -> Result<(), Box<ProviderError>> {
     // The concurrency with which we can call the trigger. If the indexer is running in-order
     // we can only call one at a time, otherwise we can call multiple in parallel based on what is
@@ -106,6 +118,7 @@ async fn process_event_logs(
         force_no_live_indexing,
         reorg_coordinator,
         trace_registry,
+        live_fetcher,
     );
     // Drain inline so handles don't accumulate during infinite live indexing.
     let mut in_flight: FuturesUnordered<JoinHandle<()>> = FuturesUnordered::new();
@@ -625,12 +638,19 @@ async fn live_indexing_for_contract_event_dependencies(
                 ordering_live_indexing_details.last_seen_block_number
             );
             let reorg_safe_distance = &config.indexing_distance_from_head();
-            let safe_block_number = latest_block_number.saturating_sub(*reorg_safe_distance);
+            let finality_tag = config.finality_tag();
+            let safe_block_number = live_safe_block_number(
+                cached_provider.as_ref(),
+                finality_tag,
+                latest_block_number,
+                *reorg_safe_distance,
+            )
+            .await;
             let from_block = ordering_live_indexing_details.filter.from_block();
 
             // check reorg distance and skip if not safe
             if from_block > safe_block_number {
-                if reorg_safe_distance.is_zero() {
+                if reorg_safe_distance.is_zero() && finality_tag.is_none() {
                     let block_distance = from_block - latest_block_number;
                     let is_outside_reorg_range = block_distance
                         > reorg_safe_distance_for_chain(cached_provider.chain().id());
@@ -1,12 +1,16 @@
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use alloy::primitives::{B256, U64};
 use anyhow::Context;
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
 
+use crate::chat::ChatClients;
 use crate::event::callback_registry::ReorgNotification;
+use crate::manifest::contract::BlockTag;
 use crate::metrics::indexing as metrics;
+use crate::notifications::ChainStateSource;
 use crate::provider::ChainProvider;
 use crate::streams::StreamsClients;
 
@@ -19,6 +23,9 @@ use super::ReorgContext;
 /// Balances write frequency against data-loss risk on crash.
 const FLUSH_INTERVAL: u64 = 50;
 
+/// How long a reorg chat alert may take before it is given up on.
+const REORG_ALERT_TIMEOUT: Duration = Duration::from_secs(30);
+
 pub struct ReorgCoordinator {
     network: String,
     window: BlockChainWindow,
@@ -34,6 +41,11 @@ pub struct ReorgCoordinator {
     /// entries whose inner `Option` is `Some` are iterated. The caller is
     /// expected to pre-filter out `None`-valued entries at construction time.
     streams_clients: Vec<Arc<Option<StreamsClients>>>,
+    /// When set, finalized stream delivery and block hash pruning follow the
+    /// node's tagged block instead of the registered `reorg_safe_distance`.
+    finality_tag: Option<BlockTag>,
+    /// Chat clients with a `reorg` alert that has a channel on this network.
+    reorg_alerts: Vec<Arc<ChatClients>>,
     blocks_since_flush: u64,
 }
 
@@ -56,10 +68,46 @@ impl ReorgCoordinator {
             event_tables,
             derived_tables,
             streams_clients,
+            finality_tag: None,
+            reorg_alerts: vec![],
             blocks_since_flush: 0,
         })
     }
 
+    /// Follow `finality_tag` (see [`super::finality_tag_for_network`]) for
+    /// finalized stream delivery and pruning.
+    pub fn with_finality_tag(mut self, finality_tag: Option<BlockTag>) -> Self {
+        self.finality_tag = finality_tag;
+        self
+    }
+
+    /// Send the `reorg` chat alert of `reorg_alerts` (see
+    /// [`super::reorg_alert_chat_clients`]) for every handled reorg deep enough.
+    pub fn with_reorg_alerts(mut self, reorg_alerts: Vec<Arc<ChatClients>>) -> Self {
+        self.reorg_alerts = reorg_alerts;
+        self
+    }
+
+    /// The tagged block capped at `block_number`, `None` when no tag is
+    /// followed or the node can not serve it — callers then fall back to the
+    /// fixed distance.
+    async fn tagged_block(&self, block_number: u64) -> Option<u64> {
+        let tag = self.finality_tag?;
+        let provider = self.provider.as_ref()?;
+        match provider.get_tagged_block_number(tag).await {
+            Ok(tagged) => tagged.map(|n| n.to::<u64>().min(block_number)),
+            Err(e) => {
+                debug!(
+                    network = %self.network,
+                    ?tag,
+                    error = %e,
+                    "Failed to fetch tagged block, using reorg_safe_distance"
+                );
+                None
+            }
+        }
+    }
+
     /// Called on each new block during live indexing.
     /// Returns `Some(ReorgTask)` if a reorg is detected, `None` otherwise.
     pub async fn on_new_block(
@@ -176,6 +224,7 @@ impl ReorgCoordinator {
         if self.streams_clients.is_empty() {
             return;
         }
+        let tagged_block = self.tagged_block(block_number).await;
         let snapshot: Vec<Arc<Option<StreamsClients>>> =
             self.streams_clients.iter().map(Arc::clone).collect();
         let network = self.network.clone();
@@ -184,7 +233,13 @@ impl ReorgCoordinator {
             let network = network.clone();
             Some(async move {
                 let clients = clients_arc.as_ref().as_ref().expect("filter_map above ensures Some");
-                (network.clone(), clients.flush_finalized(&network, block_number).await)
+                let res = match tagged_block {
+                    Some(tagged_block) => {
+                        clients.flush_finalized_up_to(&network, tagged_block).await
+                    }
+                    None => clients.flush_finalized(&network, block_number).await,
+                };
+                (network.clone(), res)
             })
         });
         let results = futures::future::join_all(futures).await;
@@ -224,6 +279,7 @@ impl ReorgCoordinator {
             network: self.network.clone(),
             fork_point,
             detection_point: block_number,
+            detection_source: "rpc",
             event_tables: self.event_tables.clone(),
             derived_tables: self.derived_tables.clone(),
             canonical_blocks,
@@ -283,6 +339,7 @@ impl ReorgCoordinator {
                     network: self.network.clone(),
                     fork_point,
                     detection_point,
+                    detection_source: "startup",
                     event_tables: self.event_tables.clone(),
                     derived_tables: self.derived_tables.clone(),
                     canonical_blocks: vec![],
@@ -307,6 +364,7 @@ impl ReorgCoordinator {
                     network: self.network.clone(),
                     fork_point: oldest,
                     detection_point: latest,
+                    detection_source: "startup",
                     event_tables: self.event_tables.clone(),
                     derived_tables: self.derived_tables.clone(),
                     canonical_blocks: vec![],
@@ -333,19 +391,21 @@ impl ReorgCoordinator {
             network: self.network.clone(),
             fork_point,
             detection_point,
+            detection_source: "removed_logs",
             event_tables: self.event_tables.clone(),
             derived_tables: self.derived_tables.clone(),
             canonical_blocks: vec![],
         })
     }
 
-    /// Handle reth ExEx notification — fork point provided directly. Matches
-    /// reth's `old_range.start()/end()` convention: `revert_from_block` is
-    /// the lowest reverted block (first reorged), `revert_to_block` is the
-    /// highest. Both are inclusive; the reverted span is
+    /// Handle a reorg pushed by a reth ExEx or a `newHeads` subscription — fork point
+    /// provided directly. Matches reth's `old_range.start()/end()` convention:
+    /// `revert_from_block` is the lowest reverted block (first reorged),
+    /// `revert_to_block` is the highest. Both are inclusive; the reverted span is
     /// `[revert_from_block, revert_to_block]`.
-    pub fn on_exex_reorg(
+    pub fn on_notified_reorg(
         &self,
+        source: ChainStateSource,
         revert_from_block: u64,
         revert_to_block: u64,
     ) -> anyhow::Result<ReorgTask> {
@@ -355,12 +415,13 @@ impl ReorgCoordinator {
             revert_from_block,
             revert_to_block
         );
-        metrics::record_reorg_detection_source(&self.network, "exex");
+        metrics::record_reorg_detection_source(&self.network, source.as_str());
         metrics::record_reorg(&self.network, revert_to_block - revert_from_block + 1);
         Ok(ReorgTask {
             network: self.network.clone(),
             fork_point: revert_from_block,
             detection_point: revert_to_block,
+            detection_source: source.as_str(),
             event_tables: self.event_tables.clone(),
             derived_tables: self.derived_tables.clone(),
             canonical_blocks: vec![],
@@ -494,6 +555,48 @@ impl ReorgCoordinator {
             }
         }
 
+        // The rollback is already committed, a failed audit insert or alert
+        // must not fail the reorg.
+        if let Err(e) = self.persistence.record_reorg_event(&reorg_task, &result).await {
+            tracing::error!(network = %network, fork_point, "Failed to record reorg event: {:#}", e);
+        }
+
+        if !self.reorg_alerts.is_empty() {
+            let reorg_data = serde_json::json!({
+                "network": network,
+                "fork_block": fork_point,
+                "detection_block": reorg_task.detection_point,
+                "depth": depth,
+                "detection_source": reorg_task.detection_source,
+                "events_deleted": events_deleted,
+                "affected_tables": affected_tables.iter().map(|t| t.to_json()).collect::<Vec<_>>(),
+            });
+            // Sent in the background, the caller holds the coordinator lock and a slow chat
+            // API must not stall reorg handling or block processing on the network.
+            for chat_clients in self.reorg_alerts.iter().cloned() {
+                let network = network.clone();
+                let reorg_data = reorg_data.clone();
+                tokio::spawn(async move {
+                    let send = chat_clients.send_reorg_alert(&network, depth, &reorg_data);
+                    match tokio::time::timeout(REORG_ALERT_TIMEOUT, send).await {
+                        Ok(Ok(_)) => {}
+                        Ok(Err(e)) => tracing::error!(
+                            network = %network,
+                            fork_point,
+                            "Failed to send reorg chat alert: {}",
+                            e
+                        ),
+                        Err(_) => tracing::error!(
+                            network = %network,
+                            fork_point,
+                            "Timed out sending reorg chat alert after {:?}",
+                            REORG_ALERT_TIMEOUT
+                        ),
+                    }
+                });
+            }
+        }
+
         Ok(())
     }
 
@@ -521,11 +624,17 @@ impl ReorgCoordinator {
 
         if self.blocks_since_flush >= FLUSH_INTERVAL {
             self.blocks_since_flush = 0;
-            if let Some(oldest) = self.window.oldest_block() {
+            // Blocks below the tagged block can no longer be reorged, so that
+            // is where pruning stops when a tag is followed.
+            let prune_below = match self.tagged_block(block_number).await {
+                Some(tagged_block) => Some(tagged_block),
+                None => self.window.oldest_block(),
+            };
+            if let Some(older_than) = prune_below {
                 let persistence = Arc::clone(&self.persistence);
                 let network = self.network.clone();
                 tokio::spawn(async move {
-                    if let Err(e) = persistence.prune(&network, oldest).await {
+                    if let Err(e) = persistence.prune(&network, older_than).await {
                         tracing::error!("Background DB prune failed: {}", e);
                     }
                 });
@@ -683,6 +792,7 @@ mod tests {
             network: "test".to_string(),
             fork_point: 11,
             detection_point: 12,
+            detection_source: "rpc",
             event_tables: vec![],
             derived_tables: vec![],
             canonical_blocks: vec![],
@@ -728,6 +838,7 @@ mod tests {
             network: "test".to_string(),
             fork_point: 11,
             detection_point: 12,
+            detection_source: "rpc",
             event_tables: vec![],
             derived_tables: vec![],
             canonical_blocks: vec![],
@@ -808,20 +919,22 @@ mod tests {
                     cross_chain: false,
                     rollback_ops: vec![],
                     journal_columns: vec![],
+                    history: false,
                 },
                 DerivedTableInfo {
                     full_table_name: "schema.global_stats".to_string(),
                     cross_chain: true,
                     rollback_ops: vec![],
                     journal_columns: vec![],
+                    history: false,
                 },
             ],
             streams_clients: vec![],
             blocks_since_flush: 0,
         };
 
-        // on_exex_reorg creates a task — verify derived_tables are included
-        let task = coordinator.on_exex_reorg(10, 12).unwrap();
+        // on_notified_reorg creates a task — verify derived_tables are included
+        let task = coordinator.on_notified_reorg(ChainStateSource::Exex, 10, 12).unwrap();
         assert_eq!(task.derived_tables.len(), 2);
         assert_eq!(task.derived_tables[0].full_table_name, "schema.balances");
         assert!(!task.derived_tables[0].cross_chain);
@@ -844,6 +957,7 @@ mod tests {
                 cross_chain: false,
                 rollback_ops: vec![],
                 journal_columns: vec![],
+                history: false,
             }],
             streams_clients: vec![],
             blocks_since_flush: 0,
@@ -855,7 +969,7 @@ mod tests {
     }
 
     #[test]
-    fn test_on_exex_reorg() {
+    fn test_on_notified_reorg() {
         let window = BlockChainWindow::try_new(100).unwrap();
         let persistence = Arc::new(ReorgBlockHashPersistence::new(None, None));
         let coordinator = ReorgCoordinator {
@@ -877,11 +991,15 @@ mod tests {
             blocks_since_flush: 0,
         };
 
-        let task = coordinator.on_exex_reorg(101, 110).unwrap();
+        let task = coordinator.on_notified_reorg(ChainStateSource::Exex, 101, 110).unwrap();
         assert_eq!(task.network, "test");
         assert_eq!(task.fork_point, 101);
         assert_eq!(task.detection_point, 110);
+        assert_eq!(task.detection_source, "exex");
         assert_eq!(task.event_tables.len(), 1);
+
+        let task = coordinator.on_notified_reorg(ChainStateSource::NewHeads, 101, 110).unwrap();
+        assert_eq!(task.detection_source, "new_heads");
     }
 
     // ======================================================================
@@ -923,7 +1041,7 @@ mod tests {
         let window = make_window_with_blocks(&[(99, 99, 98), (100, 100, 99)]);
         let mut coordinator = make_coordinator_with_streams(window, streams.clone());
 
-        let task = coordinator.on_exex_reorg(101, 110).unwrap();
+        let task = coordinator.on_notified_reorg(ChainStateSource::Exex, 101, 110).unwrap();
         let ctx =
             ReorgContext { postgres: None, clickhouse: None, registry: None, trace_registry: None };
         coordinator.handle_reorg(task, &ctx).await.unwrap();
@@ -942,7 +1060,7 @@ mod tests {
         // Drives the full chain: ChainStateNotification::Reorged (as reth
         // emits it) → handle_chain_notification → ReorgInfo → the arithmetic
         // applied by `fetch_logs.rs` (fork_block + depth - 1 = last_reverted)
-        // → on_exex_reorg → handle_reorg → FinalizedBuffer::discard_range.
+        // → on_notified_reorg → handle_reorg → FinalizedBuffer::discard_range.
         //
         // This is the one test that would have caught the original
         // inverted-semantic bug end-to-end. Pre-fix: depth saturated to 0
@@ -968,9 +1086,10 @@ mod tests {
             new_from_block: 101,
             new_to_block: 112,
             new_tip_hash: B256::from([0xab; 32]),
+            source: ChainStateSource::Exex,
         };
 
-        let reorg_info =
+        let (reorg_info, source) =
             handle_chain_notification(notification, "roundtrip", "ethereum").expect("reorg");
         assert_eq!(reorg_info.fork_block.to::<u64>(), 101);
         assert_eq!(reorg_info.depth, 10);
@@ -980,7 +1099,7 @@ mod tests {
         let last_reverted = fork_block + reorg_info.depth.saturating_sub(1);
         assert_eq!(last_reverted, 110);
 
-        let task = coordinator.on_exex_reorg(fork_block, last_reverted).unwrap();
+        let task = coordinator.on_notified_reorg(source, fork_block, last_reverted).unwrap();
         assert_eq!(task.fork_point, 101);
         assert_eq!(task.detection_point, 110);
 
@@ -1007,7 +1126,7 @@ mod tests {
             streams.clone(),
         );
 
-        let task = coordinator.on_exex_reorg(100, 100).unwrap();
+        let task = coordinator.on_notified_reorg(ChainStateSource::Exex, 100, 100).unwrap();
         let ctx =
             ReorgContext { postgres: None, clickhouse: None, registry: None, trace_registry: None };
         coordinator.handle_reorg(task, &ctx).await.unwrap();
@@ -1033,7 +1152,7 @@ mod tests {
             streams.clone(),
         );
 
-        let task = coordinator.on_exex_reorg(101, 102).unwrap();
+        let task = coordinator.on_notified_reorg(ChainStateSource::Exex, 101, 102).unwrap();
         let ctx =
             ReorgContext { postgres: None, clickhouse: None, registry: None, trace_registry: None };
         coordinator.handle_reorg(task, &ctx).await.unwrap();
@@ -6,21 +6,26 @@ pub mod window;
 use alloy::primitives::{B256, U64};
 use anyhow::Context;
 use serde::Serialize;
+use std::collections::HashSet;
 use std::sync::Arc;
 use tracing::{debug, warn};
 
+use crate::chat::ChatClients;
 use crate::database::clickhouse::client::ClickhouseClient;
 use crate::database::postgres::client::PostgresClient;
 use crate::event::callback_registry::{EventCallbackRegistry, TraceCallbackRegistry};
 use crate::indexer::fetch_logs::ReorgInfo;
+use crate::manifest::chat::ChatConfig;
+use crate::manifest::contract::BlockTag;
 use crate::metrics::indexing as metrics;
-use crate::notifications::ChainStateNotification;
+use crate::notifications::{ChainStateNotification, ChainStateSource};
+use crate::provider::ChainProvider;
 
 pub use coordinator::ReorgCoordinator;
 pub use persistence::ReorgBlockHashPersistence;
 pub use task::{
     AffectedTable, DerivedColumnJournal, DerivedColumnRollback, DerivedTableInfo,
-    DerivedTableRollbackOp, EventTableInfo,
+    DerivedTableRollbackOp, EventCsvInfo, EventTableInfo,
 };
 pub use window::BlockChainWindow;
 
@@ -131,15 +136,15 @@ pub struct ReorgEvent {
     pub event_name: String,
 }
 
-/// Handles chain state notifications (reorgs, reverts, commits).
-/// Used by reth feature-gated providers that emit chain state events.
-/// Returns `Some(ReorgInfo)` when a reorg/revert is detected, so the caller
-/// can forward it to the main indexing loop for recovery.
+/// Handles chain state notifications (reorgs, reverts, commits) pushed by a reth ExEx or a
+/// `newHeads` subscription.
+/// Returns the `ReorgInfo` and the source that detected it when a reorg/revert is detected,
+/// so the caller can forward it to the main indexing loop for recovery.
 pub fn handle_chain_notification(
     notification: ChainStateNotification,
     info_log_name: &str,
     network: &str,
-) -> Option<ReorgInfo> {
+) -> Option<(ReorgInfo, ChainStateSource)> {
     match notification {
         ChainStateNotification::Reorged {
             revert_from_block,
@@ -147,6 +152,7 @@ pub fn handle_chain_notification(
             new_from_block,
             new_to_block,
             new_tip_hash,
+            source,
         } => {
             // reth's `old_range.start()/end()` — `revert_from` is the lowest
             // reverted block, `revert_to` the highest, both inclusive. The
@@ -155,8 +161,9 @@ pub fn handle_chain_notification(
             metrics::record_reorg(network, depth);
 
             warn!(
-                "{} - REORG (reth): revert blocks {} to {}, re-index {} to {} (new tip: {})",
+                "{} - REORG ({}): revert blocks {} to {}, re-index {} to {} (new tip: {})",
                 info_log_name,
+                source.as_str(),
                 revert_from_block,
                 revert_to_block,
                 new_from_block,
@@ -164,22 +171,31 @@ pub fn handle_chain_notification(
                 new_tip_hash
             );
 
-            Some(ReorgInfo {
-                fork_block: U64::from(revert_from_block),
-                depth,
-                affected_tx_hashes: vec![],
-            })
+            Some((
+                ReorgInfo {
+                    fork_block: U64::from(revert_from_block),
+                    depth,
+                    affected_tx_hashes: vec![],
+                },
+                source,
+            ))
         }
-        ChainStateNotification::Reverted { from_block, to_block } => {
+        ChainStateNotification::Reverted { from_block, to_block, source } => {
             let depth = to_block.saturating_sub(from_block) + 1;
             metrics::record_reorg(network, depth);
 
             warn!(
-                "{} - CHAIN REVERTED (reth): blocks {} to {} have been reverted",
-                info_log_name, from_block, to_block
+                "{} - CHAIN REVERTED ({}): blocks {} to {} have been reverted",
+                info_log_name,
+                source.as_str(),
+                from_block,
+                to_block
             );
 
-            Some(ReorgInfo { fork_block: U64::from(from_block), depth, affected_tx_hashes: vec![] })
+            Some((
+                ReorgInfo { fork_block: U64::from(from_block), depth, affected_tx_hashes: vec![] },
+                source,
+            ))
         }
         ChainStateNotification::Committed { from_block, to_block, tip_hash } => {
             debug!(
@@ -238,6 +254,129 @@ pub fn finalized_buffer_distance_for_network(
     observed.into_iter().max().unwrap_or(chain_default)
 }
 
+/// Block tag the finalized-delivery buffer and block hash pruning follow on
+/// `network`. Any contract or native-transfer config touching the network with
+/// `reorg_safe_distance: finalized` wins over `safe`, as the finalized block is
+/// the deeper of the two. `None` when every config uses a fixed distance.
+pub fn finality_tag_for_network(
+    manifest: &crate::manifest::core::Manifest,
+    network: &str,
+) -> Option<BlockTag> {
+    let mut tags: Vec<BlockTag> = Vec::new();
+
+    for contract in manifest.all_contracts() {
+        if contract.details.iter().any(|d| d.network == network) {
+            tags.extend(contract.reorg_safe_distance.and_then(|rsd| rsd.block_tag()));
+        }
+    }
+
+    if manifest.native_transfers.enabled {
+        let touches = match &manifest.native_transfers.networks {
+            Some(nets) => nets.iter().any(|n| n.network == network),
+            None => manifest.networks.iter().any(|n| n.name == network),
+        };
+        if touches {
+            tags.extend(
+                manifest.native_transfers.reorg_safe_distance.and_then(|rsd| rsd.block_tag()),
+            );
+        }
+    }
+
+    if tags.contains(&BlockTag::Finalized) {
+        Some(BlockTag::Finalized)
+    } else {
+        tags.first().copied()
+    }
+}
+
+/// Chat clients for every `chat.reorg` alert of the manifest with a channel on `network`.
+pub async fn reorg_alert_chat_clients(
+    manifest: &crate::manifest::core::Manifest,
+    network: &str,
+) -> Vec<Arc<ChatClients>> {
+    let chats = manifest
+        .contracts
+        .iter()
+        .filter_map(|contract| contract.chat.as_ref())
+        .chain(
+            manifest.native_transfers.chat.as_ref().filter(|_| manifest.native_transfers.enabled),
+        )
+        .filter(|chat| chat.reorg.is_some() && chat_has_network(chat, network))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut alerted_channels: HashSet<String> = HashSet::new();
+    let mut clients = Vec::with_capacity(chats.len());
+    for mut chat in chats {
+        retain_unalerted_channels(&mut chat, network, &mut alerted_channels);
+        if chat_has_network(&chat, network) {
+            clients.push(Arc::new(ChatClients::new(chat).await));
+        }
+    }
+    clients
+}
+
+/// Keeps the channels of `chat` on `network` which are not in `alerted_channels` yet and adds
+/// them to it, so contracts sharing a channel send it a reorg alert once.
+fn retain_unalerted_channels(
+    chat: &mut ChatConfig,
+    network: &str,
+    alerted_channels: &mut HashSet<String>,
+) {
+    let mut unalerted = |channel: String, networks: &[String]| {
+        networks.iter().any(|n| n == network) && alerted_channels.insert(channel)
+    };
+    if let Some(configs) = chat.telegram.as_mut() {
+        configs.retain(|c| unalerted(format!("telegram:{}", c.chat_id), &c.networks));
+    }
+    if let Some(configs) = chat.discord.as_mut() {
+        configs.retain(|c| unalerted(format!("discord:{}", c.channel_id), &c.networks));
+    }
+    if let Some(configs) = chat.slack.as_mut() {
+        configs.retain(|c| unalerted(format!("slack:{}", c.channel), &c.networks));
+    }
+    if let Some(configs) = chat.twilio.as_mut() {
+        configs.retain(|c| unalerted(format!("twilio:{}", c.to_number), &c.networks));
+    }
+    if let Some(configs) = chat.pagerduty.as_mut() {
+        configs.retain(|c| unalerted(format!("pagerduty:{}", c.routing_key), &c.networks));
+    }
+    if let Some(configs) = chat.opsgenie.as_mut() {
+        configs.retain(|c| unalerted(format!("opsgenie:{}", c.api_key), &c.networks));
+    }
+}
+
+fn chat_has_network(chat: &ChatConfig, network: &str) -> bool {
+    let on_network = |networks: &[String]| networks.iter().any(|n| n == network);
+    chat.telegram.iter().flatten().any(|c| on_network(&c.networks))
+        || chat.discord.iter().flatten().any(|c| on_network(&c.networks))
+        || chat.slack.iter().flatten().any(|c| on_network(&c.networks))
+        || chat.twilio.iter().flatten().any(|c| on_network(&c.networks))
+        || chat.pagerduty.iter().flatten().any(|c| on_network(&c.networks))
+        || chat.opsgenie.iter().flatten().any(|c| on_network(&c.networks))
+}
+
+/// Highest block live indexing may process. With a `finalized` / `safe`
+/// reorg_safe_distance this is the tagged block (capped at `latest_block`),
+/// otherwise `latest_block - distance`. Falls back to the fixed distance when
+/// the node can not serve the tag.
+pub async fn live_safe_block_number(
+    provider: &dyn ChainProvider,
+    finality_tag: Option<BlockTag>,
+    latest_block: U64,
+    distance: U64,
+) -> U64 {
+    if let Some(tag) = finality_tag {
+        match provider.get_tagged_block_number(tag).await {
+            Ok(Some(tagged)) => return tagged.min(latest_block),
+            Ok(None) => debug!("No {:?} block reported yet, using fixed reorg distance", tag),
+            Err(e) => debug!("Failed to fetch {:?} block, using fixed reorg distance: {}", tag, e),
+        }
+    }
+
+    latest_block.saturating_sub(distance)
+}
+
 /// Returns the default safe reorg distance (in blocks) for a given chain.
 /// Used when `reorg_safe_distance: true` in YAML (no custom override).
 pub fn reorg_safe_distance_for_chain(chain_id: u64) -> u64 {
@@ -268,6 +407,7 @@ mod tests {
     use super::*;
 
     use crate::manifest::contract::ReorgSafeDistance;
+    use crate::provider::mock::MockChainProvider;
 
     // ======================================================================
     // validate_sql_identifier / validate_sql_value
@@ -359,6 +499,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_reorg_safe_distance_serde_tags() {
+        let val: ReorgSafeDistance = serde_yaml::from_str("finalized").unwrap();
+        assert_eq!(val.block_tag(), Some(BlockTag::Finalized));
+        let val: ReorgSafeDistance = serde_yaml::from_str("safe").unwrap();
+        assert_eq!(val.block_tag(), Some(BlockTag::Safe));
+        assert!(serde_yaml::from_str::<ReorgSafeDistance>("latest").is_err());
+
+        let yaml = serde_yaml::to_string(&ReorgSafeDistance::Tag(BlockTag::Finalized)).unwrap();
+        assert_eq!(yaml.trim(), "finalized");
+    }
+
     // ======================================================================
     // ReorgSafeDistance::resolve()
     // ======================================================================
@@ -386,6 +538,53 @@ mod tests {
         assert_eq!(rsd.resolve(999), Some(500));
     }
 
+    #[test]
+    fn test_resolve_tag_falls_back_to_chain_default() {
+        let rsd = ReorgSafeDistance::Tag(BlockTag::Safe);
+        assert_eq!(rsd.resolve(137), Some(200));
+        assert_eq!(ReorgSafeDistance::Custom(500).block_tag(), None);
+        assert_eq!(ReorgSafeDistance::Enabled(true).block_tag(), None);
+    }
+
+    // ======================================================================
+    // live_safe_block_number()
+    // ======================================================================
+
+    #[tokio::test]
+    async fn test_live_safe_block_number_follows_tag() {
+        let provider = MockChainProvider::new(1).with_tagged_block(BlockTag::Finalized, 940);
+        let safe = live_safe_block_number(
+            &provider,
+            Some(BlockTag::Finalized),
+            U64::from(1000),
+            U64::from(20),
+        )
+        .await;
+        assert_eq!(safe, U64::from(940));
+
+        // Never ahead of the latest block the caller saw.
+        let safe = live_safe_block_number(
+            &provider,
+            Some(BlockTag::Finalized),
+            U64::from(900),
+            U64::from(20),
+        )
+        .await;
+        assert_eq!(safe, U64::from(900));
+    }
+
+    #[tokio::test]
+    async fn test_live_safe_block_number_falls_back_to_distance() {
+        let provider = MockChainProvider::new(1);
+        let safe =
+            live_safe_block_number(&provider, Some(BlockTag::Safe), U64::from(1000), U64::from(20))
+                .await;
+        assert_eq!(safe, U64::from(980));
+
+        let safe = live_safe_block_number(&provider, None, U64::from(10), U64::from(20)).await;
+        assert_eq!(safe, U64::ZERO);
+    }
+
     // ======================================================================
     // handle_chain_notification()
     // ======================================================================
@@ -401,10 +600,12 @@ mod tests {
             new_from_block: 101,
             new_to_block: 112,
             new_tip_hash: B256::from([0xab; 32]),
+            source: ChainStateSource::NewHeads,
         };
         let result = handle_chain_notification(notification, "test", "polygon");
         assert!(result.is_some());
-        let reorg = result.unwrap();
+        let (reorg, source) = result.unwrap();
+        assert_eq!(source, ChainStateSource::NewHeads);
         assert_eq!(reorg.fork_block, U64::from(101));
         assert_eq!(reorg.depth, 10);
         assert!(reorg.affected_tx_hashes.is_empty());
@@ -413,10 +614,15 @@ mod tests {
     #[test]
     fn test_handle_chain_notification_reverted() {
         // Blocks 196..=200 reverted → 5 blocks, fork_block=196.
-        let notification = ChainStateNotification::Reverted { from_block: 196, to_block: 200 };
+        let notification = ChainStateNotification::Reverted {
+            from_block: 196,
+            to_block: 200,
+            source: ChainStateSource::Exex,
+        };
         let result = handle_chain_notification(notification, "test", "ethereum");
         assert!(result.is_some());
-        let reorg = result.unwrap();
+        let (reorg, source) = result.unwrap();
+        assert_eq!(source, ChainStateSource::Exex);
         assert_eq!(reorg.fork_block, U64::from(196));
         assert_eq!(reorg.depth, 5);
     }
@@ -479,4 +685,72 @@ mod tests {
         let json = serde_json::to_value(&event).unwrap();
         assert!(json["affected_tx_hashes"].as_array().unwrap().is_empty());
     }
+
+    // ======================================================================
+    // chat.reorg alerts
+    // ======================================================================
+
+    fn reorg_alert_chat(min_depth: Option<u64>) -> ChatConfig {
+        let min_depth = min_depth.map(|d| format!("min_depth: {d}\n  ")).unwrap_or_default();
+        serde_yaml::from_str(&format!(
+            r#"
+slack:
+  - bot_token: token
+    channel: "#alerts"
+    networks: [ethereum]
+    messages: []
+reorg:
+  {min_depth}template_inline: "Reorg of {{{{depth}}}} blocks on {{{{network}}}}"
+"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_reorg_alert_min_depth_defaults_to_one() {
+        let chat = reorg_alert_chat(None);
+        let alert = chat.reorg.unwrap();
+        assert_eq!(alert.min_depth, 1);
+        assert_eq!(alert.template_inline, "Reorg of {{depth}} blocks on {{network}}");
+    }
+
+    #[test]
+    fn test_chat_has_network() {
+        let chat = reorg_alert_chat(Some(3));
+        assert!(chat_has_network(&chat, "ethereum"));
+        assert!(!chat_has_network(&chat, "base"));
+    }
+
+    #[test]
+    fn test_shared_channels_are_alerted_once() {
+        let mut alerted_channels = HashSet::new();
+
+        let mut first = reorg_alert_chat(None);
+        retain_unalerted_channels(&mut first, "ethereum", &mut alerted_channels);
+        assert!(chat_has_network(&first, "ethereum"));
+
+        // A second contract with the same chat config adds no channel
+        let mut second = reorg_alert_chat(None);
+        retain_unalerted_channels(&mut second, "ethereum", &mut alerted_channels);
+        assert!(!chat_has_network(&second, "ethereum"));
+
+        let mut other_channel = reorg_alert_chat(None);
+        other_channel.slack.as_mut().unwrap()[0].channel = "#reorgs".to_string();
+        retain_unalerted_channels(&mut other_channel, "ethereum", &mut alerted_channels);
+        assert!(chat_has_network(&other_channel, "ethereum"));
+    }
+
+    #[tokio::test]
+    async fn test_send_reorg_alert_skips_shallow_reorgs() {
+        let clients = ChatClients::new(reorg_alert_chat(Some(3))).await;
+        let sent = clients.send_reorg_alert("ethereum", 2, &serde_json::json!({})).await.unwrap();
+        assert_eq!(sent, 0);
+    }
+
+    #[tokio::test]
+    async fn test_send_reorg_alert_skips_other_networks() {
+        let clients = ChatClients::new(reorg_alert_chat(Some(1))).await;
+        let sent = clients.send_reorg_alert("base", 5, &serde_json::json!({})).await.unwrap();
+        assert_eq!(sent, 0);
+    }
 }
@@ -10,6 +10,7 @@ use crate::database::clickhouse::client::ClickhouseClient;
 use crate::database::clickhouse::types::ClickhouseHash;
 use crate::database::postgres::client::PostgresClient;
 
+use super::task::{ReorgTask, ReorgTaskResult};
 use super::window::BlockChainWindow;
 
 pub struct ReorgBlockHashPersistence {
@@ -202,6 +203,74 @@ impl ReorgBlockHashPersistence {
                 .context("Failed to prune reorg_block_hashes in clickhouse")?;
         }
 
+        Ok(())
+    }
+    /// Append a handled reorg to the `reorg_events` audit log.
+    pub async fn record_reorg_event(
+        &self,
+        task: &ReorgTask,
+        result: &ReorgTaskResult,
+    ) -> anyhow::Result<()> {
+        super::validate_sql_value(&task.network, "network")?;
+        let depth = task.detection_point.saturating_sub(task.fork_point) + 1;
+        let affected_tables =
+            serde_json::Value::Array(result.affected_tables.iter().map(|t| t.to_json()).collect())
+                .to_string();
+
+        if let Some(postgres) = &self.postgres {
+            let query = r#"INSERT INTO rindexer_internal.reorg_events
+                         (network, fork_block, detection_block, depth, detection_source,
+                          events_deleted, affected_tables, duration_secs)
+                         VALUES ($1, $2, $3, $4, $5, $6, $7::text::jsonb, $8)"#;
+
+            let to_i64 = |value: u64, kind: &str| {
+                i64::try_from(value)
+                    .with_context(|| format!("{} {} exceeds i64 range", kind, value))
+            };
+            let fork_block = to_i64(task.fork_point, "fork_block")?;
+            let detection_block = to_i64(task.detection_point, "detection_block")?;
+            let depth = to_i64(depth, "depth")?;
+            let events_deleted = to_i64(result.events_deleted, "events_deleted")?;
+            postgres
+                .execute(
+                    query,
+                    &[
+                        &task.network,
+                        &fork_block,
+                        &detection_block,
+                        &depth,
+                        &task.detection_source,
+                        &events_deleted,
+                        &affected_tables,
+                        &result.duration_secs,
+                    ],
+                )
+                .await
+                .context("Failed to record reorg event in postgres")?;
+        }
+
+        if let Some(clickhouse) = &self.clickhouse {
+            let query = format!(
+                "INSERT INTO rindexer_internal.reorg_events \
+                 (network, fork_block, detection_block, depth, detection_source, \
+                 events_deleted, affected_tables, duration_secs) \
+                 VALUES ('{}', {}, {}, {}, '{}', {}, '{}', {})",
+                task.network,
+                task.fork_point,
+                task.detection_point,
+                depth,
+                task.detection_source,
+                result.events_deleted,
+                affected_tables.replace('\\', "\\\\").replace('\'', "\\'"),
+                result.duration_secs
+            );
+
+            clickhouse
+                .execute(&query)
+                .await
+                .context("Failed to record reorg event in clickhouse")?;
+        }
+
         Ok(())
     }
 }
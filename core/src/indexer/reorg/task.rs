@@ -1,13 +1,19 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use alloy::primitives::{B256, U64};
 use anyhow::Context;
 
 use crate::database::clickhouse::client::ClickhouseClient;
+use crate::database::generate::{
+    generate_distinct_values_table_full_name, generate_history_table_full_name,
+};
 use crate::database::postgres::client::PostgresClient;
-use crate::manifest::contract::SetAction;
+use crate::indexer::last_synced::rewind_last_synced_block_number_file;
+use crate::manifest::contract::{injected_columns, SetAction};
 use crate::metrics::indexing as metrics;
 use crate::provider::ChainProvider;
+use crate::simple_file_formatters::csv::AsyncCsvAppender;
 
 use super::validate_sql_condition;
 use super::window::BlockChainWindow;
@@ -26,6 +32,17 @@ pub struct EventTableInfo {
     pub contract_name: String,
     /// Event name for stream-payload metadata (not used in SQL).
     pub event_name: String,
+    /// CSV output of the event, rolled back alongside the database tables.
+    pub csv: Option<EventCsvInfo>,
+}
+
+/// Where the CSV storage of an event lives.
+#[derive(Clone, Debug)]
+pub struct EventCsvInfo {
+    /// The event's CSV file.
+    pub path: PathBuf,
+    /// The CSV storage folder, holding the `last-synced-blocks` checkpoints.
+    pub csv_folder: PathBuf,
 }
 
 impl EventTableInfo {
@@ -51,8 +68,14 @@ impl EventTableInfo {
             indexer_name,
             contract_name,
             event_name,
+            csv: None,
         })
     }
+
+    pub fn with_csv(mut self, csv: Option<EventCsvInfo>) -> Self {
+        self.csv = csv;
+        self
+    }
 }
 
 /// Per-table summary emitted to downstream consumers in the `__rindexer_reorg`
@@ -62,9 +85,6 @@ impl EventTableInfo {
 pub struct AffectedTable {
     pub schema: String,
     pub table_name: String,
-    /// TODO(future): per-table counts are not available from the DB layer today;
-    /// the total is on `ReorgTaskResult.events_deleted`. Set to 0 until a
-    /// cheap per-table tally is added.
     pub rows_deleted: u64,
     pub indexer_name: String,
     pub contract_name: String,
@@ -72,6 +92,21 @@ pub struct AffectedTable {
     pub event_name: String,
 }
 
+impl AffectedTable {
+    /// JSON shape shared by the `__rindexer_reorg` stream payload and the
+    /// `rindexer_internal.reorg_events` audit log.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "indexer": self.indexer_name,
+            "contract": self.contract_name,
+            "event": self.event_name,
+            "schema": self.schema,
+            "table": self.table_name,
+            "rows_deleted": self.rows_deleted,
+        })
+    }
+}
+
 /// Describes how to reverse one column's accumulation during reorg.
 #[derive(Clone, Debug)]
 pub struct DerivedColumnRollback {
@@ -133,8 +168,8 @@ impl DerivedTableRollbackOp {
     }
 }
 
-/// Describes a non-reversible column (Set/Max/Min) that uses the operation journal
-/// for recalculation during reorg.
+/// Describes a non-reversible column (Set/Max/Min, array actions, ...) that uses the
+/// operation journal for recalculation during reorg.
 #[derive(Clone, Debug)]
 pub struct DerivedColumnJournal {
     /// Column in the derived table (e.g., "max_trade")
@@ -143,6 +178,13 @@ pub struct DerivedColumnJournal {
     pub action: SetAction,
     /// WHERE clause columns in the derived table (for matching journal where_key)
     pub where_columns: Vec<String>,
+    /// Bucket column of rollup tables. Its where_key part is stored as unix seconds.
+    pub bucket_column: Option<String>,
+    /// Postgres type of array columns rebuilt from their journaled elements (e.g. `TEXT[]`).
+    pub array_type: Option<String>,
+    /// Whether every write of the column is journaled, so sums are recalculated by summing
+    /// the journal. Set for tables following other tables' changes.
+    pub full_journal: bool,
 }
 
 impl DerivedColumnJournal {
@@ -155,7 +197,46 @@ impl DerivedColumnJournal {
         for col in &where_columns {
             super::validate_sql_identifier(col, "journal WHERE column")?;
         }
-        Ok(Self { derived_column, action, where_columns })
+        Ok(Self {
+            derived_column,
+            action,
+            where_columns,
+            bucket_column: None,
+            array_type: None,
+            full_journal: false,
+        })
+    }
+
+    /// Sets the Postgres type journaled array elements are cast back to. The type comes
+    /// from the column definition, not from manifest text.
+    pub fn with_array_type(mut self, array_type: String) -> Self {
+        self.array_type = Some(array_type);
+        self
+    }
+
+    /// Whether the column is rebuilt from journaled array elements.
+    fn journals_elements(&self) -> bool {
+        self.action.is_array_action()
+    }
+
+    /// Marks every write of the column as journaled, subtractions as negative values.
+    pub fn with_full_journal(mut self) -> Self {
+        self.full_journal = true;
+        self
+    }
+
+    /// Whether the column is recalculated by summing its journal. A bucket is small enough
+    /// to re-sum, other sums need every write journaled.
+    fn resums(&self) -> bool {
+        (self.bucket_column.is_some() && self.action.is_sum())
+            || (self.full_journal && self.action.reverse().is_some())
+    }
+
+    /// Marks the journal as belonging to a bucket table keyed by `bucket_column`.
+    pub fn with_bucket_column(mut self, bucket_column: String) -> anyhow::Result<Self> {
+        super::validate_sql_identifier(&bucket_column, "journal bucket column")?;
+        self.bucket_column = Some(bucket_column);
+        Ok(self)
     }
 }
 
@@ -168,6 +249,8 @@ pub struct DerivedTableInfo {
     pub rollback_ops: Vec<DerivedTableRollbackOp>,
     /// Non-reversible columns (Set/Max/Min) — recalculated from operation journal.
     pub journal_columns: Vec<DerivedColumnJournal>,
+    /// Whether the table keeps row versions in `{table}_history` (Postgres only).
+    pub history: bool,
 }
 
 impl DerivedTableInfo {
@@ -184,7 +267,13 @@ impl DerivedTableInfo {
         } else {
             super::validate_sql_identifier(&full_table_name, "derived table name")?;
         }
-        Ok(Self { full_table_name, cross_chain, rollback_ops, journal_columns })
+        Ok(Self { full_table_name, cross_chain, rollback_ops, journal_columns, history: false })
+    }
+
+    /// Marks the table as keeping row versions, truncated above the fork point on reorg.
+    pub fn with_history(mut self, history: bool) -> Self {
+        self.history = history;
+        self
     }
 }
 
@@ -192,6 +281,9 @@ pub struct ReorgTask {
     pub network: String,
     pub fork_point: u64,
     pub detection_point: u64,
+    /// How the reorg was detected: "rpc", "startup", "removed_logs", "exex" or "new_heads".
+    /// Same label as the `reorg_detection_source` metric.
+    pub detection_source: &'static str,
     pub event_tables: Vec<EventTableInfo>,
     pub derived_tables: Vec<DerivedTableInfo>,
     /// Pre-fetched canonical blocks `(block_number, block_hash, parent_hash)` from
@@ -251,6 +343,56 @@ fn quote_ch_ident(name: &str) -> String {
 }
 
 impl ReorgTask {
+    /// Remove the reorged rows from the CSV file of every event table and rewind its
+    /// `last-synced-blocks` checkpoint to `fork_point - 1`. Returns
+    /// `(rows_deleted, affected_tx_hashes)` like the database rollbacks.
+    async fn rollback_csv_files(&self) -> anyhow::Result<(Vec<u64>, Vec<String>)> {
+        let mut rows_deleted: Vec<u64> = Vec::with_capacity(self.event_tables.len());
+        let mut affected_tx_hashes: Vec<String> = Vec::new();
+        let rewind_block = U64::from(self.fork_point.saturating_sub(1));
+
+        for table in &self.event_tables {
+            let Some(csv) = &table.csv else {
+                rows_deleted.push(0);
+                continue;
+            };
+
+            // Appenders of the same file share one writer lock, so the handlers can't append
+            // while the file is rewritten
+            let tx_hashes = AsyncCsvAppender::new(&csv.path.to_string_lossy())
+                .remove_block_range(&self.network, self.fork_point, self.detection_point)
+                .await
+                .with_context(|| format!("Failed to roll back {}", csv.path.display()))?;
+
+            rewind_last_synced_block_number_file(
+                &csv.csv_folder,
+                &table.contract_name,
+                &self.network,
+                &table.event_name,
+                rewind_block,
+            )
+            .await
+            .with_context(|| {
+                format!("Failed to rewind CSV checkpoint of {}", csv.path.display())
+            })?;
+
+            tracing::info!(
+                "CSV: deleted {} rows from blocks {}-{} in {}",
+                tx_hashes.len(),
+                self.fork_point,
+                self.detection_point,
+                csv.path.display()
+            );
+            rows_deleted.push(tx_hashes.len() as u64);
+            affected_tx_hashes.extend(tx_hashes);
+        }
+
+        affected_tx_hashes.sort();
+        affected_tx_hashes.dedup();
+
+        Ok((rows_deleted, affected_tx_hashes))
+    }
+
     /// Returns ` AND network = '<network>'` when not cross-chain, empty string otherwise.
     fn network_filter(&self, cross_chain: bool) -> String {
         if cross_chain {
@@ -617,28 +759,70 @@ impl ReorgTask {
                 } else {
                     format!(" AND dt.network = '{}'", self.network)
                 };
+                let where_key_join = Self::journal_where_key_join(
+                    &jc.where_columns,
+                    jc.bucket_column.as_deref(),
+                    "dt",
+                    "sub",
+                );
+
+                if jc.action == SetAction::CountDistinct {
+                    self.recount_distinct_values(dt, jc, &network_filter, pg, ch).await?;
+                    continue;
+                }
 
                 // --- Postgres recalculation ---
                 if let Some(pg) = pg {
-                    let update_sql = if matches!(jc.action, SetAction::Set) {
+                    let update_sql = if jc.journals_elements() {
+                        format!(
+                            "UPDATE {} AS dt SET {} = sub.recalc::{} FROM ({}) sub WHERE {} {}",
+                            dt.full_table_name,
+                            jc.derived_column,
+                            jc.array_type.as_deref().unwrap_or("TEXT[]"),
+                            Self::journal_elements_pg(&dt.full_table_name, jc, &network_filter),
+                            where_key_join,
+                            network_join,
+                        )
+                    } else if jc.action.keeps_last() || jc.action.keeps_first() {
+                        let order = if jc.action.keeps_first() { "ASC" } else { "DESC" };
                         format!(
                             "UPDATE {} AS dt SET {} = sub.value \
                              FROM ( \
                                  SELECT DISTINCT ON (where_key) where_key, value \
                                  FROM rindexer_internal.derived_op_log \
                                  WHERE derived_table = '{}' AND column_name = '{}'{} \
-                                 ORDER BY where_key, block_number DESC, tx_index DESC, log_index DESC \
+                                 ORDER BY where_key, block_number {order}, tx_index {order}, log_index {order} \
                              ) sub \
                              WHERE {} {}",
                             dt.full_table_name, jc.derived_column,
                             dt.full_table_name, jc.derived_column, network_filter,
-                            Self::journal_where_key_join(&jc.where_columns, "dt", "sub"),
+                            where_key_join,
+                            network_join,
+                        )
+                    } else if jc.action == SetAction::Avg {
+                        format!(
+                            "UPDATE {} AS dt SET {} = sub.recalc, {} = sub.samples \
+                             FROM ( \
+                                 SELECT where_key, AVG(value) AS recalc, COUNT(*) AS samples \
+                                 FROM rindexer_internal.derived_op_log \
+                                 WHERE derived_table = '{}' AND column_name = '{}'{} \
+                                 GROUP BY where_key \
+                             ) sub \
+                             WHERE {} {}",
+                            dt.full_table_name,
+                            jc.derived_column,
+                            injected_columns::avg_samples(&jc.derived_column),
+                            dt.full_table_name,
+                            jc.derived_column,
+                            network_filter,
+                            where_key_join,
                             network_join,
                         )
                     } else {
                         let agg_fn = match jc.action {
                             SetAction::Max => "MAX(value)",
                             SetAction::Min => "MIN(value)",
+                            _ if jc.resums() => "SUM(value)",
                             _ => continue,
                         };
                         format!(
@@ -656,7 +840,7 @@ impl ReorgTask {
                             dt.full_table_name,
                             jc.derived_column,
                             network_filter,
-                            Self::journal_where_key_join(&jc.where_columns, "dt", "sub"),
+                            where_key_join,
                             network_join,
                         )
                     };
@@ -675,7 +859,9 @@ impl ReorgTask {
                 }
 
                 // --- ClickHouse recalculation ---
-                if let Some(ch) = ch {
+                // Bucket tables on ClickHouse store per-block partial aggregates, which are
+                // dropped below instead of recalculated.
+                if let Some(ch) = ch.filter(|_| jc.bucket_column.is_none()) {
                     let ch_network = if dt.cross_chain {
                         "1 = 1".to_string()
                     } else {
@@ -684,12 +870,15 @@ impl ReorgTask {
 
                     let ch_where_key_expr = Self::journal_where_key_concat_ch(&jc.where_columns);
 
-                    let ch_subquery = if matches!(jc.action, SetAction::Set) {
+                    let ch_subquery = if jc.journals_elements() {
+                        Self::journal_elements_ch(&dt.full_table_name, jc, &network_filter)
+                    } else if jc.action.keeps_last() || jc.action.keeps_first() {
+                        let order = if jc.action.keeps_first() { "ASC" } else { "DESC" };
                         format!(
                             "(SELECT value FROM rindexer_internal.derived_op_log \
                              WHERE derived_table = '{}' AND column_name = '{}' \
                              AND where_key = {} {} \
-                             ORDER BY block_number DESC, tx_index DESC, log_index DESC LIMIT 1)",
+                             ORDER BY block_number {order}, tx_index {order}, log_index {order} LIMIT 1)",
                             dt.full_table_name,
                             jc.derived_column,
                             ch_where_key_expr,
@@ -699,6 +888,8 @@ impl ReorgTask {
                         let agg_fn = match jc.action {
                             SetAction::Max => "max(value)",
                             SetAction::Min => "min(value)",
+                            SetAction::Avg => "avg(value)",
+                            _ if jc.resums() => "sum(value)",
                             _ => continue,
                         };
                         format!(
@@ -731,14 +922,179 @@ impl ReorgTask {
                     );
                 }
             }
+
+            // Rows whose every write was reorged out no longer have journal entries.
+            if let Some(jc) =
+                dt.journal_columns.iter().find(|jc| jc.bucket_column.is_some() || jc.full_journal)
+            {
+                self.remove_reorged_rows(dt, jc, &network_filter, pg, ch).await?;
+            }
         }
         Ok(())
     }
 
+    /// Drops the values a `count_distinct` column first saw in the reorged blocks from the
+    /// table's distinct values side table, then recounts the rows written since the fork
+    /// point. Older rows can't have counted a reorged value.
+    async fn recount_distinct_values(
+        &self,
+        dt: &DerivedTableInfo,
+        jc: &DerivedColumnJournal,
+        network_filter: &str,
+        pg: Option<&PostgresClient>,
+        ch: Option<&Arc<ClickhouseClient>>,
+    ) -> anyhow::Result<()> {
+        let distinct_table = generate_distinct_values_table_full_name(&dt.full_table_name);
+
+        if let Some(pg) = pg {
+            let (dt_network, sub_network) = if dt.cross_chain {
+                (String::new(), String::new())
+            } else {
+                (
+                    format!(" AND dt.network = '{}'", self.network),
+                    " AND sub.network = dt.network".to_string(),
+                )
+            };
+            let sql = format!(
+                "DELETE FROM {distinct_table} WHERE block_number >= {fork_point}{network_filter}; \
+                 UPDATE {table} AS dt SET {column} = ( \
+                     SELECT COUNT(*) FROM {distinct_table} sub \
+                     WHERE sub.column_name = '{column}'{sub_network} AND {where_key_join} \
+                 ) \
+                 WHERE dt.rindexer_block_number >= {fork_point}{dt_network}",
+                distinct_table = distinct_table,
+                fork_point = self.fork_point,
+                network_filter = network_filter,
+                table = dt.full_table_name,
+                column = jc.derived_column,
+                sub_network = sub_network,
+                where_key_join = Self::journal_where_key_join(
+                    &jc.where_columns,
+                    jc.bucket_column.as_deref(),
+                    "dt",
+                    "sub"
+                ),
+                dt_network = dt_network,
+            );
+            pg.batch_execute(&sql).await.with_context(|| {
+                format!(
+                    "PG: failed to recount distinct values of {} in {}",
+                    jc.derived_column, dt.full_table_name
+                )
+            })?;
+            tracing::info!(
+                table = %dt.full_table_name,
+                column = %jc.derived_column,
+                "PG: recounted distinct values"
+            );
+        }
+
+        if let Some(ch) = ch {
+            let ch_delete = format!(
+                "ALTER TABLE {} DELETE WHERE block_number >= {}{} SETTINGS mutations_sync = 1",
+                distinct_table, self.fork_point, network_filter,
+            );
+            ch.execute(&ch_delete).await.with_context(|| {
+                format!("CH: failed to delete reorged distinct values of {}", dt.full_table_name)
+            })?;
+
+            let ch_update = format!(
+                "ALTER TABLE {table} UPDATE {column} = ( \
+                     SELECT count() FROM {distinct_table} FINAL \
+                     WHERE column_name = '{column}' AND where_key = {where_key}{network_filter} \
+                 ) \
+                 WHERE rindexer_block_number >= {fork_point}{network_filter} \
+                 SETTINGS mutations_sync = 1",
+                table = dt.full_table_name,
+                column = jc.derived_column,
+                distinct_table = distinct_table,
+                where_key = Self::journal_where_key_concat_ch(&jc.where_columns),
+                network_filter = network_filter,
+                fork_point = self.fork_point,
+            );
+            ch.execute(&ch_update).await.with_context(|| {
+                format!(
+                    "CH: failed to recount distinct values of {} in {}",
+                    jc.derived_column, dt.full_table_name
+                )
+            })?;
+            tracing::info!(
+                table = %dt.full_table_name,
+                column = %jc.derived_column,
+                "CH: recounted distinct values"
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Removes rows of fully journaled tables emptied by the reorg. Postgres rows are deleted
+    /// when they were touched at or after the fork point and no journal entry matches them
+    /// any more; ClickHouse bucket partial aggregates are keyed by block, so the reorged ones
+    /// are dropped.
+    async fn remove_reorged_rows(
+        &self,
+        dt: &DerivedTableInfo,
+        jc: &DerivedColumnJournal,
+        network_filter: &str,
+        pg: Option<&PostgresClient>,
+        ch: Option<&Arc<ClickhouseClient>>,
+    ) -> anyhow::Result<()> {
+        let dt_network = if dt.cross_chain {
+            String::new()
+        } else {
+            format!(" AND dt.network = '{}'", self.network)
+        };
+
+        if let Some(pg) = pg {
+            let delete_sql = format!(
+                "DELETE FROM {} AS dt \
+                 WHERE dt.rindexer_block_number >= {}{} \
+                 AND NOT EXISTS ( \
+                     SELECT 1 FROM rindexer_internal.derived_op_log sub \
+                     WHERE sub.derived_table = '{}'{} AND {} \
+                 )",
+                dt.full_table_name,
+                self.fork_point,
+                dt_network,
+                dt.full_table_name,
+                network_filter.replace(" AND network", " AND sub.network"),
+                Self::journal_where_key_join(
+                    &jc.where_columns,
+                    jc.bucket_column.as_deref(),
+                    "dt",
+                    "sub"
+                ),
+            );
+            pg.batch_execute(&delete_sql).await.with_context(|| {
+                format!("PG: failed to remove reorged buckets in {}", dt.full_table_name)
+            })?;
+        }
+
+        if let Some(ch) = ch.filter(|_| jc.bucket_column.is_some()) {
+            let ch_delete = format!(
+                "ALTER TABLE {} DELETE WHERE rindexer_block_number >= {}{} \
+                 SETTINGS mutations_sync = 1",
+                dt.full_table_name, self.fork_point, network_filter,
+            );
+            ch.execute(&ch_delete).await.with_context(|| {
+                format!("CH: failed to remove reorged buckets in {}", dt.full_table_name)
+            })?;
+        }
+
+        Ok(())
+    }
+
     /// Build a WHERE clause joining derived table rows to journal where_key.
     /// The journal stores where_key as "col1=val1,col2=val2", so we match using
-    /// string concatenation on the derived table side.
-    fn journal_where_key_join(where_columns: &[String], dt_alias: &str, sub_alias: &str) -> String {
+    /// string concatenation on the derived table side. The bucket column is journaled
+    /// as unix seconds.
+    fn journal_where_key_join(
+        where_columns: &[String],
+        bucket_column: Option<&str>,
+        dt_alias: &str,
+        sub_alias: &str,
+    ) -> String {
         if where_columns.is_empty() {
             return format!("{}.network = {}.where_key", dt_alias, sub_alias);
         }
@@ -747,10 +1103,14 @@ impl ReorgTask {
             .iter()
             .enumerate()
             .map(|(i, col)| {
-                if i == 0 {
-                    format!("'{}=' || {}.{}::TEXT", col, dt_alias, col)
+                let separator = if i == 0 { "" } else { "," };
+                if Some(col.as_str()) == bucket_column {
+                    format!(
+                        "'{}{}=' || EXTRACT(EPOCH FROM {}.{})::BIGINT::TEXT",
+                        separator, col, dt_alias, col
+                    )
                 } else {
-                    format!("',{}=' || {}.{}::TEXT", col, dt_alias, col)
+                    format!("'{}{}=' || {}.{}::TEXT", separator, col, dt_alias, col)
                 }
             })
             .collect();
@@ -758,6 +1118,63 @@ impl ReorgTask {
         format!("{}.where_key = {}", sub_alias, concat_parts.join(" || "))
     }
 
+    /// Elements of an array column left after replaying its journal, one `recalc` array per
+    /// where_key. An element survives when it was written after its last
+    /// removal; `append` lists keep event order, sets are deduplicated.
+    fn journal_elements_pg(table: &str, jc: &DerivedColumnJournal, network_filter: &str) -> String {
+        let seq = "block_number * 100000000 + tx_index::BIGINT * 100000 + log_index";
+        let elements = if matches!(jc.action, SetAction::Append | SetAction::Remove) {
+            "array_agg(value_text ORDER BY seq)"
+        } else {
+            "array_agg(DISTINCT value_text)"
+        };
+
+        format!(
+            "SELECT where_key, COALESCE({elements} \
+                 FILTER (WHERE value > 0 AND seq > COALESCE(removed_at, -1)), '{{}}') AS recalc \
+             FROM ( \
+                 SELECT where_key, value, value_text, {seq} AS seq, \
+                        MAX(CASE WHEN value < 0 THEN {seq} END) \
+                            OVER (PARTITION BY where_key, value_text) AS removed_at \
+                 FROM rindexer_internal.derived_op_log \
+                 WHERE derived_table = '{table}' AND column_name = '{column}'{network_filter} \
+             ) ops \
+             GROUP BY where_key",
+            elements = elements,
+            seq = seq,
+            table = table,
+            column = jc.derived_column,
+            network_filter = network_filter,
+        )
+    }
+
+    /// ClickHouse counterpart of `journal_elements_pg`, correlated with the updated row.
+    fn journal_elements_ch(table: &str, jc: &DerivedColumnJournal, network_filter: &str) -> String {
+        let seq = "block_number * 100000000 + tx_index * 100000 + log_index";
+        let elements = "groupArrayIf(assumeNotNull(value_text), value > 0 AND seq > removed_at)";
+        let elements = if matches!(jc.action, SetAction::Append | SetAction::Remove) {
+            elements.to_string()
+        } else {
+            format!("arrayDistinct({})", elements)
+        };
+
+        format!(
+            "(SELECT {elements} FROM ( \
+                 SELECT value_text, value, {seq} AS seq, \
+                        max(if(value < 0, {seq}, 0)) OVER (PARTITION BY value_text) AS removed_at \
+                 FROM rindexer_internal.derived_op_log \
+                 WHERE derived_table = '{table}' AND column_name = '{column}' \
+                 AND where_key = {where_key}{network_filter} \
+                 ORDER BY seq))",
+            elements = elements,
+            seq = seq,
+            table = table,
+            column = jc.derived_column,
+            where_key = Self::journal_where_key_concat_ch(&jc.where_columns),
+            network_filter = network_filter,
+        )
+    }
+
     /// Build a ClickHouse expression that reconstructs the where_key string
     /// from table columns using `concat()`.
     fn journal_where_key_concat_ch(where_columns: &[String]) -> String {
@@ -834,7 +1251,7 @@ impl ReorgTask {
         let reversal_snapshots = self.snapshot_for_reversal(postgres, clickhouse).await?;
 
         let mut affected_tx_hashes: Vec<String> = Vec::new();
-        let mut total_deleted = 0u64;
+        let mut rows_deleted: Vec<u64> = vec![0; self.event_tables.len()];
 
         if let Some(pg) = postgres {
             let table_names: Vec<&str> =
@@ -853,7 +1270,7 @@ impl ReorgTask {
                 )
                 .await
                 .context("PostgreSQL reorg rollback transaction failed")?;
-            total_deleted = deleted;
+            rows_deleted = deleted;
             affected_tx_hashes = tx_hashes;
         }
 
@@ -879,18 +1296,28 @@ impl ReorgTask {
                 .context("ClickHouse reorg rollback failed")?;
 
             if postgres.is_none() {
-                total_deleted = ch_deleted;
+                rows_deleted = ch_deleted;
                 affected_tx_hashes = ch_tx_hashes;
-            } else if ch_deleted != total_deleted {
+            } else if ch_deleted != rows_deleted {
                 tracing::warn!(
                     network = %self.network,
-                    postgres_deleted = total_deleted,
-                    clickhouse_deleted = ch_deleted,
+                    postgres_deleted = rows_deleted.iter().sum::<u64>(),
+                    clickhouse_deleted = ch_deleted.iter().sum::<u64>(),
                     "Reorg rollback: postgres and clickhouse deleted counts differ"
                 );
             }
         }
 
+        // CSV output is append-only, the files are rewritten without the reorged rows.
+        if self.event_tables.iter().any(|t| t.csv.is_some()) {
+            let (csv_deleted, csv_tx_hashes) =
+                self.rollback_csv_files().await.context("CSV reorg rollback failed")?;
+            if postgres.is_none() && clickhouse.is_none() {
+                rows_deleted = csv_deleted;
+                affected_tx_hashes = csv_tx_hashes;
+            }
+        }
+
         // Phase 2: apply accumulative reversals from snapshots (after event deletion)
         Self::apply_reversal_from_snapshots(&reversal_snapshots, postgres, clickhouse)
             .await
@@ -945,6 +1372,31 @@ impl ReorgTask {
             }
         }
 
+        // Phase 5: truncate row versions of history tables above the fork point. The
+        // versions still open at the fork point match the rolled back table again.
+        if let Some(pg) = postgres {
+            for dt in self.derived_tables.iter().filter(|dt| dt.history) {
+                let history_table_name = generate_history_table_full_name(&dt.full_table_name);
+                let network_filter = self.network_filter(dt.cross_chain);
+                let query = format!(
+                    "DELETE FROM {history} WHERE {valid_from} >= {fork}{network_filter};\n\
+                     UPDATE {history} SET {valid_to} = NULL WHERE {valid_to} >= {fork}{network_filter};",
+                    history = history_table_name,
+                    valid_from = injected_columns::VALID_FROM,
+                    valid_to = injected_columns::VALID_TO,
+                    fork = self.fork_point,
+                );
+                pg.batch_execute(&query).await.with_context(|| {
+                    format!("PostgreSQL: failed to truncate row versions in {}", history_table_name)
+                })?;
+                tracing::info!(
+                    "PostgreSQL: truncated row versions from block >= {} in {}",
+                    self.fork_point,
+                    history_table_name
+                );
+            }
+        }
+
         // Update the in-memory window after all DB changes succeed.
         // When canonical blocks are available (parent-hash detection), overwrite with corrected hashes.
         // When canonical blocks are empty (removed-logs / ExEx detection), remove stale entries
@@ -955,6 +1407,7 @@ impl ReorgTask {
             window.remove_from(self.fork_point);
         }
 
+        let total_deleted: u64 = rows_deleted.iter().sum();
         let duration = start.elapsed().as_secs_f64();
         metrics::record_reorg_handling_duration(&self.network, duration);
         metrics::record_reorg_events_deleted(&self.network, total_deleted);
@@ -972,12 +1425,11 @@ impl ReorgTask {
         let affected_tables: Vec<AffectedTable> = self
             .event_tables
             .iter()
-            .map(|t| AffectedTable {
+            .zip(&rows_deleted)
+            .map(|(t, &rows_deleted)| AffectedTable {
                 schema: t.schema.clone(),
                 table_name: t.table_name.clone(),
-                // TODO(future): per-table counts from DB layer; total is on
-                // `events_deleted`.
-                rows_deleted: 0,
+                rows_deleted,
                 indexer_name: t.indexer_name.clone(),
                 contract_name: t.contract_name.clone(),
                 event_name: t.event_name.clone(),
@@ -1087,27 +1539,20 @@ mod tests {
         let at = AffectedTable {
             schema: "s1".to_string(),
             table_name: "t1".to_string(),
-            rows_deleted: 0,
+            rows_deleted: 3,
             indexer_name: "idx".to_string(),
             contract_name: "USDC".to_string(),
             event_name: "NativeTransfer".to_string(),
         };
-        // The struct is used inside stream payloads; mirror the JSON shape
-        // that downstream consumers rely on (field-by-field).
-        let json = serde_json::json!({
-            "schema": at.schema,
-            "table_name": at.table_name,
-            "rows_deleted": at.rows_deleted,
-            "indexer_name": at.indexer_name,
-            "contract_name": at.contract_name,
-            "event_name": at.event_name,
-        });
+        // The JSON is used inside stream payloads and the reorg audit log;
+        // downstream consumers rely on these keys.
+        let json = at.to_json();
         assert_eq!(json["schema"], "s1");
-        assert_eq!(json["table_name"], "t1");
-        assert_eq!(json["rows_deleted"], 0);
-        assert_eq!(json["indexer_name"], "idx");
-        assert_eq!(json["contract_name"], "USDC");
-        assert_eq!(json["event_name"], "NativeTransfer");
+        assert_eq!(json["table"], "t1");
+        assert_eq!(json["rows_deleted"], 3);
+        assert_eq!(json["indexer"], "idx");
+        assert_eq!(json["contract"], "USDC");
+        assert_eq!(json["event"], "NativeTransfer");
     }
 
     // ======================================================================
@@ -1181,6 +1626,45 @@ mod tests {
         assert!(err.is_err(), "where_columns entry with SQL injection must be rejected");
     }
 
+    #[test]
+    fn test_journal_elements_replays_removals() {
+        let list = DerivedColumnJournal::try_new(
+            "holders".to_string(),
+            SetAction::Append,
+            vec!["pool".to_string()],
+        )
+        .unwrap()
+        .with_array_type("TEXT[]".to_string());
+        assert_eq!(list.array_type.as_deref(), Some("TEXT[]"));
+
+        let pg = ReorgTask::journal_elements_pg("s.pools", &list, " AND network = 'base'");
+        assert!(pg.contains("array_agg(value_text ORDER BY seq)"));
+        assert!(pg.contains("OVER (PARTITION BY where_key, value_text) AS removed_at"));
+        assert!(pg.contains("column_name = 'holders' AND network = 'base'"));
+
+        let set = DerivedColumnJournal::try_new(
+            "unique_holders".to_string(),
+            SetAction::SetUnion,
+            vec!["pool".to_string()],
+        )
+        .unwrap();
+        assert!(set.journals_elements());
+        assert!(ReorgTask::journal_elements_pg("s.pools", &set, "")
+            .contains("array_agg(DISTINCT value_text)"));
+        let ch = ReorgTask::journal_elements_ch("s.pools", &set, "");
+        assert!(ch.starts_with("(SELECT arrayDistinct(groupArrayIf("));
+        assert!(ch.contains("AND where_key = concat('pool=', toString(pool))"));
+
+        // Distinct counts are recounted from their side table instead
+        let count = DerivedColumnJournal::try_new(
+            "unique_holders".to_string(),
+            SetAction::CountDistinct,
+            vec!["pool".to_string()],
+        )
+        .unwrap();
+        assert!(!count.journals_elements());
+    }
+
     // ======================================================================
     // DerivedTableRollbackOp::try_new
     // ======================================================================
@@ -1240,4 +1724,57 @@ mod tests {
         assert!(dt.cross_chain);
         assert_eq!(dt.rollback_ops.len(), 1);
     }
+
+    // ======================================================================
+    // CSV rollback
+    // ======================================================================
+
+    #[tokio::test]
+    async fn test_rollback_csv_files_removes_rows_and_rewinds_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("usdc").join("usdc-transfer.csv");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(
+            &path,
+            "contract_address,value,tx_hash,block_number,block_hash,network\n\
+             0xa,1,0x1,99,0xb1,ethereum\n\
+             0xa,2,0x2,100,0xb2,ethereum\n\
+             0xa,3,0x3,101,0xb3,ethereum\n",
+        )
+        .unwrap();
+        let checkpoint_dir = dir.path().join("USDC").join("last-synced-blocks");
+        std::fs::create_dir_all(&checkpoint_dir).unwrap();
+        std::fs::write(checkpoint_dir.join("usdc-ethereum-transfer.txt"), "101").unwrap();
+
+        let table = EventTableInfo::try_new(
+            "my_schema".to_string(),
+            "transfer".to_string(),
+            "my_schema_transfer".to_string(),
+            "my_indexer".to_string(),
+            "USDC".to_string(),
+            "Transfer".to_string(),
+        )
+        .unwrap()
+        .with_csv(Some(EventCsvInfo { path: path.clone(), csv_folder: dir.path().to_path_buf() }));
+        let task = ReorgTask {
+            network: "ethereum".to_string(),
+            fork_point: 100,
+            detection_point: 101,
+            detection_source: "rpc",
+            event_tables: vec![table],
+            derived_tables: vec![],
+            canonical_blocks: vec![],
+        };
+
+        let (rows_deleted, tx_hashes) = task.rollback_csv_files().await.unwrap();
+        assert_eq!(rows_deleted, vec![2]);
+        assert_eq!(tx_hashes, vec!["0x2", "0x3"]);
+
+        let remaining = std::fs::read_to_string(&path).unwrap();
+        assert!(remaining.contains("0x1"));
+        assert!(!remaining.contains("0x2") && !remaining.contains("0x3"));
+        let checkpoint =
+            std::fs::read_to_string(checkpoint_dir.join("usdc-ethereum-transfer.txt")).unwrap();
+        assert_eq!(checkpoint.trim(), "99");
+    }
 }
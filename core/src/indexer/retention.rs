@@ -0,0 +1,381 @@
+//! Retention policies dropping old rows of raw event and custom tables.
+//!
+//! ClickHouse drops rows past a `max_age` on its own through the table `TTL`. Everything else,
+//! Postgres rows and ClickHouse rows kept by block age, is deleted by a periodic job which
+//! never touches blocks still inside the reorg window of a network.
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use clickhouse::Row;
+use serde::Deserialize;
+use tracing::{debug, error, info};
+
+use crate::database::clickhouse::client::ClickhouseClient;
+use crate::database::generate::{generate_event_table_full_name, generate_table_full_name};
+use crate::database::postgres::client::PostgresClient;
+use crate::indexer::reorg::{
+    finalized_buffer_distance_for_network, reorg_safe_distance_for_chain, validate_sql_value,
+};
+use crate::is_running;
+use crate::manifest::contract::{injected_columns, RetentionPolicy};
+use crate::manifest::core::Manifest;
+use crate::metrics::database::record_rows_pruned;
+use crate::provider::ChainProvider;
+
+/// How often the retention job runs.
+const RETENTION_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Rows deleted per Postgres statement, keeping locks and WAL bursts short.
+const POSTGRES_DELETE_BATCH_SIZE: u64 = 10_000;
+
+/// A table pruned by the retention job.
+#[derive(Debug, Clone)]
+pub struct RetentionTarget {
+    pub table_full_name: String,
+    pub policy: RetentionPolicy,
+    pub block_column: String,
+    pub timestamp_column: Option<String>,
+    /// Networks the table holds rows of, empty for cross-chain tables which have no
+    /// `network` column.
+    pub networks: Vec<String>,
+}
+
+/// Rows of a table a run deletes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PruneCutoff {
+    /// Rows of blocks before this one.
+    Block(u64),
+    /// Rows older than `max_age_secs`, restricted to blocks before `safe_block` when the
+    /// table is per network.
+    Age { max_age_secs: u64, safe_block: Option<u64> },
+}
+
+/// Collects the tables with a retention policy.
+pub fn retention_targets(manifest: &Manifest) -> Vec<RetentionTarget> {
+    let mut targets = vec![];
+
+    for contract in manifest.all_contracts() {
+        let networks: Vec<String> = contract.details.iter().map(|d| d.network.clone()).collect();
+
+        for event in contract.include_events.iter().flatten() {
+            let Some(Ok(policy)) = contract.event_retention(&event.name).map(|r| r.policy()) else {
+                continue;
+            };
+            targets.push(RetentionTarget {
+                table_full_name: generate_event_table_full_name(
+                    &manifest.name,
+                    &contract.before_modify_name_if_filter_readonly(),
+                    &event.name,
+                ),
+                policy,
+                block_column: "block_number".to_string(),
+                timestamp_column: Some("block_timestamp".to_string()),
+                networks: networks.clone(),
+            });
+        }
+
+        for table in contract.tables.iter().flatten() {
+            let Some(Ok(policy)) = table.retention.as_ref().map(|r| r.policy()) else {
+                continue;
+            };
+            targets.push(RetentionTarget {
+                table_full_name: generate_table_full_name(
+                    &manifest.name,
+                    &contract.name,
+                    &table.name,
+                    table.database.as_deref(),
+                ),
+                policy,
+                block_column: injected_columns::BLOCK_NUMBER.to_string(),
+                timestamp_column: table.retention_timestamp_column().map(str::to_string),
+                networks: if table.cross_chain { vec![] } else { networks.clone() },
+            });
+        }
+    }
+
+    targets
+}
+
+/// Works out the rows of a network to delete, None when the chain is shorter than the
+/// retention. Block ages never reach into the reorg window.
+fn prune_cutoff(policy: RetentionPolicy, head: u64, reorg_distance: u64) -> Option<PruneCutoff> {
+    match policy {
+        RetentionPolicy::MaxBlocks(max_blocks) => {
+            head.checked_sub(max_blocks.max(reorg_distance)).map(PruneCutoff::Block)
+        }
+        RetentionPolicy::MaxAge(max_age_secs) => Some(PruneCutoff::Age {
+            max_age_secs,
+            safe_block: Some(head.saturating_sub(reorg_distance)),
+        }),
+    }
+}
+
+/// Builds the condition matching the rows to delete. `network` is interpolated and must be
+/// validated first.
+fn prune_condition(
+    target: &RetentionTarget,
+    network: Option<&str>,
+    cutoff: PruneCutoff,
+    quote: char,
+) -> Option<String> {
+    let mut conditions = vec![];
+    if let Some(network) = network {
+        conditions.push(format!("network = '{}'", network));
+    }
+
+    let block_column = format!("{quote}{}{quote}", target.block_column);
+    match cutoff {
+        PruneCutoff::Block(block) => conditions.push(format!("{} < {}", block_column, block)),
+        PruneCutoff::Age { max_age_secs, safe_block } => {
+            let timestamp_column = target.timestamp_column.as_ref()?;
+            conditions.push(format!(
+                "{quote}{}{quote} < NOW() - INTERVAL '{} seconds'",
+                timestamp_column, max_age_secs
+            ));
+            if let Some(safe_block) = safe_block {
+                conditions.push(format!("{} < {}", block_column, safe_block));
+            }
+        }
+    }
+
+    Some(conditions.join(" AND "))
+}
+
+/// Deletes the rows matching `condition` in batches, returning how many were deleted.
+/// `tableoid` keeps the `ctid` of partitioned tables unique.
+async fn prune_postgres(
+    postgres: &PostgresClient,
+    table_full_name: &str,
+    condition: &str,
+) -> anyhow::Result<u64> {
+    let query = format!(
+        "DELETE FROM {table} WHERE (tableoid, ctid) IN \
+         (SELECT tableoid, ctid FROM {table} WHERE {condition} LIMIT {limit})",
+        table = table_full_name,
+        condition = condition,
+        limit = POSTGRES_DELETE_BATCH_SIZE
+    );
+
+    let mut deleted = 0;
+    while is_running() {
+        let batch = postgres.execute(&query, &[]).await?;
+        deleted += batch;
+        if batch < POSTGRES_DELETE_BATCH_SIZE {
+            break;
+        }
+    }
+
+    Ok(deleted)
+}
+
+/// Deletes the rows matching `condition` with a lightweight delete, returning how many were
+/// deleted.
+async fn prune_clickhouse(
+    clickhouse: &ClickhouseClient,
+    table_full_name: &str,
+    condition: &str,
+) -> anyhow::Result<u64> {
+    #[derive(Row, Deserialize)]
+    struct CountRow {
+        count: u64,
+    }
+
+    let count = clickhouse
+        .query_one::<CountRow>(&format!(
+            "SELECT count() AS count FROM {} WHERE {}",
+            table_full_name, condition
+        ))
+        .await?
+        .count;
+    if count > 0 {
+        clickhouse.execute(&format!("DELETE FROM {} WHERE {}", table_full_name, condition)).await?;
+    }
+
+    Ok(count)
+}
+
+/// Deletes the expired rows of one table on one network, None meaning a cross-chain table.
+async fn prune_target(
+    target: &RetentionTarget,
+    network: Option<&str>,
+    cutoff: PruneCutoff,
+    postgres: Option<&PostgresClient>,
+    clickhouse: Option<&ClickhouseClient>,
+) -> anyhow::Result<()> {
+    if let Some(network) = network {
+        validate_sql_value(network, "network")?;
+    }
+
+    let mut pruned = 0;
+    if let Some(postgres) = postgres {
+        if let Some(condition) = prune_condition(target, network, cutoff, '"') {
+            pruned += prune_postgres(postgres, &target.table_full_name, &condition).await?;
+        }
+    }
+    // Timestamp ages are enforced by the ClickHouse TTL
+    if let (Some(clickhouse), PruneCutoff::Block(_)) = (clickhouse, cutoff) {
+        if let Some(condition) = prune_condition(target, network, cutoff, '`') {
+            pruned += prune_clickhouse(clickhouse, &target.table_full_name, &condition).await?;
+        }
+    }
+
+    if pruned > 0 {
+        let network = network.unwrap_or("all");
+        info!("Retention - pruned {} rows from {} on {}", pruned, target.table_full_name, network);
+        record_rows_pruned(network, &target.table_full_name, pruned);
+    }
+
+    Ok(())
+}
+
+/// Runs the retention job in the background until shutdown.
+pub fn spawn_retention_job(
+    manifest: &Manifest,
+    postgres: Option<Arc<PostgresClient>>,
+    clickhouse: Option<Arc<ClickhouseClient>>,
+    providers: Arc<HashMap<String, Arc<dyn ChainProvider>>>,
+) {
+    let targets = retention_targets(manifest);
+    if targets.is_empty() {
+        return;
+    }
+
+    // The deepest reorg any config of the network can roll back
+    let reorg_distances: HashMap<String, u64> = providers
+        .iter()
+        .map(|(network, provider)| {
+            let chain_id = provider.chain().id();
+            let distance = reorg_safe_distance_for_chain(chain_id)
+                .max(finalized_buffer_distance_for_network(manifest, network, chain_id));
+            (network.clone(), distance)
+        })
+        .collect();
+
+    info!("Starting retention job for {} tables", targets.len());
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(RETENTION_INTERVAL);
+
+        while is_running() {
+            interval.tick().await;
+
+            let mut heads: HashMap<&str, u64> = HashMap::new();
+            for (network, provider) in providers.iter() {
+                match provider.get_block_number().await {
+                    Ok(head) => {
+                        heads.insert(network.as_str(), head.to::<u64>());
+                    }
+                    Err(e) => debug!("Retention - could not get the head of {}: {}", network, e),
+                }
+            }
+
+            for target in &targets {
+                if target.networks.is_empty() {
+                    // Cross-chain tables only support ages, which can't reach the reorg window
+                    let RetentionPolicy::MaxAge(max_age_secs) = target.policy else {
+                        continue;
+                    };
+                    let cutoff = PruneCutoff::Age { max_age_secs, safe_block: None };
+                    if let Err(e) = prune_target(
+                        target,
+                        None,
+                        cutoff,
+                        postgres.as_deref(),
+                        clickhouse.as_deref(),
+                    )
+                    .await
+                    {
+                        error!("Retention - failed to prune {}: {}", target.table_full_name, e);
+                    }
+                    continue;
+                }
+
+                for network in &target.networks {
+                    let (Some(head), Some(distance)) =
+                        (heads.get(network.as_str()), reorg_distances.get(network))
+                    else {
+                        continue;
+                    };
+                    let Some(cutoff) = prune_cutoff(target.policy, *head, *distance) else {
+                        continue;
+                    };
+                    if let Err(e) = prune_target(
+                        target,
+                        Some(network),
+                        cutoff,
+                        postgres.as_deref(),
+                        clickhouse.as_deref(),
+                    )
+                    .await
+                    {
+                        error!(
+                            "Retention - failed to prune {} on {}: {}",
+                            target.table_full_name, network, e
+                        );
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(policy: RetentionPolicy) -> RetentionTarget {
+        RetentionTarget {
+            table_full_name: "indexer_pool.swap".to_string(),
+            policy,
+            block_column: "block_number".to_string(),
+            timestamp_column: Some("block_timestamp".to_string()),
+            networks: vec!["ethereum".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_prune_cutoff_stays_clear_of_reorg_window() {
+        let cutoff = prune_cutoff(RetentionPolicy::MaxBlocks(1_000), 10_000, 64);
+        assert_eq!(cutoff, Some(PruneCutoff::Block(9_000)));
+
+        // A retention shorter than the reorg window keeps the whole window
+        let cutoff = prune_cutoff(RetentionPolicy::MaxBlocks(10), 10_000, 64);
+        assert_eq!(cutoff, Some(PruneCutoff::Block(9_936)));
+
+        // Nothing is old enough on a short chain
+        assert_eq!(prune_cutoff(RetentionPolicy::MaxBlocks(1_000), 500, 64), None);
+
+        let cutoff = prune_cutoff(RetentionPolicy::MaxAge(3_600), 10_000, 64);
+        assert_eq!(cutoff, Some(PruneCutoff::Age { max_age_secs: 3_600, safe_block: Some(9_936) }));
+    }
+
+    #[test]
+    fn test_prune_condition() {
+        let blocks = target(RetentionPolicy::MaxBlocks(1_000));
+        assert_eq!(
+            prune_condition(&blocks, Some("ethereum"), PruneCutoff::Block(9_000), '"').unwrap(),
+            r#"network = 'ethereum' AND "block_number" < 9000"#
+        );
+        assert_eq!(
+            prune_condition(&blocks, Some("ethereum"), PruneCutoff::Block(9_000), '`').unwrap(),
+            "network = 'ethereum' AND `block_number` < 9000"
+        );
+
+        let age = target(RetentionPolicy::MaxAge(3_600));
+        let cutoff = PruneCutoff::Age { max_age_secs: 3_600, safe_block: Some(9_936) };
+        assert_eq!(
+            prune_condition(&age, Some("ethereum"), cutoff, '"').unwrap(),
+            r#"network = 'ethereum' AND "block_timestamp" < NOW() - INTERVAL '3600 seconds' AND "block_number" < 9936"#
+        );
+
+        let cutoff = PruneCutoff::Age { max_age_secs: 3_600, safe_block: None };
+        assert_eq!(
+            prune_condition(&age, None, cutoff, '"').unwrap(),
+            r#""block_timestamp" < NOW() - INTERVAL '3600 seconds'"#
+        );
+
+        let no_timestamp = RetentionTarget { timestamp_column: None, ..age };
+        assert_eq!(prune_condition(&no_timestamp, None, cutoff, '"'), None);
+    }
+}
@@ -12,19 +12,23 @@ use tokio::{
 use tokio_util::sync::CancellationToken;
 use tracing::{error, info, warn};
 
+use crate::abi::csv_file_path_for_event;
+use crate::blockclock::configure_blockclock_catch_up;
 use crate::database::clickhouse::client::{ClickhouseClient, ClickhouseConnectionError};
 use crate::database::generate::generate_indexer_contract_schema_name;
 use crate::database::postgres::generate::generate_internal_event_table_name;
 use crate::event::config::{ContractEventProcessingConfig, FactoryEventProcessingConfig};
 use crate::helpers::{camel_to_snake, format_duration};
+use crate::indexer::log_archive::register_log_archives;
 use crate::indexer::native_transfer::native_transfer_block_processor;
 use crate::indexer::reorg::{
     reorg_safe_distance_for_chain, BlockChainWindow, DerivedColumnJournal, DerivedColumnRollback,
-    DerivedTableInfo, DerivedTableRollbackOp, EventTableInfo, ReorgBlockHashPersistence,
-    ReorgContext, ReorgCoordinator,
+    DerivedTableInfo, DerivedTableRollbackOp, EventCsvInfo, EventTableInfo,
+    ReorgBlockHashPersistence, ReorgContext, ReorgCoordinator,
 };
 use crate::indexer::Indexer;
 use crate::manifest::network::ReorgHandlingConfig;
+use crate::rpc_cache::configure_rpc_cache;
 use crate::{
     database::postgres::client::PostgresConnectionError,
     event::{
@@ -34,6 +38,7 @@ use crate::{
     indexer::{
         dependency::ContractEventsDependenciesConfig,
         last_synced::{get_last_synced_block_number, SyncConfig},
+        live_fetcher::NetworkLiveFetcher,
         native_transfer::{native_transfer_block_fetch, NATIVE_TRANSFER_CONTRACT_NAME},
         process::{
             process_contracts_events_with_dependencies, process_non_blocking_event,
@@ -42,7 +47,10 @@ use crate::{
         progress::IndexingEventsProgressState,
         ContractEventDependencies,
     },
-    manifest::{contract::ReorgSafeDistance, core::Manifest},
+    manifest::{
+        contract::{ReorgSafeDistance, SetAction, Table, TableOperation},
+        core::Manifest,
+    },
     provider::{ChainProvider, ProviderError},
     PostgresClient,
 };
@@ -170,10 +178,25 @@ async fn get_start_end_block(
         }
     }
 
+    let tagged_block = match reorg_safe_distance.and_then(|rsd| rsd.block_tag()) {
+        Some(tag) => match provider.get_tagged_block_number(tag).await {
+            Ok(tagged_block) => tagged_block,
+            Err(e) => {
+                warn!(
+                    "{}::{} - Failed to fetch {:?} block, using the chain default reorg distance: {}",
+                    event_name, network, tag, e
+                );
+                None
+            }
+        },
+        None => None,
+    };
+
     let (end_block, indexing_distance_from_head) = calculate_safe_block_number(
         reorg_safe_distance,
         provider.chain().id(),
         latest_block,
+        tagged_block,
         end_block,
     );
 
@@ -296,6 +319,7 @@ async fn start_indexing_traces(
             start_block,
             network_details.end_block,
             indexing_distance_from_head,
+            first_event.trace_information.reorg_safe_distance.and_then(|rsd| rsd.block_tag()),
             network_name.clone(),
             cancel_token.clone(),
             postgres.clone(),
@@ -434,6 +458,27 @@ fn collect_streams_clients_for_network(
     out
 }
 
+/// The CSV file of an event when CSV storage is enabled for it, so a reorg can roll it back.
+fn event_csv_info(
+    manifest: &Manifest,
+    project_path: &Path,
+    csv_enabled: bool,
+    contract_name: &str,
+    event_name: &str,
+) -> Option<EventCsvInfo> {
+    if !csv_enabled {
+        return None;
+    }
+
+    let csv_path = manifest.storage.csv.as_ref().map_or("generated_csv", |c| c.path.as_str());
+    let csv_path = csv_path.strip_prefix("./").unwrap_or(csv_path);
+
+    Some(EventCsvInfo {
+        path: csv_file_path_for_event(project_path, csv_path, contract_name, event_name),
+        csv_folder: project_path.join(csv_path),
+    })
+}
+
 /// Build derived-table rollback + journal entries for an event's tables and merge
 /// them into `accumulator` keyed by `network`. Shared between contract events and
 /// native-transfer trace events so both sources contribute to reorg rollback.
@@ -480,6 +525,35 @@ fn build_derived_tables_for_event(
                 let where_col_names: Vec<String> =
                     where_columns.iter().map(|(col, _)| col.clone()).collect();
 
+                // Tables following other tables' changes are recalculated from the journal
+                if !tr.table.on_change.is_empty() {
+                    push_full_journal_columns(&tr.table, operation, &mut journal_columns)?;
+                    continue;
+                }
+
+                // Bucket tables can't be reversed from the event table, which has no bucket
+                // column, so every column is recalculated from the journal instead.
+                if let Some(bucket) = &tr.table.bucket {
+                    let mut journal_where: Vec<String> =
+                        operation.where_clause.keys().cloned().collect();
+                    journal_where.push(bucket.column.clone());
+                    journal_where.sort();
+
+                    for set_col in &operation.set {
+                        if !journal_columns.iter().any(|jc| jc.derived_column == set_col.column) {
+                            journal_columns.push(
+                                DerivedColumnJournal::try_new(
+                                    set_col.column.clone(),
+                                    set_col.action.clone(),
+                                    journal_where.clone(),
+                                )?
+                                .with_bucket_column(bucket.column.clone())?,
+                            );
+                        }
+                    }
+                    continue;
+                }
+
                 let columns: Vec<DerivedColumnRollback> = operation
                     .set
                     .iter()
@@ -500,13 +574,31 @@ fn build_derived_tables_for_event(
                     if set_col.action.reverse().is_some() {
                         continue; // handled by rollback_ops
                     }
-                    if !journal_columns.iter().any(|jc| jc.derived_column == set_col.column) {
-                        journal_columns.push(DerivedColumnJournal::try_new(
-                            set_col.column.clone(),
-                            set_col.action.clone(),
-                            where_col_names.clone(),
-                        )?);
+                    if let Some(existing) =
+                        journal_columns.iter_mut().find(|jc| jc.derived_column == set_col.column)
+                    {
+                        merge_journal_action(existing, &set_col.action);
+                        continue;
                     }
+
+                    let mut journal_column = DerivedColumnJournal::try_new(
+                        set_col.column.clone(),
+                        set_col.action.clone(),
+                        where_col_names.clone(),
+                    )?;
+                    if set_col.action.is_array_action() {
+                        if let Some(column_type) = tr
+                            .table
+                            .columns
+                            .iter()
+                            .find(|c| c.name == set_col.column)
+                            .and_then(|c| c.column_type.as_ref())
+                        {
+                            journal_column =
+                                journal_column.with_array_type(column_type.to_postgres_type());
+                        }
+                    }
+                    journal_columns.push(journal_column);
                 }
 
                 if !columns.is_empty() {
@@ -520,28 +612,95 @@ fn build_derived_tables_for_event(
             }
         }
 
+        // Rows changed by this event in followed tables trigger their operations too
+        for operation in tr.table.on_change.iter().flat_map(|c| c.operations.iter()) {
+            push_full_journal_columns(&tr.table, operation, &mut journal_columns)?;
+        }
+
         // Merge into existing entry or create a new one
         if let Some(existing) = derived.iter_mut().find(|d| d.full_table_name == tr.full_table_name)
         {
             existing.rollback_ops.extend(rollback_ops);
             for jc in journal_columns {
-                if !existing.journal_columns.iter().any(|e| e.derived_column == jc.derived_column) {
-                    existing.journal_columns.push(jc);
+                match existing
+                    .journal_columns
+                    .iter_mut()
+                    .find(|e| e.derived_column == jc.derived_column)
+                {
+                    Some(e) => merge_journal_action(e, &jc.action),
+                    None => existing.journal_columns.push(jc),
                 }
             }
         } else {
-            derived.push(DerivedTableInfo::try_new(
-                tr.full_table_name.clone(),
-                tr.table.cross_chain,
-                rollback_ops,
-                journal_columns,
-            )?);
+            derived.push(
+                DerivedTableInfo::try_new(
+                    tr.full_table_name.clone(),
+                    tr.table.cross_chain,
+                    rollback_ops,
+                    journal_columns,
+                )?
+                .with_history(tr.table.history),
+            );
         }
     }
 
     Ok(())
 }
 
+/// Registers every column written by `operation` for recalculation from the journal. Tables
+/// following other tables' changes journal all their writes, their values can't be reversed
+/// from an event table.
+fn push_full_journal_columns(
+    table: &Table,
+    operation: &TableOperation,
+    journal_columns: &mut Vec<DerivedColumnJournal>,
+) -> anyhow::Result<()> {
+    use crate::manifest::contract::OperationType;
+    if !matches!(operation.operation_type, OperationType::Upsert | OperationType::Update) {
+        return Ok(());
+    }
+
+    let mut where_columns: Vec<String> = operation.where_clause.keys().cloned().collect();
+    where_columns.sort();
+
+    for set_col in &operation.set {
+        if let Some(existing) =
+            journal_columns.iter_mut().find(|jc| jc.derived_column == set_col.column)
+        {
+            merge_journal_action(existing, &set_col.action);
+            continue;
+        }
+
+        let mut journal_column = DerivedColumnJournal::try_new(
+            set_col.column.clone(),
+            set_col.action.clone(),
+            where_columns.clone(),
+        )?
+        .with_full_journal();
+        if set_col.action.is_array_action() {
+            if let Some(column_type) = table
+                .columns
+                .iter()
+                .find(|c| c.name == set_col.column)
+                .and_then(|c| c.column_type.as_ref())
+            {
+                journal_column = journal_column.with_array_type(column_type.to_postgres_type());
+            }
+        }
+        journal_columns.push(journal_column);
+    }
+
+    Ok(())
+}
+
+/// An array column written by several operations is rebuilt from its journal the way its
+/// appends build it, whichever operation registered the column first.
+fn merge_journal_action(journal_column: &mut DerivedColumnJournal, action: &SetAction) {
+    if journal_column.action == SetAction::Remove && action.is_array_action() {
+        journal_column.action = action.clone();
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn start_indexing_contract_events(
     manifest: &Manifest,
@@ -671,7 +830,14 @@ async fn start_indexing_contract_events(
                     event.indexer_name.clone(),
                     event.contract.name.clone(),
                     event.event_name.clone(),
-                )?,
+                )?
+                .with_csv(event_csv_info(
+                    manifest,
+                    project_path,
+                    manifest.contract_csv_enabled(&event.contract.name),
+                    &event.contract.name,
+                    &event.event_name,
+                )),
             );
 
             build_derived_tables_for_event(
@@ -705,7 +871,15 @@ async fn start_indexing_contract_events(
                         manifest.name.clone(),
                         NATIVE_TRANSFER_CONTRACT_NAME.to_string(),
                         "NativeTransfer".to_string(),
-                    )?,
+                    )?
+                    .with_csv(event_csv_info(
+                        manifest,
+                        project_path,
+                        manifest.storage.csv_enabled()
+                            && manifest.native_transfers.generate_csv.unwrap_or(true),
+                        NATIVE_TRANSFER_CONTRACT_NAME,
+                        "NativeTransfer",
+                    )),
                 );
             }
         }
@@ -793,7 +967,14 @@ async fn start_indexing_contract_events(
                 event_tables,
                 derived_tables,
                 streams_clients,
-            )?;
+            )?
+            .with_finality_tag(crate::indexer::reorg::finality_tag_for_network(
+                manifest,
+                network_name,
+            ))
+            .with_reorg_alerts(
+                crate::indexer::reorg::reorg_alert_chat_clients(manifest, network_name).await,
+            );
 
             // Run startup validation
             match coordinator.validate_on_startup().await {
@@ -837,6 +1018,8 @@ async fn start_indexing_contract_events(
         }
     }
 
+    let mut network_live_fetchers: HashMap<String, Arc<NetworkLiveFetcher>> = HashMap::new();
+
     while let Some(res) = block_tasks.next().await {
         let (
             event,
@@ -881,6 +1064,8 @@ async fn start_indexing_contract_events(
         //     None => {}
         // };
 
+        let finality_tag = event.contract.reorg_safe_distance.and_then(|rsd| rsd.block_tag());
+
         let event_processing_config: EventProcessingConfig = match event.is_factory_filter_event() {
             true => {
                 let factory_details = network_contract
@@ -918,6 +1103,7 @@ async fn start_indexing_contract_events(
                     },
                     index_event_in_order: event.index_event_in_order,
                     indexing_distance_from_head,
+                    finality_tag,
                     cancel_token: cancel_token.clone(),
                     tables: event.tables.clone(),
                     reorg_sender: event.reorg_sender.clone(),
@@ -958,6 +1144,7 @@ async fn start_indexing_contract_events(
                 },
                 index_event_in_order: event.index_event_in_order,
                 indexing_distance_from_head,
+                finality_tag,
                 cancel_token: cancel_token.clone(),
                 tables: event.tables.clone(),
                 reorg_sender: event.reorg_sender.clone(),
@@ -1010,10 +1197,37 @@ async fn start_indexing_contract_events(
                     None
                 };
 
+            // Networks with `shared_live_fetcher: true` hand every event over to one
+            // `NetworkLiveFetcher` once its historic sync is done.
+            let live_fetcher = if event_processing_config.live_indexing()
+                && !no_live_indexing_forced
+                && manifest.networks.iter().any(|network| {
+                    network.name == network_contract.network
+                        && network.shared_live_fetcher.unwrap_or(false)
+                }) {
+                Some(Arc::clone(
+                    network_live_fetchers.entry(network_contract.network.clone()).or_insert_with(
+                        || {
+                            NetworkLiveFetcher::new(
+                                network_contract.network.clone(),
+                                network_contract.cached_provider.clone(),
+                                network_contract.disable_logs_bloom_checks,
+                                reorg_coordinator.clone(),
+                                Some(trace_registry.clone()),
+                                cancel_token.clone(),
+                            )
+                        },
+                    ),
+                ))
+            } else {
+                None
+            };
+
             let process_event = tokio::spawn(process_non_blocking_event(
                 event_processing_config,
                 reorg_coordinator,
                 Some(trace_registry.clone()),
+                live_fetcher,
             ));
             non_blocking_process_events.push(process_event);
         }
@@ -1102,7 +1316,15 @@ async fn start_indexing_contract_events(
                         event_tables,
                         derived_tables,
                         streams_clients,
-                    )?;
+                    )?
+                    .with_finality_tag(crate::indexer::reorg::finality_tag_for_network(
+                        manifest,
+                        network_name,
+                    ))
+                    .with_reorg_alerts(
+                        crate::indexer::reorg::reorg_alert_chat_clients(manifest, network_name)
+                            .await,
+                    );
 
                     match coordinator.validate_on_startup().await {
                         Ok(Some(startup_task)) => {
@@ -1240,6 +1462,10 @@ async fn start_indexing(
     let database = initialize_database(manifest).await?;
     let clickhouse = initialize_clickhouse(manifest).await?;
 
+    register_log_archives(manifest, project_path)?;
+    configure_rpc_cache(manifest, project_path);
+    configure_blockclock_catch_up(manifest);
+
     // any events which are non-blocking and can be fired in parallel
     let mut non_blocking_process_events = Vec::new();
 
@@ -1380,15 +1606,24 @@ pub async fn initialize_clickhouse(
     }
 }
 
+/// `tagged_block` is the block reported for a `finalized` / `safe` reorg_safe_distance, when
+/// missing those modes fall back to the chain default distance.
 pub fn calculate_safe_block_number(
     reorg_safe_distance: Option<ReorgSafeDistance>,
     chain_id: u64,
     latest_block: U64,
+    tagged_block: Option<U64>,
     mut end_block: U64,
 ) -> (U64, U64) {
     let mut indexing_distance_from_head = U64::ZERO;
     if let Some(ref config) = reorg_safe_distance {
-        if let Some(distance) = config.resolve(chain_id) {
+        let distance = match (config.block_tag(), tagged_block) {
+            (Some(_), Some(tagged_block)) => {
+                Some(latest_block.saturating_sub(tagged_block).to::<u64>())
+            }
+            _ => config.resolve(chain_id),
+        };
+        if let Some(distance) = distance {
             let safe_distance = U64::from(distance);
             let safe_block_number = latest_block.saturating_sub(safe_distance);
             if end_block > safe_block_number {
@@ -1404,7 +1639,7 @@ pub fn calculate_safe_block_number(
 mod tests {
     use super::*;
     use crate::indexer::last_synced::SyncConfig;
-    use crate::manifest::contract::ReorgSafeDistance;
+    use crate::manifest::contract::{BlockTag, ReorgSafeDistance};
     use crate::provider::mock::MockChainProvider;
     use std::path::Path;
 
@@ -1426,7 +1661,7 @@ mod tests {
     #[test]
     fn safe_block_no_reorg_distance() {
         let (end, distance) =
-            calculate_safe_block_number(None, 1, U64::from(1000), U64::from(1000));
+            calculate_safe_block_number(None, 1, U64::from(1000), None, U64::from(1000));
         assert_eq!(end, U64::from(1000));
         assert_eq!(distance, U64::ZERO);
     }
@@ -1437,6 +1672,7 @@ mod tests {
             Some(ReorgSafeDistance::Enabled(false)),
             1,
             U64::from(1000),
+            None,
             U64::from(1000),
         );
         assert_eq!(end, U64::from(1000));
@@ -1450,6 +1686,7 @@ mod tests {
             Some(ReorgSafeDistance::Custom(20)),
             1,
             U64::from(1000),
+            None,
             U64::from(1000),
         );
         assert_eq!(end, U64::from(980));
@@ -1463,6 +1700,7 @@ mod tests {
             Some(ReorgSafeDistance::Custom(20)),
             1,
             U64::from(1000),
+            None,
             U64::from(500),
         );
         assert_eq!(end, U64::from(500));
@@ -1476,12 +1714,60 @@ mod tests {
             Some(ReorgSafeDistance::Enabled(true)),
             1, // ethereum mainnet
             U64::from(10000),
+            None,
             U64::from(10000),
         );
         assert!(distance > U64::ZERO);
         assert!(end < U64::from(10000));
     }
 
+    #[test]
+    fn safe_block_finalized_tag_uses_tagged_block() {
+        let (end, distance) = calculate_safe_block_number(
+            Some(ReorgSafeDistance::Tag(BlockTag::Finalized)),
+            1,
+            U64::from(1000),
+            Some(U64::from(936)),
+            U64::from(1000),
+        );
+        assert_eq!(end, U64::from(936));
+        assert_eq!(distance, U64::from(64));
+    }
+
+    #[test]
+    fn safe_block_tag_without_tagged_block_uses_chain_default() {
+        let (end, distance) = calculate_safe_block_number(
+            Some(ReorgSafeDistance::Tag(BlockTag::Safe)),
+            1,
+            U64::from(1000),
+            None,
+            U64::from(1000),
+        );
+        assert_eq!(distance, U64::from(reorg_safe_distance_for_chain(1)));
+        assert_eq!(end, U64::from(1000) - distance);
+    }
+
+    #[tokio::test]
+    async fn safe_tag_clamps_end_to_provider_safe_block() {
+        let mock = MockChainProvider::new(1)
+            .with_block_number(1000)
+            .with_tagged_block(BlockTag::Safe, 990);
+        let (_, end, distance) = get_start_end_block(
+            &mock,
+            Some(U64::from(100)),
+            None,
+            empty_sync_config(),
+            "Test",
+            "ethereum",
+            Some(ReorgSafeDistance::Tag(BlockTag::Safe)),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(end, U64::from(990));
+        assert_eq!(distance, U64::from(10));
+    }
+
     #[tokio::test]
     async fn start_block_higher_than_latest_errors() {
         let mock = MockChainProvider::new(1).with_block_number(100);
@@ -1688,6 +1974,7 @@ mod tests {
             Some(ReorgSafeDistance::Custom(100)),
             1,
             U64::from(50),
+            None,
             U64::from(50),
         );
         assert_eq!(end, U64::ZERO);
@@ -1701,6 +1988,7 @@ mod tests {
             Some(ReorgSafeDistance::Custom(20)),
             1,
             U64::from(1000),
+            None,
             U64::from(980),
         );
         assert_eq!(end, U64::from(980));
@@ -1714,6 +2002,7 @@ mod tests {
             Some(ReorgSafeDistance::Custom(0)),
             1,
             U64::from(1000),
+            None,
             U64::from(1000),
         );
         assert_eq!(end, U64::from(1000));
@@ -1907,7 +2196,13 @@ mod tests {
                 }],
             }],
             cron: None,
+            on_change: vec![],
             timestamp: false,
+            bucket: None,
+            history: false,
+            indexes: vec![],
+            partition_by: None,
+            retention: None,
             database: None,
         };
 
@@ -0,0 +1,523 @@
+//! Resolves `start_block: auto` to the block a contract was deployed at.
+//!
+//! Deployment blocks are found with a binary search over `eth_getCode` at historical blocks, or
+//! for factory children from the birth blocks of the addresses already indexed. Every lookup is
+//! stored in `rindexer_internal.contract_deployment_blocks` so it only ever runs once.
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use alloy::primitives::{Address, U64};
+use alloy::rpc::types::ValueOrArray;
+use anyhow::Context;
+use clickhouse::Row;
+use serde::Deserialize;
+use thiserror::Error;
+use tracing::{debug, info};
+
+use crate::database::clickhouse::client::ClickhouseClient;
+use crate::database::postgres::client::PostgresClient;
+use crate::event::callback_registry::EventCallbackRegistry;
+use crate::event::{
+    get_factory_addresses_with_birth_blocks, GetFactoryAddressesWithBirthBlocksParams,
+};
+use crate::helpers::to_pascal_case;
+use crate::indexer::reorg::validate_sql_value;
+use crate::manifest::contract::{ContractDetails, FactoryDetailsYaml};
+use crate::manifest::core::Manifest;
+use crate::provider::{ChainProvider, ProviderError};
+
+#[derive(Error, Debug)]
+pub enum ResolveStartBlockError {
+    #[error("Could not look up contract code: {0}")]
+    Provider(#[from] ProviderError),
+
+    #[error("{0} on {1} uses `start_block: auto` but has no address or factory to look up")]
+    NoAddressToResolve(String, String),
+
+    #[error("{0} on {1} uses `start_block: auto` but no code exists at {2}")]
+    NotDeployed(String, String, Address),
+
+    #[error("{0} on {1} uses `start_block: auto` but the RPC has no historical state to find the deployment block of {2}, `auto` needs an archive node so set `start_block` explicitly instead: {3}")]
+    ArchiveStateUnavailable(String, String, Address, ProviderError),
+
+    #[error("{0} uses `start_block: auto` on {1} but there is no provider for {1}")]
+    NoProvider(String, String),
+
+    #[error("Could not access the contract deployment block cache: {0}")]
+    Cache(#[from] anyhow::Error),
+}
+
+/// Binary searches for the first block where `address` has code, `None` if it has no code at
+/// `latest_block`.
+pub async fn find_deployment_block(
+    provider: &dyn ChainProvider,
+    address: Address,
+    latest_block: u64,
+) -> Result<Option<u64>, ProviderError> {
+    if provider.get_code_at(address, latest_block).await?.is_empty() {
+        return Ok(None);
+    }
+
+    let (mut low, mut high) = (0, latest_block);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if provider.get_code_at(address, mid).await?.is_empty() {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+
+    Ok(Some(low))
+}
+
+/// Errors nodes return for `eth_getCode` at a block whose state has been pruned.
+const MISSING_STATE_ERRORS: &[&str] = &[
+    "missing trie node",
+    "historical state",
+    "state is not available",
+    "state not available",
+    "world state unavailable",
+    "pruned",
+    "archive",
+];
+
+/// Whether `error` is a node refusing a historical state read, i.e. it is not an archive node.
+fn is_missing_state_error(error: &ProviderError) -> bool {
+    let message = error.to_string().to_lowercase();
+    MISSING_STATE_ERRORS.iter().any(|pattern| message.contains(pattern))
+}
+
+pub struct DeploymentBlockCache {
+    postgres: Option<Arc<PostgresClient>>,
+    clickhouse: Option<Arc<ClickhouseClient>>,
+}
+
+impl DeploymentBlockCache {
+    pub fn new(
+        postgres: Option<Arc<PostgresClient>>,
+        clickhouse: Option<Arc<ClickhouseClient>>,
+    ) -> Self {
+        Self { postgres, clickhouse }
+    }
+
+    /// Load the cached deployment block for an address. Priority: postgres > clickhouse.
+    pub async fn load(&self, network: &str, address: Address) -> anyhow::Result<Option<u64>> {
+        validate_sql_value(network, "network")?;
+        let address = format!("{:?}", address);
+
+        if let Some(postgres) = &self.postgres {
+            let query = r#"
+                SELECT block_number
+                FROM rindexer_internal.contract_deployment_blocks
+                WHERE network = $1 AND address = $2"#;
+
+            let rows = postgres
+                .query(query, &[&network, &address])
+                .await
+                .context("Failed to load contract_deployment_blocks from postgres")?;
+
+            return match rows.first() {
+                Some(row) => {
+                    let block_number: i64 = row.get("block_number");
+                    let block_number = u64::try_from(block_number).with_context(|| {
+                        format!(
+                            "Negative block_number {} in contract_deployment_blocks",
+                            block_number
+                        )
+                    })?;
+                    Ok(Some(block_number))
+                }
+                None => Ok(None),
+            };
+        }
+
+        if let Some(clickhouse) = &self.clickhouse {
+            #[derive(Row, Deserialize)]
+            struct DeploymentBlockRow {
+                block_number: u64,
+            }
+
+            let query = format!(
+                r#"SELECT block_number
+                 FROM rindexer_internal.contract_deployment_blocks FINAL
+                 WHERE network = '{}' AND address = '{}'"#,
+                network, address
+            );
+
+            let rows = clickhouse
+                .query_all::<DeploymentBlockRow>(&query)
+                .await
+                .context("Failed to load contract_deployment_blocks from clickhouse")?;
+
+            return Ok(rows.first().map(|row| row.block_number));
+        }
+
+        Ok(None)
+    }
+
+    pub async fn store(
+        &self,
+        network: &str,
+        address: Address,
+        block_number: u64,
+    ) -> anyhow::Result<()> {
+        validate_sql_value(network, "network")?;
+        let address = format!("{:?}", address);
+
+        if let Some(postgres) = &self.postgres {
+            let query = r#"INSERT INTO rindexer_internal.contract_deployment_blocks
+                         (network, address, block_number)
+                         VALUES ($1, $2, $3)
+                         ON CONFLICT (network, address)
+                         DO UPDATE SET block_number = EXCLUDED.block_number"#;
+
+            let block_number_i64 = i64::try_from(block_number)
+                .with_context(|| format!("block_number {} exceeds i64 range", block_number))?;
+            postgres
+                .execute(query, &[&network, &address, &block_number_i64])
+                .await
+                .with_context(|| format!("Failed to store deployment block of {}", address))?;
+        }
+
+        if let Some(clickhouse) = &self.clickhouse {
+            let query = format!(
+                "INSERT INTO rindexer_internal.contract_deployment_blocks \
+                 (network, address, block_number) \
+                 VALUES ('{}', '{}', {})",
+                network, address, block_number
+            );
+
+            clickhouse
+                .execute(&query)
+                .await
+                .with_context(|| format!("Failed to store deployment block of {}", address))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolves the earliest deployment block across `addresses`, reading and filling the cache.
+pub async fn resolve_deployment_block(
+    provider: &dyn ChainProvider,
+    cache: &DeploymentBlockCache,
+    contract_name: &str,
+    network: &str,
+    addresses: &[Address],
+) -> Result<u64, ResolveStartBlockError> {
+    let mut latest_block = None;
+    let mut earliest: Option<u64> = None;
+
+    for address in addresses {
+        let block = match cache.load(network, *address).await? {
+            Some(block) => block,
+            None => {
+                let latest = match latest_block {
+                    Some(latest) => latest,
+                    None => *latest_block.insert(provider.get_block_number().await?.to::<u64>()),
+                };
+                let block = find_deployment_block(provider, *address, latest)
+                    .await
+                    .map_err(|e| {
+                        if is_missing_state_error(&e) {
+                            ResolveStartBlockError::ArchiveStateUnavailable(
+                                contract_name.to_string(),
+                                network.to_string(),
+                                *address,
+                                e,
+                            )
+                        } else {
+                            ResolveStartBlockError::Provider(e)
+                        }
+                    })?
+                    .ok_or_else(|| {
+                        ResolveStartBlockError::NotDeployed(
+                            contract_name.to_string(),
+                            network.to_string(),
+                            *address,
+                        )
+                    })?;
+                cache.store(network, *address, block).await?;
+                block
+            }
+        };
+
+        debug!("{} - {} deployed at block {} on {}", contract_name, address, block, network);
+        earliest = Some(earliest.map_or(block, |earliest| earliest.min(block)));
+    }
+
+    earliest.ok_or_else(|| {
+        ResolveStartBlockError::NoAddressToResolve(contract_name.to_string(), network.to_string())
+    })
+}
+
+fn addresses(address: &ValueOrArray<Address>) -> Vec<Address> {
+    match address {
+        ValueOrArray::Value(address) => vec![*address],
+        ValueOrArray::Array(addresses) => addresses.clone(),
+    }
+}
+
+/// The contract name the factory indexing registers its factory event under.
+fn factory_contract_name(factory: &FactoryDetailsYaml) -> String {
+    format!(
+        "{}{}{}",
+        factory.name,
+        to_pascal_case(&factory.event_name),
+        factory.input_names().iter().map(|v| to_pascal_case(v)).collect::<Vec<_>>().join("")
+    )
+}
+
+/// The provider of `network` for the events of `contract_names`, `None` when none of their
+/// events are registered so there is nothing to index.
+fn provider_for(
+    registry: &EventCallbackRegistry,
+    contract_names: &[&str],
+    network: &str,
+) -> Result<Option<Arc<dyn ChainProvider>>, ResolveStartBlockError> {
+    let mut events = registry
+        .events
+        .iter()
+        .filter(|e| contract_names.contains(&e.contract.name.as_str()))
+        .peekable();
+    if events.peek().is_none() {
+        return Ok(None);
+    }
+
+    events
+        .flat_map(|e| e.contract.details.iter())
+        .find(|d| d.network == network)
+        .map(|d| Some(Arc::clone(&d.cached_provider)))
+        .ok_or_else(|| {
+            ResolveStartBlockError::NoProvider(contract_names[0].to_string(), network.to_string())
+        })
+}
+
+fn apply_start_block(
+    registry: &mut EventCallbackRegistry,
+    contract_name: &str,
+    network: &str,
+    start_block: u64,
+) {
+    registry
+        .events
+        .iter_mut()
+        .filter(|e| e.contract.name == contract_name)
+        .flat_map(|e| e.contract.details.iter_mut())
+        .filter(|d| d.network == network && d.start_block.is_none())
+        .for_each(|d| d.start_block = Some(U64::from(start_block)));
+}
+
+/// Resolves every `start_block: auto` in the manifest and writes the result onto the matching
+/// registry entries, so the rest of indexing only ever sees a fixed start block.
+pub async fn resolve_auto_start_blocks(
+    manifest: &Manifest,
+    registry: &mut EventCallbackRegistry,
+    postgres: Option<Arc<PostgresClient>>,
+    clickhouse: Option<Arc<ClickhouseClient>>,
+) -> Result<(), ResolveStartBlockError> {
+    let cache = DeploymentBlockCache::new(postgres.clone(), clickhouse.clone());
+
+    for contract in &manifest.contracts {
+        let contract_name = contract.before_modify_name_if_filter_readonly().into_owned();
+
+        for detail in contract.details.iter().filter(|d| d.start_block.is_some_and(|s| s.is_auto()))
+        {
+            resolve_contract_details(
+                manifest,
+                registry,
+                &cache,
+                &contract_name,
+                detail,
+                &postgres,
+                &clickhouse,
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn resolve_contract_details(
+    manifest: &Manifest,
+    registry: &mut EventCallbackRegistry,
+    cache: &DeploymentBlockCache,
+    contract_name: &str,
+    detail: &ContractDetails,
+    postgres: &Option<Arc<PostgresClient>>,
+    clickhouse: &Option<Arc<ClickhouseClient>>,
+) -> Result<(), ResolveStartBlockError> {
+    let network = detail.network.as_str();
+
+    if let Some(address) = &detail.address {
+        let Some(provider) = provider_for(registry, &[contract_name], network)? else {
+            return Ok(());
+        };
+
+        let block = resolve_deployment_block(
+            &*provider,
+            cache,
+            contract_name,
+            network,
+            &addresses(address),
+        )
+        .await?;
+        info!("{} - start_block: auto resolved to {} on {}", contract_name, block, network);
+        apply_start_block(registry, contract_name, network, block);
+        return Ok(());
+    }
+
+    let Some(factory) = &detail.factory else {
+        return Err(ResolveStartBlockError::NoAddressToResolve(
+            contract_name.to_string(),
+            network.to_string(),
+        ));
+    };
+
+    let factory_name = factory_contract_name(factory);
+    let Some(provider) = provider_for(registry, &[contract_name, &factory_name], network)? else {
+        return Ok(());
+    };
+
+    let factory_block = resolve_deployment_block(
+        &*provider,
+        cache,
+        &factory.name,
+        network,
+        &addresses(&factory.address),
+    )
+    .await?;
+    info!("{} - start_block: auto resolved to {} on {}", factory_name, factory_block, network);
+    apply_start_block(registry, &factory_name, network, factory_block);
+
+    // children can only exist after the factory, but when some are already known the earliest
+    // birth block skips the gap between the factory deployment and its first child
+    let birth_blocks =
+        get_factory_addresses_with_birth_blocks(&GetFactoryAddressesWithBirthBlocksParams {
+            indexer_name: manifest.name.clone(),
+            contract_name: factory_name.clone(),
+            event_name: factory.event_name.clone(),
+            input_names: factory.input_names(),
+            network: network.to_string(),
+            postgres: postgres.clone(),
+            clickhouse: clickhouse.clone(),
+        })
+        .await
+        .unwrap_or_else(|e| {
+            debug!("{} - no factory birth blocks known yet: {}", contract_name, e);
+            HashMap::new()
+        });
+
+    let block = birth_blocks.values().min().copied().unwrap_or(factory_block);
+    info!("{} - start_block: auto resolved to {} on {}", contract_name, block, network);
+    apply_start_block(registry, contract_name, network, block);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::mock::MockChainProvider;
+
+    fn address(byte: u8) -> Address {
+        Address::repeat_byte(byte)
+    }
+
+    #[tokio::test]
+    async fn test_find_deployment_block() {
+        let provider = MockChainProvider::new(1).with_deployment(address(1), 12_345);
+
+        for latest in [12_345, 12_346, 20_000_000] {
+            let block = find_deployment_block(&provider, address(1), latest).await.unwrap();
+            assert_eq!(block, Some(12_345));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_find_deployment_block_genesis() {
+        let provider = MockChainProvider::new(1).with_deployment(address(1), 0);
+
+        let block = find_deployment_block(&provider, address(1), 1_000).await.unwrap();
+        assert_eq!(block, Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_find_deployment_block_not_deployed() {
+        let provider = MockChainProvider::new(1).with_deployment(address(1), 500);
+
+        assert_eq!(find_deployment_block(&provider, address(2), 1_000).await.unwrap(), None);
+        assert_eq!(find_deployment_block(&provider, address(1), 499).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_deployment_block_takes_minimum() {
+        let provider = MockChainProvider::new(1)
+            .with_block_number(10_000)
+            .with_deployment(address(1), 9_000)
+            .with_deployment(address(2), 4_200)
+            .with_deployment(address(3), 7_777);
+        let cache = DeploymentBlockCache::new(None, None);
+
+        let block = resolve_deployment_block(
+            &provider,
+            &cache,
+            "Token",
+            "ethereum",
+            &[address(1), address(2), address(3)],
+        )
+        .await
+        .unwrap();
+        assert_eq!(block, 4_200);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_deployment_block_errors_when_not_deployed() {
+        let provider =
+            MockChainProvider::new(1).with_block_number(10_000).with_deployment(address(1), 9_000);
+        let cache = DeploymentBlockCache::new(None, None);
+
+        let err = resolve_deployment_block(
+            &provider,
+            &cache,
+            "Token",
+            "ethereum",
+            &[address(1), address(2)],
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, ResolveStartBlockError::NotDeployed(_, _, a) if a == address(2)));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_deployment_block_reports_missing_archive_state() {
+        let provider = MockChainProvider::new(1)
+            .with_block_number(10_000)
+            .with_deployment(address(1), 9_000)
+            .with_state_pruned_before(9_900);
+        let cache = DeploymentBlockCache::new(None, None);
+
+        let err = resolve_deployment_block(&provider, &cache, "Token", "ethereum", &[address(1)])
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ResolveStartBlockError::ArchiveStateUnavailable(_, _, a, _) if a == address(1)
+        ));
+        assert!(err.to_string().contains("set `start_block` explicitly"));
+    }
+
+    #[test]
+    fn test_is_missing_state_error() {
+        for message in [
+            "missing trie node 0x1234 (path ) <nil>",
+            "historical state 0xabcd is not available",
+            "World state unavailable for block",
+        ] {
+            assert!(is_missing_state_error(&ProviderError::CustomError(message.to_string())));
+        }
+        assert!(!is_missing_state_error(&ProviderError::CustomError("rate limited".to_string())));
+    }
+}
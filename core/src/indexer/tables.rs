@@ -142,11 +142,12 @@
 //!                 value: $value
 //! ```
 
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::sync::Arc;
 
 use alloy::dyn_abi::{DynSolType, DynSolValue};
-use alloy::primitives::{Address, Bytes, B256, U256, U64};
+use alloy::primitives::{Address, Bytes, B256, I256, U256, U64};
 use chrono::{DateTime, Utc};
 use once_cell::sync::Lazy;
 use serde_json::{json, Value};
@@ -154,12 +155,19 @@ use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
 use crate::adaptive_concurrency::ADAPTIVE_CONCURRENCY;
+use crate::blockclock::{blocks_to_extend, record_block_timestamps};
 use crate::database::batch_operations::{
     BatchOperationAction, BatchOperationColumnBehavior, BatchOperationSqlType, BatchOperationType,
     DynamicColumnDefinition,
 };
 use crate::database::clickhouse::batch_operations::execute_dynamic_batch_operation as execute_clickhouse_dynamic_batch_operation;
+use crate::database::clickhouse::batch_operations::{
+    column_aggregate, execute_rollup_batch_operation, RollupAggregate, RollupColumn,
+};
 use crate::database::clickhouse::client::ClickhouseClient;
+use crate::database::generate::generate_block_range_partition_sql;
+use crate::database::generate::generate_distinct_values_table_full_name;
+use crate::database::generate::generate_history_table_full_name;
 use crate::database::generate::generate_indexer_contract_schema_name;
 use crate::database::generate::generate_table_full_name;
 use crate::database::postgres::batch_operations::execute_dynamic_batch_operation;
@@ -167,11 +175,19 @@ use crate::database::postgres::client::PostgresClient;
 use crate::database::postgres::generate::generate_internal_event_table_name;
 use crate::database::sql_type_wrapper::EthereumSqlTypeWrapper;
 use crate::event::{
-    evaluate_arithmetic, filter_by_expression, parse_filter_expression, ComputedValue,
+    evaluate_arithmetic, filter_by_expression, find_lookups, find_tokens, fits_decimal_precision,
+    fixed_to_string, lookup_literal, parse_filter_expression, parse_lookup, parse_token,
+    rescale_fixed, string_to_fixed, substitute_lookups, substitute_tokens, ComputedValue,
+    LookupRef, TokenField, TokenRef, LOOKUP_PREFIX, SCALE_PREFIX, TOKEN_PREFIX,
+};
+use crate::indexer::token_metadata::{
+    load_token_metadata, store_token_metadata, TokenMetadata, TokenMetadataMap,
+    TOKEN_METADATA_CACHE,
 };
 use crate::manifest::contract::{
-    compute_sequence_id, injected_columns, ColumnType, IterateBinding, OperationType, SetAction,
-    SetColumn, Table, TableOperation,
+    compute_sequence_id, find_lookup_table, injected_columns, ColumnType, Contract, IterateBinding,
+    OperationType, SetAction, SetColumn, Table, TableBucket, TableChangeMapping, TableColumn,
+    TableOperation,
 };
 use crate::manifest::core::Constants;
 use crate::provider::ChainProvider;
@@ -249,6 +265,10 @@ const BLOCK_TIMESTAMP_CACHE_MAX_SIZE: usize = 10_000;
 static BLOCK_TIMESTAMP_CACHE: Lazy<RwLock<HashMap<BlockTimestampCacheKey, u64>>> =
     Lazy::new(|| RwLock::new(HashMap::new()));
 
+/// Block range partitions already created by this process, as (table, start block).
+static BLOCK_RANGE_PARTITIONS: Lazy<RwLock<std::collections::HashSet<(String, u64)>>> =
+    Lazy::new(|| RwLock::new(std::collections::HashSet::new()));
+
 /// Evicts oldest entries from VIEW_CALL_CACHE when it exceeds max size.
 /// Removes entries with the lowest block numbers since they won't be needed again.
 async fn evict_old_view_call_cache_entries() {
@@ -1050,6 +1070,32 @@ async fn execute_static_multicall3_batch(
     Ok(())
 }
 
+/// Resolves the contract a call is made to - a constant, an event field reference, or a literal
+/// address.
+fn resolve_call_target(
+    target: &str,
+    log_params: &[LogParam],
+    tx_metadata: &TxMetadata,
+    network: &str,
+    constants: &Constants,
+) -> Option<Address> {
+    if target.starts_with("$constant(") {
+        let resolved = resolve_constants_in_value(target, constants, network)?;
+        resolved.parse().ok()
+    } else if let Some(field_name) = target.strip_prefix('$') {
+        if field_name == "rindexer_contract_address" {
+            Some(tx_metadata.contract_address)
+        } else {
+            match resolve_field_path(field_name, log_params)? {
+                DynSolValue::Address(addr) => Some(addr),
+                _ => None,
+            }
+        }
+    } else {
+        target.parse().ok()
+    }
+}
+
 /// Resolves a ViewCall to a PendingViewCall with concrete address and calldata.
 fn resolve_view_call_to_pending(
     view_call: &ViewCall,
@@ -1061,23 +1107,13 @@ fn resolve_view_call_to_pending(
     use alloy::primitives::keccak256;
 
     // Resolve contract address
-    let contract_address: Address = if view_call.contract_address.starts_with("$constant(") {
-        let resolved = resolve_constants_in_value(&view_call.contract_address, constants, network)?;
-        resolved.parse().ok()?
-    } else if view_call.contract_address.starts_with('$') {
-        let field_name = &view_call.contract_address[1..];
-        if field_name == "rindexer_contract_address" {
-            tx_metadata.contract_address
-        } else {
-            let value = resolve_field_path(field_name, log_params)?;
-            match value {
-                DynSolValue::Address(addr) => addr,
-                _ => return None,
-            }
-        }
-    } else {
-        view_call.contract_address.parse().ok()?
-    };
+    let contract_address = resolve_call_target(
+        &view_call.contract_address,
+        log_params,
+        tx_metadata,
+        network,
+        constants,
+    )?;
 
     // Parse function signature
     let (_, param_types) = parse_function_signature(&view_call.function_sig)?;
@@ -1290,7 +1326,7 @@ async fn prefetch_block_timestamps(
     // Create adaptive semaphore based on current concurrency level
     let adaptive_semaphore = Arc::new(tokio::sync::Semaphore::new(ADAPTIVE_CONCURRENCY.current()));
 
-    for (network, block_numbers) in blocks_to_fetch {
+    for (network, mut block_numbers) in blocks_to_fetch {
         if block_numbers.is_empty() {
             continue;
         }
@@ -1300,6 +1336,10 @@ async fn prefetch_block_timestamps(
             continue;
         };
 
+        // With `blockclock_catch_up`, also fetch the blocks right after the network's `.blockclock`
+        // encoding so it keeps up
+        block_numbers.extend(blocks_to_extend(provider.chain().id(), &block_numbers));
+
         // Split into batches using adaptive batch size
         let batch_size = ADAPTIVE_CONCURRENCY.current_batch_size();
         for chunk in block_numbers.chunks(batch_size) {
@@ -1378,14 +1418,17 @@ async fn prefetch_block_timestamps(
             if !is_running() {
                 continue;
             }
+            let timestamps: Vec<(u64, u64)> =
+                blocks.iter().map(|block| (block.header.number, block.header.timestamp)).collect();
             {
                 let mut cache = BLOCK_TIMESTAMP_CACHE.write().await;
-                for block in blocks {
-                    let block_num = block.header.number;
-                    let timestamp = block.header.timestamp;
-                    cache.insert((network.clone(), block_num), timestamp);
+                for (block_num, timestamp) in &timestamps {
+                    cache.insert((network.clone(), *block_num), *timestamp);
                 }
             }
+            if let Some(provider) = providers.get(&network) {
+                record_block_timestamps(provider.chain().id(), timestamps).await;
+            }
             // Evict old entries if cache is getting large (skip during shutdown)
             if is_running() {
                 evict_old_block_timestamp_cache_entries().await;
@@ -1401,183 +1444,1289 @@ async fn get_cached_block_timestamp(network: &str, block_number: u64) -> Option<
     cache.get(&(network.to_string(), block_number)).copied()
 }
 
-/// Transaction metadata available for table value references.
-#[derive(Clone, Debug)]
-pub struct TxMetadata {
-    pub block_number: u64,
-    pub block_timestamp: Option<U256>,
-    pub tx_hash: B256,
-    pub block_hash: B256,
-    pub contract_address: Address,
-    pub log_index: U256,
-    pub tx_index: u64,
-}
+/// Key of a row read by `$lookup`: (full_table_name, network, key). The network is empty for
+/// cross-chain tables.
+type LookupRowKey = (String, String, String);
 
-/// Runtime representation of a table with resolved table name.
-#[derive(Clone, Debug)]
-pub struct TableRuntime {
-    pub table: Table,
-    pub full_table_name: String,
-    pub indexer_name: String,
-    pub contract_name: String,
-}
+/// Rows read by `$lookup` for a batch of events, with every requested column as text.
+/// Looked up tables keep changing, so unlike view calls rows are read again for every batch.
+type LookupRows = HashMap<LookupRowKey, HashMap<String, Option<String>>>;
 
-impl TableRuntime {
-    pub fn new(table: Table, indexer_name: &str, contract_name: &str) -> Self {
-        let full_table_name = generate_table_full_name(
-            indexer_name,
-            contract_name,
-            &table.name,
-            table.database.as_deref(),
-        );
-        Self {
-            table,
-            full_table_name,
-            indexer_name: indexer_name.to_string(),
-            contract_name: contract_name.to_string(),
-        }
-    }
-}
+/// Maximum keys per `$lookup` query.
+const LOOKUP_QUERY_BATCH_SIZE: usize = 1_000;
 
-/// Data for a single table row to be processed.
+/// Writes of a batch to rows read by `$lookup`, in the order the batch made them. Tables
+/// run after the tables they look up, so they read these before the rows prefetched for
+/// the batch.
+type LookupWrites = HashMap<LookupRowKey, Vec<LookupWrite>>;
+
+/// A write of the current batch to a row read by `$lookup`.
 #[derive(Debug)]
-pub struct TableRowData {
-    /// Column values keyed by column name
-    pub columns: HashMap<String, EthereumSqlTypeWrapper>,
-    /// Network for this row
-    pub network: String,
+struct LookupWrite {
+    /// (block_number, tx_index, log_index) of the event writing the row
+    position: (u64, u64, u64),
+    /// Values of the columns the write replaced, as text. None if the row was deleted.
+    values: Option<HashMap<String, Option<String>>>,
 }
 
-/// Checks if a value string contains arithmetic operators indicating it's a computed expression.
-/// Computed expressions like "$value * 2", "$amount + $fee", "$ratio / 100", "10 ^ $decimals" will return true.
-/// Also supports $call() in arithmetic: "$amount / (10 ^ $call($asset, \"decimals()\"))"
-fn is_arithmetic_expression(value: &str) -> bool {
-    // Must contain at least one arithmetic operator
-    // Check for operators that are not part of comparison (==, !=, >=, <=)
-    let has_operator = value.chars().enumerate().any(|(i, c)| {
-        if c == '*' || c == '/' || c == '^' {
-            true
-        } else if c == '+' || c == '-' {
-            // Check it's not a unary operator at the start
-            i > 0
-        } else {
-            false
-        }
-    });
+/// The `$lookup` targets of a table together with the rows and the `$token` metadata
+/// prefetched for the current batch, and the writes earlier tables of the batch made to
+/// the looked up rows.
+#[derive(Clone, Copy)]
+struct Lookups<'a> {
+    tables: &'a HashMap<String, LookupTable>,
+    rows: &'a LookupRows,
+    writes: &'a LookupWrites,
+    tokens: &'a TokenMetadataMap,
+}
 
-    // Must have operators AND contain either $field or $call references
-    has_operator && (value.contains('$'))
+/// Keys and columns of a looked up table needed by a batch of events.
+struct PendingLookups {
+    table: LookupTable,
+    network: String,
+    columns: Vec<String>,
+    /// Row key -> SQL literal of the key
+    keys: HashMap<String, String>,
 }
 
-/// Checks if an arithmetic expression contains $call() or $call_static() patterns that need async resolution.
-fn arithmetic_has_calls(value: &str) -> bool {
-    value.contains("$call(") || value.contains("$call_static(")
+/// Resolves the key of a lookup for one event to the row key and the SQL literal of the key.
+fn resolve_lookup_key(
+    lookup: &LookupRef,
+    lookup_table: &LookupTable,
+    log_params: &[LogParam],
+    tx_metadata: &TxMetadata,
+    network: &str,
+    constants: &Constants,
+) -> Option<(LookupRowKey, String)> {
+    let key_expr = if lookup.key.contains("$constant(") {
+        resolve_all_constants_in_value(&lookup.key, constants, network)?
+    } else {
+        lookup.key.clone()
+    };
+    let key = extract_value_from_event(&key_expr, log_params, tx_metadata, &lookup_table.key_type)?;
+    if matches!(key, EthereumSqlTypeWrapper::Null) {
+        return None;
+    }
+
+    let scope = if lookup_table.cross_chain { String::new() } else { network.to_string() };
+    let row_key = (lookup_table.full_table_name.clone(), scope, format_wrapper_for_where_key(&key));
+    Some((row_key, format_wrapper_for_sql(&key)))
 }
 
-/// Checks if a value string is a conditional expression like `$if(condition, trueValue, falseValue)`.
-fn is_conditional_value(value: &str) -> bool {
-    value.starts_with("$if(") && value.ends_with(')')
+/// Resolves a lookup for one event from the last write the batch made to the row up to the
+/// event, falling back to the prefetched rows.
+/// Returns None if the row doesn't exist and Some(None) if the column is NULL.
+fn resolve_lookup(
+    lookup: &LookupRef,
+    lookups: Lookups<'_>,
+    log_params: &[LogParam],
+    tx_metadata: &TxMetadata,
+    network: &str,
+    constants: &Constants,
+) -> Option<Option<String>> {
+    let lookup_table = lookups.tables.get(&lookup.table)?;
+    let (row_key, _) =
+        resolve_lookup_key(lookup, lookup_table, log_params, tx_metadata, network, constants)?;
+
+    let position = event_position(tx_metadata);
+    let last_write = lookups.writes.get(&row_key).and_then(|writes| {
+        writes
+            .iter()
+            .rev()
+            .filter(|write| {
+                write.position <= position
+                    && !matches!(&write.values, Some(values) if !values.contains_key(&lookup.column))
+            })
+            .min_by_key(|write| std::cmp::Reverse(write.position))
+    });
+    if let Some(write) = last_write {
+        return write.values.as_ref()?.get(&lookup.column).cloned();
+    }
+
+    lookups.rows.get(&row_key)?.get(&lookup.column).cloned()
 }
 
-/// Parses a conditional expression `$if(condition, trueValue, falseValue)`.
-/// Returns (condition, true_value, false_value) or an error.
-fn parse_conditional_value(value: &str) -> Result<(String, String, String), String> {
-    // Strip "$if(" prefix and ")" suffix
-    let inner = value
-        .strip_prefix("$if(")
-        .and_then(|s| s.strip_suffix(')'))
-        .ok_or_else(|| "Invalid $if() syntax".to_string())?;
+/// Position of an event in the chain, ordering the writes of a batch.
+fn event_position(tx_metadata: &TxMetadata) -> (u64, u64, u64) {
+    (tx_metadata.block_number, tx_metadata.tx_index, tx_metadata.log_index.to::<u64>())
+}
 
-    // Split by commas, but respect nested parentheses
-    let mut parts = Vec::new();
-    let mut current = String::new();
-    let mut depth = 0;
+/// Records the values `rows` write to a table read by `$lookup`. Only values replacing the
+/// column (`set`, `last`, `close` and inserts) are known before the write, other columns
+/// keep resolving to the row before the batch. Updates are only recorded for rows known to
+/// exist and operations with a table condition aren't recorded, they may not write the row.
+fn collect_lookup_writes(
+    lookup_table: &LookupTable,
+    operation: &TableOperation,
+    sql_condition: Option<&str>,
+    rows: &[TableRowData],
+    rows_metadata: &[&TxMetadata],
+    lookups: Lookups<'_>,
+    writes: &mut LookupWrites,
+) {
+    if sql_condition.is_some() {
+        return;
+    }
 
-    for c in inner.chars() {
-        match c {
-            '(' => {
-                depth += 1;
-                current.push(c);
-            }
-            ')' => {
-                depth -= 1;
-                current.push(c);
-            }
-            ',' if depth == 0 => {
-                parts.push(current.trim().to_string());
-                current = String::new();
+    for (row, tx_metadata) in rows.iter().zip(rows_metadata) {
+        let Some(key) = row.columns.get(&lookup_table.key_column) else {
+            continue;
+        };
+        let scope = if lookup_table.cross_chain { String::new() } else { row.network.clone() };
+        let row_key =
+            (lookup_table.full_table_name.clone(), scope, format_wrapper_for_where_key(key));
+
+        let values = match operation.operation_type {
+            OperationType::Delete => None,
+            OperationType::Update
+                if !lookups.rows.contains_key(&row_key)
+                    && !lookups.writes.contains_key(&row_key)
+                    && !writes.contains_key(&row_key) =>
+            {
+                continue;
             }
-            _ => current.push(c),
-        }
-    }
-    parts.push(current.trim().to_string());
+            _ => Some(
+                operation
+                    .set
+                    .iter()
+                    .filter(|sc| {
+                        sc.action.keeps_last() || operation.operation_type == OperationType::Insert
+                    })
+                    .filter_map(|sc| {
+                        let value = match row.columns.get(&sc.column)? {
+                            EthereumSqlTypeWrapper::Null => None,
+                            value => Some(format_wrapper_for_where_key(value)),
+                        };
+                        Some((sc.column.clone(), value))
+                    })
+                    .collect(),
+            ),
+        };
 
-    if parts.len() != 3 {
-        return Err(format!(
-            "Invalid $if() syntax: expected 3 arguments (condition, trueValue, falseValue), got {}",
-            parts.len()
-        ));
+        writes
+            .entry(row_key)
+            .or_default()
+            .push(LookupWrite { position: event_position(tx_metadata), values });
     }
+}
 
-    Ok((parts[0].clone(), parts[1].clone(), parts[2].clone()))
+/// Replaces the `$lookup` and `$token` references of a filter expression with literals of the
+/// looked up values. Returns None if a looked up row or value doesn't exist.
+fn resolve_lookups_in_condition(
+    condition: &str,
+    lookups: Lookups<'_>,
+    log_params: &[LogParam],
+    tx_metadata: &TxMetadata,
+    network: &str,
+    constants: &Constants,
+) -> Option<String> {
+    let condition = substitute_lookups(condition, |lookup| {
+        resolve_lookup(lookup, lookups, log_params, tx_metadata, network, constants)
+            .flatten()
+            .map(|value| lookup_literal(&value))
+    })?;
+    substitute_tokens(&condition, |token| {
+        resolve_token(token, lookups.tokens, log_params, tx_metadata, network, constants)
+            .flatten()
+            .map(|value| lookup_literal(&value))
+    })
 }
 
-/// Finds all $call(...) and $call_static(...) patterns in a string, handling nested parentheses.
-/// Returns a vector of (start_index, end_index, call_expression) for each match.
-fn find_call_patterns(value: &str) -> Vec<(usize, usize, String)> {
-    let mut results = Vec::new();
-    let mut search_start = 0;
+/// Whether a value references other tables or token metadata resolved per batch.
+fn has_batch_references(value: &str) -> bool {
+    value.contains(LOOKUP_PREFIX) || value.contains(TOKEN_PREFIX)
+}
 
-    while search_start < value.len() {
-        // Find the next $call( or $call_static( pattern
-        let remaining = &value[search_start..];
-        let (absolute_start, prefix_len) =
-            match (remaining.find("$call_static("), remaining.find("$call(")) {
-                (Some(static_pos), Some(regular_pos)) => {
-                    // Check if $call( is actually part of $call_static(
-                    if regular_pos < static_pos {
-                        // Make sure this isn't a prefix of $call_static
-                        if remaining[regular_pos..].starts_with("$call_static(") {
-                            (search_start + static_pos, 13) // "$call_static("
-                        } else {
-                            (search_start + regular_pos, 6) // "$call("
-                        }
-                    } else {
-                        (search_start + static_pos, 13) // "$call_static("
-                    }
-                }
-                (Some(static_pos), None) => (search_start + static_pos, 13),
-                (None, Some(regular_pos)) => (search_start + regular_pos, 6),
-                (None, None) => break,
-            };
+/// Reads the rows referenced by `$lookup` for a batch of events. Keys are collected across the
+/// whole batch so every looked up table is queried once per network.
+async fn prefetch_lookups(
+    tables: &[TableRuntime],
+    event_name: &str,
+    events_data: &[(Vec<LogParam>, String, TxMetadata)],
+    postgres: Option<&PostgresClient>,
+    clickhouse: Option<&ClickhouseClient>,
+    constants: &Constants,
+) -> Result<LookupRows, String> {
+    let mut pending: HashMap<(String, String), PendingLookups> = HashMap::new();
 
-        let call_start = absolute_start + prefix_len;
+    for table_runtime in tables {
+        if table_runtime.lookup_tables.is_empty() {
+            continue;
+        }
+        let event_mapping = match table_runtime.table.events.iter().find(|e| e.event == event_name)
+        {
+            Some(em) => em,
+            None => continue,
+        };
 
-        // Find matching closing paren, handling nested parens
-        let mut depth = 1;
-        let mut end_pos = None;
+        for operation in &event_mapping.operations {
+            let operation_lookups: Vec<LookupRef> = operation
+                .where_clause
+                .values()
+                .map(String::as_str)
+                .chain(operation.set.iter().map(|s| s.effective_value()))
+                .chain(operation.condition())
+                .flat_map(|value| find_lookups(value).into_iter().map(|(_, _, lookup)| lookup))
+                .collect();
+            if operation_lookups.is_empty() {
+                continue;
+            }
 
-        for (i, c) in value[call_start..].char_indices() {
-            match c {
-                '(' => depth += 1,
-                ')' => {
-                    depth -= 1;
-                    if depth == 0 {
-                        end_pos = Some(call_start + i);
-                        break;
+            for (log_params, network, tx_metadata) in events_data {
+                let expanded_params_list =
+                    match expand_iterate_bindings(&event_mapping.iterate, log_params) {
+                        Some(params) => params,
+                        None => continue,
+                    };
+
+                for expanded_log_params in &expanded_params_list {
+                    for lookup in &operation_lookups {
+                        let Some(lookup_table) = table_runtime.lookup_tables.get(&lookup.table)
+                        else {
+                            continue;
+                        };
+                        let Some(((full_table_name, scope, key), sql_literal)) = resolve_lookup_key(
+                            lookup,
+                            lookup_table,
+                            expanded_log_params,
+                            tx_metadata,
+                            network,
+                            constants,
+                        ) else {
+                            continue;
+                        };
+
+                        let entry =
+                            pending.entry((full_table_name, scope.clone())).or_insert_with(|| {
+                                PendingLookups {
+                                    table: lookup_table.clone(),
+                                    network: scope,
+                                    columns: Vec::new(),
+                                    keys: HashMap::new(),
+                                }
+                            });
+                        if !entry.columns.contains(&lookup.column) {
+                            entry.columns.push(lookup.column.clone());
+                        }
+                        entry.keys.insert(key, sql_literal);
                     }
                 }
-                _ => {}
             }
         }
+    }
 
-        if let Some(end) = end_pos {
-            let call_expr = value[absolute_start..=end].to_string();
-            results.push((absolute_start, end + 1, call_expr));
-            search_start = end + 1;
-        } else {
-            // Malformed - no closing paren, skip this match
-            search_start = call_start;
+    let mut rows: LookupRows = HashMap::new();
+    for pending_lookups in pending.into_values() {
+        let keys: Vec<(&String, &String)> = pending_lookups.keys.iter().collect();
+        for chunk in keys.chunks(LOOKUP_QUERY_BATCH_SIZE) {
+            let fetched = if let Some(postgres) = postgres {
+                fetch_lookup_rows_postgres(postgres, &pending_lookups, chunk).await?
+            } else if let Some(clickhouse) = clickhouse {
+                fetch_lookup_rows_clickhouse(clickhouse, &pending_lookups, chunk).await?
+            } else {
+                return Ok(rows);
+            };
+
+            for (key, values) in fetched {
+                let row_key = (
+                    pending_lookups.table.full_table_name.clone(),
+                    pending_lookups.network.clone(),
+                    key,
+                );
+                rows.insert(row_key, pending_lookups.columns.iter().cloned().zip(values).collect());
+            }
+        }
+    }
+
+    Ok(rows)
+}
+
+/// Reads one chunk of looked up rows from Postgres as (key, column values) pairs.
+async fn fetch_lookup_rows_postgres(
+    postgres: &PostgresClient,
+    pending: &PendingLookups,
+    keys: &[(&String, &String)],
+) -> Result<Vec<(String, Vec<Option<String>>)>, String> {
+    let key_column = &pending.table.key_column;
+    let columns =
+        pending.columns.iter().map(|c| format!("\"{}\"::TEXT", c)).collect::<Vec<_>>().join(", ");
+    let literals = keys.iter().map(|(_, literal)| literal.as_str()).collect::<Vec<_>>().join(", ");
+    let network_filter = if pending.table.cross_chain {
+        String::new()
+    } else {
+        format!("network = '{}' AND ", pending.network.replace('\'', "''"))
+    };
+    let query = format!(
+        "SELECT \"{key_column}\"::TEXT, {columns} FROM {} WHERE {network_filter}\"{key_column}\" IN ({literals})",
+        pending.table.full_table_name
+    );
+
+    let rows = postgres
+        .query(&query, &[])
+        .await
+        .map_err(|e| format!("Failed to read $lookup rows: {}", e))?;
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            let key: Option<String> = row.get(0);
+            let values: Vec<Option<String>> =
+                (1..=pending.columns.len()).map(|i| row.get(i)).collect();
+            key.map(|key| (key, values))
+        })
+        .collect())
+}
+
+/// Reads one chunk of looked up rows from ClickHouse as (key, column values) pairs.
+async fn fetch_lookup_rows_clickhouse(
+    clickhouse: &ClickhouseClient,
+    pending: &PendingLookups,
+    keys: &[(&String, &String)],
+) -> Result<Vec<(String, Vec<Option<String>>)>, String> {
+    use clickhouse::Row;
+    use serde::Deserialize;
+
+    #[derive(Row, Deserialize)]
+    struct LookupRow {
+        lookup_key: String,
+        lookup_values: Vec<Option<String>>,
+    }
+
+    // ClickHouse stores addresses checksummed while row keys are lowercase
+    let key_column = if pending.table.key_type == ColumnType::Address {
+        format!("lower(toString({}))", pending.table.key_column)
+    } else {
+        format!("toString({})", pending.table.key_column)
+    };
+    let columns =
+        pending.columns.iter().map(|c| format!("toString({})", c)).collect::<Vec<_>>().join(", ");
+    let keys = keys
+        .iter()
+        .map(|(key, _)| format!("'{}'", key.replace('\'', "''")))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let network_filter = if pending.table.cross_chain {
+        String::new()
+    } else {
+        format!("network = '{}' AND ", pending.network.replace('\'', "''"))
+    };
+    let query = format!(
+        "SELECT {key_column} AS lookup_key, CAST([{columns}] AS Array(Nullable(String))) AS lookup_values \
+         FROM {} FINAL WHERE {network_filter}{key_column} IN ({keys})",
+        pending.table.full_table_name
+    );
+
+    let rows = clickhouse
+        .query_all::<LookupRow>(&query)
+        .await
+        .map_err(|e| format!("Failed to read $lookup rows: {}", e))?;
+
+    Ok(rows.into_iter().map(|row| (row.lookup_key, row.lookup_values)).collect())
+}
+
+/// Resolves a `$token` reference for one event from the metadata prefetched for the batch.
+/// Returns None if the token address or its metadata can't be resolved and Some(None) if the
+/// token doesn't implement the field.
+fn resolve_token(
+    token: &TokenRef,
+    tokens: &TokenMetadataMap,
+    log_params: &[LogParam],
+    tx_metadata: &TxMetadata,
+    network: &str,
+    constants: &Constants,
+) -> Option<Option<String>> {
+    let address = resolve_call_target(&token.address, log_params, tx_metadata, network, constants)?;
+    Some(tokens.get(&(network.to_string(), address))?.get(token.field))
+}
+
+/// Reads the metadata of the tokens referenced by `$token` for a batch of events.
+#[allow(clippy::too_many_arguments)]
+async fn prefetch_token_metadata(
+    tables: &[TableRuntime],
+    event_name: &str,
+    events_data: &[(Vec<LogParam>, String, TxMetadata)],
+    providers: &HashMap<String, Arc<dyn ChainProvider>>,
+    postgres: Option<&PostgresClient>,
+    clickhouse: Option<&ClickhouseClient>,
+    constants: &Constants,
+    multicall_addresses: &HashMap<String, Option<String>>,
+) -> TokenMetadataMap {
+    let mut tokens: std::collections::HashSet<(String, Address)> = std::collections::HashSet::new();
+
+    for table_runtime in tables {
+        let event_mapping = match table_runtime.table.events.iter().find(|e| e.event == event_name)
+        {
+            Some(em) => em,
+            None => continue,
+        };
+
+        for operation in &event_mapping.operations {
+            let operation_tokens: Vec<TokenRef> = operation
+                .where_clause
+                .values()
+                .map(String::as_str)
+                .chain(operation.set.iter().map(|s| s.effective_value()))
+                .chain(operation.condition())
+                .flat_map(|value| find_tokens(value).into_iter().map(|(_, _, token)| token))
+                .collect();
+            if operation_tokens.is_empty() {
+                continue;
+            }
+
+            for (log_params, network, tx_metadata) in events_data {
+                let expanded_params_list =
+                    match expand_iterate_bindings(&event_mapping.iterate, log_params) {
+                        Some(params) => params,
+                        None => continue,
+                    };
+
+                for expanded_log_params in &expanded_params_list {
+                    for token in &operation_tokens {
+                        if let Some(address) = resolve_call_target(
+                            &token.address,
+                            expanded_log_params,
+                            tx_metadata,
+                            network,
+                            constants,
+                        ) {
+                            tokens.insert((network.clone(), address));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    resolve_token_metadata(tokens, providers, multicall_addresses, postgres, clickhouse).await
+}
+
+/// Calldata of the view function returning a token metadata field.
+fn token_field_calldata(field: TokenField) -> Bytes {
+    use alloy::primitives::keccak256;
+
+    Bytes::copy_from_slice(&keccak256(field.signature().as_bytes())[..4])
+}
+
+/// Reads the metadata of `tokens` from memory, then from `rindexer_internal.token_metadata`,
+/// and finally with `decimals()`, `symbol()` and `name()` calls batched through Multicall3.
+/// Complete metadata read from the chain is stored so every token is only called once, tokens
+/// missing a field are used for this batch and called again with the next one.
+/// Tokens whose metadata couldn't be read at all are left out of the result.
+pub async fn resolve_token_metadata(
+    tokens: std::collections::HashSet<(String, Address)>,
+    providers: &HashMap<String, Arc<dyn ChainProvider>>,
+    multicall_addresses: &HashMap<String, Option<String>>,
+    postgres: Option<&PostgresClient>,
+    clickhouse: Option<&ClickhouseClient>,
+) -> TokenMetadataMap {
+    let mut resolved: TokenMetadataMap = HashMap::new();
+    let mut missing: HashMap<String, Vec<Address>> = HashMap::new();
+    {
+        let cache = TOKEN_METADATA_CACHE.read().await;
+        for key in tokens {
+            match cache.get(&key) {
+                Some(metadata) => {
+                    resolved.insert(key, metadata.clone());
+                }
+                None => missing.entry(key.0).or_default().push(key.1),
+            }
+        }
+    }
+    if missing.is_empty() {
+        return resolved;
+    }
+
+    // Metadata stored by earlier runs or other contracts
+    let mut to_call: Vec<(String, Address)> = Vec::new();
+    for (network, addresses) in missing {
+        let stored: HashMap<Address, TokenMetadata> =
+            match load_token_metadata(postgres, clickhouse, &network, &addresses).await {
+                Ok(stored) => stored.into_iter().collect(),
+                Err(e) => {
+                    warn!("Failed to load token metadata on {}: {:#}", network, e);
+                    HashMap::new()
+                }
+            };
+
+        let mut cache = TOKEN_METADATA_CACHE.write().await;
+        for address in addresses {
+            match stored.get(&address) {
+                Some(metadata) if metadata.is_complete() => {
+                    cache.insert((network.clone(), address), metadata.clone());
+                    resolved.insert((network.clone(), address), metadata.clone());
+                }
+                _ => to_call.push((network.clone(), address)),
+            }
+        }
+    }
+    if to_call.is_empty() {
+        return resolved;
+    }
+
+    let pending_calls: Vec<PendingViewCall> = to_call
+        .iter()
+        .flat_map(|(network, address)| {
+            TokenField::ALL.iter().map(|field| PendingViewCall {
+                target: *address,
+                calldata: token_field_calldata(*field),
+                block_number: 0,
+                network: network.clone(),
+            })
+        })
+        .collect();
+    prefetch_static_calls_via_multicall(pending_calls, providers, multicall_addresses).await;
+
+    let mut fetched: HashMap<String, Vec<(Address, TokenMetadata)>> = HashMap::new();
+    for (network, address) in to_call {
+        let Some(provider) = providers.get(&network) else {
+            continue;
+        };
+
+        let mut metadata = TokenMetadata::default();
+        for field in TokenField::ALL {
+            let value =
+                read_static_call(provider.as_ref(), &network, address, token_field_calldata(field))
+                    .await;
+            match (field, value) {
+                (TokenField::Decimals, Some(DynSolValue::Uint(decimals, _)))
+                    if decimals <= U256::from(u8::MAX) =>
+                {
+                    metadata.decimals = Some(decimals.to::<u8>());
+                }
+                (TokenField::Symbol, Some(DynSolValue::String(symbol))) if !symbol.is_empty() => {
+                    metadata.symbol = Some(symbol);
+                }
+                (TokenField::Name, Some(DynSolValue::String(name))) if !name.is_empty() => {
+                    metadata.name = Some(name);
+                }
+                _ => {}
+            }
+        }
+
+        // Nothing could be read, retry with the next batch instead of caching the failure
+        if metadata.is_empty() {
+            warn!("Could not read token metadata of {} on {}", address, network);
+            continue;
+        }
+
+        // A missing field may be a failed call, use what was read but call again next batch
+        if !metadata.is_complete() {
+            debug!("Token metadata of {} on {} is incomplete: {:?}", address, network, metadata);
+            resolved.insert((network, address), metadata);
+            continue;
+        }
+
+        TOKEN_METADATA_CACHE.write().await.insert((network.clone(), address), metadata.clone());
+        resolved.insert((network.clone(), address), metadata.clone());
+        fetched.entry(network).or_default().push((address, metadata));
+    }
+
+    for (network, tokens) in fetched {
+        if let Err(e) = store_token_metadata(postgres, clickhouse, &network, &tokens).await {
+            warn!("Failed to store token metadata on {}: {:#}", network, e);
+        }
+    }
+
+    resolved
+}
+
+/// Reads a static call from STATIC_CALL_CACHE, calling the contract directly at the latest block
+/// if the Multicall3 batch didn't return it.
+async fn read_static_call(
+    provider: &dyn ChainProvider,
+    network: &str,
+    target: Address,
+    calldata: Bytes,
+) -> Option<DynSolValue> {
+    let cache_key = (network.to_string(), target, calldata.clone());
+    if let Some(cached) = STATIC_CALL_CACHE.read().await.get(&cache_key) {
+        return Some(cached.clone());
+    }
+
+    let semaphore = VIEW_CALL_SEMAPHORE.read().await.clone();
+    let _permit = semaphore.acquire().await.ok()?;
+
+    let result_hex = match provider.eth_call_latest(target, calldata).await {
+        Ok(r) => r,
+        Err(e) => {
+            debug!("Static call to {} on {} failed: {}", target, network, e);
+            return None;
+        }
+    };
+    let result_bytes = hex::decode(result_hex.trim_start_matches("0x")).ok()?;
+    let decoded = try_decode_return_value(&result_bytes)?;
+
+    STATIC_CALL_CACHE.write().await.insert(cache_key, decoded.clone());
+    Some(decoded)
+}
+
+/// Events a table's operations run for: (log_params, network, tx_metadata).
+type TriggerEvents = [(Vec<LogParam>, String, TxMetadata)];
+
+/// Maximum keys per query reading the rows of a followed table.
+const CHANGE_QUERY_BATCH_SIZE: usize = 1_000;
+
+/// Key of a row of a followed table: (network, key column values as text). The network is
+/// empty for cross-chain tables.
+type ChangeRowKey = (String, Vec<String>);
+
+/// Column values of a row of a followed table, as text.
+type ChangeRowValues = HashMap<String, Option<String>>;
+
+/// Orders tables so tables following the changes of other tables with `on_change` run after
+/// the tables they follow, and tables reading other tables with `$lookup` after the tables
+/// they read. Manifest validation rejects `on_change` cycles, lookup cycles keep the manifest
+/// order.
+fn order_by_table_dependencies(tables: &[TableRuntime]) -> Vec<&TableRuntime> {
+    let mut ordered: Vec<&TableRuntime> = Vec::with_capacity(tables.len());
+    let mut remaining: Vec<&TableRuntime> = tables.iter().collect();
+
+    while !remaining.is_empty() {
+        let remaining_before = remaining.len();
+        remaining.retain(|table_runtime| {
+            let followed_ready = table_runtime.table.on_change.iter().all(|change_mapping| {
+                ordered.iter().any(|t| t.table.name == change_mapping.table)
+                    || !tables.iter().any(|t| t.table.name == change_mapping.table)
+            });
+            let looked_up_ready = table_runtime.lookup_tables.values().all(|lookup_table| {
+                lookup_table.full_table_name == table_runtime.full_table_name
+                    || ordered.iter().any(|t| t.full_table_name == lookup_table.full_table_name)
+                    || !tables.iter().any(|t| t.full_table_name == lookup_table.full_table_name)
+            });
+            let ready = followed_ready && looked_up_ready;
+            if ready {
+                ordered.push(table_runtime);
+            }
+            !ready
+        });
+        if remaining.len() == remaining_before {
+            ordered.append(&mut remaining);
+        }
+    }
+
+    ordered
+}
+
+/// A row of a followed table written by the current batch.
+struct TrackedRow {
+    /// Network of the last event writing the row
+    network: String,
+    key: ChangeRowKey,
+    /// SQL literals of the key column values
+    key_literals: Vec<String>,
+    /// Metadata of the last event writing the row
+    tx_metadata: TxMetadata,
+    /// The row before the batch wrote it, None if it didn't exist
+    before: Option<ChangeRowValues>,
+}
+
+/// Reads the rows of a table followed with `on_change` before and after a batch writes them.
+/// A row written several times by the batch is reported as a single change, from its values
+/// before the first write to its values after the last one.
+struct ChangeTracker<'a> {
+    table_runtime: &'a TableRuntime,
+    key_columns: Vec<&'a str>,
+    rows: Vec<TrackedRow>,
+    /// Index of every tracked row in `rows`
+    index: HashMap<ChangeRowKey, usize>,
+}
+
+impl<'a> ChangeTracker<'a> {
+    fn new(table_runtime: &'a TableRuntime) -> Self {
+        Self {
+            table_runtime,
+            key_columns: table_runtime.table.primary_key_columns(),
+            rows: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+
+    /// Tracks the rows an operation is about to write, reading the ones the batch didn't
+    /// write yet.
+    async fn track(
+        &mut self,
+        rows: &[TableRowData],
+        rows_metadata: &[&TxMetadata],
+        postgres: Option<&PostgresClient>,
+        clickhouse: Option<&ClickhouseClient>,
+    ) -> Result<(), String> {
+        let first_new = self.rows.len();
+
+        for (row, tx_metadata) in rows.iter().zip(rows_metadata) {
+            let Some(key_values) =
+                self.key_columns.iter().map(|c| row.columns.get(*c)).collect::<Option<Vec<_>>>()
+            else {
+                continue;
+            };
+            let scope = if self.table_runtime.table.cross_chain {
+                String::new()
+            } else {
+                row.network.clone()
+            };
+            let key: ChangeRowKey =
+                (scope, key_values.iter().map(|v| format_wrapper_for_where_key(v)).collect());
+
+            match self.index.get(&key) {
+                Some(&i) => {
+                    let tracked = &mut self.rows[i];
+                    if event_sequence_id(tx_metadata) >= event_sequence_id(&tracked.tx_metadata) {
+                        tracked.network = row.network.clone();
+                        tracked.tx_metadata = (*tx_metadata).clone();
+                    }
+                }
+                None => {
+                    self.index.insert(key.clone(), self.rows.len());
+                    self.rows.push(TrackedRow {
+                        network: row.network.clone(),
+                        key,
+                        key_literals: key_values
+                            .iter()
+                            .map(|v| format_wrapper_for_sql(v))
+                            .collect(),
+                        tx_metadata: (*tx_metadata).clone(),
+                        before: None,
+                    });
+                }
+            }
+        }
+
+        if self.rows.len() > first_new {
+            let mut before = self.read_rows(&self.rows[first_new..], postgres, clickhouse).await?;
+            for tracked in &mut self.rows[first_new..] {
+                tracked.before = before.remove(&tracked.key);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the tracked rows again once the batch is written. Returns one event per changed
+    /// row for the operations of the following tables, in the order the rows were written.
+    async fn changes(
+        self,
+        postgres: Option<&PostgresClient>,
+        clickhouse: Option<&ClickhouseClient>,
+    ) -> Result<Vec<(Vec<LogParam>, String, TxMetadata)>, String> {
+        if self.rows.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut after = self.read_rows(&self.rows, postgres, clickhouse).await?;
+        let mut changes = Vec::new();
+
+        for tracked in self.rows {
+            let after = after.remove(&tracked.key);
+            let change = match (&tracked.before, &after) {
+                (None, None) => continue,
+                (Some(before), Some(after)) if before == after => continue,
+                (None, Some(_)) => "insert",
+                (Some(_), None) => "delete",
+                (Some(_), Some(_)) => "update",
+            };
+            let log_params = change_log_params(
+                &self.table_runtime.table,
+                &self.key_columns,
+                &tracked.key.1,
+                change,
+                tracked.before.as_ref(),
+                after.as_ref(),
+            );
+            changes.push((log_params, tracked.network, tracked.tx_metadata));
+        }
+
+        changes.sort_by_key(|(_, _, tx_metadata)| event_sequence_id(tx_metadata));
+        Ok(changes)
+    }
+
+    /// Reads the current values of `rows`, missing rows are left out.
+    async fn read_rows(
+        &self,
+        rows: &[TrackedRow],
+        postgres: Option<&PostgresClient>,
+        clickhouse: Option<&ClickhouseClient>,
+    ) -> Result<HashMap<ChangeRowKey, ChangeRowValues>, String> {
+        let mut rows_per_network: HashMap<&str, Vec<&TrackedRow>> = HashMap::new();
+        for tracked in rows {
+            rows_per_network.entry(tracked.key.0.as_str()).or_default().push(tracked);
+        }
+
+        let column_names: Vec<&String> =
+            self.table_runtime.table.columns.iter().map(|c| &c.name).collect();
+        let mut fetched = HashMap::new();
+
+        for (network, network_rows) in rows_per_network {
+            for chunk in network_rows.chunks(CHANGE_QUERY_BATCH_SIZE) {
+                let chunk_rows = if let Some(postgres) = postgres {
+                    self.read_rows_postgres(postgres, network, chunk).await?
+                } else if let Some(clickhouse) = clickhouse {
+                    self.read_rows_clickhouse(clickhouse, network, chunk).await?
+                } else {
+                    return Ok(fetched);
+                };
+
+                for (key, values) in chunk_rows {
+                    let row_values: ChangeRowValues =
+                        column_names.iter().map(|name| (*name).clone()).zip(values).collect();
+                    fetched.insert((network.to_string(), key), row_values);
+                }
+            }
+        }
+
+        Ok(fetched)
+    }
+
+    /// Reads one chunk of rows from Postgres as (key, column values) pairs.
+    async fn read_rows_postgres(
+        &self,
+        postgres: &PostgresClient,
+        network: &str,
+        rows: &[&TrackedRow],
+    ) -> Result<Vec<(Vec<String>, Vec<Option<String>>)>, String> {
+        let query = self.rows_query_postgres(network, rows);
+        let fetched = postgres.query(&query, &[]).await.map_err(|e| {
+            format!("Failed to read changed rows of {}: {}", self.table_runtime.full_table_name, e)
+        })?;
+
+        let key_count = self.key_columns.len();
+        let column_count = self.table_runtime.table.columns.len();
+        Ok(fetched
+            .iter()
+            .map(|row| {
+                let key: Vec<String> = (0..key_count)
+                    .map(|i| row.get::<_, Option<String>>(i).unwrap_or_default())
+                    .collect();
+                let values: Vec<Option<String>> =
+                    (key_count..key_count + column_count).map(|i| row.get(i)).collect();
+                (key, values)
+            })
+            .collect())
+    }
+
+    /// Builds the Postgres query reading the key and every column of `rows` as text.
+    fn rows_query_postgres(&self, network: &str, rows: &[&TrackedRow]) -> String {
+        let table = &self.table_runtime.table;
+        let select = self
+            .key_columns
+            .iter()
+            .map(|c| format!("\"{}\"::TEXT", c))
+            .chain(table.columns.iter().map(|c| match c.resolved_type() {
+                ColumnType::Timestamp => {
+                    format!("EXTRACT(EPOCH FROM \"{}\")::BIGINT::TEXT", c.name)
+                }
+                _ => format!("\"{}\"::TEXT", c.name),
+            }))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut filters: Vec<String> = Vec::new();
+        if !table.cross_chain {
+            filters.push(format!("network = '{}'", network.replace('\'', "''")));
+        }
+        if !self.key_columns.is_empty() {
+            let columns = self
+                .key_columns
+                .iter()
+                .map(|c| format!("\"{}\"", c))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let keys = rows
+                .iter()
+                .map(|r| format!("({})", r.key_literals.join(", ")))
+                .collect::<Vec<_>>()
+                .join(", ");
+            filters.push(format!("({}) IN ({})", columns, keys));
+        }
+        let filter = if filters.is_empty() { "TRUE".to_string() } else { filters.join(" AND ") };
+
+        format!("SELECT {} FROM {} WHERE {}", select, self.table_runtime.full_table_name, filter)
+    }
+
+    /// Reads one chunk of rows from ClickHouse as (key, column values) pairs.
+    async fn read_rows_clickhouse(
+        &self,
+        clickhouse: &ClickhouseClient,
+        network: &str,
+        rows: &[&TrackedRow],
+    ) -> Result<Vec<(Vec<String>, Vec<Option<String>>)>, String> {
+        use clickhouse::Row;
+        use serde::Deserialize;
+
+        #[derive(Row, Deserialize)]
+        struct ChangedRow {
+            change_key: Vec<String>,
+            change_values: Vec<Option<String>>,
+        }
+
+        let table = &self.table_runtime.table;
+        // ClickHouse stores addresses checksummed while row keys are lowercase
+        let as_text = |name: &str| match table.columns.iter().find(|c| c.name == name) {
+            Some(c) if *c.resolved_type() == ColumnType::Address => {
+                format!("lower(toString({}))", name)
+            }
+            Some(c) if *c.resolved_type() == ColumnType::Timestamp => {
+                format!("toString(toUnixTimestamp({}))", name)
+            }
+            _ => format!("toString({})", name),
+        };
+        let key_columns: Vec<String> = self.key_columns.iter().map(|c| as_text(c)).collect();
+        let columns: Vec<String> = table.columns.iter().map(|c| as_text(&c.name)).collect();
+
+        let mut filters: Vec<String> = Vec::new();
+        if !table.cross_chain {
+            filters.push(format!("network = '{}'", network.replace('\'', "''")));
+        }
+        if !key_columns.is_empty() {
+            let keys = rows
+                .iter()
+                .map(|r| {
+                    let key = r.key.1.iter().map(|k| format!("'{}'", k.replace('\'', "''")));
+                    format!("({})", key.collect::<Vec<_>>().join(", "))
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            filters.push(format!("({}) IN ({})", key_columns.join(", "), keys));
+        }
+        let filter = if filters.is_empty() { "1 = 1".to_string() } else { filters.join(" AND ") };
+
+        let query = format!(
+            "SELECT CAST([{}] AS Array(String)) AS change_key, \
+             CAST([{}] AS Array(Nullable(String))) AS change_values \
+             FROM {} FINAL WHERE {}",
+            key_columns.join(", "),
+            columns.join(", "),
+            self.table_runtime.full_table_name,
+            filter
+        );
+
+        let fetched = clickhouse.query_all::<ChangedRow>(&query).await.map_err(|e| {
+            format!("Failed to read changed rows of {}: {}", self.table_runtime.full_table_name, e)
+        })?;
+
+        Ok(fetched.into_iter().map(|row| (row.change_key, row.change_values)).collect())
+    }
+}
+
+/// Sequence of the event in the chain, used to order changed rows.
+fn event_sequence_id(tx_metadata: &TxMetadata) -> u128 {
+    compute_sequence_id(
+        tx_metadata.block_number,
+        tx_metadata.tx_index,
+        tx_metadata.log_index.to::<u64>(),
+    )
+}
+
+/// Builds the fields a changed row exposes to the operations of the tables following it:
+/// `$change` and `$before_<column>`/`$after_<column>` for every column. The columns of a
+/// missing row are zero values, except its key columns which never change.
+fn change_log_params(
+    table: &Table,
+    key_columns: &[&str],
+    key: &[String],
+    change: &str,
+    before: Option<&ChangeRowValues>,
+    after: Option<&ChangeRowValues>,
+) -> Vec<LogParam> {
+    let mut log_params = vec![LogParam::new(
+        TableChangeMapping::CHANGE_FIELD.to_string(),
+        DynSolValue::String(change.to_string()),
+    )];
+
+    for column in &table.columns {
+        let key_value = key_columns.iter().position(|c| *c == column.name).map(|i| key[i].clone());
+        let sides = [
+            (TableChangeMapping::before_field(&column.name), before),
+            (TableChangeMapping::after_field(&column.name), after),
+        ];
+        for (field, values) in sides {
+            let value = match values {
+                Some(values) => values.get(&column.name).cloned().flatten(),
+                None => key_value.clone(),
+            };
+            log_params.push(LogParam::new(
+                field,
+                column_text_to_sol_value(value.as_deref(), column.resolved_type()),
+            ));
+        }
+    }
+
+    log_params
+}
+
+/// Converts a column value read as text back to a value table operations can reference.
+/// NULL values become the zero value of the column type.
+fn column_text_to_sol_value(value: Option<&str>, column_type: &ColumnType) -> DynSolValue {
+    match column_type {
+        ColumnType::Uint8
+        | ColumnType::Uint16
+        | ColumnType::Uint32
+        | ColumnType::Uint64
+        | ColumnType::Uint128
+        | ColumnType::Uint256
+        | ColumnType::Timestamp => {
+            DynSolValue::Uint(value.and_then(|v| v.parse().ok()).unwrap_or_default(), 256)
+        }
+        ColumnType::Int8
+        | ColumnType::Int16
+        | ColumnType::Int32
+        | ColumnType::Int64
+        | ColumnType::Int128
+        | ColumnType::Int256 => {
+            DynSolValue::Int(value.and_then(|v| v.parse().ok()).unwrap_or_default(), 256)
+        }
+        ColumnType::Address => {
+            DynSolValue::Address(value.and_then(|v| v.parse().ok()).unwrap_or_default())
+        }
+        ColumnType::Bool => DynSolValue::Bool(matches!(value, Some("true" | "1"))),
+        ColumnType::Bytes | ColumnType::Bytes32 => {
+            let hex_value = value.map(|v| v.trim_start_matches("\\x").trim_start_matches("0x"));
+            let bytes = hex_value.and_then(|v| hex::decode(v).ok()).unwrap_or_default();
+            if *column_type == ColumnType::Bytes32 && bytes.len() == 32 {
+                DynSolValue::FixedBytes(B256::from_slice(&bytes), 32)
+            } else {
+                DynSolValue::Bytes(bytes)
+            }
+        }
+        // Kept as text so arithmetic reads it back as an exact decimal
+        ColumnType::Decimal(_) => DynSolValue::String(value.unwrap_or("0").to_string()),
+        ColumnType::String | ColumnType::Array(_) => {
+            DynSolValue::String(value.unwrap_or_default().to_string())
+        }
+    }
+}
+
+/// Transaction metadata available for table value references.
+#[derive(Clone, Debug)]
+pub struct TxMetadata {
+    pub block_number: u64,
+    pub block_timestamp: Option<U256>,
+    pub tx_hash: B256,
+    pub block_hash: B256,
+    pub contract_address: Address,
+    pub log_index: U256,
+    pub tx_index: u64,
+}
+
+/// Runtime representation of a table with resolved table name.
+#[derive(Clone, Debug)]
+pub struct TableRuntime {
+    pub table: Table,
+    pub full_table_name: String,
+    pub indexer_name: String,
+    pub contract_name: String,
+    /// Tables read by `$lookup`, keyed by the table name used in the references.
+    pub lookup_tables: HashMap<String, LookupTable>,
+}
+
+impl TableRuntime {
+    pub fn new(table: Table, indexer_name: &str, contract_name: &str) -> Self {
+        let full_table_name = generate_table_full_name(
+            indexer_name,
+            contract_name,
+            &table.name,
+            table.database.as_deref(),
+        );
+        Self {
+            table,
+            full_table_name,
+            indexer_name: indexer_name.to_string(),
+            contract_name: contract_name.to_string(),
+            lookup_tables: HashMap::new(),
+        }
+    }
+
+    pub fn with_lookup_tables(mut self, lookup_tables: HashMap<String, LookupTable>) -> Self {
+        self.lookup_tables = lookup_tables;
+        self
+    }
+}
+
+/// A custom table read by `$lookup(table, key).column`.
+#[derive(Clone, Debug)]
+pub struct LookupTable {
+    pub full_table_name: String,
+    /// The single `where` column rows are looked up by.
+    pub key_column: String,
+    pub key_type: ColumnType,
+    /// Cross-chain tables have no `network` column, every network reads the same rows.
+    pub cross_chain: bool,
+}
+
+/// Resolves the tables read by the `$lookup` references of `table`.
+pub fn resolve_lookup_tables(
+    table: &Table,
+    indexer_name: &str,
+    contract_name: &str,
+    contracts: &[Contract],
+) -> Result<HashMap<String, LookupTable>, String> {
+    let mut lookup_tables = HashMap::new();
+    for lookup in table.lookups() {
+        if lookup_tables.contains_key(&lookup.table) {
+            continue;
+        }
+        let (target_contract, target) = find_lookup_table(contracts, contract_name, &lookup.table)?;
+        let key_column = target.lookup_key_column()?;
+        lookup_tables.insert(
+            lookup.table.clone(),
+            LookupTable {
+                full_table_name: generate_table_full_name(
+                    indexer_name,
+                    &target_contract.name,
+                    &target.name,
+                    target.database.as_deref(),
+                ),
+                key_column: key_column.name.clone(),
+                key_type: key_column.resolved_type().clone(),
+                cross_chain: target.cross_chain,
+            },
+        );
+    }
+    Ok(lookup_tables)
+}
+
+/// Data for a single table row to be processed.
+#[derive(Debug, Clone)]
+pub struct TableRowData {
+    /// Column values keyed by column name
+    pub columns: HashMap<String, EthereumSqlTypeWrapper>,
+    /// Network for this row
+    pub network: String,
+}
+
+/// Checks if a value string contains arithmetic operators indicating it's a computed expression.
+/// Computed expressions like "$value * 2", "$amount + $fee", "$ratio / 100", "10 ^ $decimals" will return true.
+/// Also supports $call() in arithmetic: "$amount / (10 ^ $call($asset, \"decimals()\"))"
+/// and `$scale(value, decimals)`, which is evaluated as arithmetic even without an operator.
+fn is_arithmetic_expression(value: &str) -> bool {
+    if value.contains(SCALE_PREFIX) {
+        return true;
+    }
+
+    // Must contain at least one arithmetic operator
+    // Check for operators that are not part of comparison (==, !=, >=, <=)
+    let has_operator = value.chars().enumerate().any(|(i, c)| {
+        if c == '*' || c == '/' || c == '^' {
+            true
+        } else if c == '+' || c == '-' {
+            // Check it's not a unary operator at the start
+            i > 0
+        } else {
+            false
+        }
+    });
+
+    // Must have operators AND contain either $field or $call references
+    has_operator && (value.contains('$'))
+}
+
+/// Checks if an arithmetic expression contains $call() or $call_static() patterns that need async resolution.
+fn arithmetic_has_calls(value: &str) -> bool {
+    value.contains("$call(") || value.contains("$call_static(")
+}
+
+/// Checks if a value string is a conditional expression like `$if(condition, trueValue, falseValue)`.
+fn is_conditional_value(value: &str) -> bool {
+    value.starts_with("$if(") && value.ends_with(')')
+}
+
+/// Parses a conditional expression `$if(condition, trueValue, falseValue)`.
+/// Returns (condition, true_value, false_value) or an error.
+fn parse_conditional_value(value: &str) -> Result<(String, String, String), String> {
+    // Strip "$if(" prefix and ")" suffix
+    let inner = value
+        .strip_prefix("$if(")
+        .and_then(|s| s.strip_suffix(')'))
+        .ok_or_else(|| "Invalid $if() syntax".to_string())?;
+
+    // Split by commas, but respect nested parentheses
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+
+    for c in inner.chars() {
+        match c {
+            '(' => {
+                depth += 1;
+                current.push(c);
+            }
+            ')' => {
+                depth -= 1;
+                current.push(c);
+            }
+            ',' if depth == 0 => {
+                parts.push(current.trim().to_string());
+                current = String::new();
+            }
+            _ => current.push(c),
+        }
+    }
+    parts.push(current.trim().to_string());
+
+    if parts.len() != 3 {
+        return Err(format!(
+            "Invalid $if() syntax: expected 3 arguments (condition, trueValue, falseValue), got {}",
+            parts.len()
+        ));
+    }
+
+    Ok((parts[0].clone(), parts[1].clone(), parts[2].clone()))
+}
+
+/// Finds all $call(...) and $call_static(...) patterns in a string, handling nested parentheses.
+/// Returns a vector of (start_index, end_index, call_expression) for each match.
+fn find_call_patterns(value: &str) -> Vec<(usize, usize, String)> {
+    let mut results = Vec::new();
+    let mut search_start = 0;
+
+    while search_start < value.len() {
+        // Find the next $call( or $call_static( pattern
+        let remaining = &value[search_start..];
+        let (absolute_start, prefix_len) =
+            match (remaining.find("$call_static("), remaining.find("$call(")) {
+                (Some(static_pos), Some(regular_pos)) => {
+                    // Check if $call( is actually part of $call_static(
+                    if regular_pos < static_pos {
+                        // Make sure this isn't a prefix of $call_static
+                        if remaining[regular_pos..].starts_with("$call_static(") {
+                            (search_start + static_pos, 13) // "$call_static("
+                        } else {
+                            (search_start + regular_pos, 6) // "$call("
+                        }
+                    } else {
+                        (search_start + static_pos, 13) // "$call_static("
+                    }
+                }
+                (Some(static_pos), None) => (search_start + static_pos, 13),
+                (None, Some(regular_pos)) => (search_start + regular_pos, 6),
+                (None, None) => break,
+            };
+
+        let call_start = absolute_start + prefix_len;
+
+        // Find matching closing paren, handling nested parens
+        let mut depth = 1;
+        let mut end_pos = None;
+
+        for (i, c) in value[call_start..].char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        end_pos = Some(call_start + i);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(end) = end_pos {
+            let call_expr = value[absolute_start..=end].to_string();
+            results.push((absolute_start, end + 1, call_expr));
+            search_start = end + 1;
+        } else {
+            // Malformed - no closing paren, skip this match
+            search_start = call_start;
         }
     }
 
@@ -2124,24 +3273,13 @@ async fn execute_view_call(
     use alloy::primitives::keccak256;
 
     // Resolve contract address - may be a constant, field reference, or literal
-    let contract_address: Address = if view_call.contract_address.starts_with("$constant(") {
-        // Resolve constant reference
-        let resolved = resolve_constants_in_value(&view_call.contract_address, constants, network)?;
-        resolved.parse().ok()?
-    } else if view_call.contract_address.starts_with('$') {
-        let field_name = &view_call.contract_address[1..];
-        if field_name == "rindexer_contract_address" {
-            tx_metadata.contract_address
-        } else {
-            let value = resolve_field_path(field_name, log_params)?;
-            match value {
-                DynSolValue::Address(addr) => addr,
-                _ => return None,
-            }
-        }
-    } else {
-        view_call.contract_address.parse().ok()?
-    };
+    let contract_address = resolve_call_target(
+        &view_call.contract_address,
+        log_params,
+        tx_metadata,
+        network,
+        constants,
+    )?;
 
     // Parse function signature to get types
     // e.g., "balanceOf(address)" -> selector + encode args
@@ -2530,8 +3668,9 @@ fn try_bytes32_as_string(bytes: &[u8]) -> Option<String> {
     None
 }
 
-/// Async version of extract_value_from_event that supports view calls and constants.
+/// Async version of extract_value_from_event that supports view calls, lookups and constants.
 /// Falls back to sync extraction for non-view-call values.
+#[allow(clippy::too_many_arguments)]
 async fn extract_value_from_event_async(
     value_ref: &str,
     log_params: &[LogParam],
@@ -2540,6 +3679,7 @@ async fn extract_value_from_event_async(
     provider: Option<&dyn ChainProvider>,
     network: &str,
     constants: &Constants,
+    lookups: Lookups<'_>,
 ) -> Option<EthereumSqlTypeWrapper> {
     // Check for explicit null value first
     if value_ref == "$null" {
@@ -2550,6 +3690,20 @@ async fn extract_value_from_event_async(
     if is_conditional_value(value_ref) {
         match parse_conditional_value(value_ref) {
             Ok((condition, true_value, false_value)) => {
+                // Looked up values become literals of the condition
+                let condition = if has_batch_references(&condition) {
+                    resolve_lookups_in_condition(
+                        &condition,
+                        lookups,
+                        log_params,
+                        tx_metadata,
+                        network,
+                        constants,
+                    )?
+                } else {
+                    condition
+                };
+
                 // Evaluate the condition against event data
                 let json_data = log_params_to_json(log_params);
                 match filter_by_expression(&condition, &json_data) {
@@ -2563,6 +3717,7 @@ async fn extract_value_from_event_async(
                             provider,
                             network,
                             constants,
+                            lookups,
                         ))
                         .await;
                     }
@@ -2576,6 +3731,7 @@ async fn extract_value_from_event_async(
                             provider,
                             network,
                             constants,
+                            lookups,
                         ))
                         .await;
                     }
@@ -2592,6 +3748,55 @@ async fn extract_value_from_event_async(
         }
     }
 
+    // Replace $lookup(...) references with the rows prefetched for this batch
+    let resolved_lookups: String;
+    let value_ref = if value_ref.contains(LOOKUP_PREFIX) {
+        if let Some(lookup) = parse_lookup(value_ref) {
+            return match resolve_lookup(
+                &lookup,
+                lookups,
+                log_params,
+                tx_metadata,
+                network,
+                constants,
+            )? {
+                Some(value) => Some(literal_to_wrapper(&value, column_type)),
+                None => Some(EthereumSqlTypeWrapper::Null),
+            };
+        }
+        resolved_lookups = substitute_lookups(value_ref, |lookup| {
+            resolve_lookup(lookup, lookups, log_params, tx_metadata, network, constants).flatten()
+        })?;
+        resolved_lookups.as_str()
+    } else {
+        value_ref
+    };
+
+    // Replace $token(...) references with the token metadata prefetched for this batch
+    let resolved_tokens: String;
+    let value_ref = if value_ref.contains(TOKEN_PREFIX) {
+        if let Some(token) = parse_token(value_ref) {
+            return match resolve_token(
+                &token,
+                lookups.tokens,
+                log_params,
+                tx_metadata,
+                network,
+                constants,
+            )? {
+                Some(value) => Some(literal_to_wrapper(&value, column_type)),
+                None => Some(EthereumSqlTypeWrapper::Null),
+            };
+        }
+        resolved_tokens = substitute_tokens(value_ref, |token| {
+            resolve_token(token, lookups.tokens, log_params, tx_metadata, network, constants)
+                .flatten()
+        })?;
+        resolved_tokens.as_str()
+    } else {
+        value_ref
+    };
+
     // First resolve any constants in the value (handles $constant(...) anywhere in string)
     let resolved_constants: String;
     let after_constants = if value_ref.contains("$constant(") {
@@ -2624,12 +3829,7 @@ async fn extract_value_from_event_async(
             map.insert("rindexer_log_index".to_string(), json!(tx_metadata.log_index.to::<u64>()));
         }
         return match evaluate_arithmetic(&resolved_expr, &json_data) {
-            Ok(ComputedValue::U256(val)) => match column_type {
-                ColumnType::Uint64 => Some(EthereumSqlTypeWrapper::U64BigInt(val.to::<u64>())),
-                ColumnType::Uint128 => Some(EthereumSqlTypeWrapper::U256Numeric(val)),
-                _ => Some(EthereumSqlTypeWrapper::U256Numeric(val)),
-            },
-            Ok(ComputedValue::String(s)) => Some(EthereumSqlTypeWrapper::String(s)),
+            Ok(value) => Some(computed_value_to_wrapper(value, column_type)),
             Err(e) => {
                 debug!(
                     "Arithmetic expression with calls evaluation failed: {}. Expression: {}",
@@ -2894,15 +4094,7 @@ fn extract_value_from_event(
             }
         }
         return match evaluate_arithmetic(value_ref, &json_data) {
-            Ok(ComputedValue::U256(val)) => {
-                // Convert based on column type
-                match column_type {
-                    ColumnType::Uint64 => Some(EthereumSqlTypeWrapper::U64BigInt(val.to::<u64>())),
-                    ColumnType::Uint128 => Some(EthereumSqlTypeWrapper::U256Numeric(val)),
-                    _ => Some(EthereumSqlTypeWrapper::U256Numeric(val)),
-                }
-            }
-            Ok(ComputedValue::String(s)) => Some(EthereumSqlTypeWrapper::String(s)),
+            Ok(value) => Some(computed_value_to_wrapper(value, column_type)),
             Err(e) => {
                 warn!("Arithmetic expression evaluation failed: {}. Expression: '{}'. Row will be skipped.", e, value_ref);
                 None
@@ -2994,6 +4186,57 @@ fn extract_value_from_event(
     }
 }
 
+/// Converts the result of an arithmetic expression based on the column type.
+fn computed_value_to_wrapper(
+    value: ComputedValue,
+    column_type: &ColumnType,
+) -> EthereumSqlTypeWrapper {
+    match (value, column_type) {
+        (ComputedValue::U256(val), ColumnType::Uint64) => {
+            EthereumSqlTypeWrapper::U64BigInt(val.to::<u64>())
+        }
+        (ComputedValue::U256(val), ColumnType::Decimal(scale)) => {
+            uint_to_decimal_wrapper(val, *scale)
+        }
+        (ComputedValue::U256(val), _) => EthereumSqlTypeWrapper::U256Numeric(val),
+        (ComputedValue::Decimal { value, scale: value_scale }, ColumnType::Decimal(scale)) => {
+            decimal_to_wrapper(value, value_scale, *scale)
+        }
+        (ComputedValue::Decimal { value, scale }, _) => {
+            EthereumSqlTypeWrapper::String(fixed_to_string(value, scale))
+        }
+        (ComputedValue::String(s), _) => EthereumSqlTypeWrapper::String(s),
+    }
+}
+
+/// Converts an exact decimal to the scale of a `decimal(scale)` column. Extra fractional
+/// digits are truncated toward zero, never rounded. Values over the 76 digits of the column
+/// are stored as NULL by every backend.
+fn decimal_to_wrapper(value: I256, value_scale: u8, scale: u8) -> EthereumSqlTypeWrapper {
+    match rescale_fixed(value, value_scale, scale).filter(|value| fits_decimal_precision(*value)) {
+        Some(value) => EthereumSqlTypeWrapper::DecimalNumeric(value, scale),
+        None => {
+            warn!(
+                "Decimal {} does not fit a decimal({}) column, storing NULL",
+                fixed_to_string(value, value_scale),
+                scale
+            );
+            EthereumSqlTypeWrapper::Null
+        }
+    }
+}
+
+/// Converts an unsigned integer for a `decimal(scale)` column, see [`decimal_to_wrapper`].
+fn uint_to_decimal_wrapper(value: U256, scale: u8) -> EthereumSqlTypeWrapper {
+    match I256::try_from(value) {
+        Ok(value) => decimal_to_wrapper(value, 0, scale),
+        Err(_) => {
+            warn!("Decimal {} does not fit a decimal({}) column, storing NULL", value, scale);
+            EthereumSqlTypeWrapper::Null
+        }
+    }
+}
+
 /// Converts a DynSolValue to the appropriate EthereumSqlTypeWrapper.
 /// Uses PostgreSQL-compatible types (U256Numeric for NUMERIC, U64BigInt for BIGINT).
 fn dyn_sol_value_to_wrapper(
@@ -3117,6 +4360,14 @@ fn dyn_sol_value_to_wrapper(
         (DynSolValue::Int(val, _), ColumnType::String) => {
             EthereumSqlTypeWrapper::String(val.to_string())
         }
+        // Integers into decimal columns keep their value, e.g. 5 is stored as 5.000000
+        (DynSolValue::Uint(val, _), ColumnType::Decimal(scale)) => {
+            uint_to_decimal_wrapper(*val, *scale)
+        }
+        (DynSolValue::Int(val, _), ColumnType::Decimal(scale)) => {
+            decimal_to_wrapper(*val, 0, *scale)
+        }
+        (DynSolValue::String(s), ColumnType::Decimal(_)) => literal_to_wrapper(s, column_type),
         // Fallback conversions - use PostgreSQL-compatible types
         (DynSolValue::Uint(val, _), _) => EthereumSqlTypeWrapper::U256Numeric(*val),
         (DynSolValue::Int(val, _), _) => EthereumSqlTypeWrapper::I256Numeric(*val),
@@ -3289,6 +4540,13 @@ fn literal_to_wrapper(value: &str, column_type: &ColumnType) -> EthereumSqlTypeW
                 EthereumSqlTypeWrapper::String(value.to_string())
             }
         }
+        ColumnType::Decimal(scale) => {
+            if let Ok(num) = string_to_fixed(value, *scale) {
+                decimal_to_wrapper(num, *scale, *scale)
+            } else {
+                EthereumSqlTypeWrapper::String(value.to_string())
+            }
+        }
         // Arrays from literals are stored as JSON strings
         // (arrays from event data are handled in dyn_sol_value_to_wrapper)
         ColumnType::Array(_) => EthereumSqlTypeWrapper::String(value.to_string()),
@@ -3485,7 +4743,9 @@ pub async fn process_table_operations(
 
     // Check if any table needs timestamps and prefetch them in batch
     let any_table_needs_timestamp = tables.iter().any(|t| {
-        let handles_event = t.table.events.iter().any(|e| e.event == event_name);
+        // Tables following other tables run for rows changed by the same events
+        let handles_event =
+            t.table.events.iter().any(|e| e.event == event_name) || !t.table.on_change.is_empty();
         if !handles_event {
             return false;
         }
@@ -3496,15 +4756,16 @@ pub async fn process_table_operations(
         // Column mapping references $rindexer_block_timestamp (custom column using the
         // built-in metadata). Without prefetch, this resolves to NULL when the RPC doesn't
         // include blockTimestamp in eth_getLogs responses.
-        t.table.events.iter().any(|e| {
-            e.event == event_name
-                && e.operations.iter().any(|op| {
-                    op.where_clause.values().any(|v| v.contains("rindexer_block_timestamp"))
-                        || op
-                            .set
-                            .iter()
-                            .any(|s| s.effective_value().contains("rindexer_block_timestamp"))
-                })
+        let event_operations = t
+            .table
+            .events
+            .iter()
+            .filter(|e| e.event == event_name)
+            .flat_map(|e| e.operations.iter());
+        let change_operations = t.table.on_change.iter().flat_map(|c| c.operations.iter());
+        event_operations.chain(change_operations).any(|op| {
+            op.where_clause.values().any(|v| v.contains("rindexer_block_timestamp"))
+                || op.set.iter().any(|s| s.effective_value().contains("rindexer_block_timestamp"))
         })
     });
 
@@ -3551,10 +4812,40 @@ pub async fn process_table_operations(
         }
     }
 
+    // Read the rows referenced by $lookup once for the whole batch
+    let lookup_rows = prefetch_lookups(
+        tables,
+        event_name,
+        events_data,
+        postgres.as_deref(),
+        clickhouse.as_deref(),
+        constants,
+    )
+    .await?;
+
+    // Read the token metadata referenced by $token, stored once per token
+    let token_metadata = prefetch_token_metadata(
+        tables,
+        event_name,
+        events_data,
+        &providers,
+        postgres.as_deref(),
+        clickhouse.as_deref(),
+        constants,
+        multicall_addresses,
+    )
+    .await;
+
     // Track the max block number written per network - used for checkpointing on shutdown
     let mut max_block_written_per_network: HashMap<String, u64> = HashMap::new();
 
-    for table_runtime in tables {
+    // Rows changed by the batch in tables followed with `on_change`, keyed by table name
+    let mut table_changes: HashMap<&str, Vec<(Vec<LogParam>, String, TxMetadata)>> = HashMap::new();
+
+    // Values written by the batch to tables other tables read with `$lookup`
+    let mut lookup_writes = LookupWrites::new();
+
+    for table_runtime in order_by_table_dependencies(tables) {
         // Check for shutdown before processing each table
         if !is_running() {
             // Only checkpoint if we've actually written data to the database
@@ -3576,61 +4867,106 @@ pub async fn process_table_operations(
             return Err("Shutdown requested".to_string());
         }
 
-        // Find operations for this event
-        let event_mapping = table_runtime.table.events.iter().find(|e| e.event == event_name);
+        // Find operations for this event and for the rows changed in followed tables
+        let mut triggers: Vec<(&[TableOperation], &[IterateBinding], &TriggerEvents)> = Vec::new();
+        if let Some(em) = table_runtime.table.events.iter().find(|e| e.event == event_name) {
+            triggers.push((&em.operations, &em.iterate, events_data));
+        }
+        for change_mapping in &table_runtime.table.on_change {
+            if let Some(changes) = table_changes.get(change_mapping.table.as_str()) {
+                triggers.push((&change_mapping.operations, &[], changes));
+            }
+        }
+        if triggers.is_empty() {
+            continue;
+        }
 
-        let event_mapping = match event_mapping {
-            Some(em) => em,
-            None => continue,
+        // Tables followed by other tables record the rows they change
+        let is_followed = tables
+            .iter()
+            .any(|t| t.table.on_change.iter().any(|c| c.table == table_runtime.table.name));
+        let mut change_tracker = is_followed.then(|| ChangeTracker::new(table_runtime));
+
+        // Bucket width of rollup tables, validated when the manifest is loaded
+        let bucket_interval_secs = match &table_runtime.table.bucket {
+            Some(bucket) => Some(bucket.interval_secs().map_err(|e| {
+                format!("Invalid bucket for table {}: {}", table_runtime.full_table_name, e)
+            })?),
+            None => None,
         };
 
-        for operation in &event_mapping.operations {
-            let mut rows_to_process: Vec<TableRowData> = Vec::new();
-            // Track max block per network for this batch of rows
-            let mut batch_max_blocks: HashMap<String, u64> = HashMap::new();
-
-            // Check if condition has @table references - push to SQL instead of Rust evaluation
-            let (should_filter_in_rust, sql_condition) =
-                if let Some(condition_expr) = operation.condition() {
-                    match parse_filter_expression(condition_expr) {
-                        Ok(expr) => {
-                            if expr.has_table_references() {
-                                let sql = expr.to_sql_condition(&table_runtime.full_table_name);
-                                (false, Some(sql))
-                            } else {
+        let lookups = Lookups {
+            tables: &table_runtime.lookup_tables,
+            rows: &lookup_rows,
+            writes: &lookup_writes,
+            tokens: &token_metadata,
+        };
+
+        // Tables read by other tables with `$lookup` record what they write for them
+        let looked_up_as = tables
+            .iter()
+            .flat_map(|t| t.lookup_tables.values())
+            .find(|lookup_table| lookup_table.full_table_name == table_runtime.full_table_name);
+        let mut table_lookup_writes = LookupWrites::new();
+
+        let mut history_batches: Vec<(&TableOperation, Option<String>, Vec<TableRowData>)> =
+            Vec::new();
+        let mut history_max_blocks: Vec<HashMap<String, u64>> = Vec::new();
+
+        for (operations, iterate, trigger_events) in triggers {
+            for operation in operations {
+                let mut rows_to_process: Vec<TableRowData> = Vec::new();
+                let mut rows_metadata: Vec<&TxMetadata> = Vec::new();
+                // Track max block per network for this batch of rows
+                let mut batch_max_blocks: HashMap<String, u64> = HashMap::new();
+
+                // Check if condition has @table references - push to SQL instead of Rust evaluation
+                let (should_filter_in_rust, sql_condition) = if let Some(condition_expr) =
+                    operation.condition()
+                {
+                    if has_batch_references(condition_expr) {
+                        // Lookups are substituted per event before the condition is parsed
+                        (true, None)
+                    } else {
+                        match parse_filter_expression(condition_expr) {
+                            Ok(expr) => {
+                                if expr.has_table_references() {
+                                    let sql = expr.to_sql_condition(&table_runtime.full_table_name);
+                                    (false, Some(sql))
+                                } else {
+                                    (true, None)
+                                }
+                            }
+                            Err(e) => {
+                                debug!("Failed to parse condition for SQL generation: {}", e);
                                 (true, None)
                             }
                         }
-                        Err(e) => {
-                            debug!("Failed to parse condition for SQL generation: {}", e);
-                            (true, None)
-                        }
                     }
                 } else {
                     (false, None)
                 };
 
-            for (log_params, network, tx_metadata) in events_data {
-                // Check for shutdown before processing each event - exit quickly
-                if !is_running() {
-                    // Checkpoint what we've written so far
-                    if !max_block_written_per_network.is_empty() {
-                        if let Some(checkpoint) = checkpoint_config {
-                            for (net, max_block) in &max_block_written_per_network {
-                                info!(
-                                    "Shutdown - checkpointing block {} for {} (mid-batch)",
-                                    max_block, net
-                                );
-                                checkpoint.checkpoint(net, *max_block).await;
+                for (log_params, network, tx_metadata) in trigger_events {
+                    // Check for shutdown before processing each event - exit quickly
+                    if !is_running() {
+                        // Checkpoint what we've written so far
+                        if !max_block_written_per_network.is_empty() {
+                            if let Some(checkpoint) = checkpoint_config {
+                                for (net, max_block) in &max_block_written_per_network {
+                                    info!(
+                                        "Shutdown - checkpointing block {} for {} (mid-batch)",
+                                        max_block, net
+                                    );
+                                    checkpoint.checkpoint(net, *max_block).await;
+                                }
                             }
                         }
+                        return Err("Shutdown requested".to_string());
                     }
-                    return Err("Shutdown requested".to_string());
-                }
 
-                // Expand iterate bindings - creates multiple virtual events from array fields
-                let expanded_params_list =
-                    match expand_iterate_bindings(&event_mapping.iterate, log_params) {
+                    // Expand iterate bindings - creates multiple virtual events from array fields
+                    let expanded_params_list = match expand_iterate_bindings(iterate, log_params) {
                         Some(params) => params,
                         None => {
                             debug!("Failed to expand iterate bindings for event {}", event_name);
@@ -3638,194 +4974,297 @@ pub async fn process_table_operations(
                         }
                     };
 
-                for expanded_log_params in &expanded_params_list {
-                    if should_filter_in_rust {
-                        if let Some(condition_expr) = operation.condition() {
-                            if !evaluate_filter(
-                                condition_expr,
-                                expanded_log_params,
-                                Some(tx_metadata),
-                            ) {
-                                continue;
+                    for expanded_log_params in &expanded_params_list {
+                        if should_filter_in_rust {
+                            if let Some(condition_expr) = operation.condition() {
+                                // A lookup of a missing row never matches
+                                let resolved_condition: String;
+                                let condition_expr = if has_batch_references(condition_expr) {
+                                    match resolve_lookups_in_condition(
+                                        condition_expr,
+                                        lookups,
+                                        expanded_log_params,
+                                        tx_metadata,
+                                        network,
+                                        constants,
+                                    ) {
+                                        Some(condition) => {
+                                            resolved_condition = condition;
+                                            resolved_condition.as_str()
+                                        }
+                                        None => continue,
+                                    }
+                                } else {
+                                    condition_expr
+                                };
+
+                                if !evaluate_filter(
+                                    condition_expr,
+                                    expanded_log_params,
+                                    Some(tx_metadata),
+                                ) {
+                                    continue;
+                                }
                             }
                         }
-                    }
 
-                    let mut columns: HashMap<String, EthereumSqlTypeWrapper> = HashMap::new();
-
-                    // Get provider for this network (for view calls)
-                    let provider = providers.get(network).map(|p| p.as_ref());
-
-                    // Add where clause columns
-                    for (column_name, value_ref) in &operation.where_clause {
-                        let column_def =
-                            table_runtime.table.columns.iter().find(|c| &c.name == column_name);
-
-                        if let Some(column_def) = column_def {
-                            if let Some(value) = extract_value_from_event_async(
-                                value_ref,
-                                expanded_log_params,
-                                tx_metadata,
-                                column_def.resolved_type(),
-                                provider,
-                                network,
-                                constants,
-                            )
-                            .await
-                            {
-                                columns.insert(column_name.clone(), value);
-                            }
-                        }
-                    }
+                        let mut columns: HashMap<String, EthereumSqlTypeWrapper> = HashMap::new();
+
+                        // Get provider for this network (for view calls)
+                        let provider = providers.get(network).map(|p| p.as_ref());
+
+                        // Add where clause columns
+                        for (column_name, value_ref) in &operation.where_clause {
+                            let column_def =
+                                table_runtime.table.columns.iter().find(|c| &c.name == column_name);
+
+                            if let Some(column_def) = column_def {
+                                if let Some(value) = extract_value_from_event_async(
+                                    value_ref,
+                                    expanded_log_params,
+                                    tx_metadata,
+                                    column_def.resolved_type(),
+                                    provider,
+                                    network,
+                                    constants,
+                                    lookups,
+                                )
+                                .await
+                                {
+                                    columns.insert(column_name.clone(), value);
+                                }
+                            }
+                        }
+
+                        // Add set columns with their values
+                        for set_col in &operation.set {
+                            let column_def = table_runtime
+                                .table
+                                .columns
+                                .iter()
+                                .find(|c| c.name == set_col.column);
+
+                            if let Some(column_def) = column_def {
+                                // Array actions write one element, distinct counts count values
+                                // of any type by their text form
+                                let value_type = match set_col.action {
+                                    SetAction::CountDistinct => &ColumnType::String,
+                                    _ if set_col.action.is_array_action() => {
+                                        column_def.resolved_type().element_type()
+                                    }
+                                    _ => column_def.resolved_type(),
+                                };
+
+                                if let Some(value) = extract_value_from_event_async(
+                                    set_col.effective_value(),
+                                    expanded_log_params,
+                                    tx_metadata,
+                                    value_type,
+                                    provider,
+                                    network,
+                                    constants,
+                                    lookups,
+                                )
+                                .await
+                                {
+                                    if set_col.action == SetAction::CountDistinct {
+                                        columns.insert(
+                                            injected_columns::count_distinct_values(
+                                                &set_col.column,
+                                            ),
+                                            EthereumSqlTypeWrapper::String(
+                                                format_wrapper_for_where_key(&value),
+                                            ),
+                                        );
+                                        continue;
+                                    }
+
+                                    columns.insert(set_col.column.clone(), value);
 
-                    // Add set columns with their values
-                    for set_col in &operation.set {
-                        let column_def =
-                            table_runtime.table.columns.iter().find(|c| c.name == set_col.column);
-
-                        if let Some(column_def) = column_def {
-                            if let Some(value) = extract_value_from_event_async(
-                                set_col.effective_value(),
-                                expanded_log_params,
-                                tx_metadata,
-                                column_def.resolved_type(),
-                                provider,
-                                network,
-                                constants,
-                            )
-                            .await
-                            {
-                                columns.insert(set_col.column.clone(), value);
+                                    // Each value folded into an average counts as one sample
+                                    if set_col.action == SetAction::Avg {
+                                        columns.insert(
+                                            injected_columns::avg_samples(&set_col.column),
+                                            EthereumSqlTypeWrapper::U64BigInt(1),
+                                        );
+                                    }
+                                }
                             }
                         }
-                    }
 
-                    if !columns.is_empty() {
-                        // Auto-injected metadata columns
-                        let sequence_id = compute_sequence_id(
-                            tx_metadata.block_number,
-                            tx_metadata.tx_index,
-                            tx_metadata.log_index.to::<u64>(),
-                        );
-                        columns.insert(
-                            injected_columns::RINDEXER_SEQUENCE_ID.to_string(),
-                            EthereumSqlTypeWrapper::U128(sequence_id),
-                        );
-                        columns.insert(
-                            injected_columns::BLOCK_NUMBER.to_string(),
-                            EthereumSqlTypeWrapper::U64BigInt(tx_metadata.block_number),
-                        );
-                        // Only insert block_timestamp if table.timestamp is true
-                        if table_runtime.table.timestamp {
-                            let block_timestamp = if let Some(ts) = tx_metadata.block_timestamp {
-                                // Use timestamp from metadata if available
-                                Some(ts.to::<u64>())
-                            } else {
-                                // Look up from cache (prefetched at start of processing)
-                                get_cached_block_timestamp(network, tx_metadata.block_number).await
-                            };
-
-                            // block_timestamp is required (NOT NULL column) — if missing after
-                            // prefetch + cache lookup, the RPC was unreachable. Return error so
-                            // the batch retries instead of writing NULLs. (fix: #320 cascade)
-                            let ts = block_timestamp.ok_or_else(|| {
-                                format!(
-                                    "block_timestamp unavailable for block {} on {} — RPC may be down",
-                                    tx_metadata.block_number, network
-                                )
-                            })?;
+                        if !columns.is_empty() {
+                            // Auto-injected metadata columns
+                            let sequence_id = compute_sequence_id(
+                                tx_metadata.block_number,
+                                tx_metadata.tx_index,
+                                tx_metadata.log_index.to::<u64>(),
+                            );
+                            columns.insert(
+                                injected_columns::RINDEXER_SEQUENCE_ID.to_string(),
+                                EthereumSqlTypeWrapper::U128(sequence_id),
+                            );
+                            columns.insert(
+                                injected_columns::BLOCK_NUMBER.to_string(),
+                                EthereumSqlTypeWrapper::U64BigInt(tx_metadata.block_number),
+                            );
+                            // Only insert block_timestamp if table.timestamp is true
+                            if table_runtime.table.timestamp {
+                                let block_timestamp = if let Some(ts) = tx_metadata.block_timestamp
+                                {
+                                    // Use timestamp from metadata if available
+                                    Some(ts.to::<u64>())
+                                } else {
+                                    // Look up from cache (prefetched at start of processing)
+                                    get_cached_block_timestamp(network, tx_metadata.block_number)
+                                        .await
+                                };
+
+                                // block_timestamp is required (NOT NULL column) — if missing after
+                                // prefetch + cache lookup, the RPC was unreachable. Return error so
+                                // the batch retries instead of writing NULLs. (fix: #320 cascade)
+                                let ts = block_timestamp.ok_or_else(|| {
+                                    format!(
+                                        "block_timestamp unavailable for block {} on {} — RPC may be down",
+                                        tx_metadata.block_number, network
+                                    )
+                                })?;
+
+                                let dt = DateTime::from_timestamp(ts as i64, 0).ok_or_else(|| {
+                                    format!(
+                                        "block_timestamp {} out of DateTime range for block {} on {}",
+                                        ts, tx_metadata.block_number, network
+                                    )
+                                })?;
+                                columns.insert(
+                                    injected_columns::BLOCK_TIMESTAMP.to_string(),
+                                    EthereumSqlTypeWrapper::DateTime(dt.with_timezone(&Utc)),
+                                );
 
-                            let dt = DateTime::from_timestamp(ts as i64, 0).ok_or_else(|| {
-                                format!(
-                                    "block_timestamp {} out of DateTime range for block {} on {}",
-                                    ts, tx_metadata.block_number, network
-                                )
-                            })?;
+                                // Rollup tables key every row by the bucket the block falls into
+                                if let (Some(bucket), Some(interval_secs)) =
+                                    (&table_runtime.table.bucket, bucket_interval_secs)
+                                {
+                                    let bucket_start = TableBucket::bucket_start(ts, interval_secs);
+                                    let bucket_dt = DateTime::from_timestamp(bucket_start as i64, 0)
+                                        .ok_or_else(|| {
+                                            format!(
+                                                "bucket start {} out of DateTime range for block {} on {}",
+                                                bucket_start, tx_metadata.block_number, network
+                                            )
+                                        })?;
+                                    columns.insert(
+                                        bucket.column.clone(),
+                                        EthereumSqlTypeWrapper::DateTime(
+                                            bucket_dt.with_timezone(&Utc),
+                                        ),
+                                    );
+                                }
+                            }
+                            columns.insert(
+                                injected_columns::TX_HASH.to_string(),
+                                EthereumSqlTypeWrapper::StringChar(format!(
+                                    "{:?}",
+                                    tx_metadata.tx_hash
+                                )),
+                            );
                             columns.insert(
-                                injected_columns::BLOCK_TIMESTAMP.to_string(),
-                                EthereumSqlTypeWrapper::DateTime(dt.with_timezone(&Utc)),
+                                injected_columns::BLOCK_HASH.to_string(),
+                                EthereumSqlTypeWrapper::StringChar(format!(
+                                    "{:?}",
+                                    tx_metadata.block_hash
+                                )),
                             );
+                            columns.insert(
+                                injected_columns::CONTRACT_ADDRESS.to_string(),
+                                EthereumSqlTypeWrapper::Address(tx_metadata.contract_address),
+                            );
+
+                            rows_to_process
+                                .push(TableRowData { columns, network: network.clone() });
+                            rows_metadata.push(tx_metadata);
+                            // Track max block for this batch
+                            batch_max_blocks
+                                .entry(network.clone())
+                                .and_modify(|max| {
+                                    if tx_metadata.block_number > *max {
+                                        *max = tx_metadata.block_number;
+                                    }
+                                })
+                                .or_insert(tx_metadata.block_number);
                         }
-                        columns.insert(
-                            injected_columns::TX_HASH.to_string(),
-                            EthereumSqlTypeWrapper::StringChar(format!(
-                                "{:?}",
-                                tx_metadata.tx_hash
-                            )),
-                        );
-                        columns.insert(
-                            injected_columns::BLOCK_HASH.to_string(),
-                            EthereumSqlTypeWrapper::StringChar(format!(
-                                "{:?}",
-                                tx_metadata.block_hash
-                            )),
-                        );
-                        columns.insert(
-                            injected_columns::CONTRACT_ADDRESS.to_string(),
-                            EthereumSqlTypeWrapper::Address(tx_metadata.contract_address),
-                        );
+                    } // end for expanded_log_params
+                }
 
-                        rows_to_process.push(TableRowData { columns, network: network.clone() });
-                        // Track max block for this batch
-                        batch_max_blocks
-                            .entry(network.clone())
-                            .and_modify(|max| {
-                                if tx_metadata.block_number > *max {
-                                    *max = tx_metadata.block_number;
-                                }
-                            })
-                            .or_insert(tx_metadata.block_number);
-                    }
-                } // end for expanded_log_params
-            }
+                if rows_to_process.is_empty() {
+                    continue;
+                }
 
-            if rows_to_process.is_empty() {
-                continue;
-            }
+                if let Some(lookup_table) = looked_up_as {
+                    collect_lookup_writes(
+                        lookup_table,
+                        operation,
+                        sql_condition.as_deref(),
+                        &rows_to_process,
+                        &rows_metadata,
+                        lookups,
+                        &mut table_lookup_writes,
+                    );
+                }
 
-            // Execute the operation
-            if let Some(postgres) = &postgres {
-                execute_postgres_operation(
-                    postgres,
-                    &table_runtime.full_table_name,
-                    &table_runtime.table,
-                    operation,
-                    &rows_to_process,
-                    sql_condition.as_deref(),
-                )
-                .await?;
+                // Rows of followed tables are read before the batch first writes them
+                if let Some(change_tracker) = change_tracker.as_mut() {
+                    change_tracker
+                        .track(
+                            &rows_to_process,
+                            &rows_metadata,
+                            postgres.as_deref(),
+                            clickhouse.as_deref(),
+                        )
+                        .await?;
+                }
 
-                // Journal non-reversible operations (Set/Max/Min) for reorg recalculation
-                journal_non_reversible_ops(
-                    postgres,
-                    &table_runtime.full_table_name,
-                    operation,
-                    &rows_to_process,
-                )
-                .await;
-            }
+                // History tables replay their rows block by block once every operation is
+                // resolved, so each block's row versions are recorded.
+                if table_runtime.table.history {
+                    history_batches.push((operation, sql_condition, rows_to_process));
+                    history_max_blocks.push(batch_max_blocks);
+                    continue;
+                }
 
-            if let Some(clickhouse) = &clickhouse {
-                execute_clickhouse_operation(
-                    clickhouse,
-                    &table_runtime.full_table_name,
-                    &table_runtime.table,
+                write_table_operation(
+                    postgres.as_ref(),
+                    clickhouse.as_ref(),
+                    table_runtime,
                     operation,
                     &rows_to_process,
+                    sql_condition.as_deref(),
                 )
                 .await?;
 
-                journal_non_reversible_ops_clickhouse(
-                    clickhouse,
-                    &table_runtime.full_table_name,
-                    operation,
-                    &rows_to_process,
-                )
-                .await;
+                // DB write succeeded - update max blocks written tracker
+                for (network, block) in &batch_max_blocks {
+                    max_block_written_per_network
+                        .entry(network.clone())
+                        .and_modify(|max| {
+                            if *block > *max {
+                                *max = *block;
+                            }
+                        })
+                        .or_insert(*block);
+                }
             }
+        }
 
-            // DB write succeeded - update max blocks written tracker
-            for (network, block) in &batch_max_blocks {
+        if !history_batches.is_empty() {
+            write_history_table_operations(
+                postgres.as_ref(),
+                clickhouse.as_ref(),
+                table_runtime,
+                history_batches,
+            )
+            .await?;
+
+            for (network, block) in history_max_blocks.iter().flatten() {
                 max_block_written_per_network
                     .entry(network.clone())
                     .and_modify(|max| {
@@ -3836,11 +5275,630 @@ pub async fn process_table_operations(
                     .or_insert(*block);
             }
         }
+
+        if let Some(change_tracker) = change_tracker {
+            let changes =
+                change_tracker.changes(postgres.as_deref(), clickhouse.as_deref()).await?;
+            if !changes.is_empty() {
+                table_changes.insert(table_runtime.table.name.as_str(), changes);
+            }
+        }
+
+        for (row_key, writes) in table_lookup_writes {
+            lookup_writes.entry(row_key).or_default().extend(writes);
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes one operation's rows to every configured backend and journals the
+/// non-reversible columns used to recalculate them on reorg.
+async fn write_table_operation(
+    postgres: Option<&Arc<PostgresClient>>,
+    clickhouse: Option<&Arc<ClickhouseClient>>,
+    table_runtime: &TableRuntime,
+    operation: &TableOperation,
+    rows_to_process: &[TableRowData],
+    sql_condition: Option<&str>,
+) -> Result<(), String> {
+    if let Some(postgres) = postgres {
+        let rows_to_process = count_distinct_postgres(
+            postgres,
+            &table_runtime.full_table_name,
+            &table_runtime.table,
+            operation,
+            rows_to_process,
+        )
+        .await?;
+        let rows_to_process = rows_to_process.as_ref();
+
+        execute_postgres_operation(
+            postgres,
+            &table_runtime.full_table_name,
+            &table_runtime.table,
+            operation,
+            rows_to_process,
+            sql_condition,
+        )
+        .await?;
+
+        // Journal non-reversible operations (Set/Max/Min) for reorg recalculation
+        journal_non_reversible_ops(
+            postgres,
+            &table_runtime.full_table_name,
+            &table_runtime.table,
+            operation,
+            rows_to_process,
+        )
+        .await;
+    }
+
+    if let Some(clickhouse) = clickhouse {
+        let rows_to_process = count_distinct_clickhouse(
+            clickhouse,
+            &table_runtime.full_table_name,
+            &table_runtime.table,
+            operation,
+            rows_to_process,
+        )
+        .await?;
+        let rows_to_process = rows_to_process.as_ref();
+
+        if table_runtime.table.bucket.is_some() {
+            // Rollups keep per-block partial aggregates, a reorg drops them
+            // directly so no journal is needed.
+            execute_clickhouse_rollup_operation(
+                clickhouse,
+                &table_runtime.full_table_name,
+                &table_runtime.table,
+                operation,
+                rows_to_process,
+            )
+            .await?;
+        } else {
+            execute_clickhouse_operation(
+                clickhouse,
+                &table_runtime.full_table_name,
+                &table_runtime.table,
+                operation,
+                rows_to_process,
+            )
+            .await?;
+
+            journal_non_reversible_ops_clickhouse(
+                clickhouse,
+                &table_runtime.full_table_name,
+                &table_runtime.table,
+                operation,
+                rows_to_process,
+            )
+            .await;
+        }
+    }
+
+    Ok(())
+}
+
+/// A value counted by a `count_distinct` column, keyed like its side table row.
+#[derive(Debug, PartialEq)]
+struct DistinctValue {
+    network: String,
+    where_key: String,
+    column: String,
+    value: String,
+    block_number: u64,
+}
+
+/// Network the side table counts a row under. Cross chain tables count every network
+/// together.
+fn distinct_values_network(table: &Table, row: &TableRowData) -> String {
+    if table.cross_chain {
+        String::new()
+    } else {
+        row.network.clone()
+    }
+}
+
+/// Values `rows` add to the `count_distinct` columns of `operation`, keyed by the journal
+/// where_key of their row.
+fn collect_distinct_values(
+    table: &Table,
+    operation: &TableOperation,
+    rows: &[TableRowData],
+) -> Vec<DistinctValue> {
+    let columns: Vec<&SetColumn> =
+        operation.set.iter().filter(|sc| sc.action == SetAction::CountDistinct).collect();
+    if columns.is_empty() {
+        return vec![];
+    }
+
+    let where_keys = journal_where_columns(table, operation);
+    let mut values = Vec::new();
+    for row in rows {
+        let Some(where_key) = journal_where_key(&where_keys, table, row) else {
+            continue;
+        };
+        let block_number = extract_row_metadata(row).map(|(block, _, _)| block).unwrap_or(0);
+        for sc in &columns {
+            let Some(EthereumSqlTypeWrapper::String(value)) =
+                row.columns.get(&injected_columns::count_distinct_values(&sc.column))
+            else {
+                continue;
+            };
+            values.push(DistinctValue {
+                network: distinct_values_network(table, row),
+                where_key: where_key.clone(),
+                column: sc.column.clone(),
+                value: value.clone(),
+                block_number,
+            });
+        }
+    }
+    values
+}
+
+/// Copies of `rows` with every `count_distinct` column they counted a value for set to the
+/// number of distinct values stored for the row, looked up in `totals` by
+/// (network, where_key, column).
+fn with_distinct_counts(
+    table: &Table,
+    operation: &TableOperation,
+    rows: &[TableRowData],
+    totals: &HashMap<(String, String, String), u64>,
+) -> Vec<TableRowData> {
+    let where_keys = journal_where_columns(table, operation);
+    rows.iter()
+        .map(|row| {
+            let mut row = row.clone();
+            let Some(where_key) = journal_where_key(&where_keys, table, &row) else {
+                return row;
+            };
+            let network = distinct_values_network(table, &row);
+            for sc in operation.set.iter().filter(|sc| sc.action == SetAction::CountDistinct) {
+                if !row.columns.contains_key(&injected_columns::count_distinct_values(&sc.column)) {
+                    continue;
+                }
+                let Some(column) = table.columns.iter().find(|c| c.name == sc.column) else {
+                    continue;
+                };
+                let total = totals
+                    .get(&(network.clone(), where_key.clone(), sc.column.clone()))
+                    .copied()
+                    .unwrap_or(0);
+                row.columns.insert(
+                    sc.column.clone(),
+                    literal_to_wrapper(&total.to_string(), column.resolved_type()),
+                );
+            }
+            row
+        })
+        .collect()
+}
+
+/// Stores the values `rows` count for the `count_distinct` columns of `operation` in the
+/// table's `_distinct_values` side table and returns the rows with each counted column set
+/// to the number of distinct values stored for its row. Stored values are skipped so a
+/// retried batch counts the same.
+async fn count_distinct_postgres<'a>(
+    postgres: &PostgresClient,
+    table_name: &str,
+    table: &Table,
+    operation: &TableOperation,
+    rows: &'a [TableRowData],
+) -> Result<Cow<'a, [TableRowData]>, String> {
+    let values = collect_distinct_values(table, operation, rows);
+    if values.is_empty() {
+        return Ok(Cow::Borrowed(rows));
+    }
+
+    let distinct_table = generate_distinct_values_table_full_name(table_name);
+    let quote = |value: &str| format!("'{}'", value.replace('\'', "''"));
+
+    let value_tuples: Vec<String> = values
+        .iter()
+        .map(|v| {
+            format!(
+                "({}, {}, {}, {}, {})",
+                quote(&v.network),
+                quote(&v.where_key),
+                quote(&v.column),
+                quote(&v.value),
+                v.block_number
+            )
+        })
+        .collect();
+    let insert_sql = format!(
+        "INSERT INTO {} (network, where_key, column_name, value, block_number) VALUES {} \
+         ON CONFLICT DO NOTHING",
+        distinct_table,
+        value_tuples.join(", ")
+    );
+    postgres
+        .batch_execute(&insert_sql)
+        .await
+        .map_err(|e| format!("Failed to store distinct values of {}: {}", table_name, e))?;
+
+    let keys: BTreeSet<String> = values
+        .iter()
+        .map(|v| format!("({}, {}, {})", quote(&v.network), quote(&v.where_key), quote(&v.column)))
+        .collect();
+    let count_sql = format!(
+        "SELECT network, where_key, column_name, COUNT(*) AS total FROM {} \
+         WHERE (network, where_key, column_name) IN ({}) \
+         GROUP BY network, where_key, column_name",
+        distinct_table,
+        keys.into_iter().collect::<Vec<_>>().join(", ")
+    );
+    let totals: HashMap<(String, String, String), u64> = postgres
+        .query(&count_sql, &[])
+        .await
+        .map_err(|e| format!("Failed to count distinct values of {}: {}", table_name, e))?
+        .iter()
+        .map(|row| {
+            let total: i64 = row.get("total");
+            ((row.get("network"), row.get("where_key"), row.get("column_name")), total as u64)
+        })
+        .collect();
+
+    Ok(Cow::Owned(with_distinct_counts(table, operation, rows, &totals)))
+}
+
+/// ClickHouse counterpart of `count_distinct_postgres`. Only values not stored yet are
+/// inserted, so each value keeps the block it was first seen at for reorgs.
+async fn count_distinct_clickhouse<'a>(
+    clickhouse: &ClickhouseClient,
+    table_name: &str,
+    table: &Table,
+    operation: &TableOperation,
+    rows: &'a [TableRowData],
+) -> Result<Cow<'a, [TableRowData]>, String> {
+    use clickhouse::Row;
+    use serde::Deserialize;
+
+    #[derive(Row, Deserialize)]
+    struct StoredValue {
+        network: String,
+        where_key: String,
+        column_name: String,
+        value: String,
+    }
+
+    #[derive(Row, Deserialize)]
+    struct DistinctTotal {
+        network: String,
+        where_key: String,
+        column_name: String,
+        total: u64,
+    }
+
+    let values = collect_distinct_values(table, operation, rows);
+    if values.is_empty() {
+        return Ok(Cow::Borrowed(rows));
+    }
+
+    let distinct_table = generate_distinct_values_table_full_name(table_name);
+    let quote = |value: &str| format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"));
+
+    let value_keys: BTreeSet<String> = values
+        .iter()
+        .map(|v| {
+            format!(
+                "({}, {}, {}, {})",
+                quote(&v.network),
+                quote(&v.where_key),
+                quote(&v.column),
+                quote(&v.value)
+            )
+        })
+        .collect();
+    let stored_sql = format!(
+        "SELECT network, where_key, column_name, value FROM {} FINAL \
+         WHERE (network, where_key, column_name, value) IN ({})",
+        distinct_table,
+        value_keys.into_iter().collect::<Vec<_>>().join(", ")
+    );
+    let mut seen: HashSet<(String, String, String, String)> = clickhouse
+        .query_all::<StoredValue>(&stored_sql)
+        .await
+        .map_err(|e| format!("Failed to read distinct values of {}: {}", table_name, e))?
+        .into_iter()
+        .map(|v| (v.network, v.where_key, v.column_name, v.value))
+        .collect();
+
+    let new_tuples: Vec<String> = values
+        .iter()
+        .filter(|v| {
+            seen.insert((v.network.clone(), v.where_key.clone(), v.column.clone(), v.value.clone()))
+        })
+        .map(|v| {
+            format!(
+                "({}, {}, {}, {}, {})",
+                quote(&v.network),
+                quote(&v.where_key),
+                quote(&v.column),
+                quote(&v.value),
+                v.block_number
+            )
+        })
+        .collect();
+    if !new_tuples.is_empty() {
+        let insert_sql = format!(
+            "INSERT INTO {} (network, where_key, column_name, value, block_number) VALUES {}",
+            distinct_table,
+            new_tuples.join(", ")
+        );
+        clickhouse
+            .execute(&insert_sql)
+            .await
+            .map_err(|e| format!("Failed to store distinct values of {}: {}", table_name, e))?;
+    }
+
+    let keys: BTreeSet<String> = values
+        .iter()
+        .map(|v| format!("({}, {}, {})", quote(&v.network), quote(&v.where_key), quote(&v.column)))
+        .collect();
+    let count_sql = format!(
+        "SELECT network, where_key, column_name, count() AS total FROM {} FINAL \
+         WHERE (network, where_key, column_name) IN ({}) \
+         GROUP BY network, where_key, column_name",
+        distinct_table,
+        keys.into_iter().collect::<Vec<_>>().join(", ")
+    );
+    let totals: HashMap<(String, String, String), u64> = clickhouse
+        .query_all::<DistinctTotal>(&count_sql)
+        .await
+        .map_err(|e| format!("Failed to count distinct values of {}: {}", table_name, e))?
+        .into_iter()
+        .map(|t| ((t.network, t.where_key, t.column_name), t.total))
+        .collect();
+
+    Ok(Cow::Owned(with_distinct_counts(table, operation, rows, &totals)))
+}
+
+/// Creates the Postgres partitions of a table partitioned by block range that the rows
+/// are about to be written to. Rows without a matching partition would be rejected.
+async fn ensure_block_range_partitions(
+    postgres: &PostgresClient,
+    table_name: &str,
+    table_def: &Table,
+    rows: &[TableRowData],
+) -> Result<(), String> {
+    let Some(interval) = table_def.partition_by.as_ref().and_then(|p| p.block_range()) else {
+        return Ok(());
+    };
+
+    let mut missing: Vec<u64> = Vec::new();
+    {
+        let created = BLOCK_RANGE_PARTITIONS.read().await;
+        for row in rows {
+            let Some(EthereumSqlTypeWrapper::U64BigInt(block)) =
+                row.columns.get(injected_columns::BLOCK_NUMBER)
+            else {
+                continue;
+            };
+            let start_block = block - block % interval;
+            if !missing.contains(&start_block)
+                && !created.contains(&(table_name.to_string(), start_block))
+            {
+                missing.push(start_block);
+            }
+        }
+    }
+
+    for start_block in missing {
+        let sql = generate_block_range_partition_sql(table_name, start_block, interval);
+        if let Err(e) = postgres.batch_execute(&sql).await {
+            // Another writer may have created the partition in the meantime
+            if !e.to_string().contains("already exists") {
+                return Err(format!(
+                    "Failed to create partition of {} from block {}: {}",
+                    table_name, start_block, e
+                ));
+            }
+        }
+        debug!("Tables::{} - created partition from block {}", table_name, start_block);
+        BLOCK_RANGE_PARTITIONS.write().await.insert((table_name.to_string(), start_block));
+    }
+
+    Ok(())
+}
+
+/// Rows of one history round, at most one block per row key so every block a row is written at
+/// leaves its own version.
+struct HistoryRound<'a> {
+    rows_per_operation: Vec<Vec<TableRowData>>,
+    blocks: BTreeSet<(&'a str, u64)>,
+}
+
+/// Writes the operations of a `history: true` table round by round. After each round the
+/// rows it touched are copied into `{table}_history` as new versions, closing the versions
+/// they replace, so the table can be queried as of any block.
+///
+/// A round takes the next block of every row key, so the round trips grow with the number of
+/// blocks a single row is written at in the batch rather than with the blocks in the batch.
+/// Tables with delete operations keep one round per block as the closing block of a deleted
+/// row can't be read back from the table.
+async fn write_history_table_operations(
+    postgres: Option<&Arc<PostgresClient>>,
+    clickhouse: Option<&Arc<ClickhouseClient>>,
+    table_runtime: &TableRuntime,
+    batches: Vec<(&TableOperation, Option<String>, Vec<TableRowData>)>,
+) -> Result<(), String> {
+    let table = &table_runtime.table;
+    let batch_count = batches.len();
+    let mut operations: Vec<(&TableOperation, Option<String>)> = Vec::with_capacity(batch_count);
+    let mut rows_per_block: BTreeMap<(u64, String), Vec<(usize, TableRowData)>> = BTreeMap::new();
+
+    for (index, (operation, sql_condition, rows)) in batches.into_iter().enumerate() {
+        operations.push((operation, sql_condition));
+        for row in rows {
+            let block_number = extract_row_metadata(&row).map_or(0, |(block, _, _)| block);
+            rows_per_block
+                .entry((block_number, row.network.clone()))
+                .or_default()
+                .push((index, row));
+        }
+    }
+
+    let rounds = history_rounds(table, batch_count, &rows_per_block);
+
+    for round in rounds {
+        for ((operation, sql_condition), rows) in operations.iter().zip(&round.rows_per_operation) {
+            if rows.is_empty() {
+                continue;
+            }
+            write_table_operation(
+                postgres,
+                clickhouse,
+                table_runtime,
+                operation,
+                rows,
+                sql_condition.as_deref(),
+            )
+            .await?;
+        }
+
+        if let Some(postgres) = postgres {
+            let sql =
+                build_history_versions_sql(&table_runtime.full_table_name, table, &round.blocks);
+            postgres.batch_execute(&sql).await.map_err(|e| {
+                format!(
+                    "Failed to record history of {} for blocks {:?}: {}",
+                    table_runtime.full_table_name, round.blocks, e
+                )
+            })?;
+        }
     }
 
     Ok(())
 }
 
+/// Splits the rows of a batch into history rounds, the n-th block a row key is written at goes
+/// into the n-th round.
+fn history_rounds<'a>(
+    table: &Table,
+    operation_count: usize,
+    rows_per_block: &'a BTreeMap<(u64, String), Vec<(usize, TableRowData)>>,
+) -> Vec<HistoryRound<'a>> {
+    let round_per_block =
+        table.all_operations().any(|op| op.operation_type == OperationType::Delete);
+    let primary_key_columns = table.primary_key_columns();
+
+    let mut rounds: Vec<HistoryRound<'a>> = Vec::new();
+    let mut blocks_per_key: HashMap<String, usize> = HashMap::new();
+
+    for ((block_number, network), rows) in rows_per_block {
+        let first_round = rounds.len();
+        let mut block_keys: HashMap<String, usize> = HashMap::new();
+
+        for (index, row) in rows {
+            let round = if round_per_block {
+                first_round
+            } else {
+                let key = history_row_key(&primary_key_columns, row);
+                *block_keys
+                    .entry(key.clone())
+                    .or_insert_with(|| *blocks_per_key.get(&key).unwrap_or(&0))
+            };
+
+            while rounds.len() <= round {
+                rounds.push(HistoryRound {
+                    rows_per_operation: (0..operation_count).map(|_| Vec::new()).collect(),
+                    blocks: BTreeSet::new(),
+                });
+            }
+            rounds[round].rows_per_operation[*index].push(row.clone());
+            rounds[round].blocks.insert((network.as_str(), *block_number));
+        }
+
+        for key in block_keys.into_keys() {
+            *blocks_per_key.entry(key).or_default() += 1;
+        }
+    }
+
+    rounds
+}
+
+/// The primary key of `row` with its network, history tables can't be cross chain.
+fn history_row_key(primary_key_columns: &[&str], row: &TableRowData) -> String {
+    let mut key = row.network.clone();
+    for column in primary_key_columns {
+        key.push_str(&format!("|{:?}", row.columns.get(*column)));
+    }
+    key
+}
+
+/// Builds the statements recording the versions of the rows written at `blocks`, a version
+/// starts at the block its row was written at. A version written earlier in the same block is
+/// replaced, an older open version is closed at that block. Rows removed by delete operations
+/// get their version closed, tables with deletes record a single block at a time.
+fn build_history_versions_sql(
+    full_table_name: &str,
+    table: &Table,
+    blocks: &BTreeSet<(&str, u64)>,
+) -> String {
+    let history_table_name = generate_history_table_full_name(full_table_name);
+
+    let mut row_columns: Vec<String> = vec!["network".to_string()];
+    row_columns.extend(table.columns.iter().map(|c| c.name.clone()));
+    row_columns.push(injected_columns::BLOCK_NUMBER.to_string());
+    if table.timestamp {
+        row_columns.push(injected_columns::BLOCK_TIMESTAMP.to_string());
+    }
+    row_columns.extend(
+        [
+            injected_columns::TX_HASH,
+            injected_columns::BLOCK_HASH,
+            injected_columns::CONTRACT_ADDRESS,
+            injected_columns::RINDEXER_SEQUENCE_ID,
+        ]
+        .map(String::from),
+    );
+    let row_columns =
+        row_columns.iter().map(|c| format!("\"{}\"", c)).collect::<Vec<_>>().join(", ");
+
+    let key_match = std::iter::once("network")
+        .chain(table.primary_key_columns())
+        .map(|c| format!("h.\"{c}\" = t.\"{c}\""))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    let block_number = injected_columns::BLOCK_NUMBER;
+    let written_at_blocks = format!(
+        "(t.network, t.\"{}\") IN ({})",
+        block_number,
+        blocks
+            .iter()
+            .map(|(network, block)| format!("('{}', {})", network.replace('\'', "''"), block))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let valid_from = injected_columns::VALID_FROM;
+    let valid_to = injected_columns::VALID_TO;
+
+    let mut sql = format!(
+        "DELETE FROM {history_table_name} h USING {full_table_name} t \
+         WHERE {written_at_blocks} AND {key_match} AND h.\"{valid_from}\" = t.\"{block_number}\";\n\
+         UPDATE {history_table_name} h SET \"{valid_to}\" = t.\"{block_number}\" FROM {full_table_name} t \
+         WHERE {written_at_blocks} AND {key_match} AND h.\"{valid_to}\" IS NULL;\n\
+         INSERT INTO {history_table_name} ({row_columns}, \"{valid_from}\") \
+         SELECT {row_columns}, t.\"{block_number}\" FROM {full_table_name} t WHERE {written_at_blocks};"
+    );
+
+    if table.all_operations().any(|op| op.operation_type == OperationType::Delete) {
+        for (network, block) in blocks {
+            let network = network.replace('\'', "''");
+            sql.push_str(&format!(
+                "\nUPDATE {history_table_name} h SET \"{valid_to}\" = {block} \
+                 WHERE h.network = '{network}' AND h.\"{valid_to}\" IS NULL \
+                 AND NOT EXISTS (SELECT 1 FROM {full_table_name} t WHERE {key_match});"
+            ));
+        }
+    }
+
+    sql
+}
+
 /// Maps ColumnType to BatchOperationSqlType.
 fn column_type_to_batch_sql_type(column_type: &ColumnType) -> BatchOperationSqlType {
     match column_type {
@@ -3854,9 +5912,11 @@ fn column_type_to_batch_sql_type(column_type: &ColumnType) -> BatchOperationSqlT
         // 64-bit integers
         ColumnType::Uint64 | ColumnType::Int64 => BatchOperationSqlType::Bigint,
         // Large integers -> NUMERIC
-        ColumnType::Uint128 | ColumnType::Uint256 | ColumnType::Int128 | ColumnType::Int256 => {
-            BatchOperationSqlType::Numeric
-        }
+        ColumnType::Uint128
+        | ColumnType::Uint256
+        | ColumnType::Int128
+        | ColumnType::Int256
+        | ColumnType::Decimal(_) => BatchOperationSqlType::Numeric,
         // Bytes types
         ColumnType::Bytes | ColumnType::Bytes32 => BatchOperationSqlType::Bytea,
         ColumnType::String => BatchOperationSqlType::Text,
@@ -3867,6 +5927,37 @@ fn column_type_to_batch_sql_type(column_type: &ColumnType) -> BatchOperationSqlT
     }
 }
 
+/// SQL type the batch binds a column value as. Array actions bind a single element; address
+/// arrays are `TEXT[]` so their elements are bound as text.
+fn column_value_sql_type(
+    column_type: &ColumnType,
+    action: BatchOperationAction,
+) -> BatchOperationSqlType {
+    match action {
+        BatchOperationAction::Append
+        | BatchOperationAction::Remove
+        | BatchOperationAction::SetUnion => match column_type.element_type() {
+            ColumnType::Address => BatchOperationSqlType::Text,
+            element_type => column_type_to_batch_sql_type(element_type),
+        },
+        _ => column_type_to_batch_sql_type(column_type),
+    }
+}
+
+/// Action of a column rindexer maintains for another set column of `operation`: `avg`
+/// sample counters accumulate.
+fn generated_column_action(
+    operation: &TableOperation,
+    column_name: &str,
+) -> Option<BatchOperationAction> {
+    operation.set.iter().find_map(|s| match s.action {
+        SetAction::Avg if injected_columns::avg_samples(&s.column) == column_name => {
+            Some(BatchOperationAction::Add)
+        }
+        _ => None,
+    })
+}
+
 /// Maps SetAction to BatchOperationAction.
 fn set_action_to_batch_action(action: &SetAction) -> BatchOperationAction {
     match action {
@@ -3875,9 +5966,17 @@ fn set_action_to_batch_action(action: &SetAction) -> BatchOperationAction {
         SetAction::Subtract => BatchOperationAction::Subtract,
         SetAction::Max => BatchOperationAction::Max,
         SetAction::Min => BatchOperationAction::Min,
-        // Increment/Decrement are syntactic sugar for Add/Subtract with value "1"
-        SetAction::Increment => BatchOperationAction::Add,
+        // Increment/Decrement/Count are syntactic sugar for Add/Subtract with value "1"
+        SetAction::Increment | SetAction::Count => BatchOperationAction::Add,
         SetAction::Decrement => BatchOperationAction::Subtract,
+        SetAction::First | SetAction::Open => BatchOperationAction::First,
+        SetAction::Last | SetAction::Close => BatchOperationAction::Set,
+        SetAction::Avg => BatchOperationAction::Avg,
+        SetAction::Append => BatchOperationAction::Append,
+        SetAction::Remove => BatchOperationAction::Remove,
+        SetAction::SetUnion => BatchOperationAction::SetUnion,
+        // The total is counted in the distinct values side table before the write
+        SetAction::CountDistinct => BatchOperationAction::Set,
     }
 }
 
@@ -3909,6 +6008,8 @@ async fn execute_postgres_operation(
         return Ok(());
     }
 
+    ensure_block_range_partitions(postgres, table_name, table_def, rows).await?;
+
     // Build rows of DynamicColumnDefinition for the batch operation
     let mut batch_rows: Vec<Vec<DynamicColumnDefinition>> = Vec::with_capacity(rows.len());
 
@@ -3962,6 +6063,9 @@ async fn execute_postgres_operation(
                 BatchOperationAction::Where
             } else if let Some(set_col) = operation.set.iter().find(|s| s.column == column.name) {
                 set_action_to_batch_action(&set_col.action)
+            } else if let Some(action) = generated_column_action(operation, &column.name) {
+                // Sample counters and distinct values are written alongside their column
+                action
             } else {
                 BatchOperationAction::Nothing
             };
@@ -3969,7 +6073,7 @@ async fn execute_postgres_operation(
             columns.push(DynamicColumnDefinition::new(
                 column.name.clone(),
                 value,
-                column_type_to_batch_sql_type(column_type),
+                column_value_sql_type(column_type, action),
                 behavior,
                 action,
             ));
@@ -4124,6 +6228,8 @@ async fn execute_clickhouse_operation(
                 BatchOperationAction::Where
             } else if let Some(set_col) = operation.set.iter().find(|s| s.column == column.name) {
                 set_action_to_batch_action(&set_col.action)
+            } else if let Some(action) = generated_column_action(operation, &column.name) {
+                action
             } else {
                 BatchOperationAction::Nothing
             };
@@ -4131,7 +6237,7 @@ async fn execute_clickhouse_operation(
             columns.push(DynamicColumnDefinition::new(
                 column.name.clone(),
                 value,
-                column_type_to_batch_sql_type(column_type),
+                column_value_sql_type(column_type, action),
                 behavior,
                 action,
             ));
@@ -4234,6 +6340,101 @@ async fn execute_clickhouse_operation(
     Ok(())
 }
 
+/// Executes a ClickHouse operation for a bucket table by folding the rows into its
+/// `AggregatingMergeTree` rollup. Only the columns this operation writes are inserted,
+/// the aggregate states of the other columns stay empty and merge away.
+async fn execute_clickhouse_rollup_operation(
+    clickhouse: &ClickhouseClient,
+    table_name: &str,
+    table_def: &Table,
+    operation: &TableOperation,
+    rows: &[TableRowData],
+) -> Result<(), String> {
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    let mut columns: Vec<RollupColumn> = vec![RollupColumn {
+        name: "network".to_string(),
+        clickhouse_type: "String".to_string(),
+        aggregate: RollupAggregate::Key,
+    }];
+    let mut table_columns: Vec<&TableColumn> = Vec::new();
+
+    for column in &table_def.columns {
+        let aggregate = column_aggregate(table_def, &column.name);
+        let is_written = aggregate == RollupAggregate::Key
+            || operation.set.iter().any(|s| {
+                s.column == column.name
+                    || (s.action == SetAction::Avg
+                        && injected_columns::avg_samples(&s.column) == column.name)
+            });
+        if !is_written {
+            continue;
+        }
+
+        columns.push(RollupColumn {
+            name: column.name.clone(),
+            clickhouse_type: column.resolved_type().to_clickhouse_type(),
+            aggregate,
+        });
+        table_columns.push(column);
+    }
+
+    for (name, clickhouse_type, aggregate) in [
+        (injected_columns::BLOCK_NUMBER, "UInt64", RollupAggregate::Key),
+        (injected_columns::BLOCK_TIMESTAMP, "DateTime('UTC')", RollupAggregate::Latest),
+        (injected_columns::RINDEXER_SEQUENCE_ID, "UInt128", RollupAggregate::Latest),
+    ] {
+        columns.push(RollupColumn {
+            name: name.to_string(),
+            clickhouse_type: clickhouse_type.to_string(),
+            aggregate,
+        });
+    }
+
+    let batch_rows: Vec<Vec<EthereumSqlTypeWrapper>> = rows
+        .iter()
+        .map(|row| {
+            let mut values = vec![EthereumSqlTypeWrapper::String(row.network.clone())];
+            for column in &table_columns {
+                values.push(if let Some(v) = row.columns.get(&column.name) {
+                    v.clone()
+                } else if let Some(default) = &column.default {
+                    literal_to_wrapper(default, column.resolved_type())
+                } else {
+                    EthereumSqlTypeWrapper::Null
+                });
+            }
+            for name in [
+                injected_columns::BLOCK_NUMBER,
+                injected_columns::BLOCK_TIMESTAMP,
+                injected_columns::RINDEXER_SEQUENCE_ID,
+            ] {
+                values.push(row.columns.get(name).cloned().unwrap_or(EthereumSqlTypeWrapper::Null));
+            }
+            values
+        })
+        .collect();
+
+    let short_table_name = table_name.split('.').next_back().unwrap_or(table_name);
+    let event_name = format!("Tables::{}", short_table_name);
+
+    execute_rollup_batch_operation(
+        clickhouse,
+        table_name,
+        &columns,
+        injected_columns::RINDEXER_SEQUENCE_ID,
+        batch_rows,
+        &event_name,
+    )
+    .await?;
+
+    info!("Tables::{} - ROLLUP - {} rows", short_table_name, rows.len());
+
+    Ok(())
+}
+
 // =============================================================================
 // Public helper functions for cron scheduler
 // =============================================================================
@@ -4507,49 +6708,92 @@ fn extract_row_metadata(row: &TableRowData) -> Option<(u64, u64, u64)> {
     Some((block_number, tx_index, log_index))
 }
 
+/// Columns identifying a row of `operation` in journal where_keys: its where columns and the
+/// bucket column, sorted so the where_key string can be rebuilt from the row on reorg.
+fn journal_where_columns<'a>(table: &'a Table, operation: &'a TableOperation) -> Vec<&'a String> {
+    let mut where_keys: Vec<&String> = operation.where_clause.keys().collect();
+    if let Some(bucket) = &table.bucket {
+        where_keys.push(&bucket.column);
+    }
+    where_keys.sort();
+    where_keys
+}
+
+/// Where_key of `row` as `col1=val1,col2=val2`. Global tables have a single row per network
+/// and are keyed by the network name.
+fn journal_where_key(where_keys: &[&String], table: &Table, row: &TableRowData) -> Option<String> {
+    let mut where_clauses: Vec<String> = Vec::new();
+    for col in where_keys {
+        if let Some(v) = row.columns.get(col.as_str()) {
+            where_clauses.push(format!("{}={}", col, format_wrapper_for_where_key(v)));
+        }
+    }
+    if where_keys.is_empty() && table.global {
+        where_clauses.push(row.network.clone());
+    }
+    if where_clauses.is_empty() {
+        return None;
+    }
+    Some(where_clauses.join(","))
+}
+
 /// Build where_key string and collect journal value rows for non-reversible operations.
+/// Bucket tables and tables following other tables' changes journal every column: their
+/// values can't be reversed from the source event table, so reorgs recalculate them from the
+/// journal instead. Subtracted values are journaled negated so sums can be recalculated.
+/// Array actions journal their element as `value_text`, with a value of -1 for removals and
+/// 1 otherwise.
 /// Returns a list of SQL VALUES tuples ready to be joined into a batch INSERT.
 fn collect_journal_values(
     derived_table: &str,
+    table: &Table,
     operation: &TableOperation,
     rows: &[TableRowData],
     escape_quote: &str,
 ) -> Vec<String> {
-    let non_reversible: Vec<&SetColumn> =
-        operation.set.iter().filter(|sc| sc.action.reverse().is_none()).collect();
+    let non_reversible: Vec<&SetColumn> = operation
+        .set
+        .iter()
+        .filter(|sc| {
+            table.bucket.is_some() || !table.on_change.is_empty() || sc.action.reverse().is_none()
+        })
+        .collect();
 
     if non_reversible.is_empty() || rows.is_empty() {
         return vec![];
     }
 
-    let mut where_keys: Vec<&String> = operation.where_clause.keys().collect();
-    where_keys.sort(); // deterministic ordering for where_key string matching during recalculation
+    let where_keys = journal_where_columns(table, operation);
     let mut value_tuples: Vec<String> = Vec::new();
 
     for row in rows {
         let Some((block_number, tx_index, log_index)) = extract_row_metadata(row) else {
             continue;
         };
-
-        let mut where_clauses: Vec<String> = Vec::new();
-        for col in &where_keys {
-            if let Some(v) = row.columns.get(col.as_str()) {
-                where_clauses.push(format!("{}={}", col, format_wrapper_for_sql(v)));
-            }
-        }
-        if where_clauses.is_empty() {
+        let Some(where_key) = journal_where_key(&where_keys, table, row) else {
             continue;
-        }
-        let where_key_str = where_clauses.join(",").replace('\'', escape_quote);
+        };
+        let where_key_str = where_key.replace('\'', escape_quote);
 
         for sc in &non_reversible {
-            let value_str = match row.columns.get(sc.column.as_str()) {
-                Some(v) => format_wrapper_for_sql(v),
+            let (value_str, value_text) = match row.columns.get(sc.column.as_str()) {
+                Some(v) if sc.action == SetAction::Remove => ("-1".to_string(), Some(v)),
+                Some(v) if sc.action.is_array_action() => ("1".to_string(), Some(v)),
+                Some(v) if matches!(sc.action, SetAction::Subtract | SetAction::Decrement) => {
+                    (format!("-{}", format_wrapper_for_sql(v)), None)
+                }
+                Some(v) => (format_wrapper_for_sql(v), None),
                 None => continue,
             };
+            let value_text = match value_text {
+                Some(v) => {
+                    format!("'{}'", format_wrapper_for_where_key(v).replace('\'', escape_quote))
+                }
+                None => "NULL".to_string(),
+            };
 
             value_tuples.push(format!(
-                "('{derived_table}', '{network}', '{where_key}', '{column}', {value}, {block}, {tx_idx}, {log_idx})",
+                "('{derived_table}', '{network}', '{where_key}', '{column}', {value}, {block}, {tx_idx}, {log_idx}, {value_text})",
                 derived_table = derived_table,
                 network = row.network,
                 where_key = where_key_str,
@@ -4558,6 +6802,7 @@ fn collect_journal_values(
                 block = block_number,
                 tx_idx = tx_index,
                 log_idx = log_index,
+                value_text = value_text,
             ));
         }
     }
@@ -4570,17 +6815,18 @@ fn collect_journal_values(
 async fn journal_non_reversible_ops(
     postgres: &PostgresClient,
     derived_table: &str,
+    table: &Table,
     operation: &TableOperation,
     rows: &[TableRowData],
 ) {
-    let values = collect_journal_values(derived_table, operation, rows, "''");
+    let values = collect_journal_values(derived_table, table, operation, rows, "''");
     if values.is_empty() {
         return;
     }
 
     let sql = format!(
         "INSERT INTO rindexer_internal.derived_op_log \
-         (derived_table, network, where_key, column_name, value, block_number, tx_index, log_index) \
+         (derived_table, network, where_key, column_name, value, block_number, tx_index, log_index, value_text) \
          VALUES {}",
         values.join(", ")
     );
@@ -4598,17 +6844,18 @@ async fn journal_non_reversible_ops(
 async fn journal_non_reversible_ops_clickhouse(
     clickhouse: &ClickhouseClient,
     derived_table: &str,
+    table: &Table,
     operation: &TableOperation,
     rows: &[TableRowData],
 ) {
-    let values = collect_journal_values(derived_table, operation, rows, "\\'");
+    let values = collect_journal_values(derived_table, table, operation, rows, "\\'");
     if values.is_empty() {
         return;
     }
 
     let sql = format!(
         "INSERT INTO rindexer_internal.derived_op_log \
-         (derived_table, network, where_key, column_name, value, block_number, tx_index, log_index) \
+         (derived_table, network, where_key, column_name, value, block_number, tx_index, log_index, value_text) \
          VALUES {}",
         values.join(", ")
     );
@@ -4621,6 +6868,19 @@ async fn journal_non_reversible_ops_clickhouse(
     }
 }
 
+/// Format an EthereumSqlTypeWrapper the way the derived table column renders as text, so
+/// journal `where_key`s match `col::TEXT` during reorg recalculation. Timestamps (bucket
+/// columns) are rendered as unix seconds.
+fn format_wrapper_for_where_key(w: &EthereumSqlTypeWrapper) -> String {
+    match w {
+        EthereumSqlTypeWrapper::String(s) | EthereumSqlTypeWrapper::StringChar(s) => s.clone(),
+        EthereumSqlTypeWrapper::Address(a) => format!("{:#x}", a),
+        EthereumSqlTypeWrapper::Bytes(b) => format!("\\x{}", hex::encode(b)),
+        EthereumSqlTypeWrapper::DateTime(dt) => dt.timestamp().to_string(),
+        other => format_wrapper_for_sql(other),
+    }
+}
+
 /// Format an EthereumSqlTypeWrapper as a SQL literal for WHERE clauses.
 fn format_wrapper_for_sql(w: &EthereumSqlTypeWrapper) -> String {
     match w {
@@ -4634,12 +6894,15 @@ fn format_wrapper_for_sql(w: &EthereumSqlTypeWrapper) -> String {
         EthereumSqlTypeWrapper::U64BigInt(n) => format!("{}", n),
         EthereumSqlTypeWrapper::U128(n) => format!("{}", n),
         EthereumSqlTypeWrapper::U256(n) => format!("{}", n),
+        EthereumSqlTypeWrapper::U256Numeric(n) => format!("{}", n),
         EthereumSqlTypeWrapper::I8(n) => format!("{}", n),
         EthereumSqlTypeWrapper::I16(n) => format!("{}", n),
         EthereumSqlTypeWrapper::I32(n) => format!("{}", n),
         EthereumSqlTypeWrapper::I64(n) => format!("{}", n),
         EthereumSqlTypeWrapper::I128(n) => format!("{}", n),
         EthereumSqlTypeWrapper::I256(n) => format!("{}", n),
+        EthereumSqlTypeWrapper::I256Numeric(n) => format!("{}", n),
+        EthereumSqlTypeWrapper::DecimalNumeric(n, scale) => fixed_to_string(*n, *scale),
         EthereumSqlTypeWrapper::Bytes(b) => format!("'\\x{}'", hex::encode(b)),
         // Fallback: use Debug formatting (safe for SQL numerics)
         other => format!("'{:?}'", other),
@@ -4795,6 +7058,7 @@ mod tests {
         assert!(!is_arithmetic_expression("$fieldName"));
         assert!(!is_arithmetic_expression("TRADE"));
         assert!(!is_arithmetic_expression("$if($x == 0, a, b)"));
+        assert!(is_arithmetic_expression("$scale($amount, 6)"));
     }
 
     #[test]
@@ -5176,6 +7440,10 @@ mod tests {
         }
 
         let constants = Constants::default();
+        let lookup_tables = HashMap::new();
+        let lookup_rows = LookupRows::new();
+        let lookup_writes = LookupWrites::new();
+        let token_metadata = TokenMetadataMap::new();
         let result = extract_value_from_event_async(
             "$rindexer_block_timestamp",
             &params,
@@ -5184,6 +7452,12 @@ mod tests {
             None,
             "polygon",
             &constants,
+            Lookups {
+                tables: &lookup_tables,
+                rows: &lookup_rows,
+                writes: &lookup_writes,
+                tokens: &token_metadata,
+            },
         )
         .await;
 
@@ -5223,6 +7497,10 @@ mod tests {
         }
 
         let constants = Constants::default();
+        let lookup_tables = HashMap::new();
+        let lookup_rows = LookupRows::new();
+        let lookup_writes = LookupWrites::new();
+        let token_metadata = TokenMetadataMap::new();
         let result = extract_value_from_event_async(
             "$rindexer_block_timestamp",
             &params,
@@ -5231,6 +7509,12 @@ mod tests {
             None,
             "polygon",
             &constants,
+            Lookups {
+                tables: &lookup_tables,
+                rows: &lookup_rows,
+                writes: &lookup_writes,
+                tokens: &token_metadata,
+            },
         )
         .await;
 
@@ -5265,6 +7549,10 @@ mod tests {
         };
 
         let constants = Constants::default();
+        let lookup_tables = HashMap::new();
+        let lookup_rows = LookupRows::new();
+        let lookup_writes = LookupWrites::new();
+        let token_metadata = TokenMetadataMap::new();
         let result = extract_value_from_event_async(
             "$rindexer_block_timestamp",
             &params,
@@ -5273,12 +7561,221 @@ mod tests {
             None,
             "polygon",
             &constants,
+            Lookups {
+                tables: &lookup_tables,
+                rows: &lookup_rows,
+                writes: &lookup_writes,
+                tokens: &token_metadata,
+            },
         )
         .await;
 
         assert!(result.is_none(), "Should return None when both metadata and cache miss");
     }
 
+    #[tokio::test]
+    async fn test_extract_value_resolves_lookups() {
+        let token = Address::with_last_byte(1);
+        let params = vec![
+            LogParam::new("token".to_string(), DynSolValue::Address(token)),
+            LogParam::new("amount".to_string(), DynSolValue::Uint(U256::from(5_000_000u64), 256)),
+        ];
+        let meta = TxMetadata {
+            block_number: 1,
+            block_timestamp: None,
+            tx_hash: B256::ZERO,
+            block_hash: B256::ZERO,
+            contract_address: Address::ZERO,
+            log_index: U256::from(0u64),
+            tx_index: 0,
+        };
+
+        let lookup_tables = HashMap::from([(
+            "tokens".to_string(),
+            LookupTable {
+                full_table_name: "indexer_registry.tokens".to_string(),
+                key_column: "token".to_string(),
+                key_type: ColumnType::Address,
+                cross_chain: false,
+            },
+        )]);
+        let lookup_rows = LookupRows::from([(
+            (
+                "indexer_registry.tokens".to_string(),
+                "ethereum".to_string(),
+                format!("{:#x}", token),
+            ),
+            HashMap::from([
+                ("decimals".to_string(), Some("6".to_string())),
+                ("symbol".to_string(), Some("USDC".to_string())),
+            ]),
+        )]);
+        let lookup_writes = LookupWrites::new();
+        let token_metadata = TokenMetadataMap::new();
+        let lookups = Lookups {
+            tables: &lookup_tables,
+            rows: &lookup_rows,
+            writes: &lookup_writes,
+            tokens: &token_metadata,
+        };
+        let constants = Constants::default();
+
+        let decimals = extract_value_from_event_async(
+            "$lookup(tokens, $token).decimals",
+            &params,
+            &meta,
+            &ColumnType::Uint8,
+            None,
+            "ethereum",
+            &constants,
+            lookups,
+        )
+        .await;
+        assert!(matches!(decimals, Some(EthereumSqlTypeWrapper::U8(6))));
+
+        let normalized = extract_value_from_event_async(
+            "$amount / 10 ^ $lookup(tokens, $token).decimals",
+            &params,
+            &meta,
+            &ColumnType::Uint256,
+            None,
+            "ethereum",
+            &constants,
+            lookups,
+        )
+        .await;
+        assert!(
+            matches!(normalized, Some(EthereumSqlTypeWrapper::U256Numeric(v)) if v == U256::from(5))
+        );
+
+        // Rows are scoped to the network of the event
+        let other_network = extract_value_from_event_async(
+            "$lookup(tokens, $token).decimals",
+            &params,
+            &meta,
+            &ColumnType::Uint8,
+            None,
+            "base",
+            &constants,
+            lookups,
+        )
+        .await;
+        assert!(other_network.is_none());
+
+        assert_eq!(
+            resolve_lookups_in_condition(
+                "$lookup(tokens, $token).symbol == 'USDC' && $lookup(tokens, $token).decimals > 2",
+                lookups,
+                &params,
+                &meta,
+                "ethereum",
+                &constants,
+            ),
+            Some("'USDC' == 'USDC' && 6 > 2".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_extract_value_resolves_tokens() {
+        let token = Address::with_last_byte(2);
+        let params = vec![
+            LogParam::new("token".to_string(), DynSolValue::Address(token)),
+            LogParam::new("amount".to_string(), DynSolValue::Uint(U256::from(5_000_000u64), 256)),
+        ];
+        let meta = TxMetadata {
+            block_number: 1,
+            block_timestamp: None,
+            tx_hash: B256::ZERO,
+            block_hash: B256::ZERO,
+            contract_address: Address::ZERO,
+            log_index: U256::from(0u64),
+            tx_index: 0,
+        };
+
+        let lookup_tables = HashMap::new();
+        let lookup_rows = LookupRows::new();
+        let lookup_writes = LookupWrites::new();
+        let token_metadata = TokenMetadataMap::from([(
+            ("ethereum".to_string(), token),
+            TokenMetadata { decimals: Some(6), symbol: Some("USDC".to_string()), name: None },
+        )]);
+        let lookups = Lookups {
+            tables: &lookup_tables,
+            rows: &lookup_rows,
+            writes: &lookup_writes,
+            tokens: &token_metadata,
+        };
+        let constants = Constants::default();
+
+        let decimals = extract_value_from_event_async(
+            "$token($token).decimals",
+            &params,
+            &meta,
+            &ColumnType::Uint8,
+            None,
+            "ethereum",
+            &constants,
+            lookups,
+        )
+        .await;
+        assert!(matches!(decimals, Some(EthereumSqlTypeWrapper::U8(6))));
+
+        let normalized = extract_value_from_event_async(
+            "$amount / 10 ^ $token($token).decimals",
+            &params,
+            &meta,
+            &ColumnType::Uint256,
+            None,
+            "ethereum",
+            &constants,
+            lookups,
+        )
+        .await;
+        assert!(
+            matches!(normalized, Some(EthereumSqlTypeWrapper::U256Numeric(v)) if v == U256::from(5))
+        );
+
+        // Fields the token doesn't implement are NULL
+        let name = extract_value_from_event_async(
+            "$token($token).name",
+            &params,
+            &meta,
+            &ColumnType::String,
+            None,
+            "ethereum",
+            &constants,
+            lookups,
+        )
+        .await;
+        assert!(matches!(name, Some(EthereumSqlTypeWrapper::Null)));
+
+        // Metadata is scoped to the network of the event
+        let other_network = extract_value_from_event_async(
+            "$token($token).decimals",
+            &params,
+            &meta,
+            &ColumnType::Uint8,
+            None,
+            "base",
+            &constants,
+            lookups,
+        )
+        .await;
+        assert!(other_network.is_none());
+
+        assert_eq!(
+            resolve_lookups_in_condition(
+                "$token($token).symbol == 'USDC' && $amount > 0",
+                lookups,
+                &params,
+                &meta,
+                "ethereum",
+                &constants,
+            ),
+            Some("'USDC' == 'USDC' && $amount > 0".to_string())
+        );
+    }
+
     /// When metadata IS present, the async path should use it directly (not touch cache).
     #[tokio::test]
     async fn test_block_timestamp_async_prefers_metadata_over_cache() {
@@ -5301,6 +7798,10 @@ mod tests {
         }
 
         let constants = Constants::default();
+        let lookup_tables = HashMap::new();
+        let lookup_rows = LookupRows::new();
+        let lookup_writes = LookupWrites::new();
+        let token_metadata = TokenMetadataMap::new();
         let result = extract_value_from_event_async(
             "$rindexer_block_timestamp",
             &params,
@@ -5309,6 +7810,12 @@ mod tests {
             None,
             "polygon",
             &constants,
+            Lookups {
+                tables: &lookup_tables,
+                rows: &lookup_rows,
+                writes: &lookup_writes,
+                tokens: &token_metadata,
+            },
         )
         .await;
 
@@ -5751,4 +8258,450 @@ mod tests {
         );
         assert_eq!(result, Some("blk=42,log=3".to_string()));
     }
+
+    fn history_balances_table() -> Table {
+        serde_yaml::from_str(
+            r#"
+            name: balances
+            history: true
+            columns:
+              - name: holder
+                type: address
+              - name: balance
+                type: uint256
+            events:
+              - event: Transfer
+                operations:
+                  - type: upsert
+                    where:
+                      holder: $to
+                    set:
+                      - column: balance
+                        action: add
+                        value: $value
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn history_row(holder: u8, block_number: u64) -> TableRowData {
+        TableRowData {
+            columns: HashMap::from([
+                (
+                    "holder".to_string(),
+                    EthereumSqlTypeWrapper::Address(Address::repeat_byte(holder)),
+                ),
+                (
+                    injected_columns::BLOCK_NUMBER.to_string(),
+                    EthereumSqlTypeWrapper::U64BigInt(block_number),
+                ),
+            ]),
+            network: "ethereum".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_build_history_versions_sql() {
+        let table = history_balances_table();
+        let blocks = BTreeSet::from([("ethereum", 42), ("ethereum", 43)]);
+
+        let sql = build_history_versions_sql("idx_token.balances", &table, &blocks);
+
+        assert!(sql.contains("DELETE FROM idx_token.balances_history h USING idx_token.balances t"));
+        assert!(sql.contains("h.\"network\" = t.\"network\" AND h.\"holder\" = t.\"holder\""));
+        assert!(sql.contains("SET \"rindexer_valid_to\" = t.\"rindexer_block_number\""));
+        assert!(sql.contains(
+            "(t.network, t.\"rindexer_block_number\") IN (('ethereum', 42), ('ethereum', 43))"
+        ));
+        assert!(sql.contains("\"balance\", \"rindexer_block_number\""));
+        // No delete operations, so no rows can disappear
+        assert!(!sql.contains("NOT EXISTS"));
+    }
+
+    #[test]
+    fn test_history_rounds_take_one_block_per_row_key() {
+        let table = history_balances_table();
+        let mut rows_per_block: BTreeMap<(u64, String), Vec<(usize, TableRowData)>> =
+            BTreeMap::new();
+        for (holder, block) in [(1, 100), (2, 100), (1, 100), (2, 101), (3, 102), (1, 103)] {
+            rows_per_block
+                .entry((block, "ethereum".to_string()))
+                .or_default()
+                .push((0, history_row(holder, block)));
+        }
+
+        let rounds = history_rounds(&table, 1, &rows_per_block);
+
+        // holder 1 is written at blocks 100 and 103, holder 2 at 100 and 101
+        assert_eq!(rounds.len(), 2);
+        assert_eq!(rounds[0].blocks, BTreeSet::from([("ethereum", 100), ("ethereum", 102)]));
+        assert_eq!(rounds[0].rows_per_operation[0].len(), 4);
+        assert_eq!(rounds[1].blocks, BTreeSet::from([("ethereum", 101), ("ethereum", 103)]));
+        assert_eq!(rounds[1].rows_per_operation[0].len(), 2);
+    }
+
+    fn distinct_traders_table() -> Table {
+        serde_yaml::from_str(
+            r#"
+            name: pools
+            columns:
+              - name: pool
+                type: address
+              - name: unique_traders
+                type: uint64
+            events:
+              - event: Swap
+                operations:
+                  - type: upsert
+                    where:
+                      pool: $pool
+                    set:
+                      - column: unique_traders
+                        action: count_distinct
+                        value: $sender
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn distinct_row(pool: u8, trader: &str, block_number: u64) -> TableRowData {
+        TableRowData {
+            columns: HashMap::from([
+                ("pool".to_string(), EthereumSqlTypeWrapper::Address(Address::repeat_byte(pool))),
+                (
+                    injected_columns::count_distinct_values("unique_traders"),
+                    EthereumSqlTypeWrapper::String(trader.to_string()),
+                ),
+                (
+                    injected_columns::BLOCK_NUMBER.to_string(),
+                    EthereumSqlTypeWrapper::U64BigInt(block_number),
+                ),
+            ]),
+            network: "ethereum".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_distinct_values_are_counted_per_row_key() {
+        let table = distinct_traders_table();
+        let operation = table.all_operations().next().unwrap();
+        let rows = vec![distinct_row(1, "0xa", 10), distinct_row(1, "0xb", 11)];
+        let pool_key = format!("pool={:#x}", Address::repeat_byte(1));
+
+        let values = collect_distinct_values(&table, operation, &rows);
+
+        assert_eq!(
+            values,
+            vec![
+                DistinctValue {
+                    network: "ethereum".to_string(),
+                    where_key: pool_key.clone(),
+                    column: "unique_traders".to_string(),
+                    value: "0xa".to_string(),
+                    block_number: 10,
+                },
+                DistinctValue {
+                    network: "ethereum".to_string(),
+                    where_key: pool_key.clone(),
+                    column: "unique_traders".to_string(),
+                    value: "0xb".to_string(),
+                    block_number: 11,
+                },
+            ]
+        );
+
+        // Every row of the key gets the total stored in the side table
+        let totals =
+            HashMap::from([(("ethereum".to_string(), pool_key, "unique_traders".to_string()), 7)]);
+        let counted = with_distinct_counts(&table, operation, &rows, &totals);
+        for row in &counted {
+            assert!(matches!(
+                row.columns.get("unique_traders"),
+                Some(EthereumSqlTypeWrapper::U64BigInt(7))
+            ));
+        }
+    }
+
+    const CHANGE_TABLES: &str = r#"
+        - name: holder_count
+          global: true
+          columns:
+            - name: holders
+              type: uint256
+          on_change:
+            - table: balances
+              operations:
+                - type: upsert
+                  if: "$before_balance == 0 && $after_balance > 0"
+                  set:
+                    - column: holders
+                      action: increment
+        - name: balances
+          columns:
+            - name: holder
+              type: address
+            - name: balance
+              type: uint256
+          events:
+            - event: Transfer
+              operations:
+                - type: upsert
+                  where:
+                    holder: $to
+                  set:
+                    - column: balance
+                      action: add
+                      value: $value
+        "#;
+
+    #[test]
+    fn test_order_by_table_dependencies() {
+        let tables: Vec<Table> = serde_yaml::from_str(CHANGE_TABLES).unwrap();
+        let runtimes: Vec<TableRuntime> =
+            tables.into_iter().map(|table| TableRuntime::new(table, "idx", "Token")).collect();
+
+        let ordered: Vec<&str> =
+            order_by_table_dependencies(&runtimes).iter().map(|t| t.table.name.as_str()).collect();
+        assert_eq!(ordered, vec!["balances", "holder_count"]);
+    }
+
+    const LOOKUP_TABLES: &str = r#"
+        - name: swaps
+          columns:
+            - name: pool
+              type: address
+            - name: amount
+              type: uint256
+          events:
+            - event: Swap
+              operations:
+                - type: upsert
+                  where:
+                    pool: $pool
+                  set:
+                    - column: amount
+                      action: set
+                      value: $amount / 10 ^ $lookup(tokens, $token).decimals
+        - name: tokens
+          columns:
+            - name: token
+              type: address
+            - name: decimals
+              type: uint8
+          events:
+            - event: Swap
+              operations:
+                - type: upsert
+                  where:
+                    token: $token
+                  set:
+                    - column: decimals
+                      action: set
+                      value: $decimals
+        "#;
+
+    #[test]
+    fn test_lookups_read_writes_of_the_same_batch() {
+        let tables: Vec<Table> = serde_yaml::from_str(LOOKUP_TABLES).unwrap();
+        let tokens = TableRuntime::new(tables[1].clone(), "idx", "Dex");
+        let tokens_lookup = LookupTable {
+            full_table_name: tokens.full_table_name.clone(),
+            key_column: "token".to_string(),
+            key_type: ColumnType::Address,
+            cross_chain: false,
+        };
+        let swaps = TableRuntime::new(tables[0].clone(), "idx", "Dex")
+            .with_lookup_tables(HashMap::from([("tokens".to_string(), tokens_lookup.clone())]));
+        let runtimes = vec![swaps, tokens];
+
+        // The looked up table is written first although it is declared last
+        let ordered: Vec<&str> =
+            order_by_table_dependencies(&runtimes).iter().map(|t| t.table.name.as_str()).collect();
+        assert_eq!(ordered, vec!["tokens", "swaps"]);
+
+        let token = Address::with_last_byte(1);
+        let lookup_rows = LookupRows::from([(
+            (
+                tokens_lookup.full_table_name.clone(),
+                "ethereum".to_string(),
+                format!("{:#x}", token),
+            ),
+            HashMap::from([("decimals".to_string(), Some("6".to_string()))]),
+        )]);
+        let token_metadata = TokenMetadataMap::new();
+        let no_writes = LookupWrites::new();
+        let before_batch = Lookups {
+            tables: &runtimes[0].lookup_tables,
+            rows: &lookup_rows,
+            writes: &no_writes,
+            tokens: &token_metadata,
+        };
+
+        let tx_at = |block_number: u64| TxMetadata {
+            block_number,
+            block_timestamp: None,
+            tx_hash: B256::ZERO,
+            block_hash: B256::ZERO,
+            contract_address: Address::ZERO,
+            log_index: U256::from(0u64),
+            tx_index: 0,
+        };
+
+        // The batch sets the decimals of the token at block 5
+        let token_row = TableRowData {
+            columns: HashMap::from([
+                ("token".to_string(), EthereumSqlTypeWrapper::Address(token)),
+                ("decimals".to_string(), EthereumSqlTypeWrapper::U8(18)),
+            ]),
+            network: "ethereum".to_string(),
+        };
+        let mut writes = LookupWrites::new();
+        collect_lookup_writes(
+            &tokens_lookup,
+            runtimes[1].table.all_operations().next().unwrap(),
+            None,
+            &[token_row],
+            &[&tx_at(5)],
+            before_batch,
+            &mut writes,
+        );
+        let lookups = Lookups { writes: &writes, ..before_batch };
+
+        let params = vec![LogParam::new("token".to_string(), DynSolValue::Address(token))];
+        let constants = Constants::default();
+        let decimals_at = |block_number: u64| {
+            resolve_lookup(
+                &parse_lookup("$lookup(tokens, $token).decimals").unwrap(),
+                lookups,
+                &params,
+                &tx_at(block_number),
+                "ethereum",
+                &constants,
+            )
+        };
+
+        // Swaps before the write read the prefetched row, later ones the written value
+        assert_eq!(decimals_at(4), Some(Some("6".to_string())));
+        assert_eq!(decimals_at(5), Some(Some("18".to_string())));
+        assert_eq!(decimals_at(6), Some(Some("18".to_string())));
+    }
+
+    #[test]
+    fn test_change_log_params() {
+        let tables: Vec<Table> = serde_yaml::from_str(CHANGE_TABLES).unwrap();
+        let balances = &tables[1];
+        let holder = format!("{:#x}", Address::with_last_byte(1));
+        let after: ChangeRowValues = HashMap::from([
+            ("holder".to_string(), Some(holder.clone())),
+            ("balance".to_string(), Some("5".to_string())),
+        ]);
+
+        // The key of a row that didn't exist yet is still known
+        let params = change_log_params(
+            balances,
+            &["holder"],
+            std::slice::from_ref(&holder),
+            "insert",
+            None,
+            Some(&after),
+        );
+        let value = |name: &str| params.iter().find(|p| p.name == name).unwrap().value.clone();
+        assert_eq!(value("change"), DynSolValue::String("insert".to_string()));
+        assert_eq!(value("before_holder"), DynSolValue::Address(Address::with_last_byte(1)));
+        assert_eq!(value("before_balance"), DynSolValue::Uint(U256::ZERO, 256));
+        assert_eq!(value("after_balance"), DynSolValue::Uint(U256::from(5u64), 256));
+
+        assert!(evaluate_filter("$before_balance == 0 && $after_balance > 0", &params, None));
+        assert!(evaluate_filter("$change == 'insert'", &params, None));
+    }
+
+    #[test]
+    fn test_change_rows_query_postgres() {
+        let tables: Vec<Table> = serde_yaml::from_str(CHANGE_TABLES).unwrap();
+        let runtime = TableRuntime::new(tables[1].clone(), "idx", "Token");
+        let tracker = ChangeTracker::new(&runtime);
+        let holder = format!("{:#x}", Address::with_last_byte(1));
+        let tracked = TrackedRow {
+            network: "ethereum".to_string(),
+            key: ("ethereum".to_string(), vec![holder.clone()]),
+            key_literals: vec![format!("'{}'", holder)],
+            tx_metadata: TxMetadata {
+                block_number: 1,
+                block_timestamp: None,
+                tx_hash: B256::ZERO,
+                block_hash: B256::ZERO,
+                contract_address: Address::ZERO,
+                log_index: U256::from(0u64),
+                tx_index: 0,
+            },
+            before: None,
+        };
+
+        let query = tracker.rows_query_postgres("ethereum", &[&tracked]);
+        assert_eq!(
+            query,
+            format!(
+                "SELECT \"holder\"::TEXT, \"holder\"::TEXT, \"balance\"::TEXT FROM \
+                 idx_token.balances WHERE network = 'ethereum' AND (\"holder\") IN (('{}'))",
+                holder
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn incomplete_token_metadata_is_not_cached() {
+        use crate::provider::mock::MockChainProvider;
+
+        let network = "incomplete-token-metadata".to_string();
+        let token = Address::repeat_byte(0x7e);
+        let encode = |value: DynSolValue| format!("0x{}", hex::encode(value.abi_encode()));
+        let provider = MockChainProvider::new(1)
+            .with_call_result(
+                token,
+                token_field_calldata(TokenField::Decimals),
+                &encode(DynSolValue::Uint(U256::from(6), 8)),
+            )
+            .with_call_result(
+                token,
+                token_field_calldata(TokenField::Symbol),
+                &encode(DynSolValue::String("USDC".to_string())),
+            );
+        let providers: HashMap<String, Arc<dyn ChainProvider>> =
+            HashMap::from([(network.clone(), Arc::new(provider) as Arc<dyn ChainProvider>)]);
+        let tokens = std::collections::HashSet::from([(network.clone(), token)]);
+
+        // `name()` failed, what was read is used but not kept
+        let resolved =
+            resolve_token_metadata(tokens, &providers, &HashMap::new(), None, None).await;
+        let metadata = &resolved[&(network.clone(), token)];
+        assert_eq!(metadata.decimals, Some(6));
+        assert_eq!(metadata.symbol.as_deref(), Some("USDC"));
+        assert_eq!(metadata.name, None);
+        assert!(!TOKEN_METADATA_CACHE.read().await.contains_key(&(network, token)));
+    }
+
+    #[test]
+    fn decimal_values_over_the_column_precision_are_null() {
+        use std::str::FromStr;
+
+        let max = I256::from_str(&"9".repeat(76)).unwrap();
+        assert!(matches!(
+            decimal_to_wrapper(max, 0, 0),
+            EthereumSqlTypeWrapper::DecimalNumeric(value, 0) if value == max
+        ));
+        assert!(matches!(decimal_to_wrapper(max + I256::ONE, 0, 0), EthereumSqlTypeWrapper::Null));
+        // 70 integer digits leave room for 6 fractional ones only
+        assert!(matches!(
+            decimal_to_wrapper(I256::from_str(&"9".repeat(71)).unwrap(), 0, 6),
+            EthereumSqlTypeWrapper::Null
+        ));
+        assert!(matches!(
+            dyn_sol_value_to_wrapper(&DynSolValue::Uint(U256::MAX, 256), &ColumnType::Decimal(0)),
+            EthereumSqlTypeWrapper::Null
+        ));
+        assert!(matches!(
+            literal_to_wrapper(&"1".repeat(77), &ColumnType::Decimal(0)),
+            EthereumSqlTypeWrapper::Null
+        ));
+    }
 }
@@ -0,0 +1,192 @@
+//! Token metadata read by `$token(address).decimals|symbol|name` references.
+//!
+//! Metadata never changes once a token is deployed, so it is read once per token through the
+//! static Multicall3 path and kept both in memory and in `rindexer_internal.token_metadata`, which
+//! is shared by every contract of the indexer. Tokens missing a field are read again every batch
+//! instead of caching what may have been a failed call.
+use std::collections::HashMap;
+
+use alloy::primitives::Address;
+use anyhow::Context;
+use clickhouse::Row;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use tokio::sync::RwLock;
+
+use crate::database::clickhouse::client::ClickhouseClient;
+use crate::database::postgres::client::PostgresClient;
+use crate::event::TokenField;
+use crate::indexer::reorg::validate_sql_value;
+
+/// Metadata of an ERC20 token. Fields are None when the token doesn't implement them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TokenMetadata {
+    pub decimals: Option<u8>,
+    pub symbol: Option<String>,
+    pub name: Option<String>,
+}
+
+impl TokenMetadata {
+    /// Returns a field as text.
+    pub fn get(&self, field: TokenField) -> Option<String> {
+        match field {
+            TokenField::Decimals => self.decimals.map(|d| d.to_string()),
+            TokenField::Symbol => self.symbol.clone(),
+            TokenField::Name => self.name.clone(),
+        }
+    }
+
+    /// Whether no field could be read, usually meaning the address is not a token or the
+    /// calls failed.
+    pub fn is_empty(&self) -> bool {
+        self.decimals.is_none() && self.symbol.is_none() && self.name.is_none()
+    }
+
+    /// Whether every field was read. Only complete metadata is cached, a missing field may be
+    /// a call that failed and is read again with the next batch.
+    pub fn is_complete(&self) -> bool {
+        self.decimals.is_some() && self.symbol.is_some() && self.name.is_some()
+    }
+}
+
+/// Token metadata keyed by (network, token address).
+pub type TokenMetadataMap = HashMap<(String, Address), TokenMetadata>;
+
+/// Metadata already read by this process.
+pub(crate) static TOKEN_METADATA_CACHE: Lazy<RwLock<TokenMetadataMap>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Loads the stored metadata of `addresses` on a network. Priority: postgres > clickhouse.
+pub async fn load_token_metadata(
+    postgres: Option<&PostgresClient>,
+    clickhouse: Option<&ClickhouseClient>,
+    network: &str,
+    addresses: &[Address],
+) -> anyhow::Result<Vec<(Address, TokenMetadata)>> {
+    validate_sql_value(network, "network")?;
+    if addresses.is_empty() {
+        return Ok(vec![]);
+    }
+    let addresses: Vec<String> = addresses.iter().map(|a| format!("{:?}", a)).collect();
+
+    if let Some(postgres) = postgres {
+        let query = r#"
+            SELECT address, decimals, symbol, name
+            FROM rindexer_internal.token_metadata
+            WHERE network = $1 AND address = ANY($2)"#;
+
+        let rows = postgres
+            .query(query, &[&network, &addresses])
+            .await
+            .context("Failed to load token_metadata from postgres")?;
+
+        return Ok(rows
+            .iter()
+            .filter_map(|row| {
+                let address: String = row.get("address");
+                let decimals: Option<i16> = row.get("decimals");
+                let metadata = TokenMetadata {
+                    decimals: decimals.and_then(|d| u8::try_from(d).ok()),
+                    symbol: row.get("symbol"),
+                    name: row.get("name"),
+                };
+                Some((address.parse().ok()?, metadata))
+            })
+            .collect());
+    }
+
+    if let Some(clickhouse) = clickhouse {
+        #[derive(Row, Deserialize)]
+        struct TokenMetadataRow {
+            address: String,
+            decimals: Option<u8>,
+            symbol: Option<String>,
+            name: Option<String>,
+        }
+
+        let addresses = addresses.iter().map(|a| format!("'{}'", a)).collect::<Vec<_>>().join(", ");
+        let query = format!(
+            r#"SELECT address, decimals, symbol, name
+             FROM rindexer_internal.token_metadata FINAL
+             WHERE network = '{}' AND address IN ({})"#,
+            network, addresses
+        );
+
+        let rows = clickhouse
+            .query_all::<TokenMetadataRow>(&query)
+            .await
+            .context("Failed to load token_metadata from clickhouse")?;
+
+        return Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                let metadata =
+                    TokenMetadata { decimals: row.decimals, symbol: row.symbol, name: row.name };
+                Some((row.address.parse().ok()?, metadata))
+            })
+            .collect());
+    }
+
+    Ok(vec![])
+}
+
+/// Stores the metadata of tokens on a network in every configured database.
+pub async fn store_token_metadata(
+    postgres: Option<&PostgresClient>,
+    clickhouse: Option<&ClickhouseClient>,
+    network: &str,
+    tokens: &[(Address, TokenMetadata)],
+) -> anyhow::Result<()> {
+    validate_sql_value(network, "network")?;
+    if tokens.is_empty() {
+        return Ok(());
+    }
+
+    if let Some(postgres) = postgres {
+        let query = r#"INSERT INTO rindexer_internal.token_metadata
+                     (network, address, decimals, symbol, name)
+                     VALUES ($1, $2, $3, $4, $5)
+                     ON CONFLICT (network, address)
+                     DO UPDATE SET decimals = EXCLUDED.decimals, symbol = EXCLUDED.symbol,
+                                   name = EXCLUDED.name"#;
+
+        for (address, metadata) in tokens {
+            let address = format!("{:?}", address);
+            let decimals = metadata.decimals.map(i16::from);
+            postgres
+                .execute(query, &[&network, &address, &decimals, &metadata.symbol, &metadata.name])
+                .await
+                .with_context(|| format!("Failed to store token metadata of {}", address))?;
+        }
+    }
+
+    if let Some(clickhouse) = clickhouse {
+        let nullable_string = |value: &Option<String>| match value {
+            Some(v) => format!("'{}'", v.replace('\\', "\\\\").replace('\'', "\\'")),
+            None => "NULL".to_string(),
+        };
+        let values = tokens
+            .iter()
+            .map(|(address, metadata)| {
+                format!(
+                    "('{}', '{:?}', {}, {}, {})",
+                    network,
+                    address,
+                    metadata.decimals.map_or("NULL".to_string(), |d| d.to_string()),
+                    nullable_string(&metadata.symbol),
+                    nullable_string(&metadata.name)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let query = format!(
+            "INSERT INTO rindexer_internal.token_metadata \
+             (network, address, decimals, symbol, name) VALUES {}",
+            values
+        );
+
+        clickhouse.execute(&query).await.context("Failed to store token metadata")?;
+    }
+
+    Ok(())
+}
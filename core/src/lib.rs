@@ -1,4 +1,5 @@
 // public
+pub mod adaptive_block_range;
 pub mod adaptive_concurrency;
 pub mod generator;
 pub mod hot_reload;
@@ -7,6 +8,7 @@ pub mod indexer;
 pub mod layer_extensions;
 pub mod manifest;
 pub mod metrics;
+pub mod new_heads;
 pub mod reth;
 
 mod system_state;
@@ -56,12 +58,16 @@ mod chat;
 pub mod event;
 pub mod notifications;
 pub use indexer::reorg::ReorgEvent;
-pub use notifications::ChainStateNotification;
+pub use notifications::{ChainStateNotification, ChainStateSource};
 pub mod blockclock;
 pub mod phantom;
 pub mod provider;
+pub mod proxy;
+pub mod rpc_cache;
 mod start;
 mod streams;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub use streams::StreamsClients;
 mod types;
 
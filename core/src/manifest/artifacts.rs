@@ -0,0 +1,619 @@
+//! Read contracts straight out of Foundry and Hardhat build artifacts.
+//!
+//! This powers `rindexer add from-artifacts`, it resolves ABIs, deployed addresses and deployment
+//! blocks from files already on disk so no explorer or RPC calls are needed:
+//!
+//! - Foundry: ABIs from `out/<Source>.sol/<Contract>.json` and deployments from
+//!   `broadcast/<Script>.s.sol/<chain_id>/run-latest.json`.
+//! - Hardhat (hardhat-deploy): `deployments/<network>/<Contract>.json` which hold the ABI, address
+//!   and receipt together, the chain id is read from `deployments/<network>/.chainId`.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use alloy::{
+    primitives::{Address, U64},
+    rpc::types::ValueOrArray,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+
+use crate::manifest::contract::ContractDetails;
+
+const FOUNDRY_CONFIG_FILE: &str = "foundry.toml";
+const FOUNDRY_DEFAULT_OUT_DIR: &str = "out";
+const FOUNDRY_BROADCAST_DIR: &str = "broadcast";
+const FOUNDRY_LATEST_RUN_FILE: &str = "run-latest.json";
+const HARDHAT_DEPLOYMENTS_DIR: &str = "deployments";
+const HARDHAT_CHAIN_ID_FILE: &str = ".chainId";
+
+#[derive(thiserror::Error, Debug)]
+pub enum ReadArtifactsError {
+    #[error("No Foundry broadcast/ or Hardhat deployments/ folder found in {0}")]
+    NoArtifactsFound(PathBuf),
+
+    #[error("Could not read {0}: {1}")]
+    CouldNotRead(PathBuf, std::io::Error),
+
+    #[error("Could not parse {0}: {1}")]
+    CouldNotParse(PathBuf, serde_json::Error),
+
+    #[error("Could not parse {0}: {1}")]
+    CouldNotParseConfig(PathBuf, toml::de::Error),
+}
+
+/// A single deployment of a contract found in the artifacts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactDeployment {
+    /// Foundry broadcasts and hardhat `.chainId` files give us the chain id.
+    pub chain_id: Option<u64>,
+    /// Hardhat deployment folders are named after the hardhat network.
+    pub network_name: Option<String>,
+    pub address: Address,
+    pub block_number: Option<u64>,
+}
+
+/// A contract with its ABI and every deployment found for it.
+#[derive(Debug, Clone)]
+pub struct ArtifactContract {
+    pub name: String,
+    /// The ABI as pretty printed JSON, ready to be written to the abis folder.
+    pub abi: String,
+    pub deployments: Vec<ArtifactDeployment>,
+}
+
+impl ArtifactContract {
+    /// Map the deployments onto the manifest networks, grouping by network so a contract
+    /// deployed twice on the same chain is indexed with both addresses from the earliest
+    /// deployment block.
+    ///
+    /// Returns the contract details alongside any deployments that do not match a network.
+    pub fn contract_details(
+        &self,
+        networks: &[(&str, u64)],
+    ) -> (Vec<ContractDetails>, Vec<ArtifactDeployment>) {
+        let mut by_network: BTreeMap<&str, (BTreeSet<Address>, Option<u64>)> = BTreeMap::new();
+        let mut unmatched = vec![];
+
+        for deployment in &self.deployments {
+            let network = networks.iter().find(|(name, chain_id)| match deployment.chain_id {
+                Some(deployment_chain_id) => *chain_id == deployment_chain_id,
+                None => deployment.network_name.as_deref() == Some(*name),
+            });
+
+            let Some((network, _)) = network else {
+                unmatched.push(deployment.clone());
+                continue;
+            };
+
+            let (addresses, start_block) = by_network.entry(*network).or_default();
+            addresses.insert(deployment.address);
+            *start_block = match (*start_block, deployment.block_number) {
+                (Some(current), Some(block)) => Some(current.min(block)),
+                (current, block) => current.or(block),
+            };
+        }
+
+        let details = by_network
+            .into_iter()
+            .map(|(network, (addresses, start_block))| {
+                let address = if addresses.len() == 1 {
+                    ValueOrArray::Value(*addresses.first().expect("checked length"))
+                } else {
+                    ValueOrArray::Array(addresses.into_iter().collect())
+                };
+
+                ContractDetails::new_with_address(
+                    network.to_string(),
+                    address,
+                    None,
+                    start_block.map(U64::from),
+                    None,
+                )
+            })
+            .collect();
+
+        (details, unmatched)
+    }
+}
+
+/// Everything read from the artifacts folder.
+#[derive(Debug, Default)]
+pub struct Artifacts {
+    pub contracts: Vec<ArtifactContract>,
+    /// Deployed contracts which we could not find an ABI for.
+    pub missing_abis: Vec<String>,
+}
+
+/// Read contracts from a Foundry or Hardhat project, both are read if both exist.
+pub fn read_artifacts(project_path: &Path) -> Result<Artifacts, ReadArtifactsError> {
+    let broadcast_path = project_path.join(FOUNDRY_BROADCAST_DIR);
+    let deployments_path = project_path.join(HARDHAT_DEPLOYMENTS_DIR);
+
+    if !broadcast_path.is_dir() && !deployments_path.is_dir() {
+        return Err(ReadArtifactsError::NoArtifactsFound(project_path.to_path_buf()));
+    }
+
+    let mut artifacts = Artifacts::default();
+
+    if broadcast_path.is_dir() {
+        let foundry = read_foundry_artifacts(project_path)?;
+        artifacts.contracts.extend(foundry.contracts);
+        artifacts.missing_abis.extend(foundry.missing_abis);
+    }
+
+    if deployments_path.is_dir() {
+        merge_contracts(&mut artifacts.contracts, read_hardhat_deployments(&deployments_path)?);
+    }
+
+    artifacts.contracts.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(artifacts)
+}
+
+fn merge_contracts(contracts: &mut Vec<ArtifactContract>, new_contracts: Vec<ArtifactContract>) {
+    for contract in new_contracts {
+        match contracts.iter_mut().find(|c| c.name == contract.name) {
+            Some(existing) => existing.deployments.extend(contract.deployments),
+            None => contracts.push(contract),
+        }
+    }
+}
+
+fn read_dir_sorted(path: &Path) -> Result<Vec<PathBuf>, ReadArtifactsError> {
+    let mut entries = fs::read_dir(path)
+        .map_err(|e| ReadArtifactsError::CouldNotRead(path.to_path_buf(), e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect::<Vec<_>>();
+    entries.sort();
+    Ok(entries)
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, ReadArtifactsError> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| ReadArtifactsError::CouldNotRead(path.to_path_buf(), e))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| ReadArtifactsError::CouldNotParse(path.to_path_buf(), e))
+}
+
+/// Block numbers are hex strings in foundry receipts and plain numbers in hardhat receipts.
+fn parse_block_number(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(number) => number.as_u64(),
+        Value::String(value) => match value.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16).ok(),
+            None => value.parse().ok(),
+        },
+        _ => None,
+    }
+}
+
+fn file_name(path: &Path) -> &str {
+    path.file_name().and_then(|name| name.to_str()).unwrap_or_default()
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FoundryBroadcastRun {
+    #[serde(default)]
+    transactions: Vec<FoundryBroadcastTransaction>,
+    #[serde(default)]
+    receipts: Vec<FoundryBroadcastReceipt>,
+    chain: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FoundryBroadcastTransaction {
+    hash: Option<String>,
+    transaction_type: Option<String>,
+    contract_name: Option<String>,
+    contract_address: Option<Address>,
+    #[serde(default)]
+    additional_contracts: Vec<FoundryAdditionalContract>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FoundryAdditionalContract {
+    contract_name: Option<String>,
+    address: Option<Address>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FoundryBroadcastReceipt {
+    transaction_hash: String,
+    block_number: Value,
+}
+
+#[derive(Deserialize)]
+struct FoundryArtifact {
+    abi: Value,
+}
+
+#[derive(Deserialize, Default)]
+struct FoundryConfig {
+    #[serde(default)]
+    profile: HashMap<String, FoundryProfile>,
+}
+
+#[derive(Deserialize)]
+struct FoundryProfile {
+    out: Option<String>,
+}
+
+/// Foundry lets the out dir be overridden with `profile.default.out` in `foundry.toml`.
+fn foundry_out_dir(project_path: &Path) -> Result<PathBuf, ReadArtifactsError> {
+    let config_path = project_path.join(FOUNDRY_CONFIG_FILE);
+    let config: FoundryConfig = if config_path.is_file() {
+        let contents = fs::read_to_string(&config_path)
+            .map_err(|e| ReadArtifactsError::CouldNotRead(config_path.clone(), e))?;
+        toml::from_str(&contents)
+            .map_err(|e| ReadArtifactsError::CouldNotParseConfig(config_path, e))?
+    } else {
+        FoundryConfig::default()
+    };
+
+    let out_dir = config
+        .profile
+        .get("default")
+        .and_then(|profile| profile.out.clone())
+        .unwrap_or_else(|| FOUNDRY_DEFAULT_OUT_DIR.to_string());
+
+    Ok(project_path.join(out_dir))
+}
+
+/// Index every ABI in the foundry out dir by contract name. Artifacts compiled with multiple
+/// solc versions are written as `<Contract>.<version>.json`, the first one found wins.
+fn read_foundry_abis(out_path: &Path) -> Result<BTreeMap<String, Value>, ReadArtifactsError> {
+    let mut abis = BTreeMap::new();
+    if !out_path.is_dir() {
+        return Ok(abis);
+    }
+
+    for source_dir in read_dir_sorted(out_path)? {
+        if !source_dir.is_dir() || file_name(&source_dir) == "build-info" {
+            continue;
+        }
+
+        for artifact_path in read_dir_sorted(&source_dir)? {
+            if artifact_path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+
+            let name = file_name(&artifact_path).split('.').next().unwrap_or_default().to_string();
+            if name.is_empty() || abis.contains_key(&name) {
+                continue;
+            }
+
+            let artifact: FoundryArtifact = read_json(&artifact_path)?;
+            abis.insert(name, artifact.abi);
+        }
+    }
+
+    Ok(abis)
+}
+
+fn read_foundry_artifacts(project_path: &Path) -> Result<Artifacts, ReadArtifactsError> {
+    let abis = read_foundry_abis(&foundry_out_dir(project_path)?)?;
+    let mut deployments: BTreeMap<String, Vec<ArtifactDeployment>> = BTreeMap::new();
+
+    for script_dir in read_dir_sorted(&project_path.join(FOUNDRY_BROADCAST_DIR))? {
+        if !script_dir.is_dir() {
+            continue;
+        }
+
+        // dry runs are written to `<chain_id>/dry-run/` and were never broadcast, only the
+        // `run-latest.json` right in the chain folder is read
+        for chain_dir in read_dir_sorted(&script_dir)? {
+            let run_path = chain_dir.join(FOUNDRY_LATEST_RUN_FILE);
+            if !run_path.is_file() {
+                continue;
+            }
+
+            let run: FoundryBroadcastRun = read_json(&run_path)?;
+            let chain_id = file_name(&chain_dir).parse::<u64>().ok().or(run.chain);
+
+            for transaction in &run.transactions {
+                if !matches!(transaction.transaction_type.as_deref(), Some("CREATE" | "CREATE2")) {
+                    continue;
+                }
+
+                let block_number = transaction.hash.as_ref().and_then(|hash| {
+                    run.receipts
+                        .iter()
+                        .find(|receipt| receipt.transaction_hash.eq_ignore_ascii_case(hash))
+                        .and_then(|receipt| parse_block_number(&receipt.block_number))
+                });
+
+                let created =
+                    std::iter::once((&transaction.contract_name, &transaction.contract_address))
+                        .chain(
+                            transaction
+                                .additional_contracts
+                                .iter()
+                                .map(|contract| (&contract.contract_name, &contract.address)),
+                        );
+
+                for (name, address) in created {
+                    let (Some(name), Some(address)) = (name, address) else {
+                        continue;
+                    };
+
+                    deployments.entry(name.clone()).or_default().push(ArtifactDeployment {
+                        chain_id,
+                        network_name: None,
+                        address: *address,
+                        block_number,
+                    });
+                }
+            }
+        }
+    }
+
+    let mut artifacts = Artifacts::default();
+    for (name, deployments) in deployments {
+        match abis.get(&name) {
+            Some(abi) => artifacts.contracts.push(ArtifactContract {
+                abi: serde_json::to_string_pretty(abi).expect("ABI is valid JSON"),
+                name,
+                deployments,
+            }),
+            None => artifacts.missing_abis.push(name),
+        }
+    }
+
+    Ok(artifacts)
+}
+
+#[derive(Deserialize)]
+struct HardhatDeployment {
+    address: Address,
+    abi: Value,
+    receipt: Option<HardhatReceipt>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HardhatReceipt {
+    block_number: Value,
+}
+
+fn read_hardhat_deployments(
+    deployments_path: &Path,
+) -> Result<Vec<ArtifactContract>, ReadArtifactsError> {
+    let mut contracts: Vec<ArtifactContract> = vec![];
+
+    for network_dir in read_dir_sorted(deployments_path)? {
+        if !network_dir.is_dir() {
+            continue;
+        }
+
+        let network_name = file_name(&network_dir).to_string();
+        let chain_id = fs::read_to_string(network_dir.join(HARDHAT_CHAIN_ID_FILE))
+            .ok()
+            .and_then(|chain_id| chain_id.trim().parse::<u64>().ok());
+
+        let deployment_files = read_dir_sorted(&network_dir)?
+            .into_iter()
+            .filter(|path| {
+                path.is_file()
+                    && path.extension().is_some_and(|ext| ext == "json")
+                    && !file_name(path).starts_with('.')
+            })
+            .collect::<Vec<_>>();
+
+        let names = deployment_files
+            .iter()
+            .filter_map(|path| path.file_stem().and_then(|stem| stem.to_str()))
+            .collect::<BTreeSet<_>>();
+
+        for deployment_path in &deployment_files {
+            let name = deployment_path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+
+            // hardhat-deploy writes `<Name>_Proxy` and `<Name>_Implementation` next to
+            // `<Name>`, which already holds the proxy address with the implementation ABI
+            let is_proxy_part = ["_Proxy", "_Implementation"]
+                .iter()
+                .any(|suffix| name.strip_suffix(suffix).is_some_and(|base| names.contains(base)));
+            if is_proxy_part {
+                continue;
+            }
+
+            let deployment: HardhatDeployment = read_json(deployment_path)?;
+            let deployment_info = ArtifactDeployment {
+                chain_id,
+                network_name: Some(network_name.clone()),
+                address: deployment.address,
+                block_number: deployment
+                    .receipt
+                    .and_then(|receipt| parse_block_number(&receipt.block_number)),
+            };
+
+            merge_contracts(
+                &mut contracts,
+                vec![ArtifactContract {
+                    name: name.to_string(),
+                    abi: serde_json::to_string_pretty(&deployment.abi).expect("ABI is valid JSON"),
+                    deployments: vec![deployment_info],
+                }],
+            );
+        }
+    }
+
+    Ok(contracts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COUNTER_ADDRESS: &str = "0x5FbDB2315678afecb367f032d93F642f64180aa3";
+    const SECOND_COUNTER_ADDRESS: &str = "0xe7f1725E7734CE288F8367e1Bb143E90bb3F0512";
+    const TOKEN_ADDRESS: &str = "0x9fE46736679d2D9a65F0992F2272dE9f3c7fa6e0";
+
+    fn write(path: PathBuf, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn counter_abi() -> &'static str {
+        r#"[{"type":"event","name":"Incremented","inputs":[{"name":"by","type":"uint256","indexed":false}],"anonymous":false}]"#
+    }
+
+    fn setup_foundry(root: &Path) {
+        write(
+            root.join("out/Counter.sol/Counter.json"),
+            &format!(r#"{{"abi":{},"bytecode":{{"object":"0x"}}}}"#, counter_abi()),
+        );
+        write(root.join("out/build-info/abc.json"), r#"{"id":"abc"}"#);
+        write(
+            root.join("broadcast/Deploy.s.sol/31337/run-latest.json"),
+            &format!(
+                r#"{{
+                    "transactions": [
+                        {{"hash":"0xaa","transactionType":"CREATE","contractName":"Counter","contractAddress":"{COUNTER_ADDRESS}","additionalContracts":[]}},
+                        {{"hash":"0xbb","transactionType":"CREATE","contractName":"Counter","contractAddress":"{SECOND_COUNTER_ADDRESS}","additionalContracts":[]}},
+                        {{"hash":"0xcc","transactionType":"CALL","contractName":"Counter","contractAddress":"{COUNTER_ADDRESS}","additionalContracts":[]}},
+                        {{"hash":"0xdd","transactionType":"CREATE","contractName":"Unknown","contractAddress":"{TOKEN_ADDRESS}","additionalContracts":[]}}
+                    ],
+                    "receipts": [
+                        {{"transactionHash":"0xAA","blockNumber":"0x10"}},
+                        {{"transactionHash":"0xbb","blockNumber":"0x12"}},
+                        {{"transactionHash":"0xcc","blockNumber":"0x13"}}
+                    ],
+                    "chain": 31337
+                }}"#
+            ),
+        );
+        write(
+            root.join("broadcast/Deploy.s.sol/31337/dry-run/run-latest.json"),
+            r#"{"transactions":[],"receipts":[]}"#,
+        );
+    }
+
+    fn setup_hardhat(root: &Path) {
+        write(root.join("deployments/sepolia/.chainId"), "11155111\n");
+        write(
+            root.join("deployments/sepolia/Token.json"),
+            &format!(
+                r#"{{"address":"{TOKEN_ADDRESS}","abi":{},"receipt":{{"blockNumber":5000}}}}"#,
+                counter_abi()
+            ),
+        );
+        write(
+            root.join("deployments/sepolia/Token_Implementation.json"),
+            &format!(r#"{{"address":"{COUNTER_ADDRESS}","abi":[]}}"#),
+        );
+        write(
+            root.join("deployments/localhost/Token.json"),
+            &format!(r#"{{"address":"{TOKEN_ADDRESS}","abi":[]}}"#),
+        );
+    }
+
+    #[test]
+    fn test_read_foundry_artifacts() {
+        let dir = tempfile::tempdir().unwrap();
+        setup_foundry(dir.path());
+
+        let artifacts = read_artifacts(dir.path()).unwrap();
+
+        assert_eq!(artifacts.missing_abis, vec!["Unknown".to_string()]);
+        assert_eq!(artifacts.contracts.len(), 1);
+
+        let counter = &artifacts.contracts[0];
+        assert_eq!(counter.name, "Counter");
+        assert!(counter.abi.contains("Incremented"));
+        assert_eq!(counter.deployments.len(), 2);
+        assert_eq!(counter.deployments[0].chain_id, Some(31337));
+        assert_eq!(counter.deployments[0].block_number, Some(16));
+        assert_eq!(counter.deployments[1].block_number, Some(18));
+
+        let (details, unmatched) = counter.contract_details(&[("anvil", 31337)]);
+        assert!(unmatched.is_empty());
+        assert_eq!(details.len(), 1);
+        assert_eq!(details[0].network, "anvil");
+        assert_eq!(details[0].start_block_number(), Some(U64::from(16)));
+        assert!(matches!(&details[0].address, Some(ValueOrArray::Array(a)) if a.len() == 2));
+    }
+
+    #[test]
+    fn test_read_foundry_artifacts_custom_out_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        setup_foundry(dir.path());
+        fs::rename(dir.path().join("out"), dir.path().join("build")).unwrap();
+        write(
+            dir.path().join("foundry.toml"),
+            "[profile.default]\nsrc = \"src\"\nout = \"build\"\n",
+        );
+
+        let artifacts = read_artifacts(dir.path()).unwrap();
+
+        assert_eq!(artifacts.contracts.len(), 1);
+        assert_eq!(artifacts.contracts[0].name, "Counter");
+    }
+
+    #[test]
+    fn test_foundry_out_dir_reads_default_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(foundry_out_dir(dir.path()).unwrap(), dir.path().join("out"));
+
+        write(
+            dir.path().join("foundry.toml"),
+            "# out = \"commented\"\n[profile.default]\nsrc = 'src' # out = \"inline\"\nout = 'build'\n\n[profile.ci]\nout = \"ci-out\"\n",
+        );
+        assert_eq!(foundry_out_dir(dir.path()).unwrap(), dir.path().join("build"));
+
+        write(dir.path().join("foundry.toml"), "[profile.ci]\nout = \"ci-out\"\n");
+        assert_eq!(foundry_out_dir(dir.path()).unwrap(), dir.path().join("out"));
+
+        write(dir.path().join("foundry.toml"), "[profile.default\nout = \"build\"\n");
+        assert!(matches!(
+            foundry_out_dir(dir.path()),
+            Err(ReadArtifactsError::CouldNotParseConfig(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_read_hardhat_deployments() {
+        let dir = tempfile::tempdir().unwrap();
+        setup_hardhat(dir.path());
+
+        let artifacts = read_artifacts(dir.path()).unwrap();
+
+        assert!(artifacts.missing_abis.is_empty());
+        assert_eq!(artifacts.contracts.len(), 1);
+
+        let token = &artifacts.contracts[0];
+        assert_eq!(token.name, "Token");
+        assert_eq!(token.deployments.len(), 2);
+
+        let (details, unmatched) = token.contract_details(&[("sepolia", 11155111)]);
+        assert_eq!(details.len(), 1);
+        assert_eq!(details[0].network, "sepolia");
+        assert_eq!(details[0].start_block_number(), Some(U64::from(5000)));
+        assert!(
+            matches!(&details[0].address, Some(ValueOrArray::Value(a)) if *a == TOKEN_ADDRESS.parse::<Address>().unwrap())
+        );
+
+        assert_eq!(unmatched.len(), 1);
+        assert_eq!(unmatched[0].network_name.as_deref(), Some("localhost"));
+
+        // without a `.chainId` file the network is matched by the folder name
+        let (details, unmatched) = token.contract_details(&[("localhost", 1337)]);
+        assert_eq!(details.len(), 1);
+        assert_eq!(details[0].start_block_number(), None);
+        assert_eq!(unmatched.len(), 1);
+    }
+
+    #[test]
+    fn test_read_artifacts_without_artifacts_errors() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(matches!(read_artifacts(dir.path()), Err(ReadArtifactsError::NoArtifactsFound(_))));
+    }
+}
@@ -1,7 +1,8 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct TelegramConfig {
     pub bot_token: String,
     pub chat_id: i64,
@@ -9,7 +10,7 @@ pub struct TelegramConfig {
     pub messages: Vec<TelegramEvent>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct TelegramEvent {
     pub event_name: String,
 
@@ -22,7 +23,7 @@ pub struct TelegramEvent {
     pub template_inline: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct DiscordConfig {
     pub bot_token: String,
     pub channel_id: u64,
@@ -30,7 +31,7 @@ pub struct DiscordConfig {
     pub messages: Vec<DiscordEvent>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct DiscordEvent {
     pub event_name: String,
 
@@ -43,7 +44,7 @@ pub struct DiscordEvent {
     pub template_inline: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct SlackConfig {
     pub bot_token: String,
     pub channel: String,
@@ -51,7 +52,7 @@ pub struct SlackConfig {
     pub messages: Vec<SlackEvent>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct SlackEvent {
     pub event_name: String,
 
@@ -64,7 +65,7 @@ pub struct SlackEvent {
     pub template_inline: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct TwilioConfig {
     pub account_sid: String,
     pub auth_token: String,
@@ -74,7 +75,7 @@ pub struct TwilioConfig {
     pub messages: Vec<TwilioEvent>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct TwilioEvent {
     pub event_name: String,
 
@@ -87,7 +88,7 @@ pub struct TwilioEvent {
     pub template_inline: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct PagerDutyConfig {
     pub routing_key: String,
     #[serde(default = "default_pagerduty_severity")]
@@ -100,7 +101,7 @@ fn default_pagerduty_severity() -> String {
     "critical".to_string()
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct PagerDutyEvent {
     pub event_name: String,
 
@@ -113,7 +114,7 @@ pub struct PagerDutyEvent {
     pub template_inline: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct OpsGenieConfig {
     pub api_key: String,
     #[serde(default = "default_opsgenie_priority")]
@@ -126,7 +127,7 @@ fn default_opsgenie_priority() -> String {
     "P1".to_string()
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct OpsGenieEvent {
     pub event_name: String,
 
@@ -139,7 +140,21 @@ pub struct OpsGenieEvent {
     pub template_inline: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// A message sent through every channel of the chat config when a reorg on one of its networks
+/// rolls back at least `min_depth` blocks.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct ReorgAlertConfig {
+    #[serde(default = "default_reorg_alert_min_depth")]
+    pub min_depth: u64,
+
+    pub template_inline: String,
+}
+
+fn default_reorg_alert_min_depth() -> u64 {
+    1
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct ChatConfig {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub telegram: Option<Vec<TelegramConfig>>,
@@ -158,4 +173,7 @@ pub struct ChatConfig {
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub opsgenie: Option<Vec<OpsGenieConfig>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reorg: Option<ReorgAlertConfig>,
 }
@@ -0,0 +1,426 @@
+//! Composes a manifest out of several yaml files.
+//!
+//! `include:` merges other yaml files (or every yaml file in a folder) into the file, `extends:`
+//! layers the file over a base manifest and `RINDEXER_ENV=prod` merges `rindexer.prod.yaml` over
+//! the composed `rindexer.yaml`. Mappings are merged key by key, lists of items with a `name`
+//! are merged by name and everything else is replaced by the file merged last.
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_yaml::{Mapping, Value};
+
+use crate::manifest::yaml::substitute_env_variables;
+
+pub const INCLUDE_KEY: &str = "include";
+pub const EXTENDS_KEY: &str = "extends";
+
+/// The environment variable which selects the overlay merged over the manifest.
+pub const ENVIRONMENT_VARIABLE: &str = "RINDEXER_ENV";
+
+#[derive(thiserror::Error, Debug)]
+pub enum ComposeManifestError {
+    #[error("Could not read {0}: {1}")]
+    CouldNotReadFile(PathBuf, std::io::Error),
+
+    #[error("Could not parse {0}: {1}")]
+    CouldNotParseFile(PathBuf, serde_yaml::Error),
+
+    #[error("Could not substitute env variables in {0}: {1}")]
+    CouldNotSubstituteEnvVariables(PathBuf, regex::Error),
+
+    #[error("`{1}` in {0} must be a path or a list of paths")]
+    InvalidPaths(PathBuf, &'static str),
+
+    #[error("{0} must be a yaml mapping to be composed")]
+    NotAMapping(PathBuf),
+
+    #[error("{0} includes itself")]
+    IncludeCycle(PathBuf),
+
+    #[error("Environment overlay {0} does not exist")]
+    OverlayNotFound(PathBuf),
+
+    #[error("Could not serialize the composed manifest: {0}")]
+    CouldNotSerialize(serde_yaml::Error),
+}
+
+#[derive(Debug, Clone)]
+pub struct ComposedManifest {
+    /// The composed manifest yaml.
+    pub contents: String,
+
+    /// Every file the manifest was composed from, starting with the manifest itself.
+    pub files: Vec<PathBuf>,
+
+    /// Every folder included whole, a yaml file added to one joins the manifest.
+    pub folders: Vec<PathBuf>,
+}
+
+/// The overlay for `environment`, `rindexer.yaml` with `prod` resolves to `rindexer.prod.yaml`.
+pub fn overlay_path(file_path: &Path, environment: &str) -> PathBuf {
+    let stem = file_path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = file_path.extension().map_or("yaml".into(), |e| e.to_string_lossy());
+    file_path.with_file_name(format!("{stem}.{environment}.{extension}"))
+}
+
+/// The environment selected with `RINDEXER_ENV`, if any.
+pub fn environment_from_env() -> Option<String> {
+    std::env::var(ENVIRONMENT_VARIABLE).ok().filter(|e| !e.trim().is_empty())
+}
+
+/// Composes the manifest at `file_path` with its includes, bases and environment overlay.
+///
+/// When `substitute_env` is set `${ENV}` values are substituted in every file before it is
+/// merged. A manifest which doesn't use any of the composition features is returned as is.
+pub fn compose_manifest(
+    file_path: &Path,
+    environment: Option<&str>,
+    substitute_env: bool,
+) -> Result<ComposedManifest, ComposeManifestError> {
+    let mut composer = Composer { substitute_env, files: vec![], folders: vec![], stack: vec![] };
+
+    let contents = composer.read(file_path)?;
+    let root = parse(file_path, &contents)?;
+
+    let overlay = environment.map(|environment| overlay_path(file_path, environment));
+    if let Some(overlay) = &overlay {
+        if !overlay.exists() {
+            return Err(ComposeManifestError::OverlayNotFound(overlay.clone()));
+        }
+    }
+
+    let composes = root
+        .as_mapping()
+        .is_some_and(|m| m.contains_key(INCLUDE_KEY) || m.contains_key(EXTENDS_KEY));
+    if !composes && overlay.is_none() {
+        return Ok(ComposedManifest { contents, files: composer.files, folders: composer.folders });
+    }
+
+    composer.stack.push(canonical(file_path));
+    let mut manifest = composer.compose_value(file_path, root)?;
+    composer.stack.pop();
+
+    if let Some(overlay) = overlay {
+        let overlay = composer.compose(&overlay)?;
+        merge(&mut manifest, overlay);
+    }
+
+    let contents =
+        serde_yaml::to_string(&manifest).map_err(ComposeManifestError::CouldNotSerialize)?;
+
+    Ok(ComposedManifest { contents, files: composer.files, folders: composer.folders })
+}
+
+/// The files and folders the manifest at `file_path` is composed from.
+#[derive(Debug, Clone, Default)]
+pub struct ManifestSources {
+    pub files: Vec<PathBuf>,
+    pub folders: Vec<PathBuf>,
+}
+
+/// Every file and included folder the manifest at `file_path` is composed from, used to watch
+/// them for changes.
+pub fn manifest_sources(
+    file_path: &Path,
+    environment: Option<&str>,
+) -> Result<ManifestSources, ComposeManifestError> {
+    let ComposedManifest { mut files, folders, .. } =
+        compose_manifest(file_path, environment, false)?;
+    if let Some(environment) = environment {
+        let overlay = canonical(&overlay_path(file_path, environment));
+        if !files.contains(&overlay) {
+            files.push(overlay);
+        }
+    }
+    Ok(ManifestSources { files, folders })
+}
+
+/// Merges `overlay` into `base`.
+pub fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Mapping(base), Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Sequence(base), Value::Sequence(overlay))
+            if is_named_list(base) && is_named_list(&overlay) =>
+        {
+            for item in overlay {
+                match base.iter_mut().find(|existing| existing.get("name") == item.get("name")) {
+                    Some(existing) => merge(existing, item),
+                    None => base.push(item),
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+fn is_named_list(items: &[Value]) -> bool {
+    items.iter().all(|item| item.get("name").is_some_and(Value::is_string))
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn parse(file_path: &Path, contents: &str) -> Result<Value, ComposeManifestError> {
+    let value: Value = serde_yaml::from_str(contents)
+        .map_err(|e| ComposeManifestError::CouldNotParseFile(file_path.to_path_buf(), e))?;
+
+    match value {
+        Value::Null => Ok(Value::Mapping(Mapping::new())),
+        Value::Mapping(_) => Ok(value),
+        _ => Err(ComposeManifestError::NotAMapping(file_path.to_path_buf())),
+    }
+}
+
+struct Composer {
+    substitute_env: bool,
+    files: Vec<PathBuf>,
+    folders: Vec<PathBuf>,
+    stack: Vec<PathBuf>,
+}
+
+impl Composer {
+    fn read(&mut self, file_path: &Path) -> Result<String, ComposeManifestError> {
+        let contents = fs::read_to_string(file_path)
+            .map_err(|e| ComposeManifestError::CouldNotReadFile(file_path.to_path_buf(), e))?;
+
+        let file = canonical(file_path);
+        if !self.files.contains(&file) {
+            self.files.push(file);
+        }
+
+        if self.substitute_env {
+            substitute_env_variables(&contents).map_err(|e| {
+                ComposeManifestError::CouldNotSubstituteEnvVariables(file_path.to_path_buf(), e)
+            })
+        } else {
+            Ok(contents)
+        }
+    }
+
+    fn compose(&mut self, file_path: &Path) -> Result<Value, ComposeManifestError> {
+        let file = canonical(file_path);
+        if self.stack.contains(&file) {
+            return Err(ComposeManifestError::IncludeCycle(file));
+        }
+
+        let contents = self.read(file_path)?;
+        let value = parse(file_path, &contents)?;
+
+        self.stack.push(file);
+        let value = self.compose_value(file_path, value)?;
+        self.stack.pop();
+
+        Ok(value)
+    }
+
+    /// Merges the includes of `value` over its base, then `value` itself over the result.
+    fn compose_value(
+        &mut self,
+        file_path: &Path,
+        value: Value,
+    ) -> Result<Value, ComposeManifestError> {
+        let Value::Mapping(mut mapping) = value else {
+            return Err(ComposeManifestError::NotAMapping(file_path.to_path_buf()));
+        };
+
+        let directory = file_path.parent().unwrap_or(Path::new(""));
+        let extends = paths(file_path, mapping.remove(EXTENDS_KEY), EXTENDS_KEY)?;
+        let includes = paths(file_path, mapping.remove(INCLUDE_KEY), INCLUDE_KEY)?;
+
+        let mut composed = Value::Mapping(Mapping::new());
+        for base in extends {
+            let base = self.compose(&directory.join(base))?;
+            merge(&mut composed, base);
+        }
+
+        for include in includes {
+            let include = directory.join(include);
+            if include.is_dir() {
+                let folder = canonical(&include);
+                if !self.folders.contains(&folder) {
+                    self.folders.push(folder);
+                }
+            }
+
+            for include in expand_folder(&include, file_path)? {
+                let include = self.compose(&include)?;
+                merge(&mut composed, include);
+            }
+        }
+
+        merge(&mut composed, Value::Mapping(mapping));
+        Ok(composed)
+    }
+}
+
+fn paths(
+    file_path: &Path,
+    value: Option<Value>,
+    key: &'static str,
+) -> Result<Vec<String>, ComposeManifestError> {
+    let invalid = || ComposeManifestError::InvalidPaths(file_path.to_path_buf(), key);
+    match value {
+        None | Some(Value::Null) => Ok(vec![]),
+        Some(Value::String(path)) => Ok(vec![path]),
+        Some(Value::Sequence(paths)) => paths
+            .into_iter()
+            .map(|path| match path {
+                Value::String(path) => Ok(path),
+                _ => Err(invalid()),
+            })
+            .collect(),
+        Some(_) => Err(invalid()),
+    }
+}
+
+/// An included folder includes every yaml file directly inside it in name order.
+fn expand_folder(path: &Path, file_path: &Path) -> Result<Vec<PathBuf>, ComposeManifestError> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let entries = fs::read_dir(path)
+        .map_err(|e| ComposeManifestError::CouldNotReadFile(path.to_path_buf(), e))?;
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|p| p.is_file())
+        .filter(|p| p.extension().is_some_and(|e| e == "yaml" || e == "yml"))
+        .filter(|p| canonical(p) != canonical(file_path))
+        .collect();
+    files.sort();
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, name: &str, contents: &str) -> PathBuf {
+        let path = dir.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn composed(path: &Path, environment: Option<&str>) -> Value {
+        let composed = compose_manifest(path, environment, false).unwrap();
+        serde_yaml::from_str(&composed.contents).unwrap()
+    }
+
+    #[test]
+    fn test_manifest_without_composition_is_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let contents = "name: test # comment\nproject_type: no-code\n";
+        let path = write(dir.path(), "rindexer.yaml", contents);
+
+        let composed = compose_manifest(&path, None, false).unwrap();
+        assert_eq!(composed.contents, contents);
+        assert_eq!(composed.files, vec![canonical(&path)]);
+    }
+
+    #[test]
+    fn test_include_files_and_folders() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            "networks.yaml",
+            "networks:\n  - name: ethereum\n    chain_id: 1\n    rpc: https://eth.rpc\n",
+        );
+        write(dir.path(), "contracts/a.yaml", "contracts:\n  - name: A\n    abi: ./abis/A.json\n");
+        write(dir.path(), "contracts/b.yml", "contracts:\n  - name: B\n    abi: ./abis/B.json\n");
+        write(dir.path(), "contracts/notes.txt", "not yaml");
+        let path = write(
+            dir.path(),
+            "rindexer.yaml",
+            "name: test\ninclude:\n  - ./networks.yaml\n  - ./contracts\n",
+        );
+
+        let manifest = composed(&path, None);
+        assert!(manifest.get(INCLUDE_KEY).is_none());
+        assert_eq!(manifest["name"], "test");
+        assert_eq!(manifest["networks"][0]["chain_id"], 1);
+        assert_eq!(manifest["contracts"][0]["name"], "A");
+        assert_eq!(manifest["contracts"][1]["name"], "B");
+
+        let composed = compose_manifest(&path, None, false).unwrap();
+        assert_eq!(composed.files.len(), 4);
+        assert_eq!(composed.folders, vec![canonical(&dir.path().join("contracts"))]);
+    }
+
+    #[test]
+    fn test_extends_and_environment_overlay() {
+        let dir = tempfile::tempdir().unwrap();
+        write(
+            dir.path(),
+            "base.yaml",
+            "name: test\nnetworks:\n  - name: ethereum\n    chain_id: 1\n    rpc: https://dev.rpc\n  - name: base\n    chain_id: 8453\n    rpc: https://base.rpc\nstorage:\n  postgres:\n    enabled: true\n",
+        );
+        let path = write(
+            dir.path(),
+            "rindexer.yaml",
+            "extends: ./base.yaml\nstorage:\n  csv:\n    enabled: true\n",
+        );
+        write(
+            dir.path(),
+            "rindexer.prod.yaml",
+            "networks:\n  - name: ethereum\n    rpc: https://prod.rpc\nstorage:\n  csv:\n    enabled: false\n",
+        );
+
+        let dev = composed(&path, None);
+        assert_eq!(dev["networks"][0]["rpc"], "https://dev.rpc");
+        assert_eq!(dev["storage"]["postgres"]["enabled"], true);
+        assert_eq!(dev["storage"]["csv"]["enabled"], true);
+
+        let prod = composed(&path, Some("prod"));
+        assert_eq!(prod["networks"][0]["rpc"], "https://prod.rpc");
+        assert_eq!(prod["networks"][0]["chain_id"], 1);
+        assert_eq!(prod["networks"][1]["rpc"], "https://base.rpc");
+        assert_eq!(prod["storage"]["postgres"]["enabled"], true);
+        assert_eq!(prod["storage"]["csv"]["enabled"], false);
+    }
+
+    #[test]
+    fn test_missing_overlay_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(dir.path(), "rindexer.yaml", "name: test\n");
+
+        let err = compose_manifest(&path, Some("staging"), false).unwrap_err();
+        assert!(
+            matches!(err, ComposeManifestError::OverlayNotFound(p) if p.ends_with("rindexer.staging.yaml"))
+        );
+    }
+
+    #[test]
+    fn test_include_cycle_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "a.yaml", "include: ./b.yaml\n");
+        write(dir.path(), "b.yaml", "include: ./a.yaml\n");
+        let path = write(dir.path(), "rindexer.yaml", "include: ./a.yaml\n");
+
+        let err = compose_manifest(&path, None, false).unwrap_err();
+        assert!(matches!(err, ComposeManifestError::IncludeCycle(_)));
+    }
+
+    #[test]
+    fn test_merge_replaces_unnamed_lists() {
+        let mut base: Value = serde_yaml::from_str("include_events: [Transfer, Approval]").unwrap();
+        merge(&mut base, serde_yaml::from_str("include_events: [Transfer]").unwrap());
+        assert_eq!(base["include_events"], serde_yaml::from_str::<Value>("[Transfer]").unwrap());
+    }
+}
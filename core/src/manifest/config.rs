@@ -1,7 +1,8 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 /// Advanced config options for tuning rindexer beyond its default settings.
-#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct Config {
     /// Sets the buffer of events we hold in memory per "network-event". Useful for balancing
     /// memory with event throughput on large scale backfill operations.
@@ -53,6 +54,30 @@ pub struct Config {
     /// Recommended: 4-10 for dedicated RPC nodes. Ignored for factory contracts.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub fetch_concurrency: Option<usize>,
+
+    /// Keep finalized RPC responses (logs, blocks, `eth_call` at a block and traces) on disk so
+    /// re-indexing the same blocks does not download them again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rpc_cache: Option<RpcCacheConfig>,
+
+    /// Also fetch the blocks between the loaded `.blockclock` encoding of a network and the
+    /// indexed blocks, up to 500 per batch, so the encoding catches up with the chain while
+    /// indexing. Costs extra `eth_getBlockByNumber` calls. Without it timestamps fetched for
+    /// events are only recorded while they follow on from the encoding. Default: `false`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blockclock_catch_up: Option<bool>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct RpcCacheConfig {
+    /// Directory of the cache relative to the project, defaults to `.rindexer/cache`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+
+    /// Size the cache is kept under in megabytes, the oldest entries are dropped first. Defaults
+    /// to `1024`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size_mb: Option<u64>,
 }
 
 #[cfg(test)]
@@ -86,6 +111,15 @@ mod tests {
         assert_eq!(transfer.callback_concurrency, None);
     }
 
+    #[test]
+    fn test_config_blockclock_catch_up() {
+        let config: Config = serde_yaml::from_str("blockclock_catch_up: true").unwrap();
+        assert_eq!(config.blockclock_catch_up, Some(true));
+
+        let config: Config = serde_yaml::from_str("buffer: 4").unwrap();
+        assert_eq!(config.blockclock_catch_up, None);
+    }
+
     #[test]
     fn test_config_fetch_concurrency() {
         let yaml = r#"
@@ -105,4 +139,16 @@ mod tests {
         let config: Config = serde_yaml::from_str(yaml).unwrap();
         assert_eq!(config.fetch_concurrency, None);
     }
+
+    #[test]
+    fn test_config_rpc_cache() {
+        let yaml = r#"
+          rpc_cache:
+            max_size_mb: 512
+        "#;
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        let rpc_cache = config.rpc_cache.expect("rpc_cache should be set");
+        assert_eq!(rpc_cache.path, None);
+        assert_eq!(rpc_cache.max_size_mb, Some(512));
+    }
 }
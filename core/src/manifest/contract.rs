@@ -2,25 +2,35 @@ use std::{borrow::Cow, collections::HashMap, collections::HashSet, fs, path::Pat
 
 use alloy::rpc::types::Topic;
 use alloy::{
+    eips::BlockNumberOrTag,
     primitives::{Address, U64},
     rpc::types::ValueOrArray,
 };
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
 use super::core::{deserialize_option_u64_from_string, serialize_option_u64_as_string};
+use super::schema::{
+    address_or_array_schema, block_number_schema, filter_or_array_schema, include_events_schema,
+    string_or_array_schema, tables_schema,
+};
 use crate::event::contract_setup::FactoryDetails;
-use crate::helpers::parse_topic;
+use crate::event::{
+    find_lookups, LookupRef, LOOKUP_PREFIX, MAX_DECIMAL_SCALE, SCALE_PREFIX, TOKEN_PREFIX,
+};
+use crate::helpers::{camel_to_snake, parse_topic};
 use crate::{
     event::contract_setup::{
         AddressDetails, ContractEventMapping, FilterDetails, IndexingContractSetup,
     },
     helpers::get_full_path,
     manifest::{chat::ChatConfig, stream::StreamsConfig},
+    proxy::implementation_abi_paths,
     types::single_or_array::StringOrArray,
 };
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct EventInputIndexedFilters {
     pub event_name: String,
 
@@ -52,19 +62,21 @@ impl From<EventInputIndexedFilters> for [Topic; 4] {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct FilterDetailsYaml {
     pub event_name: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct FactoryDetailsYaml {
     pub name: String,
 
+    #[schemars(schema_with = "address_or_array_schema")]
     pub address: ValueOrArray<Address>,
 
     pub event_name: String,
 
+    #[schemars(schema_with = "string_or_array_schema")]
     pub input_name: ValueOrArray<String>,
 
     pub abi: String,
@@ -79,14 +91,16 @@ impl FactoryDetailsYaml {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct ContractDetails {
     pub network: String,
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(schema_with = "address_or_array_schema")]
     pub address: Option<ValueOrArray<Address>>,
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(schema_with = "filter_or_array_schema")]
     pub filter: Option<ValueOrArray<FilterDetailsYaml>>,
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -95,13 +109,8 @@ pub struct ContractDetails {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub factory: Option<FactoryDetailsYaml>,
 
-    #[serde(
-        default,
-        skip_serializing_if = "Option::is_none",
-        deserialize_with = "deserialize_option_u64_from_string",
-        serialize_with = "serialize_option_u64_as_string"
-    )]
-    pub start_block: Option<U64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_block: Option<StartBlock>,
 
     #[serde(
         default,
@@ -109,10 +118,85 @@ pub struct ContractDetails {
         deserialize_with = "deserialize_option_u64_from_string",
         serialize_with = "serialize_option_u64_as_string"
     )]
+    #[schemars(schema_with = "block_number_schema")]
     pub end_block: Option<U64>,
 }
 
+/// Where indexing of a contract starts, either a fixed block or `auto` which discovers the
+/// deployment block on startup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartBlock {
+    Number(U64),
+    Auto,
+}
+
+impl StartBlock {
+    pub fn number(&self) -> Option<U64> {
+        match self {
+            StartBlock::Number(block) => Some(*block),
+            StartBlock::Auto => None,
+        }
+    }
+
+    pub fn is_auto(&self) -> bool {
+        matches!(self, StartBlock::Auto)
+    }
+}
+
+impl From<U64> for StartBlock {
+    fn from(block: U64) -> Self {
+        StartBlock::Number(block)
+    }
+}
+
+impl<'de> Deserialize<'de> for StartBlock {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        if value.eq_ignore_ascii_case("auto") {
+            return Ok(StartBlock::Auto);
+        }
+        value.parse::<U64>().map(StartBlock::Number).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Serialize for StartBlock {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            StartBlock::Number(block) => serializer.serialize_str(&block.to_string()),
+            StartBlock::Auto => serializer.serialize_str("auto"),
+        }
+    }
+}
+
+impl JsonSchema for StartBlock {
+    fn schema_name() -> Cow<'static, str> {
+        "StartBlock".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "anyOf": [
+                { "type": "integer", "minimum": 0 },
+                { "type": "string", "pattern": "^[0-9]+$" },
+                { "const": "auto" }
+            ]
+        })
+    }
+}
+
 impl ContractDetails {
+    /// The fixed start block, `None` when indexing from latest or when `start_block: auto` has
+    /// not been resolved yet.
+    pub fn start_block_number(&self) -> Option<U64> {
+        self.start_block.and_then(|start_block| start_block.number())
+    }
+
     pub fn indexing_contract_setup(&self, project_path: &Path) -> IndexingContractSetup {
         if let Some(address) = &self.address {
             IndexingContractSetup::Address(AddressDetails {
@@ -170,20 +254,20 @@ impl ContractDetails {
             filter: None,
             indexed_filters,
             factory: None,
-            start_block,
+            start_block: start_block.map(StartBlock::Number),
             end_block,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum SimpleEventOrContractEvent {
     SimpleEvent(String),
     ContractEvent(ContractEventMapping),
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct DependencyEventTreeYaml {
     pub events: Vec<SimpleEventOrContractEvent>,
 
@@ -228,14 +312,16 @@ where
     Ok(Some(
         defs.into_iter()
             .map(|def| match def {
-                ContractEventDeserializer::String(s) => ContractEvent { name: s, timestamps: None },
+                ContractEventDeserializer::String(s) => {
+                    ContractEvent { name: s, timestamps: None, retention: None }
+                }
                 ContractEventDeserializer::Struct(ev) => ev,
             })
             .collect(),
     ))
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, JsonSchema)]
 pub struct ContractEvent {
     /// The name of the event.
     pub name: String,
@@ -243,6 +329,9 @@ pub struct ContractEvent {
     /// setting with either the true or false state if set.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub timestamps: Option<bool>,
+    /// How long rows of the raw event table are kept, overrides the contract `retention`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention: Option<Retention>,
 }
 
 // ============================================================================
@@ -275,6 +364,23 @@ pub mod injected_columns {
 
     /// The contract address that emitted the event.
     pub const CONTRACT_ADDRESS: &str = "rindexer_contract_address";
+
+    /// First block a row version of a `history` table is valid for.
+    pub const VALID_FROM: &str = "rindexer_valid_from";
+
+    /// Block a row version of a `history` table stopped being valid at, NULL while current.
+    pub const VALID_TO: &str = "rindexer_valid_to";
+
+    /// The hidden column counting the samples folded into an `avg` column.
+    pub fn avg_samples(column: &str) -> String {
+        format!("rindexer_{}_samples", column)
+    }
+
+    /// The row value carrying the value counted by a `count_distinct` column. It isn't a
+    /// column of the table, counted values are kept in its `_distinct_values` side table.
+    pub fn count_distinct_values(column: &str) -> String {
+        format!("rindexer_{}_distinct", column)
+    }
 }
 
 /// Computes a unique sequence ID from block number, transaction index, and log index.
@@ -290,7 +396,7 @@ pub fn compute_sequence_id(block_number: u64, tx_index: u64, log_index: u64) ->
 }
 
 /// Defines a custom table for aggregated indexing.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct Table {
     /// The table name (will be prefixed with {indexer}_{contract}_)
     pub name: String,
@@ -320,6 +426,10 @@ pub struct Table {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub cron: Option<Vec<TableCronMapping>>,
 
+    /// Operations run when rows of other tables of the same contract change.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_change: Vec<TableChangeMapping>,
+
     /// Whether to include the `rindexer_block_timestamp` column.
     /// Default is false - the column will not be created.
     /// When true, the column is created as `TIMESTAMPTZ NOT NULL` and rindexer will
@@ -328,6 +438,28 @@ pub struct Table {
     #[serde(default, skip_serializing_if = "std::ops::Not::not")]
     pub timestamp: bool,
 
+    /// Turns the table into a time-bucketed rollup. Every row is keyed by the bucket
+    /// derived from `rindexer_block_timestamp` in addition to the `where` columns.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bucket: Option<TableBucket>,
+
+    /// Keeps every version of each row in `{table}_history` with the block range it was
+    /// valid for, so the table can be queried as of any block. Postgres only.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub history: bool,
+
+    /// Secondary indexes created alongside the primary key.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub indexes: Vec<TableIndex>,
+
+    /// Splits the table into partitions by network or block range.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partition_by: Option<TablePartition>,
+
+    /// Drops rows last written before a block age or timestamp age.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention: Option<Retention>,
+
     /// Optional database/schema override for this table.
     /// When set, this table is created in the specified database (CH) or schema (PG)
     /// instead of the default `{indexer_name}_{contract_name}` schema.
@@ -339,9 +471,9 @@ pub struct Table {
 }
 
 impl Table {
-    /// Returns true if this table has at least one trigger (event or cron).
+    /// Returns true if this table has at least one trigger (event, cron or table change).
     pub fn has_triggers(&self) -> bool {
-        !self.events.is_empty() || self.has_cron()
+        !self.events.is_empty() || self.has_cron() || !self.on_change.is_empty()
     }
 
     /// Returns true if this table has cron triggers.
@@ -356,16 +488,18 @@ impl Table {
         !ops.is_empty() && ops.iter().all(|op| op.operation_type == OperationType::Insert)
     }
 
-    /// Get all operations (from both events and cron).
+    /// Get all operations (from events, cron and table changes).
     pub fn all_operations(&self) -> impl Iterator<Item = &TableOperation> {
         let event_ops = self.events.iter().flat_map(|e| e.operations.iter());
         let cron_ops =
             self.cron.iter().flat_map(|crons| crons.iter().flat_map(|c| c.operations.iter()));
-        event_ops.chain(cron_ops)
+        let change_ops = self.on_change.iter().flat_map(|c| c.operations.iter());
+        event_ops.chain(cron_ops).chain(change_ops)
     }
 
     /// Get all primary key column names derived from `where` clauses.
     /// Primary key = all unique column names used in `where` across all operations.
+    /// Bucket tables append the bucket column, so each interval gets its own row.
     pub fn primary_key_columns(&self) -> Vec<&str> {
         let mut pk_columns: Vec<&str> = Vec::new();
         for operation in self.all_operations() {
@@ -375,12 +509,25 @@ impl Table {
                 }
             }
         }
+        if let Some(bucket) = &self.bucket {
+            pk_columns.push(bucket.column.as_str());
+        }
         pk_columns
     }
 
     /// Check if a column is part of the primary key (used in any where clause)
     pub fn is_primary_key_column(&self, column_name: &str) -> bool {
-        self.all_operations().any(|op| op.where_clause.contains_key(column_name))
+        self.bucket.as_ref().is_some_and(|b| b.column == column_name)
+            || self.all_operations().any(|op| op.where_clause.contains_key(column_name))
+    }
+
+    /// Returns the action a bucket table applies to `column_name`, if any operation sets it.
+    /// `validate_bucket` guarantees a column is always aggregated with the same action.
+    pub fn bucket_column_action(&self, column_name: &str) -> Option<&SetAction> {
+        self.all_operations()
+            .flat_map(|op| op.set.iter())
+            .find(|set_col| set_col.column == column_name)
+            .map(|set_col| &set_col.action)
     }
 
     /// Validate that all operations use the same where clause columns.
@@ -448,8 +595,10 @@ impl Table {
             }
         }
 
-        // Only require where columns if there are non-insert operations
+        // Only require where columns if there are non-insert operations.
+        // Bucket tables are always keyed by their bucket, so `where` is optional there.
         if has_non_insert_operations
+            && self.bucket.is_none()
             && (expected_columns.is_none() || expected_columns.as_ref().unwrap().is_empty())
         {
             return Err(format!(
@@ -464,6 +613,564 @@ impl Table {
         Ok(())
     }
 
+    /// Validates the `bucket` option of rollup tables.
+    ///
+    /// Bucket tables only support `upsert` operations, every column must be aggregated with a
+    /// single action and `subtract`/`decrement` are rejected as rollups only ever accumulate.
+    pub fn validate_bucket(&self) -> Result<(), String> {
+        let Some(bucket) = &self.bucket else {
+            return Ok(());
+        };
+
+        bucket
+            .interval_secs()
+            .map_err(|e| format!("Invalid bucket interval in table '{}': {}", self.name, e))?;
+
+        if self.cross_chain {
+            return Err(format!(
+                "Bucket table '{}' cannot be 'cross_chain'. \
+                 Rollups are rolled back per network on reorg.",
+                self.name
+            ));
+        }
+
+        if self.has_cron() {
+            return Err(format!(
+                "Bucket table '{}' cannot use cron triggers. \
+                 Buckets are derived from the block timestamp of each event.",
+                self.name
+            ));
+        }
+
+        if let Some(column) = self.columns.iter().find(|c| c.name == bucket.column) {
+            if column.column_type.as_ref().is_some_and(|t| *t != ColumnType::Timestamp) {
+                return Err(format!(
+                    "Bucket column '{}' in table '{}' must be of type 'timestamp'.",
+                    bucket.column, self.name
+                ));
+            }
+        }
+
+        let mut actions: HashMap<&str, &SetAction> = HashMap::new();
+        for operation in self.all_operations() {
+            if operation.operation_type != OperationType::Upsert {
+                return Err(format!(
+                    "Bucket table '{}' only supports 'upsert' operations.",
+                    self.name
+                ));
+            }
+
+            if operation.where_clause.contains_key(&bucket.column) {
+                return Err(format!(
+                    "Bucket column '{}' in table '{}' is derived from the block timestamp \
+                     and must not be used in 'where'.",
+                    bucket.column, self.name
+                ));
+            }
+
+            for set_col in &operation.set {
+                if set_col.column == bucket.column {
+                    return Err(format!(
+                        "Bucket column '{}' in table '{}' is derived from the block timestamp \
+                         and must not be set.",
+                        bucket.column, self.name
+                    ));
+                }
+
+                if matches!(set_col.action, SetAction::Subtract | SetAction::Decrement)
+                    || set_col.action.is_array_action()
+                    || set_col.action == SetAction::CountDistinct
+                {
+                    return Err(format!(
+                        "Column '{}' in bucket table '{}' uses '{:?}' which is not supported \
+                         in rollups. Use add, count, max, min, first, last or avg.",
+                        set_col.column, self.name, set_col.action
+                    ));
+                }
+
+                if let Some(existing) = actions.insert(&set_col.column, &set_col.action) {
+                    if existing != &set_col.action {
+                        return Err(format!(
+                            "Column '{}' in bucket table '{}' is aggregated with both {:?} \
+                             and {:?}. Each rollup column must use a single action.",
+                            set_col.column, self.name, existing, set_col.action
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validates the `history` option. Versions are recorded per block, so history is only
+    /// meaningful for tables updating rows in place on a single chain.
+    pub fn validate_history(&self) -> Result<(), String> {
+        if !self.history {
+            return Ok(());
+        }
+
+        let unsupported = if self.is_insert_only() {
+            Some("insert-only tables already keep every row")
+        } else if self.cross_chain {
+            Some("block numbers of different chains can't be compared")
+        } else if self.bucket.is_some() {
+            Some("rollup buckets are already versioned by time")
+        } else if self.has_cron() {
+            Some("cron triggers don't write at event blocks")
+        } else {
+            None
+        };
+
+        match unsupported {
+            Some(reason) => {
+                Err(format!("Table '{}' cannot use 'history: true': {}.", self.name, reason))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Validates the secondary `indexes`. Every column must exist in the table and index
+    /// names must be unique and fit in a Postgres identifier (63 bytes).
+    pub fn validate_indexes(&self) -> Result<(), String> {
+        let mut columns: HashSet<&str> = self.columns.iter().map(|c| c.name.as_str()).collect();
+        if !self.cross_chain {
+            columns.insert("network");
+        }
+        if let Some(bucket) = &self.bucket {
+            columns.insert(bucket.column.as_str());
+        }
+        columns.insert(injected_columns::BLOCK_NUMBER);
+        if self.timestamp || self.bucket.is_some() {
+            columns.insert(injected_columns::BLOCK_TIMESTAMP);
+        }
+        if self.bucket.is_none() {
+            columns.extend([
+                injected_columns::TX_HASH,
+                injected_columns::BLOCK_HASH,
+                injected_columns::CONTRACT_ADDRESS,
+                injected_columns::RINDEXER_SEQUENCE_ID,
+            ]);
+        }
+
+        let mut names: HashSet<String> = HashSet::new();
+        for index in &self.indexes {
+            if index.columns.is_empty() {
+                return Err(format!("An index of table '{}' has no columns.", self.name));
+            }
+
+            if let Some(column) = index.columns.iter().find(|c| !columns.contains(c.as_str())) {
+                return Err(format!(
+                    "Index column '{}' is not a column of table '{}'.",
+                    column, self.name
+                ));
+            }
+
+            // The condition is interpolated into the CREATE INDEX statement
+            if let Some(condition) = &index.condition {
+                crate::indexer::reorg::validate_sql_condition(condition).map_err(|e| {
+                    format!("Invalid 'where' of an index of table '{}': {}", self.name, e)
+                })?;
+            }
+
+            let name = index.index_name(&self.name);
+            if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return Err(format!(
+                    "Index name '{}' of table '{}' may only contain letters, digits and '_'.",
+                    name, self.name
+                ));
+            }
+            if name.len() > 63 {
+                return Err(format!(
+                    "Index name '{}' of table '{}' is longer than 63 characters. \
+                     Give the index a shorter 'name'.",
+                    name, self.name
+                ));
+            }
+            if !names.insert(name.clone()) {
+                return Err(format!(
+                    "Index name '{}' is used twice in table '{}'. Give the indexes distinct names.",
+                    name, self.name
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Timestamp column rows are aged by for a `max_age` retention: the bucket column of
+    /// rollup tables, otherwise `rindexer_block_timestamp` when the table has one.
+    pub fn retention_timestamp_column(&self) -> Option<&str> {
+        match &self.bucket {
+            Some(bucket) => Some(bucket.column.as_str()),
+            None if self.timestamp => Some(injected_columns::BLOCK_TIMESTAMP),
+            None => None,
+        }
+    }
+
+    /// Validates `retention`. Rows are aged by the block they were last written at, so the
+    /// table needs a timestamp for `max_age` and a single chain for `max_blocks`. Only
+    /// insert-only and rollup tables qualify: pruning a row aggregating values would make the
+    /// next event for its key start the aggregate over from scratch.
+    pub fn validate_retention(&self) -> Result<(), String> {
+        let Some(retention) = &self.retention else {
+            return Ok(());
+        };
+
+        let policy = retention.policy().map_err(|e| format!("Table '{}': {}", self.name, e))?;
+        if self.history {
+            return Err(format!(
+                "Table '{}' cannot use both 'history: true' and 'retention', history keeps every version.",
+                self.name
+            ));
+        }
+        if !self.is_insert_only() && self.bucket.is_none() {
+            return Err(format!(
+                "Table '{}' uses 'retention' but updates rows in place, pruned rows would silently reset their values. Retention only applies to insert-only tables and 'bucket' rollups.",
+                self.name
+            ));
+        }
+        match policy {
+            RetentionPolicy::MaxAge(_) if self.retention_timestamp_column().is_none() => {
+                Err(format!(
+                    "Table '{}' uses 'retention.max_age' which requires 'timestamp: true' or a 'bucket'.",
+                    self.name
+                ))
+            }
+            RetentionPolicy::MaxBlocks(_) if self.cross_chain => Err(format!(
+                "Table '{}' is 'cross_chain' and can't use 'retention.max_blocks': block numbers of different chains can't be compared. Use 'max_age' instead.",
+                self.name
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Validates `partition_by`. Rows must never move between partitions, so block range
+    /// partitions are limited to insert-only tables.
+    pub fn validate_partition(&self) -> Result<(), String> {
+        let Some(partition) = &self.partition_by else {
+            return Ok(());
+        };
+
+        if partition.column == "network" {
+            if self.cross_chain {
+                return Err(format!(
+                    "Table '{}' is 'cross_chain' and has no network column to partition by.",
+                    self.name
+                ));
+            }
+            if partition.interval.is_some() {
+                return Err(format!(
+                    "Table '{}' partitions by network, 'interval' only applies to {}.",
+                    self.name,
+                    injected_columns::BLOCK_NUMBER
+                ));
+            }
+            return Ok(());
+        }
+
+        if partition.column != injected_columns::BLOCK_NUMBER {
+            return Err(format!(
+                "Table '{}' can only be partitioned by 'network' or '{}'.",
+                self.name,
+                injected_columns::BLOCK_NUMBER
+            ));
+        }
+        if !partition.interval.is_some_and(|interval| interval > 0) {
+            return Err(format!(
+                "Table '{}' partitions by {} and needs a block 'interval' greater than 0.",
+                self.name,
+                injected_columns::BLOCK_NUMBER
+            ));
+        }
+        if !self.is_insert_only() {
+            return Err(format!(
+                "Table '{}' partitions by {} which is only supported for insert-only tables, \
+                 rows updated in place would have to move between partitions.",
+                self.name,
+                injected_columns::BLOCK_NUMBER
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Validates the array actions (append/remove/set_union) and count_distinct. Array
+    /// actions need an array column, `remove` needs an existing row to remove from and a
+    /// distinct count is a number.
+    pub fn validate_array_actions(&self) -> Result<(), String> {
+        for operation in self.all_operations() {
+            for set_col in &operation.set {
+                let declared_type = self
+                    .columns
+                    .iter()
+                    .find(|c| c.name == set_col.column)
+                    .and_then(|c| c.column_type.as_ref());
+
+                if set_col.action.is_array_action() {
+                    if declared_type.is_some_and(|t| !matches!(t, ColumnType::Array(_))) {
+                        return Err(format!(
+                            "Column '{}' in table '{}' uses '{:?}' which requires an array \
+                             column type (e.g. 'address[]').",
+                            set_col.column, self.name, set_col.action
+                        ));
+                    }
+
+                    if set_col.action == SetAction::Remove
+                        && operation.operation_type != OperationType::Update
+                    {
+                        return Err(format!(
+                            "Column '{}' in table '{}' uses 'remove' which is only supported \
+                             in 'update' operations.",
+                            set_col.column, self.name
+                        ));
+                    }
+                }
+
+                if set_col.action == SetAction::CountDistinct
+                    && declared_type.is_some_and(|t| matches!(t, ColumnType::Array(_)))
+                {
+                    return Err(format!(
+                        "Column '{}' in table '{}' uses 'count_distinct' which stores a count \
+                         and can't be an array column.",
+                        set_col.column, self.name
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validates that `$scale()` values are written to `decimal(S)` columns, the only column
+    /// type that keeps their fractional digits.
+    pub fn validate_decimal_columns(&self) -> Result<(), String> {
+        for operation in self.all_operations() {
+            for set_col in &operation.set {
+                if !set_col.effective_value().contains(SCALE_PREFIX) {
+                    continue;
+                }
+                let declared_type = self
+                    .columns
+                    .iter()
+                    .find(|c| c.name == set_col.column)
+                    .and_then(|c| c.column_type.as_ref());
+                if declared_type.is_some_and(|t| !matches!(t, ColumnType::Decimal(_))) {
+                    return Err(format!(
+                        "Column '{}' in table '{}' is set from $scale() which requires a \
+                         'decimal(S)' column type.",
+                        set_col.column, self.name
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns every `$lookup(table, key).column` referenced by the event operations.
+    pub fn lookups(&self) -> Vec<LookupRef> {
+        let mut lookups: Vec<LookupRef> = Vec::new();
+        for operation in self.events.iter().flat_map(|e| e.operations.iter()) {
+            let values = operation
+                .where_clause
+                .values()
+                .map(String::as_str)
+                .chain(operation.set.iter().map(|s| s.effective_value()))
+                .chain(operation.condition());
+            for value in values {
+                for (_, _, lookup) in find_lookups(value) {
+                    if !lookups.contains(&lookup) {
+                        lookups.push(lookup);
+                    }
+                }
+            }
+        }
+        lookups
+    }
+
+    /// Returns the column `$lookup` finds rows of this table by. Rows must be identified by a
+    /// single `where` column, so global, insert-only and rollup tables can't be looked up.
+    pub fn lookup_key_column(&self) -> Result<&TableColumn, String> {
+        let key_columns = self.primary_key_columns();
+        if self.global || self.bucket.is_some() || self.is_insert_only() || key_columns.len() != 1 {
+            return Err(format!(
+                "Table '{}' can't be used with $lookup: rows must be identified by exactly one \
+                 'where' column.",
+                self.name
+            ));
+        }
+        self.columns.iter().find(|c| c.name == key_columns[0]).ok_or_else(|| {
+            format!("Column '{}' not found in table '{}'.", key_columns[0], self.name)
+        })
+    }
+
+    /// Validates that every `$lookup` targets an existing table and column.
+    pub fn validate_lookups(
+        &self,
+        contract_name: &str,
+        contracts: &[Contract],
+    ) -> Result<(), String> {
+        for lookup in self.lookups() {
+            let (_, target) = find_lookup_table(contracts, contract_name, &lookup.table)?;
+            target.lookup_key_column()?;
+            if !target.columns.iter().any(|c| c.name == lookup.column) {
+                return Err(format!(
+                    "$lookup column '{}' not found in table '{}'.",
+                    lookup.column, target.name
+                ));
+            }
+        }
+
+        // Conditions reading the current row are evaluated in SQL, lookups and tokens are not
+        for operation in self.all_operations() {
+            if let Some(condition) = operation.condition() {
+                let has_references =
+                    condition.contains(LOOKUP_PREFIX) || condition.contains(TOKEN_PREFIX);
+                if has_references && condition.contains('@') {
+                    return Err(format!(
+                        "Condition '{}' in table '{}' can't combine $lookup or $token with \
+                         @column references.",
+                        condition, self.name
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validates the `on_change` triggers of the table against the other tables of its
+    /// contract. Followed tables must exist and be keyed by their `where` columns, and tables
+    /// can't follow each other in a cycle.
+    pub fn validate_change_triggers(&self, tables: &[Table]) -> Result<(), String> {
+        if self.on_change.is_empty() {
+            return Ok(());
+        }
+
+        if self.bucket.is_some() {
+            return Err(format!(
+                "Bucket table '{}' cannot use 'on_change' triggers. \
+                 Buckets are derived from the block timestamp of each event.",
+                self.name
+            ));
+        }
+
+        for change_mapping in &self.on_change {
+            let Some(source) = tables.iter().find(|t| t.name == change_mapping.table) else {
+                return Err(format!(
+                    "Table '{}' follows table '{}' which is not defined in the same contract.",
+                    self.name, change_mapping.table
+                ));
+            };
+            if source.name == self.name {
+                return Err(format!("Table '{}' cannot follow its own changes.", self.name));
+            }
+            if source.bucket.is_some() || source.is_insert_only() {
+                return Err(format!(
+                    "Table '{}' cannot follow table '{}': only tables keyed by their 'where' \
+                     columns or global tables can be followed.",
+                    self.name, source.name
+                ));
+            }
+
+            for operation in &change_mapping.operations {
+                // Several rows can change for the same event, they'd share a sequence id
+                if operation.operation_type == OperationType::Insert {
+                    return Err(format!(
+                        "Table '{}' cannot use 'insert' in 'on_change' operations. \
+                         Use 'upsert' keyed by the changed row instead.",
+                        self.name
+                    ));
+                }
+                let has_lookup = operation
+                    .where_clause
+                    .values()
+                    .map(String::as_str)
+                    .chain(operation.set.iter().map(|s| s.effective_value()))
+                    .chain(operation.condition())
+                    .any(|value| value.contains(LOOKUP_PREFIX) || value.contains(TOKEN_PREFIX));
+                if has_lookup {
+                    return Err(format!(
+                        "Table '{}' cannot use $lookup or $token in 'on_change' operations.",
+                        self.name
+                    ));
+                }
+            }
+        }
+
+        // Walk the followed tables, reaching this table again means a cycle
+        let mut pending: Vec<&str> = self.on_change.iter().map(|c| c.table.as_str()).collect();
+        let mut visited: HashSet<&str> = HashSet::new();
+        while let Some(name) = pending.pop() {
+            if name == self.name {
+                return Err(format!(
+                    "Table '{}' follows the changes of a table that follows its own changes.",
+                    self.name
+                ));
+            }
+            if !visited.insert(name) {
+                continue;
+            }
+            if let Some(table) = tables.iter().find(|t| t.name == name) {
+                pending.extend(table.on_change.iter().map(|c| c.table.as_str()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds the columns rindexer maintains on behalf of the table: the bucket column of
+    /// rollup tables and the sample counters backing `avg` columns. Bucket tables always store
+    /// `rindexer_block_timestamp` since the bucket is derived from it.
+    pub fn expand_generated_columns(&mut self) {
+        if let Some(bucket) = &self.bucket {
+            self.timestamp = true;
+            match self.columns.iter_mut().find(|c| c.name == bucket.column) {
+                Some(column) => {
+                    column.column_type.get_or_insert(ColumnType::Timestamp);
+                }
+                None => self.columns.push(TableColumn {
+                    name: bucket.column.clone(),
+                    column_type: Some(ColumnType::Timestamp),
+                    nullable: false,
+                    default: None,
+                }),
+            }
+        }
+
+        let samples_columns: Vec<String> = self
+            .all_operations()
+            .flat_map(|op| op.set.iter())
+            .filter(|set_col| set_col.action == SetAction::Avg)
+            .map(|set_col| injected_columns::avg_samples(&set_col.column))
+            .collect();
+        for samples_column in samples_columns {
+            if !self.columns.iter().any(|c| c.name == samples_column) {
+                self.columns.push(TableColumn {
+                    name: samples_column,
+                    column_type: Some(ColumnType::Uint64),
+                    nullable: false,
+                    default: Some("0".to_string()),
+                });
+            }
+        }
+    }
+
+    /// The columns written with `count_distinct`, the values they count are kept in the
+    /// table's `_distinct_values` side table.
+    pub fn count_distinct_columns(&self) -> Vec<&str> {
+        let mut columns: Vec<&str> = Vec::new();
+        for set_col in self.all_operations().flat_map(|op| op.set.iter()) {
+            if set_col.action == SetAction::CountDistinct
+                && !columns.contains(&set_col.column.as_str())
+            {
+                columns.push(&set_col.column);
+            }
+        }
+        columns
+    }
+
     /// Validates that `$null` values are only used on columns marked as `nullable: true`.
     ///
     /// # Returns
@@ -586,6 +1293,8 @@ impl Table {
         &mut self,
         event_abi_types: &HashMap<String, HashMap<String, String>>,
     ) -> Result<(), String> {
+        self.expand_generated_columns();
+
         // Build a map of column_name -> inferred_type for each column
         let mut inferred_types: HashMap<String, ColumnType> = HashMap::new();
 
@@ -603,11 +1312,23 @@ impl Table {
 
                 // Check set columns
                 for set_col in &operation.set {
+                    // A distinct count is a number whatever the counted values are
+                    if set_col.action == SetAction::CountDistinct {
+                        inferred_types.entry(set_col.column.clone()).or_insert(ColumnType::Uint64);
+                        continue;
+                    }
+
                     let effective_value = set_col.effective_value();
                     if !effective_value.is_empty() {
                         if let Some(inferred) =
                             Self::infer_type_from_value(effective_value, event_types)
                         {
+                            // Array actions write single elements into an array column
+                            let inferred = if set_col.action.is_array_action() {
+                                ColumnType::Array(Box::new(inferred))
+                            } else {
+                                inferred
+                            };
                             inferred_types.entry(set_col.column.clone()).or_insert(inferred);
                         }
                     }
@@ -652,6 +1373,11 @@ impl Table {
         value_ref: &str,
         event_types: Option<&HashMap<String, String>>,
     ) -> Option<ColumnType> {
+        // The scale of a decimal column has to be chosen explicitly
+        if value_ref.contains(SCALE_PREFIX) {
+            return None;
+        }
+
         if Self::value_contains_arithmetic(value_ref) {
             return Some(ColumnType::Uint256);
         }
@@ -691,8 +1417,112 @@ impl Table {
     }
 }
 
+/// Finds the table a `$lookup` reads. `Contract.table` names the table of a specific
+/// contract, a bare name prefers the tables of `contract_name` and must otherwise be unique.
+pub fn find_lookup_table<'a>(
+    contracts: &'a [Contract],
+    contract_name: &str,
+    table_ref: &str,
+) -> Result<(&'a Contract, &'a Table), String> {
+    let (contract_filter, table_name) = match table_ref.split_once('.') {
+        Some((contract, table)) => (Some(contract), table),
+        None => (None, table_ref),
+    };
+
+    let matches: Vec<(&Contract, &Table)> = contracts
+        .iter()
+        .filter(|c| contract_filter.is_none_or(|name| c.name == name))
+        .flat_map(|c| c.tables.iter().flatten().map(move |t| (c, t)))
+        .filter(|(_, t)| t.name == table_name)
+        .collect();
+
+    if contract_filter.is_none() {
+        if let Some(own) = matches.iter().find(|(c, _)| c.name == contract_name) {
+            return Ok(*own);
+        }
+    }
+
+    match matches.as_slice() {
+        [single] => Ok(*single),
+        [] => Err(format!("$lookup table '{}' not found.", table_ref)),
+        _ => Err(format!(
+            "$lookup table '{}' is defined by several contracts, use 'Contract.{}' to pick one.",
+            table_ref, table_name
+        )),
+    }
+}
+
+/// Time bucketing for rollup tables (OHLC, hourly volume, daily active users...).
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct TableBucket {
+    /// Name of the `timestamp` column holding the start of each bucket.
+    /// It is created automatically if not declared and becomes part of the primary key.
+    pub column: String,
+
+    /// Bucket width like "5m", "1h" or "1d" (same syntax as cron intervals).
+    pub interval: String,
+}
+
+impl TableBucket {
+    /// Bucket width in seconds.
+    pub fn interval_secs(&self) -> Result<u64, String> {
+        parse_interval(&self.interval).map(|interval| interval.as_secs())
+    }
+
+    /// Start of the bucket containing `timestamp`, both in unix seconds.
+    pub fn bucket_start(timestamp: u64, interval_secs: u64) -> u64 {
+        timestamp - timestamp % interval_secs
+    }
+}
+
+/// A secondary index of a custom table. Postgres creates a B-tree index (partial when
+/// `where` is set), ClickHouse a `bloom_filter` data skipping index on the same columns.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct TableIndex {
+    /// Indexed columns in order. Table columns, `network` or injected `rindexer_*` columns.
+    pub columns: Vec<String>,
+
+    /// Index name suffix, defaults to the column names joined by `_`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// SQL condition turning the index into a partial index (Postgres only).
+    #[serde(rename = "where", default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+}
+
+impl TableIndex {
+    /// Name of the index in the database: `idx_{table}_{name}`.
+    pub fn index_name(&self, table_name: &str) -> String {
+        let suffix = self.name.clone().unwrap_or_else(|| self.columns.join("_"));
+        format!("idx_{}_{}", camel_to_snake(table_name), suffix)
+    }
+}
+
+/// Partitioning of a custom table.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct TablePartition {
+    /// Either `network` or `rindexer_block_number`.
+    pub column: String,
+
+    /// Number of blocks per partition, required when partitioning by block number.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval: Option<u64>,
+}
+
+impl TablePartition {
+    /// Blocks per partition when partitioning by `rindexer_block_number`.
+    pub fn block_range(&self) -> Option<u64> {
+        if self.column == injected_columns::BLOCK_NUMBER {
+            self.interval
+        } else {
+            None
+        }
+    }
+}
+
 /// A single column in a table.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct TableColumn {
     /// Column name
     pub name: String,
@@ -756,6 +1586,9 @@ pub enum ColumnType {
     Bool,
     Timestamp,
 
+    // Fixed point number with the given number of decimals, e.g. "decimal(18)"
+    Decimal(u8),
+
     // Array type (wraps any base type)
     Array(Box<ColumnType>),
 }
@@ -781,7 +1614,61 @@ impl serde::Serialize for ColumnType {
     }
 }
 
-impl ColumnType {
+impl JsonSchema for ColumnType {
+    fn schema_name() -> Cow<'static, str> {
+        "ColumnType".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        let type_strings: Vec<String> = ColumnType::BASE_TYPE_STRINGS
+            .iter()
+            .map(|t| t.to_string())
+            .chain(ColumnType::BASE_TYPE_STRINGS.iter().map(|t| format!("{t}[]")))
+            .collect();
+
+        json_schema!({
+            "description": "The column data type, suffix with `[]` for an array of that type. \
+                            `decimal(S)` stores a fixed point number with S decimals (0-76).",
+            "type": "string",
+            "anyOf": [
+                { "enum": type_strings },
+                { "pattern": "^decimal\\(([0-9]|[1-6][0-9]|7[0-6])\\)$" }
+            ]
+        })
+    }
+}
+
+impl ColumnType {
+    /// All base type strings accepted by [`ColumnType::from_type_string`].
+    pub const BASE_TYPE_STRINGS: &'static [&'static str] = &[
+        "uint8",
+        "uint16",
+        "uint32",
+        "uint64",
+        "uint128",
+        "uint256",
+        "int8",
+        "int16",
+        "int32",
+        "int64",
+        "int128",
+        "int256",
+        "address",
+        "bytes",
+        "bytes32",
+        "string",
+        "bool",
+        "timestamp",
+    ];
+
+    /// The type of the elements of an array type, or the type itself for scalars.
+    pub fn element_type(&self) -> &ColumnType {
+        match self {
+            ColumnType::Array(inner) => inner,
+            other => other,
+        }
+    }
+
     /// Convert to PostgreSQL type
     pub fn to_postgres_type(&self) -> String {
         match self {
@@ -797,6 +1684,7 @@ impl ColumnType {
             ColumnType::String => "TEXT".to_string(),
             ColumnType::Bool => "BOOLEAN".to_string(),
             ColumnType::Timestamp => "TIMESTAMPTZ".to_string(),
+            ColumnType::Decimal(scale) => format!("NUMERIC(76, {})", scale),
             ColumnType::Array(inner) => {
                 // Use TEXT[] for address arrays (CHAR(42)[] doesn't work well with parsers)
                 if **inner == ColumnType::Address {
@@ -829,6 +1717,7 @@ impl ColumnType {
             ColumnType::String => "String".to_string(),
             ColumnType::Bool => "Bool".to_string(),
             ColumnType::Timestamp => "DateTime('UTC')".to_string(),
+            ColumnType::Decimal(scale) => format!("Decimal256({})", scale),
             ColumnType::Array(inner) => format!("Array({})", inner.to_clickhouse_type()),
         }
     }
@@ -857,6 +1746,14 @@ impl ColumnType {
             "string" => ColumnType::String,
             "bool" => ColumnType::Bool,
             "timestamp" => ColumnType::Timestamp,
+            // Arrays of decimals are not supported
+            decimal if !is_array => {
+                let scale = decimal.strip_prefix("decimal(")?.strip_suffix(')')?.trim();
+                match scale.parse::<u8>() {
+                    Ok(scale) if scale <= MAX_DECIMAL_SCALE => ColumnType::Decimal(scale),
+                    _ => return None,
+                }
+            }
             _ => return None,
         };
 
@@ -888,6 +1785,7 @@ impl ColumnType {
             ColumnType::String => "string".to_string(),
             ColumnType::Bool => "bool".to_string(),
             ColumnType::Timestamp => "timestamp".to_string(),
+            ColumnType::Decimal(scale) => format!("decimal({})", scale),
             ColumnType::Array(inner) => format!("{}[]", inner.to_type_string()),
         }
     }
@@ -1003,8 +1901,22 @@ impl<'de> Deserialize<'de> for IterateBinding {
     }
 }
 
+impl JsonSchema for IterateBinding {
+    fn schema_name() -> Cow<'static, str> {
+        "IterateBinding".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "An array field to iterate over and its element alias, e.g. `$ids as id`.",
+            "type": "string",
+            "pattern": "^\\s*\\$\\S+ as \\S+\\s*$"
+        })
+    }
+}
+
 /// Maps an event to operations on a table.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct TableEventMapping {
     /// The event name (must exist in contract ABI)
     pub event: String,
@@ -1027,9 +1939,37 @@ pub struct TableEventMapping {
     pub operations: Vec<TableOperation>,
 }
 
+/// Operations run for every row of another table changed by a batch of events.
+/// Each changed row exposes its column values before and after the change as
+/// `$before_<column>` and `$after_<column>`, and the kind of change as `$change`
+/// (`insert`, `update` or `delete`).
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct TableChangeMapping {
+    /// Name of the table to follow, defined in the same contract
+    pub table: String,
+
+    /// Operations to perform for every changed row
+    pub operations: Vec<TableOperation>,
+}
+
+impl TableChangeMapping {
+    /// Field holding the kind of change: `insert`, `update` or `delete`.
+    pub const CHANGE_FIELD: &'static str = "change";
+
+    /// Field holding the value of `column` before the change.
+    pub fn before_field(column: &str) -> String {
+        format!("before_{}", column)
+    }
+
+    /// Field holding the value of `column` after the change.
+    pub fn after_field(column: &str) -> String {
+        format!("after_{}", column)
+    }
+}
+
 /// Cron-triggered operations for a table.
 /// Allows operations to run on a time-based schedule instead of (or in addition to) events.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct TableCronMapping {
     /// Simple interval like "5m", "1h", "30s", "1d".
     /// Either `interval` or `schedule` must be specified, but not both.
@@ -1054,6 +1994,7 @@ pub struct TableCronMapping {
         deserialize_with = "deserialize_option_u64_from_string",
         serialize_with = "serialize_option_u64_as_string"
     )]
+    #[schemars(schema_with = "block_number_schema")]
     pub start_block: Option<U64>,
 
     /// Ending block for historical sync.
@@ -1065,6 +2006,7 @@ pub struct TableCronMapping {
         deserialize_with = "deserialize_option_u64_from_string",
         serialize_with = "serialize_option_u64_as_string"
     )]
+    #[schemars(schema_with = "block_number_schema")]
     pub end_block: Option<U64>,
 
     /// Block interval for historical sync.
@@ -1076,6 +2018,7 @@ pub struct TableCronMapping {
         deserialize_with = "deserialize_option_u64_from_string",
         serialize_with = "serialize_option_u64_as_string"
     )]
+    #[schemars(schema_with = "block_number_schema")]
     pub block_interval: Option<U64>,
 
     /// Operations to perform on each cron tick.
@@ -1122,7 +2065,7 @@ pub fn parse_interval(interval: &str) -> Result<std::time::Duration, String> {
 }
 
 /// A single operation to perform when an event is received.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct TableOperation {
     /// The type of operation
     #[serde(rename = "type")]
@@ -1163,7 +2106,7 @@ impl TableOperation {
 }
 
 /// The type of database operation.
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum OperationType {
     /// Insert or update row (creates if not exists)
@@ -1177,7 +2120,7 @@ pub enum OperationType {
 }
 
 /// A column to set/update in an operation.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct SetColumn {
     /// The column name to update
     pub column: String,
@@ -1215,7 +2158,7 @@ impl SetColumn {
 }
 
 /// Action to perform when setting a column value.
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum SetAction {
     /// Replace the existing value
@@ -1232,12 +2175,55 @@ pub enum SetAction {
     Increment,
     /// Decrement by 1 (shorthand for subtract with value "1")
     Decrement,
+    /// Keep the first value written to the row, in event order
+    First,
+    /// Keep the latest value written to the row, in event order
+    Last,
+    /// Same as `first`, reads naturally in OHLC rollups
+    Open,
+    /// Same as `last`, reads naturally in OHLC rollups
+    Close,
+    /// Count events (shorthand for add with value "1")
+    Count,
+    /// Keep the running average of every value written to the row
+    Avg,
+    /// Append the value to an array column, duplicates are kept
+    Append,
+    /// Remove every occurrence of the value from an array column
+    Remove,
+    /// Add the value to an array column unless it is already present
+    #[serde(rename = "set_union")]
+    SetUnion,
+    /// Count the distinct values written to the row
+    #[serde(rename = "count_distinct")]
+    CountDistinct,
 }
 
 impl SetAction {
-    /// Returns true if this action doesn't require a value (increment/decrement)
+    /// Returns true if this action doesn't require a value (increment/decrement/count)
     pub fn is_counter_action(&self) -> bool {
-        matches!(self, SetAction::Increment | SetAction::Decrement)
+        matches!(self, SetAction::Increment | SetAction::Decrement | SetAction::Count)
+    }
+
+    /// Returns true if this action keeps the earliest value (first/open).
+    pub fn keeps_first(&self) -> bool {
+        matches!(self, SetAction::First | SetAction::Open)
+    }
+
+    /// Returns true if this action keeps the latest value (set/last/close).
+    pub fn keeps_last(&self) -> bool {
+        matches!(self, SetAction::Set | SetAction::Last | SetAction::Close)
+    }
+
+    /// Returns true if this action accumulates a sum (add/increment/count).
+    pub fn is_sum(&self) -> bool {
+        matches!(self, SetAction::Add | SetAction::Increment | SetAction::Count)
+    }
+
+    /// Returns true if this action writes single elements into an array column
+    /// (append/remove/set_union).
+    pub fn is_array_action(&self) -> bool {
+        matches!(self, SetAction::Append | SetAction::Remove | SetAction::SetUnion)
     }
 
     /// Returns the reverse action for reorg rollback, or None if not reversible.
@@ -1245,9 +2231,20 @@ impl SetAction {
         match self {
             SetAction::Add => Some(SetAction::Subtract),
             SetAction::Subtract => Some(SetAction::Add),
-            SetAction::Increment => Some(SetAction::Decrement),
+            SetAction::Increment | SetAction::Count => Some(SetAction::Decrement),
             SetAction::Decrement => Some(SetAction::Increment),
-            SetAction::Set | SetAction::Max | SetAction::Min => None,
+            SetAction::Set
+            | SetAction::Max
+            | SetAction::Min
+            | SetAction::First
+            | SetAction::Last
+            | SetAction::Open
+            | SetAction::Close
+            | SetAction::Avg
+            | SetAction::Append
+            | SetAction::Remove
+            | SetAction::SetUnion
+            | SetAction::CountDistinct => None,
         }
     }
 }
@@ -1260,11 +2257,31 @@ impl SetAction {
 /// - `false` / omitted: index at head with active reorg detection
 /// - `true`: use chain-specific default safe distance
 /// - integer (u64): override with custom block distance
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+/// - `finalized` / `safe`: follow the node's tagged block instead of a fixed distance
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum ReorgSafeDistance {
     Enabled(bool),
     Custom(u64),
+    Tag(BlockTag),
+}
+
+/// Block tags a node reports for blocks which are unlikely (`safe`) or unable (`finalized`) to
+/// be reorged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BlockTag {
+    Finalized,
+    Safe,
+}
+
+impl From<BlockTag> for BlockNumberOrTag {
+    fn from(tag: BlockTag) -> Self {
+        match tag {
+            BlockTag::Finalized => BlockNumberOrTag::Finalized,
+            BlockTag::Safe => BlockNumberOrTag::Safe,
+        }
+    }
 }
 
 impl Default for ReorgSafeDistance {
@@ -1275,22 +2292,96 @@ impl Default for ReorgSafeDistance {
 
 impl ReorgSafeDistance {
     /// Resolve to a concrete block distance, or None if disabled.
+    ///
+    /// Tagged modes resolve to the chain default, which is used whenever the tagged block can
+    /// not be fetched.
     pub fn resolve(&self, chain_id: u64) -> Option<u64> {
         match self {
-            ReorgSafeDistance::Enabled(true) => {
+            ReorgSafeDistance::Enabled(true) | ReorgSafeDistance::Tag(_) => {
                 Some(crate::indexer::reorg::reorg_safe_distance_for_chain(chain_id))
             }
             ReorgSafeDistance::Custom(blocks) => Some(*blocks),
             ReorgSafeDistance::Enabled(false) => None,
         }
     }
+
+    /// The block tag to track, if the distance follows one.
+    pub fn block_tag(&self) -> Option<BlockTag> {
+        match self {
+            ReorgSafeDistance::Tag(tag) => Some(*tag),
+            ReorgSafeDistance::Enabled(_) | ReorgSafeDistance::Custom(_) => None,
+        }
+    }
+}
+
+// ============================================================================
+// Retention
+// ============================================================================
+
+/// How long indexed rows are kept. Exactly one of the settings is required:
+/// - `max_age`: drop rows whose block timestamp is older than the interval ("30d", "12h")
+/// - `max_blocks`: drop rows more than this many blocks behind the chain head
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Retention {
+    /// Maximum timestamp age, same syntax as cron intervals.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age: Option<String>,
+
+    /// Maximum block age.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_blocks: Option<u64>,
+}
+
+/// A validated [`Retention`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetentionPolicy {
+    /// Keep rows at most this many seconds old.
+    MaxAge(u64),
+    /// Keep rows at most this many blocks behind the chain head.
+    MaxBlocks(u64),
+}
+
+/// Shortest `max_age`. Anything younger could still be rolled back by a reorg.
+pub const MIN_RETENTION_AGE_SECS: u64 = 60 * 60;
+
+impl Retention {
+    pub fn policy(&self) -> Result<RetentionPolicy, String> {
+        match (&self.max_age, self.max_blocks) {
+            (Some(max_age), None) => {
+                let secs = parse_interval(max_age)?.as_secs();
+                if secs < MIN_RETENTION_AGE_SECS {
+                    return Err(format!("retention max_age '{}' must be at least 1h", max_age));
+                }
+                Ok(RetentionPolicy::MaxAge(secs))
+            }
+            (None, Some(0)) => Err("retention max_blocks must be greater than 0".to_string()),
+            (None, Some(max_blocks)) => Ok(RetentionPolicy::MaxBlocks(max_blocks)),
+            (Some(_), Some(_)) => {
+                Err("retention can set either max_age or max_blocks, not both".to_string())
+            }
+            (None, None) => Err("retention requires max_age or max_blocks".to_string()),
+        }
+    }
+}
+
+// ============================================================================
+// Proxy
+// ============================================================================
+
+/// Proxy handling for a contract.
+/// - `auto`: resolve the implementation(s) behind the contract addresses (EIP-1967, beacon,
+///   EIP-1822, diamond) and merge their ABIs with the contract ABI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxySetting {
+    Auto,
 }
 
 // ============================================================================
 // Contract Struct
 // ============================================================================
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct Contract {
     pub name: String,
 
@@ -1303,6 +2394,7 @@ pub struct Contract {
         deserialize_with = "deserialize_events",
         skip_serializing_if = "Option::is_none"
     )]
+    #[schemars(schema_with = "include_events_schema")]
     pub include_events: Option<Vec<ContractEvent>>,
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -1325,7 +2417,16 @@ pub struct Contract {
 
     /// Custom indexing tables for aggregations (upsert, update, delete operations)
     #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(schema_with = "tables_schema")]
     pub tables: Option<Vec<Table>>,
+
+    /// Resolve proxy implementations and merge their ABIs, see [`ProxySetting`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ProxySetting>,
+
+    /// How long rows of the raw event tables are kept, see [`Retention`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention: Option<Retention>,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -1349,18 +2450,33 @@ impl Contract {
     }
 
     pub fn parse_abi(&self, project_path: &Path) -> Result<String, ParseAbiError> {
+        // `proxy: auto` contracts also merge the implementation ABIs resolved on startup
+        let implementation_abis = match self.proxy {
+            Some(ProxySetting::Auto) => implementation_abi_paths(project_path, &self.name)?,
+            None => vec![],
+        };
+
         match &self.abi {
-            StringOrArray::Single(abi_path) => {
+            StringOrArray::Single(abi_path) if implementation_abis.is_empty() => {
                 let full_path = get_full_path(project_path, abi_path)?;
                 let abi_str = fs::read_to_string(full_path)?;
                 Ok(abi_str)
             }
-            StringOrArray::Multiple(abis) => {
+            abi => {
+                let abi_paths = match abi {
+                    StringOrArray::Single(abi_path) => vec![abi_path],
+                    StringOrArray::Multiple(abis) => abis.iter().collect(),
+                };
+                let mut full_paths = abi_paths
+                    .into_iter()
+                    .map(|abi_path| get_full_path(project_path, abi_path))
+                    .collect::<Result<Vec<_>, _>>()?;
+                full_paths.extend(implementation_abis);
+
                 let mut unique_entries = HashSet::new();
                 let mut merged_abi_value = Vec::new();
 
-                for abi_path in abis {
-                    let full_path = get_full_path(project_path, abi_path)?;
+                for full_path in full_paths {
                     let abi_str = fs::read_to_string(full_path)?;
                     let abi_value: Value = serde_json::from_str(&abi_str)?;
 
@@ -1473,6 +2589,44 @@ impl Contract {
             None => false, // No include_events means no raw event storage
         }
     }
+
+    /// Retention of the raw table of an event. The event setting overrides the contract one.
+    pub fn event_retention(&self, event_name: &str) -> Option<&Retention> {
+        self.include_events
+            .iter()
+            .flatten()
+            .find(|e| e.name == event_name)
+            .and_then(|e| e.retention.as_ref())
+            .or(self.retention.as_ref())
+    }
+
+    /// Validates the retention of the raw event tables. `max_age` needs the block timestamps
+    /// of the events, so both the event's `timestamps`, falling back to the global one, and the
+    /// global `timestamps` the indexer fetches them with have to be true.
+    pub fn validate_retention(&self, timestamps: Option<bool>) -> Result<(), String> {
+        if self.retention.is_some() && self.include_events.is_none() {
+            return Err(
+                "'retention' applies to raw event tables but the contract has no 'include_events'. Set 'retention' on the tables instead."
+                    .to_string(),
+            );
+        }
+
+        for event in self.include_events.iter().flatten() {
+            let Some(retention) = event.retention.as_ref().or(self.retention.as_ref()) else {
+                continue;
+            };
+            if let RetentionPolicy::MaxAge(_) = retention.policy()? {
+                if !timestamps.unwrap_or(false) || event.timestamps == Some(false) {
+                    return Err(format!(
+                        "'retention.max_age' on the raw event table of {} requires 'timestamps: true'.",
+                        event.name
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -1502,8 +2656,8 @@ mod tests {
         assert_eq!(
             contract.include_events,
             Some(vec![
-                ContractEvent { name: "Transfer".to_string(), timestamps: None },
-                ContractEvent { name: "Approval".to_string(), timestamps: None }
+                ContractEvent { name: "Transfer".to_string(), timestamps: None, retention: None },
+                ContractEvent { name: "Approval".to_string(), timestamps: None, retention: None }
             ])
         );
     }
@@ -1531,8 +2685,16 @@ mod tests {
         assert_eq!(
             contract.include_events,
             Some(vec![
-                ContractEvent { name: "Transfer".to_string(), timestamps: Some(true) },
-                ContractEvent { name: "Approval".to_string(), timestamps: Some(false) }
+                ContractEvent {
+                    name: "Transfer".to_string(),
+                    timestamps: Some(true),
+                    retention: None
+                },
+                ContractEvent {
+                    name: "Approval".to_string(),
+                    timestamps: Some(false),
+                    retention: None
+                }
             ])
         );
     }
@@ -2016,6 +3178,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_decimal_column_type() {
+        let decimal = ColumnType::from_type_string("decimal(18)").unwrap();
+        assert_eq!(decimal, ColumnType::Decimal(18));
+        assert_eq!(decimal.to_type_string(), "decimal(18)");
+        assert_eq!(decimal.to_postgres_type(), "NUMERIC(76, 18)");
+        assert_eq!(decimal.to_clickhouse_type(), "Decimal256(18)");
+
+        assert_eq!(ColumnType::from_type_string("decimal(0)"), Some(ColumnType::Decimal(0)));
+        assert_eq!(ColumnType::from_type_string("decimal(77)"), None);
+        assert_eq!(ColumnType::from_type_string("decimal"), None);
+        assert_eq!(ColumnType::from_type_string("decimal(6)[]"), None);
+    }
+
     #[test]
     fn test_from_type_string_unknown_returns_none() {
         assert_eq!(ColumnType::from_type_string("UINT256"), None); // case-sensitive
@@ -2264,7 +3440,13 @@ mod tests {
                 operations: ops,
             }],
             cron: None,
+            on_change: vec![],
             timestamp: false,
+            bucket: None,
+            history: false,
+            indexes: vec![],
+            partition_by: None,
+            retention: None,
             database: None,
         }
     }
@@ -2305,7 +3487,13 @@ mod tests {
             columns: vec![],
             events: vec![],
             cron: None,
+            on_change: vec![],
             timestamp: false,
+            bucket: None,
+            history: false,
+            indexes: vec![],
+            partition_by: None,
+            retention: None,
             database: None,
         };
         assert!(!table.is_insert_only());
@@ -2428,4 +3616,628 @@ mod tests {
         assert_eq!(SetAction::Max.reverse(), None);
         assert_eq!(SetAction::Min.reverse(), None);
     }
+
+    #[test]
+    fn test_set_action_rollup_actions() {
+        let actions: Vec<SetAction> =
+            serde_yaml::from_str("[first, last, open, close, count, avg]").unwrap();
+        assert_eq!(
+            actions,
+            vec![
+                SetAction::First,
+                SetAction::Last,
+                SetAction::Open,
+                SetAction::Close,
+                SetAction::Count,
+                SetAction::Avg,
+            ]
+        );
+        assert_eq!(SetAction::Count.reverse(), Some(SetAction::Decrement));
+        assert_eq!(SetAction::Open.reverse(), None);
+        assert_eq!(SetAction::Avg.reverse(), None);
+        assert!(SetAction::Open.keeps_first());
+        assert!(SetAction::Close.keeps_last());
+        assert!(SetAction::Count.is_sum());
+    }
+
+    // =========================================================================
+    // Table::bucket
+    // =========================================================================
+
+    fn make_bucket_table(set: Vec<(&str, SetAction)>) -> Table {
+        let mut operation = make_operation(OperationType::Upsert, &[("pool", "$pool")]);
+        operation.set = set
+            .into_iter()
+            .map(|(column, action)| SetColumn {
+                column: column.to_string(),
+                action,
+                value: Some("$price".to_string()),
+            })
+            .collect();
+        let mut table = make_table_with_event_ops(vec![operation]);
+        table.bucket =
+            Some(TableBucket { column: "bucket".to_string(), interval: "1h".to_string() });
+        table
+    }
+
+    #[test]
+    fn test_bucket_start() {
+        assert_eq!(TableBucket::bucket_start(7_250, 3_600), 7_200);
+        assert_eq!(TableBucket::bucket_start(7_200, 3_600), 7_200);
+    }
+
+    #[test]
+    fn test_primary_key_columns_include_bucket() {
+        let table = make_bucket_table(vec![("open", SetAction::Open)]);
+        assert_eq!(table.primary_key_columns(), vec!["pool", "bucket"]);
+        assert!(table.is_primary_key_column("bucket"));
+    }
+
+    #[test]
+    fn test_validate_bucket_ok() {
+        let table = make_bucket_table(vec![
+            ("open", SetAction::Open),
+            ("high", SetAction::Max),
+            ("volume", SetAction::Add),
+            ("price", SetAction::Avg),
+        ]);
+        assert!(table.validate_bucket().is_ok());
+    }
+
+    #[test]
+    fn test_validate_bucket_invalid_interval_err() {
+        let mut table = make_bucket_table(vec![("open", SetAction::Open)]);
+        table.bucket.as_mut().unwrap().interval = "1x".to_string();
+        assert!(table.validate_bucket().is_err());
+    }
+
+    #[test]
+    fn test_validate_bucket_subtract_err() {
+        let table = make_bucket_table(vec![("volume", SetAction::Subtract)]);
+        assert!(table.validate_bucket().is_err());
+    }
+
+    #[test]
+    fn test_validate_bucket_insert_err() {
+        let mut table = make_bucket_table(vec![]);
+        table.events[0].operations = vec![make_operation(OperationType::Insert, &[])];
+        assert!(table.validate_bucket().is_err());
+    }
+
+    #[test]
+    fn test_validate_bucket_set_bucket_column_err() {
+        let table = make_bucket_table(vec![("bucket", SetAction::Set)]);
+        assert!(table.validate_bucket().is_err());
+    }
+
+    #[test]
+    fn test_validate_bucket_mixed_actions_err() {
+        let table = make_bucket_table(vec![("price", SetAction::Max), ("price", SetAction::Min)]);
+        assert!(table.validate_bucket().is_err());
+    }
+
+    #[test]
+    fn test_validate_bucket_cross_chain_err() {
+        let mut table = make_bucket_table(vec![("open", SetAction::Open)]);
+        table.cross_chain = true;
+        assert!(table.validate_bucket().is_err());
+    }
+
+    #[test]
+    fn test_validate_history() {
+        let mut table = make_table_with_event_ops(vec![make_operation(
+            OperationType::Upsert,
+            &[("holder", "$to")],
+        )]);
+        table.history = true;
+        assert!(table.validate_history().is_ok());
+
+        table.cross_chain = true;
+        assert!(table.validate_history().is_err());
+    }
+
+    #[test]
+    fn test_validate_history_insert_only_err() {
+        let mut table = make_table_with_event_ops(vec![make_operation(OperationType::Insert, &[])]);
+        table.history = true;
+        assert!(table.validate_history().is_err());
+    }
+
+    #[test]
+    fn test_validate_indexes_and_partition() {
+        let mut table: Table = serde_yaml::from_str(
+            r#"
+            name: transfers
+            columns:
+              - name: from
+                type: address
+              - name: value
+                type: uint256
+            indexes:
+              - columns: [from, rindexer_block_number]
+              - name: large
+                columns: [value]
+                where: "value > 1000"
+            partition_by:
+              column: rindexer_block_number
+              interval: 1000000
+            events:
+              - event: Transfer
+                operations:
+                  - type: insert
+                    set:
+                      - column: from
+                        action: set
+                        value: $from
+                      - column: value
+                        action: set
+                        value: $value
+            "#,
+        )
+        .unwrap();
+        assert!(table.validate_indexes().is_ok());
+        assert!(table.validate_partition().is_ok());
+        assert_eq!(
+            table.indexes[0].index_name(&table.name),
+            "idx_transfers_from_rindexer_block_number"
+        );
+        assert_eq!(table.indexes[1].index_name(&table.name), "idx_transfers_large");
+        assert_eq!(table.partition_by.as_ref().unwrap().block_range(), Some(1_000_000));
+
+        let mut unknown = table.clone();
+        unknown.indexes[1].columns.push("missing".to_string());
+        let err = unknown.validate_indexes().unwrap_err();
+        assert!(err.contains("not a column"), "{}", err);
+
+        let mut injected = table.clone();
+        injected.indexes[1].condition = Some("value > 1000; DROP TABLE transfers".to_string());
+        let err = injected.validate_indexes().unwrap_err();
+        assert!(err.contains("Invalid 'where'"), "{}", err);
+
+        let mut duplicate = table.clone();
+        duplicate.indexes.push(table.indexes[0].clone());
+        let err = duplicate.validate_indexes().unwrap_err();
+        assert!(err.contains("used twice"), "{}", err);
+
+        // Rows updated in place can't move between block range partitions
+        table.events[0].operations[0].operation_type = OperationType::Upsert;
+        let err = table.validate_partition().unwrap_err();
+        assert!(err.contains("insert-only"), "{}", err);
+
+        table.partition_by = Some(TablePartition { column: "network".to_string(), interval: None });
+        assert!(table.validate_partition().is_ok());
+        assert_eq!(table.partition_by.as_ref().unwrap().block_range(), None);
+
+        table.cross_chain = true;
+        assert!(table.validate_partition().is_err());
+    }
+
+    #[test]
+    fn test_expand_generated_columns() {
+        let mut table = make_bucket_table(vec![("price", SetAction::Avg)]);
+        table.expand_generated_columns();
+
+        assert!(table.timestamp);
+        let bucket = table.columns.iter().find(|c| c.name == "bucket").unwrap();
+        assert_eq!(bucket.column_type, Some(ColumnType::Timestamp));
+        let samples = table.columns.iter().find(|c| c.name == "rindexer_price_samples").unwrap();
+        assert_eq!(samples.column_type, Some(ColumnType::Uint64));
+        assert_eq!(samples.default.as_deref(), Some("0"));
+
+        // Running it again must not duplicate columns
+        table.expand_generated_columns();
+        assert_eq!(table.columns.len(), 2);
+    }
+
+    // =========================================================================
+    // Array actions
+    // =========================================================================
+
+    fn make_array_table(op_type: OperationType, action: SetAction) -> Table {
+        let mut operation = make_operation(op_type, &[("pool", "$pool")]);
+        operation.set = vec![SetColumn {
+            column: "holders".to_string(),
+            action,
+            value: Some("$to".to_string()),
+        }];
+        make_table_with_event_ops(vec![operation])
+    }
+
+    fn make_lookup_contract(name: &str, tables_yaml: &str) -> Contract {
+        let yaml = format!(
+            "name: {name}\nabi: ./abis/{name}.abi.json\ndetails:\n  - network: ethereum\ntables:\n{tables_yaml}"
+        );
+        serde_yaml::from_str(&yaml).unwrap()
+    }
+
+    const TOKENS_TABLE: &str = r#"
+  - name: tokens
+    columns:
+      - name: token
+        type: address
+      - name: decimals
+        type: uint8
+    events:
+      - event: TokenAdded
+        operations:
+          - type: upsert
+            where:
+              token: $token
+            set:
+              - column: decimals
+                value: $decimals
+"#;
+
+    const SWAPS_TABLE: &str = r#"
+  - name: swaps
+    columns:
+      - name: pool
+        type: address
+      - name: volume
+        type: uint256
+    events:
+      - event: Swap
+        operations:
+          - type: upsert
+            where:
+              pool: $pool
+            if: "$lookup(tokens, $token).decimals > 0"
+            set:
+              - column: volume
+                action: add
+                value: $amount / 10 ^ $lookup(tokens, $token).decimals
+"#;
+
+    #[test]
+    fn test_table_lookups() {
+        let pool = make_lookup_contract("Pool", SWAPS_TABLE);
+        let swaps = &pool.tables.as_ref().unwrap()[0];
+        let lookups = swaps.lookups();
+        assert_eq!(lookups.len(), 1);
+        assert_eq!(lookups[0].table, "tokens");
+        assert_eq!(lookups[0].key, "$token");
+        assert_eq!(lookups[0].column, "decimals");
+    }
+
+    #[test]
+    fn test_validate_lookups() {
+        let pool = make_lookup_contract("Pool", SWAPS_TABLE);
+        let registry = make_lookup_contract("Registry", TOKENS_TABLE);
+        let swaps = &pool.tables.as_ref().unwrap()[0];
+
+        let contracts = vec![pool.clone(), registry.clone()];
+        assert!(swaps.validate_lookups("Pool", &contracts).is_ok());
+        let (contract, table) = find_lookup_table(&contracts, "Pool", "Registry.tokens").unwrap();
+        assert_eq!((contract.name.as_str(), table.name.as_str()), ("Registry", "tokens"));
+
+        // Unknown table
+        let err = swaps.validate_lookups("Pool", std::slice::from_ref(&pool)).unwrap_err();
+        assert!(err.contains("not found"), "{}", err);
+
+        // Same table name in several contracts must be qualified
+        let other_registry = make_lookup_contract("OtherRegistry", TOKENS_TABLE);
+        let err =
+            swaps.validate_lookups("Pool", &[pool.clone(), registry, other_registry]).unwrap_err();
+        assert!(err.contains("Contract.tokens"), "{}", err);
+    }
+
+    #[test]
+    fn test_validate_lookups_requires_single_key_column() {
+        let pool = make_lookup_contract("Pool", SWAPS_TABLE);
+        let mut registry = make_lookup_contract("Registry", TOKENS_TABLE);
+        let tokens = &mut registry.tables.as_mut().unwrap()[0];
+        tokens.global = true;
+        tokens.events[0].operations[0].where_clause.clear();
+
+        let swaps = &pool.tables.as_ref().unwrap()[0];
+        let err = swaps.validate_lookups("Pool", &[pool.clone(), registry]).unwrap_err();
+        assert!(err.contains("exactly one 'where' column"), "{}", err);
+    }
+
+    const CHANGE_TABLES: &str = r#"
+  - name: balances
+    columns:
+      - name: holder
+        type: address
+      - name: balance
+        type: uint256
+    events:
+      - event: Transfer
+        operations:
+          - type: upsert
+            where:
+              holder: $to
+            set:
+              - column: balance
+                action: add
+                value: $value
+  - name: holder_count
+    global: true
+    columns:
+      - name: holders
+        type: uint256
+    on_change:
+      - table: balances
+        operations:
+          - type: upsert
+            if: "$before_balance == 0 && $after_balance > 0"
+            set:
+              - column: holders
+                action: increment
+"#;
+
+    #[test]
+    fn test_validate_change_triggers() {
+        let token = make_lookup_contract("Token", CHANGE_TABLES);
+        let mut tables = token.tables.unwrap();
+        assert!(tables[1].has_triggers());
+        assert_eq!(tables[1].all_operations().count(), 1);
+        assert!(tables[1].validate_change_triggers(&tables).is_ok());
+
+        // Followed table must be defined in the same contract
+        let err = tables[1].validate_change_triggers(&tables[1..]).unwrap_err();
+        assert!(err.contains("not defined in the same contract"), "{}", err);
+
+        // Several rows can change per event, inserts would share a sequence id
+        let mut inserting = tables[1].clone();
+        inserting.on_change[0].operations[0].operation_type = OperationType::Insert;
+        let err = inserting.validate_change_triggers(&tables).unwrap_err();
+        assert!(err.contains("'insert'"), "{}", err);
+
+        // Tables can't follow each other in a cycle
+        tables[0].on_change = vec![TableChangeMapping {
+            table: "holder_count".to_string(),
+            operations: vec![make_operation(OperationType::Upsert, &[("holder", "$holder")])],
+        }];
+        let err = tables[1].validate_change_triggers(&tables).unwrap_err();
+        assert!(err.contains("follows its own changes"), "{}", err);
+    }
+
+    #[test]
+    fn test_set_action_array_actions() {
+        let actions: Vec<SetAction> =
+            serde_yaml::from_str("[append, remove, set_union, count_distinct]").unwrap();
+        assert_eq!(
+            actions,
+            vec![
+                SetAction::Append,
+                SetAction::Remove,
+                SetAction::SetUnion,
+                SetAction::CountDistinct,
+            ]
+        );
+        assert!(SetAction::SetUnion.is_array_action());
+        assert!(!SetAction::CountDistinct.is_array_action());
+        assert_eq!(SetAction::Append.reverse(), None);
+        assert_eq!(SetAction::CountDistinct.reverse(), None);
+    }
+
+    #[test]
+    fn test_validate_array_actions() {
+        let mut table = make_array_table(OperationType::Upsert, SetAction::Append);
+        assert!(table.validate_array_actions().is_ok());
+
+        table.columns.push(TableColumn {
+            name: "holders".to_string(),
+            column_type: Some(ColumnType::Address),
+            nullable: false,
+            default: None,
+        });
+        let err = table.validate_array_actions().unwrap_err();
+        assert!(err.contains("requires an array column type"));
+
+        table.columns[0].column_type = Some(ColumnType::Array(Box::new(ColumnType::Address)));
+        assert!(table.validate_array_actions().is_ok());
+    }
+
+    #[test]
+    fn test_validate_decimal_columns() {
+        let mut operation = make_operation(OperationType::Upsert, &[("pool", "$pool")]);
+        operation.set = vec![SetColumn {
+            column: "volume".to_string(),
+            action: SetAction::Add,
+            value: Some("$scale($amount, 6)".to_string()),
+        }];
+        let mut table = make_table_with_event_ops(vec![operation]);
+        table.columns.push(TableColumn {
+            name: "volume".to_string(),
+            column_type: Some(ColumnType::Uint256),
+            nullable: false,
+            default: None,
+        });
+        let err = table.validate_decimal_columns().unwrap_err();
+        assert!(err.contains("requires a 'decimal(S)' column type"), "{}", err);
+
+        table.columns[0].column_type = Some(ColumnType::Decimal(6));
+        assert!(table.validate_decimal_columns().is_ok());
+    }
+
+    #[test]
+    fn test_retention_policy() {
+        let retention: Retention = serde_yaml::from_str("max_age: 30d").unwrap();
+        assert_eq!(retention.policy(), Ok(RetentionPolicy::MaxAge(30 * 24 * 60 * 60)));
+
+        let retention: Retention = serde_yaml::from_str("max_blocks: 216000").unwrap();
+        assert_eq!(retention.policy(), Ok(RetentionPolicy::MaxBlocks(216_000)));
+
+        for invalid in ["max_age: 10m", "max_blocks: 0", "{max_age: 1d, max_blocks: 100}", "{}"] {
+            let retention: Retention = serde_yaml::from_str(invalid).unwrap();
+            assert!(retention.policy().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_validate_retention() {
+        let operation = make_operation(OperationType::Insert, &[]);
+        let mut table = make_table_with_event_ops(vec![operation]);
+        table.retention = Some(Retention { max_age: Some("30d".to_string()), max_blocks: None });
+        let err = table.validate_retention().unwrap_err();
+        assert!(err.contains("requires 'timestamp: true'"), "{}", err);
+
+        table.timestamp = true;
+        assert!(table.validate_retention().is_ok());
+        assert_eq!(table.retention_timestamp_column(), Some(injected_columns::BLOCK_TIMESTAMP));
+
+        table.retention = Some(Retention { max_age: None, max_blocks: Some(1_000) });
+        table.cross_chain = true;
+        assert!(table.validate_retention().unwrap_err().contains("cross_chain"));
+    }
+
+    #[test]
+    fn test_validate_retention_rejects_tables_updated_in_place() {
+        let retention = Some(Retention { max_age: None, max_blocks: Some(1_000) });
+
+        let operation = make_operation(OperationType::Upsert, &[("pool", "$pool")]);
+        let mut upsert = make_table_with_event_ops(vec![operation]);
+        upsert.retention = retention.clone();
+        let err = upsert.validate_retention().unwrap_err();
+        assert!(err.contains("updates rows in place"), "{}", err);
+
+        let mut rollup = make_bucket_table(vec![("volume", SetAction::Add)]);
+        rollup.retention = retention;
+        assert!(rollup.validate_retention().is_ok());
+    }
+
+    #[test]
+    fn test_event_retention_overrides_contract() {
+        let yaml = r#"
+            name: Pool
+            abi: ./abis/Pool.abi.json
+            details:
+              - network: ethereum
+            retention:
+              max_age: 30d
+            include_events:
+              - Mint
+              - name: Swap
+                retention:
+                  max_blocks: 216000
+        "#;
+        let contract: Contract = serde_yaml::from_str(yaml).unwrap();
+
+        assert_eq!(contract.event_retention("Mint").unwrap().max_age.as_deref(), Some("30d"));
+        assert_eq!(contract.event_retention("Swap").unwrap().max_blocks, Some(216_000));
+        assert!(contract.validate_retention(Some(true)).is_ok());
+        assert!(contract.validate_retention(None).unwrap_err().contains("timestamps: true"));
+    }
+
+    #[test]
+    fn test_retention_max_age_uses_event_timestamps() {
+        let yaml = r#"
+            name: Pool
+            abi: ./abis/Pool.abi.json
+            details:
+              - network: ethereum
+            retention:
+              max_age: 30d
+            include_events:
+              - name: Mint
+                timestamps: true
+              - name: Swap
+                timestamps: false
+        "#;
+        let mut contract: Contract = serde_yaml::from_str(yaml).unwrap();
+
+        // the global flag doesn't cover an event turning timestamps off
+        let err = contract.validate_retention(Some(true)).unwrap_err();
+        assert!(err.contains("Swap"), "{err}");
+
+        // an event turning them on still needs them fetched
+        contract.include_events.as_mut().unwrap().retain(|e| e.name == "Mint");
+        assert!(contract.validate_retention(Some(true)).is_ok());
+        assert!(contract.validate_retention(Some(false)).unwrap_err().contains("Mint"));
+    }
+
+    #[test]
+    fn test_validate_array_actions_remove_requires_update() {
+        let table = make_array_table(OperationType::Upsert, SetAction::Remove);
+        assert!(table.validate_array_actions().unwrap_err().contains("only supported"));
+
+        let table = make_array_table(OperationType::Update, SetAction::Remove);
+        assert!(table.validate_array_actions().is_ok());
+    }
+
+    #[test]
+    fn test_validate_bucket_rejects_array_actions() {
+        let table = make_bucket_table(vec![("price", SetAction::SetUnion)]);
+        assert!(table.validate_bucket().is_err());
+    }
+
+    #[test]
+    fn test_resolve_column_types_array_actions() {
+        let mut table = make_array_table(OperationType::Upsert, SetAction::Append);
+        let mut operation = make_operation(OperationType::Upsert, &[("pool", "$pool")]);
+        operation.set = vec![SetColumn {
+            column: "unique_holders".to_string(),
+            action: SetAction::CountDistinct,
+            value: Some("$to".to_string()),
+        }];
+        table.events[0].operations.push(operation);
+        table.columns = vec![
+            TableColumn {
+                name: "pool".to_string(),
+                column_type: None,
+                nullable: false,
+                default: None,
+            },
+            TableColumn {
+                name: "holders".to_string(),
+                column_type: None,
+                nullable: true,
+                default: None,
+            },
+            TableColumn {
+                name: "unique_holders".to_string(),
+                column_type: None,
+                nullable: false,
+                default: None,
+            },
+        ];
+
+        let event_types = HashMap::from([(
+            "Transfer".to_string(),
+            HashMap::from([
+                ("pool".to_string(), "address".to_string()),
+                ("to".to_string(), "address".to_string()),
+            ]),
+        )]);
+        table.resolve_column_types(&event_types).unwrap();
+
+        let column_type = |name: &str| {
+            table.columns.iter().find(|c| c.name == name).unwrap().resolved_type().clone()
+        };
+        assert_eq!(column_type("holders"), ColumnType::Array(Box::new(ColumnType::Address)));
+        assert_eq!(column_type("unique_holders"), ColumnType::Uint64);
+        // counted values live in a side table, not in a column of the table
+        assert!(!table.columns.iter().any(|c| c.name == "rindexer_unique_holders_distinct"));
+    }
+
+    #[test]
+    fn test_contract_details_start_block() {
+        let yaml = r#"
+            - network: ethereum
+              address: "0xae78736Cd615f374D3085123A210448E74Fc6393"
+              start_block: 20090000
+            - network: base
+              address: "0xae78736Cd615f374D3085123A210448E74Fc6393"
+              start_block: "20090000"
+            - network: arbitrum
+              address: "0xae78736Cd615f374D3085123A210448E74Fc6393"
+              start_block: auto
+            - network: optimism
+              address: "0xae78736Cd615f374D3085123A210448E74Fc6393"
+        "#;
+
+        let details: Vec<ContractDetails> = serde_yaml::from_str(yaml).unwrap();
+
+        assert_eq!(details[0].start_block, Some(StartBlock::Number(U64::from(20090000))));
+        assert_eq!(details[1].start_block_number(), Some(U64::from(20090000)));
+        assert_eq!(details[2].start_block, Some(StartBlock::Auto));
+        assert_eq!(details[2].start_block_number(), None);
+        assert_eq!(details[3].start_block, None);
+
+        let serialized = serde_yaml::to_string(&details[2]).unwrap();
+        assert!(serialized.contains("start_block: auto"), "{serialized}");
+        assert!(serde_yaml::from_str::<ContractDetails>("network: x\nstart_block: soon").is_err());
+    }
 }
@@ -1,6 +1,7 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{borrow::Cow, collections::HashMap, str::FromStr};
 
 use alloy::{primitives::U64, transports::http::reqwest::header::HeaderMap};
+use schemars::{JsonSchema, Schema, SchemaGenerator};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_yaml::Value;
 
@@ -17,9 +18,13 @@ use crate::{
         contract::Contract,
         global::Global,
         graphql::GraphQLSettings,
-        native_transfer::{deserialize_native_transfers, NativeTransferDetails, NativeTransfers},
+        native_transfer::{
+            deserialize_native_transfers, NativeTransferDetails, NativeTransferFullOrSimple,
+            NativeTransfers,
+        },
         network::Network,
         phantom::Phantom,
+        schema::string_enum_schema,
         storage::Storage,
     },
 };
@@ -41,7 +46,7 @@ use crate::{
 ///     ethereum: "0x1234..."
 ///     polygon: "0x5678..."
 /// ```
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum ConstantValue {
     /// A simple string constant (same for all networks)
@@ -97,11 +102,24 @@ pub enum ProjectType {
     NoCode,
 }
 
+impl JsonSchema for ProjectType {
+    fn schema_name() -> Cow<'static, str> {
+        "ProjectType".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        string_enum_schema(
+            &["rust", "no-code"],
+            "`no-code` runs the indexer straight from the yaml, `rust` generates a rust project.",
+        )
+    }
+}
+
 fn default_storage() -> Storage {
     Storage::default()
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct Manifest {
     pub name: String,
 
@@ -133,6 +151,7 @@ pub struct Manifest {
 
     #[serde(default)]
     #[serde(deserialize_with = "deserialize_native_transfers")]
+    #[schemars(with = "NativeTransferFullOrSimple")]
     pub native_transfers: NativeTransfers,
 
     pub contracts: Vec<Contract>,
@@ -145,6 +164,12 @@ pub struct Manifest {
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub graphql: Option<GraphQLSettings>,
+
+    /// `tables:` of the contracts using table templates as written in the yaml, keyed by
+    /// contract name. Set by `read_manifest_raw` so `write_manifest` writes the templates back
+    /// rather than the tables they expanded to.
+    #[serde(skip)]
+    pub templated_tables: HashMap<String, Value>,
 }
 
 impl Manifest {
@@ -190,13 +215,15 @@ impl Manifest {
                         }).collect::<Vec<_>>(),
                         abi: first_factory.abi.clone().into(),
                         dependency_events: None,
-                        include_events: Some(vec![ContractEvent { name: first_factory.event_name.clone(), timestamps: None }]),
+                        include_events: Some(vec![ContractEvent { name: first_factory.event_name.clone(), timestamps: None, retention: None }]),
                         index_event_in_order: contract.index_event_in_order.clone(),
                         reorg_safe_distance: contract.reorg_safe_distance,
                         generate_csv: contract.generate_csv,
                         streams: None,
                         chat: None,
                         tables: None,
+                        proxy: None,
+                        retention: None,
                     };
 
                     // Get event names from include_events or tables before moving contract
@@ -263,6 +290,12 @@ impl Manifest {
         self.all_contracts().iter().any(|c| c.details.iter().any(|p| p.end_block.is_none()))
     }
 
+    pub fn has_any_auto_start_block(&self) -> bool {
+        self.contracts
+            .iter()
+            .any(|c| c.details.iter().any(|d| d.start_block.is_some_and(|s| s.is_auto())))
+    }
+
     /// Returns true if any contract OR native-transfer network is configured
     /// for live indexing (i.e., has `end_block` unset). Native-transfer-only
     /// networks still need the live-indexing path — contracts alone don't
@@ -1,10 +1,11 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 pub fn default_graphql_port() -> u16 {
     3001
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct GraphQLSettings {
     #[serde(default = "default_graphql_port")]
     pub port: u16,
@@ -1,4 +1,6 @@
+pub mod artifacts;
 pub mod chat;
+pub mod compose;
 pub mod config;
 pub mod contract;
 pub mod core;
@@ -8,6 +10,8 @@ pub mod native_transfer;
 pub mod network;
 pub mod phantom;
 pub mod reth;
+pub mod schema;
 pub mod storage;
 pub mod stream;
+pub mod table_template;
 pub mod yaml;
@@ -1,9 +1,11 @@
 use std::str::FromStr;
 
 use alloy::primitives::U64;
+use schemars::JsonSchema;
 use serde::{Deserialize, Deserializer, Serialize};
 
 use super::core::serialize_option_u64_as_string;
+use super::schema::block_number_schema;
 use crate::manifest::{chat::ChatConfig, contract::Table, stream::StreamsConfig};
 
 #[derive(Serialize, Deserialize)]
@@ -13,7 +15,7 @@ enum StringOrNum {
     Num(u64),
 }
 
-#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Default, Deserialize, Serialize, JsonSchema)]
 pub enum TraceProcessingMethod {
     #[serde(rename = "trace_block")]
     TraceBlock,
@@ -43,7 +45,7 @@ where
     }
 }
 
-#[derive(Debug, Hash, PartialEq, Eq, Serialize, Deserialize, Clone)]
+#[derive(Debug, Hash, PartialEq, Eq, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct NativeTransferDetails {
     pub network: String,
 
@@ -56,6 +58,7 @@ pub struct NativeTransferDetails {
         deserialize_with = "deserialize_option_u64_from_string_or_num",
         serialize_with = "serialize_option_u64_as_string"
     )]
+    #[schemars(schema_with = "block_number_schema")]
     pub start_block: Option<U64>,
 
     #[serde(
@@ -64,6 +67,7 @@ pub struct NativeTransferDetails {
         deserialize_with = "deserialize_option_u64_from_string_or_num",
         serialize_with = "serialize_option_u64_as_string"
     )]
+    #[schemars(schema_with = "block_number_schema")]
     pub end_block: Option<U64>,
 }
 
@@ -72,7 +76,7 @@ fn default_enabled() -> bool {
 }
 
 /// The normalized 'Native Transfers' config.
-#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct NativeTransfers {
     #[serde(default = "default_enabled")]
     pub enabled: bool,
@@ -113,7 +117,7 @@ pub struct NativeTransfers {
 /// native_transfers: true
 /// ```
 #[allow(clippy::large_enum_variant)]
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(untagged)]
 pub enum NativeTransferFullOrSimple {
     Simple(bool),
@@ -1,7 +1,9 @@
+use std::borrow::Cow;
 use std::fmt;
 use std::time::Duration;
 
 use alloy::primitives::U64;
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
 use serde::de::Visitor;
 use serde::{de, Deserialize, Deserializer, Serialize};
 
@@ -13,6 +15,7 @@ use tokio::time::sleep;
 use super::core::{deserialize_option_u64_from_string, serialize_option_u64_as_string};
 #[cfg(feature = "reth")]
 use super::reth::RethConfig;
+use super::schema::block_number_schema;
 
 #[cfg(feature = "reth")]
 use crate::notifications::ChainStateNotification;
@@ -23,7 +26,7 @@ use crate::reth::node::start_reth_node_with_exex;
 #[cfg(feature = "reth")]
 use reth::cli::Commands;
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
 pub struct ReorgHandlingConfig {
     #[serde(default = "default_reorg_enabled")]
     pub enabled: bool,
@@ -38,7 +41,13 @@ fn default_reorg_enabled() -> bool {
     true
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LogArchiveConfig {
+    /// Directory holding the `cryo logs --hex` JSON files, relative to the project.
+    pub path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct Network {
     pub name: String,
 
@@ -46,6 +55,12 @@ pub struct Network {
 
     pub rpc: String,
 
+    /// Optional WebSocket RPC used to subscribe to `newHeads`, new blocks and reorgs are then
+    /// pushed to the indexer instead of waiting for the next poll. Polling takes over whenever
+    /// the socket is disconnected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ws_rpc: Option<String>,
+
     /// Poll the latest block at a defined frequency. It is recommended that this frequency be a
     /// multiple faster than the networks block time to ensure fast indexing.
     ///
@@ -62,6 +77,7 @@ pub struct Network {
         deserialize_with = "deserialize_option_u64_from_string",
         serialize_with = "serialize_option_u64_as_string"
     )]
+    #[schemars(schema_with = "block_number_schema")]
     pub max_block_range: Option<U64>,
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -70,6 +86,13 @@ pub struct Network {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub disable_logs_bloom_checks: Option<bool>,
 
+    /// Live index every event of this network with one shared poller. The head is polled once,
+    /// the logs of all events are fetched with a combined `eth_getLogs` and fanned out to the
+    /// events, instead of every event polling the RPC on its own. Defaults to `false`, every
+    /// event having its own live poller.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shared_live_fetcher: Option<bool>,
+
     /// Custom Multicall3 contract address for this network.
     /// If not specified, uses the standard address 0xcA11bde05977b3631167028862bE2a173976CA11.
     /// Set to "disabled" to disable Multicall3 batching for this network.
@@ -77,12 +100,18 @@ pub struct Network {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub multicall3_address: Option<String>,
 
+    /// Local archive of historic logs, historic sync reads the blocks it holds from disk and only
+    /// uses the RPC for the blocks after it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_archive: Option<LogArchiveConfig>,
+
     /// Reth configuration for this network
     #[serde(default, skip_serializing_if = "Option::is_none")]
     #[cfg(feature = "reth")]
     pub reth: Option<RethConfig>,
 
     #[cfg(not(feature = "reth"))]
+    #[schemars(skip)]
     pub reth: Option<()>,
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -139,7 +168,7 @@ pub enum AddressFiltering {
     MaxAddressPerGetLogsRequest(usize),
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct AddressFilteringConfig {
     pub max_address_per_get_logs_request: usize,
 }
@@ -169,7 +198,25 @@ impl<'de> Deserialize<'de> for AddressFiltering {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl JsonSchema for AddressFiltering {
+    fn schema_name() -> Cow<'static, str> {
+        "AddressFiltering".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let config = generator.subschema_for::<AddressFilteringConfig>();
+        json_schema!({
+            "description": "Filter factory/multi-address logs `in-memory`, or cap the number of \
+                addresses sent in a single `eth_getLogs` request.",
+            "anyOf": [
+                { "type": "string", "const": "in-memory" },
+                config
+            ]
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct GetLogsSettings {
     pub address_filtering: AddressFiltering,
 }
@@ -226,6 +273,24 @@ impl<'de> Deserialize<'de> for BlockPollFrequency {
     }
 }
 
+impl JsonSchema for BlockPollFrequency {
+    fn schema_name() -> Cow<'static, str> {
+        "BlockPollFrequency".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "`rapid`, `optimized`, a divisor of the network block time such as \
+                `/3`, or a fixed poll rate in milliseconds such as `500`.",
+            "anyOf": [
+                { "type": "string", "enum": ["rapid", "optimized"] },
+                { "type": "string", "pattern": "^(/)?[0-9]+$" },
+                { "type": "integer", "minimum": 0 }
+            ]
+        })
+    }
+}
+
 /// Wait for IPC socket file to be ready
 pub async fn wait_for_ipc_ready(ipc_path: &str) -> Result<(), eyre::Error> {
     use alloy::providers::{IpcConnect, Provider, ProviderBuilder};
@@ -1,5 +1,6 @@
 #![cfg(feature = "reth")]
 use reth::cli::Cli;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 /// Default value for logging field
@@ -8,7 +9,7 @@ fn default_true() -> bool {
 }
 
 /// Configuration for Reth node and ExEx
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RethConfig {
     /// Whether to enable Reth integration
     pub enabled: bool,
@@ -0,0 +1,223 @@
+//! JSON Schema for `rindexer.yaml`.
+//!
+//! The schema is generated from the manifest types themselves, so any field added to
+//! [`Manifest`] or its children is picked up automatically. Types with hand-written serde
+//! implementations (`ColumnType`, `BlockPollFrequency`, `IterateBinding`, ...) provide their own
+//! `JsonSchema` impls next to their deserializers, and the helpers below cover the foreign types
+//! (alloy `U64`, `Address`, `ValueOrArray`) we accept in the yaml.
+//!
+//! Editors using the YAML language server can pick the schema up with a modeline:
+//!
+//! ```yaml
+//! # yaml-language-server: $schema=./rindexer.schema.json
+//! ```
+
+use schemars::{generate::SchemaSettings, json_schema, Schema, SchemaGenerator};
+
+use crate::manifest::{
+    compose::{EXTENDS_KEY, INCLUDE_KEY},
+    contract::{ContractEvent, FilterDetailsYaml, Table},
+    core::Manifest,
+    table_template::TableTemplateEntry,
+};
+
+pub const SCHEMA_FILE_NAME: &str = "rindexer.schema.json";
+
+/// An address, or an `${ENV}` variable substituted with one when the manifest is read.
+const ADDRESS_PATTERN: &str = r"^(0x[0-9a-fA-F]{40}|\$\{[^}]+\})$";
+
+/// A block number, or an `${ENV}` variable substituted with one when the manifest is read.
+const BLOCK_NUMBER_PATTERN: &str = r"^([0-9]+|\$\{[^}]+\})$";
+
+/// Generate the JSON Schema (draft-07) describing `rindexer.yaml`.
+///
+/// Draft-07 is used as it has the widest support across editor tooling.
+pub fn generate_manifest_schema() -> Schema {
+    let mut schema = SchemaSettings::draft07()
+        .for_deserialize()
+        .into_generator()
+        .into_root_schema_for::<Manifest>();
+
+    // `include` and `extends` are resolved while composing the manifest so they aren't fields of
+    // `Manifest` itself
+    if let Some(properties) = schema
+        .as_object_mut()
+        .and_then(|schema| schema.get_mut("properties"))
+        .and_then(|properties| properties.as_object_mut())
+    {
+        properties.insert(
+            INCLUDE_KEY.to_string(),
+            compose_paths_schema("Yaml files, or folders of yaml files, merged into this file"),
+        );
+        properties.insert(
+            EXTENDS_KEY.to_string(),
+            compose_paths_schema("Base manifests this file is layered over"),
+        );
+    }
+
+    schema
+}
+
+/// A path or a list of paths, relative to the file they are in.
+fn compose_paths_schema(description: &str) -> serde_json::Value {
+    serde_json::json!({
+        "description": description,
+        "anyOf": [
+            { "type": "string" },
+            { "type": "array", "items": { "type": "string" } }
+        ]
+    })
+}
+
+/// Generate the `rindexer.yaml` JSON Schema as a pretty printed JSON string.
+pub fn generate_manifest_schema_json() -> Result<String, serde_json::Error> {
+    serde_json::to_string_pretty(&generate_manifest_schema())
+}
+
+/// Block numbers are read through `deserialize_option_u64_from_string`, which accepts both a
+/// plain yaml integer and a numeric string.
+pub(crate) fn block_number_schema(_: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+        "anyOf": [
+            { "type": "integer", "minimum": 0 },
+            { "type": "string", "pattern": BLOCK_NUMBER_PATTERN }
+        ]
+    })
+}
+
+/// A single address or a list of addresses.
+pub(crate) fn address_or_array_schema(_: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+        "anyOf": [
+            { "type": "string", "pattern": ADDRESS_PATTERN },
+            { "type": "array", "items": { "type": "string", "pattern": ADDRESS_PATTERN } }
+        ]
+    })
+}
+
+/// A single string or a list of strings.
+pub(crate) fn string_or_array_schema(_: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+        "anyOf": [
+            { "type": "string" },
+            { "type": "array", "items": { "type": "string" } }
+        ]
+    })
+}
+
+/// A single filter or a list of filters.
+pub(crate) fn filter_or_array_schema(generator: &mut SchemaGenerator) -> Schema {
+    let filter = generator.subschema_for::<FilterDetailsYaml>();
+    json_schema!({
+        "anyOf": [
+            filter,
+            { "type": "array", "items": filter }
+        ]
+    })
+}
+
+/// `include_events` accepts either bare event names or `{ name, timestamps }` objects.
+pub(crate) fn include_events_schema(generator: &mut SchemaGenerator) -> Schema {
+    let event = generator.subschema_for::<ContractEvent>();
+    json_schema!({
+        "type": "array",
+        "items": {
+            "anyOf": [
+                { "type": "string" },
+                event
+            ]
+        }
+    })
+}
+
+/// `tables` accepts full table definitions or `{ template, ... }` entries expanded on read.
+pub(crate) fn tables_schema(generator: &mut SchemaGenerator) -> Schema {
+    let table = generator.subschema_for::<Table>();
+    let template = generator.subschema_for::<TableTemplateEntry>();
+    json_schema!({
+        "type": "array",
+        "items": {
+            "anyOf": [
+                table,
+                template
+            ]
+        }
+    })
+}
+
+/// Shorthand for the schema of a type that only accepts one of a fixed set of strings.
+pub(crate) fn string_enum_schema(values: &[&str], description: &str) -> Schema {
+    json_schema!({
+        "type": "string",
+        "enum": values,
+        "description": description
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::contract::ColumnType;
+
+    #[test]
+    fn test_manifest_schema_has_required_root_fields() {
+        let schema = generate_manifest_schema();
+        let required = schema.get("required").and_then(|r| r.as_array()).unwrap();
+
+        for field in ["name", "project_type", "networks", "contracts"] {
+            assert!(
+                required.iter().any(|r| r == field),
+                "expected `{field}` to be required, got {required:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_manifest_schema_accepts_compose_keys() {
+        let schema = generate_manifest_schema();
+        let properties = schema.get("properties").and_then(|p| p.as_object()).unwrap();
+
+        for key in [INCLUDE_KEY, EXTENDS_KEY] {
+            assert!(properties.contains_key(key), "missing property `{key}`");
+        }
+    }
+
+    #[test]
+    fn test_manifest_schema_exposes_table_definitions() {
+        let schema = generate_manifest_schema();
+        let definitions = schema.get("definitions").and_then(|d| d.as_object()).unwrap();
+
+        for definition in
+            ["Contract", "Table", "TableColumn", "SetAction", "ColumnType", "TableTemplateEntry"]
+        {
+            assert!(definitions.contains_key(definition), "missing definition `{definition}`");
+        }
+    }
+
+    #[test]
+    fn test_column_type_schema_includes_arrays() {
+        let schema =
+            SchemaSettings::draft07().into_generator().into_root_schema_for::<ColumnType>();
+        let values = schema.get("enum").and_then(|e| e.as_array()).unwrap();
+
+        assert!(values.iter().any(|v| v == "uint256"));
+        assert!(values.iter().any(|v| v == "address[]"));
+        assert!(values.iter().all(|v| ColumnType::from_type_string(v.as_str().unwrap()).is_some()));
+    }
+
+    #[test]
+    fn test_address_pattern_accepts_env_variables() {
+        let pattern = regex::Regex::new(ADDRESS_PATTERN).unwrap();
+
+        assert!(pattern.is_match("0xae78736Cd615f374D3085123A210448E74Fc6393"));
+        assert!(pattern.is_match("${TOKEN_ADDRESS}"));
+        assert!(!pattern.is_match("0xae78736Cd615f374D3085123A210448E74Fc639"));
+        assert!(!pattern.is_match("${TOKEN_ADDRESS"));
+    }
+
+    #[test]
+    fn test_manifest_schema_serializes() {
+        let json = generate_manifest_schema_json().unwrap();
+        assert!(json.contains("\"$schema\": \"http://json-schema.org/draft-07/schema#\""));
+    }
+}
@@ -1,5 +1,6 @@
 use std::path::Path;
 
+use schemars::JsonSchema;
 use serde::de::Error;
 use serde::{Deserialize, Deserializer, Serialize};
 use tracing::info;
@@ -18,7 +19,7 @@ use crate::{
     manifest::contract::Contract,
 };
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct ForeignKey {
     pub contract_name: String,
 
@@ -27,7 +28,7 @@ pub struct ForeignKey {
     pub event_input_name: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct ForeignKeys {
     pub contract_name: String,
 
@@ -39,12 +40,12 @@ pub struct ForeignKeys {
     pub foreign_keys: Vec<ForeignKey>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct EventIndex {
     pub event_input_names: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct ContractEventsIndexes {
     pub name: String,
 
@@ -54,7 +55,7 @@ pub struct ContractEventsIndexes {
     pub events: Vec<EventIndexes>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct EventIndexes {
     pub name: String,
 
@@ -64,7 +65,7 @@ pub struct EventIndexes {
     pub indexes: Vec<EventIndex>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct PostgresIndexes {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub global_injected_parameters: Option<Vec<String>>,
@@ -73,7 +74,7 @@ pub struct PostgresIndexes {
     pub contracts: Option<Vec<ContractEventsIndexes>>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct PostgresDetails {
     pub enabled: bool,
 
@@ -90,7 +91,7 @@ pub struct PostgresDetails {
     pub disable_create_tables: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct ClickhouseDetails {
     pub enabled: bool,
 
@@ -105,7 +106,7 @@ fn default_csv_path() -> String {
     "./generated_csv".to_string()
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct CsvDetails {
     pub enabled: bool,
 
@@ -116,7 +117,7 @@ pub struct CsvDetails {
     pub disable_create_headers: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Default, Clone)]
+#[derive(Debug, Serialize, Default, Clone, JsonSchema)]
 pub struct Storage {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub postgres: Option<PostgresDetails>,
@@ -1,13 +1,14 @@
-use std::path::Path;
+use std::{borrow::Cow, path::Path};
 
 use lapin::ExchangeKind;
+use schemars::{JsonSchema, Schema, SchemaGenerator};
 use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::{Map, Value};
 use tokio::fs;
 
-use crate::types::aws_config::AwsConfig;
+use crate::{manifest::schema::string_enum_schema, types::aws_config::AwsConfig};
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum StreamDeliveryMode {
     #[default]
@@ -15,7 +16,7 @@ pub enum StreamDeliveryMode {
     Finalized,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct StreamEvent {
     pub event_name: String,
 
@@ -26,7 +27,7 @@ pub struct StreamEvent {
     pub alias: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct SNSStreamTopicConfig {
     pub prefix_id: Option<String>,
     pub topic_arn: String,
@@ -37,13 +38,13 @@ pub struct SNSStreamTopicConfig {
     pub delivery: Option<StreamDeliveryMode>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct SNSStreamConfig {
     pub aws_config: AwsConfig,
     pub topics: Vec<SNSStreamTopicConfig>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct WebhookStreamConfig {
     pub endpoint: String,
     pub shared_secret: String,
@@ -54,7 +55,7 @@ pub struct WebhookStreamConfig {
     pub delivery: Option<StreamDeliveryMode>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct RedisStreamConfig {
     pub connection_uri: String,
     #[serde(default = "default_pool_size")]
@@ -66,7 +67,7 @@ fn default_pool_size() -> u32 {
     50
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct RedisStreamStreamConfig {
     pub stream_name: String,
     pub networks: Vec<String>,
@@ -113,7 +114,20 @@ impl<'de> Deserialize<'de> for ExchangeKindWrapper {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl JsonSchema for ExchangeKindWrapper {
+    fn schema_name() -> Cow<'static, str> {
+        "ExchangeKind".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        string_enum_schema(
+            &["direct", "fanout", "topic"],
+            "The RabbitMQ exchange type, only direct, topic and fanout exchanges are supported.",
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct RabbitMQStreamQueueConfig {
     pub exchange: String,
     pub exchange_type: ExchangeKindWrapper,
@@ -127,7 +141,7 @@ pub struct RabbitMQStreamQueueConfig {
     pub delivery: Option<StreamDeliveryMode>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct RabbitMQStreamConfig {
     pub url: String,
     pub exchanges: Vec<RabbitMQStreamQueueConfig>,
@@ -165,7 +179,7 @@ impl RabbitMQStreamConfig {
 }
 
 #[cfg(feature = "kafka")]
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct KafkaStreamQueueConfig {
     pub topic: String,
 
@@ -179,7 +193,7 @@ pub struct KafkaStreamQueueConfig {
 }
 
 #[cfg(feature = "kafka")]
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct KafkaStreamConfig {
     pub brokers: Vec<String>,
     pub security_protocol: String,
@@ -197,7 +211,7 @@ pub struct KafkaStreamConfig {
     pub topics: Vec<KafkaStreamQueueConfig>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct CloudflareQueuesStreamQueueConfig {
     pub queue_id: String,
     pub networks: Vec<String>,
@@ -207,14 +221,14 @@ pub struct CloudflareQueuesStreamQueueConfig {
     pub delivery: Option<StreamDeliveryMode>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct CloudflareQueuesStreamConfig {
     pub api_token: String,
     pub account_id: String,
     pub queues: Vec<CloudflareQueuesStreamQueueConfig>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct StreamsConfig {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub sns: Option<SNSStreamConfig>,
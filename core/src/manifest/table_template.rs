@@ -0,0 +1,904 @@
+//! Table templates for the token tables nearly every indexer needs.
+//!
+//! An entry of `tables:` with `template: erc20_balances` is expanded into the full table
+//! definition before the manifest is read. The event field names come from the contract ABI, so
+//! a token naming the `Transfer` fields `src`/`dst`/`wad` gets the same table as one naming them
+//! `from`/`to`/`value`. `fields:` maps template fields to ABI fields explicitly and every other
+//! key of the entry is merged over the generated table, so `name`, `timestamp`, `columns` and the
+//! rest of the table options can be overridden.
+use std::{collections::HashMap, path::Path};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+
+use crate::{
+    abi::ABIItem,
+    manifest::{compose::merge, contract::Contract},
+};
+
+pub const TEMPLATE_KEY: &str = "template";
+pub const FIELDS_KEY: &str = "fields";
+
+const ZERO_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
+
+#[derive(thiserror::Error, Debug)]
+pub enum TableTemplateError {
+    #[error("Invalid table template in contract {0}: {1}")]
+    InvalidEntry(String, String),
+
+    #[error("Unknown table template '{0}' in contract {1}, expected one of: {2}")]
+    UnknownTemplate(String, String, String),
+
+    #[error("Could not read the ABI of contract {0} for table templates: {1}")]
+    CouldNotReadAbi(String, String),
+
+    #[error("Table template {0} requires the event {1} in the ABI of contract {2}")]
+    MissingEvent(&'static str, String, String),
+
+    #[error("Table template {0} has no field '{1}' to map in contract {2}")]
+    UnknownTemplateField(&'static str, String, String),
+
+    #[error(
+        "Table template {0} could not find the '{1}' field of event {2} in contract {3}, map it with `fields`"
+    )]
+    UnmappedField(&'static str, &'static str, &'static str, String),
+
+    #[error(
+        "Table template {0} maps '{1}' to '{2}' but event {3} of contract {4} has no such field"
+    )]
+    UnknownEventField(&'static str, &'static str, String, &'static str, String),
+
+    #[error("Could not build table template {0}: {1}")]
+    CouldNotBuildTable(&'static str, serde_yaml::Error),
+}
+
+/// A table template usable with `template:`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TableTemplate {
+    /// Balance of every holder from ERC20 `Transfer` events.
+    Erc20Balances,
+    /// Allowance of every (owner, spender) pair from ERC20 `Approval` events.
+    Erc20Allowances,
+    /// Owner of every token id from ERC721 `Transfer` events.
+    Erc721Owners,
+    /// Balance of every (holder, token id) pair from ERC1155 `TransferSingle`/`TransferBatch`.
+    Erc1155Balances,
+}
+
+/// An event a template reads, with the template fields in ABI order.
+struct TemplateEvent {
+    name: &'static str,
+    /// (template field, ABI type) of every input of the standard event, None for inputs the
+    /// table doesn't read.
+    fields: &'static [(Option<&'static str>, &'static str)],
+    required: bool,
+}
+
+impl TemplateEvent {
+    fn signature(&self) -> String {
+        let types: Vec<&str> = self.fields.iter().map(|(_, type_)| *type_).collect();
+        format!("{}({})", self.name, types.join(","))
+    }
+}
+
+/// A table entry using a template, as written in the yaml. Only used for the JSON schema.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TableTemplateEntry {
+    /// The template to expand.
+    pub template: TableTemplate,
+
+    /// The table name, the template name by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Maps template fields to the event fields of the ABI, e.g. `value: wad`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fields: Option<HashMap<String, String>>,
+}
+
+impl TableTemplate {
+    pub const ALL: [TableTemplate; 4] = [
+        TableTemplate::Erc20Balances,
+        TableTemplate::Erc20Allowances,
+        TableTemplate::Erc721Owners,
+        TableTemplate::Erc1155Balances,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TableTemplate::Erc20Balances => "erc20_balances",
+            TableTemplate::Erc20Allowances => "erc20_allowances",
+            TableTemplate::Erc721Owners => "erc721_owners",
+            TableTemplate::Erc1155Balances => "erc1155_balances",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|template| template.name() == name)
+    }
+
+    fn events(&self) -> &'static [TemplateEvent] {
+        match self {
+            TableTemplate::Erc20Balances => &[TemplateEvent {
+                name: "Transfer",
+                fields: &[
+                    (Some("from"), "address"),
+                    (Some("to"), "address"),
+                    (Some("value"), "uint256"),
+                ],
+                required: true,
+            }],
+            TableTemplate::Erc20Allowances => &[TemplateEvent {
+                name: "Approval",
+                fields: &[
+                    (Some("owner"), "address"),
+                    (Some("spender"), "address"),
+                    (Some("value"), "uint256"),
+                ],
+                required: true,
+            }],
+            TableTemplate::Erc721Owners => &[TemplateEvent {
+                name: "Transfer",
+                fields: &[
+                    (None, "address"),
+                    (Some("to"), "address"),
+                    (Some("token_id"), "uint256"),
+                ],
+                required: true,
+            }],
+            TableTemplate::Erc1155Balances => &[
+                TemplateEvent {
+                    name: "TransferSingle",
+                    fields: &[
+                        (None, "address"),
+                        (Some("from"), "address"),
+                        (Some("to"), "address"),
+                        (Some("id"), "uint256"),
+                        (Some("value"), "uint256"),
+                    ],
+                    required: true,
+                },
+                TemplateEvent {
+                    name: "TransferBatch",
+                    fields: &[
+                        (None, "address"),
+                        (Some("from"), "address"),
+                        (Some("to"), "address"),
+                        (Some("ids"), "uint256[]"),
+                        (Some("values"), "uint256[]"),
+                    ],
+                    required: false,
+                },
+            ],
+        }
+    }
+
+    /// The table yaml with the resolved ABI field names. Events missing from `fields` are
+    /// optional events the ABI doesn't have.
+    fn table_yaml(&self, fields: &ResolvedFields) -> String {
+        let f = |event, field| fields.get(event, field);
+        match self {
+            TableTemplate::Erc20Balances => format!(
+                r#"
+name: {name}
+columns:
+  - name: holder
+  - name: balance
+    default: "0"
+events:
+  - event: Transfer
+    operations:
+      - type: upsert
+        where:
+          holder: ${from}
+        if: "${from} != {zero}"
+        set:
+          - column: balance
+            action: subtract
+            value: ${value}
+      - type: upsert
+        where:
+          holder: ${to}
+        if: "${to} != {zero}"
+        set:
+          - column: balance
+            action: add
+            value: ${value}
+"#,
+                name = self.name(),
+                from = f("Transfer", "from"),
+                to = f("Transfer", "to"),
+                value = f("Transfer", "value"),
+                zero = ZERO_ADDRESS,
+            ),
+            TableTemplate::Erc20Allowances => format!(
+                r#"
+name: {name}
+columns:
+  - name: owner
+  - name: spender
+  - name: amount
+    default: "0"
+events:
+  - event: Approval
+    operations:
+      - type: upsert
+        where:
+          owner: ${owner}
+          spender: ${spender}
+        set:
+          - column: amount
+            action: set
+            value: ${value}
+"#,
+                name = self.name(),
+                owner = f("Approval", "owner"),
+                spender = f("Approval", "spender"),
+                value = f("Approval", "value"),
+            ),
+            TableTemplate::Erc721Owners => format!(
+                r#"
+name: {name}
+columns:
+  - name: token_id
+  - name: owner
+events:
+  - event: Transfer
+    operations:
+      - type: upsert
+        where:
+          token_id: ${token_id}
+        set:
+          - column: owner
+            action: set
+            value: ${to}
+"#,
+                name = self.name(),
+                token_id = f("Transfer", "token_id"),
+                to = f("Transfer", "to"),
+            ),
+            TableTemplate::Erc1155Balances => {
+                let mut yaml = format!(
+                    r#"
+name: {name}
+columns:
+  - name: holder
+  - name: token_id
+  - name: balance
+    default: "0"
+events:
+  - event: TransferSingle
+    operations:
+      - type: upsert
+        where:
+          holder: ${from}
+          token_id: ${id}
+        if: "${from} != {zero}"
+        set:
+          - column: balance
+            action: subtract
+            value: ${value}
+      - type: upsert
+        where:
+          holder: ${to}
+          token_id: ${id}
+        if: "${to} != {zero}"
+        set:
+          - column: balance
+            action: add
+            value: ${value}
+"#,
+                    name = self.name(),
+                    from = f("TransferSingle", "from"),
+                    to = f("TransferSingle", "to"),
+                    id = f("TransferSingle", "id"),
+                    value = f("TransferSingle", "value"),
+                    zero = ZERO_ADDRESS,
+                );
+                if fields.has_event("TransferBatch") {
+                    yaml.push_str(&format!(
+                        r#"  - event: TransferBatch
+    iterate:
+      - "${ids} as token_id"
+      - "${values} as amount"
+    operations:
+      - type: upsert
+        where:
+          holder: ${from}
+          token_id: $token_id
+        if: "${from} != {zero}"
+        set:
+          - column: balance
+            action: subtract
+            value: $amount
+      - type: upsert
+        where:
+          holder: ${to}
+          token_id: $token_id
+        if: "${to} != {zero}"
+        set:
+          - column: balance
+            action: add
+            value: $amount
+"#,
+                        ids = f("TransferBatch", "ids"),
+                        values = f("TransferBatch", "values"),
+                        from = f("TransferBatch", "from"),
+                        to = f("TransferBatch", "to"),
+                        zero = ZERO_ADDRESS,
+                    ));
+                }
+                yaml
+            }
+        }
+    }
+}
+
+/// ABI field names of the template fields, keyed by (event, template field).
+#[derive(Debug, Default)]
+struct ResolvedFields(HashMap<(&'static str, &'static str), String>);
+
+impl ResolvedFields {
+    fn get(&self, event: &'static str, field: &'static str) -> &str {
+        self.0.get(&(event, field)).map(String::as_str).unwrap_or_default()
+    }
+
+    fn has_event(&self, event: &str) -> bool {
+        self.0.keys().any(|(e, _)| *e == event)
+    }
+}
+
+/// Maps the template fields to the ABI field names. `fields` overrides take priority, other
+/// fields are read by position from an event matching the standard signature.
+fn resolve_fields(
+    template: TableTemplate,
+    contract_name: &str,
+    abi_items: &[ABIItem],
+    overrides: &HashMap<String, String>,
+) -> Result<ResolvedFields, TableTemplateError> {
+    for field in overrides.keys() {
+        let known = template
+            .events()
+            .iter()
+            .any(|e| e.fields.iter().any(|(f, _)| *f == Some(field.as_str())));
+        if !known {
+            return Err(TableTemplateError::UnknownTemplateField(
+                template.name(),
+                field.clone(),
+                contract_name.to_string(),
+            ));
+        }
+    }
+
+    let mut resolved = ResolvedFields::default();
+    for event in template.events() {
+        let candidates: Vec<&ABIItem> =
+            abi_items.iter().filter(|i| i.type_ == "event" && i.name == event.name).collect();
+        let standard = candidates.iter().find(|item| {
+            item.inputs.len() == event.fields.len()
+                && item.inputs.iter().zip(event.fields).all(|(i, (_, type_))| i.type_ == *type_)
+        });
+        let Some(abi_event) = standard.or(candidates.first()) else {
+            if event.required {
+                return Err(TableTemplateError::MissingEvent(
+                    template.name(),
+                    event.signature(),
+                    contract_name.to_string(),
+                ));
+            }
+            continue;
+        };
+
+        for (position, (field, _)) in event.fields.iter().enumerate() {
+            let Some(field) = *field else {
+                continue;
+            };
+            let name = match overrides.get(field) {
+                Some(name) => {
+                    if !abi_event.inputs.iter().any(|i| &i.name == name) {
+                        return Err(TableTemplateError::UnknownEventField(
+                            template.name(),
+                            field,
+                            name.clone(),
+                            event.name,
+                            contract_name.to_string(),
+                        ));
+                    }
+                    name.clone()
+                }
+                None => match standard.map(|s| &s.inputs[position].name) {
+                    Some(name) if !name.is_empty() => name.clone(),
+                    _ => {
+                        return Err(TableTemplateError::UnmappedField(
+                            template.name(),
+                            field,
+                            event.name,
+                            contract_name.to_string(),
+                        ))
+                    }
+                },
+            };
+            resolved.0.insert((event.name, field), name);
+        }
+    }
+
+    Ok(resolved)
+}
+
+/// Builds the table of a template entry, with the entry merged over the generated table.
+pub fn expand_table_template(
+    contract_name: &str,
+    abi_items: &[ABIItem],
+    entry: &Mapping,
+) -> Result<Value, TableTemplateError> {
+    let invalid = |reason: &str| {
+        TableTemplateError::InvalidEntry(contract_name.to_string(), reason.to_string())
+    };
+
+    let template_name = entry
+        .get(TEMPLATE_KEY)
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid("`template` must be a string"))?;
+    let template = TableTemplate::from_name(template_name).ok_or_else(|| {
+        TableTemplateError::UnknownTemplate(
+            template_name.to_string(),
+            contract_name.to_string(),
+            TableTemplate::ALL.map(|t| t.name()).join(", "),
+        )
+    })?;
+
+    let overrides: HashMap<String, String> = match entry.get(FIELDS_KEY) {
+        Some(fields) => serde_yaml::from_value(fields.clone())
+            .map_err(|_| invalid("`fields` must map template fields to event field names"))?,
+        None => HashMap::new(),
+    };
+
+    let fields = resolve_fields(template, contract_name, abi_items, &overrides)?;
+    let mut table: Value = serde_yaml::from_str(&template.table_yaml(&fields))
+        .map_err(|e| TableTemplateError::CouldNotBuildTable(template.name(), e))?;
+
+    let mut table_overrides = entry.clone();
+    table_overrides.remove(TEMPLATE_KEY);
+    table_overrides.remove(FIELDS_KEY);
+    merge(&mut table, Value::Mapping(table_overrides));
+
+    Ok(table)
+}
+
+/// Expands the template entries of every contract's `tables:` in a manifest yaml value.
+pub fn expand_table_templates(
+    project_path: &Path,
+    manifest: &mut Value,
+) -> Result<(), TableTemplateError> {
+    let Some(contracts) = manifest.get_mut("contracts").and_then(Value::as_sequence_mut) else {
+        return Ok(());
+    };
+
+    for contract in contracts {
+        let has_templates = contract
+            .get("tables")
+            .and_then(Value::as_sequence)
+            .is_some_and(|tables| tables.iter().any(|t| t.get(TEMPLATE_KEY).is_some()));
+        if !has_templates {
+            continue;
+        }
+
+        let contract_name =
+            contract.get("name").and_then(Value::as_str).unwrap_or_default().to_string();
+
+        // The ABI is read through the contract itself so `proxy: auto` ABIs are included
+        let mut contract_without_tables = contract.clone();
+        if let Some(mapping) = contract_without_tables.as_mapping_mut() {
+            mapping.remove("tables");
+        }
+        let abi_items = serde_yaml::from_value::<Contract>(contract_without_tables)
+            .map_err(|e| e.to_string())
+            .and_then(|c| c.parse_abi(project_path).map_err(|e| e.to_string()))
+            .and_then(|abi| serde_json::from_str::<Vec<ABIItem>>(&abi).map_err(|e| e.to_string()))
+            .map_err(|e| TableTemplateError::CouldNotReadAbi(contract_name.clone(), e))?;
+
+        let Some(tables) = contract.get_mut("tables").and_then(Value::as_sequence_mut) else {
+            continue;
+        };
+        for table in tables.iter_mut() {
+            if let Some(entry) = table.as_mapping().filter(|t| t.contains_key(TEMPLATE_KEY)) {
+                *table = expand_table_template(&contract_name, &abi_items, entry)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The unexpanded `tables:` of every contract using a table template, keyed by contract name.
+pub fn templated_tables(manifest: &Value) -> HashMap<String, Value> {
+    let Some(contracts) = manifest.get("contracts").and_then(Value::as_sequence) else {
+        return HashMap::new();
+    };
+
+    contracts
+        .iter()
+        .filter_map(|contract| {
+            let tables = contract.get("tables")?;
+            let has_templates = tables
+                .as_sequence()
+                .is_some_and(|tables| tables.iter().any(|t| t.get(TEMPLATE_KEY).is_some()));
+            let name = contract.get("name").and_then(Value::as_str)?;
+            has_templates.then(|| (name.to_string(), tables.clone()))
+        })
+        .collect()
+}
+
+/// Puts the unexpanded `tables:` from [`templated_tables`] back on their contracts.
+pub fn restore_templated_tables(manifest: &mut Value, tables: &HashMap<String, Value>) {
+    let Some(contracts) = manifest.get_mut("contracts").and_then(Value::as_sequence_mut) else {
+        return;
+    };
+
+    for contract in contracts {
+        let Some(name) = contract.get("name").and_then(Value::as_str) else {
+            continue;
+        };
+        if let (Some(tables), Some(mapping)) = (tables.get(name), contract.as_mapping_mut()) {
+            mapping.insert(Value::from("tables"), tables.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ERC20_ABI: &str = r#"[
+        {"type": "event", "name": "Transfer", "inputs": [
+            {"name": "from", "type": "address", "indexed": true},
+            {"name": "to", "type": "address", "indexed": true},
+            {"name": "value", "type": "uint256", "indexed": false}]},
+        {"type": "event", "name": "Approval", "inputs": [
+            {"name": "owner", "type": "address", "indexed": true},
+            {"name": "spender", "type": "address", "indexed": true},
+            {"name": "value", "type": "uint256", "indexed": false}]}
+    ]"#;
+
+    const WETH_ABI: &str = r#"[
+        {"type": "event", "name": "Transfer", "inputs": [
+            {"name": "src", "type": "address", "indexed": true},
+            {"name": "dst", "type": "address", "indexed": true},
+            {"name": "wad", "type": "uint256", "indexed": false}]}
+    ]"#;
+
+    const ERC1155_ABI: &str = r#"[
+        {"type": "event", "name": "TransferSingle", "inputs": [
+            {"name": "operator", "type": "address", "indexed": true},
+            {"name": "from", "type": "address", "indexed": true},
+            {"name": "to", "type": "address", "indexed": true},
+            {"name": "id", "type": "uint256", "indexed": false},
+            {"name": "value", "type": "uint256", "indexed": false}]},
+        {"type": "event", "name": "TransferBatch", "inputs": [
+            {"name": "operator", "type": "address", "indexed": true},
+            {"name": "from", "type": "address", "indexed": true},
+            {"name": "to", "type": "address", "indexed": true},
+            {"name": "ids", "type": "uint256[]", "indexed": false},
+            {"name": "values", "type": "uint256[]", "indexed": false}]}
+    ]"#;
+
+    fn abi(json: &str) -> Vec<ABIItem> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn expand(abi_json: &str, entry: &str) -> Result<Value, TableTemplateError> {
+        let entry: Value = serde_yaml::from_str(entry).unwrap();
+        expand_table_template("Token", &abi(abi_json), entry.as_mapping().unwrap())
+    }
+
+    fn yaml(table: &str) -> Value {
+        serde_yaml::from_str(table).unwrap()
+    }
+
+    #[test]
+    fn test_erc20_balances_matches_hand_written_table() {
+        let expected = yaml(
+            r#"
+name: erc20_balances
+columns:
+  - name: holder
+  - name: balance
+    default: "0"
+events:
+  - event: Transfer
+    operations:
+      - type: upsert
+        where:
+          holder: $from
+        if: "$from != 0x0000000000000000000000000000000000000000"
+        set:
+          - column: balance
+            action: subtract
+            value: $value
+      - type: upsert
+        where:
+          holder: $to
+        if: "$to != 0x0000000000000000000000000000000000000000"
+        set:
+          - column: balance
+            action: add
+            value: $value
+"#,
+        );
+        assert_eq!(expand(ERC20_ABI, "template: erc20_balances").unwrap(), expected);
+
+        // The generated yaml is a valid table
+        serde_yaml::from_value::<crate::manifest::contract::Table>(expected).unwrap();
+    }
+
+    #[test]
+    fn test_template_uses_abi_field_names() {
+        let expected = yaml(
+            r#"
+name: erc20_balances
+columns:
+  - name: holder
+  - name: balance
+    default: "0"
+events:
+  - event: Transfer
+    operations:
+      - type: upsert
+        where:
+          holder: $src
+        if: "$src != 0x0000000000000000000000000000000000000000"
+        set:
+          - column: balance
+            action: subtract
+            value: $wad
+      - type: upsert
+        where:
+          holder: $dst
+        if: "$dst != 0x0000000000000000000000000000000000000000"
+        set:
+          - column: balance
+            action: add
+            value: $wad
+"#,
+        );
+        assert_eq!(expand(WETH_ABI, "template: erc20_balances").unwrap(), expected);
+    }
+
+    #[test]
+    fn test_template_overrides() {
+        // A non standard Transfer needs its fields mapped
+        let abi_json = r#"[
+            {"type": "event", "name": "Transfer", "inputs": [
+                {"name": "sender", "type": "address", "indexed": true},
+                {"name": "receiver", "type": "address", "indexed": true},
+                {"name": "amount", "type": "uint256", "indexed": false},
+                {"name": "data", "type": "bytes", "indexed": false}]}
+        ]"#;
+        let err = expand(abi_json, "template: erc20_balances").unwrap_err();
+        assert!(matches!(err, TableTemplateError::UnmappedField(_, "from", "Transfer", _)));
+
+        let table = expand(
+            abi_json,
+            r#"
+template: erc20_balances
+name: balances
+timestamp: true
+fields:
+  from: sender
+  to: receiver
+  value: amount
+columns:
+  - name: balance
+    type: uint256
+"#,
+        )
+        .unwrap();
+        let expected = yaml(
+            r#"
+name: balances
+timestamp: true
+columns:
+  - name: holder
+  - name: balance
+    default: "0"
+    type: uint256
+events:
+  - event: Transfer
+    operations:
+      - type: upsert
+        where:
+          holder: $sender
+        if: "$sender != 0x0000000000000000000000000000000000000000"
+        set:
+          - column: balance
+            action: subtract
+            value: $amount
+      - type: upsert
+        where:
+          holder: $receiver
+        if: "$receiver != 0x0000000000000000000000000000000000000000"
+        set:
+          - column: balance
+            action: add
+            value: $amount
+"#,
+        );
+        assert_eq!(table, expected);
+
+        let err = expand(ERC20_ABI, "{template: erc20_balances, fields: {value: amount}}");
+        assert!(matches!(err, Err(TableTemplateError::UnknownEventField(_, "value", _, _, _))));
+
+        let err = expand(ERC20_ABI, "{template: erc20_balances, fields: {spender: x}}");
+        assert!(matches!(err, Err(TableTemplateError::UnknownTemplateField(..))));
+    }
+
+    #[test]
+    fn test_erc20_allowances_and_erc721_owners() {
+        let expected = yaml(
+            r#"
+name: erc20_allowances
+columns:
+  - name: owner
+  - name: spender
+  - name: amount
+    default: "0"
+events:
+  - event: Approval
+    operations:
+      - type: upsert
+        where:
+          owner: $owner
+          spender: $spender
+        set:
+          - column: amount
+            action: set
+            value: $value
+"#,
+        );
+        assert_eq!(expand(ERC20_ABI, "template: erc20_allowances").unwrap(), expected);
+
+        let erc721_abi = r#"[
+            {"type": "event", "name": "Transfer", "inputs": [
+                {"name": "from", "type": "address", "indexed": true},
+                {"name": "to", "type": "address", "indexed": true},
+                {"name": "tokenId", "type": "uint256", "indexed": true}]}
+        ]"#;
+        let expected = yaml(
+            r#"
+name: erc721_owners
+columns:
+  - name: token_id
+  - name: owner
+events:
+  - event: Transfer
+    operations:
+      - type: upsert
+        where:
+          token_id: $tokenId
+        set:
+          - column: owner
+            action: set
+            value: $to
+"#,
+        );
+        assert_eq!(expand(erc721_abi, "template: erc721_owners").unwrap(), expected);
+    }
+
+    #[test]
+    fn test_erc1155_balances() {
+        let expected = yaml(
+            r#"
+name: erc1155_balances
+columns:
+  - name: holder
+  - name: token_id
+  - name: balance
+    default: "0"
+events:
+  - event: TransferSingle
+    operations:
+      - type: upsert
+        where:
+          holder: $from
+          token_id: $id
+        if: "$from != 0x0000000000000000000000000000000000000000"
+        set:
+          - column: balance
+            action: subtract
+            value: $value
+      - type: upsert
+        where:
+          holder: $to
+          token_id: $id
+        if: "$to != 0x0000000000000000000000000000000000000000"
+        set:
+          - column: balance
+            action: add
+            value: $value
+  - event: TransferBatch
+    iterate:
+      - "$ids as token_id"
+      - "$values as amount"
+    operations:
+      - type: upsert
+        where:
+          holder: $from
+          token_id: $token_id
+        if: "$from != 0x0000000000000000000000000000000000000000"
+        set:
+          - column: balance
+            action: subtract
+            value: $amount
+      - type: upsert
+        where:
+          holder: $to
+          token_id: $token_id
+        if: "$to != 0x0000000000000000000000000000000000000000"
+        set:
+          - column: balance
+            action: add
+            value: $amount
+"#,
+        );
+        let table = expand(ERC1155_ABI, "template: erc1155_balances").unwrap();
+        assert_eq!(table, expected);
+        serde_yaml::from_value::<crate::manifest::contract::Table>(table).unwrap();
+
+        // TransferBatch is optional
+        let single_only: Vec<serde_json::Value> = serde_json::from_str(ERC1155_ABI).unwrap();
+        let single_only = serde_json::to_string(&single_only[..1]).unwrap();
+        let table = expand(&single_only, "template: erc1155_balances").unwrap();
+        assert_eq!(table["events"].as_sequence().unwrap().len(), 1);
+
+        let err = expand(ERC20_ABI, "template: erc1155_balances").unwrap_err();
+        assert!(matches!(err, TableTemplateError::MissingEvent(_, _, _)));
+        assert!(err
+            .to_string()
+            .contains("TransferSingle(address,address,address,uint256,uint256)"));
+    }
+
+    #[test]
+    fn test_unknown_template() {
+        let err = expand(ERC20_ABI, "template: erc4626_vaults").unwrap_err();
+        assert!(matches!(err, TableTemplateError::UnknownTemplate(..)));
+    }
+
+    #[test]
+    fn test_expand_table_templates_in_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("weth.abi.json"), WETH_ABI).unwrap();
+
+        let mut manifest = yaml(
+            r#"
+contracts:
+  - name: WETH
+    details:
+      - network: ethereum
+        address: "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"
+    abi: ./weth.abi.json
+    tables:
+      - template: erc20_balances
+      - name: transfers
+        columns:
+          - name: value
+        events:
+          - event: Transfer
+            operations:
+              - type: insert
+                set:
+                  - column: value
+                    action: set
+                    value: $wad
+"#,
+        );
+        expand_table_templates(dir.path(), &mut manifest).unwrap();
+
+        let tables = manifest["contracts"][0]["tables"].as_sequence().unwrap();
+        assert_eq!(tables.len(), 2);
+        assert_eq!(tables[0]["name"], Value::from("erc20_balances"));
+        assert_eq!(tables[0]["events"][0]["operations"][1]["where"]["holder"], Value::from("$dst"));
+        assert_eq!(tables[1]["name"], Value::from("transfers"));
+    }
+}
@@ -1,4 +1,5 @@
 use std::{
+    borrow::Cow,
     collections::HashSet,
     env,
     fs::File,
@@ -13,19 +14,44 @@ use tracing::error;
 
 use crate::{
     abi::ABIItem,
-    event::{parse_arithmetic_expression, parse_filter_expression},
+    event::{
+        find_lookups, find_tokens, parse_arithmetic_expression, parse_filter_expression,
+        substitute_lookups, substitute_tokens, LOOKUP_PREFIX, SCALE_PREFIX, TOKEN_PREFIX,
+    },
     helpers::{load_env_from_full_path, replace_env_variable_to_raw_name},
     manifest::{
+        compose::{compose_manifest, environment_from_env, ComposeManifestError},
+        contract::{Table, TableChangeMapping},
         core::{Manifest, ProjectType},
         network::Network,
+        table_template::{
+            expand_table_templates, restore_templated_tables, templated_tables, TableTemplateError,
+        },
     },
     StringOrArray,
 };
 
 pub const YAML_CONFIG_NAME: &str = "rindexer.yaml";
 
+/// Built-in transaction metadata fields that are always available to table operations.
+/// All prefixed with rindexer_ to avoid conflicts with event fields.
+const BUILTIN_METADATA_FIELDS: &[&str] = &[
+    "rindexer_block_number",
+    "rindexer_block_timestamp",
+    "rindexer_tx_hash",
+    "rindexer_block_hash",
+    "rindexer_contract_address",
+    "rindexer_log_index",
+    "rindexer_tx_index",
+];
+
 /// Checks if a value string contains arithmetic operators indicating it's a computed expression.
+/// `$scale(...)` is always an expression, even without operators.
 fn is_arithmetic_expression(value: &str) -> bool {
+    if value.contains(SCALE_PREFIX) {
+        return true;
+    }
+
     // Must contain at least one arithmetic operator
     let has_operator = value.chars().enumerate().any(|(i, c)| {
         if c == '*' || c == '/' || c == '^' {
@@ -46,6 +72,21 @@ fn contains_call_pattern(value: &str) -> bool {
     value.contains("$call(") || value.contains("$call_static(")
 }
 
+/// Replaces `$lookup(...)` and `$token(...)` references with a placeholder literal so the rest of
+/// a value can be validated like any other expression. Lookup targets, lookup keys and token
+/// addresses are validated separately.
+fn without_lookups(value: &str) -> Cow<'_, str> {
+    if !value.contains(LOOKUP_PREFIX) && !value.contains(TOKEN_PREFIX) {
+        return Cow::Borrowed(value);
+    }
+    let replaced = substitute_lookups(value, |_| Some("0".to_string()))
+        .and_then(|value| substitute_tokens(&value, |_| Some("0".to_string())));
+    match replaced {
+        Some(replaced) => Cow::Owned(replaced),
+        None => Cow::Borrowed(value),
+    }
+}
+
 /// Extracts variable names from a filter/condition expression string.
 /// For example, "from != to && value > 100" would return ["from", "to", "value"].
 /// Also handles $ prefix: "$from != $to" returns ["from", "to"].
@@ -56,8 +97,20 @@ fn extract_filter_variables(expr: &str) -> Vec<String> {
 
     // Keywords and operators to skip
     let keywords = ["true", "false", "null", "and", "or", "AND", "OR"];
+    // Quote character of the string literal being skipped
+    let mut quote: Option<char> = None;
 
     for c in expr.chars() {
+        if let Some(open) = quote {
+            if c == open {
+                quote = None;
+            }
+            continue;
+        }
+        if (c == '\'' || c == '"') && current_word.is_empty() {
+            quote = Some(c);
+            continue;
+        }
         if c == '$' && current_word.is_empty() {
             // Start of a $-prefixed variable
             has_dollar_prefix = true;
@@ -151,6 +204,14 @@ fn is_event_field_reference_for_cron(value: &str) -> Option<String> {
         return None;
     }
 
+    // $scale(...) = allowed as long as its operands are
+    if value.starts_with(SCALE_PREFIX) {
+        return extract_arithmetic_variables(value).into_iter().find(|var_name| {
+            !matches!(var_name.as_str(), "scale" | "call" | "call_static" | "constant" | "contract")
+                && !var_name.starts_with("rindexer_")
+        });
+    }
+
     // $rindexer_* = built-in metadata, allowed
     if value.starts_with("$rindexer_") {
         return None;
@@ -163,7 +224,97 @@ fn is_event_field_reference_for_cron(value: &str) -> Option<String> {
     Some(root_field.to_string())
 }
 
-fn substitute_env_variables(contents: &str) -> Result<String, regex::Error> {
+/// Validates the operations a table runs for the changed rows of `change_mapping.table`.
+/// Values can reference `$change`, `$before_<column>` and `$after_<column>` of the followed
+/// table, as well as the metadata of the event that changed the row.
+fn validate_change_operations(
+    table: &Table,
+    change_mapping: &TableChangeMapping,
+    tables: &[Table],
+    contract_name: &str,
+) -> Result<(), ValidateManifestError> {
+    // A missing followed table is reported by `validate_change_triggers`
+    let Some(source) = tables.iter().find(|t| t.name == change_mapping.table) else {
+        return Ok(());
+    };
+
+    let mut change_fields: HashSet<String> = HashSet::new();
+    change_fields.insert(TableChangeMapping::CHANGE_FIELD.to_string());
+    for column in &source.columns {
+        change_fields.insert(TableChangeMapping::before_field(&column.name));
+        change_fields.insert(TableChangeMapping::after_field(&column.name));
+    }
+
+    let check_field = |field: &str| {
+        let root_field = field.split(['.', '[']).next().unwrap_or(field);
+        if BUILTIN_METADATA_FIELDS.contains(&root_field) || change_fields.contains(root_field) {
+            Ok(())
+        } else {
+            Err(ValidateManifestError::CustomIndexingChangeFieldNotFound(
+                field.to_string(),
+                table.name.clone(),
+                contract_name.to_string(),
+                source.name.clone(),
+            ))
+        }
+    };
+    let invalid = |message: String| {
+        ValidateManifestError::CustomIndexingValidationError(message, contract_name.to_string())
+    };
+
+    for operation in &change_mapping.operations {
+        let columns = operation.where_clause.keys().chain(operation.set.iter().map(|s| &s.column));
+        for column in columns {
+            if !table.columns.iter().any(|c| &c.name == column) {
+                return Err(invalid(format!(
+                    "Column '{}' used in 'on_change' of table '{}' not found in table fields.",
+                    column, table.name
+                )));
+            }
+        }
+
+        let values = operation
+            .where_clause
+            .values()
+            .map(String::as_str)
+            .chain(operation.set.iter().map(|s| s.effective_value()));
+        for value in values {
+            if is_arithmetic_expression(value) && !contains_call_pattern(value) {
+                if let Err(e) = parse_arithmetic_expression(value) {
+                    return Err(invalid(format!(
+                        "Invalid arithmetic expression '{}' in 'on_change' of table '{}': {}",
+                        value, table.name, e
+                    )));
+                }
+            }
+            for var_name in extract_arithmetic_variables(value) {
+                if matches!(
+                    var_name.as_str(),
+                    "call" | "call_static" | "constant" | "null" | "if" | "scale"
+                ) {
+                    continue;
+                }
+                check_field(&var_name)?;
+            }
+        }
+
+        if let Some(condition_expr) = operation.condition() {
+            if let Err(e) = parse_filter_expression(condition_expr) {
+                return Err(invalid(format!(
+                    "Invalid condition expression '{}' in 'on_change' of table '{}': {}",
+                    condition_expr, table.name, e
+                )));
+            }
+            for var_name in extract_filter_variables(condition_expr) {
+                check_field(&var_name)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn substitute_env_variables(contents: &str) -> Result<String, regex::Error> {
     let re = Regex::new(r"\$\{([^}]+)}")?;
     let result = re.replace_all(contents, |caps: &Captures| {
         let var_name = &caps[1];
@@ -210,6 +361,9 @@ pub enum ValidateManifestError {
     #[error("Relationship foreign key contract {0} not found")]
     RelationshipForeignKeyContractNotFound(String),
 
+    #[error("Invalid retention for contract {1}: {0}")]
+    InvalidRetention(String, String),
+
     #[error("Streams config is invalid: {0}")]
     StreamsConfigValidationError(String),
 
@@ -238,9 +392,15 @@ pub enum ValidateManifestError {
     #[error("Iterate field '${0}' in event '{1}' for table '{2}' in contract '{3}' not found in event ABI")]
     CustomIndexingIterateFieldNotFound(String, String, String, String),
 
+    #[error("Field '${0}' referenced in 'on_change' of table '{1}' for contract '{2}' is not a change field of table '{3}'. Use $change, $before_<column> or $after_<column>")]
+    CustomIndexingChangeFieldNotFound(String, String, String, String),
+
     #[error("Tables are defined in contract '{0}' but project_type is not 'no-code'. Tables only work with 'project_type: no-code'. Either change project_type to 'no-code' or remove the tables configuration.")]
     TablesRequireNoCodeProjectType(String),
 
+    #[error("Retention is defined in contract '{0}' but project_type is not 'no-code'. Raw event tables are only pruned with 'project_type: no-code'. Either change project_type to 'no-code' or remove the retention configuration.")]
+    RetentionRequiresNoCodeProjectType(String),
+
     // Cron validation errors
     #[error("Table '{0}' in contract '{1}' has no triggers. Must have at least one 'events' or 'cron' entry.")]
     TableNoTriggers(String, String),
@@ -290,6 +450,13 @@ fn validate_manifest(
                     contract.name.clone(),
                 ));
             }
+            if contract.retention.is_some()
+                || contract.include_events.iter().flatten().any(|e| e.retention.is_some())
+            {
+                return Err(ValidateManifestError::RetentionRequiresNoCodeProjectType(
+                    contract.name.clone(),
+                ));
+            }
         }
     }
 
@@ -381,6 +548,10 @@ fn validate_manifest(
             }
         }
 
+        if let Err(e) = contract.validate_retention(manifest.timestamps) {
+            return Err(ValidateManifestError::InvalidRetention(e, contract.name.clone()));
+        }
+
         if let Some(_dependency_events) = &contract.dependency_events {
             // TODO - validate the events all exist in the contract ABIs
         }
@@ -403,6 +574,77 @@ fn validate_manifest(
                     ));
                 }
 
+                // Validate rollup bucketing (interval, supported actions)
+                if let Err(e) = table.validate_bucket() {
+                    return Err(ValidateManifestError::CustomIndexingValidationError(
+                        e,
+                        contract.name.clone(),
+                    ));
+                }
+
+                // Validate array actions and distinct counts
+                if let Err(e) = table.validate_array_actions() {
+                    return Err(ValidateManifestError::CustomIndexingValidationError(
+                        e,
+                        contract.name.clone(),
+                    ));
+                }
+
+                // Validate that $scale() values go to decimal columns
+                if let Err(e) = table.validate_decimal_columns() {
+                    return Err(ValidateManifestError::CustomIndexingValidationError(
+                        e,
+                        contract.name.clone(),
+                    ));
+                }
+
+                // Validate that $lookup references existing tables and columns
+                if let Err(e) = table.validate_lookups(&contract.name, &manifest.contracts) {
+                    return Err(ValidateManifestError::CustomIndexingValidationError(
+                        e,
+                        contract.name.clone(),
+                    ));
+                }
+
+                // Validate versioned tables, history is stored in Postgres only
+                if let Err(e) = table.validate_history() {
+                    return Err(ValidateManifestError::CustomIndexingValidationError(
+                        e,
+                        contract.name.clone(),
+                    ));
+                }
+                if table.history && !manifest.storage.postgres_enabled() {
+                    return Err(ValidateManifestError::CustomIndexingValidationError(
+                        format!(
+                            "Table '{}' uses 'history: true' which requires postgres storage.",
+                            table.name
+                        ),
+                        contract.name.clone(),
+                    ));
+                }
+
+                // Validate secondary indexes and partitioning
+                if let Err(e) = table.validate_indexes() {
+                    return Err(ValidateManifestError::CustomIndexingValidationError(
+                        e,
+                        contract.name.clone(),
+                    ));
+                }
+                if let Err(e) = table.validate_partition() {
+                    return Err(ValidateManifestError::CustomIndexingValidationError(
+                        e,
+                        contract.name.clone(),
+                    ));
+                }
+
+                // Validate the retention policy
+                if let Err(e) = table.validate_retention() {
+                    return Err(ValidateManifestError::CustomIndexingValidationError(
+                        e,
+                        contract.name.clone(),
+                    ));
+                }
+
                 // Validate that $null is only used on nullable columns
                 if let Err(e) = table.validate_null_values() {
                     return Err(ValidateManifestError::CustomIndexingValidationError(
@@ -440,18 +682,6 @@ fn validate_manifest(
                     let event_input_names: HashSet<&str> =
                         abi_event.inputs.iter().map(|i| i.name.as_str()).collect();
 
-                    // Built-in transaction metadata fields that are always available
-                    // All prefixed with rindexer_ to avoid conflicts with event fields
-                    const BUILTIN_METADATA_FIELDS: &[&str] = &[
-                        "rindexer_block_number",
-                        "rindexer_block_timestamp",
-                        "rindexer_tx_hash",
-                        "rindexer_block_hash",
-                        "rindexer_contract_address",
-                        "rindexer_log_index",
-                        "rindexer_tx_index",
-                    ];
-
                     // Validate iterate bindings and collect aliases for later validation
                     let mut iterate_aliases: HashSet<String> = HashSet::new();
                     for binding in &event_mapping.iterate {
@@ -471,8 +701,47 @@ fn validate_manifest(
                     }
 
                     for operation in &event_mapping.operations {
+                        // Validate event fields used as $lookup keys and $token addresses
+                        let operation_values = operation
+                            .where_clause
+                            .values()
+                            .map(String::as_str)
+                            .chain(operation.set.iter().map(|s| s.effective_value()))
+                            .chain(operation.condition());
+                        for value in operation_values {
+                            let lookup_keys =
+                                find_lookups(value).into_iter().map(|(_, _, lookup)| lookup.key);
+                            let token_addresses =
+                                find_tokens(value).into_iter().map(|(_, _, token)| token.address);
+                            for key in lookup_keys.chain(token_addresses) {
+                                let Some(event_field) = key.strip_prefix('$') else {
+                                    continue;
+                                };
+                                if event_field.starts_with("constant(") {
+                                    continue;
+                                }
+                                let root_field =
+                                    event_field.split(['.', '[']).next().unwrap_or(event_field);
+                                if !BUILTIN_METADATA_FIELDS.contains(&root_field)
+                                    && !iterate_aliases.contains(root_field)
+                                    && !event_input_names.contains(root_field)
+                                {
+                                    return Err(
+                                        ValidateManifestError::CustomIndexingEventFieldNotFound(
+                                            event_field.to_string(),
+                                            event_mapping.event.clone(),
+                                            table.name.clone(),
+                                            contract.name.clone(),
+                                        ),
+                                    );
+                                }
+                            }
+                        }
+
                         // Validate where clause columns
                         for (table_column, value) in &operation.where_clause {
+                            let value = without_lookups(value);
+                            let value = value.as_ref();
                             // Check table column exists
                             if !table_column_names.contains(table_column.as_str()) {
                                 return Err(ValidateManifestError::CustomIndexingFieldNotFound(
@@ -528,6 +797,8 @@ fn validate_manifest(
 
                         // Validate condition expression (from `if` or `filter` field)
                         if let Some(condition_expr) = operation.condition() {
+                            let condition_expr = without_lookups(condition_expr);
+                            let condition_expr = condition_expr.as_ref();
                             // Validate the expression parses correctly
                             if let Err(e) = parse_filter_expression(condition_expr) {
                                 return Err(
@@ -582,7 +853,8 @@ fn validate_manifest(
                             }
 
                             // Get the effective value (handles increment/decrement defaults)
-                            let effective_value = set_col.effective_value();
+                            let effective_value = without_lookups(set_col.effective_value());
+                            let effective_value = effective_value.as_ref();
 
                             if effective_value.starts_with("$if(") {
                                 // Conditional expressions are validated at runtime
@@ -612,12 +884,13 @@ fn validate_manifest(
                                 // (skip variables inside $call() as they're validated separately)
                                 let variables = extract_arithmetic_variables(effective_value);
                                 for var_name in variables {
-                                    // Skip 'call', 'call_static', 'constant', 'null', and 'if' which are special keywords
+                                    // Skip 'call', 'call_static', 'constant', 'null', 'if' and 'scale' which are special keywords
                                     if var_name == "call"
                                         || var_name == "call_static"
                                         || var_name == "constant"
                                         || var_name == "null"
                                         || var_name == "if"
+                                        || var_name == "scale"
                                     {
                                         continue;
                                     }
@@ -681,7 +954,18 @@ fn validate_manifest(
                     }
                 }
 
-                // Validate table has at least one trigger (event or cron)
+                // Validate tables following the changes of other tables
+                if let Err(e) = table.validate_change_triggers(tables) {
+                    return Err(ValidateManifestError::CustomIndexingValidationError(
+                        e,
+                        contract.name.clone(),
+                    ));
+                }
+                for change_mapping in &table.on_change {
+                    validate_change_operations(table, change_mapping, tables, &contract.name)?;
+                }
+
+                // Validate table has at least one trigger (event, cron or table change)
                 if !table.has_triggers() {
                     return Err(ValidateManifestError::TableNoTriggers(
                         table.name.clone(),
@@ -851,9 +1135,15 @@ pub enum ReadManifestError {
     #[error("Could not substitute env variables: {0}")]
     CouldNotSubstituteEnvVariables(#[from] regex::Error),
 
+    #[error("Could not compose manifest: {0}")]
+    CouldNotComposeManifest(#[from] ComposeManifestError),
+
     #[error("Could not validate manifest: {0}")]
     CouldNotValidateManifest(#[from] ValidateManifestError),
 
+    #[error("Could not expand table templates: {0}")]
+    CouldNotExpandTableTemplates(#[from] TableTemplateError),
+
     #[error("No project path found using parent of manifest path")]
     NoProjectPathFoundUsingParentOfManifestPath,
 }
@@ -865,16 +1155,24 @@ pub fn read_manifest_raw(file_path: &PathBuf) -> Result<Manifest, ReadManifestEr
 
     file.read_to_string(&mut contents)?;
 
-    let manifest: Manifest = serde_yaml::from_str(&contents)?;
+    let project_path =
+        file_path.parent().ok_or(ReadManifestError::NoProjectPathFoundUsingParentOfManifestPath)?;
 
-    let project_path = file_path.parent();
-    match project_path {
-        None => Err(ReadManifestError::NoProjectPathFoundUsingParentOfManifestPath),
-        Some(project_path) => {
-            validate_manifest(project_path, &manifest)?;
-            Ok(manifest)
-        }
-    }
+    let mut manifest = parse_manifest(project_path, &contents)?;
+    validate_manifest(project_path, &manifest)?;
+
+    // Commands editing the yaml write the manifest back, keep the templates rather than the
+    // tables they expanded to
+    manifest.templated_tables = templated_tables(&serde_yaml::from_str(&contents)?);
+    Ok(manifest)
+}
+
+/// Parses the manifest yaml, expanding the table templates of its contracts.
+#[allow(clippy::result_large_err)]
+fn parse_manifest(project_path: &Path, contents: &str) -> Result<Manifest, ReadManifestError> {
+    let mut manifest: serde_yaml::Value = serde_yaml::from_str(contents)?;
+    expand_table_templates(project_path, &mut manifest)?;
+    Ok(serde_yaml::from_value(manifest)?)
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -894,6 +1192,16 @@ fn extract_environment_path(contents: &str, file_path: &Path) -> Option<PathBuf>
 
 #[allow(clippy::result_large_err)]
 pub fn read_manifest(file_path: &PathBuf) -> Result<Manifest, ReadManifestError> {
+    read_manifest_for_environment(file_path, None)
+}
+
+/// Reads the manifest composed with its includes and the overlay for `environment`, falling
+/// back to the `RINDEXER_ENV` environment variable when no environment is given.
+#[allow(clippy::result_large_err)]
+pub fn read_manifest_for_environment(
+    file_path: &PathBuf,
+    environment: Option<&str>,
+) -> Result<Manifest, ReadManifestError> {
     let mut file = File::open(file_path)?;
     let mut contents = String::new();
 
@@ -904,11 +1212,17 @@ pub fn read_manifest(file_path: &PathBuf) -> Result<Manifest, ReadManifestError>
         load_env_from_full_path(path);
     }
 
-    let contents_before_transform = contents.clone();
+    let environment = environment.map(str::to_string).or_else(environment_from_env);
+
+    let contents_before_transform =
+        compose_manifest(file_path, environment.as_deref(), false)?.contents;
 
-    contents = substitute_env_variables(&contents)?;
+    contents = compose_manifest(file_path, environment.as_deref(), true)?.contents;
 
-    let mut manifest_after_transform: Manifest = serde_yaml::from_str(&contents)?;
+    let project_path =
+        file_path.parent().ok_or(ReadManifestError::NoProjectPathFoundUsingParentOfManifestPath)?;
+
+    let mut manifest_after_transform = parse_manifest(project_path, &contents)?;
 
     // Assign networks to the Native Transfer if opted into without defining networks.
     // We treat None as "All available".
@@ -932,14 +1246,8 @@ pub fn read_manifest(file_path: &PathBuf) -> Result<Manifest, ReadManifestError>
         }
     }
 
-    let project_path = file_path.parent();
-    match project_path {
-        None => Err(ReadManifestError::NoProjectPathFoundUsingParentOfManifestPath),
-        Some(project_path) => {
-            validate_manifest(project_path, &manifest_after_transform)?;
-            Ok(manifest_after_transform)
-        }
-    }
+    validate_manifest(project_path, &manifest_after_transform)?;
+    Ok(manifest_after_transform)
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -958,10 +1266,71 @@ pub enum WriteManifestError {
 }
 
 pub fn write_manifest(data: &Manifest, file_path: &PathBuf) -> Result<(), WriteManifestError> {
+    let mut yaml =
+        serde_yaml::to_value(data).map_err(WriteManifestError::CouldNotTurnManifestToString)?;
+    restore_templated_tables(&mut yaml, &data.templated_tables);
     let yaml_string =
-        serde_yaml::to_string(data).map_err(WriteManifestError::CouldNotTurnManifestToString)?;
+        serde_yaml::to_string(&yaml).map_err(WriteManifestError::CouldNotTurnManifestToString)?;
 
     let mut file = File::create(file_path).map_err(WriteManifestError::CouldNotCreateFile)?;
     file.write_all(yaml_string.as_bytes()).map_err(WriteManifestError::CouldNotWriteToFile)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ERC20_ABI: &str = r#"[
+        {"type": "event", "name": "Transfer", "inputs": [
+            {"name": "from", "type": "address", "indexed": true},
+            {"name": "to", "type": "address", "indexed": true},
+            {"name": "value", "type": "uint256", "indexed": false}]}
+    ]"#;
+
+    const MANIFEST: &str = r#"
+name: test-indexer
+project_type: no-code
+networks:
+  - name: ethereum
+    chain_id: 1
+    rpc: https://eth.rpc.example.com
+contracts:
+  - name: USDC
+    details:
+      - network: ethereum
+        address: "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+    abi: ./erc20.abi.json
+    tables:
+      - template: erc20_balances
+storage:
+  postgres:
+    enabled: true
+"#;
+
+    #[test]
+    fn test_table_templates_survive_rewriting_the_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("erc20.abi.json"), ERC20_ABI).unwrap();
+        let path = dir.path().join(YAML_CONFIG_NAME);
+        std::fs::write(&path, MANIFEST).unwrap();
+
+        // What `rindexer add` does: read, push a contract and write back
+        let mut manifest = read_manifest_raw(&path).unwrap();
+        assert_eq!(manifest.contracts[0].tables.as_ref().map(Vec::len), Some(1));
+        let mut added = manifest.contracts[0].clone();
+        added.name = "DAI".to_string();
+        added.tables = None;
+        manifest.contracts.push(added);
+        write_manifest(&manifest, &path).unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(written.contains("template: erc20_balances"));
+        assert!(!written.contains("operations:"));
+
+        let reread = read_manifest_raw(&path).unwrap();
+        assert_eq!(reread.contracts.len(), 2);
+        assert_eq!(reread.contracts[0].tables.as_ref().map(Vec::len), Some(1));
+        assert!(reread.contracts[1].tables.is_none());
+    }
+}
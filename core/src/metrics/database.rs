@@ -1,6 +1,8 @@
 //! Database-specific metrics helpers.
 
-use super::definitions::{DB_OPERATIONS_TOTAL, DB_OPERATION_DURATION, DB_POOL_CONNECTIONS};
+use super::definitions::{
+    DB_OPERATIONS_TOTAL, DB_OPERATION_DURATION, DB_POOL_CONNECTIONS, RETENTION_ROWS_PRUNED_TOTAL,
+};
 use super::timer::TimerGuard;
 
 /// Database operation types for labeling.
@@ -43,3 +45,8 @@ pub fn set_pool_connections(database: &str, active: usize, idle: usize) {
     DB_POOL_CONNECTIONS.with_label_values(&[database, "active"]).set(active as f64);
     DB_POOL_CONNECTIONS.with_label_values(&[database, "idle"]).set(idle as f64);
 }
+
+/// Record rows deleted from a table by its retention policy.
+pub fn record_rows_pruned(network: &str, table: &str, count: u64) {
+    RETENTION_ROWS_PRUNED_TOTAL.with_label_values(&[network, table]).inc_by(count as f64);
+}
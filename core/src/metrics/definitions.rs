@@ -148,6 +148,17 @@ pub static DB_POOL_CONNECTIONS: Lazy<GaugeVec> = Lazy::new(|| {
     .expect("failed to register DB_POOL_CONNECTIONS")
 });
 
+/// Total rows deleted by retention policies.
+/// Labels: network, table
+pub static RETENTION_ROWS_PRUNED_TOTAL: Lazy<CounterVec> = Lazy::new(|| {
+    register_counter_vec!(
+        "rindexer_retention_rows_pruned_total",
+        "Total number of rows deleted by retention policies",
+        &["network", "table"]
+    )
+    .expect("failed to register RETENTION_ROWS_PRUNED_TOTAL")
+});
+
 // =============================================================================
 // Metadata Fetch Metrics
 // =============================================================================
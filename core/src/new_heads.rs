@@ -0,0 +1,256 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use alloy::{
+    eips::BlockNumberOrTag,
+    network::AnyNetwork,
+    primitives::B256,
+    providers::{Provider, ProviderBuilder, WsConnect},
+};
+use futures::StreamExt;
+use tokio::sync::broadcast::{self, Sender};
+use tracing::{debug, info, warn};
+
+use crate::is_running;
+use crate::notifications::{ChainStateNotification, ChainStateSource};
+
+/// How many recent heads are remembered to find the fork point of a reorg.
+const TRACKED_HEADS: usize = 256;
+
+/// Wait before reconnecting a dropped `newHeads` subscription, the provider falls back to
+/// polling in the meantime.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Subscribe to `newHeads` on `ws_rpc` and publish the heads as [`ChainStateNotification`]s.
+///
+/// New heads are sent as `Committed`, a head which does not build on the previously seen chain
+/// is sent as `Reorged` from the fork point. The returned sender is meant to be handed to
+/// `create_client` as the `chain_state_notification`, which is the same channel the reth ExEx
+/// feeds, so live indexing picks up heads and reorgs the same way.
+///
+/// The subscription reconnects on its own when the socket drops and runs until shutdown.
+pub fn subscribe_new_heads(ws_rpc: String, network: String) -> Sender<ChainStateNotification> {
+    let (notification_tx, _notification_rx) = broadcast::channel::<ChainStateNotification>(1000);
+
+    let tx = notification_tx.clone();
+    tokio::spawn(async move {
+        let mut tracker = HeadTracker::new(TRACKED_HEADS);
+
+        while is_running() {
+            if let Err(e) = run_subscription(&ws_rpc, &network, &tx, &mut tracker).await {
+                warn!("{} - newHeads subscription failed, falling back to polling: {}", network, e);
+            }
+
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+
+        debug!("{} - newHeads subscription stopped", network);
+    });
+
+    notification_tx
+}
+
+async fn run_subscription(
+    ws_rpc: &str,
+    network: &str,
+    tx: &Sender<ChainStateNotification>,
+    tracker: &mut HeadTracker,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let provider =
+        ProviderBuilder::new().network::<AnyNetwork>().connect_ws(WsConnect::new(ws_rpc)).await?;
+    let mut heads = provider.subscribe_blocks().await?.into_stream();
+
+    info!("{} - Subscribed to newHeads", network);
+
+    while let Some(header) = heads.next().await {
+        if !is_running() {
+            return Ok(());
+        }
+
+        let (number, hash) = (header.number, header.hash);
+        let notification = match tracker.on_head(number, hash, header.parent_hash) {
+            HeadChange::Duplicate => continue,
+            HeadChange::Committed { from_block } => {
+                tracker.record(number, hash);
+                ChainStateNotification::Committed { from_block, to_block: number, tip_hash: hash }
+            }
+            HeadChange::Diverged { first_unknown } => {
+                let revert_to_block = tracker.tip().unwrap_or(number);
+
+                // Walk back while our remembered hashes are no longer canonical.
+                let mut fork_block = first_unknown;
+                while fork_block > 0 {
+                    let Some(known) = tracker.hash(fork_block - 1) else {
+                        break;
+                    };
+                    let canonical = provider
+                        .get_block_by_number(BlockNumberOrTag::Number(fork_block - 1))
+                        .await?
+                        .map(|block| block.header.hash);
+                    if canonical == Some(known) {
+                        break;
+                    }
+                    fork_block -= 1;
+                }
+
+                tracker.truncate_from(fork_block);
+                tracker.record(number, hash);
+                ChainStateNotification::Reorged {
+                    revert_from_block: fork_block,
+                    revert_to_block: revert_to_block.max(fork_block),
+                    new_from_block: fork_block,
+                    new_to_block: number,
+                    new_tip_hash: hash,
+                    source: ChainStateSource::NewHeads,
+                }
+            }
+        };
+
+        // No listeners yet is fine, the provider subscribes once it is created.
+        let _ = tx.send(notification);
+    }
+
+    Err("newHeads stream closed".into())
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum HeadChange {
+    /// The head was already seen.
+    Duplicate,
+    /// The head builds on the tracked chain, `from_block` is the first block not seen yet.
+    Committed { from_block: u64 },
+    /// The head does not build on the tracked chain, blocks from `first_unknown` may be reorged.
+    Diverged { first_unknown: u64 },
+}
+
+/// Remembers the hashes of the latest heads to tell new heads from reorgs.
+struct HeadTracker {
+    hashes: BTreeMap<u64, B256>,
+    capacity: usize,
+}
+
+impl HeadTracker {
+    fn new(capacity: usize) -> Self {
+        Self { hashes: BTreeMap::new(), capacity }
+    }
+
+    fn tip(&self) -> Option<u64> {
+        self.hashes.last_key_value().map(|(number, _)| *number)
+    }
+
+    fn hash(&self, number: u64) -> Option<B256> {
+        self.hashes.get(&number).copied()
+    }
+
+    fn on_head(&self, number: u64, hash: B256, parent_hash: B256) -> HeadChange {
+        if self.hash(number) == Some(hash) {
+            return HeadChange::Duplicate;
+        }
+
+        let Some(tip) = self.tip() else {
+            return HeadChange::Committed { from_block: number };
+        };
+
+        if number <= tip {
+            return HeadChange::Diverged { first_unknown: number };
+        }
+
+        match number.checked_sub(1).and_then(|parent| self.hash(parent)) {
+            Some(known_parent) if known_parent != parent_hash => {
+                HeadChange::Diverged { first_unknown: number - 1 }
+            }
+            _ => HeadChange::Committed { from_block: tip + 1 },
+        }
+    }
+
+    fn record(&mut self, number: u64, hash: B256) {
+        self.truncate_from(number);
+        self.hashes.insert(number, hash);
+        while self.hashes.len() > self.capacity {
+            self.hashes.pop_first();
+        }
+    }
+
+    fn truncate_from(&mut self, number: u64) {
+        self.hashes.split_off(&number);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(n: u8) -> B256 {
+        B256::from([n; 32])
+    }
+
+    #[test]
+    fn first_head_is_committed() {
+        let tracker = HeadTracker::new(8);
+        assert_eq!(
+            tracker.on_head(10, hash(10), hash(9)),
+            HeadChange::Committed { from_block: 10 }
+        );
+    }
+
+    #[test]
+    fn extending_head_is_committed_including_missed_heads() {
+        let mut tracker = HeadTracker::new(8);
+        tracker.record(10, hash(10));
+
+        assert_eq!(
+            tracker.on_head(11, hash(11), hash(10)),
+            HeadChange::Committed { from_block: 11 }
+        );
+        assert_eq!(
+            tracker.on_head(13, hash(13), hash(12)),
+            HeadChange::Committed { from_block: 11 }
+        );
+    }
+
+    #[test]
+    fn repeated_head_is_duplicate() {
+        let mut tracker = HeadTracker::new(8);
+        tracker.record(10, hash(10));
+
+        assert_eq!(tracker.on_head(10, hash(10), hash(9)), HeadChange::Duplicate);
+    }
+
+    #[test]
+    fn parent_hash_mismatch_diverges_from_parent() {
+        let mut tracker = HeadTracker::new(8);
+        tracker.record(10, hash(10));
+
+        assert_eq!(
+            tracker.on_head(11, hash(11), hash(99)),
+            HeadChange::Diverged { first_unknown: 10 }
+        );
+    }
+
+    #[test]
+    fn replaced_head_diverges_from_its_number() {
+        let mut tracker = HeadTracker::new(8);
+        tracker.record(10, hash(10));
+        tracker.record(11, hash(11));
+
+        assert_eq!(
+            tracker.on_head(11, hash(77), hash(10)),
+            HeadChange::Diverged { first_unknown: 11 }
+        );
+    }
+
+    #[test]
+    fn record_truncates_above_and_evicts_oldest() {
+        let mut tracker = HeadTracker::new(2);
+        tracker.record(10, hash(10));
+        tracker.record(11, hash(11));
+        tracker.record(12, hash(12));
+
+        assert_eq!(tracker.hash(10), None);
+        assert_eq!(tracker.tip(), Some(12));
+
+        tracker.record(11, hash(77));
+        assert_eq!(tracker.tip(), Some(11));
+        assert_eq!(tracker.hash(11), Some(hash(77)));
+    }
+}
@@ -1,5 +1,24 @@
 use alloy::primitives::{BlockNumber, B256};
 
+/// What pushed a chain state notification. Reorgs it reports are recorded with this
+/// `detection_source`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainStateSource {
+    /// A reth ExEx notification
+    Exex,
+    /// A `newHeads` websocket subscription
+    NewHeads,
+}
+
+impl ChainStateSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChainStateSource::Exex => "exex",
+            ChainStateSource::NewHeads => "new_heads",
+        }
+    }
+}
+
 /// Represents different types of chain state changes
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ChainStateNotification {
@@ -24,6 +43,8 @@ pub enum ChainStateNotification {
         new_to_block: BlockNumber,
         /// Hash of the new chain tip after reorg
         new_tip_hash: B256,
+        /// What detected the reorg
+        source: ChainStateSource,
     },
     /// Blocks have been reverted (chain rollback)
     Reverted {
@@ -31,6 +52,8 @@ pub enum ChainStateNotification {
         from_block: BlockNumber,
         /// Ending block number of reverted range
         to_block: BlockNumber,
+        /// What detected the revert
+        source: ChainStateSource,
     },
 }
 
@@ -64,6 +87,7 @@ mod tests {
             new_from_block: 150,
             new_to_block: 210,
             new_tip_hash: B256::from([1u8; 32]),
+            source: ChainStateSource::Exex,
         };
 
         match reorged {
@@ -73,7 +97,9 @@ mod tests {
                 new_from_block,
                 new_to_block,
                 new_tip_hash,
+                source,
             } => {
+                assert_eq!(source, ChainStateSource::Exex);
                 assert_eq!(revert_from_block, 150);
                 assert_eq!(revert_to_block, 200);
                 assert_eq!(new_from_block, 150);
@@ -84,10 +110,14 @@ mod tests {
         }
 
         // Test Reverted variant
-        let reverted = ChainStateNotification::Reverted { from_block: 100, to_block: 150 };
+        let reverted = ChainStateNotification::Reverted {
+            from_block: 100,
+            to_block: 150,
+            source: ChainStateSource::Exex,
+        };
 
         match reverted {
-            ChainStateNotification::Reverted { from_block, to_block } => {
+            ChainStateNotification::Reverted { from_block, to_block, .. } => {
                 assert_eq!(from_block, 100);
                 assert_eq!(to_block, 150);
             }
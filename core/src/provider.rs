@@ -1,12 +1,13 @@
+use crate::adaptive_block_range::register_block_range_controller;
 use crate::adaptive_concurrency::ADAPTIVE_CONCURRENCY;
 use crate::notifications::ChainStateNotification;
 use alloy::network::{AnyNetwork, AnyRpcBlock, AnyTransactionReceipt};
-use alloy::rpc::types::{Filter, ValueOrArray};
+use alloy::rpc::types::{Filter, Topic, ValueOrArray};
 #[cfg(test)]
 use alloy::transports::mock::Asserter;
 use alloy::{
     eips::{BlockId, BlockNumberOrTag},
-    primitives::{Address, Bytes, TxHash, U256, U64},
+    primitives::{Address, Bytes, TxHash, B256, U256, U64},
     providers::{
         ext::TraceApi,
         fillers::{BlobGasFiller, ChainIdFiller, FillProvider, GasFiller, JoinFill, NonceFiller},
@@ -32,17 +33,21 @@ use alloy::{
 };
 use alloy_chains::{Chain, NamedChain};
 use futures::future::try_join_all;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::json;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
-use std::future::IntoFuture;
+use std::future::{Future, IntoFuture};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::{
-    sync::Arc,
+    sync::{Arc, Weak},
     time::{Duration, Instant},
 };
 use thiserror::Error;
-use tokio::sync::{broadcast::Sender, Mutex, Semaphore};
+use tokio::sync::{
+    broadcast::{error::RecvError, Receiver, Sender},
+    Mutex, Semaphore,
+};
 use tokio::task::JoinError;
 use tracing::{debug, debug_span, error, Instrument};
 use url::Url;
@@ -50,10 +55,24 @@ use url::Url;
 use async_trait::async_trait;
 
 use crate::helpers::chunk_hashset;
+use crate::indexer::reorg::{
+    finality_tag_for_network, finalized_buffer_distance_for_network, reorg_safe_distance_for_chain,
+};
 use crate::layer_extensions::RpcLoggingLayer;
+use crate::manifest::contract::BlockTag;
 use crate::manifest::network::{AddressFiltering, BlockPollFrequency};
 use crate::metrics::rpc as rpc_metrics;
-use crate::{event::RindexerEventFilter, manifest::core::Manifest};
+use crate::new_heads::subscribe_new_heads;
+use crate::rpc_cache::rpc_disk_cache;
+use crate::{
+    event::{CombinedEventFilter, RindexerEventFilter},
+    manifest::core::Manifest,
+};
+
+/// The block the reorg-safe distance of `chain` behind `head`.
+pub fn reorg_safe_block_number(chain: Chain, head: U64) -> U64 {
+    head.saturating_sub(U64::from(reorg_safe_distance_for_chain(chain.id())))
+}
 
 /// Trait abstracting the chain-facing RPC operations that rindexer needs.
 ///
@@ -68,8 +87,30 @@ pub trait ChainProvider: Send + Sync + Debug {
 
     async fn get_latest_block(&self) -> Result<Option<Arc<AnyRpcBlock>>, ProviderError>;
     async fn get_block_number(&self) -> Result<U64, ProviderError>;
+    /// The number of the block the node reports for `tag`, `None` if it has none yet.
+    ///
+    /// Providers without block tags keep the default, which treats the block the reorg-safe
+    /// distance of the chain behind the head as both finalized and safe.
+    async fn get_tagged_block_number(&self, _tag: BlockTag) -> Result<Option<U64>, ProviderError> {
+        let head = self.get_block_number().await?;
+        Ok(Some(reorg_safe_block_number(self.chain(), head)))
+    }
     async fn get_logs(&self, event_filter: &RindexerEventFilter)
         -> Result<Vec<Log>, ProviderError>;
+    /// The logs of every event of a [`CombinedEventFilter`].
+    ///
+    /// Providers without a combined `eth_getLogs` keep the default, which fetches the events
+    /// one by one and merges their logs.
+    async fn get_combined_logs(
+        &self,
+        filter: &CombinedEventFilter,
+    ) -> Result<Vec<Log>, ProviderError> {
+        let mut logs = Vec::new();
+        for event_filter in filter.event_filters() {
+            logs.extend(self.get_logs(&event_filter).await?);
+        }
+        Ok(logs)
+    }
     async fn get_block_by_number_batch(
         &self,
         block_numbers: &[U64],
@@ -100,6 +141,34 @@ pub trait ChainProvider: Send + Sync + Debug {
         block_number: u64,
     ) -> Result<String, ProviderError>;
     async fn eth_call_latest(&self, to: Address, data: Bytes) -> Result<String, ProviderError>;
+    /// Read a storage slot at the latest block, used to resolve proxy implementations.
+    ///
+    /// Providers which can't read storage keep the default, `proxy: auto` then reports the
+    /// contract as unresolved instead of failing.
+    async fn get_storage_at(&self, address: Address, slot: B256) -> Result<B256, ProviderError> {
+        Err(ProviderError::CustomError(format!(
+            "{} does not support reading storage slot {} of {}",
+            self.chain(),
+            slot,
+            address
+        )))
+    }
+    /// Read the code of `address` at a historical block, used by `start_block: auto`.
+    ///
+    /// Providers which can't read historical code keep the default, the start block then has to
+    /// be set explicitly.
+    async fn get_code_at(
+        &self,
+        address: Address,
+        block_number: u64,
+    ) -> Result<Bytes, ProviderError> {
+        Err(ProviderError::CustomError(format!(
+            "{} does not support reading the code of {} at block {}",
+            self.chain(),
+            address,
+            block_number
+        )))
+    }
 }
 
 /// An alias type for a complex alloy Provider
@@ -128,14 +197,75 @@ pub struct JsonRpcCachedProvider {
     provider: Arc<RindexerProvider>,
     client: RpcClient,
     cache: Mutex<Option<(Instant, Arc<AnyRpcBlock>)>>,
+    tagged_cache: std::sync::Mutex<HashMap<BlockTag, (Instant, Option<U64>)>>,
     is_zk_chain: bool,
     pub chain: Chain,
     block_poll_frequency: Option<BlockPollFrequency>,
     address_filtering: Option<AddressFiltering>,
     pub max_block_range: Option<U64>,
     pub chain_state_notification: Option<Sender<ChainStateNotification>>,
+    pushed_head: Arc<std::sync::Mutex<Option<PushedHead>>>,
+    /// Highest block number seen, responses at or below it minus the reorg-safe distance can
+    /// go to the disk cache.
+    latest_block_seen: AtomicU64,
+    /// Reorg-safe distance and finality tag a response has to be past before it goes to the disk
+    /// cache. Starts at the chain default, networks from a manifest take their contracts' settings.
+    disk_cache_finality: std::sync::RwLock<(u64, Option<BlockTag>)>,
 }
 
+/// Identifies an `eth_getLogs` request in the disk cache. Topics and addresses are sorted, the
+/// filter itself holds them in hash sets which serialize in a different order every run.
+#[derive(Serialize)]
+struct LogsCacheKey {
+    topic_ids: Vec<B256>,
+    topics: [Vec<B256>; 3],
+    from_block: u64,
+    to_block: u64,
+    addresses: Option<Vec<Address>>,
+}
+
+impl LogsCacheKey {
+    fn new(
+        mut topic_ids: Vec<B256>,
+        topics: [&Topic; 3],
+        from_block: U64,
+        to_block: U64,
+        addresses: &Option<HashSet<Address>>,
+    ) -> Self {
+        topic_ids.sort();
+
+        Self {
+            topic_ids,
+            topics: topics.map(|topic| {
+                let mut values: Vec<B256> = topic.iter().copied().collect();
+                values.sort();
+                values
+            }),
+            from_block: from_block.to(),
+            to_block: to_block.to(),
+            addresses: addresses.as_ref().map(|addresses| {
+                let mut addresses: Vec<Address> = addresses.iter().copied().collect();
+                addresses.sort();
+                addresses
+            }),
+        }
+    }
+}
+
+/// The latest head pushed through the `chain_state_notification` channel (reth ExEx or a
+/// `ws_rpc` `newHeads` subscription).
+#[derive(Debug, Clone, Copy)]
+struct PushedHead {
+    number: u64,
+    received_at: Instant,
+    /// Set on a reorg so the cached latest block is refetched.
+    reorged: bool,
+}
+
+/// The cached latest block is refetched at most this often while a pushed head is ahead of it,
+/// in case the RPC lags behind the node pushing heads.
+const MIN_PUSHED_HEAD_REFRESH: Duration = Duration::from_millis(50);
+
 #[derive(Error, Debug)]
 pub enum ProviderError {
     #[error("Failed to make rpc request: {0}")]
@@ -257,6 +387,15 @@ impl JsonRpcCachedProvider {
         }
     }
 
+    /// Pushed heads older than this are ignored and the latest block is polled again, three
+    /// block times gives room for slow blocks before assuming the subscription is gone.
+    fn pushed_head_stale_after(&self) -> Duration {
+        self.chain
+            .average_blocktime_hint()
+            .map(|t| (t * 3).max(Duration::from_secs(3)))
+            .unwrap_or(Duration::from_secs(30))
+    }
+
     #[tracing::instrument(skip_all)]
     pub async fn get_latest_block(&self) -> Result<Option<Arc<AnyRpcBlock>>, ProviderError> {
         let mut cache_guard = self.cache.lock().await;
@@ -267,8 +406,18 @@ impl JsonRpcCachedProvider {
         //
         // If we want to reduce RPC calls further at the cost of we could consider indexing delay we
         // could set this to block-time directly.
+        //
+        // When heads are pushed to us the cache is instead kept until a newer head arrives, and
+        // falls back to the poll frequency once heads stop coming in.
         if let Some((timestamp, block)) = &*cache_guard {
-            if timestamp.elapsed() < cache_time {
+            let pushed_head = *self.pushed_head.lock().expect("pushed head lock poisoned");
+            if is_cached_block_fresh(
+                block.header.number,
+                timestamp.elapsed(),
+                cache_time,
+                pushed_head.map(|head| (head.number, head.received_at.elapsed(), head.reorged)),
+                self.pushed_head_stale_after(),
+            ) {
                 return Ok(Some(Arc::clone(block)));
             }
         }
@@ -290,7 +439,12 @@ impl JsonRpcCachedProvider {
         // Now propagate the error if any
         let latest_block = result?;
 
+        if let Some(head) = self.pushed_head.lock().expect("pushed head lock poisoned").as_mut() {
+            head.reorged = false;
+        }
+
         if let Some(block) = latest_block {
+            self.latest_block_seen.fetch_max(block.header.number, Ordering::Relaxed);
             let arc_block = Arc::new(block);
             *cache_guard = Some((Instant::now(), Arc::clone(&arc_block)));
             return Ok(Some(arc_block));
@@ -304,9 +458,49 @@ impl JsonRpcCachedProvider {
     #[tracing::instrument(skip_all)]
     pub async fn get_block_number(&self) -> Result<U64, ProviderError> {
         let number = self.provider.get_block_number().await?;
+        self.latest_block_seen.fetch_max(number, Ordering::Relaxed);
         Ok(U64::from(number))
     }
 
+    /// Tagged blocks move at most once a block, so they are cached for a block time (or the
+    /// poll frequency when that is longer) as every live indexing loop asks for them.
+    #[tracing::instrument(skip_all)]
+    pub async fn get_tagged_block_number(
+        &self,
+        tag: BlockTag,
+    ) -> Result<Option<U64>, ProviderError> {
+        let cache_time = self
+            .chain
+            .average_blocktime_hint()
+            .unwrap_or(Duration::from_secs(1))
+            .max(self.block_poll_frequency());
+
+        if let Some((timestamp, number)) =
+            self.tagged_cache.lock().expect("tagged cache lock poisoned").get(&tag)
+        {
+            if timestamp.elapsed() < cache_time {
+                return Ok(*number);
+            }
+        }
+
+        let start = Instant::now();
+        let result = self.provider.get_block(BlockId::Number(tag.into())).into_future().await;
+        rpc_metrics::record_rpc_request(
+            &self.chain.to_string(),
+            "eth_getBlockByNumber",
+            result.is_ok(),
+            start.elapsed().as_secs_f64(),
+        );
+
+        let number = result?.map(|block| U64::from(block.header.number));
+        self.tagged_cache
+            .lock()
+            .expect("tagged cache lock poisoned")
+            .insert(tag, (Instant::now(), number));
+
+        Ok(number)
+    }
+
     /// Prefer using `trace_block` where possible as it returns more information.
     ///
     /// The current ethers version does not allow batching, we should upgrade to alloy.
@@ -329,6 +523,19 @@ impl JsonRpcCachedProvider {
     pub async fn debug_trace_block_by_number(
         &self,
         block_number: U64,
+    ) -> Result<Vec<LocalizedTransactionTrace>, ProviderError> {
+        self.with_disk_cache(
+            "debug_traceBlockByNumber",
+            &block_number,
+            block_number.to(),
+            self.fetch_debug_trace_block_by_number(block_number),
+        )
+        .await
+    }
+
+    async fn fetch_debug_trace_block_by_number(
+        &self,
+        block_number: U64,
     ) -> Result<Vec<LocalizedTransactionTrace>, ProviderError> {
         // TODO: Consider the need to use `arbtrace_block` for early arbitrum blocks?
         let block = json!(serde_json::to_string_pretty(&block_number)?.replace("\"", ""));
@@ -411,12 +618,15 @@ impl JsonRpcCachedProvider {
         &self,
         block_number: U64,
     ) -> Result<Vec<LocalizedTransactionTrace>, ProviderError> {
-        let traces = self
-            .provider
-            .trace_block(BlockId::Number(BlockNumberOrTag::Number(block_number.as_limbs()[0])))
-            .await?;
+        self.with_disk_cache("trace_block", &block_number, block_number.to(), async {
+            let traces = self
+                .provider
+                .trace_block(BlockId::Number(BlockNumberOrTag::Number(block_number.as_limbs()[0])))
+                .await?;
 
-        Ok(traces)
+            Ok(traces)
+        })
+        .await
     }
 
     /// Makes an `eth_call` request at a specific block for view function calls.
@@ -435,21 +645,24 @@ impl JsonRpcCachedProvider {
         data: Bytes,
         block_number: u64,
     ) -> Result<String, ProviderError> {
-        let result: String = self
-            .provider
-            .raw_request(
-                "eth_call".into(),
-                (
-                    json!({
-                        "to": format!("{:?}", to),
-                        "data": format!("0x{}", hex::encode(&data)),
-                    }),
-                    format!("0x{:x}", block_number),
-                ),
-            )
-            .await?;
+        self.with_disk_cache("eth_call", &(to, &data, block_number), block_number, async {
+            let result: String = self
+                .provider
+                .raw_request(
+                    "eth_call".into(),
+                    (
+                        json!({
+                            "to": format!("{:?}", to),
+                            "data": format!("0x{}", hex::encode(&data)),
+                        }),
+                        format!("0x{:x}", block_number),
+                    ),
+                )
+                .await?;
 
-        Ok(result)
+            Ok(result)
+        })
+        .await
     }
 
     /// Executes eth_call at the "latest" block.
@@ -473,6 +686,35 @@ impl JsonRpcCachedProvider {
         Ok(result)
     }
 
+    /// Reads a storage slot at the "latest" block, used to resolve proxy implementations.
+    #[tracing::instrument(skip_all)]
+    pub async fn get_storage_at(
+        &self,
+        address: Address,
+        slot: B256,
+    ) -> Result<B256, ProviderError> {
+        let value: U256 =
+            self.provider.raw_request("eth_getStorageAt".into(), (address, slot, "latest")).await?;
+
+        Ok(B256::from(value))
+    }
+
+    /// Reads the deployed bytecode of `address` at a historical block, empty if nothing was
+    /// deployed there yet.
+    #[tracing::instrument(skip_all)]
+    pub async fn get_code_at(
+        &self,
+        address: Address,
+        block_number: u64,
+    ) -> Result<Bytes, ProviderError> {
+        let code: Bytes = self
+            .provider
+            .raw_request("eth_getCode".into(), (address, format!("0x{:x}", block_number)))
+            .await?;
+
+        Ok(code)
+    }
+
     /// Fetches blocks in concurrent rpc batches.
     #[tracing::instrument(skip_all, fields(len = block_numbers.len()))]
     pub async fn get_block_by_number_batch(
@@ -490,6 +732,52 @@ impl JsonRpcCachedProvider {
         block_numbers: &[U64],
         include_txs: bool,
         rpc_batch_size: Option<usize>,
+    ) -> Result<Vec<AnyRpcBlock>, ProviderError> {
+        let Some(cache) = rpc_disk_cache() else {
+            return self
+                .fetch_block_by_number_batch(block_numbers, include_txs, rpc_batch_size)
+                .await;
+        };
+
+        let chain_id = self.chain.id();
+        let mut blocks: Vec<AnyRpcBlock> = Vec::with_capacity(block_numbers.len());
+        let mut missing = Vec::new();
+        for block_number in block_numbers {
+            match cache.get(chain_id, "eth_getBlockByNumber", &(block_number, include_txs)).await {
+                Some(block) => blocks.push(block),
+                None => missing.push(*block_number),
+            }
+        }
+
+        let fetched =
+            self.fetch_block_by_number_batch(&missing, include_txs, rpc_batch_size).await?;
+        for block in &fetched {
+            let block_number = U64::from(block.header.number);
+            if self.is_reorg_safe(block.header.number).await {
+                cache
+                    .put(chain_id, "eth_getBlockByNumber", &(block_number, include_txs), block)
+                    .await;
+            }
+        }
+
+        // Keep the order the blocks were requested in
+        let positions: HashMap<u64, usize> = block_numbers
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(position, block_number)| (block_number.to::<u64>(), position))
+            .collect();
+        blocks.extend(fetched);
+        blocks.sort_by_key(|block| positions.get(&block.header.number).copied());
+
+        Ok(blocks)
+    }
+
+    async fn fetch_block_by_number_batch(
+        &self,
+        block_numbers: &[U64],
+        include_txs: bool,
+        rpc_batch_size: Option<usize>,
     ) -> Result<Vec<AnyRpcBlock>, ProviderError> {
         let chain_id = self.chain.id();
         // Use adaptive batch size (auto-scales down on rate limits for free nodes)
@@ -615,9 +903,6 @@ impl JsonRpcCachedProvider {
         &self,
         event_filter: &RindexerEventFilter,
     ) -> Result<Vec<Log>, ProviderError> {
-        let start = Instant::now();
-        let network = self.chain.to_string();
-
         let addresses = event_filter.contract_addresses().await;
 
         let base_filter = Filter::new()
@@ -628,20 +913,87 @@ impl JsonRpcCachedProvider {
             .from_block(event_filter.from_block())
             .to_block(event_filter.to_block());
 
-        let logs = match addresses {
+        let cache_key = LogsCacheKey::new(
+            vec![event_filter.event_signature()],
+            [&event_filter.topic1(), &event_filter.topic2(), &event_filter.topic3()],
+            event_filter.from_block(),
+            event_filter.to_block(),
+            &addresses,
+        );
+
+        self.with_disk_cache("eth_getLogs", &cache_key, cache_key.to_block, async {
+            let start = Instant::now();
+            let network = self.chain.to_string();
+
+            let logs = self.get_logs_for_addresses(&base_filter, addresses).await;
+
+            // Record RPC metrics
+            let duration = start.elapsed().as_secs_f64();
+            rpc_metrics::record_rpc_request(&network, "eth_getLogs", logs.is_ok(), duration);
+
+            logs
+        })
+        .await
+    }
+
+    /// Get the logs of several events at once, see [`CombinedEventFilter`].
+    #[tracing::instrument(skip_all)]
+    pub async fn get_combined_logs(
+        &self,
+        filter: &CombinedEventFilter,
+    ) -> Result<Vec<Log>, ProviderError> {
+        let [topic1, topic2, topic3] = &filter.topics;
+        let base_filter = Filter::new()
+            .event_signature(filter.topic_ids.clone())
+            .topic1(topic1.clone())
+            .topic2(topic2.clone())
+            .topic3(topic3.clone())
+            .from_block(filter.from_block)
+            .to_block(filter.to_block);
+
+        let cache_key = LogsCacheKey::new(
+            filter.topic_ids.clone(),
+            [topic1, topic2, topic3],
+            filter.from_block,
+            filter.to_block,
+            &filter.addresses,
+        );
+
+        self.with_disk_cache("eth_getLogs", &cache_key, cache_key.to_block, async {
+            let start = Instant::now();
+            let network = self.chain.to_string();
+
+            let logs = self.get_logs_for_addresses(&base_filter, filter.addresses.clone()).await;
+
+            let duration = start.elapsed().as_secs_f64();
+            rpc_metrics::record_rpc_request(&network, "eth_getLogs", logs.is_ok(), duration);
+
+            logs
+        })
+        .await
+    }
+
+    /// Get the logs of a filter restricted to `addresses`, following the address filtering
+    /// settings of the network.
+    async fn get_logs_for_addresses(
+        &self,
+        base_filter: &Filter,
+        addresses: Option<HashSet<Address>>,
+    ) -> Result<Vec<Log>, ProviderError> {
+        match addresses {
             // no addresses, which means nothing to get
             // different rpc providers implement an empty array differently,
             // therefore, we assume an empty addresses array means no events to fetch
             Some(addresses) if addresses.is_empty() => Ok(vec![]),
             Some(addresses) => match self.address_filtering {
                 Some(AddressFiltering::InMemory) => {
-                    self.get_logs_for_address_in_memory(&base_filter, addresses).await
+                    self.get_logs_for_address_in_memory(base_filter, addresses).await
                 }
                 Some(AddressFiltering::MaxAddressPerGetLogsRequest(
                     max_address_per_get_logs_request,
                 )) => {
                     self.get_logs_for_address_in_batches(
-                        &base_filter,
+                        base_filter,
                         addresses,
                         max_address_per_get_logs_request,
                     )
@@ -649,21 +1001,15 @@ impl JsonRpcCachedProvider {
                 }
                 None => {
                     self.get_logs_for_address_in_batches(
-                        &base_filter,
+                        base_filter,
                         addresses,
                         DEFAULT_RPC_SUPPORTED_ACCOUNT_FILTERS,
                     )
                     .await
                 }
             },
-            None => Ok(self.provider.get_logs(&base_filter).await?),
-        };
-
-        // Record RPC metrics
-        let duration = start.elapsed().as_secs_f64();
-        rpc_metrics::record_rpc_request(&network, "eth_getLogs", logs.is_ok(), duration);
-
-        logs
+            None => Ok(self.provider.get_logs(base_filter).await?),
+        }
     }
 
     /// Get logs by chunking addresses and fetching asynchronously in batches
@@ -702,6 +1048,63 @@ impl JsonRpcCachedProvider {
         Ok(filtered_logs)
     }
 
+    /// Only cache responses the indexer itself treats as final: past `distance` blocks of the
+    /// latest block and, with a `finalized` or `safe` tag, at or below the tagged block.
+    pub fn set_disk_cache_finality(&self, distance: u64, tag: Option<BlockTag>) {
+        *self.disk_cache_finality.write().expect("disk cache finality lock poisoned") =
+            (distance, tag);
+    }
+
+    /// Whether `block_number` is final by the disk cache finality. Nodes without support for
+    /// the tag fall back to the distance, the same as the indexer does.
+    async fn is_reorg_safe(&self, block_number: u64) -> bool {
+        let (distance, tag) =
+            *self.disk_cache_finality.read().expect("disk cache finality lock poisoned");
+        let latest = self.latest_block_seen.load(Ordering::Relaxed);
+        if latest == 0 || block_number.saturating_add(distance) > latest {
+            return false;
+        }
+
+        match tag {
+            Some(tag) => match self.get_tagged_block_number(tag).await {
+                Ok(Some(tagged)) => block_number <= tagged.to::<u64>(),
+                Ok(None) => true,
+                Err(_) => false,
+            },
+            None => true,
+        }
+    }
+
+    /// Serve a request from the disk cache when enabled, storing the fetched response once
+    /// `to_block` is reorg safe.
+    async fn with_disk_cache<T, F>(
+        &self,
+        method: &str,
+        params: &impl Serialize,
+        to_block: u64,
+        fetch: F,
+    ) -> Result<T, ProviderError>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Future<Output = Result<T, ProviderError>>,
+    {
+        let Some(cache) = rpc_disk_cache() else {
+            return fetch.await;
+        };
+
+        let chain_id = self.chain.id();
+        if let Some(value) = cache.get(chain_id, method, params).await {
+            return Ok(value);
+        }
+
+        let value = fetch.await?;
+        if self.is_reorg_safe(to_block).await {
+            cache.put(chain_id, method, params, &value).await;
+        }
+
+        Ok(value)
+    }
+
     pub fn get_inner_provider(&self) -> Arc<RindexerProvider> {
         Arc::clone(&self.provider)
     }
@@ -739,12 +1142,19 @@ impl JsonRpcCachedProvider {
             provider: Arc::new(provider),
             client,
             cache: Mutex::new(None),
+            tagged_cache: std::sync::Mutex::new(HashMap::new()),
             is_zk_chain,
             chain,
             block_poll_frequency: None,
             address_filtering: None,
             max_block_range: None,
             chain_state_notification: None,
+            pushed_head: Arc::new(std::sync::Mutex::new(None)),
+            latest_block_seen: AtomicU64::new(0),
+            disk_cache_finality: std::sync::RwLock::new((
+                reorg_safe_distance_for_chain(chain_id),
+                None,
+            )),
         });
 
         (cached, asserter)
@@ -773,6 +1183,10 @@ impl ChainProvider for JsonRpcCachedProvider {
         self.get_block_number().await
     }
 
+    async fn get_tagged_block_number(&self, tag: BlockTag) -> Result<Option<U64>, ProviderError> {
+        self.get_tagged_block_number(tag).await
+    }
+
     async fn get_logs(
         &self,
         event_filter: &RindexerEventFilter,
@@ -780,6 +1194,13 @@ impl ChainProvider for JsonRpcCachedProvider {
         self.get_logs(event_filter).await
     }
 
+    async fn get_combined_logs(
+        &self,
+        filter: &CombinedEventFilter,
+    ) -> Result<Vec<Log>, ProviderError> {
+        self.get_combined_logs(filter).await
+    }
+
     async fn get_block_by_number_batch(
         &self,
         block_numbers: &[U64],
@@ -830,6 +1251,18 @@ impl ChainProvider for JsonRpcCachedProvider {
     async fn eth_call_latest(&self, to: Address, data: Bytes) -> Result<String, ProviderError> {
         self.eth_call_latest(to, data).await
     }
+
+    async fn get_storage_at(&self, address: Address, slot: B256) -> Result<B256, ProviderError> {
+        self.get_storage_at(address, slot).await
+    }
+
+    async fn get_code_at(
+        &self,
+        address: Address,
+        block_number: u64,
+    ) -> Result<Bytes, ProviderError> {
+        self.get_code_at(address, block_number).await
+    }
 }
 
 /// Blanket implementation so that `Arc<T>` can be used wherever `T: ChainProvider`.
@@ -859,6 +1292,10 @@ impl<T: ChainProvider + ?Sized> ChainProvider for Arc<T> {
         (**self).get_block_number().await
     }
 
+    async fn get_tagged_block_number(&self, tag: BlockTag) -> Result<Option<U64>, ProviderError> {
+        (**self).get_tagged_block_number(tag).await
+    }
+
     async fn get_logs(
         &self,
         event_filter: &RindexerEventFilter,
@@ -866,6 +1303,13 @@ impl<T: ChainProvider + ?Sized> ChainProvider for Arc<T> {
         (**self).get_logs(event_filter).await
     }
 
+    async fn get_combined_logs(
+        &self,
+        filter: &CombinedEventFilter,
+    ) -> Result<Vec<Log>, ProviderError> {
+        (**self).get_combined_logs(filter).await
+    }
+
     async fn get_block_by_number_batch(
         &self,
         block_numbers: &[U64],
@@ -918,6 +1362,18 @@ impl<T: ChainProvider + ?Sized> ChainProvider for Arc<T> {
     async fn eth_call_latest(&self, to: Address, data: Bytes) -> Result<String, ProviderError> {
         (**self).eth_call_latest(to, data).await
     }
+
+    async fn get_storage_at(&self, address: Address, slot: B256) -> Result<B256, ProviderError> {
+        (**self).get_storage_at(address, slot).await
+    }
+
+    async fn get_code_at(
+        &self,
+        address: Address,
+        block_number: u64,
+    ) -> Result<Bytes, ProviderError> {
+        (**self).get_code_at(address, block_number).await
+    }
 }
 
 /// A mock implementation of [`ChainProvider`] for testing.
@@ -926,6 +1382,8 @@ impl<T: ChainProvider + ?Sized> ChainProvider for Arc<T> {
 /// functions that accept `impl ChainProvider`.
 #[cfg(test)]
 pub mod mock {
+    use std::collections::HashMap;
+
     use super::*;
 
     #[derive(Debug)]
@@ -935,8 +1393,13 @@ pub mod mock {
         logs: Vec<Log>,
         blocks: Vec<Arc<AnyRpcBlock>>,
         block_number: U64,
+        tagged_blocks: HashMap<BlockTag, U64>,
         receipts: Vec<AnyTransactionReceipt>,
         traces: Vec<LocalizedTransactionTrace>,
+        storage: HashMap<(Address, B256), B256>,
+        call_results: HashMap<(Address, Bytes), String>,
+        deployments: HashMap<Address, u64>,
+        state_pruned_before: Option<u64>,
     }
 
     impl MockChainProvider {
@@ -947,8 +1410,13 @@ pub mod mock {
                 logs: vec![],
                 blocks: vec![],
                 block_number: U64::ZERO,
+                tagged_blocks: HashMap::new(),
                 receipts: vec![],
                 traces: vec![],
+                storage: HashMap::new(),
+                call_results: HashMap::new(),
+                deployments: HashMap::new(),
+                state_pruned_before: None,
             }
         }
 
@@ -957,6 +1425,11 @@ pub mod mock {
             self
         }
 
+        pub fn with_tagged_block(mut self, tag: BlockTag, n: u64) -> Self {
+            self.tagged_blocks.insert(tag, U64::from(n));
+            self
+        }
+
         pub fn with_logs(mut self, logs: Vec<Log>) -> Self {
             self.logs = logs;
             self
@@ -976,6 +1449,29 @@ pub mod mock {
             self.max_block_range = Some(U64::from(range));
             self
         }
+
+        pub fn with_storage(mut self, address: Address, slot: B256, value: B256) -> Self {
+            self.storage.insert((address, slot), value);
+            self
+        }
+
+        /// Respond to an `eth_call` to `to` with `data` with the given hex encoded result.
+        pub fn with_call_result(mut self, to: Address, data: Bytes, result: &str) -> Self {
+            self.call_results.insert((to, data), result.to_string());
+            self
+        }
+
+        /// Report code for `address` from `block_number` onwards.
+        pub fn with_deployment(mut self, address: Address, block_number: u64) -> Self {
+            self.deployments.insert(address, block_number);
+            self
+        }
+
+        /// Fail state reads below `block_number` the way a non-archive node does.
+        pub fn with_state_pruned_before(mut self, block_number: u64) -> Self {
+            self.state_pruned_before = Some(block_number);
+            self
+        }
     }
 
     #[async_trait]
@@ -1000,6 +1496,13 @@ pub mod mock {
             Ok(self.block_number)
         }
 
+        async fn get_tagged_block_number(
+            &self,
+            tag: BlockTag,
+        ) -> Result<Option<U64>, ProviderError> {
+            Ok(self.tagged_blocks.get(&tag).copied())
+        }
+
         async fn get_logs(
             &self,
             event_filter: &RindexerEventFilter,
@@ -1087,19 +1590,43 @@ pub mod mock {
 
         async fn eth_call(
             &self,
-            _to: Address,
-            _data: Bytes,
+            to: Address,
+            data: Bytes,
             _block_number: u64,
         ) -> Result<String, ProviderError> {
-            Ok(String::new())
+            Ok(self.call_results.get(&(to, data)).cloned().unwrap_or_default())
+        }
+
+        async fn eth_call_latest(&self, to: Address, data: Bytes) -> Result<String, ProviderError> {
+            Ok(self.call_results.get(&(to, data)).cloned().unwrap_or_default())
         }
 
-        async fn eth_call_latest(
+        async fn get_storage_at(
             &self,
-            _to: Address,
-            _data: Bytes,
-        ) -> Result<String, ProviderError> {
-            Ok(String::new())
+            address: Address,
+            slot: B256,
+        ) -> Result<B256, ProviderError> {
+            Ok(self.storage.get(&(address, slot)).copied().unwrap_or_default())
+        }
+
+        async fn get_code_at(
+            &self,
+            address: Address,
+            block_number: u64,
+        ) -> Result<Bytes, ProviderError> {
+            if self.state_pruned_before.is_some_and(|pruned| block_number < pruned) {
+                return Err(ProviderError::CustomError(format!(
+                    "missing trie node for the state of block {}",
+                    block_number
+                )));
+            }
+
+            match self.deployments.get(&address) {
+                Some(deployed_at) if block_number >= *deployed_at => {
+                    Ok(Bytes::from_static(&[0x60, 0x80]))
+                }
+                _ => Ok(Bytes::new()),
+            }
         }
     }
 }
@@ -1201,9 +1728,15 @@ pub async fn create_client(
         }
     };
 
+    let pushed_head = Arc::new(std::sync::Mutex::new(None));
+    if let Some(notifications) = &chain_state_notification {
+        track_pushed_head(notifications.subscribe(), Arc::downgrade(&pushed_head));
+    }
+
     Ok(Arc::new(JsonRpcCachedProvider {
         provider: Arc::new(provider),
         cache: Mutex::new(None),
+        tagged_cache: std::sync::Mutex::new(HashMap::new()),
         max_block_range,
         client,
         chain,
@@ -1211,9 +1744,63 @@ pub async fn create_client(
         block_poll_frequency,
         address_filtering,
         chain_state_notification,
+        pushed_head,
+        latest_block_seen: AtomicU64::new(0),
+        disk_cache_finality: std::sync::RwLock::new((
+            reorg_safe_distance_for_chain(chain_id),
+            None,
+        )),
     }))
 }
 
+/// Keep `pushed_head` up to date with the chain state notifications until the provider is dropped.
+fn track_pushed_head(
+    mut notifications: Receiver<ChainStateNotification>,
+    pushed_head: Weak<std::sync::Mutex<Option<PushedHead>>>,
+) {
+    tokio::spawn(async move {
+        loop {
+            let notification = match notifications.recv().await {
+                Ok(notification) => notification,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+
+            let Some(pushed_head) = pushed_head.upgrade() else {
+                break;
+            };
+
+            let (number, reorged) = match notification {
+                ChainStateNotification::Committed { to_block, .. } => (to_block, false),
+                ChainStateNotification::Reorged { new_to_block, .. } => (new_to_block, true),
+                ChainStateNotification::Reverted { from_block, .. } => {
+                    (from_block.saturating_sub(1), true)
+                }
+            };
+
+            *pushed_head.lock().expect("pushed head lock poisoned") =
+                Some(PushedHead { number, received_at: Instant::now(), reorged });
+        }
+    });
+}
+
+/// Whether the cached latest block can be served. `pushed_head` is the `(number, age, reorged)`
+/// of the latest pushed head.
+fn is_cached_block_fresh(
+    cached_number: u64,
+    cached_age: Duration,
+    cache_time: Duration,
+    pushed_head: Option<(u64, Duration, bool)>,
+    pushed_head_stale_after: Duration,
+) -> bool {
+    match pushed_head {
+        Some((number, age, reorged)) if age < pushed_head_stale_after => {
+            !reorged && (cached_number >= number || cached_age < MIN_PUSHED_HEAD_REFRESH)
+        }
+        _ => cached_age < cache_time,
+    }
+}
+
 pub async fn get_chain_id(rpc_url: &str) -> Result<U256, RpcError<TransportErrorKind>> {
     let url = Url::parse(rpc_url).map_err(|e| RpcError::LocalUsageError(Box::new(e)))?;
     let provider = ProviderBuilder::new().connect_http(url);
@@ -1226,14 +1813,33 @@ pub async fn get_chain_id(rpc_url: &str) -> Result<U256, RpcError<TransportError
 pub struct CreateNetworkProvider {
     pub network_name: String,
     pub disable_logs_bloom_checks: bool,
-    pub client: Arc<JsonRpcCachedProvider>,
+    pub client: Arc<dyn ChainProvider>,
 }
 
 impl CreateNetworkProvider {
     pub async fn create(
         manifest: &Manifest,
+    ) -> Result<Vec<CreateNetworkProvider>, RetryClientError> {
+        Self::create_with_overrides(manifest, &HashMap::new()).await
+    }
+
+    /// Like [`CreateNetworkProvider::create`], but networks named in `overrides` use the given
+    /// provider instead of connecting to their `rpc`, e.g. a `SimChain` in tests.
+    pub async fn create_with_overrides(
+        manifest: &Manifest,
+        overrides: &HashMap<String, Arc<dyn ChainProvider>>,
     ) -> Result<Vec<CreateNetworkProvider>, RetryClientError> {
         let provider_futures = manifest.networks.iter().map(|network| async move {
+            if let Some(provider) = overrides.get(&network.name) {
+                return Ok(CreateNetworkProvider {
+                    network_name: network.name.clone(),
+                    disable_logs_bloom_checks: network
+                        .disable_logs_bloom_checks
+                        .unwrap_or_default(),
+                    client: Arc::clone(provider),
+                });
+            }
+
             #[cfg(not(feature = "reth"))]
             let provider_url = network.rpc.clone();
 
@@ -1256,6 +1862,14 @@ impl CreateNetworkProvider {
                 network.rpc.clone()
             };
 
+            // without a reth node, heads can still be pushed through a `newHeads` subscription
+            let chain_state_notification = reth_tx.clone().or_else(|| {
+                network
+                    .ws_rpc
+                    .clone()
+                    .map(|ws_rpc| subscribe_new_heads(ws_rpc, network.name.clone()))
+            });
+
             // create the provider
             let provider = create_client(
                 &provider_url,
@@ -1265,10 +1879,16 @@ impl CreateNetworkProvider {
                 network.block_poll_frequency,
                 manifest.get_custom_headers(),
                 network.get_logs_settings.clone().map(|settings| settings.address_filtering),
-                reth_tx.clone(),
+                chain_state_notification,
             )
             .await?;
 
+            provider.set_disk_cache_finality(
+                finalized_buffer_distance_for_network(manifest, &network.name, network.chain_id),
+                finality_tag_for_network(manifest, &network.name),
+            );
+            register_block_range_controller(&network.name, &provider_url);
+
             Ok::<_, RetryClientError>(CreateNetworkProvider {
                 network_name: network.name.clone(),
                 disable_logs_bloom_checks: network.disable_logs_bloom_checks.unwrap_or_default(),
@@ -1281,7 +1901,7 @@ impl CreateNetworkProvider {
 
     /// Get the chain state notification for this network
     pub fn chain_state_notification(&self) -> Option<Sender<ChainStateNotification>> {
-        self.client.chain_state_notification.clone()
+        self.client.chain_state_notification()
     }
 }
 
@@ -1312,6 +1932,13 @@ mod tests {
         assert_eq!(mock.chain().id(), 137);
     }
 
+    #[test]
+    fn reorg_safe_block_number_trails_the_head() {
+        assert_eq!(reorg_safe_block_number(Chain::from(1), U64::from(1_000)), U64::from(980));
+        assert_eq!(reorg_safe_block_number(Chain::from(137), U64::from(1_000)), U64::from(800));
+        assert_eq!(reorg_safe_block_number(Chain::from(1), U64::from(5)), U64::ZERO);
+    }
+
     #[tokio::test]
     async fn mock_chain_provider_get_block_number() {
         let mock = MockChainProvider::new(1).with_block_number(42);
@@ -1499,4 +2126,87 @@ mod tests {
         let result = provider.get_latest_block().await;
         assert!(result.is_err(), "expected Err when asserter queue is empty");
     }
+
+    // ── is_cached_block_fresh tests ─────────────────────────────────────────
+
+    const POLL: Duration = Duration::from_millis(500);
+    const STALE: Duration = Duration::from_secs(30);
+
+    #[test]
+    fn cached_block_without_pushed_heads_follows_poll_frequency() {
+        assert!(is_cached_block_fresh(10, Duration::from_millis(100), POLL, None, STALE));
+        assert!(!is_cached_block_fresh(10, Duration::from_secs(1), POLL, None, STALE));
+    }
+
+    #[test]
+    fn cached_block_kept_until_a_newer_head_is_pushed() {
+        let head = Some((10, Duration::from_secs(1), false));
+        assert!(is_cached_block_fresh(10, Duration::from_secs(5), POLL, head, STALE));
+
+        let newer_head = Some((11, Duration::from_millis(10), false));
+        assert!(!is_cached_block_fresh(10, Duration::from_millis(100), POLL, newer_head, STALE));
+    }
+
+    #[test]
+    fn cached_block_refetched_on_pushed_reorg() {
+        let head = Some((10, Duration::from_millis(10), true));
+        assert!(!is_cached_block_fresh(10, Duration::from_millis(10), POLL, head, STALE));
+    }
+
+    #[test]
+    fn stale_pushed_head_falls_back_to_polling() {
+        let head = Some((10, Duration::from_secs(60), false));
+        assert!(!is_cached_block_fresh(10, Duration::from_secs(1), POLL, head, STALE));
+    }
+
+    #[tokio::test]
+    async fn disk_cache_only_takes_blocks_past_the_reorg_safe_distance() {
+        let provider = JsonRpcCachedProvider::mock(1);
+        assert!(!provider.is_reorg_safe(0).await, "nothing is safe before a block was seen");
+
+        provider.latest_block_seen.store(100, Ordering::Relaxed);
+        assert!(provider.is_reorg_safe(80).await);
+        assert!(!provider.is_reorg_safe(81).await);
+
+        provider.set_disk_cache_finality(50, None);
+        assert!(provider.is_reorg_safe(50).await);
+        assert!(!provider.is_reorg_safe(51).await);
+    }
+
+    #[tokio::test]
+    async fn disk_cache_waits_for_the_finality_tag() {
+        let (provider, asserter) = JsonRpcCachedProvider::mock_with_asserter(1);
+        provider.latest_block_seen.store(100, Ordering::Relaxed);
+        provider.set_disk_cache_finality(20, Some(BlockTag::Finalized));
+
+        asserter.push_success(&Some(&make_block(50)));
+        assert!(provider.is_reorg_safe(50).await);
+        assert!(!provider.is_reorg_safe(51).await, "past the distance but not finalized yet");
+        assert!(!provider.is_reorg_safe(81).await);
+    }
+
+    #[test]
+    fn logs_cache_key_ignores_topic_and_address_order() {
+        let a = Address::repeat_byte(1);
+        let b = Address::repeat_byte(2);
+        let topic = |n: u8| B256::repeat_byte(n);
+
+        let key = |topic_ids: Vec<B256>, addresses: Vec<Address>| {
+            let topic1 = Topic::from(vec![topic(9), topic(8)]);
+            serde_json::to_string(&LogsCacheKey::new(
+                topic_ids,
+                [&topic1, &Topic::default(), &Topic::default()],
+                U64::from(1),
+                U64::from(10),
+                &Some(addresses.into_iter().collect()),
+            ))
+            .unwrap()
+        };
+
+        assert_eq!(
+            key(vec![topic(1), topic(2)], vec![a, b]),
+            key(vec![topic(2), topic(1)], vec![b, a])
+        );
+        assert_ne!(key(vec![topic(1)], vec![a]), key(vec![topic(1)], vec![b]));
+    }
 }
@@ -0,0 +1,648 @@
+//! Proxy detection and implementation ABI merging.
+//!
+//! Contracts marked `proxy: auto` have their implementation(s) resolved from the standard storage
+//! slots (EIP-1967, EIP-1967 beacon, EIP-1822, legacy OpenZeppelin) or from the diamond loupe
+//! (EIP-2535). The implementation ABIs live in `abis/proxies/<contract>/<implementation>.abi.json`
+//! and are merged into the contract ABI by [`crate::manifest::contract::Contract::parse_abi`].
+//!
+//! While indexing the implementations are re-checked on an interval, an upgrade which changes the
+//! merged event set is surfaced as a warning as the new events need a restart to be indexed.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use alloy::{
+    dyn_abi::{DynSolType, DynSolValue},
+    json_abi::JsonAbi,
+    primitives::{b256, Address, Bytes, B256},
+};
+use alloy_chains::Chain;
+use foundry_block_explorers::{errors::EtherscanError, Client};
+use tracing::{debug, info, warn};
+
+use crate::{
+    helpers::{write_file, WriteFileError},
+    is_running,
+    manifest::{
+        contract::{Contract, ProxySetting},
+        core::Manifest,
+    },
+    provider::{
+        get_network_provider, ChainProvider, CreateNetworkProvider, ProviderError, RetryClientError,
+    },
+    public_read_env_value,
+};
+
+/// `bytes32(uint256(keccak256('eip1967.proxy.implementation')) - 1)`
+pub const EIP1967_IMPLEMENTATION_SLOT: B256 =
+    b256!("360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc");
+
+/// `bytes32(uint256(keccak256('eip1967.proxy.beacon')) - 1)`
+pub const EIP1967_BEACON_SLOT: B256 =
+    b256!("a3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50");
+
+/// `keccak256("PROXIABLE")`
+pub const EIP1822_PROXIABLE_SLOT: B256 =
+    b256!("c5f16f0fcc639fa48a6947836d9850f504798523bf8c9a3a87d5876cf622bcf7");
+
+/// `keccak256("org.zeppelinos.proxy.implementation")`
+pub const OPENZEPPELIN_IMPLEMENTATION_SLOT: B256 =
+    b256!("7050c9e0f4ca769c69bd3a8ef740bc37934f8e2c036e5a723fd8ee048ed3f8c3");
+
+/// `implementation()` on an EIP-1967 beacon
+const BEACON_IMPLEMENTATION_SELECTOR: [u8; 4] = [0x5c, 0x60, 0xda, 0x1b];
+
+/// `facetAddresses()` on an EIP-2535 diamond
+const DIAMOND_FACET_ADDRESSES_SELECTOR: [u8; 4] = [0x52, 0xef, 0x6b, 0x2c];
+
+/// Where the implementation ABIs are stored, relative to the project path.
+pub const PROXY_ABIS_FOLDER: &str = "abis/proxies";
+
+const IMPLEMENTATION_CHECK_INTERVAL: Duration = Duration::from_secs(300);
+
+#[derive(thiserror::Error, Debug)]
+pub enum ProxyError {
+    #[error("Provider error: {0}")]
+    Provider(#[from] ProviderError),
+
+    #[error("Could not fetch ABI from etherscan: {0}")]
+    Etherscan(EtherscanError),
+
+    #[error("Invalid ABI: {0}")]
+    InvalidAbi(#[from] serde_json::Error),
+
+    #[error("Could not write implementation ABI: {0}")]
+    CouldNotWriteAbi(#[from] WriteFileError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyKind {
+    /// EIP-1967 transparent or UUPS proxy
+    Eip1967,
+    /// EIP-1967 beacon proxy
+    Beacon,
+    /// EIP-1822 UUPS proxy
+    Eip1822,
+    /// Pre EIP-1967 OpenZeppelin proxy
+    OpenZeppelin,
+    /// EIP-2535 diamond
+    Diamond,
+}
+
+impl fmt::Display for ProxyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProxyKind::Eip1967 => write!(f, "EIP-1967"),
+            ProxyKind::Beacon => write!(f, "EIP-1967 beacon"),
+            ProxyKind::Eip1822 => write!(f, "EIP-1822"),
+            ProxyKind::OpenZeppelin => write!(f, "OpenZeppelin"),
+            ProxyKind::Diamond => write!(f, "EIP-2535 diamond"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyImplementation {
+    pub kind: ProxyKind,
+    /// The implementation contract, or every facet for a diamond.
+    pub implementations: Vec<Address>,
+}
+
+async fn read_address_slot(
+    provider: &dyn ChainProvider,
+    address: Address,
+    slot: B256,
+) -> Result<Option<Address>, ProviderError> {
+    let word = provider.get_storage_at(address, slot).await?;
+    let implementation = Address::from_word(word);
+    Ok((!implementation.is_zero()).then_some(implementation))
+}
+
+/// Calls a view function with no arguments, any revert or undecodable result is treated as the
+/// contract not supporting it.
+async fn call_view(
+    provider: &dyn ChainProvider,
+    address: Address,
+    selector: [u8; 4],
+    return_type: DynSolType,
+) -> Option<DynSolValue> {
+    let result = provider.eth_call_latest(address, Bytes::from(selector.to_vec())).await.ok()?;
+    let bytes = hex::decode(result.trim_start_matches("0x")).ok()?;
+    if bytes.is_empty() {
+        return None;
+    }
+    return_type.abi_decode(&bytes).ok()
+}
+
+/// Detect whether `address` is a proxy and resolve its current implementation(s).
+pub async fn detect_proxy(
+    provider: &dyn ChainProvider,
+    address: Address,
+) -> Result<Option<ProxyImplementation>, ProviderError> {
+    if let Some(implementation) =
+        read_address_slot(provider, address, EIP1967_IMPLEMENTATION_SLOT).await?
+    {
+        return Ok(Some(ProxyImplementation {
+            kind: ProxyKind::Eip1967,
+            implementations: vec![implementation],
+        }));
+    }
+
+    if let Some(beacon) = read_address_slot(provider, address, EIP1967_BEACON_SLOT).await? {
+        if let Some(DynSolValue::Address(implementation)) =
+            call_view(provider, beacon, BEACON_IMPLEMENTATION_SELECTOR, DynSolType::Address).await
+        {
+            return Ok(Some(ProxyImplementation {
+                kind: ProxyKind::Beacon,
+                implementations: vec![implementation],
+            }));
+        }
+    }
+
+    for (kind, slot) in [
+        (ProxyKind::Eip1822, EIP1822_PROXIABLE_SLOT),
+        (ProxyKind::OpenZeppelin, OPENZEPPELIN_IMPLEMENTATION_SLOT),
+    ] {
+        if let Some(implementation) = read_address_slot(provider, address, slot).await? {
+            return Ok(Some(ProxyImplementation { kind, implementations: vec![implementation] }));
+        }
+    }
+
+    let facets = call_view(
+        provider,
+        address,
+        DIAMOND_FACET_ADDRESSES_SELECTOR,
+        DynSolType::Array(Box::new(DynSolType::Address)),
+    )
+    .await;
+
+    if let Some(DynSolValue::Array(facets)) = facets {
+        let mut implementations = facets
+            .into_iter()
+            .filter_map(|facet| facet.as_address())
+            .filter(|facet| !facet.is_zero() && *facet != address)
+            .collect::<Vec<_>>();
+        // facets are listed once per selector batch, not necessarily next to each other
+        implementations.sort_unstable();
+        implementations.dedup();
+
+        if !implementations.is_empty() {
+            return Ok(Some(ProxyImplementation { kind: ProxyKind::Diamond, implementations }));
+        }
+    }
+
+    Ok(None)
+}
+
+/// The folder holding every implementation ABI resolved for a contract.
+pub fn implementation_abis_folder(project_path: &Path, contract_name: &str) -> PathBuf {
+    project_path.join(PROXY_ABIS_FOLDER).join(contract_name)
+}
+
+pub fn implementation_abi_path(
+    project_path: &Path,
+    contract_name: &str,
+    implementation: Address,
+) -> PathBuf {
+    implementation_abis_folder(project_path, contract_name)
+        .join(format!("{implementation}.abi.json"))
+}
+
+/// Every implementation ABI stored for a contract, sorted so the merged ABI is stable.
+pub fn implementation_abi_paths(
+    project_path: &Path,
+    contract_name: &str,
+) -> Result<Vec<PathBuf>, std::io::Error> {
+    let folder = implementation_abis_folder(project_path, contract_name);
+    if !folder.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut paths = std::fs::read_dir(folder)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.to_string_lossy().ends_with(".abi.json"))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    Ok(paths)
+}
+
+impl From<EtherscanError> for ProxyError {
+    fn from(error: EtherscanError) -> Self {
+        // the api key is sent in the query string, keep the url out of errors and logs
+        match error {
+            EtherscanError::Reqwest(e) => {
+                ProxyError::Etherscan(EtherscanError::Reqwest(e.without_url()))
+            }
+            error => ProxyError::Etherscan(error),
+        }
+    }
+}
+
+/// Fetch a verified contract ABI from the Etherscan multichain API.
+pub async fn fetch_etherscan_abi(
+    chain_id: u64,
+    address: Address,
+    api_key: &str,
+) -> Result<String, ProxyError> {
+    let client = Client::builder().with_api_key(api_key).chain(Chain::from(chain_id))?.build()?;
+    let abi = client.contract_abi(address.to_string().parse().expect("address is valid")).await?;
+
+    Ok(serde_json::to_string_pretty(&abi)?)
+}
+
+/// The event signatures in an ABI, used to compare what is indexed against an upgrade.
+fn event_signatures(abi: &str) -> Result<BTreeSet<String>, serde_json::Error> {
+    let abi: JsonAbi = serde_json::from_str(abi)?;
+    Ok(abi.events().map(|event| event.signature()).collect())
+}
+
+/// A proxy address being indexed and the implementations its ABI was merged with.
+#[derive(Debug, Clone)]
+pub struct ProxyWatch {
+    pub contract_name: String,
+    pub network: String,
+    pub chain_id: u64,
+    pub address: Address,
+    pub implementations: Vec<Address>,
+}
+
+fn etherscan_api_key(manifest: &Manifest) -> Option<String> {
+    manifest
+        .global
+        .etherscan_api_key
+        .as_ref()
+        .map(|key| public_read_env_value(key).unwrap_or_else(|_| key.to_string()))
+}
+
+/// Make sure the implementation ABI is stored for the contract, fetching it from Etherscan if
+/// it is not there yet. Returns the ABI if it is available.
+async fn ensure_implementation_abi(
+    project_path: &Path,
+    contract_name: &str,
+    chain_id: u64,
+    implementation: Address,
+    etherscan_api_key: Option<&str>,
+) -> Option<String> {
+    let abi_path = implementation_abi_path(project_path, contract_name, implementation);
+    if let Ok(abi) = std::fs::read_to_string(&abi_path) {
+        return Some(abi);
+    }
+
+    let Some(api_key) = etherscan_api_key else {
+        warn!(
+            "{} - no `global.etherscan_api_key` set to download the ABI of implementation {}, add it to {} to index its events",
+            contract_name,
+            implementation,
+            abi_path.display()
+        );
+        return None;
+    };
+
+    match fetch_etherscan_abi(chain_id, implementation, api_key).await {
+        Ok(abi) => {
+            if let Err(e) = write_file(&abi_path, &abi) {
+                warn!("{} - could not write {}: {}", contract_name, abi_path.display(), e);
+            }
+            Some(abi)
+        }
+        Err(e) => {
+            warn!(
+                "{} - could not download the ABI of implementation {}, add it to {} to index its events: {}",
+                contract_name,
+                implementation,
+                abi_path.display(),
+                e
+            );
+            None
+        }
+    }
+}
+
+fn proxy_addresses(contract: &Contract) -> impl Iterator<Item = (&str, Address)> {
+    contract.details.iter().flat_map(|detail| {
+        let addresses: Vec<Address> = match detail.address() {
+            Some(alloy::rpc::types::ValueOrArray::Value(address)) => vec![*address],
+            Some(alloy::rpc::types::ValueOrArray::Array(addresses)) => addresses.clone(),
+            None => vec![],
+        };
+        addresses.into_iter().map(move |address| (detail.network.as_str(), address))
+    })
+}
+
+/// Resolve the implementations of every `proxy: auto` contract and store their ABIs so they are
+/// merged when the contract ABI is parsed. This must run before anything reads the ABIs.
+pub async fn sync_proxy_abis(
+    project_path: &Path,
+    manifest: &Manifest,
+    providers: &[CreateNetworkProvider],
+) -> Vec<ProxyWatch> {
+    let etherscan_api_key = etherscan_api_key(manifest);
+    let mut watches = vec![];
+
+    for contract in
+        manifest.contracts.iter().filter(|contract| contract.proxy == Some(ProxySetting::Auto))
+    {
+        for (network, address) in proxy_addresses(contract) {
+            let Some(provider) = get_network_provider(network, providers) else {
+                continue;
+            };
+            let chain_id = provider.client.chain().id();
+
+            let proxy = match detect_proxy(provider.client.as_ref(), address).await {
+                Ok(Some(proxy)) => proxy,
+                Ok(None) => {
+                    warn!(
+                        "{} - `proxy: auto` is set but {} on {} is not a recognised proxy",
+                        contract.name, address, network
+                    );
+                    continue;
+                }
+                Err(e) => {
+                    warn!(
+                        "{} - could not resolve proxy implementation for {} on {}: {}",
+                        contract.name, address, network, e
+                    );
+                    continue;
+                }
+            };
+
+            info!(
+                "{} - {} proxy {} on {} resolved to implementation(s): {}",
+                contract.name,
+                proxy.kind,
+                address,
+                network,
+                proxy.implementations.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(", ")
+            );
+
+            for implementation in &proxy.implementations {
+                ensure_implementation_abi(
+                    project_path,
+                    &contract.name,
+                    chain_id,
+                    *implementation,
+                    etherscan_api_key.as_deref(),
+                )
+                .await;
+            }
+
+            watches.push(ProxyWatch {
+                contract_name: contract.name.clone(),
+                network: network.to_string(),
+                chain_id,
+                address,
+                implementations: proxy.implementations,
+            });
+        }
+    }
+
+    watches
+}
+
+/// Resolve the `proxy: auto` contracts with providers created from the manifest, for codegen
+/// which unlike the no-code startup has no providers of its own.
+pub async fn sync_manifest_proxy_abis(
+    project_path: &Path,
+    manifest: &Manifest,
+) -> Result<Vec<ProxyWatch>, RetryClientError> {
+    if !manifest.contracts.iter().any(|contract| contract.proxy == Some(ProxySetting::Auto)) {
+        return Ok(vec![]);
+    }
+
+    let providers = CreateNetworkProvider::create(manifest).await?;
+    Ok(sync_proxy_abis(project_path, manifest, &providers).await)
+}
+
+/// The events in the implementation ABIs which are not part of the events being indexed.
+fn events_missing_from(
+    indexed_events: &BTreeSet<String>,
+    implementation_abis: &[String],
+) -> BTreeSet<String> {
+    implementation_abis
+        .iter()
+        .filter_map(|abi| event_signatures(abi).ok())
+        .flatten()
+        .filter(|event| !indexed_events.contains(event))
+        .collect()
+}
+
+/// Re-check the proxy implementations on an interval and warn when an upgrade changes the
+/// merged event set. Upgrades are not applied live, the new events are indexed after a restart.
+pub fn spawn_proxy_implementation_watcher(
+    project_path: &Path,
+    manifest: &Manifest,
+    watches: Vec<ProxyWatch>,
+    providers: Arc<HashMap<String, Arc<dyn ChainProvider>>>,
+) {
+    if watches.is_empty() {
+        return;
+    }
+
+    let indexed_events: HashMap<String, BTreeSet<String>> = manifest
+        .contracts
+        .iter()
+        .filter(|contract| watches.iter().any(|w| w.contract_name == contract.name))
+        .filter_map(|contract| {
+            let abi = contract.parse_abi(project_path).ok()?;
+            Some((contract.name.clone(), event_signatures(&abi).ok()?))
+        })
+        .collect();
+
+    let project_path = project_path.to_path_buf();
+    let etherscan_api_key = etherscan_api_key(manifest);
+
+    tokio::spawn(async move {
+        let mut watches = watches;
+        let mut interval = tokio::time::interval(IMPLEMENTATION_CHECK_INTERVAL);
+        // the first tick completes immediately and we have just resolved the implementations
+        interval.tick().await;
+
+        while is_running() {
+            interval.tick().await;
+
+            for watch in &mut watches {
+                let Some(provider) = providers.get(&watch.network) else {
+                    continue;
+                };
+
+                let proxy = match detect_proxy(provider.as_ref(), watch.address).await {
+                    Ok(Some(proxy)) => proxy,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        debug!(
+                            "{} - could not re-check proxy {} on {}: {}",
+                            watch.contract_name, watch.address, watch.network, e
+                        );
+                        continue;
+                    }
+                };
+
+                let upgraded = proxy
+                    .implementations
+                    .iter()
+                    .filter(|implementation| !watch.implementations.contains(implementation))
+                    .copied()
+                    .collect::<Vec<_>>();
+                if upgraded.is_empty() {
+                    continue;
+                }
+
+                let mut abis = vec![];
+                let mut missing_abi = false;
+                for implementation in &upgraded {
+                    match ensure_implementation_abi(
+                        &project_path,
+                        &watch.contract_name,
+                        watch.chain_id,
+                        *implementation,
+                        etherscan_api_key.as_deref(),
+                    )
+                    .await
+                    {
+                        Some(abi) => abis.push(abi),
+                        None => missing_abi = true,
+                    }
+                }
+
+                let indexed = indexed_events.get(&watch.contract_name).cloned().unwrap_or_default();
+                let new_events = events_missing_from(&indexed, &abis);
+                let upgraded =
+                    upgraded.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(", ");
+
+                if !new_events.is_empty() {
+                    warn!(
+                        "{} - proxy {} on {} was upgraded to {} which emits events not being indexed: {}. Restart rindexer to index them",
+                        watch.contract_name,
+                        watch.address,
+                        watch.network,
+                        upgraded,
+                        new_events.into_iter().collect::<Vec<_>>().join(", ")
+                    );
+                } else if missing_abi {
+                    warn!(
+                        "{} - proxy {} on {} was upgraded to {} but its ABI is unknown so new events may be missed",
+                        watch.contract_name, watch.address, watch.network, upgraded
+                    );
+                } else {
+                    info!(
+                        "{} - proxy {} on {} was upgraded to {}, the indexed events are unchanged",
+                        watch.contract_name, watch.address, watch.network, upgraded
+                    );
+                }
+
+                watch.implementations = proxy.implementations;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::mock::MockChainProvider;
+
+    const PROXY: Address = Address::repeat_byte(0x11);
+    const IMPLEMENTATION: Address = Address::repeat_byte(0x22);
+    const BEACON: Address = Address::repeat_byte(0x33);
+    const FACET: Address = Address::repeat_byte(0x44);
+
+    fn selector_data(selector: [u8; 4]) -> Bytes {
+        Bytes::from(selector.to_vec())
+    }
+
+    fn encode_hex(value: DynSolValue) -> String {
+        format!("0x{}", hex::encode(value.abi_encode()))
+    }
+
+    #[tokio::test]
+    async fn test_detect_eip1967_proxy() {
+        let provider = MockChainProvider::new(1).with_storage(
+            PROXY,
+            EIP1967_IMPLEMENTATION_SLOT,
+            IMPLEMENTATION.into_word(),
+        );
+
+        let proxy = detect_proxy(&provider, PROXY).await.unwrap().unwrap();
+
+        assert_eq!(proxy.kind, ProxyKind::Eip1967);
+        assert_eq!(proxy.implementations, vec![IMPLEMENTATION]);
+    }
+
+    #[tokio::test]
+    async fn test_detect_beacon_proxy() {
+        let provider = MockChainProvider::new(1)
+            .with_storage(PROXY, EIP1967_BEACON_SLOT, BEACON.into_word())
+            .with_call_result(
+                BEACON,
+                selector_data(BEACON_IMPLEMENTATION_SELECTOR),
+                &encode_hex(DynSolValue::Address(IMPLEMENTATION)),
+            );
+
+        let proxy = detect_proxy(&provider, PROXY).await.unwrap().unwrap();
+
+        assert_eq!(proxy.kind, ProxyKind::Beacon);
+        assert_eq!(proxy.implementations, vec![IMPLEMENTATION]);
+    }
+
+    #[tokio::test]
+    async fn test_detect_diamond() {
+        let provider = MockChainProvider::new(1).with_call_result(
+            PROXY,
+            selector_data(DIAMOND_FACET_ADDRESSES_SELECTOR),
+            &encode_hex(DynSolValue::Array(vec![
+                DynSolValue::Address(IMPLEMENTATION),
+                DynSolValue::Address(FACET),
+                // diamonds register their own loupe functions against themselves
+                DynSolValue::Address(PROXY),
+                DynSolValue::Address(IMPLEMENTATION),
+            ])),
+        );
+
+        let proxy = detect_proxy(&provider, PROXY).await.unwrap().unwrap();
+
+        assert_eq!(proxy.kind, ProxyKind::Diamond);
+        assert_eq!(proxy.implementations, vec![IMPLEMENTATION, FACET]);
+    }
+
+    #[tokio::test]
+    async fn test_detect_not_a_proxy() {
+        let provider = MockChainProvider::new(1);
+
+        assert_eq!(detect_proxy(&provider, PROXY).await.unwrap(), None);
+    }
+
+    #[test]
+    fn test_events_missing_from_indexed_set() {
+        let indexed = event_signatures(
+            r#"[{"type":"event","name":"Transfer","anonymous":false,"inputs":[{"name":"from","type":"address","indexed":true},{"name":"to","type":"address","indexed":true},{"name":"value","type":"uint256","indexed":false}]}]"#,
+        )
+        .unwrap();
+
+        let upgraded = r#"[
+            {"type":"event","name":"Transfer","anonymous":false,"inputs":[{"name":"from","type":"address","indexed":true},{"name":"to","type":"address","indexed":true},{"name":"value","type":"uint256","indexed":false}]},
+            {"type":"event","name":"Paused","anonymous":false,"inputs":[{"name":"account","type":"address","indexed":false}]}
+        ]"#;
+
+        let missing = events_missing_from(&indexed, &[upgraded.to_string()]);
+
+        assert_eq!(missing.into_iter().collect::<Vec<_>>(), vec!["Paused(address)".to_string()]);
+    }
+
+    #[test]
+    fn test_implementation_abi_paths_are_merged_by_contract() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(implementation_abi_paths(dir.path(), "Token").unwrap().is_empty());
+
+        let path = implementation_abi_path(dir.path(), "Token", IMPLEMENTATION);
+        write_file(&path, "[]").unwrap();
+
+        assert_eq!(implementation_abi_paths(dir.path(), "Token").unwrap(), vec![path]);
+        assert!(implementation_abi_paths(dir.path(), "Other").unwrap().is_empty());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::notifications::ChainStateNotification;
+use crate::notifications::{ChainStateNotification, ChainStateSource};
 use futures::StreamExt;
 use reth_exex::{ExExContext, ExExNotification};
 use reth_node_api::FullNodeComponents;
@@ -63,6 +63,7 @@ impl<Node: FullNodeComponents> RindexerExEx<Node> {
                         new_from_block: *new_range.start(),
                         new_to_block: *new_range.end(),
                         new_tip_hash: new.tip().hash(),
+                        source: ChainStateSource::Exex,
                     };
 
                     let _ = self.notification_tx.send(chain_notification);
@@ -78,6 +79,7 @@ impl<Node: FullNodeComponents> RindexerExEx<Node> {
                     let chain_notification = ChainStateNotification::Reverted {
                         from_block: *range.start(),
                         to_block: *range.end(),
+                        source: ChainStateSource::Exex,
                     };
 
                     let _ = self.notification_tx.send(chain_notification);
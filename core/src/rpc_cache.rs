@@ -0,0 +1,407 @@
+//! Persistent on-disk cache of finalized RPC responses.
+//!
+//! Re-indexing the same blocks (`drop_each_run: true`, rewinds) downloads the same logs, blocks,
+//! `eth_call` results and traces again. With `config.rpc_cache` set, `JsonRpcCachedProvider`
+//! stores these responses on disk, addressed by the hash of the request, once the blocks they
+//! cover are past the reorg-safe distance of the chain. Finalized responses never change, so an
+//! entry is never invalidated, the cache is only kept under its size limit by dropping the oldest
+//! entries.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use alloy::primitives::keccak256;
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tracing::{debug, info, warn};
+
+use crate::manifest::config::RpcCacheConfig;
+use crate::manifest::core::Manifest;
+
+pub const DEFAULT_RPC_CACHE_PATH: &str = ".rindexer/cache";
+
+pub const DEFAULT_RPC_CACHE_MAX_SIZE_MB: u64 = 1024;
+
+/// Once over its limit the cache is pruned down to this share of it, so a full cache is not
+/// pruned again on every write.
+const PRUNE_TARGET_RATIO: f64 = 0.9;
+
+const ENTRY_EXTENSION: &str = "json.zst";
+
+/// Entries are written to `{hash}.json.tmp{n}` and renamed into place, a crash in between
+/// leaves the temporary file behind.
+const TMP_EXTENSION: &str = ".json.tmp";
+
+/// Temporary files younger than this can still be renamed by a running write, older ones are
+/// leftovers and always pruned.
+const TMP_GRACE_PERIOD: Duration = Duration::from_secs(60);
+
+static RPC_DISK_CACHE: Lazy<RwLock<Option<Arc<RpcDiskCache>>>> = Lazy::new(|| RwLock::new(None));
+
+/// Enable the disk cache when the manifest configures `rpc_cache`, disable it otherwise.
+pub fn configure_rpc_cache(manifest: &Manifest, project_path: &Path) {
+    let mut current = RPC_DISK_CACHE.write().unwrap_or_else(|e| e.into_inner());
+
+    let Some(config) = &manifest.config.rpc_cache else {
+        *current = None;
+        return;
+    };
+
+    let root = config.root(project_path);
+    if current.as_ref().is_some_and(|cache| cache.root == root) {
+        return;
+    }
+
+    let cache = RpcDiskCache::open(root, config.max_size_bytes());
+    info!(
+        "RPC cache enabled at {} ({} MB of {} MB used)",
+        cache.root.display(),
+        cache.size_bytes() / 1024 / 1024,
+        cache.max_size_bytes / 1024 / 1024
+    );
+
+    *current = Some(Arc::new(cache));
+}
+
+/// The disk cache, if enabled.
+pub fn rpc_disk_cache() -> Option<Arc<RpcDiskCache>> {
+    RPC_DISK_CACHE.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+impl RpcCacheConfig {
+    pub fn root(&self, project_path: &Path) -> PathBuf {
+        project_path.join(self.path.as_deref().unwrap_or(DEFAULT_RPC_CACHE_PATH))
+    }
+
+    pub fn max_size_bytes(&self) -> u64 {
+        self.max_size_mb.unwrap_or(DEFAULT_RPC_CACHE_MAX_SIZE_MB).saturating_mul(1024 * 1024)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PruneResult {
+    pub removed_entries: u64,
+    pub removed_bytes: u64,
+    pub remaining_bytes: u64,
+}
+
+pub struct RpcDiskCache {
+    root: PathBuf,
+    max_size_bytes: u64,
+    size_bytes: Arc<AtomicU64>,
+    pruning: Arc<AtomicBool>,
+}
+
+impl RpcDiskCache {
+    pub fn open(root: impl Into<PathBuf>, max_size_bytes: u64) -> Self {
+        let root = root.into();
+        let size_bytes = cache_entries(&root).iter().map(|entry| entry.size).sum();
+
+        Self {
+            root,
+            max_size_bytes,
+            size_bytes: Arc::new(AtomicU64::new(size_bytes)),
+            pruning: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn size_bytes(&self) -> u64 {
+        self.size_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Entries are laid out as `{chain_id}/{method}/{hash prefix}/{hash}.json.zst`, the hash
+    /// covering the method and its parameters.
+    fn entry_path(&self, chain_id: u64, method: &str, params: &impl Serialize) -> Option<PathBuf> {
+        let request = serde_json::to_vec(&(method, params)).ok()?;
+        let hash = hex::encode(keccak256(request));
+
+        Some(
+            self.root
+                .join(chain_id.to_string())
+                .join(method)
+                .join(&hash[..2])
+                .join(format!("{hash}.{ENTRY_EXTENSION}")),
+        )
+    }
+
+    /// The cached response of a request, `None` if it was never stored.
+    pub async fn get<T: DeserializeOwned>(
+        &self,
+        chain_id: u64,
+        method: &str,
+        params: &impl Serialize,
+    ) -> Option<T> {
+        let path = self.entry_path(chain_id, method, params)?;
+        let compressed = tokio::fs::read(&path).await.ok()?;
+
+        let value = zstd::decode_all(compressed.as_slice())
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok());
+
+        if value.is_none() {
+            warn!("RPC cache: dropping unreadable entry {}", path.display());
+            let _ = tokio::fs::remove_file(&path).await;
+        }
+
+        value
+    }
+
+    /// Store the response of a request, the caller makes sure it is final.
+    pub async fn put<T: Serialize>(
+        &self,
+        chain_id: u64,
+        method: &str,
+        params: &impl Serialize,
+        value: &T,
+    ) {
+        let Some(path) = self.entry_path(chain_id, method, params) else {
+            return;
+        };
+
+        if let Err(e) = self.write_entry(&path, value).await {
+            debug!("RPC cache: could not write {}: {}", path.display(), e);
+            return;
+        }
+
+        if self.size_bytes() > self.max_size_bytes {
+            self.prune_in_background();
+        }
+    }
+
+    async fn write_entry<T: Serialize>(&self, path: &Path, value: &T) -> io::Result<()> {
+        let bytes = serde_json::to_vec(value)?;
+        let compressed = zstd::encode_all(bytes.as_slice(), 3)?;
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        // Write and rename so a crash never leaves a half written entry behind
+        let tmp_path = path.with_extension(format!("tmp{}", rand::random::<u32>()));
+        tokio::fs::write(&tmp_path, &compressed).await?;
+        if let Err(e) = tokio::fs::rename(&tmp_path, path).await {
+            let _ = tokio::fs::remove_file(&tmp_path).await;
+            return Err(e);
+        }
+
+        self.size_bytes.fetch_add(compressed.len() as u64, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    fn prune_in_background(&self) {
+        if self.pruning.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let root = self.root.clone();
+        let target = (self.max_size_bytes as f64 * PRUNE_TARGET_RATIO) as u64;
+        let size_bytes = Arc::clone(&self.size_bytes);
+        let pruning = Arc::clone(&self.pruning);
+
+        tokio::spawn(async move {
+            match tokio::task::spawn_blocking(move || prune_entries(&root, target)).await {
+                Ok(Ok(result)) => {
+                    size_bytes.store(result.remaining_bytes, Ordering::Relaxed);
+                    info!(
+                        "RPC cache: pruned {} entries ({} MB), {} MB left",
+                        result.removed_entries,
+                        result.removed_bytes / 1024 / 1024,
+                        result.remaining_bytes / 1024 / 1024
+                    );
+                }
+                Ok(Err(e)) => warn!("RPC cache: prune failed: {}", e),
+                Err(e) => warn!("RPC cache: prune task failed: {}", e),
+            }
+            pruning.store(false, Ordering::Release);
+        });
+    }
+
+    /// Drop leftover temporary files and then the oldest entries until the cache is at most
+    /// `max_size_bytes`.
+    pub fn prune(&self, max_size_bytes: u64) -> io::Result<PruneResult> {
+        let result = prune_entries(&self.root, max_size_bytes)?;
+        self.size_bytes.store(result.remaining_bytes, Ordering::Relaxed);
+
+        Ok(result)
+    }
+}
+
+struct CacheEntry {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+    /// A temporary file of a write, not a readable entry.
+    tmp: bool,
+}
+
+fn cache_entries(root: &Path) -> Vec<CacheEntry> {
+    let mut entries = Vec::new();
+    let mut directories = vec![root.to_path_buf()];
+
+    while let Some(directory) = directories.pop() {
+        let Ok(read_dir) = std::fs::read_dir(&directory) else {
+            continue;
+        };
+
+        for entry in read_dir.flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+
+            let path = entry.path();
+            if metadata.is_dir() {
+                directories.push(path);
+            } else {
+                let name = path.to_string_lossy();
+                let (tmp, is_entry) =
+                    (name.contains(TMP_EXTENSION), name.ends_with(ENTRY_EXTENSION));
+                drop(name);
+                if tmp || is_entry {
+                    entries.push(CacheEntry {
+                        path,
+                        size: metadata.len(),
+                        modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                        tmp,
+                    });
+                }
+            }
+        }
+    }
+
+    entries
+}
+
+fn prune_entries(root: &Path, max_size_bytes: u64) -> io::Result<PruneResult> {
+    let mut entries = cache_entries(root);
+    let now = SystemTime::now();
+    let is_leftover = |entry: &CacheEntry| {
+        entry.tmp && now.duration_since(entry.modified).unwrap_or_default() > TMP_GRACE_PERIOD
+    };
+    // leftovers are dropped whatever the size of the cache, then the oldest entries
+    entries.sort_by_key(|entry| (!is_leftover(entry), entry.modified));
+
+    let mut result = PruneResult {
+        remaining_bytes: entries.iter().map(|entry| entry.size).sum(),
+        ..Default::default()
+    };
+
+    for entry in entries {
+        if result.remaining_bytes <= max_size_bytes && !is_leftover(&entry) {
+            break;
+        }
+        if entry.tmp && !is_leftover(&entry) {
+            continue;
+        }
+
+        match std::fs::remove_file(&entry.path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        result.removed_entries += 1;
+        result.removed_bytes += entry.size;
+        result.remaining_bytes -= entry.size;
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn put_then_get_round_trips_per_request() {
+        let dir = tempfile::tempdir().expect("could not create temp dir");
+        let cache = RpcDiskCache::open(dir.path(), u64::MAX);
+
+        cache.put(1, "eth_call", &("0xabc", 100u64), &"0x01".to_string()).await;
+
+        assert_eq!(
+            cache.get::<String>(1, "eth_call", &("0xabc", 100u64)).await.as_deref(),
+            Some("0x01")
+        );
+        assert_eq!(cache.get::<String>(1, "eth_call", &("0xabc", 101u64)).await, None);
+        assert_eq!(cache.get::<String>(137, "eth_call", &("0xabc", 100u64)).await, None);
+        assert!(cache.size_bytes() > 0);
+    }
+
+    #[tokio::test]
+    async fn reopening_counts_existing_entries() {
+        let dir = tempfile::tempdir().expect("could not create temp dir");
+        let cache = RpcDiskCache::open(dir.path(), u64::MAX);
+        cache.put(1, "trace_block", &100u64, &vec![1u64, 2, 3]).await;
+
+        let reopened = RpcDiskCache::open(dir.path(), u64::MAX);
+        assert_eq!(reopened.size_bytes(), cache.size_bytes());
+        assert_eq!(reopened.get::<Vec<u64>>(1, "trace_block", &100u64).await, Some(vec![1, 2, 3]));
+    }
+
+    #[tokio::test]
+    async fn unreadable_entries_are_dropped() {
+        let dir = tempfile::tempdir().expect("could not create temp dir");
+        let cache = RpcDiskCache::open(dir.path(), u64::MAX);
+        cache.put(1, "trace_block", &100u64, &"not a list").await;
+
+        assert_eq!(cache.get::<Vec<u64>>(1, "trace_block", &100u64).await, None);
+        assert!(cache_entries(dir.path()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn prune_drops_the_oldest_entries_first() {
+        let dir = tempfile::tempdir().expect("could not create temp dir");
+        let cache = RpcDiskCache::open(dir.path(), u64::MAX);
+
+        for block in 0..4u64 {
+            cache.put(1, "eth_getBlockByNumber", &block, &vec![block; 64]).await;
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        let entry_size = cache.size_bytes() / 4;
+        let result = cache.prune(entry_size * 2).expect("prune should succeed");
+
+        assert_eq!(result.removed_entries, 2);
+        assert_eq!(cache.size_bytes(), result.remaining_bytes);
+        assert_eq!(cache.get::<Vec<u64>>(1, "eth_getBlockByNumber", &0u64).await, None);
+        assert_eq!(cache.get::<Vec<u64>>(1, "eth_getBlockByNumber", &1u64).await, None);
+        assert!(cache.get::<Vec<u64>>(1, "eth_getBlockByNumber", &3u64).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn leftover_tmp_files_count_and_are_pruned() {
+        let dir = tempfile::tempdir().expect("could not create temp dir");
+        let cache = RpcDiskCache::open(dir.path(), u64::MAX);
+        cache.put(1, "trace_block", &100u64, &vec![1u64, 2, 3]).await;
+
+        let leftover = dir.path().join("1/trace_block/ab/abcd.json.tmp42");
+        let in_flight = dir.path().join("1/trace_block/ab/abce.json.tmp43");
+        std::fs::create_dir_all(leftover.parent().unwrap()).unwrap();
+        std::fs::write(&leftover, [0u8; 100]).unwrap();
+        std::fs::write(&in_flight, [0u8; 100]).unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&leftover)
+            .unwrap()
+            .set_modified(SystemTime::now() - TMP_GRACE_PERIOD * 2)
+            .unwrap();
+
+        let reopened = RpcDiskCache::open(dir.path(), u64::MAX);
+        assert_eq!(reopened.size_bytes(), cache.size_bytes() + 200);
+
+        let result = reopened.prune(u64::MAX).expect("prune should succeed");
+        assert_eq!(result.removed_entries, 1);
+        assert!(!leftover.exists());
+        assert!(in_flight.exists(), "a write in progress keeps its file");
+        assert!(reopened.get::<Vec<u64>>(1, "trace_block", &100u64).await.is_some());
+    }
+}
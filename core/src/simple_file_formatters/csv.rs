@@ -1,12 +1,44 @@
 use std::{
+    collections::HashMap,
     fs::File,
     path::{Path, PathBuf},
     sync::Arc,
 };
 
 use csv::Reader;
+use csv::ReaderBuilder;
 use csv::Writer;
+use once_cell::sync::Lazy;
 use tokio::sync::Mutex;
+use tracing::warn;
+
+/// Writer locks by file, shared by every appender of the process so appends from the event
+/// handlers never land while a reorg rollback rewrites the same file.
+static WRITER_LOCKS: Lazy<std::sync::Mutex<HashMap<PathBuf, Arc<Mutex<()>>>>> =
+    Lazy::new(|| std::sync::Mutex::new(HashMap::new()));
+
+fn writer_lock(path: &Path) -> Arc<Mutex<()>> {
+    let key = writer_lock_key(path);
+    let mut locks = WRITER_LOCKS.lock().expect("CSV writer locks poisoned");
+    Arc::clone(locks.entry(key).or_default())
+}
+
+/// The canonical path of the file, so relative, `..` and symlinked paths to it share a lock.
+/// Files not created yet are keyed on their canonical folder, or on their absolute path when
+/// the folder does not exist either.
+fn writer_lock_key(path: &Path) -> PathBuf {
+    if let Ok(path) = path.canonicalize() {
+        return path;
+    }
+
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    if let (Ok(parent), Some(file_name)) = (parent.canonicalize(), path.file_name()) {
+        return parent.join(file_name);
+    }
+
+    // `components` drops `.` segments, so `a/./b.csv` and `a/b.csv` still share a lock
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()).components().collect()
+}
 
 pub struct AsyncCsvAppender {
     path: Arc<Path>,
@@ -15,10 +47,8 @@ pub struct AsyncCsvAppender {
 
 impl AsyncCsvAppender {
     pub fn new(file_path: &str) -> Self {
-        AsyncCsvAppender {
-            path: Arc::from(PathBuf::from(file_path)),
-            writer_lock: Arc::new(Mutex::new(())),
-        }
+        let path = PathBuf::from(file_path);
+        AsyncCsvAppender { writer_lock: writer_lock(&path), path: Arc::from(path) }
     }
 
     pub async fn append(&self, data: Vec<String>) -> Result<(), csv::Error> {
@@ -26,7 +56,7 @@ impl AsyncCsvAppender {
         let path = Arc::clone(&self.path);
 
         tokio::task::spawn_blocking(move || {
-            let _guard = lock.lock();
+            let _guard = lock.blocking_lock();
             let file = File::options().create(true).append(true).open(path)?;
             let mut writer = Writer::from_writer(file);
 
@@ -43,7 +73,7 @@ impl AsyncCsvAppender {
         let path = Arc::clone(&self.path);
 
         tokio::task::spawn_blocking(move || {
-            let _guard = lock.lock();
+            let _guard = lock.blocking_lock();
             let file = File::options().create(true).append(true).open(&path)?;
             let mut writer = Writer::from_writer(file);
 
@@ -62,7 +92,7 @@ impl AsyncCsvAppender {
         let path = Arc::clone(&self.path);
 
         tokio::task::spawn_blocking(move || {
-            let _guard = lock.lock();
+            let _guard = lock.blocking_lock();
             // Create parent directories if they don't exist
             if let Some(parent) = path.parent() {
                 std::fs::create_dir_all(parent).expect("Failed to create directory");
@@ -77,6 +107,77 @@ impl AsyncCsvAppender {
         .await
         .expect("Failed to run CSV write operation")
     }
+
+    /// Removes the rows of `network` with a block number in `from_block..=to_block`, used to
+    /// roll back a reorg. The columns are found through the header row, files without one are
+    /// left untouched. The file is rewritten next to the original and renamed over it.
+    ///
+    /// Returns the tx hash of every removed row.
+    pub async fn remove_block_range(
+        &self,
+        network: &str,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<String>, csv::Error> {
+        let lock = Arc::clone(&self.writer_lock);
+        let path = Arc::clone(&self.path);
+        let network = network.to_string();
+
+        tokio::task::spawn_blocking(move || {
+            let _guard = lock.blocking_lock();
+            if !path.exists() {
+                return Ok(vec![]);
+            }
+
+            let mut reader =
+                ReaderBuilder::new().has_headers(false).flexible(true).from_path(&path)?;
+            let mut records = reader.records();
+            let Some(header) = records.next().transpose()? else {
+                return Ok(vec![]);
+            };
+
+            let column = |name: &str| header.iter().position(|h| h == name);
+            let (Some(tx_hash), Some(block_number), Some(network_column)) =
+                (column("tx_hash"), column("block_number"), column("network"))
+            else {
+                warn!("{} has no header row, skipping reorg rollback", path.display());
+                return Ok(vec![]);
+            };
+
+            let temp_path = path.with_extension("csv.tmp");
+            let mut writer = Writer::from_path(&temp_path)?;
+            writer.write_record(&header)?;
+
+            let mut removed_tx_hashes = Vec::new();
+            for record in records {
+                let record = record?;
+                let reorged = record.get(network_column) == Some(network.as_str())
+                    && record
+                        .get(block_number)
+                        .and_then(|n| n.parse::<u64>().ok())
+                        .is_some_and(|n| n >= from_block && n <= to_block);
+
+                if reorged {
+                    removed_tx_hashes.push(record.get(tx_hash).unwrap_or_default().to_string());
+                } else {
+                    writer.write_record(&record)?;
+                }
+            }
+
+            writer.flush()?;
+            drop(writer);
+
+            if removed_tx_hashes.is_empty() {
+                std::fs::remove_file(&temp_path)?;
+            } else {
+                std::fs::rename(&temp_path, path.as_ref())?;
+            }
+
+            Ok(removed_tx_hashes)
+        })
+        .await
+        .expect("Failed to run CSV reorg rollback operation")
+    }
 }
 
 pub struct AsyncCsvReader {
@@ -107,3 +208,82 @@ impl AsyncCsvReader {
         .expect("Failed to run CSV read operation")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(tx_hash: &str, block_number: u64, network: &str) -> Vec<String> {
+        vec![
+            "0xcontract".to_string(),
+            "100".to_string(),
+            tx_hash.to_string(),
+            block_number.to_string(),
+            "0xblockhash".to_string(),
+            network.to_string(),
+        ]
+    }
+
+    #[tokio::test]
+    async fn remove_block_range_drops_reorged_rows_of_the_network() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transfer.csv");
+        let csv = AsyncCsvAppender::new(path.to_str().unwrap());
+        csv.append_header(
+            ["contract_address", "value", "tx_hash", "block_number", "block_hash", "network"]
+                .map(String::from)
+                .to_vec(),
+        )
+        .await
+        .unwrap();
+        csv.append_bulk(vec![
+            row("0x1", 10, "ethereum"),
+            row("0x2", 11, "ethereum"),
+            row("0x3", 12, "ethereum"),
+            row("0x4", 11, "base"),
+        ])
+        .await
+        .unwrap();
+
+        let removed = csv.remove_block_range("ethereum", 11, 12).await.unwrap();
+        assert_eq!(removed, vec!["0x2", "0x3"]);
+
+        let rows = AsyncCsvReader::new(path.to_str().unwrap()).read_all().await.unwrap();
+        let tx_hashes: Vec<&str> = rows.iter().map(|r| r[2].as_str()).collect();
+        assert_eq!(tx_hashes, vec!["0x1", "0x4"]);
+    }
+
+    #[test]
+    fn appenders_of_the_same_file_share_the_writer_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transfer.csv");
+        let dotted = dir.path().join(".").join("transfer.csv");
+        std::fs::create_dir(dir.path().join("events")).unwrap();
+        let parent_dir = dir.path().join("events").join("..").join("transfer.csv");
+
+        let handler = AsyncCsvAppender::new(path.to_str().unwrap());
+        let rollback = AsyncCsvAppender::new(dotted.to_str().unwrap());
+        let other = AsyncCsvAppender::new(dir.path().join("swap.csv").to_str().unwrap());
+
+        assert!(Arc::ptr_eq(&handler.writer_lock, &rollback.writer_lock));
+        assert!(!Arc::ptr_eq(&handler.writer_lock, &other.writer_lock));
+
+        // Once the file exists its canonical path is used, it still has to match
+        std::fs::write(&path, "").unwrap();
+        let created = AsyncCsvAppender::new(parent_dir.to_str().unwrap());
+        assert!(Arc::ptr_eq(&handler.writer_lock, &created.writer_lock));
+    }
+
+    #[tokio::test]
+    async fn remove_block_range_skips_files_without_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transfer.csv");
+        let csv = AsyncCsvAppender::new(path.to_str().unwrap());
+        csv.append_bulk(vec![row("0x1", 10, "ethereum")]).await.unwrap();
+
+        let removed = csv.remove_block_range("ethereum", 0, 100).await.unwrap();
+        assert!(removed.is_empty());
+
+        assert!(std::fs::read_to_string(&path).unwrap().contains("0x1"));
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -5,11 +6,15 @@ use tokio::signal;
 use tokio_util::sync::CancellationToken;
 use tracing::{error, info};
 
+use crate::blockclock::flush_block_clocks;
 use crate::database::clickhouse::setup::SetupClickhouseError;
 use crate::events::RindexerEventEmitter;
 use crate::hot_reload::orchestrator::ReloadOrchestrator;
 use crate::hot_reload::watcher::ManifestWatcher;
-use crate::indexer::start::{start_historical_indexing, start_live_indexing};
+use crate::indexer::start::{
+    initialize_clickhouse, initialize_database, start_historical_indexing, start_live_indexing,
+};
+use crate::indexer::start_block::{resolve_auto_start_blocks, ResolveStartBlockError};
 use crate::indexer::IndexingEventsProgressState;
 use crate::{
     api::{
@@ -35,6 +40,7 @@ use crate::{
         storage::RelationshipsAndIndexersError,
         yaml::{read_manifest, ReadManifestError},
     },
+    provider::ChainProvider,
     setup_clickhouse, setup_info_logger, RindexerEventStream,
 };
 
@@ -92,6 +98,9 @@ pub enum StartRindexerError {
     #[error("{0}")]
     RelationshipsAndIndexersError(#[from] RelationshipsAndIndexersError),
 
+    #[error("Could not resolve start_block: auto: {0}")]
+    ResolveStartBlockError(#[from] ResolveStartBlockError),
+
     #[error("Shutdown handler failed with error: {0}")]
     ShutdownHandlerFailed(String),
 
@@ -112,6 +121,7 @@ async fn handle_shutdown(signal: &str) {
     // Signal GraphQL server to stop its restart loop
     stop_graphql_server();
     initiate_shutdown().await;
+    flush_block_clocks();
     // These info! calls work because they're before/after the shutdown process
     info!("Graceful shutdown completed for {}", signal);
     std::process::exit(0);
@@ -275,6 +285,16 @@ pub async fn start_rindexer(details: StartDetails<'_>) -> Result<(), StartRindex
                 let event_emitter =
                     indexing_details.event_stream.map(RindexerEventEmitter::from_stream);
 
+                if manifest.has_any_auto_start_block() {
+                    resolve_auto_start_blocks(
+                        &manifest,
+                        &mut indexing_details.registry,
+                        initialize_database(&manifest).await?,
+                        initialize_clickhouse(&manifest).await?,
+                    )
+                    .await?;
+                }
+
                 let historical_registry = indexing_details.registry.complete();
                 let trace_registry = indexing_details.trace_registry.complete();
 
@@ -448,6 +468,9 @@ pub async fn start_rindexer(details: StartDetails<'_>) -> Result<(), StartRindex
 
 pub struct IndexerNoCodeDetails {
     pub enabled: bool,
+    /// Providers to index the named networks with instead of connecting to their `rpc`, so a
+    /// YAML project can be run against a `SimChain` in tests.
+    pub provider_overrides: HashMap<String, Arc<dyn ChainProvider>>,
 }
 
 pub struct StartNoCodeDetails<'a> {
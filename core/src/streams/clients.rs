@@ -1135,14 +1135,7 @@ impl StreamsClients {
             "depth": depth,
             "events_deleted": events_deleted,
             "affected_tx_hashes": affected_tx_hashes.iter().map(|h| format!("{:#x}", h)).collect::<Vec<_>>(),
-            "affected_events": affected_tables.iter().map(|t| json!({
-                "indexer": t.indexer_name,
-                "contract": t.contract_name,
-                "event": t.event_name,
-                "schema": t.schema,
-                "table": t.table_name,
-                "rows_deleted": t.rows_deleted,
-            })).collect::<Vec<_>>(),
+            "affected_events": affected_tables.iter().map(AffectedTable::to_json).collect::<Vec<_>>(),
         });
 
         let event_message = EventMessage {
@@ -1176,6 +1169,17 @@ impl StreamsClients {
         head_block: u64,
     ) -> Result<usize, StreamError> {
         let distance = self.reorg_safe_distance_for(network);
+        self.flush_finalized_up_to(network, head_block.saturating_sub(distance)).await
+    }
+
+    /// Drain finalized-delivery buffers for `network` up to and including
+    /// `finalized_block`. Used directly when the network follows the node's
+    /// `finalized` / `safe` block tag instead of a fixed distance.
+    pub async fn flush_finalized_up_to(
+        &self,
+        network: &str,
+        finalized_block: u64,
+    ) -> Result<usize, StreamError> {
         let flush_start = Instant::now();
         let mut ready_by_key: Vec<FinalizedDeliveryBuffer> = Vec::new();
         {
@@ -1184,7 +1188,7 @@ impl StreamsClients {
                 if key.network != network {
                     continue;
                 }
-                let ready = buffer.flush(head_block, distance);
+                let ready = buffer.flush(finalized_block, 0);
                 if !ready.is_empty() {
                     ready_by_key.push((key.clone(), ready));
                 }
@@ -2188,7 +2192,7 @@ mod tests {
     // These exercise the public `discard_finalized` / `flush_finalized`
     // surface that `ReorgCoordinator::handle_reorg` drives, covering the
     // exact path that silently leaked stale events before the reth-semantic
-    // fix (`on_exex_reorg` now passes the correct `fork_point = first
+    // fix (`on_notified_reorg` now passes the correct `fork_point = first
     // reverted, detection_point = last reverted` range).
 
     async fn seed_buffer(
@@ -2245,7 +2249,7 @@ mod tests {
 
     #[tokio::test]
     async fn discard_finalized_single_block_reorg_only_drops_that_block() {
-        // Minimum-depth reorg: `on_exex_reorg(n, n)` must discard only block
+        // Minimum-depth reorg: `on_notified_reorg(source, n, n)` must discard only block
         // n. This guards against an accidental re-introduction of a `+ 1`
         // drift in the inclusive-range discard.
         let clients = empty_clients();
@@ -2317,6 +2321,29 @@ mod tests {
         mock.assert_async().await;
     }
 
+    #[tokio::test]
+    async fn flush_finalized_up_to_ignores_registered_distance() {
+        // Networks following the `finalized` tag flush up to the tagged block
+        // even when it is closer to the head than the registered distance.
+        let mut server = mockito::Server::new_async().await;
+        let mock = server.mock("POST", "/hook").with_status(200).expect(2).create_async().await;
+        let config = WebhookStreamConfig {
+            endpoint: format!("{}/hook", server.url()),
+            shared_secret: "s".to_string(),
+            networks: vec!["ethereum".to_string()],
+            events: vec![stream_event("Transfer")],
+            delivery: None,
+        };
+        let clients = webhook_clients(vec![config]);
+        seed_buffer(&clients, "ethereum", "Transfer", &[100, 101, 102], 64).await;
+
+        let sent = clients.flush_finalized_up_to("ethereum", 101).await.unwrap();
+
+        assert_eq!(sent, 2);
+        assert_eq!(buffered_blocks(&clients, "ethereum").await, vec![102]);
+        mock.assert_async().await;
+    }
+
     #[tokio::test]
     async fn flush_then_reorg_clears_only_unflushed_reorged_blocks() {
         // End-to-end: blocks 95..=115 buffered, head=105 flushes only block
@@ -0,0 +1,2 @@
+mod sim_chain;
+pub use sim_chain::{SimBlock, SimChain};
@@ -0,0 +1,813 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use alloy::network::{AnyHeader, AnyRpcBlock, AnyRpcHeader, AnyTransactionReceipt};
+use alloy::primitives::{
+    keccak256, Address, Bytes, Log as PrimitiveLog, LogData, TxHash, B256, U256, U64,
+};
+use alloy::rpc::types::trace::parity::{
+    Action, CallAction, CallType, LocalizedTransactionTrace, TransactionTrace,
+};
+use alloy::rpc::types::{Block, BlockTransactions, Log};
+use alloy_chains::Chain;
+use async_trait::async_trait;
+use tokio::sync::broadcast::{self, Sender};
+
+use crate::event::{CombinedEventFilter, RindexerEventFilter};
+use crate::indexer::live_fetcher::log_matches;
+use crate::manifest::contract::BlockTag;
+use crate::notifications::{ChainStateNotification, ChainStateSource};
+use crate::provider::{ChainProvider, ProviderError};
+
+/// Timestamp of block 0 unless overridden with [`SimChain::with_genesis_timestamp`].
+const DEFAULT_GENESIS_TIMESTAMP: u64 = 1_700_000_000;
+
+/// Seconds between blocks unless overridden with [`SimChain::with_block_time`].
+const DEFAULT_BLOCK_TIME: u64 = 12;
+
+/// The contents of one simulated block. Every log and native transfer is its own
+/// transaction, in the order they were added.
+#[derive(Debug, Clone, Default)]
+pub struct SimBlock {
+    logs: Vec<SimLog>,
+    transfers: Vec<SimTransfer>,
+}
+
+#[derive(Debug, Clone)]
+struct SimLog {
+    address: Address,
+    topics: Vec<B256>,
+    data: Bytes,
+}
+
+#[derive(Debug, Clone)]
+struct SimTransfer {
+    from: Address,
+    to: Address,
+    value: U256,
+}
+
+impl SimBlock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Emit a log from `address`, `topics[0]` being the event signature.
+    pub fn with_log(mut self, address: Address, topics: Vec<B256>, data: Bytes) -> Self {
+        self.logs.push(SimLog { address, topics, data });
+        self
+    }
+
+    /// Add a native transfer, served as a call trace by `trace_block`.
+    pub fn with_native_transfer(mut self, from: Address, to: Address, value: U256) -> Self {
+        self.transfers.push(SimTransfer { from, to, value });
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MinedBlock {
+    number: u64,
+    hash: B256,
+    parent_hash: B256,
+    timestamp: u64,
+    tx_hashes: Vec<TxHash>,
+    logs: Vec<Log>,
+    traces: Vec<LocalizedTransactionTrace>,
+}
+
+#[derive(Debug)]
+struct SimState {
+    /// The canonical chain, indexed by block number.
+    blocks: Vec<MinedBlock>,
+    script: VecDeque<SimBlock>,
+    /// Bumped on every reorg so the replacement blocks get new hashes.
+    fork_id: u64,
+    /// Extra seconds added to the timestamp of the next block.
+    time_gap: u64,
+    rate_limited_requests: u64,
+    request_count: u64,
+    /// Seeded `eth_call` results keyed by the called address and the calldata.
+    call_results: HashMap<(Address, Bytes), Bytes>,
+    storage: HashMap<(Address, B256), B256>,
+    /// Seeded contract code and the block it was deployed at.
+    code: HashMap<Address, (u64, Bytes)>,
+}
+
+/// An in-process chain implementing [`ChainProvider`] for deterministic tests.
+///
+/// Blocks are mined from a script or one by one, reorgs of any depth replace the
+/// tip with new blocks, and `eth_getLogs` range limits and rate limits are answered
+/// with the errors real providers return. Block hashes, transaction hashes and
+/// timestamps only depend on the chain id, the block contents and the calls made,
+/// so every run of a test sees the same chain.
+///
+/// Receipts are not simulated and `get_tx_receipts_batch` returns nothing. Contract
+/// state is not executed either: `eth_call`, `get_storage_at` and `get_code_at` answer
+/// with the values seeded by [`SimChain::with_call_result`], [`SimChain::with_storage`]
+/// and [`SimChain::with_code`], and with empty values for anything else, `0x` for calls like a
+/// node answers a call to an address without code.
+///
+/// ```ignore
+/// let chain = Arc::new(SimChain::new(1).with_script(vec![
+///     SimBlock::new().with_log(token, vec![transfer_topic, from, to], amount),
+///     SimBlock::new(),
+/// ]));
+/// chain.run_script();
+/// chain.reorg(1, vec![SimBlock::new()]);
+/// ```
+#[derive(Debug)]
+pub struct SimChain {
+    chain: Chain,
+    block_time: u64,
+    max_block_range: Option<U64>,
+    get_logs_range_limit: Option<u64>,
+    finality_depth: Option<u64>,
+    chain_state_notification: Option<Sender<ChainStateNotification>>,
+    state: Mutex<SimState>,
+}
+
+impl SimChain {
+    /// A chain with only the genesis block.
+    pub fn new(chain_id: u64) -> Self {
+        let chain = Chain::from(chain_id);
+        let genesis = MinedBlock {
+            number: 0,
+            hash: block_hash(chain_id, 0, 0),
+            parent_hash: B256::ZERO,
+            timestamp: DEFAULT_GENESIS_TIMESTAMP,
+            tx_hashes: vec![],
+            logs: vec![],
+            traces: vec![],
+        };
+
+        Self {
+            chain,
+            block_time: DEFAULT_BLOCK_TIME,
+            max_block_range: None,
+            get_logs_range_limit: None,
+            finality_depth: None,
+            chain_state_notification: None,
+            state: Mutex::new(SimState {
+                blocks: vec![genesis],
+                script: VecDeque::new(),
+                fork_id: 0,
+                time_gap: 0,
+                rate_limited_requests: 0,
+                request_count: 0,
+                call_results: HashMap::new(),
+                storage: HashMap::new(),
+                code: HashMap::new(),
+            }),
+        }
+    }
+
+    pub fn with_genesis_timestamp(self, timestamp: u64) -> Self {
+        self.state().blocks[0].timestamp = timestamp;
+        self
+    }
+
+    pub fn with_block_time(mut self, seconds: u64) -> Self {
+        self.block_time = seconds;
+        self
+    }
+
+    /// Blocks mined one at a time by [`SimChain::advance`].
+    pub fn with_script(self, blocks: impl IntoIterator<Item = SimBlock>) -> Self {
+        self.state().script.extend(blocks);
+        self
+    }
+
+    /// The `max_block_range` reported to the indexer, as set on the network in the YAML.
+    pub fn with_max_block_range(mut self, range: u64) -> Self {
+        self.max_block_range = Some(U64::from(range));
+        self
+    }
+
+    /// Reject `eth_getLogs` calls spanning more than `blocks` blocks with the
+    /// Alchemy style "this block range should work" error.
+    pub fn with_get_logs_range_limit(mut self, blocks: u64) -> Self {
+        self.get_logs_range_limit = Some(blocks.max(1));
+        self
+    }
+
+    /// Report the `finalized` block `depth` blocks behind the head and the `safe`
+    /// block half way in between. Without it the tags are not supported.
+    pub fn with_finality_depth(mut self, depth: u64) -> Self {
+        self.finality_depth = Some(depth);
+        self
+    }
+
+    /// Push new blocks and reorgs through `chain_state_notification`, like a reth
+    /// ExEx or a `ws_rpc` subscription.
+    pub fn with_notifications(mut self) -> Self {
+        let (notification_tx, _notification_rx) = broadcast::channel(1000);
+        self.chain_state_notification = Some(notification_tx);
+        self
+    }
+
+    /// Answer `eth_call`s to `to` with exactly this calldata with `result`, at any block.
+    pub fn with_call_result(self, to: Address, data: Bytes, result: Bytes) -> Self {
+        self.state().call_results.insert((to, data), result);
+        self
+    }
+
+    /// Set a storage slot, e.g. the EIP-1967 implementation slot of a proxy.
+    pub fn with_storage(self, address: Address, slot: B256, value: B256) -> Self {
+        self.state().storage.insert((address, slot), value);
+        self
+    }
+
+    /// Give `address` code from `deployed_at` onwards, as `start_block: auto` looks for.
+    pub fn with_code(self, address: Address, deployed_at: u64, code: Bytes) -> Self {
+        self.state().code.insert(address, (deployed_at, code));
+        self
+    }
+
+    fn state(&self) -> MutexGuard<'_, SimState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn head(&self) -> u64 {
+        self.state().blocks.len() as u64 - 1
+    }
+
+    /// The canonical hash of `number`, `None` above the head.
+    pub fn block_hash(&self, number: u64) -> Option<B256> {
+        self.state().blocks.get(number as usize).map(|b| b.hash)
+    }
+
+    /// Number of RPC calls served, including rate limited ones.
+    pub fn request_count(&self) -> u64 {
+        self.state().request_count
+    }
+
+    /// Mine `block` on top of the head. Returns the new head.
+    pub fn mine(&self, block: SimBlock) -> u64 {
+        let mut state = self.state();
+        let number = self.push_block(&mut state, block);
+        self.notify(ChainStateNotification::Committed {
+            from_block: number,
+            to_block: number,
+            tip_hash: state.blocks[number as usize].hash,
+        });
+        number
+    }
+
+    /// Mine `count` empty blocks. Returns the new head.
+    pub fn mine_empty(&self, count: u64) -> u64 {
+        for _ in 0..count {
+            self.mine(SimBlock::new());
+        }
+        self.head()
+    }
+
+    /// Mine the next block of the script, `None` once it is exhausted.
+    pub fn advance(&self) -> Option<u64> {
+        let block = self.state().script.pop_front()?;
+        Some(self.mine(block))
+    }
+
+    /// Mine the rest of the script. Returns the new head.
+    pub fn run_script(&self) -> u64 {
+        while self.advance().is_some() {}
+        self.head()
+    }
+
+    /// Add `seconds` to the timestamp of the next block on top of the block time.
+    pub fn advance_time(&self, seconds: u64) {
+        self.state().time_gap += seconds;
+    }
+
+    /// Answer the next `requests` RPC calls with a 429 rate limit error.
+    pub fn rate_limit_next(&self, requests: u64) {
+        self.state().rate_limited_requests += requests;
+    }
+
+    /// Replace the last `depth` blocks with `replacement`, which may be longer or
+    /// shorter than `depth`. Returns the fork block, the first replaced block
+    /// number.
+    ///
+    /// # Panics
+    ///
+    /// If `depth` is 0 or would remove the genesis block.
+    pub fn reorg(&self, depth: u64, replacement: impl IntoIterator<Item = SimBlock>) -> u64 {
+        let mut state = self.state();
+        let old_head = state.blocks.len() as u64 - 1;
+        assert!(depth > 0 && depth <= old_head, "Can not reorg {depth} blocks at head {old_head}");
+
+        let fork_block = old_head - depth + 1;
+        state.blocks.truncate(fork_block as usize);
+        state.fork_id += 1;
+        for block in replacement {
+            self.push_block(&mut state, block);
+        }
+
+        let new_head = state.blocks.len() as u64 - 1;
+        if new_head >= fork_block {
+            self.notify(ChainStateNotification::Reorged {
+                revert_from_block: fork_block,
+                revert_to_block: old_head,
+                new_from_block: fork_block,
+                new_to_block: new_head,
+                new_tip_hash: state.blocks[new_head as usize].hash,
+                source: ChainStateSource::Exex,
+            });
+        } else {
+            self.notify(ChainStateNotification::Reverted {
+                from_block: fork_block,
+                to_block: old_head,
+                source: ChainStateSource::Exex,
+            });
+        }
+
+        fork_block
+    }
+
+    fn push_block(&self, state: &mut SimState, block: SimBlock) -> u64 {
+        let parent = state.blocks.last().expect("genesis is never removed");
+        let number = parent.number + 1;
+        let parent_hash = parent.hash;
+        let parent_timestamp = parent.timestamp;
+        let timestamp = parent_timestamp + self.block_time + std::mem::take(&mut state.time_gap);
+        let hash = block_hash(self.chain.id(), number, state.fork_id);
+
+        let tx_count = block.logs.len() + block.transfers.len();
+        let tx_hashes: Vec<TxHash> = (0..tx_count).map(|i| tx_hash(hash, i as u64)).collect();
+
+        let logs = block
+            .logs
+            .into_iter()
+            .enumerate()
+            .map(|(i, log)| Log {
+                inner: PrimitiveLog {
+                    address: log.address,
+                    data: LogData::new_unchecked(log.topics, log.data),
+                },
+                block_hash: Some(hash),
+                block_number: Some(number),
+                block_timestamp: Some(timestamp),
+                transaction_hash: Some(tx_hashes[i]),
+                transaction_index: Some(i as u64),
+                log_index: Some(i as u64),
+                removed: false,
+            })
+            .collect();
+
+        let log_count = tx_count - block.transfers.len();
+        let traces = block
+            .transfers
+            .into_iter()
+            .enumerate()
+            .map(|(i, transfer)| LocalizedTransactionTrace {
+                trace: TransactionTrace {
+                    action: Action::Call(CallAction {
+                        from: transfer.from,
+                        call_type: CallType::Call,
+                        gas: 21_000,
+                        input: Bytes::new(),
+                        to: transfer.to,
+                        value: transfer.value,
+                    }),
+                    error: None,
+                    result: None,
+                    subtraces: 0,
+                    trace_address: vec![],
+                },
+                block_hash: Some(hash),
+                block_number: Some(number),
+                transaction_hash: Some(tx_hashes[log_count + i]),
+                transaction_position: Some((log_count + i) as u64),
+            })
+            .collect();
+
+        state.blocks.push(MinedBlock {
+            number,
+            hash,
+            parent_hash,
+            timestamp,
+            tx_hashes,
+            logs,
+            traces,
+        });
+        number
+    }
+
+    fn notify(&self, notification: ChainStateNotification) {
+        if let Some(sender) = &self.chain_state_notification {
+            // No receivers is fine, nothing is listening yet.
+            let _ = sender.send(notification);
+        }
+    }
+
+    /// Count the call and fail it while rate limited.
+    fn request(&self) -> Result<MutexGuard<'_, SimState>, ProviderError> {
+        let mut state = self.state();
+        state.request_count += 1;
+        if state.rate_limited_requests > 0 {
+            state.rate_limited_requests -= 1;
+            return Err(ProviderError::CustomError(
+                "429 Too Many Requests: rate limit exceeded".to_string(),
+            ));
+        }
+        Ok(state)
+    }
+
+    fn check_get_logs_range(&self, from: u64, to: u64) -> Result<(), ProviderError> {
+        match self.get_logs_range_limit {
+            Some(limit) if to >= from && to - from + 1 > limit => {
+                Err(ProviderError::CustomError(format!(
+                    "Log response size exceeded. this block range should work: [{:#x}, {:#x}]",
+                    from,
+                    from + limit - 1
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn rpc_block(block: &MinedBlock) -> AnyRpcBlock {
+        let header = AnyHeader {
+            number: block.number,
+            parent_hash: block.parent_hash,
+            timestamp: block.timestamp,
+            ..Default::default()
+        };
+
+        AnyRpcBlock::new(
+            Block::new(
+                AnyRpcHeader::from_sealed(header.seal(block.hash)),
+                BlockTransactions::Hashes(block.tx_hashes.clone()),
+            )
+            .into(),
+        )
+    }
+}
+
+fn block_hash(chain_id: u64, number: u64, fork_id: u64) -> B256 {
+    let mut preimage = [0u8; 24];
+    preimage[..8].copy_from_slice(&chain_id.to_be_bytes());
+    preimage[8..16].copy_from_slice(&number.to_be_bytes());
+    preimage[16..].copy_from_slice(&fork_id.to_be_bytes());
+    keccak256(preimage)
+}
+
+fn tx_hash(block_hash: B256, index: u64) -> TxHash {
+    let mut preimage = [0u8; 40];
+    preimage[..32].copy_from_slice(block_hash.as_slice());
+    preimage[32..].copy_from_slice(&index.to_be_bytes());
+    keccak256(preimage)
+}
+
+#[async_trait]
+impl ChainProvider for SimChain {
+    fn chain(&self) -> Chain {
+        self.chain
+    }
+
+    fn max_block_range(&self) -> Option<U64> {
+        self.max_block_range
+    }
+
+    fn chain_state_notification(&self) -> Option<Sender<ChainStateNotification>> {
+        self.chain_state_notification.clone()
+    }
+
+    async fn get_latest_block(&self) -> Result<Option<Arc<AnyRpcBlock>>, ProviderError> {
+        let state = self.request()?;
+        Ok(state.blocks.last().map(|b| Arc::new(Self::rpc_block(b))))
+    }
+
+    async fn get_block_number(&self) -> Result<U64, ProviderError> {
+        let state = self.request()?;
+        Ok(U64::from(state.blocks.len() as u64 - 1))
+    }
+
+    async fn get_tagged_block_number(&self, tag: BlockTag) -> Result<Option<U64>, ProviderError> {
+        let state = self.request()?;
+        let head = state.blocks.len() as u64 - 1;
+        Ok(self.finality_depth.map(|depth| {
+            let behind = match tag {
+                BlockTag::Finalized => depth,
+                BlockTag::Safe => depth / 2,
+            };
+            U64::from(head.saturating_sub(behind))
+        }))
+    }
+
+    async fn get_logs(
+        &self,
+        event_filter: &RindexerEventFilter,
+    ) -> Result<Vec<Log>, ProviderError> {
+        let from = event_filter.from_block().to::<u64>();
+        let to = event_filter.to_block().to::<u64>();
+        let topic_id = event_filter.event_signature();
+        let addresses = event_filter.contract_addresses().await;
+
+        let state = self.request()?;
+        self.check_get_logs_range(from, to)?;
+        Ok(state
+            .blocks
+            .iter()
+            .filter(|b| b.number >= from && b.number <= to)
+            .flat_map(|b| &b.logs)
+            .filter(|log| log_matches(log, &topic_id, &addresses, event_filter))
+            .cloned()
+            .collect())
+    }
+
+    async fn get_combined_logs(
+        &self,
+        filter: &CombinedEventFilter,
+    ) -> Result<Vec<Log>, ProviderError> {
+        let from = filter.from_block.to::<u64>();
+        let to = filter.to_block.to::<u64>();
+
+        let state = self.request()?;
+        self.check_get_logs_range(from, to)?;
+        Ok(state
+            .blocks
+            .iter()
+            .filter(|b| b.number >= from && b.number <= to)
+            .flat_map(|b| &b.logs)
+            .filter(|log| filter.matches(log))
+            .cloned()
+            .collect())
+    }
+
+    async fn get_block_by_number_batch(
+        &self,
+        block_numbers: &[U64],
+        _include_txs: bool,
+    ) -> Result<Vec<AnyRpcBlock>, ProviderError> {
+        let state = self.request()?;
+        Ok(block_numbers
+            .iter()
+            .filter_map(|n| state.blocks.get(n.to::<usize>()))
+            .map(Self::rpc_block)
+            .collect())
+    }
+
+    async fn get_block_by_number_batch_with_size(
+        &self,
+        block_numbers: &[U64],
+        include_txs: bool,
+        _rpc_batch_size: Option<usize>,
+    ) -> Result<Vec<AnyRpcBlock>, ProviderError> {
+        self.get_block_by_number_batch(block_numbers, include_txs).await
+    }
+
+    async fn get_tx_receipts_batch(
+        &self,
+        _hashes: &[TxHash],
+    ) -> Result<Vec<AnyTransactionReceipt>, ProviderError> {
+        self.request()?;
+        Ok(vec![])
+    }
+
+    async fn trace_block(
+        &self,
+        block_number: U64,
+    ) -> Result<Vec<LocalizedTransactionTrace>, ProviderError> {
+        let state = self.request()?;
+        Ok(state
+            .blocks
+            .get(block_number.to::<usize>())
+            .map(|b| b.traces.clone())
+            .unwrap_or_default())
+    }
+
+    async fn debug_trace_block_by_number(
+        &self,
+        block_number: U64,
+    ) -> Result<Vec<LocalizedTransactionTrace>, ProviderError> {
+        self.trace_block(block_number).await
+    }
+
+    async fn eth_call(
+        &self,
+        to: Address,
+        data: Bytes,
+        _block_number: u64,
+    ) -> Result<String, ProviderError> {
+        self.eth_call_latest(to, data).await
+    }
+
+    async fn eth_call_latest(&self, to: Address, data: Bytes) -> Result<String, ProviderError> {
+        let state = self.request()?;
+        Ok(state
+            .call_results
+            .get(&(to, data))
+            .map(|result| format!("0x{}", hex::encode(result)))
+            .unwrap_or_else(|| "0x".to_string()))
+    }
+
+    async fn get_storage_at(&self, address: Address, slot: B256) -> Result<B256, ProviderError> {
+        let state = self.request()?;
+        Ok(state.storage.get(&(address, slot)).copied().unwrap_or_default())
+    }
+
+    async fn get_code_at(
+        &self,
+        address: Address,
+        block_number: u64,
+    ) -> Result<Bytes, ProviderError> {
+        let state = self.request()?;
+        Ok(state
+            .code
+            .get(&address)
+            .filter(|(deployed_at, _)| block_number >= *deployed_at)
+            .map(|(_, code)| code.clone())
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::rindexer_event_filter::SimpleEventFilter;
+    use crate::indexer::reorg::{BlockChainWindow, ReorgBlockHashPersistence, ReorgCoordinator};
+
+    fn topic(n: u8) -> B256 {
+        B256::from([n; 32])
+    }
+
+    fn address(n: u8) -> Address {
+        Address::from([n; 20])
+    }
+
+    fn filter(topic_id: B256, from: u64, to: u64) -> RindexerEventFilter {
+        RindexerEventFilter::Filter(SimpleEventFilter {
+            address: None,
+            topic_id,
+            topics: Default::default(),
+            current_block: U64::from(from),
+            next_block: U64::from(to),
+        })
+    }
+
+    fn transfer_block() -> SimBlock {
+        SimBlock::new().with_log(address(1), vec![topic(1)], Bytes::new())
+    }
+
+    #[tokio::test]
+    async fn mines_the_script_deterministically() {
+        let script = || vec![transfer_block(), SimBlock::new(), transfer_block()];
+        let a = SimChain::new(1).with_script(script());
+        let b = SimChain::new(1).with_script(script());
+
+        assert_eq!(a.run_script(), 3);
+        b.run_script();
+        assert_eq!(a.block_hash(3), b.block_hash(3));
+        assert_eq!(a.advance(), None);
+
+        let blocks =
+            a.get_block_by_number_batch(&[U64::from(2), U64::from(3)], false).await.unwrap();
+        assert_eq!(blocks[1].header.parent_hash, blocks[0].header.hash);
+        assert_eq!(blocks[1].header.timestamp, DEFAULT_GENESIS_TIMESTAMP + 3 * DEFAULT_BLOCK_TIME);
+    }
+
+    #[tokio::test]
+    async fn get_logs_filters_by_range_and_topic() {
+        let chain = SimChain::new(1);
+        chain.mine(transfer_block());
+        chain.mine(SimBlock::new().with_log(address(2), vec![topic(2)], Bytes::new()));
+        chain.mine(transfer_block());
+
+        let logs = chain.get_logs(&filter(topic(1), 0, 2)).await.unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].block_number, Some(1));
+        assert_eq!(logs[0].block_hash, chain.block_hash(1));
+        assert_eq!(chain.get_logs(&filter(topic(1), 0, 3)).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn advance_time_shifts_the_next_block() {
+        let chain = SimChain::new(1).with_genesis_timestamp(1000).with_block_time(2);
+        chain.mine_empty(1);
+        chain.advance_time(60);
+        chain.mine_empty(2);
+
+        let blocks = chain
+            .get_block_by_number_batch(&[U64::from(1), U64::from(2), U64::from(3)], false)
+            .await
+            .unwrap();
+        let timestamps: Vec<u64> = blocks.iter().map(|b| b.header.timestamp).collect();
+        assert_eq!(timestamps, vec![1002, 1064, 1066]);
+    }
+
+    #[tokio::test]
+    async fn reorg_replaces_the_tip() {
+        let chain = SimChain::new(1).with_notifications();
+        chain.mine_empty(3);
+        chain.mine(transfer_block());
+        chain.mine(transfer_block());
+        let orphaned = chain.block_hash(4).unwrap();
+        let mut notifications = chain.chain_state_notification().unwrap().subscribe();
+
+        let fork_block = chain.reorg(2, vec![SimBlock::new(), SimBlock::new(), transfer_block()]);
+
+        assert_eq!(fork_block, 4);
+        assert_eq!(chain.head(), 6);
+        assert_ne!(chain.block_hash(4), Some(orphaned));
+        let logs = chain.get_logs(&filter(topic(1), 0, 6)).await.unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].block_number, Some(6));
+        assert_eq!(
+            notifications.recv().await.unwrap(),
+            ChainStateNotification::Reorged {
+                revert_from_block: 4,
+                revert_to_block: 5,
+                new_from_block: 4,
+                new_to_block: 6,
+                new_tip_hash: chain.block_hash(6).unwrap(),
+                source: ChainStateSource::Exex,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn get_logs_over_the_range_limit_suggests_a_range() {
+        let chain = SimChain::new(1).with_get_logs_range_limit(10);
+        chain.mine_empty(20);
+
+        let err = chain.get_logs(&filter(topic(1), 5, 20)).await.unwrap_err();
+        assert!(err.to_string().contains("this block range should work: [0x5, 0xe]"));
+        assert!(chain.get_logs(&filter(topic(1), 5, 14)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn rate_limits_the_next_requests() {
+        let chain = SimChain::new(1);
+        chain.rate_limit_next(2);
+
+        assert!(chain.get_block_number().await.unwrap_err().to_string().contains("429"));
+        assert!(chain.get_block_number().await.is_err());
+        assert_eq!(chain.get_block_number().await.unwrap(), U64::ZERO);
+        assert_eq!(chain.request_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn tagged_blocks_follow_the_finality_depth() {
+        let chain = SimChain::new(1).with_finality_depth(10);
+        chain.mine_empty(30);
+
+        let finalized = chain.get_tagged_block_number(BlockTag::Finalized).await.unwrap();
+        let safe = chain.get_tagged_block_number(BlockTag::Safe).await.unwrap();
+        assert_eq!(finalized, Some(U64::from(20)));
+        assert_eq!(safe, Some(U64::from(25)));
+        let untagged = SimChain::new(1).get_tagged_block_number(BlockTag::Safe).await.unwrap();
+        assert_eq!(untagged, None);
+    }
+
+    #[tokio::test]
+    async fn answers_with_seeded_contract_state() {
+        let calldata = Bytes::from(vec![0x31, 0x3c, 0xe5, 0x67]);
+        let chain = SimChain::new(1)
+            .with_call_result(address(1), calldata.clone(), Bytes::from(vec![0x12]))
+            .with_storage(address(2), topic(3), topic(4))
+            .with_code(address(5), 10, Bytes::from(vec![0x60, 0x80]));
+
+        assert_eq!(chain.eth_call(address(1), calldata.clone(), 7).await.unwrap(), "0x12");
+        assert_eq!(chain.eth_call_latest(address(1), Bytes::new()).await.unwrap(), "0x");
+        assert_eq!(chain.get_storage_at(address(2), topic(3)).await.unwrap(), topic(4));
+        assert_eq!(chain.get_storage_at(address(2), topic(4)).await.unwrap(), B256::ZERO);
+        assert!(chain.get_code_at(address(5), 9).await.unwrap().is_empty());
+        assert_eq!(chain.get_code_at(address(5), 10).await.unwrap(), Bytes::from(vec![0x60, 0x80]));
+    }
+
+    #[tokio::test]
+    async fn reorg_coordinator_detects_simulated_reorg() {
+        let chain = Arc::new(SimChain::new(1));
+        let mut coordinator = ReorgCoordinator::new(
+            "test".to_string(),
+            BlockChainWindow::try_new(100).unwrap(),
+            Arc::new(ReorgBlockHashPersistence::new(None, None)),
+            chain.clone(),
+            vec![],
+            vec![],
+            vec![],
+        )
+        .unwrap();
+
+        let feed = |number: u64| {
+            let block = chain.state().blocks[number as usize].clone();
+            (block.number, block.hash, block.parent_hash)
+        };
+
+        chain.mine_empty(10);
+        for number in 1..=10 {
+            let (n, hash, parent) = feed(number);
+            assert!(coordinator.on_new_block(n, hash, parent).await.unwrap().is_none());
+        }
+
+        chain.reorg(3, (0..4).map(|_| SimBlock::new()));
+        let (n, hash, parent) = feed(11);
+        let task = coordinator
+            .on_new_block(n, hash, parent)
+            .await
+            .unwrap()
+            .expect("reorg should be detected");
+        assert_eq!(task.fork_point, 8);
+    }
+}
@@ -33,6 +33,7 @@
 //! adds a second `#[tokio::test]` to this file and runs under plain
 //! `cargo test`, or (b) removes the nextest requirement from CI.
 
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
@@ -435,7 +436,10 @@ async fn run_one_iteration(
     // rows and shuts down.
     let rindexer_fut = rindexer::start_rindexer_no_code(StartNoCodeDetails {
         manifest_path: &manifest_path,
-        indexing_details: IndexerNoCodeDetails { enabled: true },
+        indexing_details: IndexerNoCodeDetails {
+            enabled: true,
+            provider_overrides: HashMap::new(),
+        },
         graphql_details: GraphqlOverrideSettings { enabled: false, override_port: None },
         watch: false,
     });
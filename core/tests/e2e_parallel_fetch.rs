@@ -8,6 +8,7 @@
 //! tests mutate `DATABASE_URL` env, which would race under `cargo test`:
 //!   cargo nextest run -q -p rindexer --test e2e_parallel_fetch
 
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -369,7 +370,10 @@ async fn run_rindexer_historical(manifest_path: PathBuf) {
     // an end_block set; start_rindexer_no_code returns after historical sync.
     let result = rindexer::start_rindexer_no_code(StartNoCodeDetails {
         manifest_path: &manifest_path,
-        indexing_details: IndexerNoCodeDetails { enabled: true },
+        indexing_details: IndexerNoCodeDetails {
+            enabled: true,
+            provider_overrides: HashMap::new(),
+        },
         graphql_details: GraphqlOverrideSettings { enabled: false, override_port: None },
         watch: false,
     })
@@ -665,7 +669,10 @@ async fn parallel_historical_to_live_transition() {
 
     let rindexer_fut = rindexer::start_rindexer_no_code(StartNoCodeDetails {
         manifest_path: &manifest_path,
-        indexing_details: IndexerNoCodeDetails { enabled: true },
+        indexing_details: IndexerNoCodeDetails {
+            enabled: true,
+            provider_overrides: HashMap::new(),
+        },
         graphql_details: GraphqlOverrideSettings { enabled: false, override_port: None },
         watch: false,
     });
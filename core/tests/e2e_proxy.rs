@@ -0,0 +1,155 @@
+//! End-to-end test for `proxy: auto` against a proxy on Anvil.
+//!
+//! The proxy is an account with code whose EIP-1967 implementation slot points at a deployed
+//! contract, set through `anvil_setCode` and `anvil_setStorageAt`.
+//!
+//! Requires Docker:
+//!   cargo nextest run -q -p rindexer --test e2e_proxy
+
+use std::time::Duration;
+
+use alloy::primitives::Address;
+use reqwest::Client as HttpClient;
+use rindexer::{
+    manifest::yaml::read_manifest,
+    provider::create_client,
+    proxy::{
+        detect_proxy, implementation_abi_path, sync_manifest_proxy_abis, ProxyKind,
+        EIP1967_IMPLEMENTATION_SLOT,
+    },
+};
+use serde_json::{json, Value};
+use testcontainers::runners::AsyncRunner;
+use testcontainers::{GenericImage, ImageExt};
+
+const PROXY: Address = Address::repeat_byte(0x11);
+
+// Emits Ping(uint256 indexed id, address sender). Same bytecode as e2e_reorg.rs.
+const PING_PONG_BYTECODE: &str =
+    "6080604052348015600e575f5ffd5b506101748061001c5f395ff3fe608060405234801561000f575f5ffd5b5060043610610029575f3560e01c8063773acdef1461002d575b5f5ffd5b610047600480360381019061004291906100bb565b610049565b005b807fc05b373e05c47417d9c7204807552389e512c0e21cbc01a03d1554561080ac6e336040516100799190610125565b60405180910390a250565b5f5ffd5b5f819050919050565b61009a81610088565b81146100a4575f5ffd5b50565b5f813590506100b581610091565b92915050565b5f602082840312156100d0576100cf610084565b5b5f6100dd848285016100a7565b91505092915050565b5f73ffffffffffffffffffffffffffffffffffffffff82169050919050565b5f61010f826100e6565b9050919050565b61011f81610105565b82525050565b5f6020820190506101385f830184610116565b9291505056fea2646970667358221220dc07dd9f297d16a6d4ac329e4565c9ecb79b34df9738da42d568df67b039348764736f6c634300081c0033";
+
+const PING_PONG_ABI: &str = r#"[
+  {
+    "type": "event",
+    "name": "Ping",
+    "inputs": [
+      { "name": "id", "type": "uint256", "indexed": true },
+      { "name": "sender", "type": "address", "indexed": false }
+    ],
+    "anonymous": false
+  }
+]"#;
+
+async fn rpc_call(http: &HttpClient, rpc_url: &str, method: &str, params: Value) -> Value {
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": method,
+        "params": params,
+    });
+    let resp = http.post(rpc_url).json(&body).send().await.expect("RPC request failed");
+    let json: Value = resp.json().await.expect("RPC response not JSON");
+    if let Some(err) = json.get("error") {
+        panic!("RPC error calling {}: {:?}", method, err);
+    }
+    json["result"].clone()
+}
+
+async fn deploy_ping_pong(http: &HttpClient, rpc_url: &str) -> Address {
+    let accounts = rpc_call(http, rpc_url, "eth_accounts", json!([])).await;
+    let tx = json!({
+        "from": accounts[0],
+        "data": format!("0x{}", PING_PONG_BYTECODE),
+        "gas": "0x100000",
+    });
+    let tx_hash = rpc_call(http, rpc_url, "eth_sendTransaction", json!([tx])).await;
+
+    for _ in 0..120 {
+        let receipt = rpc_call(http, rpc_url, "eth_getTransactionReceipt", json!([tx_hash])).await;
+        if let Some(address) = receipt["contractAddress"].as_str() {
+            return address.parse().unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+    panic!("Timed out waiting for the deployment of PingPong");
+}
+
+#[tokio::test]
+async fn test_proxy_auto_resolves_anvil_eip1967_proxy() {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let anvil_container = GenericImage::new("ghcr.io/foundry-rs/foundry", "latest")
+        .with_exposed_port(8545_u16.into())
+        .with_cmd(vec!["anvil --host 0.0.0.0".to_string()])
+        .with_startup_timeout(Duration::from_secs(30))
+        .start()
+        .await
+        .expect("failed to start anvil container");
+    let anvil_port =
+        anvil_container.get_host_port_ipv4(8545).await.expect("failed to get anvil port");
+    let rpc_url = format!("http://127.0.0.1:{}", anvil_port);
+    let http = HttpClient::new();
+
+    for _ in 0..60 {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": "eth_blockNumber", "params": [] });
+        if http.post(&rpc_url).json(&body).send().await.is_ok() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+
+    let implementation = deploy_ping_pong(&http, &rpc_url).await;
+    rpc_call(&http, &rpc_url, "anvil_setCode", json!([PROXY, "0x00"])).await;
+    rpc_call(
+        &http,
+        &rpc_url,
+        "anvil_setStorageAt",
+        json!([PROXY, EIP1967_IMPLEMENTATION_SLOT, implementation.into_word()]),
+    )
+    .await;
+
+    let provider = create_client(&rpc_url, 31337, None, None, None, Default::default(), None, None)
+        .await
+        .expect("failed to create provider");
+    let proxy = detect_proxy(provider.as_ref(), PROXY).await.unwrap().expect("proxy detected");
+    assert_eq!(proxy.kind, ProxyKind::Eip1967);
+    assert_eq!(proxy.implementations, vec![implementation]);
+
+    // The implementation ABI is already stored so no explorer is needed.
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("abis")).unwrap();
+    std::fs::write(dir.path().join("abis/Proxy.abi.json"), "[]").unwrap();
+    let abi_path = implementation_abi_path(dir.path(), "Proxy", implementation);
+    std::fs::create_dir_all(abi_path.parent().unwrap()).unwrap();
+    std::fs::write(&abi_path, PING_PONG_ABI).unwrap();
+
+    let yaml = format!(
+        r#"name: proxy
+project_type: rust
+networks:
+  - name: dev
+    chain_id: 31337
+    rpc: {rpc_url}
+storage:
+  postgres:
+    enabled: false
+contracts:
+  - name: Proxy
+    details:
+      - network: dev
+        address: "{PROXY:#x}"
+        start_block: "0"
+    abi: ./abis/Proxy.abi.json
+    proxy: auto
+"#
+    );
+    let manifest_path = dir.path().join("rindexer.yaml");
+    std::fs::write(&manifest_path, yaml).unwrap();
+    let manifest = read_manifest(&manifest_path).unwrap();
+
+    let watches = sync_manifest_proxy_abis(dir.path(), &manifest).await.unwrap();
+
+    assert_eq!(watches.len(), 1);
+    assert_eq!(watches[0].implementations, vec![implementation]);
+    assert!(manifest.contracts[0].parse_abi(dir.path()).unwrap().contains("Ping"));
+}
@@ -1111,6 +1111,7 @@ async fn test_reorg_derived_table_cleanup() {
             cross_chain: false,
             rollback_ops: vec![],
             journal_columns: vec![],
+            history: false,
         }],
         canonical_blocks: vec![],
     };
@@ -1209,6 +1210,7 @@ async fn test_reorg_derived_table_cross_chain() {
             cross_chain: true,
             rollback_ops: vec![],
             journal_columns: vec![],
+            history: false,
         }],
         canonical_blocks: vec![],
     };
@@ -1941,12 +1943,14 @@ async fn test_reorg_multiple_derived_tables() {
                 cross_chain: false,
                 rollback_ops: vec![],
                 journal_columns: vec![],
+                history: false,
             },
             DerivedTableInfo {
                 full_table_name: "test_schema.volumes".to_string(),
                 cross_chain: false,
                 rollback_ops: vec![],
                 journal_columns: vec![],
+                history: false,
             },
         ],
         canonical_blocks: vec![],
@@ -2035,6 +2039,7 @@ async fn test_reorg_derived_table_deep_range() {
             cross_chain: false,
             rollback_ops: vec![],
             journal_columns: vec![],
+            history: false,
         }],
         canonical_blocks: vec![],
     };
@@ -2131,6 +2136,7 @@ async fn test_reorg_reversal_add() {
                 condition: None,
             }],
             journal_columns: vec![],
+            history: false,
         }],
         canonical_blocks: vec![],
     };
@@ -2243,6 +2249,7 @@ async fn test_reorg_reversal_reserved_word_column() {
                 condition: None,
             }],
             journal_columns: vec![],
+            history: false,
         }],
         canonical_blocks: vec![],
     };
@@ -2337,6 +2344,7 @@ async fn test_reorg_reversal_subtract() {
                 condition: None,
             }],
             journal_columns: vec![],
+            history: false,
         }],
         canonical_blocks: vec![],
     };
@@ -2432,6 +2440,7 @@ async fn test_reorg_reversal_increment() {
                 condition: None,
             }],
             journal_columns: vec![],
+            history: false,
         }],
         canonical_blocks: vec![],
     };
@@ -2526,6 +2535,7 @@ async fn test_reorg_reversal_with_condition() {
                 condition: Some("id::NUMERIC > 7".to_string()),
             }],
             journal_columns: vec![],
+            history: false,
         }],
         canonical_blocks: vec![],
     };
@@ -2638,7 +2648,11 @@ async fn test_reorg_journal_max_recalculation() {
                 derived_column: "max_trade".to_string(),
                 action: SetAction::Max,
                 where_columns: vec!["trader".to_string()],
+                bucket_column: None,
+                full_journal: false,
+                array_type: None,
             }],
+            history: false,
         }],
         canonical_blocks: vec![],
     };
@@ -2747,7 +2761,11 @@ async fn test_reorg_journal_set_recalculation() {
                 derived_column: "price".to_string(),
                 action: SetAction::Set,
                 where_columns: vec!["token".to_string()],
+                bucket_column: None,
+                full_journal: false,
+                array_type: None,
             }],
+            history: false,
         }],
         canonical_blocks: vec![],
     };
@@ -2846,6 +2864,7 @@ async fn test_reorg_reversal_decrement() {
                 condition: None,
             }],
             journal_columns: vec![],
+            history: false,
         }],
         canonical_blocks: vec![],
     };
@@ -2949,7 +2968,11 @@ async fn test_reorg_journal_min_recalculation() {
                 derived_column: "min_value".to_string(),
                 action: SetAction::Min,
                 where_columns: vec!["tracker".to_string()],
+                bucket_column: None,
+                full_journal: false,
+                array_type: None,
             }],
+            history: false,
         }],
         canonical_blocks: vec![],
     };
@@ -3073,7 +3096,11 @@ async fn test_reorg_mixed_reversible_and_journal() {
                 derived_column: "max_trade".to_string(),
                 action: SetAction::Max,
                 where_columns: vec!["user_addr".to_string()],
+                bucket_column: None,
+                full_journal: false,
+                array_type: None,
             }],
+            history: false,
         }],
         canonical_blocks: vec![],
     };
@@ -3187,6 +3214,7 @@ async fn test_reorg_reversal_uninvolved_row_unchanged() {
                 condition: None,
             }],
             journal_columns: vec![],
+            history: false,
         }],
         canonical_blocks: vec![],
     };
@@ -3333,6 +3361,7 @@ async fn test_reorg_clickhouse_add_reversal() {
                 condition: None,
             }],
             journal_columns: vec![],
+            history: false,
         }],
         canonical_blocks: vec![],
     };
@@ -3473,6 +3502,7 @@ async fn test_reorg_reversal_reserved_word_column_clickhouse() {
                 condition: None,
             }],
             journal_columns: vec![],
+            history: false,
         }],
         canonical_blocks: vec![],
     };
@@ -3652,6 +3682,7 @@ async fn test_reorg_two_events_same_table_reversible() {
                 },
             ],
             journal_columns: vec![],
+            history: false,
         }],
         canonical_blocks: vec![],
     };
@@ -3813,13 +3844,20 @@ async fn test_reorg_two_events_same_table_journal() {
                     derived_column: "max_trade".to_string(),
                     action: SetAction::Max,
                     where_columns: vec!["trader".to_string()],
+                    bucket_column: None,
+                    full_journal: false,
+                    array_type: None,
                 },
                 DerivedColumnJournal {
                     derived_column: "min_trade".to_string(),
                     action: SetAction::Min,
                     where_columns: vec!["trader".to_string()],
+                    bucket_column: None,
+                    full_journal: false,
+                    array_type: None,
                 },
             ],
+            history: false,
         }],
         canonical_blocks: vec![],
     };
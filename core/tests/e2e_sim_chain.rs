@@ -0,0 +1,142 @@
+//! End-to-end test indexing a no-code YAML project against an in-process `SimChain`.
+//!
+//! The project's network is overridden with the simulated chain, so no RPC, Docker or
+//! database is needed and the indexed Transfer events are checked in the CSV storage:
+//!   cargo nextest run -q -p rindexer --features testing --test e2e_sim_chain
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use alloy::primitives::{keccak256, Address, Bytes, B256, U256};
+use rindexer::{
+    provider::ChainProvider,
+    testing::{SimBlock, SimChain},
+    GraphqlOverrideSettings, IndexerNoCodeDetails, StartNoCodeDetails,
+};
+
+const CHAIN_ID: u64 = 31337;
+
+const ERC20_ABI: &str = r#"[
+  {
+    "type": "event",
+    "name": "Transfer",
+    "inputs": [
+      { "name": "from", "type": "address", "indexed": true },
+      { "name": "to", "type": "address", "indexed": true },
+      { "name": "value", "type": "uint256", "indexed": false }
+    ],
+    "anonymous": false
+  }
+]"#;
+
+fn write_project(dir: &Path, token: Address, end_block: u64) {
+    std::fs::create_dir_all(dir.join("abis")).expect("mkdir abis");
+    std::fs::write(dir.join("abis/ERC20.abi.json"), ERC20_ABI).expect("write abi");
+
+    // the rpc is never dialed, the network is indexed through the simulated chain
+    let yaml = format!(
+        r#"name: SimChainIndexer
+project_type: no-code
+networks:
+  - name: sim
+    chain_id: {CHAIN_ID}
+    rpc: http://127.0.0.1:1
+storage:
+  csv:
+    enabled: true
+    path: ./generated_csv
+native_transfers: false
+contracts:
+  - name: Token
+    details:
+      - network: sim
+        address: "{token:#x}"
+        start_block: "1"
+        end_block: "{end_block}"
+    abi: ./abis/ERC20.abi.json
+    include_events:
+      - Transfer
+"#
+    );
+    std::fs::write(dir.join("rindexer.yaml"), yaml).expect("write yaml");
+}
+
+fn transfer(token: Address, from: Address, to: Address, value: u64) -> SimBlock {
+    SimBlock::new().with_log(
+        token,
+        vec![keccak256("Transfer(address,address,uint256)"), from.into_word(), to.into_word()],
+        Bytes::from(B256::from(U256::from(value)).to_vec()),
+    )
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn indexes_a_yaml_project_on_a_simulated_chain() {
+    let token = Address::repeat_byte(0x11);
+    let other_token = Address::repeat_byte(0x22);
+    let alice = Address::repeat_byte(0xaa);
+    let bob = Address::repeat_byte(0xbb);
+
+    let chain = Arc::new(SimChain::new(CHAIN_ID).with_script(vec![
+        transfer(token, alice, bob, 1000),
+        SimBlock::new(),
+        transfer(other_token, alice, bob, 5),
+        transfer(token, bob, alice, 250),
+        SimBlock::new(),
+    ]));
+    let end_block = chain.run_script();
+    // blocks after the end block are not indexed
+    chain.mine(transfer(token, alice, bob, 7));
+
+    let tmp = tempfile::tempdir().expect("tempdir");
+    write_project(tmp.path(), token, end_block);
+
+    let provider: Arc<dyn ChainProvider> = chain.clone();
+    rindexer::start_rindexer_no_code(StartNoCodeDetails {
+        manifest_path: &tmp.path().join("rindexer.yaml"),
+        indexing_details: IndexerNoCodeDetails {
+            enabled: true,
+            provider_overrides: HashMap::from([("sim".to_string(), provider)]),
+        },
+        graphql_details: GraphqlOverrideSettings { enabled: false, override_port: None },
+        watch: false,
+    })
+    .await
+    .unwrap_or_else(|e| panic!("rindexer run failed: {:?}", e));
+
+    let csv_file = tmp.path().join("generated_csv/Token/token-transfer.csv");
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_path(&csv_file)
+        .unwrap_or_else(|e| panic!("could not read {}: {}", csv_file.display(), e));
+    let headers = reader.headers().expect("csv headers").clone();
+    let column = |name: &str| {
+        headers.iter().position(|header| header == name).unwrap_or_else(|| panic!("{name}"))
+    };
+    let rows: Vec<(String, String, String, u64, String)> = reader
+        .records()
+        .map(|record| {
+            let record = record.expect("csv record");
+            (
+                record[column("from")].to_string(),
+                record[column("to")].to_string(),
+                record[column("value")].to_string(),
+                record[column("block_number")].parse().expect("block number"),
+                record[column("block_hash")].to_string(),
+            )
+        })
+        .collect();
+
+    let expected = [(alice, bob, "1000", 1), (bob, alice, "250", 4)]
+        .map(|(from, to, value, block_number)| {
+            (
+                format!("{from:?}"),
+                format!("{to:?}"),
+                value.to_string(),
+                block_number,
+                format!("{:?}", chain.block_hash(block_number).expect("mined block")),
+            )
+        })
+        .to_vec();
+    assert_eq!(rows, expected);
+}
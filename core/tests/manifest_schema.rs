@@ -0,0 +1,221 @@
+/// Validates the generated `rindexer.yaml` JSON Schema against every example manifest in the repo.
+///
+/// Unknown object keys are treated as errors so the schema can't silently fall behind the
+/// manifest structs.
+use std::path::{Path, PathBuf};
+
+use rindexer::manifest::schema::generate_manifest_schema;
+use serde_json::Value;
+
+/// Top level keys which are read before the manifest is deserialized.
+const PRE_PARSE_KEYS: &[&str] = &["environment_path"];
+
+fn repo_root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).parent().unwrap().to_path_buf()
+}
+
+fn example_manifests() -> Vec<PathBuf> {
+    let mut manifests = vec![];
+    for dir in ["examples", "providers"] {
+        collect_manifests(&repo_root().join(dir), &mut manifests);
+    }
+    manifests.sort();
+    manifests
+}
+
+fn collect_manifests(dir: &Path, manifests: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if path.file_name().is_some_and(|n| n == "target" || n == "node_modules") {
+                continue;
+            }
+            collect_manifests(&path, manifests);
+        } else if path.file_name().is_some_and(|n| n == "rindexer.yaml") {
+            manifests.push(path);
+        }
+    }
+}
+
+/// Rejects unknown keys on every object schema, the manifest structs ignore them which would let
+/// the schema silently fall behind.
+fn deny_unknown_fields(schema: &mut Value) {
+    match schema {
+        Value::Object(object) => {
+            if object.contains_key("properties") && !object.contains_key("additionalProperties") {
+                object.insert("additionalProperties".to_string(), Value::Bool(false));
+            }
+            object.values_mut().for_each(deny_unknown_fields);
+        }
+        Value::Array(values) => values.iter_mut().for_each(deny_unknown_fields),
+        _ => {}
+    }
+}
+
+fn strict_schema() -> Value {
+    let mut schema = serde_json::to_value(generate_manifest_schema()).unwrap();
+    deny_unknown_fields(&mut schema);
+
+    // Read before the manifest is deserialized so they are not manifest fields.
+    let properties = schema["properties"].as_object_mut().unwrap();
+    for key in PRE_PARSE_KEYS {
+        properties.entry(key.to_string()).or_insert(Value::Bool(true));
+    }
+
+    schema
+}
+
+fn validate_manifest(schema: &Value, manifest: &Value) -> Vec<String> {
+    let validator = jsonschema::draft7::new(schema).expect("the manifest schema should compile");
+    validator
+        .iter_errors(manifest)
+        .map(|error| format!("{}: {error}", error.instance_path()))
+        .collect()
+}
+
+fn yaml_to_json(yaml: &str) -> Value {
+    let yaml: serde_yaml::Value = serde_yaml::from_str(yaml).unwrap();
+    serde_json::to_value(yaml).unwrap()
+}
+
+#[test]
+fn test_example_manifests_match_schema() {
+    let schema = strict_schema();
+    let manifests = example_manifests();
+    assert!(!manifests.is_empty(), "no example manifests found");
+
+    for manifest_path in manifests {
+        let contents = std::fs::read_to_string(&manifest_path).unwrap();
+        let errors = validate_manifest(&schema, &yaml_to_json(&contents));
+        assert!(
+            errors.is_empty(),
+            "{} failed schema validation: {errors:#?}",
+            manifest_path.display()
+        );
+    }
+}
+
+#[test]
+fn test_schema_rejects_unknown_column_type() {
+    let schema = strict_schema();
+    let manifest = yaml_to_json(
+        r#"
+name: test
+project_type: no-code
+networks:
+  - name: ethereum
+    chain_id: 1
+    rpc: https://mainnet.gateway.tenderly.co
+contracts:
+  - name: Token
+    details:
+      - network: ethereum
+        address: "0xae78736Cd615f374D3085123A210448E74Fc6393"
+        start_block: "18600000"
+    abi: ./abis/Token.abi.json
+    tables:
+      - name: balances
+        columns:
+          - name: holder
+            type: address
+          - name: balance
+            type: uint257
+        events:
+          - event: Transfer
+            operations:
+              - type: upsert
+                where:
+                  holder: $to
+                set:
+                  - column: balance
+                    action: add
+                    value: $value
+"#,
+    );
+
+    let errors = validate_manifest(&schema, &manifest);
+    assert!(errors.iter().any(|e| e.contains("uint257")), "{errors:#?}");
+}
+
+#[test]
+fn test_schema_rejects_misspelled_field() {
+    let schema = strict_schema();
+    let manifest = yaml_to_json(
+        r#"
+name: test
+project_type: no-code
+networks:
+  - name: ethereum
+    chain_id: 1
+    rpc: https://mainnet.gateway.tenderly.co
+    block_poll_frequncy: rapid
+contracts: []
+"#,
+    );
+
+    let errors = validate_manifest(&schema, &manifest);
+    assert!(errors.iter().any(|e| e.contains("block_poll_frequncy")), "{errors:#?}");
+}
+
+#[test]
+fn test_schema_accepts_block_numbers_as_strings_or_integers() {
+    let schema = strict_schema();
+    let manifest = yaml_to_json(
+        r#"
+name: test
+project_type: rust
+networks:
+  - name: ethereum
+    chain_id: 1
+    rpc: https://mainnet.gateway.tenderly.co
+    max_block_range: 10000
+    block_poll_frequency: /3
+contracts:
+  - name: Token
+    details:
+      - network: ethereum
+        address:
+          - "0xae78736Cd615f374D3085123A210448E74Fc6393"
+        start_block: 18600000
+        end_block: "18700000"
+    abi:
+      - ./abis/Token.abi.json
+      - ./abis/TokenV2.abi.json
+    include_events:
+      - Transfer
+      - name: Approval
+        timestamps: true
+"#,
+    );
+
+    let errors = validate_manifest(&schema, &manifest);
+    assert!(errors.is_empty(), "{errors:#?}");
+}
+
+#[test]
+fn test_schema_accepts_env_variable_addresses() {
+    let schema = strict_schema();
+    let manifest = yaml_to_json(
+        r#"
+name: test
+project_type: no-code
+networks:
+  - name: ethereum
+    chain_id: 1
+    rpc: ${RPC_URL}
+contracts:
+  - name: Token
+    details:
+      - network: ethereum
+        address: ${TOKEN_ADDRESS}
+        start_block: ${START_BLOCK}
+    abi: ./abis/Token.abi.json
+"#,
+    );
+
+    let errors = validate_manifest(&schema, &manifest);
+    assert!(errors.is_empty(), "{errors:#?}");
+}
@@ -204,6 +204,10 @@ export default defineConfig({
               text: "Ethers to Alloy Migration",
               link: "/docs/start-building/rust-project-deep-dive/ethers-alloy-migration",
             },
+            {
+              text: "Testing",
+              link: "/docs/start-building/rust-project-deep-dive/testing",
+            },
           ],
         },
       ],
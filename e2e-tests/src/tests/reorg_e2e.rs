@@ -34,6 +34,13 @@ impl TestModule for ReorgTests {
             )
             .with_timeout(120)
             .with_chain_id(137),
+            TestDefinition::new(
+                "test_reorg_live_csv_rollback",
+                "Reorg: live detection with CSV-only storage, orphaned rows removed from CSV",
+                reorg_live_csv_rollback,
+            )
+            .with_timeout(180)
+            .with_chain_id(137),
             TestDefinition::new(
                 "test_reorg_double_reorg_idempotency",
                 "Reorg: two consecutive offline reorgs, PG state clean after both",
@@ -745,7 +752,8 @@ fn reorg_csv_invalidation(
         let post_count = wait_for_csv_count(context, pre_count, 15).await?;
         info!("Post-reorg CSV rows: {}", post_count);
 
-        // CSV is append-only — post-reorg count should be >= pre-reorg
+        // Without a database the block hashes are not persisted, so an offline
+        // reorg is not caught on restart — post-reorg count should be >= pre-reorg
         if post_count < pre_count {
             return Err(anyhow::anyhow!(
                 "Post-reorg CSV has fewer rows ({}) than pre-reorg ({})",
@@ -762,6 +770,94 @@ fn reorg_csv_invalidation(
     })
 }
 
+// ---------------------------------------------------------------------------
+// Test 3b: Live reorg with CSV-only storage
+//
+// The coordinator rewrites the CSV without the rows of the reorged blocks and
+// rewinds the CSV checkpoint, so the re-indexed canonical rows are not
+// appended next to orphaned ones.
+// ---------------------------------------------------------------------------
+fn reorg_live_csv_rollback(
+    context: &mut TestContext,
+) -> Pin<Box<dyn Future<Output = Result<()>> + '_>> {
+    Box::pin(async move {
+        info!("Running Reorg Live CSV Rollback Test");
+
+        let contract_address = context.deploy_test_contract().await?;
+        let amounts = [1000u64, 2000, 3000];
+        let recipients: Vec<_> = (0..3).map(generate_test_address).collect();
+
+        for (r, a) in recipients.iter().zip(amounts.iter()) {
+            context.anvil.send_transfer(&contract_address, r, U256::from(*a)).await?;
+            context.anvil.mine_block().await?;
+        }
+
+        // 1 mint + 3 transfers = 4 rows
+        let config = create_reorg_config(context, &contract_address);
+        helpers::copy_abis_to_project(&context.project_path)?;
+        let yaml = serde_yaml::to_string(&config)?;
+        std::fs::write(context.project_path.join("rindexer.yaml"), yaml)?;
+
+        let mut rindexer = crate::rindexer_client::RindexerInstance::new(
+            &context.rindexer_binary,
+            context.project_path.clone(),
+        );
+        rindexer.start_indexer().await?;
+        context.rindexer = Some(rindexer);
+        context.wait_for_sync_completion(30).await?;
+
+        let pre_count = wait_for_csv_count(context, 4, 15).await?;
+        info!("Pre-reorg CSV rows: {}", pre_count);
+
+        // Live transfer so the poller has the block hashes in its window
+        let live_recipient = generate_test_address(99);
+        context.anvil.send_transfer(&contract_address, &live_recipient, U256::from(777u64)).await?;
+        context.anvil.mine_block().await?;
+        let live_count = wait_for_csv_count(context, pre_count + 1, 15).await?;
+        info!("After live transfer: {} CSV rows", live_count);
+
+        context.anvil.trigger_reorg(2).await?;
+        context.anvil.mine_block().await?;
+
+        if let Some(r) = &context.rindexer {
+            r.wait_for_reorg_recovery(60).await?;
+            if !r.reorg_detected.load(std::sync::atomic::Ordering::Relaxed) {
+                return Err(anyhow::anyhow!("Reorg was not detected by rindexer"));
+            }
+        }
+
+        tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+
+        let csv_path =
+            context.get_csv_output_path().join("SimpleERC20").join("simpleerc20-transfer.csv");
+        let (_, rows) = helpers::parse_transfer_csv(&csv_path.to_string_lossy())?;
+
+        let mut seen = std::collections::HashSet::new();
+        for row in &rows {
+            if !seen.insert(row.tx_hash.clone()) {
+                return Err(anyhow::anyhow!("Duplicate tx_hash {} in CSV", row.tx_hash));
+            }
+            let canonical = context.anvil.get_block(row.block_number).await?;
+            if canonical.hash != row.block_hash {
+                return Err(anyhow::anyhow!(
+                    "CSV row {} has orphaned block hash {} at block {} (canonical {})",
+                    row.tx_hash,
+                    row.block_hash,
+                    row.block_number,
+                    canonical.hash
+                ));
+            }
+        }
+
+        info!(
+            "Reorg Live CSV Rollback Test PASSED: pre={}, post={}, no orphaned rows",
+            live_count,
+            rows.len()
+        );
+        Ok(())
+    })
+}
+
 // ---------------------------------------------------------------------------
 // Test 4: Double reorg idempotency — two consecutive offline reorgs
 //